@@ -0,0 +1,253 @@
+use color_eyre::eyre::WrapErr;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use tokio::runtime::Runtime;
+
+use crate::config::data_dir;
+use crate::notes::write_atomic;
+use crate::slurm::command::{execute_job_action, JobAction};
+use crate::snapshot::unix_now;
+
+/// Tries before a failing action is given up on
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Actions left over from an earlier session are dropped once this old (in
+/// seconds) rather than run long after they were asked for
+const MAX_AGE_SECS: u64 = 60 * 60;
+
+/// Errors that mean Slurm could not be reached, rather than that it refused
+const TRANSIENT_ERRORS: &[&str] = &[
+    "unable to contact slurm controller",
+    "socket timed out",
+    "timed out",
+    "connection refused",
+    "connection reset",
+    "zero bytes were transmitted",
+    "resource temporarily unavailable",
+    "broken pipe",
+    "try again",
+];
+
+/// A bulk action waiting to be (re)tried
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedAction {
+    pub id: u64,
+    pub action: JobAction,
+    pub job_ids: Vec<String>,
+    /// Unix time the action was asked for
+    pub queued_at: u64,
+    pub attempts: u32,
+    /// Unix time of the next try
+    pub next_attempt: u64,
+    pub last_error: Option<String>,
+}
+
+/// What became of a queued action, for the status line
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionOutcome {
+    Done(QueuedAction),
+    /// Failed but will be tried again in `in_secs`
+    Retrying {
+        action: QueuedAction,
+        in_secs: u64,
+    },
+    Failed(QueuedAction),
+    /// Left over from an earlier session and too old to run now
+    Expired(QueuedAction),
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct QueueFile {
+    actions: Vec<QueuedAction>,
+}
+
+type Finished = (u64, Result<(), String>);
+
+/// Bulk actions run in the background with retries, persisted under the data
+/// directory so a requested cancellation survives a dropped session
+pub struct ActionQueue {
+    path: Option<PathBuf>,
+    actions: Vec<QueuedAction>,
+    in_flight: HashSet<u64>,
+    tx: Sender<Finished>,
+    rx: Receiver<Finished>,
+}
+
+impl ActionQueue {
+    /// The queue left by an earlier session, without the actions that
+    /// have become too old to run (returned as `Expired`)
+    pub fn load() -> (Self, Vec<ActionOutcome>) {
+        let path = data_dir().ok().map(|d| d.join("actions.toml"));
+        let actions = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|raw| toml::from_str::<QueueFile>(&raw).ok())
+            .map(|file| file.actions)
+            .unwrap_or_default();
+        let mut queue = Self::with_path(path);
+        let now = unix_now();
+        let (fresh, expired): (Vec<_>, Vec<_>) = actions
+            .into_iter()
+            .partition(|a| now.saturating_sub(a.queued_at) < MAX_AGE_SECS);
+        queue.actions = fresh;
+        if !expired.is_empty() {
+            let _ = queue.save();
+        }
+        (
+            queue,
+            expired.into_iter().map(ActionOutcome::Expired).collect(),
+        )
+    }
+
+    fn with_path(path: Option<PathBuf>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            path,
+            actions: Vec::new(),
+            in_flight: HashSet::new(),
+            tx,
+            rx,
+        }
+    }
+
+    /// Actions not yet done or given up on
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn enqueue(&mut self, action: JobAction, job_ids: Vec<String>) -> Result<()> {
+        let now = unix_now();
+        let id = self.actions.iter().map(|a| a.id + 1).max().unwrap_or(1);
+        self.actions.push(QueuedAction {
+            id,
+            action,
+            job_ids,
+            queued_at: now,
+            attempts: 0,
+            next_attempt: now,
+            last_error: None,
+        });
+        self.save()
+    }
+
+    /// Collect finished attempts and start the ones that are due; call this
+    /// often (on every tick)
+    pub fn poll(&mut self, runtime: &Runtime) -> Vec<ActionOutcome> {
+        let mut outcomes = Vec::new();
+        while let Ok((id, result)) = self.rx.try_recv() {
+            self.in_flight.remove(&id);
+            outcomes.extend(self.settle(id, result, unix_now()));
+        }
+        if !outcomes.is_empty() {
+            let _ = self.save();
+        }
+
+        let now = unix_now();
+        for queued in &self.actions {
+            if queued.next_attempt > now || !self.in_flight.insert(queued.id) {
+                continue;
+            }
+            let (id, action, job_ids) = (queued.id, queued.action, queued.job_ids.clone());
+            let tx = self.tx.clone();
+            runtime.spawn(async move {
+                let result = execute_job_action(action, job_ids)
+                    .await
+                    .map_err(|e| format!("{:#}", e));
+                let _ = tx.send((id, result));
+            });
+        }
+        outcomes
+    }
+
+    /// Record the result of one attempt
+    fn settle(&mut self, id: u64, result: Result<(), String>, now: u64) -> Option<ActionOutcome> {
+        let index = self.actions.iter().position(|a| a.id == id)?;
+        let queued = &mut self.actions[index];
+        queued.attempts += 1;
+        match result {
+            Ok(()) => Some(ActionOutcome::Done(self.actions.remove(index))),
+            Err(error) => {
+                let retry = queued.attempts < MAX_ATTEMPTS && is_transient(&error);
+                queued.last_error = Some(error);
+                if !retry {
+                    return Some(ActionOutcome::Failed(self.actions.remove(index)));
+                }
+                let in_secs = backoff(queued.attempts);
+                queued.next_attempt = now + in_secs;
+                Some(ActionOutcome::Retrying {
+                    action: queued.clone(),
+                    in_secs,
+                })
+            }
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if self.actions.is_empty() {
+            let _ = fs::remove_file(path);
+            return Ok(());
+        }
+        let raw = toml::to_string(&QueueFile {
+            actions: self.actions.clone(),
+        })
+        .wrap_err("failed serializing the action queue")?;
+        write_atomic(path, &raw).wrap_err_with(|| format!("failed writing {}", path.display()))
+    }
+}
+
+/// Whether a failed attempt is worth repeating
+fn is_transient(error: &str) -> bool {
+    let error = error.to_lowercase();
+    TRANSIENT_ERRORS.iter().any(|e| error.contains(e))
+}
+
+/// Seconds to wait after the given number of failed attempts: 2, 4, 8, ... up to a minute
+fn backoff(attempts: u32) -> u64 {
+    (1u64 << attempts.min(6)).min(60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transient_failures_are_retried_with_backoff() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("actions.toml");
+        let mut queue = ActionQueue::with_path(Some(path.clone()));
+        queue
+            .enqueue(JobAction::Cancel, vec!["1".to_string(), "2".to_string()])
+            .unwrap();
+        queue
+            .enqueue(JobAction::Hold, vec!["3".to_string()])
+            .unwrap();
+        assert!(path.exists());
+
+        let timeout = Err("scancel failed: Socket timed out on send/recv operation".to_string());
+        match queue.settle(1, timeout, 100) {
+            Some(ActionOutcome::Retrying { action, in_secs }) => {
+                assert_eq!((action.attempts, in_secs, action.next_attempt), (1, 2, 102));
+            }
+            other => panic!("expected a retry, got {:?}", other),
+        }
+        // Refusals are not retried
+        let denied = Err("scontrol hold failed: Access/permission denied".to_string());
+        assert!(matches!(
+            queue.settle(2, denied, 100),
+            Some(ActionOutcome::Failed(_))
+        ));
+        assert!(matches!(
+            queue.settle(1, Ok(()), 103),
+            Some(ActionOutcome::Done(a)) if a.attempts == 2
+        ));
+        assert_eq!(queue.len(), 0);
+        assert_eq!(backoff(10), 60);
+    }
+}
@@ -1,43 +1,256 @@
+use chrono::{DateTime, Local, TimeDelta};
+use color_eyre::eyre::eyre;
 use color_eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent};
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use ratatui::{
-    layout::Rect,
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::Line,
-    widgets::{Block, Borders, Clear, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
-use regex;
+use signal_hook::consts::{SIGHUP, SIGTERM};
 use std::cmp::Ordering;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
 
 use crate::{
-    config::{load_config, resolve_slurm_logs_dir},
+    actions::{ActionOutcome, ActionQueue},
+    baseline::{log_metrics, Baseline},
+    clipboard,
+    config::{
+        data_dir, load_config, overrides, resolve_slurm_logs_dir, resolve_user, FilterPreset,
+        LayoutConfig, NotifyConfig, OpenConfig, SlurmerConfig, Theme,
+    },
+    efficiency::overall,
+    keymap::{Action, Input, KeyContext, KeyOutcome, Keymap},
+    metrics::write_textfile,
+    notes::{JobNote, NoteStore},
+    notify::{notify_job_finished, JobFinished},
+    open::{expand, OpenTarget},
+    output::{write_jobs_json, write_output, OutputFormat, Records},
+    poll::{Poller, Pool, Task, Timed},
+    protect::{confirmation_phrase, toggle_protected, Protection},
+    rightsize::{Suggestion, UsageHistory, UsageRun, LOOKBACK_DAYS},
+    schema::check_config,
+    session::{load_session, save_session, Session},
     slurm::{
-        sacct::{run_sacct, SacctOptions},
-        command::{execute_scancel, get_partitions, get_qos},
-        squeue::{run_squeue, SqueueOptions},
-        Job,
-        JobState,
+        aggregate::{run_squeue_aggregate, QueueGroup},
+        builder::{ScriptBuilder, BUILDER_FIELDS},
+        cache,
+        capabilities::{self, Capabilities},
+        command::{
+            self, attach_args, get_cluster_name, get_partitions, get_qos, modify_job, JobAction,
+        },
+        dependency::{dependency_tree, fetch_dependencies},
+        directives::submission_warnings,
+        error::SlurmError,
+        events::{fetch_job_events, run_events, JobEvent},
+        group_het_jobs,
+        hints::HintKeys,
+        hostlist,
+        inspect::{inspect_job, NodeSnapshot},
+        licenses::{fetch_licenses, License},
+        merge_jobs_prefer_active,
+        mock::MockJobSource,
+        nodes::{fetch_nodes, update_node, Node, NodeUpdate},
+        pending::run_squeue_pending,
+        preempt::{fetch_preempt_time, fetch_preemption, preemption_risk, QosPreemption},
+        query::JobQuery,
+        recording,
+        requeue::{attempt_chains, fetch_attempts, CHAIN_WINDOW_HOURS},
+        reservation::{fetch_reservations, Reservation},
+        sacct::{
+            fetch_job_details, fetch_job_steps, fetch_jobs, fetch_max_rss, fetch_past_runs,
+            name_regex, run_sacct, SacctOptions, SacctRows,
+        },
+        sacctmgr::fetch_limits,
+        script::{fetch_batch_script, ScriptSource},
+        scrontab::{fetch_scrontab, write_scrontab, Scrontab},
+        squeue::{fetch_start_estimates, run_squeue, SqueueOptions},
+        sstat::{run_sstat, StepUsage},
+        store::{JobDiff, JobStore},
+        submit::{fetch_submit_spec, submit, SubmitSpec, SUBMIT_FIELDS},
+        tres::{fetch_storage, Storage},
+        whatif::simulate_cancel,
+        Job, JobState,
     },
+    snapshot::{save_snapshot, unix_now, Snapshot},
+    timeline::{queue_wait, StateHistory},
+    trace::{write_trace, TraceLanes, TRACE_FIELDS},
     ui::{
+        action_menu::{ActionMenu, MenuAction},
+        aggregate::{AggregateAction, AggregateView},
         columns::{ColumnsAction, ColumnsPopup, JobColumn, SortColumn, SortOrder},
+        cron::CronView,
+        dependencies::{DependencyAction, DependencyView},
+        detail::{DetailAction, DetailLoad, JobDetail},
         filter::{FilterAction, FilterPopup},
+        form::{FormAction, FormPopup},
         jobscript::JobScript,
-        jobslist::JobsList,
+        jobslist::{GroupBy, JobsList},
         layout::{centered_popup_area, draw_footer, draw_header, draw_main_layout},
+        licenses::LicenseView,
+        limits::LimitsView,
         logview::LogView,
+        nodes::NodeView,
+        palette::{Command, CommandPalette, PaletteAction, PaletteCommand},
+        pending::{PendingAction, PendingView},
+        reference::ReferencePopup,
+        requeue::{ChainAction, ChainView},
+        reservations::ReservationView,
+        search::{SearchAction, SearchView},
         settings::{SettingsAction, SettingsPopup},
+        stats::{StatsAction, StatsView},
+        statusbar::{Controller, Source, StatusBar},
+        table_popup::{TableAction, TableView},
+        theme::{ColorDepth, Palette},
+        trends::{TrendsAction, TrendsView},
     },
     utils::{
-        event::{Event as AppEvent, EventConfig, EventHandler},
+        event::{enter_tui, leave_tui, Event as AppEvent, EventHandler},
         get_username,
     },
 };
 
+/// What the popup form is currently collecting input for
+enum FormPurpose {
+    /// Submit the edited options, as a resubmission of `job_id` or (without
+    /// one) as a new job imported from a command line
+    Resubmit {
+        job_id: Option<String>,
+        spec: Box<SubmitSpec>,
+        /// Resource warnings already shown; submitting again goes ahead
+        warned: Vec<String>,
+    },
+    /// A pasted `sbatch` command line, to be opened in the submission form
+    ImportSbatch,
+    /// Resources of a new batch script to write
+    BuildScript,
+    /// Tags and note of a job
+    Note { job_id: String },
+    /// The Slurm comment of a pending or running job, set with `scontrol
+    /// update`
+    Comment { job_id: String },
+    /// Change these fields of a pending job with `scontrol update`; holds the
+    /// original values so only edited ones are sent
+    UpdateJob {
+        job_id: String,
+        original: Vec<(String, String)>,
+    },
+    /// Export these jobs as a Chrome trace
+    Trace { job_ids: Vec<String> },
+    /// Export the listed jobs as JSON, CSV or TSV
+    ExportJobs,
+    /// New schedule and command of a scrontab entry
+    EditCron { index: usize },
+    /// Whose jobs to list
+    SwitchUser,
+    /// An ad-hoc search of squeue and accounting, listed in the results tab
+    Search,
+    /// Typed confirmation of a destructive action on protected jobs
+    ConfirmProtected { action: JobAction, phrase: String },
+}
+
+/// Fields editable on a pending job: form label and `scontrol update` key
+const UPDATE_FIELDS: &[(&str, &str)] = &[
+    ("Time limit", "TimeLimit"),
+    ("Partition", "Partition"),
+    ("Nodes", "NumNodes"),
+    ("QOS", "QOS"),
+];
+
+/// All-users lists at least this long suggest the aggregate view instead
+const LARGE_QUEUE_JOBS: usize = 10_000;
+
+/// Polls a job that left squeue is listed as last seen while accounting
+/// has not recorded its end yet
+const SETTLE_POLLS: u32 = 30;
+
+/// Longest gap between the clicks of a double-click
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// Hours ended jobs are listed for that `+` and `-` step through
+const LOOKBACK_STEPS: [u32; 6] = [1, 6, 24, 72, 168, 720];
+
+/// One Slurm query of a refresh, run in the fetch pool
+enum Fetch {
+    Squeue(Result<Vec<Job>>),
+    Estimates(Result<HashMap<String, DateTime<Local>>>),
+    Sacct(Result<SacctRows>),
+    Partitions(Result<Vec<String>>),
+    Reservations(Result<Vec<Reservation>>),
+    Licenses(Result<Vec<License>>),
+    Preemption(Result<(Vec<QosPreemption>, HashMap<String, usize>)>),
+    Qos(Result<Vec<String>>),
+    /// Records of watched jobs squeue did not list
+    Watched(Result<Vec<Job>>),
+    /// Records of jobs that left squeue since the last poll
+    Ended(Result<Vec<Job>>),
+}
+
+/// What a fetch for the detail pane returned
+enum DetailFetch {
+    PreemptTime(Option<String>),
+    Storage(Storage),
+    Usage(Result<Vec<StepUsage>, String>),
+    Nodes(Result<Vec<NodeSnapshot>, String>),
+    Events(Result<Vec<JobEvent>, String>),
+}
+
+/// What the fetches run off the UI thread returned, taken in on a tick
+enum Fetched {
+    /// A job poll started at `poll_generation` `.0`
+    Poll(u64, Box<PollPlan>, Vec<Timed<Fetch>>),
+    Cluster(Vec<Timed<Fetch>>),
+    /// For the detail pane, on job `.0`
+    Detail(String, DetailFetch),
+    /// For the cluster view
+    Nodes(Result<Vec<Node>, String>),
+    NodeUpdate(NodeUpdate, Result<(), String>),
+}
+
+/// A table popup with the method that refreshes it
+type TablePopupOf<'a> = (&'a mut dyn TableView, fn(&mut App));
+
+/// What a job poll asks Slurm for, decided on the UI thread
+struct PollPlan {
+    options: SqueueOptions,
+    /// The query of the ended jobs listed, when they are
+    sacct_options: Option<SacctOptions>,
+    /// Whether sacct is asked again with this poll
+    sacct_due: bool,
+    estimates: bool,
+    /// Whether sinfo and the other cluster queries run with this poll
+    cluster: bool,
+    /// Watched jobs without a final record
+    watched: Vec<String>,
+    /// Jobs active at the last poll of the same query
+    active: Vec<String>,
+    /// sacct fields of the records of `watched` and `active` jobs
+    record_fields: Vec<&'static str>,
+}
+
+/// What the pool fetched for a job poll; `None` for queries that did not run
+#[derive(Default)]
+struct FetchedJobs {
+    squeue: Option<Result<Vec<Job>>>,
+    estimates: Option<Result<HashMap<String, DateTime<Local>>>>,
+    sacct: Option<Result<SacctRows>>,
+    watched: Option<Result<Vec<Job>>>,
+    ended: Option<Result<Vec<Job>>>,
+}
+
 /// Application state and logic
 pub struct App {
     /// Is the application running?
@@ -50,13 +263,16 @@ pub struct App {
     pub squeue_options: SqueueOptions,
     /// Tokio runtime for async operations
     pub runtime: Runtime,
-    /// Last time jobs were refreshed
-    pub last_refresh: Instant,
+    /// When squeue was polled for the job list, and when it is due again
+    pub squeue_poll: Poller,
     /// Filter popup state
     pub filter_popup: FilterPopup,
     /// Settings popup state
     pub settings_popup: SettingsPopup,
-    /// Is the job detail popup visible?
+    /// Job detail side pane
+    pub job_detail: JobDetail,
+    /// Built-in reference of states, reasons and exit codes
+    pub reference_popup: ReferencePopup,
     /// Columns popup state
     pub columns_popup: ColumnsPopup,
     /// Log view state
@@ -69,8 +285,50 @@ pub struct App {
     pub status_timeout: Option<Instant>,
     /// Auto-refresh interval in seconds
     pub job_refresh_interval: u64,
+    /// Seconds between sacct queries for recently ended jobs
+    sacct_interval: u64,
+    /// Ended jobs read from sacct at most; `None` reads them all
+    sacct_max_rows: Option<usize>,
+    /// Hours of the look back covered by each sacct query
+    sacct_window_hours: Option<u32>,
+    sacct_poll: Poller,
+    /// Accounting records of the watched jobs the filters leave out; the
+    /// ended ones are not asked for again
+    watched_jobs: HashMap<String, Job>,
+    /// The active ones of `watched_jobs` are asked for again as often as
+    /// ended jobs
+    watched_poll: Poller,
+    /// Jobs that left squeue but are not ended in accounting yet, with the
+    /// polls they have been asked for since
+    settling: HashMap<String, u32>,
+    /// Ended jobs from the last sacct query, merged into every refresh
+    ended_jobs: Vec<Job>,
+    /// Whether sacct had more ended jobs than `[refresh] sacct_max_rows`
+    ended_truncated: bool,
+    /// The query `ended_jobs` answer; a different one is fetched at once
+    ended_query: Option<SacctOptions>,
+    /// The query of the last squeue poll: under the same one, an active job
+    /// missing from squeue has ended rather than been filtered out
+    squeue_query: Option<SqueueOptions>,
+    /// Seconds between sinfo queries for the partition list
+    sinfo_interval: u64,
+    sinfo_poll: Poller,
+    /// Auto-refresh interval while the terminal is unfocused (0: paused)
+    pub unfocused_refresh_interval: u64,
+    /// Whether the terminal has focus (assumed until it reports otherwise)
+    pub focused: bool,
+    /// Background polling is paused (`p`); `r` still refreshes
+    pub polling_paused: bool,
     /// Available partitions
     pub available_partitions: Vec<String>,
+    /// Active and upcoming reservations, read with the partitions
+    pub reservations: Vec<Reservation>,
+    /// Cluster licenses, read with the partitions
+    pub licenses: Vec<License>,
+    /// Which QOSs preempt which, read with the partitions
+    pub qos_preemption: Vec<QosPreemption>,
+    /// Jobs pending per QOS, queue-wide, read with the partitions
+    pub pending_by_qos: HashMap<String, usize>,
     /// Available QOS options
     pub available_qos: Vec<String>,
     /// Available job states
@@ -85,33 +343,181 @@ pub struct App {
     pub recent_ended_hours: u32,
     /// Base directory used to search for completed-job logs (if configured).
     pub slurm_logs_dir: Option<PathBuf>,
-    /// Confirm cancel popup state
-    cancel_confirm: bool,
+    /// Filter presets from the config file (selected with the number keys)
+    pub presets: Vec<FilterPreset>,
+    /// The `[defaults]` view, which `0` returns to
+    default_view: FilterPreset,
+    /// Keys of the job list's and detail pane's actions (`[keys]`)
+    keymap: Keymap,
+    palette: Palette,
+    /// Index of the currently applied preset, if any
+    pub active_preset: Option<usize>,
+    /// Bulk action awaiting confirmation (confirm popup is shown while set)
+    pending_action: Option<JobAction>,
+    /// `Y` was pressed: the next key picks what of the job to copy
+    yank_pending: bool,
+    /// Popup form (resubmission, ...)
+    pub form: FormPopup,
+    form_purpose: Option<FormPurpose>,
+    /// Job tags and notes, optionally shared with the team
+    pub notes: NoteStore,
+    /// Name of the cluster, for the status bar
+    cluster: String,
+    /// Job to attach a shell to once the key press is handled
+    attach_request: Option<String>,
+    /// Programs jobs are opened with (`[open]`)
+    open_config: OpenConfig,
+    /// `X` was pressed: the next key picks what to open the job with
+    open_pending: bool,
+    /// Command to run with the TUI suspended once the key press is handled
+    external_request: Option<String>,
+    /// How the views fit narrow terminals (`[layout]`)
+    layout_config: LayoutConfig,
+    /// Width of the terminal, which picks the columns asked of Slurm
+    width: u16,
+    /// Mouse events are captured (`mouse` of the config)
+    mouse: bool,
+    /// When and on which table row the mouse was last clicked, to tell a
+    /// double-click
+    last_click: Option<(Instant, usize)>,
+    /// The session and the status snapshot are saved as they change (not
+    /// under a fixture or a replay)
+    persist_session: bool,
+    /// The last session, whose cursor and detail tab are restored once
+    /// the jobs are loaded
+    session: Option<Session>,
+    /// The session as last written, so it is only written again on a change
+    saved_session: Option<Session>,
+    /// Set by SIGHUP (an ssh drop) or SIGTERM, which end the loop cleanly
+    terminated: Arc<AtomicBool>,
+    /// node_exporter textfile rewritten with aggregate metrics on every refresh
+    metrics_textfile: Option<PathBuf>,
+    /// Queue counts per user/partition/state (aggregate mode)
+    pub aggregate_view: AggregateView,
+    /// Filters to go back to when returning from an aggregate drill-down
+    aggregate_return: Option<SqueueOptions>,
+    /// Which listed jobs wait on which
+    pub dependency_view: DependencyView,
+    /// Pending jobs grouped by reason
+    pub pending_view: PendingView,
+    /// Requeued and preempted jobs linked with their later attempts
+    pub chain_view: ChainView,
+    /// Jobs found by the search form
+    pub search_view: SearchView,
+    /// Fields of the search form as last submitted, to search again with
+    search_fields: Vec<(String, String)>,
+    /// Statistics of the polled jobs per user, account or partition
+    pub stats_view: StatsView,
+    /// Queue depth and allocation over the session's polls
+    pub trends_view: TrendsView,
+    pub cron_view: CronView,
+    pub reservation_view: ReservationView,
+    pub limits_view: LimitsView,
+    pub license_view: LicenseView,
+    pub node_view: NodeView,
+    /// `[[actions]]` of the config and the output of the last one run
+    pub action_menu: ActionMenu,
+    /// Ctrl+P: the current view's commands, searchable and runnable
+    pub command_palette: CommandPalette,
+    /// Jobs that need a typed confirmation to be cancelled or requeued
+    protection: Protection,
+    /// Bulk actions still being run or retried
+    action_queue: ActionQueue,
+    /// States the listed jobs were seen in, for the detail pane's timeline
+    state_history: StateHistory,
+    /// Every job of the last poll, before the in-app filters
+    job_store: JobStore,
+    notify: NotifyConfig,
+    /// Expected start of pending jobs by id, from the last `squeue --start`
+    start_estimates: HashMap<String, DateTime<Local>>,
+    estimate_poll: Poller,
+    /// Seconds between `squeue --start` queries
+    start_estimate_interval: u64,
+    /// Where the Slurm queries run, a few at a time
+    pool: Pool,
+    /// sinfo and the other cluster queries run with the next job poll
+    cluster_due: bool,
+    /// Counts job polls; one off the UI thread is dropped when another
+    /// started after it
+    poll_generation: u64,
+    poll_in_flight: bool,
+    cluster_in_flight: bool,
+    /// What the cluster's Slurm version supports
+    capabilities: Capabilities,
+    /// Why the last squeue poll failed, shown until one succeeds
+    slurm_error: Option<SlurmError>,
+    /// Fetches run off the UI thread send their results here
+    fetched_tx: Sender<Fetched>,
+    fetched_rx: Receiver<Fetched>,
+    /// Fixture served in place of squeue and sacct (`--demo`, `--fixture`)
+    mock: Option<MockJobSource>,
 }
 
 impl App {
-    /// Create a new application instance
-    pub fn new() -> Result<Self> {
+    /// [`App::with_config`] on the config files
+    pub fn with_events(mock: Option<MockJobSource>, event_handler: EventHandler) -> Result<Self> {
+        Self::with_config(mock, event_handler, load_config().unwrap_or_default())
+    }
+
+    /// Create a new application instance reading its events from
+    /// `event_handler`; with a `mock` source the jobs come from its fixture
+    /// instead of squeue and sacct
+    pub fn with_config(
+        mock: Option<MockJobSource>,
+        event_handler: EventHandler,
+        cfg: SlurmerConfig,
+    ) -> Result<Self> {
         let runtime = tokio::runtime::Builder::new_multi_thread()
             .enable_all()
             .build()
             .expect("Failed to create Tokio runtime");
 
-        // Default username for squeue
-        let username = get_username();
-        let squeue_options = SqueueOptions {
-            user: Some(username),
-            ..Default::default()
+        // The configured initial view, "me" being the fixture's user under a mock
+        let username = match &mock {
+            Some(mock) => mock.user.clone(),
+            None => get_username(),
+        };
+        let default_view = cfg.defaults.view();
+        let (keymap, mut config_problems) = Keymap::new(&cfg.keys);
+        let theme = match std::env::var_os("NO_COLOR") {
+            Some(v) if !v.is_empty() => Theme::Mono,
+            _ => cfg.theme,
         };
+        let (palette, color_problems) = Palette::new(theme, &cfg.colors, ColorDepth::detect());
+        config_problems.extend(color_problems);
+        let mut reference_popup = ReferencePopup::new();
+        reference_popup.set_bindings(keymap.describe());
+        let hint_keys = HintKeys {
+            resubmit: keymap.label(Action::Resubmit),
+            release: keymap.label(Action::Release),
+        };
+        let mut squeue_options = SqueueOptions::default();
+        let mut recent_ended_hours = 24;
+        default_view.apply_to(&mut squeue_options, &mut recent_ended_hours, &username);
 
-        // Get available partitions and QOS
-        let available_partitions = runtime.block_on(async { get_partitions().await })?;
-        let available_qos = runtime.block_on(async { get_qos().await })?;
+        // Partitions and QOSs of a fixture; Slurm's are read in the pool below
+        let mut sinfo_poll = Poller::new("sinfo");
+        let (fetched_tx, fetched_rx) = mpsc::channel();
+        let (available_partitions, available_qos) = match &mock {
+            Some(mock) => {
+                sinfo_poll.record(Instant::now(), Instant::now(), true);
+                (mock.partitions(), mock.qos())
+            }
+            None => (Vec::new(), Vec::new()),
+        };
         let available_states = JobState::get_available_states();
 
         // Default columns and sort options
-        let selected_columns = JobColumn::defaults();
-        let sort_columns = vec![
+        let mut selected_columns: Vec<JobColumn> = cfg
+            .defaults
+            .columns
+            .iter()
+            .filter_map(|name| JobColumn::from_name(name))
+            .collect();
+        if selected_columns.is_empty() {
+            selected_columns = JobColumn::defaults();
+        }
+        let mut sort_columns = vec![
             SortColumn {
                 column: JobColumn::State,
                 order: SortOrder::Ascending,
@@ -122,34 +528,227 @@ impl App {
             },
         ];
 
-        let cfg = load_config().unwrap_or_default();
+        // Resume the last session, except what the command line changed;
+        // the watchlist is kept either way
+        let session = match &mock {
+            None => load_session(),
+            Some(_) => None,
+        };
+        let watchlist = session
+            .as_ref()
+            .map(|s| s.watchlist.clone())
+            .unwrap_or_default();
+        let session = session.filter(|_| cfg.defaults.restore_session);
+        if let Some(session) = &session {
+            let overrides = overrides();
+            session
+                .filters
+                .apply_to(&mut squeue_options, &mut recent_ended_hours, &username);
+            overrides.apply_filters(&mut squeue_options, &mut recent_ended_hours, &username);
+            if overrides.columns.is_none() {
+                let columns = session.columns();
+                if !columns.is_empty() {
+                    selected_columns = columns;
+                }
+                let sort = session.sort_columns();
+                if !sort.is_empty() {
+                    sort_columns = sort;
+                }
+            }
+        }
+
+        cache::set_ttl(Duration::from_secs(cfg.refresh.cache_ttl_secs));
+        command::set_timeout(Duration::from_secs(cfg.refresh.command_timeout_secs));
         let slurm_logs_dir = resolve_slurm_logs_dir(&cfg);
+        let cluster = match (&cfg.notes.cluster, &mock) {
+            (Some(cluster), _) => cluster.clone(),
+            (None, Some(_)) => "fixture".to_string(),
+            (None, None) => runtime
+                .block_on(async { get_cluster_name().await })
+                .unwrap_or_default(),
+        };
+        let notes = NoteStore::new(&cfg.notes, &cluster);
+        let capabilities_path = data_dir().ok().map(|d| d.join("clusters.toml"));
+        let (capabilities, version_change) = match &mock {
+            Some(_) => (Capabilities::default(), None),
+            None => runtime.block_on(async {
+                capabilities::check(capabilities_path.as_deref(), &cluster).await
+            }),
+        };
+        let (protection, invalid_patterns) = Protection::new(&cfg.protect);
+        let (action_queue, expired) = ActionQueue::load();
+        let mouse = cfg.mouse_enabled();
+        let times = cfg.time.display();
 
-        Ok(Self {
+        let mut app = Self {
             running: true,
-            event_handler: EventHandler::new(EventConfig::default()),
+            event_handler,
             jobs_list: JobsList::new(),
             squeue_options,
             runtime,
-            last_refresh: Instant::now(),
+            squeue_poll: Poller::new("squeue"),
             filter_popup: FilterPopup::new(),
             settings_popup: SettingsPopup::new(),
+            job_detail: {
+                let mut detail = JobDetail::new();
+                detail.hints = cfg.hints.clone();
+                detail.hint_keys = hint_keys;
+                detail
+            },
+            reference_popup,
             columns_popup: ColumnsPopup::new(selected_columns.clone(), sort_columns.clone()),
             log_view: LogView::new(),
             script_view: JobScript::new(),
             status_message: String::new(),
             status_timeout: None,
-            job_refresh_interval: 10, // Default to 10 seconds refresh
+            job_refresh_interval: cfg.refresh.squeue_secs.max(1),
+            sacct_interval: cfg.refresh.sacct_secs,
+            sacct_max_rows: Some(cfg.refresh.sacct_max_rows).filter(|&n| n > 0),
+            sacct_window_hours: Some(cfg.refresh.sacct_window_hours).filter(|&h| h > 0),
+            sacct_poll: Poller::new("sacct"),
+            watched_jobs: HashMap::new(),
+            watched_poll: Poller::new("watched"),
+            settling: HashMap::new(),
+            ended_jobs: Vec::new(),
+            ended_truncated: false,
+            ended_query: None,
+            squeue_query: None,
+            sinfo_interval: cfg.refresh.sinfo_secs,
+            sinfo_poll,
+            unfocused_refresh_interval: cfg.refresh.unfocused_secs,
+            focused: true,
+            polling_paused: false,
             available_partitions,
+            reservations: Vec::new(),
+            licenses: Vec::new(),
+            qos_preemption: Vec::new(),
+            pending_by_qos: HashMap::new(),
             available_qos,
             available_states,
             selected_columns,
             sort_columns,
             include_recent_ended: true,
-            recent_ended_hours: 24,
+            recent_ended_hours,
+            default_view,
+            keymap,
+            palette,
             slurm_logs_dir,
-            cancel_confirm: false,
-        })
+            presets: cfg.presets,
+            active_preset: None,
+            pending_action: None,
+            yank_pending: false,
+            form: FormPopup::new(),
+            form_purpose: None,
+            notes,
+            cluster,
+            attach_request: None,
+            open_config: cfg.open.clone(),
+            open_pending: false,
+            external_request: None,
+            layout_config: cfg.layout.clone(),
+            width: crossterm::terminal::size().map_or(u16::MAX, |(width, _)| width),
+            mouse,
+            last_click: None,
+            persist_session: mock.is_none() && !recording::is_replaying(),
+            session,
+            saved_session: None,
+            terminated: Arc::new(AtomicBool::new(false)),
+            metrics_textfile: cfg.metrics.textfile_path(),
+            aggregate_view: AggregateView::new(),
+            aggregate_return: None,
+            dependency_view: DependencyView::new(),
+            pending_view: PendingView::new(),
+            chain_view: ChainView::new(),
+            search_view: SearchView::new(),
+            search_fields: [
+                ("User", "me"),
+                ("Name", ""),
+                ("States", ""),
+                ("Partitions", ""),
+                ("Accounts", ""),
+                ("From", "7d"),
+                ("To", ""),
+                ("Node", ""),
+            ]
+            .map(|(label, value)| (label.to_string(), value.to_string()))
+            .to_vec(),
+            stats_view: StatsView::new(),
+            trends_view: TrendsView::new(),
+            cron_view: CronView::new(),
+            reservation_view: ReservationView::new(),
+            limits_view: LimitsView::new(),
+            license_view: LicenseView::new(),
+            node_view: NodeView::new(cfg.admin.node_actions),
+            action_menu: ActionMenu::new(cfg.actions.clone()),
+            command_palette: CommandPalette::new(),
+            protection,
+            action_queue,
+            state_history: StateHistory::default(),
+            job_store: JobStore::default(),
+            notify: cfg.notify.clone(),
+            start_estimates: HashMap::new(),
+            estimate_poll: Poller::new("squeue --start"),
+            start_estimate_interval: cfg.refresh.start_estimate_secs,
+            pool: Pool::new(
+                cfg.refresh.max_concurrent,
+                Duration::from_millis(cfg.refresh.stagger_ms),
+            ),
+            cluster_due: false,
+            poll_generation: 0,
+            poll_in_flight: false,
+            cluster_in_flight: false,
+            capabilities,
+            slurm_error: None,
+            fetched_tx,
+            fetched_rx,
+            mock,
+        };
+        if app.mock.is_none() {
+            app.fetch_cluster_data()?;
+        }
+        app.jobs_list.watchlist = watchlist;
+        app.jobs_list.times = times.clone();
+        app.job_detail.times = times;
+        if !expired.is_empty() {
+            app.report_action_outcomes(expired);
+        }
+        if let Some(problem) = config_problems.first() {
+            app.set_status_message(problem.clone(), 10);
+        }
+        if let Ok((path, problems)) = check_config() {
+            if let Some(first) = problems.first() {
+                let more = match problems.len() {
+                    1 => String::new(),
+                    n => format!(" (+{} more, see `slurmer check-config`)", n - 1),
+                };
+                app.set_status_message(format!("{}:{}{}", path.display(), first, more), 15);
+            }
+        }
+        if let Some(change) = version_change {
+            app.set_status_message(
+                format!(
+                    "Slurm on {} was upgraded from {} to {}; re-checked what it supports",
+                    if change.cluster.is_empty() {
+                        "this cluster"
+                    } else {
+                        &change.cluster
+                    },
+                    change.from,
+                    change.to
+                ),
+                10,
+            );
+        }
+        if !invalid_patterns.is_empty() {
+            app.set_status_message(
+                format!(
+                    "Ignoring invalid protect patterns: {}",
+                    invalid_patterns.join(", ")
+                ),
+                10,
+            );
+        }
+        Ok(app)
     }
 
     /// Run the application's main loop
@@ -157,68 +756,371 @@ impl App {
         &mut self,
         terminal: &mut ratatui::Terminal<B>,
     ) -> Result<()> {
+        for signal in [SIGHUP, SIGTERM] {
+            signal_hook::flag::register(signal, Arc::clone(&self.terminated))?;
+        }
         // Initial job loading
         self.refresh_jobs()?;
+        self.resume_session();
 
-        while self.running {
+        let result = self.main_loop(terminal);
+        // However the loop ended, a dropped terminal failing to draw included
+        self.save_session();
+        result
+    }
+
+    fn main_loop<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<B>,
+    ) -> Result<()> {
+        while self.running && !self.terminated.load(AtomicOrdering::Relaxed) {
             terminal.draw(|frame| self.render(frame))?;
             self.handle_events()?;
+            if let Some(job_id) = self.attach_request.take() {
+                self.attach_shell(terminal, &job_id)?;
+            }
+            if let Some(command) = self.external_request.take() {
+                self.run_external(terminal, &command)?;
+            }
         }
-
         Ok(())
     }
 
-    /// Refresh the jobs list from Slurm
-    fn refresh_jobs(&mut self) -> Result<()> {
+    /// Put the cursor, scroll position and detail pane back where the last
+    /// session left them
+    fn resume_session(&mut self) {
+        let Some(session) = self.session.take() else {
+            return;
+        };
+        self.jobs_list.set_group_by(session.group_by());
+        let Some(id) = &session.selected else {
+            return;
+        };
+        if !self.jobs_list.focus_job(id) {
+            return;
+        }
+        *self.jobs_list.state.offset_mut() = session.offset;
+        if let Some(tab) = session.detail_tab() {
+            self.show_job_detail();
+            self.job_detail.tab = tab;
+            self.load_job_detail();
+        }
+    }
+
+    /// Write the session when it changed since it was last written
+    fn save_session(&mut self) {
+        if !self.persist_session {
+            return;
+        }
+        let session = self.current_session();
+        if self.saved_session.as_ref() == Some(&session) {
+            return;
+        }
+        // Losing it only costs the next start its view
+        if save_session(&session).is_ok() {
+            self.saved_session = Some(session);
+        }
+    }
+
+    fn current_session(&self) -> Session {
+        let mut session = Session {
+            filters: Session::filters_of(&self.squeue_options, self.recent_ended_hours),
+            group_by: self.jobs_list.group_by.map(|g| g.title().to_string()),
+            selected: self.jobs_list.selected_job().map(|j| j.id.clone()),
+            offset: self.jobs_list.state.offset(),
+            detail_tab: self
+                .job_detail
+                .visible
+                .then(|| self.job_detail.tab.title().to_string()),
+            watchlist: self.jobs_list.watchlist.clone(),
+            ..Default::default()
+        };
+        session.set_columns(&self.selected_columns, &self.sort_columns);
+        session
+    }
+
+    /// Refresh the jobs list from Slurm. Slurm failing to answer is not an
+    /// error here: the header warns about it until a poll succeeds, and
+    /// polling retries with backoff.
+    pub fn refresh_jobs(&mut self) -> Result<()> {
+        let result = self.poll_jobs();
+        self.settle_poll(result)
+    }
+
+    /// Keep why Slurm failed to answer a poll for the header; other errors
+    /// are passed on
+    fn settle_poll(&mut self, result: Result<()>) -> Result<()> {
+        match result {
+            Ok(()) => {
+                self.slurm_error = None;
+                Ok(())
+            }
+            Err(e) => match e.downcast::<SlurmError>() {
+                Ok(error) => {
+                    self.slurm_error = Some(error);
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            },
+        }
+    }
+
+    /// Refresh the jobs list from Slurm, failing when Slurm does not answer
+    pub fn poll_jobs(&mut self) -> Result<()> {
+        // A poll under way off the UI thread would be older than this one
+        self.poll_generation += 1;
+        self.poll_in_flight = false;
+        let plan = self.plan_poll();
+        let fetches = match &self.mock {
+            Some(mock) => poll_mock(mock, &plan),
+            None => {
+                let pool = self.pool.clone();
+                self.runtime.block_on(run_poll(pool, &plan))
+            }
+        };
+        self.apply_poll(plan, fetches)
+    }
+
+    /// Start a job poll off the UI thread; a later tick applies it
+    fn start_poll(&mut self) {
+        let plan = self.plan_poll();
+        let generation = self.poll_generation;
+        let pool = self.pool.clone();
+        let tx = self.fetched_tx.clone();
+        self.runtime.spawn(async move {
+            let fetches = run_poll(pool, &plan).await;
+            let _ = tx.send(Fetched::Poll(generation, Box::new(plan), fetches));
+        });
+        self.poll_in_flight = true;
+    }
+
+    /// What the next job poll asks Slurm for
+    fn plan_poll(&mut self) -> PollPlan {
         // Update squeue format and sort options
         self.update_squeue_format();
 
         // Clone options after format has been updated
         let options = self.squeue_options.clone();
-        let mut jobs = self
-            .runtime
-            .block_on(async { run_squeue(&options).await })?;
+        let sacct_options = self.include_recent_ended.then(|| SacctOptions {
+            user: options.user.clone(),
+            states: JobState::ended_states(),
+            partitions: options.partitions.clone(),
+            qos: options.qos.clone(),
+            accounts: options.accounts.clone(),
+            wckeys: options.wckeys.clone(),
+            name: options.name_filter.clone().filter(|n| !n.is_empty()),
+            recent_hours: self.recent_ended_hours,
+            end_hours: 0,
+            format_fields: self.sacct_format_fields(),
+            max_rows: self.sacct_max_rows,
+            window_hours: self.sacct_window_hours,
+        });
+        // Ended jobs change slowly: sacct is asked again when due, or at
+        // once for a different query
+        let same_sacct_query = self.ended_query == sacct_options;
+        let sacct_due = sacct_options.is_some()
+            && (!same_sacct_query
+                || self
+                    .sacct_poll
+                    .due(Duration::from_secs(self.sacct_interval), Instant::now()));
 
-        // Fetch recently-ended jobs (default: last 24 hours) via sacct and merge.
-        if self.include_recent_ended {
-            let terminal_states = vec![
-                JobState::Completed,
-                JobState::Failed,
-                JobState::Cancelled,
-                JobState::Timeout,
-                JobState::NodeFail,
-                JobState::Preempted,
-                JobState::Boot,
-            ];
-
-            let format_fields = self
-                .selected_columns
+        // Watched jobs without a final record, and those active at the last
+        // poll of this query, are read from accounting unless squeue lists them
+        let watched = if self
+            .watched_poll
+            .due(Duration::from_secs(self.sacct_interval), Instant::now())
+        {
+            self.jobs_list
+                .watchlist
                 .iter()
-                .map(|c| c.sacct_field())
-                .collect::<Vec<_>>();
-
-            let sacct_options = SacctOptions {
-                user: options.user.clone(),
-                states: terminal_states,
-                partitions: options.partitions.clone(),
-                qos: options.qos.clone(),
-                recent_hours: self.recent_ended_hours,
-                format_fields,
-            };
+                .filter(|id| {
+                    self.watched_jobs
+                        .get(*id)
+                        .is_none_or(|j| j.state.is_active())
+                })
+                .cloned()
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let active = if self.squeue_query.as_ref() == Some(&options) {
+            self.job_store
+                .jobs()
+                .into_iter()
+                .filter(|j| j.state.is_active() && !j.id.contains('['))
+                .map(|j| j.id)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let mut record_fields = vec!["JobIDRaw", "JobName", "User", "State", "ExitCode", "End"];
+        for field in self.sacct_format_fields() {
+            if !record_fields.contains(&field) {
+                record_fields.push(field);
+            }
+        }
+        let interval = Duration::from_secs(self.start_estimate_interval);
+        PollPlan {
+            estimates: self.needs_start_estimates()
+                && self.estimate_poll.due(interval, Instant::now()),
+            cluster: self.mock.is_none() && std::mem::take(&mut self.cluster_due),
+            options,
+            sacct_options,
+            sacct_due,
+            watched,
+            active,
+            record_fields,
+        }
+    }
 
-            match self.runtime.block_on(async { run_sacct(&sacct_options).await }) {
-                Ok(ended_jobs) => {
-                    jobs = merge_jobs_prefer_active(jobs, ended_jobs);
+    /// Take in what a job poll fetched
+    fn apply_poll(&mut self, plan: PollPlan, fetches: Vec<Timed<Fetch>>) -> Result<()> {
+        let PollPlan {
+            options,
+            sacct_options,
+            ..
+        } = plan;
+        let same_sacct_query = self.ended_query == sacct_options;
+        let mut fetched = self.take_fetches(fetches);
+        let result = fetched
+            .squeue
+            .take()
+            .unwrap_or_else(|| Err(eyre!("squeue did not run")));
+        let mut jobs = result?;
+        self.apply_start_estimates(&mut jobs, fetched.estimates);
+        let same_squeue_query = self.squeue_query.as_ref() == Some(&options);
+        self.squeue_query = Some(options.clone());
+
+        // Fetch recently-ended jobs (default: last 24 hours) via sacct and merge.
+        if let Some(sacct_options) = sacct_options {
+            if let Some(result) = fetched.sacct {
+                match result {
+                    Ok(rows) => {
+                        if rows.truncated {
+                            self.set_status_message(
+                                format!(
+                                    "Only the first {} ended jobs are listed; narrow the filters or the window",
+                                    rows.jobs.len()
+                                ),
+                                5,
+                            );
+                        }
+                        self.ended_jobs = rows.jobs;
+                        self.ended_truncated = rows.truncated;
+                    }
+                    Err(e) => {
+                        if !same_sacct_query {
+                            self.ended_jobs.clear();
+                            self.ended_truncated = false;
+                        }
+                        self.set_status_message(
+                            format!("Recent-ended jobs unavailable (sacct): {}", e),
+                            3,
+                        );
+                    }
                 }
-                Err(e) => {
-                    self.set_status_message(
-                        format!("Recent-ended jobs unavailable (sacct): {}", e),
-                        3,
-                    );
+                self.ended_query = Some(sacct_options);
+            }
+            jobs = merge_jobs_prefer_active(jobs, self.ended_jobs.clone());
+        }
+
+        // Watched jobs are listed whatever the filters, however long ago
+        // they ended
+        let unlisted: Vec<String> = self
+            .jobs_list
+            .watchlist
+            .iter()
+            .filter(|id| !jobs.iter().any(|j| &j.id == *id))
+            .cloned()
+            .collect();
+        self.watched_jobs.retain(|id, _| unlisted.contains(id));
+        if let Some(Ok(records)) = fetched.watched {
+            self.watched_jobs.extend(
+                records
+                    .into_iter()
+                    .filter(|j| unlisted.contains(&j.id))
+                    .map(|j| (j.id.clone(), j)),
+            );
+        }
+        jobs.extend(
+            unlisted
+                .iter()
+                .filter_map(|id| self.watched_jobs.get(id))
+                .cloned(),
+        );
+
+        // Jobs that just left squeue are read from accounting at once, so
+        // their final state is announced now rather than with the next
+        // sacct window. Until accounting has their end they stay as last
+        // seen, and are asked for again on the next polls.
+        if same_squeue_query {
+            let vanished: Vec<Job> = self
+                .job_store
+                .jobs()
+                .into_iter()
+                .filter(|j| j.state.is_active() && !j.id.contains('['))
+                .filter(|j| !jobs.iter().any(|p| p.id == j.id))
+                .collect();
+            self.settling
+                .retain(|id, _| vanished.iter().any(|j| &j.id == id));
+            if !vanished.is_empty() {
+                let records = fetched.ended.and_then(Result::ok).unwrap_or_default();
+                for last_seen in vanished {
+                    match records.iter().find(|r| r.id == last_seen.id) {
+                        Some(ended) if !ended.state.is_active() => {
+                            self.settling.remove(&ended.id);
+                            if self.include_recent_ended {
+                                self.ended_jobs.push(ended.clone());
+                            }
+                            jobs.push(ended.clone());
+                        }
+                        _ => {
+                            let polls = self.settling.entry(last_seen.id.clone()).or_insert(0);
+                            *polls += 1;
+                            if *polls <= SETTLE_POLLS {
+                                jobs.push(last_seen);
+                            }
+                        }
+                    }
                 }
             }
+        } else {
+            self.settling.clear();
+        }
+
+        // `--record --redact` replaces the names of their users too
+        recording::note_users(jobs.iter().map(|j| j.user.as_str()));
+
+        // Jobs keep their identity across polls; the diff tells what changed
+        let diff = self.job_store.apply(jobs);
+        self.notify_finished(&diff);
+        let mut jobs = self.job_store.jobs();
+        self.trends_view.history.record(&jobs, unix_now());
+
+        // Cache the user's own queue so `slurmer status` can answer without
+        // hitting Slurm; fixtures, replays and narrowed or other users'
+        // queues are not what it reports
+        let user = get_username();
+        let own_queue = self.squeue_options.user.as_deref() == Some(user.as_str())
+            && self.squeue_options.states.is_empty()
+            && self.squeue_options.partitions.is_empty()
+            && self.squeue_options.qos.is_empty()
+            && self.squeue_options.accounts.is_empty();
+        if self.persist_session && own_queue {
+            let own: Vec<Job> = jobs.iter().filter(|j| j.user == user).cloned().collect();
+            let _ = save_snapshot(&Snapshot::from_jobs(&own));
+        }
+        if let Some(path) = &self.metrics_textfile {
+            if let Err(e) = write_textfile(path, &jobs) {
+                self.set_status_message(format!("Metrics textfile not written: {}", e), 3);
+            }
         }
 
+        let pinned: Vec<Job> = jobs
+            .iter()
+            .filter(|j| self.jobs_list.is_watched(&j.id))
+            .cloned()
+            .collect();
         let mut filter_stats = Vec::new();
         let initial_count = jobs.len();
 
@@ -226,7 +1128,7 @@ impl App {
         if let Some(name_filter) = &self.squeue_options.name_filter {
             if !name_filter.is_empty() {
                 // Try to compile the regex pattern
-                match regex::Regex::new(name_filter) {
+                match name_regex(name_filter) {
                     Ok(re) => {
                         // Filter jobs by name using regex
                         let before_count = jobs.len();
@@ -259,9 +1161,11 @@ impl App {
                         // Filter jobs by node name using regex
                         let before_count = jobs.len();
                         jobs.retain(|job| {
-                            // If there's a node field, check it against the regex
+                            // If there's a node field, check it and each of its
+                            // hosts (`gpu[05-08]` holds `gpu07`) against the regex
                             if let Some(node) = &job.node {
                                 re.is_match(node)
+                                    || hostlist::expand(node).iter().any(|h| re.is_match(h))
                             } else {
                                 // If no node name available, don't filter this job
                                 true
@@ -286,10 +1190,40 @@ impl App {
             }
         }
 
+        if self.squeue_options.gpu_only {
+            let before_count = jobs.len();
+            jobs.retain(|j| j.gpus > 0);
+            let after_count = jobs.len();
+            if before_count != after_count && before_count > 0 {
+                filter_stats.push(format!(
+                    "gpu: {}/{} ({:.1}%)",
+                    after_count,
+                    before_count,
+                    (after_count as f64 / before_count as f64) * 100.0
+                ));
+            }
+        }
+
+        // sacct filters on WCKeys itself; squeue cannot
+        let wckeys = &self.squeue_options.wckeys;
+        if !wckeys.is_empty() {
+            let before_count = jobs.len();
+            jobs.retain(|j| j.wckey.as_ref().is_some_and(|k| wckeys.contains(k)));
+            let after_count = jobs.len();
+            if before_count != after_count && before_count > 0 {
+                filter_stats.push(format!(
+                    "wckey: {}/{} ({:.1}%)",
+                    after_count,
+                    before_count,
+                    (after_count as f64 / before_count as f64) * 100.0
+                ));
+            }
+        }
+
         // Apply explicit state filter (if user selected any) to the merged list.
         if !self.squeue_options.states.is_empty() {
             let before_count = jobs.len();
-            jobs.retain(|j| self.squeue_options.states.contains(&j.state));
+            jobs.retain(|j| self.squeue_options.states.contains(&j.state.kind()));
             let after_count = jobs.len();
             if before_count != after_count && before_count > 0 {
                 filter_stats.push(format!(
@@ -322,13 +1256,93 @@ impl App {
             );
         }
 
+        if let Some(hours) = self.squeue_options.start_within_hours {
+            let deadline = Local::now() + TimeDelta::hours(hours.into());
+            let before_count = jobs.len();
+            jobs.retain(|j| {
+                j.state == JobState::Pending
+                    && j.estimated_start.is_some_and(|start| start <= deadline)
+            });
+            let after_count = jobs.len();
+            if before_count != after_count && before_count > 0 {
+                filter_stats.push(format!(
+                    "start: {}/{} ({:.1}%)",
+                    after_count,
+                    before_count,
+                    (after_count as f64 / before_count as f64) * 100.0
+                ));
+            }
+        }
+
+        if filter_stats.is_empty()
+            && self.squeue_options.user.is_none()
+            && jobs.len() >= LARGE_QUEUE_JOBS
+        {
+            self.set_status_message(
+                format!(
+                    "{} jobs listed; [A] shows counts per user/partition without fetching them",
+                    jobs.len()
+                ),
+                5,
+            );
+        }
+
+        jobs.retain(|j| !self.jobs_list.is_watched(&j.id));
+        jobs.extend(pinned);
+        let mut jobs = group_het_jobs(jobs);
+        self.apply_preemption(&mut jobs);
         sort_jobs(&mut jobs, &self.sort_columns);
+        // The sort is stable: watched jobs go first, in their own order
+        jobs.sort_by_key(|j| !self.jobs_list.is_watched(&j.id));
+        self.state_history.observe(&jobs, Local::now());
+        if let Some(job) = &self.job_detail.job {
+            self.job_detail.transitions = self.state_history.transitions(&job.id).to_vec();
+        }
         self.jobs_list.update_jobs(jobs);
-        self.last_refresh = Instant::now();
+
+        let shown = self.job_detail.job.as_ref().map(|j| j.id.clone());
+        if let Some(id) = shown.filter(|id| diff.changed.contains(id)) {
+            if let Some(job) = self.jobs_list.jobs.iter().find(|j| j.id == id).cloned() {
+                self.job_detail.update_job(job);
+                self.load_job_detail();
+            }
+        }
 
         Ok(())
     }
 
+    /// Announce the jobs that finished when `tui` of `[notify]` asks for
+    /// it: the watched ones when jobs are pinned, else the user's own
+    fn notify_finished(&mut self, diff: &JobDiff) {
+        if !self.notify.tui {
+            return;
+        }
+        let watched = !self.jobs_list.watchlist.is_empty();
+        let user = get_username();
+        for change in diff.transitions.iter().filter(|c| c.finished()) {
+            let announced = if watched {
+                self.jobs_list.is_watched(&change.id)
+            } else {
+                self.job_store
+                    .get(&change.id)
+                    .is_some_and(|j| j.user == user)
+            };
+            if !announced {
+                continue;
+            }
+            let event = JobFinished {
+                job_id: change.id.clone(),
+                state: change.to.to_string(),
+                ok: change.to == JobState::Completed,
+            };
+            let message = match notify_job_finished(&self.notify, &event).first() {
+                Some(e) => format!("{} (notification failed: {})", event.summary(), e),
+                None => event.summary(),
+            };
+            self.set_status_message(message, 5);
+        }
+    }
+
     /// Render the application UI
     pub fn render(&mut self, frame: &mut Frame) {
         let areas = draw_main_layout(frame);
@@ -339,10 +1353,41 @@ impl App {
         // Draw jobs list in the main content area with current column settings
         // Make sure to still render the jobs list even when log view is visible
         // so that the jobs list is updated when user navigates with SHIFT+arrow keys
-        self.render_joblist(frame, areas[1]);
+        let mut main_area = areas[1];
+        if self.search_view.open {
+            let tabbed = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(0)])
+                .split(main_area);
+            self.search_view.render_tabs(frame, tabbed[0]);
+            main_area = tabbed[1];
+        }
+        if self.job_detail.visible && self.job_detail.zoomed {
+            self.job_detail.render(frame, main_area);
+        } else if self.job_detail.visible {
+            let width = self.job_detail.width_percent;
+            // Narrow terminals get the pane under the list, at full width
+            let stacked = frame.area().width < self.layout_config.stack_below;
+            let panes = Layout::default()
+                .direction(if stacked {
+                    Direction::Vertical
+                } else {
+                    Direction::Horizontal
+                })
+                .constraints([
+                    Constraint::Percentage(100 - width),
+                    Constraint::Percentage(width),
+                ])
+                .split(main_area);
+            self.render_joblist(frame, panes[0]);
+            self.job_detail.render(frame, panes[1]);
+        } else {
+            self.render_joblist(frame, main_area);
+        }
 
         // Draw the footer with controls
-        self.render_footer(frame, areas[2]);
+        self.render_status_bar(frame, areas[2]);
+        draw_footer(frame, areas[3]);
 
         // If filter popup is visible, draw it
         if self.filter_popup.visible {
@@ -373,23 +1418,146 @@ impl App {
             self.render_log_view(frame, popup_area);
         }
 
-        // If cancel confirm popup is visible, draw it
-        if self.cancel_confirm {
-            let popup_area = centered_popup_area(frame.area(), 50, 30);
-            self.render_cancel_confirm(frame, popup_area);
+        if self.form.visible {
+            let popup_area = centered_popup_area(frame.area(), 60, 60);
+            self.form.render(frame, popup_area);
+        }
+
+        if self.aggregate_view.visible {
+            let popup_area = centered_popup_area(frame.area(), 80, 80);
+            self.aggregate_view.render(frame, popup_area);
+        }
+
+        if self.dependency_view.visible {
+            let popup_area = centered_popup_area(frame.area(), 80, 80);
+            self.dependency_view.render(frame, popup_area);
+        }
+
+        if self.pending_view.visible {
+            let popup_area = centered_popup_area(frame.area(), 80, 70);
+            self.pending_view.render(frame, popup_area);
+        }
+
+        if self.chain_view.visible {
+            let popup_area = centered_popup_area(frame.area(), 80, 70);
+            self.chain_view.render(frame, popup_area);
+        }
+
+        if self.stats_view.visible {
+            let popup_area = centered_popup_area(frame.area(), 90, 70);
+            self.stats_view.render(frame, popup_area);
+        }
+
+        if self.trends_view.visible {
+            let popup_area = centered_popup_area(frame.area(), 80, 80);
+            self.trends_view.render(frame, popup_area);
+        }
+
+        if self.cron_view.popup.visible {
+            let popup_area = centered_popup_area(frame.area(), 90, 70);
+            self.cron_view.render(frame, popup_area);
+        }
+
+        if self.reservation_view.popup.visible {
+            let popup_area = centered_popup_area(frame.area(), 90, 60);
+            self.reservation_view.render(frame, popup_area);
+        }
+
+        if self.limits_view.popup.visible {
+            let popup_area = centered_popup_area(frame.area(), 80, 60);
+            self.limits_view.render(frame, popup_area);
+        }
+
+        if self.license_view.popup.visible {
+            let popup_area = centered_popup_area(frame.area(), 70, 50);
+            self.license_view.render(frame, popup_area);
+        }
+
+        if self.node_view.popup.visible {
+            let popup_area = centered_popup_area(frame.area(), 85, 70);
+            self.node_view.render(frame, popup_area);
         }
+
+        if self.action_menu.visible {
+            let popup_area = centered_popup_area(frame.area(), 80, 70);
+            self.action_menu.render(frame, popup_area);
+        }
+
+        if self.reference_popup.visible {
+            let popup_area = centered_popup_area(frame.area(), 70, 70);
+            self.reference_popup.render(frame, popup_area);
+        }
+
+        if self.command_palette.visible {
+            let popup_area = centered_popup_area(frame.area(), 70, 60);
+            self.command_palette.render(frame, popup_area);
+        }
+
+        // If an action confirm popup is visible, draw it
+        if let Some(action) = self.pending_action {
+            let popup_area = centered_popup_area(frame.area(), 60, 30);
+            self.render_action_confirm(frame, popup_area, action);
+        }
+
+        self.palette.apply(frame.buffer_mut());
     }
 
-    /// Render the joblist
+    /// Render the joblist, or the search results when theirs is the tab shown
     fn render_joblist(&mut self, frame: &mut Frame, area: Rect) {
+        if self.search_view.visible {
+            self.search_view.render(frame, area);
+            return;
+        }
         // Draw the jobs list in the main content area with current column settings
+        let columns = self.shown_columns(frame.area().width);
         self.jobs_list
-            .render(frame, area, &self.selected_columns, &self.sort_columns);
+            .render(frame, area, &columns, &self.sort_columns);
+    }
+
+    /// The columns of the job list on a terminal `width` wide: those of the
+    /// narrowest `[layout]` breakpoint that applies, else the chosen ones
+    fn shown_columns(&self, width: u16) -> Vec<JobColumn> {
+        let compact: Vec<JobColumn> = self
+            .layout_config
+            .columns_for(width)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|name| JobColumn::from_name(name))
+            .collect();
+        if compact.is_empty() {
+            self.selected_columns.clone()
+        } else {
+            compact
+        }
+    }
+
+    /// The columns of the layout the terminal's width picks, the only ones
+    /// asked of Slurm
+    fn fetched_columns(&self) -> Vec<JobColumn> {
+        self.shown_columns(self.width)
+    }
+
+    /// Take the terminal's new width; when it picks other `[layout]`
+    /// columns the jobs are polled again for them
+    pub fn handle_resize(&mut self, width: u16) {
+        let before = self.fetched_columns();
+        self.width = width;
+        // Before the first poll there is nothing to ask again
+        if self.squeue_query.is_some() && self.fetched_columns() != before {
+            self.sacct_poll.expire();
+            if let Err(e) = self.refresh_jobs() {
+                self.set_status_message(format!("Failed to refresh: {}", e), 3);
+            }
+        }
     }
 
     /// Render the columns management popup
     fn render_columns_popup(&mut self, frame: &mut Frame, area: Rect) {
-        // Render the columns management popup
+        let width = frame.area().width;
+        self.columns_popup.layout_columns = self
+            .layout_config
+            .columns_for(width)
+            .map(|_| self.shown_columns(width));
         self.columns_popup.render(frame, area);
     }
 
@@ -416,26 +1584,34 @@ impl App {
         self.script_view.render(frame, area);
     }
 
-    /// Render the footer with XXX TODO:replace it
-    fn render_footer(&self, frame: &mut Frame, area: Rect) {
-        // calculate Pending/Running/Other jobs count percentages
-        let pending_count = self
-            .jobs_list
-            .jobs
-            .iter()
-            .filter(|j| j.state == JobState::Pending)
-            .count();
-        let running_count = self
-            .jobs_list
-            .jobs
-            .iter()
-            .filter(|j| j.state == JobState::Running)
-            .count();
-        let other_count = self.jobs_list.jobs.len() - pending_count - running_count;
-        let job_stat = (pending_count, running_count, other_count);
-
-        // Draw the footer
-        draw_footer(frame, area, job_stat);
+    /// Render the status bar: cluster, controller, polls and job counts
+    fn render_status_bar(&self, frame: &mut Frame, area: Rect) {
+        let controller = match (&self.mock, &self.slurm_error, self.squeue_poll.took()) {
+            (Some(_), _, _) => Controller::Fixture,
+            (None, Some(_), _) => Controller::Down,
+            (None, None, Some(_)) => Controller::Up,
+            (None, None, None) => Controller::Unknown,
+        };
+        let mut pollers = vec![&self.squeue_poll];
+        if self.include_recent_ended {
+            pollers.push(&self.sacct_poll);
+        }
+        pollers.extend([&self.sinfo_poll, &self.estimate_poll]);
+        let sources = pollers
+            .into_iter()
+            .map(|p| Source {
+                name: p.name,
+                took: p.took(),
+                error: p.error(),
+            })
+            .collect();
+        StatusBar {
+            cluster: &self.cluster,
+            controller,
+            sources,
+            jobs: self.jobs_list.listed_jobs(),
+        }
+        .render(frame, area);
     }
 
     /// Render the header with status information
@@ -464,48 +1640,134 @@ impl App {
             }
         };
 
-        // Draw the header with status information
+        // Prefix the selection state so bulk actions are never a surprise
+        let selected_count = self.jobs_list.selected_jobs.len();
+        let status_text = match (self.jobs_list.in_visual_mode(), selected_count) {
+            (true, n) => format!("-- VISUAL -- {} selected | {}", n, status_text),
+            (false, 0) => status_text,
+            (false, n) => format!("{} selected | {}", n, status_text),
+        };
+        let status_text = match self.action_queue.len() {
+            0 => status_text,
+            n => format!("{} action(s) queued | {}", n, status_text),
+        };
+
+        // Draw the header with status information
+        let warning = self
+            .slurm_error
+            .as_ref()
+            .map(|e| format!("⚠ {}: {} | [r] retry now", e, e.hint()));
         draw_header(
             frame,
             area,
             &status_text,
-            self.last_refresh.elapsed(),
-            self.job_refresh_interval,
+            warning.as_deref(),
+            &self.refresh_status(),
         );
     }
 
-    fn render_cancel_confirm(&self, frame: &mut Frame, area: Rect) {
+    /// Last and next refresh of each polled source: `squeue 4s ago, next 6s | ...`
+    pub fn refresh_status(&self) -> String {
+        let now = Instant::now();
+        let interval = self.refresh_interval().map(Duration::from_secs);
+        // The slower sources never poll faster than the job list
+        let slower = |secs: u64| interval.map(|i| i.max(Duration::from_secs(secs)));
+        let mut sources = vec![self.squeue_poll.status(interval, now)];
+        if self.include_recent_ended && !self.aggregate_view.visible {
+            let mut status = self.sacct_poll.status(slower(self.sacct_interval), now);
+            status.push_str(&format!(
+                " (last {})",
+                format_window(self.recent_ended_hours)
+            ));
+            if self.ended_truncated {
+                status.push_str(&format!(" (truncated at {})", self.ended_jobs.len()));
+            }
+            sources.push(status);
+        }
+        sources.push(self.sinfo_poll.status(slower(self.sinfo_interval), now));
+        if self.polling_paused {
+            sources.push("PAUSED ([p] resumes)".to_string());
+        } else if !self.focused {
+            sources.push("unfocused".to_string());
+        }
+        sources.join(" | ")
+    }
+
+    fn render_action_confirm(&self, frame: &mut Frame, area: Rect, action: JobAction) {
         frame.render_widget(Clear, area);
-        // Render the cancel confirm popup
+        // Render the action confirm popup
         let selected_count = self.jobs_list.get_selected_jobs().len();
-        let cancel_text = if selected_count == 0 {
-            "No jobs selected for cancellation.".to_string()
+        let mut cancel_text = if selected_count == 0 {
+            format!("No jobs selected to {}.", action.verb())
         } else {
             format!(
-                "Are you sure you want to cancel {} selected job(s)? (y/n)",
+                "Are you sure you want to {} {} selected job(s)? (y/n)",
+                action.verb(),
                 selected_count
             )
         };
+        if action == JobAction::Cancel && selected_count > 0 {
+            cancel_text.push_str(&self.cancel_what_if_text());
+        }
 
         let block = Block::default()
-            .title(Line::from("Confirm Cancel").centered())
+            .title(Line::from(format!("Confirm {}", action.verb())).centered())
             .borders(Borders::NONE)
             .style(Style::default().bg(Color::Black));
 
         let cancel_popup = Paragraph::new(cancel_text)
             .style(Style::default().fg(Color::Cyan))
             .block(block)
+            .wrap(Wrap { trim: false })
             .centered();
 
         frame.render_widget(cancel_popup, area);
     }
 
+    /// What cancelling the selected jobs would free up, and which of the
+    /// user's pending jobs could then start
+    fn cancel_what_if_text(&self) -> String {
+        let jobs = &self.jobs_list.jobs;
+        let cancelled: Vec<&Job> = self
+            .jobs_list
+            .selected_jobs
+            .iter()
+            .filter_map(|&i| jobs.get(i))
+            .collect();
+        let user = get_username();
+        let pending: Vec<&Job> = jobs.iter().filter(|j| j.user == user).collect();
+        let what_if = simulate_cancel(&cancelled, &pending);
+        if what_if.freed.is_empty() {
+            return String::new();
+        }
+
+        let freed = what_if
+            .freed
+            .iter()
+            .map(|(partition, resources)| format!("{} on {}", resources, partition))
+            .collect::<Vec<_>>()
+            .join("; ");
+        let startable = if what_if.startable.is_empty() {
+            "none of your pending jobs would fit".to_string()
+        } else {
+            what_if
+                .startable
+                .iter()
+                .map(|(id, partition)| format!("{} ({})", id, partition))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        format!("\n\nFrees {}.\nCould start: {}", freed, startable)
+    }
+
     /// Handle application events
     fn handle_events(&mut self) -> Result<()> {
         match self.event_handler.rx.recv()? {
             AppEvent::Key(key) if key.kind == KeyEventKind::Press => self.handle_key_event(key),
             AppEvent::Mouse(mouse) => self.handle_mouse_event(mouse),
-            AppEvent::Resize(_, _) => {}
+            AppEvent::Resize(width, _) => self.handle_resize(width),
+            AppEvent::Focus(focused) => self.handle_focus(focused),
+            AppEvent::Paste(text) => self.handle_paste(&text),
             AppEvent::Tick => self.handle_tick(),
             _ => {}
         }
@@ -514,136 +1776,211 @@ impl App {
     }
 
     /// Handle key events
-    fn handle_key_event(&mut self, key: KeyEvent) {
-        match (key.modifiers, key.code) {
-            // Quit application
-            (_, KeyCode::Esc) | (KeyModifiers::CONTROL, KeyCode::Char('c')) => {
-                if self.filter_popup.visible
-                    || self.settings_popup.visible
-                    || self.script_view.visible
-                    || self.columns_popup.visible
-                    || self.log_view.visible
-                    || self.cancel_confirm
-                {
-                    self.filter_popup.visible = false;
-                    self.settings_popup.visible = false;
-                    self.script_view.visible = false;
-                    self.columns_popup.visible = false;
-                    self.log_view.hide();
-                    self.cancel_confirm = false;
-                } else {
-                    self.quit();
+    pub fn handle_key_event(&mut self, key: KeyEvent) {
+        if let Some(input) = self.map_key(key) {
+            self.dispatch_key(input);
+        }
+    }
+
+    /// Handle a key as mapped by the keymap
+    fn dispatch_key(&mut self, input: Input) {
+        match input {
+            // The form captures all keys, including Esc, while it is open
+            Input::Key(key) if self.form.visible => match self.form.handle_key(key) {
+                FormAction::Cancel => {
+                    self.form.hide();
+                    if let Some(FormPurpose::EditCron { .. }) = self.form_purpose.take() {
+                        self.cron_view.popup.visible = true;
+                    }
                 }
+                FormAction::Submit => self.submit_form(),
+                FormAction::None => {}
+            },
+            Input::Key(key) if self.yank_pending => {
+                self.yank_pending = false;
+                self.yank(key.code);
             }
-
-            // Filter toggle
-            (_, KeyCode::Char('f')) if !self.script_view.visible && !self.filter_popup.visible => {
-                self.filter_popup.visible = true;
-                // Initialize filter popup with current options
-                self.filter_popup
-                    .initialize(&self.squeue_options, self.recent_ended_hours);
+            Input::Key(key) if self.open_pending => {
+                self.open_pending = false;
+                self.open_with(key.code);
             }
-
-            (_, KeyCode::Char('s'))
-                if !self.filter_popup.visible
-                    && !self.settings_popup.visible
-                    && !self.script_view.visible
-                    && !self.columns_popup.visible
-                    && !self.log_view.visible
-                    && !self.cancel_confirm =>
-            {
-                self.settings_popup.visible = true;
-                self.settings_popup.initialize(
-                    self.slurm_logs_dir
-                        .as_ref()
-                        .and_then(|p| p.to_str())
-                        .or(Some("")),
-                );
+            Input::Key(key) if self.command_palette.visible => {
+                match self.command_palette.handle_key(key) {
+                    PaletteAction::Close => self.command_palette.visible = false,
+                    PaletteAction::Run(command) => self.run_command(command),
+                    PaletteAction::None => {}
+                }
             }
-
-            // Navigation
-            (_, KeyCode::Up)
-                if !self.filter_popup.visible
-                    && !self.script_view.visible
-                    && !self.columns_popup.visible
-                    && !self.log_view.visible =>
-            {
-                self.jobs_list.previous();
+            // The reference is opened on top of other views; only close it
+            Input::Key(key) if self.reference_popup.visible => {
+                if key.code == KeyCode::Esc || input.is_ctrl_c() {
+                    self.reference_popup.visible = false;
+                } else {
+                    // It captures typing for its search box
+                    self.reference_popup.handle_key(key);
+                }
             }
-            (_, KeyCode::Down)
-                if !self.filter_popup.visible
-                    && !self.script_view.visible
-                    && !self.columns_popup.visible
-                    && !self.log_view.visible =>
+            // Views other than the job list list their own keys
+            Input::Key(key)
+                if key.code == KeyCode::Char('?')
+                    && !matches!(self.key_context(), None | Some(KeyContext::Jobs)) =>
             {
-                self.jobs_list.next();
+                if let Some(context) = self.key_context() {
+                    self.reference_popup.show_keys(context);
+                }
+            }
+            _ if self.aggregate_view.visible => match self.aggregate_view.handle_key(input) {
+                AggregateAction::Close => self.aggregate_view.visible = false,
+                AggregateAction::Refresh => self.refresh_aggregate(),
+                AggregateAction::DrillDown(group) => self.drill_down(&group),
+                AggregateAction::None => {}
+            },
+            _ if self.dependency_view.visible => match self.dependency_view.handle_key(input) {
+                DependencyAction::Close => self.dependency_view.visible = false,
+                DependencyAction::Refresh => self.show_dependencies(),
+                DependencyAction::None => {}
+            },
+            _ if self.pending_view.visible => match self.pending_view.handle_key(input) {
+                PendingAction::Close => self.pending_view.visible = false,
+                PendingAction::Refresh => self.show_pending_reasons(),
+                PendingAction::None => {}
+            },
+            _ if self.chain_view.visible => match self.chain_view.handle_key(input) {
+                ChainAction::Close => self.chain_view.visible = false,
+                ChainAction::Refresh => self.show_attempt_chains(),
+                ChainAction::None => {}
+            },
+            _ if self.search_view.visible && !self.popup_visible() => {
+                match self.search_view.handle_key(input) {
+                    SearchAction::Close => self.search_view.close(),
+                    SearchAction::SwitchTab => self.search_view.visible = false,
+                    SearchAction::Detail(job) => {
+                        self.job_detail
+                            .logs
+                            .set_slurm_logs_dir(self.slurm_logs_dir.clone());
+                        self.job_detail.show(*job);
+                        self.load_job_detail();
+                    }
+                    SearchAction::Edit => self.open_search_form(),
+                    SearchAction::Refresh => {
+                        if let Err(e) = self.run_search() {
+                            self.set_status_message(format!("Search failed: {}", e), 5);
+                        }
+                    }
+                    SearchAction::None => {}
+                }
+            }
+            _ if self.stats_view.visible => match self.stats_view.handle_key(input) {
+                StatsAction::Close => self.stats_view.visible = false,
+                StatsAction::Refresh => self.show_stats(),
+                StatsAction::None => {}
+            },
+            _ if self.trends_view.visible => match self.trends_view.handle_key(input) {
+                TrendsAction::Close => self.trends_view.visible = false,
+                TrendsAction::None => {}
+            },
+            _ if self.table_view().is_some() => {
+                let Some((view, refresh)) = self.table_view() else {
+                    return;
+                };
+                match view.handle_key(input) {
+                    TableAction::Close => view.popup_mut().visible = false,
+                    TableAction::Refresh => refresh(self),
+                    TableAction::Toggle(index) => self.toggle_cron_entry(index),
+                    TableAction::Edit(index) => self.open_cron_form(index),
+                    TableAction::Update(update) => self.update_node(update),
+                    TableAction::None => {}
+                }
+            }
+            _ if self.action_menu.visible => match self.action_menu.handle_key(input) {
+                MenuAction::Close => self.action_menu.visible = false,
+                MenuAction::Run(index) => self.run_custom_action(index),
+                MenuAction::None => {}
+            },
+            Input::Key(KeyEvent {
+                code: KeyCode::Char('p'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            }) if !self.popup_over_detail() => {
+                self.open_palette();
             }
 
-            // Selection
-            (_, KeyCode::Char(' '))
-                if !self.filter_popup.visible
-                    && !self.script_view.visible
-                    && !self.columns_popup.visible =>
+            // Close the views, or quit the application
+            Input::Action(Action::Quit)
+            | Input::Key(KeyEvent {
+                code: KeyCode::Esc, ..
+            }) => {
+                self.close_views_or_quit();
+            }
+            _ if input.is_ctrl_c() => self.close_views_or_quit(),
+
+            Input::Action(Action::Help) => match self.jobs_list.selected_job() {
+                Some(job) => self.reference_popup.show_for_job(job),
+                None => self.reference_popup.show(""),
+            },
+
+            // Job detail pane: Shift+arrows move through jobs, other keys go to the pane
+            Input::Action(Action::PrevJob)
+                if if self.search_view.visible {
+                    self.search_view.previous()
+                } else {
+                    self.jobs_list.previous()
+                } =>
             {
-                self.jobs_list.toggle_select();
+                self.show_selected_in_detail();
             }
-            (_, KeyCode::Char('a'))
-                if !self.filter_popup.visible
-                    && !self.script_view.visible
-                    && !self.columns_popup.visible =>
+            Input::Action(Action::NextJob)
+                if if self.search_view.visible {
+                    self.search_view.next()
+                } else {
+                    self.jobs_list.next()
+                } =>
             {
-                // if all jobs are selected, deselect all
-                if self.jobs_list.all_selected() {
-                    self.jobs_list.clear_selection();
+                self.show_selected_in_detail();
+            }
+            // Also over the detail pane, so its rows stop moving while read
+            Input::Action(Action::Pause | Action::DetailPause) => {
+                self.polling_paused = !self.polling_paused;
+                let message = if self.polling_paused {
+                    "Polling paused; [r] refreshes, [p] resumes"
                 } else {
-                    // Otherwise, select all jobs
-                    self.jobs_list.select_all();
+                    "Polling resumed"
+                };
+                self.set_status_message(message.to_string(), 3);
+            }
+            Input::Action(Action::DetailNote) => {
+                if let Some(job) = self.job_detail.job.clone() {
+                    self.open_note_form(&job);
                 }
             }
-            (_, KeyCode::Char('x'))
-                if !self.filter_popup.visible
-                    && !self.script_view.visible
-                    && !self.columns_popup.visible =>
-            {
-                // scancel the selected jobs and remove them
-                self.cancel_confirm = true;
-            }
-            (_, KeyCode::Char('y'))
-                if self.cancel_confirm
-                    && !self.filter_popup.visible
-                    && !self.script_view.visible
-                    && !self.columns_popup.visible =>
-            {
-                // Confirm cancel selected jobs
-                self.cancel_selected_jobs();
-                self.cancel_confirm = false;
-            }
-            (_, KeyCode::Char('n'))
-                if self.cancel_confirm
-                    && !self.filter_popup.visible
-                    && !self.script_view.visible
-                    && !self.columns_popup.visible =>
-            {
-                // Cancel the cancel confirmation
-                self.cancel_confirm = false;
+            Input::Action(Action::DetailComment) => {
+                if let Some(job) = self.job_detail.job.clone() {
+                    self.open_comment_form(&job);
+                }
             }
-
-            // Column management popup
-            (_, KeyCode::Char('c'))
-                if !self.filter_popup.visible
-                    && !self.script_view.visible
-                    && !self.columns_popup.visible
-                    && !self.cancel_confirm =>
-            {
-                self.columns_popup =
-                    ColumnsPopup::new(self.selected_columns.clone(), self.sort_columns.clone());
-                self.columns_popup.visible = true;
+            Input::Action(Action::Baseline) => self.toggle_baseline(),
+            _ if self.job_detail.visible && self.pending_action.is_none() => {
+                match self.job_detail.handle_key(input) {
+                    DetailAction::Close => self.job_detail.hide(),
+                    DetailAction::None => self.load_job_detail(),
+                }
             }
 
+            // Confirm the pending action on the selected jobs, or cancel it
+            Input::Key(key) if self.pending_action.is_some() => match key.code {
+                KeyCode::Char('y') => {
+                    if let Some(action) = self.pending_action.take() {
+                        self.run_action_on_selected(action);
+                    }
+                }
+                KeyCode::Char('n') => self.pending_action = None,
+                _ => {}
+            },
+
             // Handle filter popup key events
             _ if self.filter_popup.visible => {
                 let action = self.filter_popup.handle_key(
-                    key,
+                    input,
                     &mut self.squeue_options,
                     &self.available_states,
                     &self.available_partitions,
@@ -657,6 +1994,7 @@ impl App {
                     }
                     FilterAction::Apply => {
                         self.filter_popup.visible = false;
+                        self.active_preset = None;
                         if let Err(e) = self.apply_filters() {
                             self.set_status_message(format!("Failed to apply filters: {}", e), 3);
                         }
@@ -665,257 +2003,2455 @@ impl App {
                 }
             }
 
-            // Handle filter popup key events
-            _ if self.filter_popup.visible => {
-                let action = self.filter_popup.handle_key(
-                    key,
-                    &mut self.squeue_options,
-                    &self.available_states,
-                    &self.available_partitions,
-                    &self.available_qos,
-                    &mut self.recent_ended_hours,
-                );
+            // Change job for script view
+            Input::Key(KeyEvent {
+                code: KeyCode::Up,
+                modifiers: KeyModifiers::SHIFT,
+                ..
+            }) if self.script_view.visible
+                // If Shift is pressed, switch to previous job and show its script
+                && self.jobs_list.previous() =>
+            {
+                if let Some(job) = self.jobs_list.selected_job() {
+                    self.script_view
+                        .change_job(job.id.clone(), job.name.clone());
+                }
+            }
+            Input::Key(KeyEvent {
+                code: KeyCode::Down,
+                modifiers: KeyModifiers::SHIFT,
+                ..
+            }) if self.script_view.visible
+                // If Shift is pressed, switch to next job and show its script
+                && self.jobs_list.next() =>
+            {
+                if let Some(job) = self.jobs_list.selected_job() {
+                    self.script_view
+                        .change_job(job.id.clone(), job.name.clone());
+                }
+            }
+            _ if self.script_view.visible => {
+                // If script view is visible, handle script view specific keys
+                self.script_view.handle_key(input);
+            }
+
+            // Change job for log view
+            Input::Key(KeyEvent {
+                code: KeyCode::Up,
+                modifiers: KeyModifiers::SHIFT,
+                ..
+            }) if self.log_view.visible
+                // If Shift is pressed, switch to previous job and show its logs
+                && self.jobs_list.previous() =>
+            {
+                if let Some(job) = self.jobs_list.selected_job() {
+                    self.log_view.change_job(job.id.clone());
+                }
+            }
+            Input::Key(KeyEvent {
+                code: KeyCode::Down,
+                modifiers: KeyModifiers::SHIFT,
+                ..
+            }) if self.log_view.visible
+                // If Shift is pressed, switch to next job and show its logs
+                && self.jobs_list.next() =>
+            {
+                if let Some(job) = self.jobs_list.selected_job() {
+                    self.log_view.change_job(job.id.clone());
+                }
+            }
+            _ if self.log_view.visible => {
+                // If log view is visible, handle log view specific keys
+                self.log_view.handle_key(input);
+            }
 
+            _ if self.settings_popup.visible => {
+                let action = self.settings_popup.handle_key(input);
                 match action {
-                    FilterAction::Close => {
-                        self.filter_popup.visible = false;
+                    SettingsAction::Close => {
+                        self.settings_popup.visible = false;
                     }
-                    FilterAction::Apply => {
-                        self.filter_popup.visible = false;
-                        if let Err(e) = self.apply_filters() {
-                            self.set_status_message(format!("Failed to apply filters: {}", e), 3);
+                    SettingsAction::Saved => {
+                        self.slurm_logs_dir =
+                            self.settings_popup.current_value().map(PathBuf::from);
+                        self.log_view
+                            .set_slurm_logs_dir(self.slurm_logs_dir.clone());
+                        self.job_detail
+                            .logs
+                            .set_slurm_logs_dir(self.slurm_logs_dir.clone());
+                    }
+                    SettingsAction::None => {}
+                }
+            }
+
+            // Handle columns popup key events
+            _ if self.columns_popup.visible => {
+                let action = self.columns_popup.handle_key(input);
+
+                match action {
+                    ColumnsAction::Close => {
+                        self.columns_popup.visible = false;
+                    }
+                    ColumnsAction::Apply => {
+                        self.columns_popup.visible = false;
+                        if let Err(e) = self.apply_columns() {
+                            self.set_status_message(format!("Failed to refresh: {}", e), 3);
+                        } else {
+                            self.set_status_message("Column settings applied".to_string(), 3);
                         }
                     }
-                    FilterAction::None => {}
+                    ColumnsAction::SaveAndApply => {
+                        self.columns_popup.visible = false;
+
+                        // TODO: Save settings to config file
+                        self.set_status_message("Column settings saved and applied".to_string(), 3);
+
+                        if let Err(e) = self.apply_columns() {
+                            self.set_status_message(format!("Failed to refresh: {}", e), 3);
+                        }
+                    }
+                    ColumnsAction::None => {}
                 }
             }
 
-            // Job detail view
-            (_, KeyCode::Enter)
-                if !self.filter_popup.visible
-                    && !self.script_view.visible
-                    && !self.columns_popup.visible
-                    && !self.log_view.visible =>
-            {
+            Input::Action(action) => self.run_jobs_action(action),
+            Input::Key(key) => match key.code {
+                KeyCode::Tab if self.search_view.open && !self.popup_visible() => {
+                    self.search_view.visible = true;
+                }
+                // Filter presets: 1-9 select a preset, 0 returns to the default view
+                KeyCode::Char(c) if c.is_ascii_digit() && !self.popup_visible() => {
+                    let idx = c.to_digit(10).unwrap_or(0) as usize;
+                    if idx == 0 {
+                        self.reset_filters();
+                    } else {
+                        self.apply_preset(idx - 1);
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    /// Close the views over the job list, leave visual mode, or quit
+    fn close_views_or_quit(&mut self) {
+        if self.filter_popup.visible
+            || self.settings_popup.visible
+            || self.script_view.visible
+            || self.columns_popup.visible
+            || self.log_view.visible
+            || self.job_detail.visible
+            || self.pending_action.is_some()
+        {
+            self.filter_popup.visible = false;
+            self.settings_popup.visible = false;
+            self.script_view.visible = false;
+            self.columns_popup.visible = false;
+            self.log_view.hide();
+            self.job_detail.hide();
+            self.pending_action = None;
+        } else if self.jobs_list.in_visual_mode() {
+            self.jobs_list.end_visual();
+        } else {
+            self.quit();
+        }
+    }
+
+    /// Run an action of the job list
+    fn run_jobs_action(&mut self, action: Action) {
+        match action {
+            Action::Edit => self.open_update_form(),
+            Action::Note => {
+                if let Some(job) = self.jobs_list.selected_job().cloned() {
+                    self.open_note_form(&job);
+                }
+            }
+            Action::Comment => {
+                if let Some(job) = self.jobs_list.selected_job().cloned() {
+                    self.open_comment_form(&job);
+                }
+            }
+            Action::Resubmit => self.open_resubmit_form(),
+            Action::Attach => self.request_attach(),
+            Action::Submit => self.open_import_form(),
+            Action::NewScript => self.open_builder_form(),
+            Action::Trace => self.open_trace_form(),
+            Action::Export => self.open_export_form(),
+            Action::Aggregate => self.open_aggregate_view(),
+            Action::Dependencies => self.show_dependencies(),
+            Action::Pending => self.show_pending_reasons(),
+            Action::Chains => self.show_attempt_chains(),
+            Action::Search => self.open_search_form(),
+            Action::Stats => self.show_stats(),
+            Action::Trends => self.trends_view.visible = true,
+            Action::Cron => self.show_cron(),
+            Action::Reservations => self.show_reservations(),
+            Action::Limits => self.show_limits(),
+            Action::Licenses => self.show_licenses(),
+            Action::Cluster => self.show_nodes(),
+            Action::Yank if self.jobs_list.selected_job().is_some() => {
+                self.yank_pending = true;
+                self.set_status_message(
+                    "Copy: [i] job ID | [n] node list | [w] work dir | [o] stdout path".to_string(),
+                    5,
+                );
+            }
+            Action::Actions => self.show_action_menu(),
+            Action::OpenWith if self.jobs_list.selected_job().is_some() => {
+                self.open_pending = true;
+                let mut choices = vec!["[w] work dir".to_string(), "[o] stdout".to_string()];
+                for (i, name) in self.open_config.commands.keys().take(9).enumerate() {
+                    choices.push(format!("[{}] {}", i + 1, name));
+                }
+                self.set_status_message(format!("Open: {}", choices.join(" | ")), 5);
+            }
+            Action::Protect => self.toggle_protected(),
+            Action::Watch => self.toggle_watched(),
+            Action::RelativeTimes => self.toggle_relative_times(),
+            Action::LookbackLonger => self.step_lookback(true),
+            Action::LookbackShorter => self.step_lookback(false),
+            // Expand/collapse het jobs into their components
+            Action::Expand => {
+                self.jobs_list.set_expanded(true);
+            }
+            Action::Collapse => {
+                self.jobs_list.set_expanded(false);
+            }
+            // Scroll the unpinned columns sideways
+            Action::ScrollColumnsRight => {
+                self.jobs_list.scroll_columns(1);
+            }
+            Action::ScrollColumnsLeft => {
+                self.jobs_list.scroll_columns(-1);
+            }
+            Action::Group => self.cycle_group_by(),
+            Action::User => self.open_user_form(),
+            Action::Detail => self.show_job_detail(),
+            Action::Filter => {
+                self.filter_popup.visible = true;
+                // Initialize filter popup with current options
+                self.filter_popup
+                    .initialize(&self.squeue_options, self.recent_ended_hours);
+            }
+            Action::Settings => {
+                self.settings_popup.visible = true;
+                self.settings_popup.initialize(
+                    self.slurm_logs_dir
+                        .as_ref()
+                        .and_then(|p| p.to_str())
+                        .or(Some("")),
+                );
+            }
+
+            // Navigation
+            Action::Up => {
+                self.jobs_list.previous();
+            }
+            Action::Down => {
+                self.jobs_list.next();
+            }
+            Action::JobsPageUp => {
+                self.jobs_list.page_up();
+            }
+            Action::JobsPageDown => {
+                self.jobs_list.page_down();
+            }
+            Action::FirstJob => {
+                self.jobs_list.first();
+            }
+            Action::LastJob => {
+                self.jobs_list.last();
+            }
+
+            // Selection
+            Action::Select => self.jobs_list.toggle_select(),
+            Action::SelectAll => {
+                // if all jobs are selected, deselect all
+                if self.jobs_list.all_selected() {
+                    self.jobs_list.clear_selection();
+                } else {
+                    // Otherwise, select all jobs
+                    self.jobs_list.select_all();
+                }
+            }
+            Action::Visual => {
+                // Toggle visual (range) selection
+                if self.jobs_list.in_visual_mode() {
+                    self.jobs_list.end_visual();
+                } else {
+                    self.jobs_list.start_visual();
+                }
+            }
+            // scancel the selected jobs and remove them
+            Action::Cancel => self.request_action(JobAction::Cancel),
+            Action::Hold => self.request_action(JobAction::Hold),
+            Action::Release => self.request_action(JobAction::Release),
+            Action::Requeue => self.request_action(JobAction::Requeue),
+
+            // Column management popup
+            Action::Columns => {
+                self.columns_popup =
+                    ColumnsPopup::new(self.selected_columns.clone(), self.sort_columns.clone());
+                self.columns_popup.visible = true;
+            }
+
+            // Job script view
+            Action::Script => {
                 if let Some(job) = self.jobs_list.selected_job() {
-                    // Show job script in detail view
                     self.script_view.show(job.id.clone(), job.name.clone());
                 }
             }
 
-            // Change job for script view
-            (KeyModifiers::SHIFT, KeyCode::Up) if self.script_view.visible => {
-                // If Shift is pressed, switch to previous job and show its script
-                if self.jobs_list.previous() {
-                    if let Some(job) = self.jobs_list.selected_job() {
-                        self.script_view
-                            .change_job(job.id.clone(), job.name.clone());
+            // Show log view
+            Action::Logs => {
+                if let Some(job) = self.jobs_list.selected_job() {
+                    self.log_view
+                        .set_slurm_logs_dir(self.slurm_logs_dir.clone());
+                    self.log_view.show(job.id.clone());
+                }
+            }
+
+            // Refresh jobs
+            Action::Refresh => {
+                // A manual refresh asks every source again, ended jobs,
+                // partitions and start estimates included
+                cache::invalidate();
+                self.sacct_poll.expire();
+                self.estimate_poll.expire();
+                self.cluster_due = self.mock.is_none();
+                if let Err(e) = self.refresh_jobs() {
+                    self.set_status_message(format!("Failed to refresh: {}", e), 3);
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    /// Open the detail pane on the job under the cursor
+    fn show_job_detail(&mut self) {
+        self.job_detail
+            .logs
+            .set_slurm_logs_dir(self.slurm_logs_dir.clone());
+        if let Some(job) = self.jobs_list.selected_job().cloned() {
+            self.job_detail.show(job);
+            self.load_job_detail();
+        }
+    }
+
+    /// Handle mouse events: clicks on the job list and its headers, and the
+    /// wheel in the job list and the log view
+    pub fn handle_mouse_event(&mut self, mouse: MouseEvent) {
+        if self.log_view.visible {
+            match mouse.kind {
+                MouseEventKind::ScrollDown => self.log_view.scroll_down(),
+                MouseEventKind::ScrollUp => self.log_view.scroll_up(),
+                _ => {}
+            }
+            return;
+        }
+        if self.popup_visible() || self.search_view.visible {
+            return;
+        }
+        match mouse.kind {
+            MouseEventKind::ScrollDown => {
+                self.jobs_list.next();
+            }
+            MouseEventKind::ScrollUp => {
+                self.jobs_list.previous();
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(column) = self.jobs_list.header_at(mouse.column, mouse.row) {
+                    self.sort_by_header(column);
+                    return;
+                }
+                let Some(row) = self.jobs_list.row_at(mouse.column, mouse.row) else {
+                    return;
+                };
+                self.jobs_list.move_to(row);
+                let double = self
+                    .last_click
+                    .is_some_and(|(at, last)| last == row && at.elapsed() < DOUBLE_CLICK);
+                if double {
+                    self.last_click = None;
+                    self.show_job_detail();
+                } else {
+                    self.last_click = Some((Instant::now(), row));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Sort by a clicked header: ascending first, reversed when it is
+    /// already the first sort column
+    fn sort_by_header(&mut self, column: JobColumn) {
+        let order = match self.sort_columns.first() {
+            Some(first) if first.column == column => match first.order {
+                SortOrder::Ascending => SortOrder::Descending,
+                SortOrder::Descending => SortOrder::Ascending,
+            },
+            _ => SortOrder::Ascending,
+        };
+        self.sort_columns = vec![SortColumn { column, order }];
+        self.update_squeue_format();
+        let mut jobs = self.jobs_list.listed_jobs().to_vec();
+        sort_jobs(&mut jobs, &self.sort_columns);
+        self.jobs_list.update_jobs(jobs);
+    }
+
+    /// Handle tick events (called periodically)
+    fn handle_tick(&mut self) {
+        let outcomes = self.action_queue.poll(&self.runtime);
+        if !outcomes.is_empty() {
+            self.report_action_outcomes(outcomes);
+        }
+        self.action_menu.poll();
+        self.apply_fetched();
+        // Kept up to date, so an ssh drop or a crash loses nothing
+        self.save_session();
+
+        let interval = self.refresh_interval().map(Duration::from_secs);
+        let now = Instant::now();
+        let squeue_due = interval.is_some_and(|i| self.squeue_poll.due(i, now));
+        let jobs_due = squeue_due
+            && !self.aggregate_view.visible
+            && !self.filter_popup.visible
+            && !self.script_view.visible
+            && !self.columns_popup.visible;
+        if let Some(interval) = interval {
+            let sinfo_interval = interval.max(Duration::from_secs(self.sinfo_interval));
+            let busy = self.poll_in_flight || self.cluster_in_flight;
+            if self.mock.is_none() && !busy && self.sinfo_poll.due(sinfo_interval, now) {
+                // Run with the job poll when there is one, in the same pool
+                if jobs_due {
+                    self.cluster_due = true;
+                } else {
+                    self.start_cluster_fetch();
+                }
+            }
+        }
+
+        // In aggregate mode only the counts are polled
+        if self.aggregate_view.visible {
+            if squeue_due {
+                self.refresh_aggregate();
+            }
+            return;
+        }
+
+        // Check if it's time to auto-refresh; Slurm is polled off the UI
+        // thread, a fixture at once
+        if jobs_due && self.mock.is_some() {
+            if let Err(e) = self.refresh_jobs() {
+                self.set_status_message(format!("Auto-refresh failed: {}", e), 3);
+            }
+        } else if jobs_due && !self.poll_in_flight {
+            self.start_poll();
+        }
+
+        // Check for log view updates and refresh content
+        if self.log_view.visible {
+            self.log_view.check_refresh();
+        }
+        self.job_detail.check_refresh();
+    }
+
+    /// Current auto-refresh interval in seconds; `None` while polling is
+    /// paused, by `p` or because the terminal is unfocused
+    pub fn refresh_interval(&self) -> Option<u64> {
+        if self.polling_paused {
+            return None;
+        }
+        match (self.focused, self.unfocused_refresh_interval) {
+            (true, _) => Some(self.job_refresh_interval),
+            (false, 0) => None,
+            // Never poll faster unfocused than focused
+            (false, secs) => Some(secs.max(self.job_refresh_interval)),
+        }
+    }
+
+    /// Put pasted text into the text input being typed in, on one line;
+    /// elsewhere it is dropped rather than run as keys
+    pub fn handle_paste(&mut self, text: &str) {
+        if self.form.visible {
+            self.form.paste(text);
+            return;
+        }
+        let typing = !self.yank_pending
+            && !self.open_pending
+            && (self.command_palette.visible
+                || self.reference_popup.visible
+                || (self.filter_popup.visible && self.filter_popup.input_mode)
+                || (self.settings_popup.visible && self.settings_popup.input_mode)
+                || self.node_view.editing());
+        if !typing {
+            return;
+        }
+        for c in text.trim_end().chars() {
+            let c = if c.is_control() { ' ' } else { c };
+            self.dispatch_key(Input::Key(KeyEvent::new(
+                KeyCode::Char(c),
+                KeyModifiers::NONE,
+            )));
+        }
+    }
+
+    /// Slow down polling when the terminal loses focus and catch up at once
+    /// when it gets it back
+    fn handle_focus(&mut self, focused: bool) {
+        self.focused = focused;
+        let interval = Duration::from_secs(self.job_refresh_interval);
+        if focused && self.squeue_poll.due(interval, Instant::now()) {
+            if let Err(e) = self.refresh_jobs() {
+                self.set_status_message(format!("Auto-refresh failed: {}", e), 3);
+            }
+        }
+    }
+
+    /// Use the columns and sort of the columns popup. A new sort on shown
+    /// columns is applied to the listed jobs without asking Slurm again.
+    fn apply_columns(&mut self) -> Result<()> {
+        let columns = self.columns_popup.selected_columns.clone();
+        self.sort_columns = self.columns_popup.sort_columns.clone();
+        let sort_shown = self
+            .sort_columns
+            .iter()
+            .all(|s| columns.contains(&s.column));
+        if columns == self.selected_columns && sort_shown {
+            let mut jobs = self.jobs_list.listed_jobs().to_vec();
+            sort_jobs(&mut jobs, &self.sort_columns);
+            self.jobs_list.update_jobs(jobs);
+            return Ok(());
+        }
+        self.selected_columns = columns;
+        self.refresh_jobs()
+    }
+
+    /// Fetch the partition list (for the filters) and the other cluster
+    /// data again, off the UI thread; a later tick applies them
+    fn start_cluster_fetch(&mut self) {
+        let pool = self.pool.clone();
+        let tx = self.fetched_tx.clone();
+        self.runtime.spawn(async move {
+            let _ = tx.send(Fetched::Cluster(pool.run(cluster_tasks()).await));
+        });
+        self.cluster_in_flight = true;
+    }
+
+    /// Read the partitions, QOSs and other cluster data at start, side by
+    /// side in the fetch pool; Slurm not listing the partitions or QOSs is
+    /// an error
+    fn fetch_cluster_data(&mut self) -> Result<()> {
+        let mut tasks = cluster_tasks();
+        tasks.push(Box::pin(async { Fetch::Qos(get_qos().await) }));
+        let results = self.runtime.block_on(self.pool.run(tasks));
+        for timed in results {
+            match timed.value {
+                Fetch::Qos(result) => self.available_qos = result?,
+                Fetch::Partitions(Err(e)) => return Err(e),
+                value => self.apply_cluster_fetch(Timed { value, ..timed }),
+            }
+        }
+        Ok(())
+    }
+
+    /// Take in what the fetches of a job poll returned: the job queries
+    /// are handed back, the cluster data kept
+    fn take_fetches(&mut self, fetches: Vec<Timed<Fetch>>) -> FetchedJobs {
+        let mut fetched = FetchedJobs::default();
+        for timed in fetches {
+            let Timed {
+                started,
+                finished,
+                value,
+            } = timed;
+            match value {
+                Fetch::Squeue(result) => {
+                    self.squeue_poll
+                        .record_result_between(started, finished, &result);
+                    fetched.squeue = Some(result);
+                }
+                Fetch::Estimates(result) => {
+                    self.estimate_poll
+                        .record_result_between(started, finished, &result);
+                    fetched.estimates = Some(result);
+                }
+                Fetch::Sacct(result) => {
+                    self.sacct_poll
+                        .record_result_between(started, finished, &result);
+                    fetched.sacct = Some(result);
+                }
+                Fetch::Watched(result) => {
+                    self.watched_poll
+                        .record_result_between(started, finished, &result);
+                    fetched.watched = Some(result);
+                }
+                Fetch::Ended(result) => fetched.ended = Some(result),
+                value => self.apply_cluster_fetch(Timed {
+                    started,
+                    finished,
+                    value,
+                }),
+            }
+        }
+        fetched
+    }
+
+    /// Keep what one of the `cluster_tasks` returned
+    fn apply_cluster_fetch(&mut self, timed: Timed<Fetch>) {
+        match timed.value {
+            Fetch::Partitions(result) => {
+                self.sinfo_poll
+                    .record_result_between(timed.started, timed.finished, &result);
+                if let Ok(partitions) = result {
+                    self.available_partitions = partitions;
+                }
+            }
+            // Not every site lets users read reservations; keep the last ones
+            Fetch::Reservations(Ok(reservations)) => self.reservations = reservations,
+            Fetch::Licenses(Ok(licenses)) => self.licenses = licenses,
+            Fetch::Preemption(Ok((qos, pending))) => {
+                self.qos_preemption = qos;
+                self.pending_by_qos = pending;
+            }
+            _ => {}
+        }
+    }
+
+    /// Mark running jobs that pending jobs of another QOS may preempt
+    fn apply_preemption(&self, jobs: &mut [Job]) {
+        for job in jobs.iter_mut() {
+            job.preemption = preemption_risk(job, &self.qos_preemption, &self.pending_by_qos)
+                .map(|risk| risk.summary());
+        }
+    }
+
+    /// Set a temporary status message
+    fn set_status_message(&mut self, message: String, duration_secs: u64) {
+        self.status_message = message;
+        self.status_timeout = Some(Instant::now() + Duration::from_secs(duration_secs));
+    }
+
+    /// Set the auto-refresh interval in seconds
+    /// TODO: maybe used it in the future
+    fn _set_refresh_interval(&mut self, seconds: u64) {
+        self.job_refresh_interval = seconds;
+        self.set_status_message(format!("Auto-refresh interval set to {}s", seconds), 3);
+    }
+
+    /// Apply all filter changes and refresh jobs
+    fn apply_filters(&mut self) -> Result<()> {
+        self.filter_popup.visible = false;
+        self.set_status_message("Applying filters...".to_string(), 3);
+
+        // Ensure we refresh the jobs with the updated filters
+        let result = self.refresh_jobs();
+
+        // Display feedback about the filter application
+        if result.is_ok() {
+            let filter_desc = self.get_filter_description();
+            if !filter_desc.is_empty() {
+                let loaded_count = self.jobs_list.jobs.len();
+                self.set_status_message(
+                    format!(
+                        "Filters applied: {} ({} jobs shown)",
+                        filter_desc, loaded_count
+                    ),
+                    3,
+                );
+            } else {
+                let loaded_count = self.jobs_list.jobs.len();
+                self.set_status_message(
+                    format!("Filters cleared ({} jobs shown)", loaded_count),
+                    3,
+                );
+            }
+        }
+
+        result
+    }
+
+    /// Apply the preset at `idx` (0-based) and re-query
+    fn apply_preset(&mut self, idx: usize) {
+        let Some(preset) = self.presets.get(idx).cloned() else {
+            self.set_status_message(format!("No preset bound to key {}", idx + 1), 3);
+            return;
+        };
+        preset.apply_to(
+            &mut self.squeue_options,
+            &mut self.recent_ended_hours,
+            &get_username(),
+        );
+        self.active_preset = Some(idx);
+        self.aggregate_return = None;
+        if let Err(e) = self.apply_filters() {
+            self.set_status_message(format!("Failed to apply preset: {}", e), 3);
+        }
+    }
+
+    /// Drop all filters and return to the `[defaults]` view ("my jobs" unless configured)
+    fn reset_filters(&mut self) {
+        let username = match &self.mock {
+            Some(mock) => mock.user.clone(),
+            None => get_username(),
+        };
+        self.default_view.apply_to(
+            &mut self.squeue_options,
+            &mut self.recent_ended_hours,
+            &username,
+        );
+        self.active_preset = None;
+        self.aggregate_return = None;
+        if let Err(e) = self.apply_filters() {
+            self.set_status_message(format!("Failed to reset filters: {}", e), 3);
+        }
+    }
+
+    /// Open the aggregate view, back on the filters it was left with after a
+    /// drill-down
+    fn open_aggregate_view(&mut self) {
+        if let Some(options) = self.aggregate_return.take() {
+            self.squeue_options = options;
+        }
+        self.aggregate_view.visible = true;
+        self.refresh_aggregate();
+    }
+
+    fn refresh_aggregate(&mut self) {
+        let options = self.squeue_options.clone();
+        let started = Instant::now();
+        let result = self.fetch_now(async { run_squeue_aggregate(&options).await });
+        self.squeue_poll.record_result(started, &result);
+        match result {
+            Ok(groups) => self.aggregate_view.update(groups),
+            Err(e) => self.set_status_message(format!("Failed to count the queue: {}", e), 5),
+        }
+    }
+
+    /// List only the jobs of `group`; `A` goes back to the counts
+    fn drill_down(&mut self, group: &QueueGroup) {
+        self.aggregate_return = Some(self.squeue_options.clone());
+        self.squeue_options.user = Some(group.user.clone());
+        self.squeue_options.partitions = group.partition.split(',').map(str::to_string).collect();
+        self.squeue_options.states = vec![group.state.kind()];
+        self.aggregate_view.visible = false;
+        match self.refresh_jobs() {
+            Ok(()) => self.set_status_message(
+                format!(
+                    "{} jobs of {} in {} | [A] back to the counts",
+                    group.state, group.user, group.partition
+                ),
+                5,
+            ),
+            Err(e) => self.set_status_message(format!("Failed to list the jobs: {}", e), 5),
+        }
+    }
+
+    /// Open (or refresh) the dependency tree of the pending jobs of the
+    /// listed user
+    fn show_dependencies(&mut self) {
+        let user = self.squeue_options.user.clone();
+        match self.fetch_now(async { fetch_dependencies(user.as_deref()).await }) {
+            Ok(deps) => {
+                let lines = dependency_tree(self.jobs_list.listed_jobs(), &deps);
+                self.dependency_view.show(lines);
+            }
+            Err(e) => self.set_status_message(format!("Failed to fetch dependencies: {}", e), 5),
+        }
+    }
+
+    /// Whether a shown column, the sort or a filter needs the pending jobs'
+    /// start estimates
+    fn needs_start_estimates(&self) -> bool {
+        self.selected_columns.contains(&JobColumn::EstimatedStart)
+            || self
+                .sort_columns
+                .iter()
+                .any(|s| s.column == JobColumn::EstimatedStart)
+            || self.squeue_options.start_within_hours.is_some()
+    }
+
+    /// Fill in the expected start of pending jobs from the last `squeue
+    /// --start`, which the pool asks only every `start_estimate_interval`
+    /// seconds (longer while it fails or is slow); `fetched` is what it
+    /// returned this poll, if it ran
+    fn apply_start_estimates(
+        &mut self,
+        jobs: &mut [Job],
+        fetched: Option<Result<HashMap<String, DateTime<Local>>>>,
+    ) {
+        // Fixtures carry their own estimates
+        if !self.needs_start_estimates() || self.mock.is_some() {
+            return;
+        }
+        match fetched {
+            Some(Ok(estimates)) => self.start_estimates = estimates,
+            Some(Err(e)) => {
+                self.set_status_message(format!("Start estimates unavailable: {}", e), 3)
+            }
+            None => {}
+        }
+        for job in jobs.iter_mut().filter(|j| j.state == JobState::Pending) {
+            job.estimated_start = self.start_estimates.get(&job.id).copied();
+        }
+    }
+
+    /// Count the pending jobs (with the current filters) per reason
+    fn show_pending_reasons(&mut self) {
+        let options = self.squeue_options.clone();
+        match self.fetch_now(async { run_squeue_pending(&options).await }) {
+            Ok(groups) => {
+                self.pending_view
+                    .show(groups, &self.job_detail.hints, &self.job_detail.hint_keys)
+            }
+            Err(e) => self.set_status_message(format!("Failed to fetch pending jobs: {}", e), 5),
+        }
+    }
+
+    /// List the cluster's reservations against the listed jobs
+    fn show_reservations(&mut self) {
+        let reservations = match &self.mock {
+            Some(_) => Err("not recorded in the fixture".to_string()),
+            None => self
+                .fetch_now(async { fetch_reservations().await })
+                .map_err(|e| e.to_string()),
+        };
+        if let Ok(reservations) = &reservations {
+            self.reservations = reservations.clone();
+        }
+        self.reservation_view
+            .show(reservations, self.jobs_list.listed_jobs());
+    }
+
+    /// Used and free seats of the cluster licenses against the listed jobs
+    fn show_licenses(&mut self) {
+        let licenses = match &self.mock {
+            Some(_) => Err("not recorded in the fixture".to_string()),
+            None => self
+                .fetch_now(async { fetch_licenses().await })
+                .map_err(|e| e.to_string()),
+        };
+        if let Ok(licenses) = &licenses {
+            self.licenses = licenses.clone();
+        }
+        self.license_view
+            .show(licenses, self.jobs_list.listed_jobs());
+    }
+
+    /// The cluster's nodes, for operators to drain and resume
+    fn show_nodes(&mut self) {
+        self.node_view.open(self.cluster_summary());
+        match &self.mock {
+            Some(_) => self
+                .node_view
+                .show(Err("not recorded in the fixture".to_string())),
+            None => self.spawn_fetch(fetch_nodes(), |nodes| {
+                Fetched::Nodes(nodes.map_err(|e| e.to_string()))
+            }),
+        }
+    }
+
+    /// The cluster's name, partitions, reservations and licenses as last
+    /// polled, for the cluster view
+    fn cluster_summary(&self) -> String {
+        let mut parts = vec![format!(
+            "partitions {}",
+            self.available_partitions.join(", ")
+        )];
+        if !self.reservations.is_empty() {
+            parts.push(format!("{} reservations", self.reservations.len()));
+        }
+        if !self.licenses.is_empty() {
+            let licenses: Vec<String> = self
+                .licenses
+                .iter()
+                .map(|l| format!("{} {}/{} free", l.name, l.free, l.total))
+                .collect();
+            parts.push(format!("licenses {}", licenses.join(", ")));
+        }
+        let cluster = if self.cluster.is_empty() {
+            "cluster"
+        } else {
+            &self.cluster
+        };
+        format!("{}: {}", cluster, parts.join("; "))
+    }
+
+    /// Copy the job ID, node list, work dir or stdout path of the job under
+    /// the cursor, as picked by the key pressed after `Y`
+    fn yank(&mut self, code: KeyCode) {
+        let Some(job) = self.jobs_list.selected_job().cloned() else {
+            return;
+        };
+        let (what, value) = match code {
+            KeyCode::Char('i') => ("job ID", Some(job.id.clone())),
+            KeyCode::Char('n') => ("node list", job.node.clone()),
+            KeyCode::Char('w') => (
+                "work dir",
+                job.work_dir
+                    .clone()
+                    .or_else(|| self.fetch_work_dir(&job.id)),
+            ),
+            KeyCode::Char('o') => (
+                "stdout path",
+                self.log_view
+                    .stdout_path_of(&job.id)
+                    .map(|p| p.display().to_string()),
+            ),
+            _ => {
+                self.set_status_message(String::new(), 0);
+                return;
+            }
+        };
+        let Some(value) = value.filter(|v| !v.trim().is_empty()) else {
+            self.set_status_message(format!("Job {} has no {}", job.id, what), 3);
+            return;
+        };
+        let message = match clipboard::copy(&value) {
+            Ok(_) => format!("Copied the {}: {}", what, value),
+            Err(e) => format!("Copy failed: {}", e),
+        };
+        self.set_status_message(message, 3);
+    }
+
+    /// Open the job under the cursor with the `[open]` command picked by the
+    /// key pressed after `X`; it runs once the key press is handled
+    fn open_with(&mut self, code: KeyCode) {
+        let Some(job) = self.jobs_list.selected_job().cloned() else {
+            return;
+        };
+        let template = match code {
+            KeyCode::Char('w') => self.open_config.workdir.clone(),
+            KeyCode::Char('o') => self.open_config.stdout.clone(),
+            KeyCode::Char(c @ '1'..='9') => {
+                let index = c as usize - '1' as usize;
+                match self.open_config.commands.values().nth(index) {
+                    Some(command) => command.clone(),
+                    None => return,
+                }
+            }
+            _ => {
+                self.set_status_message(String::new(), 0);
+                return;
+            }
+        };
+        match expand(&template, &self.open_target(&job, &template)) {
+            Ok(command) => self.external_request = Some(command),
+            Err(e) => self.set_status_message(format!("Cannot open: {}", e), 3),
+        }
+    }
+
+    /// What the placeholders of `template` stand for with `job`
+    fn open_target(&self, job: &Job, template: &str) -> OpenTarget {
+        OpenTarget {
+            id: job.id.clone(),
+            // Only asked of sacct when the command needs it
+            workdir: if template.contains("{workdir}") {
+                job.work_dir
+                    .clone()
+                    .or_else(|| self.fetch_work_dir(&job.id))
+            } else {
+                None
+            },
+            stdout: self
+                .log_view
+                .stdout_path_of(&job.id)
+                .map(|p| p.display().to_string()),
+        }
+    }
+
+    /// Menu of the `[[actions]]` of the config, for the job under the cursor
+    fn show_action_menu(&mut self) {
+        if let Some(job) = self.jobs_list.selected_job() {
+            self.action_menu.show(job.id.clone());
+        }
+    }
+
+    /// Run an `[[actions]]` entry on the job under the cursor: in the
+    /// background, or waiting for its output to show it
+    fn run_custom_action(&mut self, index: usize) {
+        let (Some(job), Some(action)) = (
+            self.jobs_list.selected_job().cloned(),
+            self.action_menu.actions().get(index).cloned(),
+        ) else {
+            return;
+        };
+        let command = match expand(&action.command, &self.open_target(&job, &action.command)) {
+            Ok(command) => command,
+            Err(e) => {
+                self.action_menu.visible = false;
+                self.set_status_message(format!("Cannot run {}: {}", action.name, e), 3);
+                return;
+            }
+        };
+
+        let args = vec!["-c".to_string(), command];
+        if action.background {
+            self.action_menu.visible = false;
+            let spawned = async_process::Command::new("sh")
+                .args(&args)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+            let message = match spawned {
+                Ok(mut child) => {
+                    let pid = child.id();
+                    // Waited for, so it leaves no zombie behind
+                    self.runtime.spawn(async move {
+                        let started = Instant::now();
+                        let status = child.status().await;
+                        let logged = status.as_ref().map(|s| (s, &[][..]));
+                        command::log_run("sh", &args, started.elapsed(), logged);
+                    });
+                    format!("Started {} (pid {})", action.name, pid)
+                }
+                Err(e) => {
+                    command::log_run("sh", &args, Duration::ZERO, Err(&e));
+                    format!("Could not run {}: {}", action.name, e)
+                }
+            };
+            self.set_status_message(message, 5);
+            return;
+        }
+        // Run off the UI thread, and killed past the command timeout
+        let (tx, rx) = mpsc::channel();
+        self.runtime.spawn(async move {
+            let _ = tx.send(command::output("sh", &args).await);
+        });
+        self.action_menu
+            .wait_for(format!("{} on job {}", action.name, job.id), rx);
+    }
+
+    /// The sacct fields the shown columns need
+    fn sacct_format_fields(&self) -> Vec<&'static str> {
+        let columns = self.fetched_columns();
+        let mut format_fields = columns
+            .iter()
+            .map(|c| c.sacct_field())
+            .filter(|f| !f.is_empty())
+            .collect::<Vec<_>>();
+        if self.squeue_options.gpu_only {
+            format_fields.extend(["AllocTRES", "ReqTRES"]);
+        }
+        if !self.squeue_options.wckeys.is_empty() {
+            format_fields.push("WCKey");
+        }
+        if columns.contains(&JobColumn::QueueWait) {
+            format_fields.push("Start");
+        }
+        if columns.contains(&JobColumn::CpuUsage) {
+            format_fields.extend(["Elapsed", "AllocCPUS"]);
+        }
+        if columns.contains(&JobColumn::Efficiency) {
+            format_fields.extend(["Elapsed", "AllocCPUS", "ReqMem", "NNodes"]);
+        }
+        // What was allocated wins over what was requested
+        if columns.contains(&JobColumn::TmpDisk) || columns.contains(&JobColumn::BurstBuffer) {
+            format_fields.push("AllocTRES");
+        }
+        // Older releases reject fields they do not know
+        format_fields.retain(|f| self.capabilities.supports_sacct_field(f));
+        format_fields
+    }
+
+    /// Pin the job under the cursor to the watchlist, or unpin it
+    fn toggle_watched(&mut self) {
+        let Some(id) = self.jobs_list.selected_job().map(|j| j.id.clone()) else {
+            return;
+        };
+        let message = if self.jobs_list.is_watched(&id) {
+            self.jobs_list.watchlist.retain(|p| *p != id);
+            format!("Job {} unpinned from the watchlist", id)
+        } else {
+            self.jobs_list.watchlist.push(id.clone());
+            format!(
+                "Job {} pinned to the watchlist ({} watched)",
+                id,
+                self.jobs_list.watchlist.len()
+            )
+        };
+        // The job is listed already: only its place changes. One unpinned
+        // from outside the filters leaves with the next poll.
+        let mut jobs = self.jobs_list.listed_jobs().to_vec();
+        sort_jobs(&mut jobs, &self.sort_columns);
+        jobs.sort_by_key(|j| !self.jobs_list.is_watched(&j.id));
+        self.jobs_list.update_jobs(jobs);
+        self.jobs_list.focus_job(&id);
+        self.set_status_message(message, 3);
+    }
+
+    /// The work dir of a job whose listing did not include it
+    fn fetch_work_dir(&self, job_id: &str) -> Option<String> {
+        if self.mock.is_some() {
+            return None;
+        }
+        let job_ids = [job_id.to_string()];
+        self.fetch_now(async { fetch_jobs(&job_ids, &["WorkDir"]).await })
+            .ok()?
+            .into_iter()
+            .next()?
+            .work_dir
+    }
+
+    /// Drain or resume a node the operator confirmed, then list the nodes again
+    fn update_node(&mut self, update: NodeUpdate) {
+        if self.mock.is_some() {
+            let result = Err("not recorded in the fixture".to_string());
+            self.node_updated(update, result);
+            return;
+        }
+        self.node_view
+            .set_message(format!("Updating {}…", update.node()));
+        self.spawn_fetch(
+            async move {
+                let result = update_node(&update).await;
+                (update, result)
+            },
+            |(update, result)| Fetched::NodeUpdate(update, result.map_err(|e| e.to_string())),
+        );
+    }
+
+    /// Re-read the nodes after an update, with its outcome
+    fn node_updated(&mut self, update: NodeUpdate, result: Result<(), String>) {
+        let message = match result {
+            Ok(()) => format!("{} {}", update.past_tense(), update.node()),
+            Err(e) => format!("Failed to update {}: {}", update.node(), e),
+        };
+        if !self.node_view.popup.visible {
+            self.set_status_message(message, 5);
+            return;
+        }
+        self.show_nodes();
+        self.node_view.set_message(message);
+    }
+
+    /// Measure the user's queued and running jobs against their QOS and
+    /// association limits
+    fn show_limits(&mut self) {
+        let limits = match &self.mock {
+            Some(_) => Err("not recorded in the fixture".to_string()),
+            None => {
+                let user = get_username();
+                self.fetch_now(async { fetch_limits(&user).await })
+                    .map_err(|e| e.to_string())
+            }
+        };
+        self.limits_view.show(limits);
+    }
+
+    /// List the user's scrontab entries
+    fn show_cron(&mut self) {
+        let crontab = match &self.mock {
+            Some(_) => Err("not recorded in the fixture".to_string()),
+            None => self
+                .fetch_now(async { fetch_scrontab().await })
+                .map_err(|e| e.to_string()),
+        };
+        self.cron_view.show(crontab);
+    }
+
+    /// Write the scrontab back with `scrontab`, re-reading what Slurm kept
+    fn save_crontab(&mut self, crontab: Scrontab) -> Result<(), String> {
+        self.fetch_now(async { write_scrontab(&crontab).await })
+            .map_err(|e| e.to_string())?;
+        self.show_cron();
+        Ok(())
+    }
+
+    fn toggle_cron_entry(&mut self, index: usize) {
+        let mut crontab = self.cron_view.crontab.clone();
+        let Some(enabled) = crontab.entries.get(index).map(|e| !e.enabled) else {
+            return;
+        };
+        crontab.set_enabled(index, enabled);
+        let message = match self.save_crontab(crontab) {
+            Ok(()) if enabled => "Enabled the scrontab entry".to_string(),
+            Ok(()) => "Disabled the scrontab entry".to_string(),
+            Err(e) => format!("Could not update the scrontab: {}", e),
+        };
+        self.set_status_message(message, 3);
+    }
+
+    /// Edit the schedule and command of a scrontab entry
+    fn open_cron_form(&mut self, index: usize) {
+        let Some(entry) = self.cron_view.crontab.entries.get(index) else {
+            return;
+        };
+        self.form.show(
+            "Edit scrontab entry",
+            vec![
+                ("Schedule".to_string(), entry.schedule.clone()),
+                ("Command".to_string(), entry.command.clone()),
+            ],
+            None,
+        );
+        let mut description = vec![
+            "Minute hour day month weekday, or @hourly, @daily, @weekly, @monthly.".to_string(),
+        ];
+        if !entry.options.is_empty() {
+            description.push(format!("Runs with #SCRON {}", entry.options.join(" ")));
+        }
+        description.push(String::new());
+        self.form.description = description;
+        self.cron_view.popup.visible = false;
+        self.form_purpose = Some(FormPurpose::EditCron { index });
+    }
+
+    /// Ask what to search squeue and accounting for, starting from the last
+    /// search
+    fn open_search_form(&mut self) {
+        self.form
+            .show("Search jobs", self.search_fields.clone(), None);
+        self.form.description = vec![
+            "User: me, * for everyone, or a user name. Name and Node are regexes.".to_string(),
+            "States, Partitions, Accounts: comma-separated, empty for any.".to_string(),
+            "From, To: 2024-05-01, 2024-05-01T13:45, or back from now (12h, 7d); To empty is now."
+                .to_string(),
+            String::new(),
+        ];
+        self.form_purpose = Some(FormPurpose::Search);
+    }
+
+    /// Search with the fields of the search form and show what was found
+    fn run_search(&mut self) -> Result<()> {
+        let username = match &self.mock {
+            Some(mock) => mock.user.clone(),
+            None => get_username(),
+        };
+        let fields = &self.search_fields;
+        let value = |label: &str| {
+            fields
+                .iter()
+                .find(|(l, _)| l == label)
+                .map(|(_, v)| v.clone())
+                .unwrap_or_default()
+        };
+        let query = JobQuery::parse(value, &username, Local::now()).map_err(|e| eyre!(e))?;
+        let summary = fields
+            .iter()
+            .filter(|(_, v)| !v.trim().is_empty())
+            .map(|(l, v)| format!("{}={}", l.to_lowercase(), v.trim()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut active = Vec::new();
+        if let Some(options) = query.squeue_options() {
+            active = match &self.mock {
+                Some(mock) => mock.squeue(&options),
+                None => self.fetch_now(async { run_squeue(&options).await })?,
+            };
+        }
+        let mut ended = Vec::new();
+        if let Some(mut options) = query.sacct_options() {
+            options
+                .format_fields
+                .retain(|f| self.capabilities.supports_sacct_field(f));
+            ended = match &self.mock {
+                Some(mock) => mock.sacct(&options).jobs,
+                None => self.fetch_now(async { run_sacct(&options).await })?.jobs,
+            };
+        }
+        let mut jobs = merge_jobs_prefer_active(active, ended);
+        jobs.retain(|j| query.matches(j));
+        // Newest first
+        jobs.sort_by_key(|j| std::cmp::Reverse(j.submit_time));
+        self.search_view.show(summary, jobs);
+        Ok(())
+    }
+
+    /// Link the listed user's requeued and preempted jobs of the last week
+    /// with their later attempts
+    fn show_attempt_chains(&mut self) {
+        let user = self.squeue_options.user.clone();
+        match self.fetch_now(async { fetch_attempts(user.as_deref(), CHAIN_WINDOW_HOURS).await }) {
+            Ok(attempts) => {
+                self.chain_view.days = CHAIN_WINDOW_HOURS / 24;
+                self.chain_view.show(attempt_chains(attempts));
+            }
+            Err(e) => self.set_status_message(format!("Failed to fetch job attempts: {}", e), 5),
+        }
+    }
+
+    /// Statistics of every job of the last poll (the active ones and those
+    /// of the recent-ended window), before the in-app filters
+    fn show_stats(&mut self) {
+        self.stats_view.window = if self.include_recent_ended {
+            format!(
+                "active and ended in the last {}",
+                format_window(self.recent_ended_hours)
+            )
+        } else {
+            "active".to_string()
+        };
+        self.stats_view.show(self.job_store.jobs(), Local::now());
+    }
+
+    /// What the handlers should see for `key` under the keymap: the action
+    /// it is bound to, the key itself when the keymap does not know it, or
+    /// `None` while a chord is typed or for a key whose action was bound
+    /// elsewhere. Text boxes and one-key answers keep their keys.
+    fn map_key(&mut self, key: KeyEvent) -> Option<Input> {
+        let Some(context) = self.key_context() else {
+            self.keymap.reset();
+            return Some(Input::Key(key));
+        };
+        // The keys of the custom actions pick them in their menu
+        if context == KeyContext::Actions && self.action_menu.has_key(key) {
+            self.keymap.reset();
+            return Some(Input::Key(key));
+        }
+        match self.keymap.press(context, key) {
+            KeyOutcome::Action(action) => Some(Input::Action(action)),
+            KeyOutcome::Pending => {
+                if let Some(chord) = self.keymap.pending() {
+                    self.set_status_message(format!("{} …", chord), 2);
+                }
+                None
+            }
+            KeyOutcome::Unbound => None,
+            KeyOutcome::Other => Some(Input::Key(key)),
+        }
+    }
+
+    /// The table popup on top, with what refreshes it
+    fn table_view(&mut self) -> Option<TablePopupOf<'_>> {
+        let views: [TablePopupOf; 5] = [
+            (&mut self.cron_view, App::show_cron),
+            (&mut self.reservation_view, App::show_reservations),
+            (&mut self.limits_view, App::show_limits),
+            (&mut self.license_view, App::show_licenses),
+            (&mut self.node_view, App::show_nodes),
+        ];
+        views.into_iter().find(|(view, _)| view.popup().visible)
+    }
+
+    /// The context of the view that gets the next key, in the order of
+    /// `dispatch_key`; none while text or a one-key answer is typed
+    fn key_context(&self) -> Option<KeyContext> {
+        let context = if self.form.visible
+            || self.yank_pending
+            || self.open_pending
+            || self.command_palette.visible
+        {
+            return None;
+        } else if self.aggregate_view.visible {
+            KeyContext::Aggregate
+        } else if self.dependency_view.visible
+            || self.pending_view.visible
+            || self.chain_view.visible
+        {
+            KeyContext::Popup
+        } else if self.search_view.visible && !self.popup_visible() {
+            KeyContext::Search
+        } else if self.stats_view.visible {
+            KeyContext::Stats
+        } else if self.trends_view.visible {
+            KeyContext::Popup
+        } else if self.cron_view.popup.visible {
+            KeyContext::Cron
+        } else if self.reservation_view.popup.visible
+            || self.limits_view.popup.visible
+            || self.license_view.popup.visible
+        {
+            KeyContext::Popup
+        } else if self.node_view.popup.visible {
+            if !self.node_view.browsing() {
+                return None;
+            }
+            KeyContext::Nodes
+        } else if self.action_menu.visible {
+            KeyContext::Actions
+        } else if self.reference_popup.visible || self.pending_action.is_some() {
+            return None;
+        } else if self.filter_popup.visible {
+            if self.filter_popup.input_mode {
+                return None;
+            }
+            KeyContext::Filter
+        } else if self.script_view.visible {
+            KeyContext::Script
+        } else if self.log_view.visible {
+            KeyContext::Logs
+        } else if self.settings_popup.visible {
+            if self.settings_popup.input_mode {
+                return None;
+            }
+            KeyContext::Settings
+        } else if self.columns_popup.visible {
+            KeyContext::Columns
+        } else if self.job_detail.visible {
+            KeyContext::Detail
+        } else {
+            KeyContext::Jobs
+        };
+        Some(context)
+    }
+
+    /// Open the palette on the commands of the job list, or of the detail
+    /// pane when it is open, with their current keys
+    fn open_palette(&mut self) {
+        let context = if self.job_detail.visible {
+            KeyContext::Detail
+        } else {
+            KeyContext::Jobs
+        };
+        let mut commands: Vec<Command> = self
+            .keymap
+            .describe()
+            .into_iter()
+            .filter(|(action, _)| action.context() == context)
+            .map(|(action, keys)| Command {
+                title: action.description().to_string(),
+                keys,
+                command: PaletteCommand::Action(action),
+            })
+            .collect();
+        if context == KeyContext::Jobs {
+            commands.push(Command {
+                title: "Return to the default view".to_string(),
+                keys: "0".to_string(),
+                command: PaletteCommand::DefaultView,
+            });
+            commands.extend(self.presets.iter().enumerate().map(|(i, preset)| Command {
+                title: format!("Apply the {} preset", preset.name),
+                keys: if i < 9 {
+                    (i + 1).to_string()
+                } else {
+                    String::new()
+                },
+                command: PaletteCommand::Preset(i),
+            }));
+            let actions = self.action_menu.actions();
+            commands.extend(actions.iter().enumerate().map(|(i, action)| Command {
+                title: format!("Run the {} action", action.name),
+                keys: action.key.map(|k| format!("! {}", k)).unwrap_or_default(),
+                command: PaletteCommand::Custom(i),
+            }));
+        }
+        self.command_palette.show(context.view(), commands);
+    }
+
+    fn run_command(&mut self, command: PaletteCommand) {
+        self.command_palette.visible = false;
+        match command {
+            PaletteCommand::Action(action) => self.dispatch_key(Input::Action(action)),
+            PaletteCommand::DefaultView => self.reset_filters(),
+            PaletteCommand::Preset(i) => self.apply_preset(i),
+            PaletteCommand::Custom(i) => self.run_custom_action(i),
+        }
+    }
+
+    /// Whether any popup or overlay currently owns the keyboard
+    fn popup_visible(&self) -> bool {
+        self.job_detail.visible || self.popup_over_detail()
+    }
+
+    /// Whether a popup other than the detail pane is open (possibly over it)
+    fn popup_over_detail(&self) -> bool {
+        self.command_palette.visible
+            || self.aggregate_view.visible
+            || self.dependency_view.visible
+            || self.pending_view.visible
+            || self.chain_view.visible
+            || self.stats_view.visible
+            || self.trends_view.visible
+            || self.cron_view.popup.visible
+            || self.reservation_view.popup.visible
+            || self.limits_view.popup.visible
+            || self.license_view.popup.visible
+            || self.node_view.popup.visible
+            || self.action_menu.visible
+            || self.filter_popup.visible
+            || self.settings_popup.visible
+            || self.script_view.visible
+            || self.columns_popup.visible
+            || self.log_view.visible
+            || self.reference_popup.visible
+            || self.form.visible
+            || self.pending_action.is_some()
+    }
+
+    /// Attach a shell to the running job under the cursor, after this key press
+    fn request_attach(&mut self) {
+        let Some(job) = self.jobs_list.selected_job() else {
+            return;
+        };
+        if job.state != JobState::Running {
+            let message = format!(
+                "Job {} is {}; only running jobs can be attached to",
+                job.id, job.state
+            );
+            self.set_status_message(message, 3);
+        } else if self.mock.is_some() {
+            self.set_status_message(
+                "Attaching needs Slurm; jobs of fixtures have no nodes".to_string(),
+                3,
+            );
+        } else {
+            self.attach_request = Some(job.id.clone());
+        }
+    }
+
+    /// Suspend the TUI for `srun --jobid <id> --pty bash` on the job's
+    /// allocation, and resume it when the shell exits
+    fn attach_shell<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<B>,
+        job_id: &str,
+    ) -> Result<()> {
+        let mut srun = std::process::Command::new("srun");
+        srun.args(attach_args(job_id, self.capabilities.supports_overlap()));
+        let banner = format!(
+            "Attaching to job {}; exit the shell to return to Slurmer.",
+            job_id
+        );
+        let status = self.run_suspended(terminal, srun, Some(&banner))?;
+
+        let message = match status {
+            Ok(status) if status.success() => format!("Detached from job {}", job_id),
+            Ok(status) => match status.code() {
+                Some(code) => format!("Detached from job {} (srun exited with {})", job_id, code),
+                None => format!("Detached from job {} (srun was killed)", job_id),
+            },
+            Err(e) => format!("Could not run srun: {}", e),
+        };
+        self.set_status_message(message, 5);
+        Ok(())
+    }
+
+    /// Hand the terminal to `program`, after printing `banner`, and resume
+    /// the TUI when it exits
+    fn run_suspended<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<B>,
+        program: std::process::Command,
+        banner: Option<&str>,
+    ) -> Result<io::Result<std::process::ExitStatus>> {
+        // The program reads the terminal while it runs
+        self.event_handler.pause();
+        leave_tui()?;
+        if let Some(banner) = banner {
+            println!("{}", banner);
+        }
+        let status = command::run_interactive(program);
+        enter_tui(self.mouse)?;
+        terminal.clear()?;
+        self.event_handler.resume();
+        Ok(status)
+    }
+
+    /// Suspend the TUI for an `[open]` command, and resume it when it exits
+    fn run_external<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<B>,
+        command: &str,
+    ) -> Result<()> {
+        let mut sh = std::process::Command::new("sh");
+        sh.args(["-c", command]);
+        let status = self.run_suspended(terminal, sh, None)?;
+
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                self.set_status_message(format!("`{}` exited with {}", command, status), 5)
+            }
+            Err(e) => self.set_status_message(format!("Could not run `{}`: {}", command, e), 5),
+        }
+        Ok(())
+    }
+
+    /// Reconstruct the selected job's submission and open it in the form for review
+    fn open_resubmit_form(&mut self) {
+        let Some(job) = self.jobs_list.selected_job().cloned() else {
+            return;
+        };
+        let Some(script) = fetch_batch_script(&job.id) else {
+            self.set_status_message(format!("No batch script available for job {}", job.id), 3);
+            return;
+        };
+        let mut spec = match self.fetch_now(async { fetch_submit_spec(&job.id).await }) {
+            Ok(spec) => spec,
+            Err(e) => {
+                self.set_status_message(format!("Failed to read job {}: {}", job.id, e), 3);
+                return;
+            }
+        };
+        spec.script = script.content;
+
+        let fields = SUBMIT_FIELDS
+            .iter()
+            .map(|label| (label.to_string(), spec.field(label).to_string()))
+            .collect();
+        self.form.show(
+            format!("Resubmit job {} ({})", job.id, job.name),
+            fields,
+            Some(SubmitSpec::validate_field),
+        );
+        let source = match &script.source {
+            ScriptSource::Controller => "the controller".to_string(),
+            ScriptSource::Accounting => "accounting (sacct -B)".to_string(),
+            ScriptSource::File(path) => {
+                format!("{} (current file, may differ from what ran)", path)
+            }
+        };
+        self.form.description = vec![
+            format!("Script from {}.", source),
+            "Values below override the script's #SBATCH directives.".to_string(),
+            String::new(),
+        ];
+        if let Some(suggestion) = self.right_size(&job) {
+            self.form.description.extend(suggestion.describe(&job));
+            self.form.description.push(String::new());
+        }
+        self.form.warnings = submission_warnings(&spec);
+        self.form_purpose = Some(FormPurpose::Resubmit {
+            job_id: Some(job.id),
+            spec: Box::new(spec),
+            warned: Vec::new(),
+        });
+    }
+
+    /// Requests that past runs of `job` suggest, after recording the runs
+    /// sacct has kept in the local usage history
+    fn right_size(&mut self, job: &Job) -> Option<Suggestion> {
+        let mut history = UsageHistory::load(&self.cluster);
+        if self.mock.is_none() {
+            let past = self.fetch_now(async { fetch_past_runs(&job.name, LOOKBACK_DAYS).await });
+            if let Ok(past) = past {
+                let runs = past.iter().filter_map(UsageRun::of).collect();
+                if history.merge(runs) > 0 {
+                    if let Err(e) = history.save() {
+                        self.set_status_message(format!("{:#}", e), 3);
+                    }
+                }
+            }
+        }
+        Suggestion::from_runs(history.runs_named(&job.name))
+    }
+
+    /// Ask for an `sbatch` command line to turn into a submission
+    fn open_import_form(&mut self) {
+        self.form.show(
+            "Submit an sbatch command line",
+            vec![("Command".to_string(), String::new())],
+            None,
+        );
+        self.form.description = vec![
+            "Paste a command such as: sbatch -p gpu --gres=gpu:1 train.sh".to_string(),
+            "Its options are opened in the submission form for review.".to_string(),
+            String::new(),
+        ];
+        self.form_purpose = Some(FormPurpose::ImportSbatch);
+    }
+
+    /// Parse the pasted command line and open its options for review
+    fn import_sbatch_command(&mut self, line: &str) -> Result<(), String> {
+        let command = SubmitSpec::from_command_line(line)?;
+        let mut spec = command.spec;
+        let source = match &command.script_path {
+            Some(path) => {
+                // sbatch resolves the script against the directory it runs in
+                let path = PathBuf::from(path);
+                spec.script = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+                format!("Script {}.", path.display())
+            }
+            None => "Script wrapping the --wrap command.".to_string(),
+        };
+
+        let fields = SUBMIT_FIELDS
+            .iter()
+            .map(|label| (label.to_string(), spec.field(label).to_string()))
+            .collect();
+        self.form
+            .show("Submit new job", fields, Some(SubmitSpec::validate_field));
+        self.form.description = vec![
+            source,
+            "Values below override the script's #SBATCH directives.".to_string(),
+            String::new(),
+        ];
+        self.form.warnings = submission_warnings(&spec);
+        self.form_purpose = Some(FormPurpose::Resubmit {
+            job_id: None,
+            spec: Box::new(spec),
+            warned: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Ask for the resources of a new batch script
+    fn open_builder_form(&mut self) {
+        let builder = ScriptBuilder::default();
+        let fields = BUILDER_FIELDS
+            .iter()
+            .map(|label| (label.to_string(), builder.field(label).to_string()))
+            .collect();
+        self.form.show(
+            "New batch script",
+            fields,
+            Some(ScriptBuilder::validate_field),
+        );
+        self.form.description = vec![
+            "Writes a commented script with these resources, to edit and submit.".to_string(),
+            "Memory: 16G per node or 4G/cpu. GPUs: 2 or a100:2. Empty fields are left out."
+                .to_string(),
+            String::new(),
+        ];
+        self.form_purpose = Some(FormPurpose::BuildScript);
+    }
+
+    /// Write the script described by the form; never replaces an existing file
+    fn write_built_script(&mut self) -> Result<String, String> {
+        let mut builder = ScriptBuilder::default();
+        for field in &self.form.fields {
+            builder.set_field(&field.label, field.value.clone());
+        }
+        let path = builder.path();
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(builder.render().as_bytes()))
+            .map_err(|e| format!("cannot write {}: {}", path, e))?;
+        Ok(path)
+    }
+
+    /// Open the tags/note form for `job`
+    fn open_note_form(&mut self, job: &Job) {
+        let note = self.notes.load(&job.id).unwrap_or_default();
+        self.form.show(
+            format!("Note for job {} ({})", job.id, job.name),
+            vec![
+                ("Tags".to_string(), note.tags.join(", ")),
+                ("Note".to_string(), note.text.clone()),
+            ],
+            None,
+        );
+        let mut description = Vec::new();
+        if !note.author.is_empty() {
+            description.push(format!("Last edited by {}.", note.author));
+        }
+        if self.notes.is_shared() {
+            description.push(format!(
+                "Shared with your team (cluster {}).",
+                self.notes.cluster()
+            ));
+        }
+        if !description.is_empty() {
+            description.push(String::new());
+        }
+        self.form.description = description;
+        self.form_purpose = Some(FormPurpose::Note {
+            job_id: job.id.clone(),
+        });
+    }
+
+    /// Ask for the Slurm comment of a pending or running job
+    fn open_comment_form(&mut self, job: &Job) {
+        if !job.state.is_active() {
+            self.set_status_message(
+                format!(
+                    "Only pending or running jobs take a comment ({} is {})",
+                    job.id, job.state
+                ),
+                3,
+            );
+            return;
+        }
+        self.form.show(
+            format!("Comment of job {} ({})", job.id, job.name),
+            vec![(
+                "Comment".to_string(),
+                job.comment.clone().unwrap_or_default(),
+            )],
+            None,
+        );
+        self.form.description = vec![
+            "Set with scontrol update and kept in accounting; empty clears it.".to_string(),
+            String::new(),
+        ];
+        self.form_purpose = Some(FormPurpose::Comment {
+            job_id: job.id.clone(),
+        });
+    }
+
+    /// Open the edit form for the pending job under the cursor
+    fn open_update_form(&mut self) {
+        let Some(job) = self.jobs_list.selected_job().cloned() else {
+            return;
+        };
+        if job.state != JobState::Pending {
+            self.set_status_message(
+                format!(
+                    "Only pending jobs can be edited ({} is {})",
+                    job.id, job.state
+                ),
+                3,
+            );
+            return;
+        }
+        let spec = match self.fetch_now(async { fetch_submit_spec(&job.id).await }) {
+            Ok(spec) => spec,
+            Err(e) => {
+                self.set_status_message(format!("Failed to read job {}: {}", job.id, e), 3);
+                return;
+            }
+        };
+
+        let original: Vec<(String, String)> = UPDATE_FIELDS
+            .iter()
+            .map(|(label, _)| (label.to_string(), spec.field(label).to_string()))
+            .collect();
+        self.form.show(
+            format!("Edit pending job {} ({})", job.id, job.name),
+            original.clone(),
+            Some(SubmitSpec::validate_field),
+        );
+        self.form.description = vec![
+            "Changed fields are applied with scontrol update.".to_string(),
+            String::new(),
+        ];
+        self.form_purpose = Some(FormPurpose::UpdateJob {
+            job_id: job.id,
+            original,
+        });
+    }
+
+    /// Ask where to export the selected jobs (all listed ones when none are
+    /// selected) as a Chrome trace
+    fn open_trace_form(&mut self) {
+        let mut job_ids = self.jobs_list.get_selected_jobs();
+        if job_ids.is_empty() {
+            job_ids = self.jobs_list.jobs.iter().map(|j| j.id.clone()).collect();
+        }
+        if job_ids.is_empty() {
+            return;
+        }
+        self.form.show(
+            format!("Export {} jobs as a trace", job_ids.len()),
+            vec![
+                ("Lanes".to_string(), "partition".to_string()),
+                (
+                    "File".to_string(),
+                    format!("slurmer-trace-{}.json", unix_now()),
+                ),
+            ],
+            Some(|label, value| match label {
+                "Lanes" => value.parse::<TraceLanes>().map(|_| ()),
+                _ if value.trim().is_empty() => Err(format!("{} is required", label)),
+                _ => Ok(()),
+            }),
+        );
+        self.form.description = vec![
+            "Lanes: partition or node. Open the file in ui.perfetto.dev.".to_string(),
+            String::new(),
+        ];
+        self.form_purpose = Some(FormPurpose::Trace { job_ids });
+    }
+
+    /// Ask how and where to write the listed jobs, as filtered and sorted
+    fn open_export_form(&mut self) {
+        if self.jobs_list.jobs.is_empty() {
+            return;
+        }
+        self.form.show(
+            format!("Export {} jobs", self.jobs_list.jobs.len()),
+            vec![
+                ("Format".to_string(), "json".to_string()),
+                ("File".to_string(), format!("slurmer-jobs-{}", unix_now())),
+            ],
+            Some(|label, value| match label {
+                "Format" => match value.trim() {
+                    "json" | "csv" | "tsv" => Ok(()),
+                    _ => Err("Format is json, csv or tsv".to_string()),
+                },
+                _ if value.trim().is_empty() => Err(format!("{} is required", label)),
+                _ => Ok(()),
+            }),
+        );
+        self.form.description = vec![
+            "json: every parsed field, durations in seconds.".to_string(),
+            "csv, tsv: the table's columns, in their order.".to_string(),
+            "The format's extension is added to a file without one.".to_string(),
+            String::new(),
+        ];
+        self.form_purpose = Some(FormPurpose::ExportJobs);
+    }
+
+    /// Ask whose jobs to list: you, other users or everyone
+    fn open_user_form(&mut self) {
+        let users = self
+            .squeue_options
+            .user
+            .clone()
+            .unwrap_or_else(|| "*".to_string());
+        self.form
+            .show("List the jobs of", vec![("Users".to_string(), users)], None);
+        self.form.description = vec![
+            "me, * for all users, or user names separated by commas.".to_string(),
+            String::new(),
+        ];
+        self.form_purpose = Some(FormPurpose::SwitchUser);
+    }
+
+    /// Write the listed jobs as a table of the selected columns
+    pub fn export_table(&self, path: &Path, format: OutputFormat) -> Result<usize> {
+        let records = Records::of_columns(&self.jobs_list.jobs, &self.selected_columns);
+        write_output(path, &records.render(format))?;
+        Ok(self.jobs_list.jobs.len())
+    }
+
+    /// Write the listed jobs as JSON; `-` is stdout
+    pub fn export_json(&self, path: &Path) -> Result<usize> {
+        write_jobs_json(path, &self.jobs_list.jobs)?;
+        Ok(self.jobs_list.jobs.len())
+    }
+
+    /// Act on a submitted form
+    fn submit_form(&mut self) {
+        match self.form_purpose.take() {
+            Some(FormPurpose::Note { job_id }) => {
+                let value = |label: &str| {
+                    self.form
+                        .fields
+                        .iter()
+                        .find(|f| f.label == label)
+                        .map(|f| f.value.trim().to_string())
+                        .unwrap_or_default()
+                };
+                let note = JobNote {
+                    tags: JobNote::parse_tags(&value("Tags")),
+                    text: value("Note"),
+                    ..Default::default()
+                };
+                match self.notes.save(&job_id, note) {
+                    Ok(note) => {
+                        self.form.hide();
+                        if self.job_detail.job.as_ref().is_some_and(|j| j.id == job_id) {
+                            self.job_detail.note = Some(note.clone()).filter(|n| !n.is_empty());
+                        }
+                        let verb = if note.is_empty() { "Cleared" } else { "Saved" };
+                        self.set_status_message(format!("{} note for job {}", verb, job_id), 3);
+                    }
+                    Err(e) => {
+                        self.form.error = Some(format!("{:#}", e));
+                        self.form_purpose = Some(FormPurpose::Note { job_id });
+                    }
+                }
+            }
+            Some(FormPurpose::Comment { job_id }) => {
+                let comment = self
+                    .form
+                    .fields
+                    .first()
+                    .map(|f| f.value.trim().to_string())
+                    .unwrap_or_default();
+                let changes = [("Comment".to_string(), comment.clone())];
+                match self.fetch_now(async { modify_job(&job_id, &changes).await }) {
+                    Ok(()) => {
+                        self.form.hide();
+                        if let Some(job) = self.job_detail.job.as_mut().filter(|j| j.id == job_id) {
+                            job.comment = Some(comment.clone()).filter(|c| !c.is_empty());
+                        }
+                        let verb = if comment.is_empty() { "Cleared" } else { "Set" };
+                        self.set_status_message(format!("{} comment of job {}", verb, job_id), 3);
+                        let _ = self.refresh_jobs();
+                    }
+                    Err(e) => {
+                        self.form.error = Some(e.to_string());
+                        self.form_purpose = Some(FormPurpose::Comment { job_id });
+                    }
+                }
+            }
+            Some(FormPurpose::EditCron { index }) => {
+                let value = |label: &str| {
+                    self.form
+                        .fields
+                        .iter()
+                        .find(|f| f.label == label)
+                        .map(|f| f.value.clone())
+                        .unwrap_or_default()
+                };
+                let mut crontab = self.cron_view.crontab.clone();
+                let result = crontab
+                    .edit(index, &value("Schedule"), &value("Command"))
+                    .and_then(|()| self.save_crontab(crontab));
+                match result {
+                    Ok(()) => {
+                        self.form.hide();
+                        self.set_status_message("Saved the scrontab entry".to_string(), 3);
+                    }
+                    Err(e) => {
+                        self.form.error = Some(e);
+                        self.form_purpose = Some(FormPurpose::EditCron { index });
+                    }
+                }
+            }
+            Some(FormPurpose::UpdateJob { job_id, original }) => {
+                let changes: Vec<(String, String)> = self
+                    .form
+                    .fields
+                    .iter()
+                    .zip(&original)
+                    .filter(|(field, (_, old))| field.value.trim() != old)
+                    .filter_map(|(field, _)| {
+                        UPDATE_FIELDS
+                            .iter()
+                            .find(|(label, _)| *label == field.label)
+                            .map(|(_, key)| (key.to_string(), field.value.trim().to_string()))
+                    })
+                    .collect();
+                if changes.is_empty() {
+                    self.form.hide();
+                    self.set_status_message("Nothing changed".to_string(), 3);
+                    return;
+                }
+                match self.fetch_now(async { modify_job(&job_id, &changes).await }) {
+                    Ok(()) => {
+                        self.form.hide();
+                        let summary = changes
+                            .iter()
+                            .map(|(k, v)| format!("{}={}", k, v))
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        self.set_status_message(format!("Updated job {}: {}", job_id, summary), 5);
+                        let _ = self.refresh_jobs();
+                    }
+                    Err(e) => {
+                        self.form.error = Some(e.to_string());
+                        self.form_purpose = Some(FormPurpose::UpdateJob { job_id, original });
+                    }
+                }
+            }
+            Some(FormPurpose::Resubmit {
+                job_id,
+                mut spec,
+                warned,
+            }) => {
+                for field in &self.form.fields {
+                    spec.set_field(&field.label, field.value.clone());
+                }
+                // Conflicting options get one look before Slurm sees them
+                let warnings = submission_warnings(&spec);
+                if !warnings.is_empty() && warnings != warned {
+                    self.form.warnings = warnings.clone();
+                    self.form.error =
+                        Some("Check the warnings above; Enter again submits anyway".to_string());
+                    self.form_purpose = Some(FormPurpose::Resubmit {
+                        job_id,
+                        spec,
+                        warned: warnings,
+                    });
+                    return;
+                }
+                match self.fetch_now(async { submit(&spec).await }) {
+                    Ok(new_id) => {
+                        self.form.hide();
+                        let message = match &job_id {
+                            Some(job_id) => format!(
+                                "Submitted batch job {} (resubmission of {})",
+                                new_id, job_id
+                            ),
+                            None => format!("Submitted batch job {}", new_id),
+                        };
+                        self.set_status_message(message, 5);
+                        let _ = self.refresh_jobs();
+                    }
+                    Err(e) => {
+                        // Keep the form open so the values can be fixed
+                        self.form.error = Some(e.to_string());
+                        self.form_purpose = Some(FormPurpose::Resubmit {
+                            job_id,
+                            spec,
+                            warned,
+                        });
+                    }
+                }
+            }
+            Some(FormPurpose::ImportSbatch) => {
+                let line = self
+                    .form
+                    .fields
+                    .first()
+                    .map(|f| f.value.clone())
+                    .unwrap_or_default();
+                if let Err(e) = self.import_sbatch_command(&line) {
+                    self.form.error = Some(e);
+                    self.form_purpose = Some(FormPurpose::ImportSbatch);
+                }
+            }
+            Some(FormPurpose::BuildScript) => match self.write_built_script() {
+                Ok(path) => {
+                    self.form.hide();
+                    self.set_status_message(
+                        format!("Script saved to {}; submit it with: sbatch {}", path, path),
+                        8,
+                    );
+                }
+                Err(e) => {
+                    self.form.error = Some(e);
+                    self.form_purpose = Some(FormPurpose::BuildScript);
+                }
+            },
+            Some(FormPurpose::Trace { job_ids }) => {
+                let value = |label: &str| {
+                    self.form
+                        .fields
+                        .iter()
+                        .find(|f| f.label == label)
+                        .map(|f| f.value.trim().to_string())
+                        .unwrap_or_default()
+                };
+                let lanes = value("Lanes").parse().unwrap_or(TraceLanes::Partition);
+                let path = PathBuf::from(value("File"));
+                let result = self
+                    .fetch_now(async { fetch_jobs(&job_ids, TRACE_FIELDS).await })
+                    .and_then(|jobs| {
+                        write_trace(&path, &jobs, lanes)?;
+                        Ok(jobs.len())
+                    });
+                match result {
+                    Ok(count) => {
+                        self.form.hide();
+                        self.set_status_message(
+                            format!("Wrote a trace of {} jobs to {}", count, path.display()),
+                            5,
+                        );
+                    }
+                    Err(e) => {
+                        self.form.error = Some(format!("{:#}", e));
+                        self.form_purpose = Some(FormPurpose::Trace { job_ids });
                     }
                 }
             }
-            (KeyModifiers::SHIFT, KeyCode::Down) if self.script_view.visible => {
-                // If Shift is pressed, switch to next job and show its script
-                if self.jobs_list.next() {
-                    if let Some(job) = self.jobs_list.selected_job() {
-                        self.script_view
-                            .change_job(job.id.clone(), job.name.clone());
+            Some(FormPurpose::ExportJobs) => {
+                let value = |label: &str| {
+                    self.form
+                        .fields
+                        .iter()
+                        .find(|f| f.label == label)
+                        .map(|f| f.value.trim().to_string())
+                        .unwrap_or_default()
+                };
+                let format = value("Format");
+                let mut path = PathBuf::from(value("File"));
+                if path.extension().is_none() {
+                    path.set_extension(&format);
+                }
+                let result = match format.parse() {
+                    Ok(OutputFormat::Json) => self.export_json(&path),
+                    Ok(format) => self.export_table(&path, format),
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(count) => {
+                        self.form.hide();
+                        self.set_status_message(
+                            format!("Wrote {} jobs to {}", count, path.display()),
+                            5,
+                        );
+                    }
+                    Err(e) => {
+                        self.form.error = Some(format!("{:#}", e));
+                        self.form_purpose = Some(FormPurpose::ExportJobs);
                     }
                 }
             }
-
-            _ if self.script_view.visible => {
-                // If script view is visible, handle script view specific keys
-                self.script_view.handle_key(key);
-            }
-
-            // Close job detail view
-            (_, KeyCode::Enter) if self.script_view.visible => {
-                self.script_view.visible = false;
-            }
-
-            // Show log view
-            (_, KeyCode::Char('v'))
-                if !self.filter_popup.visible
-                    && !self.settings_popup.visible
-                    && !self.script_view.visible
-                    && !self.columns_popup.visible
-                    && !self.log_view.visible =>
-            {
-                if let Some(job) = self.jobs_list.selected_job() {
-                    self.log_view
-                        .set_slurm_logs_dir(self.slurm_logs_dir.clone());
-                    self.log_view.show(job.id.clone());
+            Some(FormPurpose::SwitchUser) => {
+                let username = match &self.mock {
+                    Some(mock) => mock.user.clone(),
+                    None => get_username(),
+                };
+                let users = self.form.fields.first().map(|f| f.value.clone());
+                self.form.hide();
+                self.squeue_options.user = resolve_user(users.as_deref(), &username);
+                self.active_preset = None;
+                self.aggregate_return = None;
+                if let Err(e) = self.apply_filters() {
+                    self.set_status_message(format!("Failed to switch user: {}", e), 5);
                 }
             }
-
-            // Change job for log view
-            (KeyModifiers::SHIFT, KeyCode::Up) if self.log_view.visible => {
-                // If Shift is pressed, switch to previous job and show its logs
-                if self.jobs_list.previous() {
-                    if let Some(job) = self.jobs_list.selected_job() {
-                        self.log_view.change_job(job.id.clone());
+            Some(FormPurpose::Search) => {
+                self.search_fields = self
+                    .form
+                    .fields
+                    .iter()
+                    .map(|f| (f.label.clone(), f.value.clone()))
+                    .collect();
+                match self.run_search() {
+                    Ok(()) => self.form.hide(),
+                    Err(e) => {
+                        self.form.error = Some(format!("{:#}", e));
+                        self.form_purpose = Some(FormPurpose::Search);
                     }
                 }
             }
-            (KeyModifiers::SHIFT, KeyCode::Down) if self.log_view.visible => {
-                // If Shift is pressed, switch to next job and show its logs
-                if self.jobs_list.next() {
-                    if let Some(job) = self.jobs_list.selected_job() {
-                        self.log_view.change_job(job.id.clone());
-                    }
+            Some(FormPurpose::ConfirmProtected { action, phrase }) => {
+                let typed = self
+                    .form
+                    .fields
+                    .first()
+                    .map(|f| f.value.trim().to_string())
+                    .unwrap_or_default();
+                if typed == phrase {
+                    self.form.hide();
+                    self.run_action_on_selected(action);
+                } else {
+                    self.form.error = Some(format!("Type '{}' exactly, or Esc to abort", phrase));
+                    self.form_purpose = Some(FormPurpose::ConfirmProtected { action, phrase });
                 }
             }
+            None => self.form.hide(),
+        }
+    }
 
-            // Handle log view keys events
-            _ if self.log_view.visible => {
-                // If log view is visible, handle log view specific keys
-                self.log_view.handle_key(key);
-            }
+    /// Point the detail pane at the job under the cursor of the tab shown
+    fn show_selected_in_detail(&mut self) {
+        let selected = if self.search_view.visible {
+            self.search_view.selected_job()
+        } else {
+            self.jobs_list.selected_job()
+        };
+        if let Some(job) = selected.cloned() {
+            self.job_detail.change_job(job);
+            self.load_job_detail();
+        }
+    }
 
-            _ if self.settings_popup.visible => {
-                let action = self.settings_popup.handle_key(key);
-                match action {
-                    SettingsAction::Close => {
-                        self.settings_popup.visible = false;
+    /// Run `fetch` in the pool off the UI thread; its result is shown on a
+    /// later tick if the detail pane is still on job `job_id`
+    fn spawn_detail_fetch(
+        &self,
+        job_id: &str,
+        fetch: impl Future<Output = DetailFetch> + Send + 'static,
+    ) {
+        let job_id = job_id.to_string();
+        self.spawn_fetch(fetch, move |fetched| Fetched::Detail(job_id, fetched));
+    }
+
+    /// Run `fetch` through the pool off the UI thread, to be taken in by
+    /// `apply_fetched` as `wrap` makes it
+    fn spawn_fetch<T: Send + 'static>(
+        &self,
+        fetch: impl Future<Output = T> + Send + 'static,
+        wrap: impl FnOnce(T) -> Fetched + Send + 'static,
+    ) {
+        let pool = self.pool.clone();
+        let tx = self.fetched_tx.clone();
+        self.runtime.spawn(async move {
+            let fetched = pool.run_one(fetch).await.value;
+            let _ = tx.send(wrap(fetched));
+        });
+    }
+
+    /// Wait for `fetch`, a Slurm command a key asks for, in a slot of the
+    /// pool like the fetches off the UI thread
+    fn fetch_now<T>(&self, fetch: impl Future<Output = T>) -> T {
+        self.runtime.block_on(self.pool.run_one(fetch)).value
+    }
+
+    /// Take in the fetches that finished off the UI thread since the last tick
+    fn apply_fetched(&mut self) {
+        while let Ok(fetched) = self.fetched_rx.try_recv() {
+            match fetched {
+                Fetched::Poll(generation, plan, fetches) => {
+                    if generation != self.poll_generation {
+                        continue;
                     }
-                    SettingsAction::Saved => {
-                        self.slurm_logs_dir = self
-                            .settings_popup
-                            .current_value()
-                            .map(PathBuf::from);
-                        self.log_view
-                            .set_slurm_logs_dir(self.slurm_logs_dir.clone());
+                    self.poll_in_flight = false;
+                    let result = self.apply_poll(*plan, fetches);
+                    if let Err(e) = self.settle_poll(result) {
+                        self.set_status_message(format!("Auto-refresh failed: {}", e), 3);
                     }
-                    SettingsAction::None => {}
                 }
-            }
-
-            // Handle columns popup key events
-            _ if self.columns_popup.visible => {
-                let action = self.columns_popup.handle_key(key);
-
-                match action {
-                    ColumnsAction::Close => {
-                        self.columns_popup.visible = false;
+                Fetched::Cluster(fetches) => {
+                    self.cluster_in_flight = false;
+                    for timed in fetches {
+                        self.apply_cluster_fetch(timed);
                     }
-                    ColumnsAction::Apply => {
-                        self.columns_popup.visible = false;
-                        self.selected_columns = self.columns_popup.selected_columns.clone();
-                        self.sort_columns = self.columns_popup.sort_columns.clone();
-
-                        // Update the format and refresh
-                        if let Err(e) = self.refresh_jobs() {
-                            self.set_status_message(format!("Failed to refresh: {}", e), 3);
-                        } else {
-                            self.set_status_message("Column settings applied".to_string(), 3);
-                        }
+                }
+                Fetched::Detail(job_id, fetch) => {
+                    if self.job_detail.job.as_ref().map(|j| j.id.as_str()) != Some(job_id.as_str())
+                    {
+                        continue;
                     }
-                    ColumnsAction::SaveAndApply => {
-                        self.columns_popup.visible = false;
-                        self.selected_columns = self.columns_popup.selected_columns.clone();
-                        self.sort_columns = self.columns_popup.sort_columns.clone();
-
-                        // TODO: Save settings to config file
-                        self.set_status_message("Column settings saved and applied".to_string(), 3);
-
-                        // Update the format and refresh
-                        if let Err(e) = self.refresh_jobs() {
-                            self.set_status_message(format!("Failed to refresh: {}", e), 3);
-                        }
+                    match fetch {
+                        DetailFetch::PreemptTime(time) => self.job_detail.preempt_time = time,
+                        DetailFetch::Storage(storage) => self.job_detail.storage = storage,
+                        DetailFetch::Usage(usage) => self.job_detail.set_usage(usage),
+                        DetailFetch::Nodes(nodes) => self.job_detail.set_nodes(nodes),
+                        DetailFetch::Events(events) => self.job_detail.set_events(events),
                     }
-                    ColumnsAction::None => {}
                 }
-            }
-
-            // Refresh jobs
-            (_, KeyCode::Char('r'))
-                if !self.filter_popup.visible
-                    && !self.script_view.visible
-                    && !self.columns_popup.visible =>
-            {
-                if let Err(e) = self.refresh_jobs() {
-                    self.set_status_message(format!("Failed to refresh: {}", e), 3);
+                Fetched::Nodes(nodes) => {
+                    if self.node_view.popup.visible {
+                        self.node_view.show(nodes);
+                    }
                 }
+                Fetched::NodeUpdate(update, result) => self.node_updated(update, result),
             }
-
-            _ => {}
         }
     }
 
-    /// Handle mouse events
-    fn handle_mouse_event(&mut self, _mouse: MouseEvent) {
-        // TODO: Implement mouse event handling for TUI interactions
-        // seems unnecessary?
-    }
-
-    /// Handle tick events (called periodically)
-    fn handle_tick(&mut self) {
-        // Check if it's time to auto-refresh
-        if !self.filter_popup.visible
-            && !self.script_view.visible
-            && !self.columns_popup.visible
-            && self.last_refresh.elapsed().as_secs() >= self.job_refresh_interval
-        {
-            if let Err(e) = self.refresh_jobs() {
-                self.set_status_message(format!("Auto-refresh failed: {}", e), 3);
+    /// Fetch whatever the detail pane's current tab is still missing
+    fn load_job_detail(&mut self) {
+        let Some(load) = self.job_detail.pending_load() else {
+            return;
+        };
+        let Some(job_id) = self.job_detail.job.as_ref().map(|j| j.id.clone()) else {
+            return;
+        };
+        match load {
+            DetailLoad::Accounting => {
+                // Re-read with the record so teammates' edits show up on reload
+                self.job_detail.note = self.notes.load(&job_id);
+                self.job_detail.transitions = self.state_history.transitions(&job_id).to_vec();
+                self.job_detail.reservations = self.reservations.clone();
+                self.job_detail.licenses = self.licenses.clone();
+                self.job_detail.preemption = self.job_detail.job.as_ref().and_then(|job| {
+                    preemption_risk(job, &self.qos_preemption, &self.pending_by_qos)
+                });
+                self.job_detail.preempt_time = None;
+                if self.mock.is_none() && self.job_detail.preemption.is_some() {
+                    let id = job_id.clone();
+                    self.spawn_detail_fetch(&job_id, async move {
+                        DetailFetch::PreemptTime(fetch_preempt_time(&id).await.ok().flatten())
+                    });
+                }
+                // Accounting has no burst buffer or scratch of jobs not yet ended
+                let active = self
+                    .job_detail
+                    .job
+                    .as_ref()
+                    .is_some_and(|j| matches!(j.state, JobState::Pending | JobState::Running));
+                if self.mock.is_none() && active {
+                    let id = job_id.clone();
+                    self.spawn_detail_fetch(&job_id, async move {
+                        DetailFetch::Storage(fetch_storage(&id).await.unwrap_or_default())
+                    });
+                }
+                let result = match &self.mock {
+                    Some(_) => Err("not recorded in the fixture".to_string()),
+                    None => self
+                        .fetch_now(async { fetch_job_details(&job_id).await })
+                        .map_err(|e| e.to_string()),
+                };
+                self.job_detail.set_accounting(result);
+                let finished = self
+                    .job_detail
+                    .job
+                    .as_ref()
+                    .is_some_and(|j| !matches!(j.state, JobState::Pending | JobState::Running));
+                if finished && self.mock.is_none() {
+                    let max_rss = self
+                        .fetch_now(async { fetch_max_rss(&job_id).await })
+                        .ok()
+                        .flatten();
+                    self.job_detail.set_max_rss(max_rss);
+                }
+                if self.job_detail.baseline.is_some() {
+                    self.job_detail.metrics = self.job_log_metrics(&job_id);
+                }
+            }
+            DetailLoad::Usage => {
+                self.job_detail.start_loading(load);
+                let id = job_id.clone();
+                self.spawn_detail_fetch(&job_id, async move {
+                    DetailFetch::Usage(run_sstat(&id).await.map_err(|e| e.to_string()))
+                });
+            }
+            DetailLoad::Nodes => {
+                if self.mock.is_some() {
+                    self.job_detail
+                        .set_nodes(Err("not recorded in the fixture".to_string()));
+                    return;
+                }
+                let nodes = self.job_detail.job.as_ref().map_or(1, |j| j.nodes);
+                let overlap = self.capabilities.supports_overlap();
+                self.job_detail.start_loading(load);
+                let id = job_id.clone();
+                self.spawn_detail_fetch(&job_id, async move {
+                    let nodes = inspect_job(&id, nodes, overlap).await;
+                    DetailFetch::Nodes(nodes.map_err(|e| e.to_string()))
+                });
+            }
+            DetailLoad::Steps => {
+                let result = match &self.mock {
+                    Some(_) => Err("not recorded in the fixture".to_string()),
+                    None => self
+                        .fetch_now(async { fetch_job_steps(&job_id).await })
+                        .map_err(|e| e.to_string()),
+                };
+                self.job_detail.set_steps(result);
+            }
+            DetailLoad::Events => {
+                if let Some(mock) = &self.mock {
+                    let events = run_events(&mock.jobs_by_id(&[job_id]));
+                    self.job_detail.set_events(Ok(events));
+                    return;
+                }
+                self.job_detail.start_loading(load);
+                let id = job_id.clone();
+                self.spawn_detail_fetch(&job_id, async move {
+                    DetailFetch::Events(fetch_job_events(&id).await.map_err(|e| e.to_string()))
+                });
             }
         }
-
-        // Check for log view updates and refresh content
-        if self.log_view.visible {
-            self.log_view.check_refresh();
+        // The Events tab shows the accounting record, then what else happened
+        if load == DetailLoad::Accounting
+            && self.job_detail.pending_load() == Some(DetailLoad::Events)
+        {
+            self.load_job_detail();
         }
     }
 
-    /// Set a temporary status message
-    fn set_status_message(&mut self, message: String, duration_secs: u64) {
-        self.status_message = message;
-        self.status_timeout = Some(Instant::now() + Duration::from_secs(duration_secs));
-    }
-
-    /// Set the auto-refresh interval in seconds
-    /// TODO: maybe used it in the future
-    fn _set_refresh_interval(&mut self, seconds: u64) {
-        self.job_refresh_interval = seconds;
-        self.set_status_message(format!("Auto-refresh interval set to {}s", seconds), 3);
+    /// Numbers the job printed to its stdout log
+    fn job_log_metrics(&self, job_id: &str) -> BTreeMap<String, f64> {
+        self.job_detail
+            .logs
+            .stdout_path_of(job_id)
+            .map(|path| log_metrics(&path))
+            .unwrap_or_default()
     }
 
-    /// Apply all filter changes and refresh jobs
-    fn apply_filters(&mut self) -> Result<()> {
-        self.filter_popup.visible = false;
-        self.set_status_message("Applying filters...".to_string(), 3);
-
-        // Ensure we refresh the jobs with the updated filters
-        let result = self.refresh_jobs();
-
-        // Display feedback about the filter application
-        if result.is_ok() {
-            let filter_desc = self.get_filter_description();
-            if !filter_desc.is_empty() {
-                let loaded_count = self.jobs_list.jobs.len();
-                self.set_status_message(
-                    format!(
-                        "Filters applied: {} ({} jobs shown)",
-                        filter_desc, loaded_count
-                    ),
-                    3,
-                );
-            } else {
-                let loaded_count = self.jobs_list.jobs.len();
-                self.set_status_message(
-                    format!("Filters cleared ({} jobs shown)", loaded_count),
-                    3,
-                );
-            }
+    /// Pin the job in the detail pane as the baseline, or unpin it if it is one
+    fn toggle_baseline(&mut self) {
+        let Some(job) = self.job_detail.job.clone() else {
+            return;
+        };
+        if self
+            .job_detail
+            .baseline
+            .as_ref()
+            .is_some_and(|b| b.job.id == job.id)
+        {
+            self.job_detail.baseline = None;
+            self.set_status_message(format!("Unpinned baseline {}", job.id), 3);
+            return;
         }
-
-        result
+        let metrics = self.job_log_metrics(&job.id);
+        self.set_status_message(
+            format!(
+                "Pinned {} as the baseline ({} metrics from its output)",
+                job.id,
+                metrics.len()
+            ),
+            3,
+        );
+        self.job_detail.metrics = metrics.clone();
+        self.job_detail.baseline = Some(Baseline { job, metrics });
     }
 
     /// Get a human-readable description of the current filters
     fn get_filter_description(&self) -> String {
         let mut parts = Vec::new();
 
+        if let Some(preset) = self.active_preset.and_then(|i| self.presets.get(i)) {
+            parts.push(format!("preset={}", preset.name));
+        }
+
         // User filter
         if let Some(user) = &self.squeue_options.user {
             parts.push(format!("user={}", user));
@@ -945,6 +4481,10 @@ impl App {
             parts.push(format!("qos={}", qos));
         }
 
+        if !self.squeue_options.wckeys.is_empty() {
+            parts.push(format!("wckey={}", self.squeue_options.wckeys.join(",")));
+        }
+
         // Name filter (regex)
         if let Some(name) = &self.squeue_options.name_filter {
             parts.push(format!("name_regex={}", name));
@@ -955,6 +4495,14 @@ impl App {
             parts.push(format!("node_regex={}", node));
         }
 
+        if self.squeue_options.gpu_only {
+            parts.push("gpus_only".to_string());
+        }
+
+        if let Some(hours) = self.squeue_options.start_within_hours {
+            parts.push(format!("starts_within_hours={}", hours));
+        }
+
         if self.include_recent_ended {
             parts.push(format!("ended_last_hours={}", self.recent_ended_hours));
         }
@@ -976,13 +4524,35 @@ impl App {
         // }
 
         // Generate format string for squeue based on column selection
-        let format_string = self
-            .selected_columns
+        let columns = self.fetched_columns();
+        let mut codes = columns
             .iter()
             .map(|col| col.format_code())
-            .collect::<Vec<&str>>()
-            .join("|");
-        self.squeue_options.format = format_string;
+            .filter(|code| !code.is_empty())
+            .collect::<Vec<&str>>();
+        // Start estimates are matched to the jobs by id
+        if self.needs_start_estimates() && !codes.contains(&"%i") {
+            codes.push("%i");
+        }
+        self.squeue_options.format = codes.join("|");
+        // The GPU filter needs GRES even when the GPUs column is hidden
+        if self.squeue_options.gpu_only && !columns.contains(&JobColumn::Gpus) {
+            self.squeue_options.format.push_str("|%b");
+        }
+        if !self.squeue_options.wckeys.is_empty() {
+            self.squeue_options.format.push_str("|%w");
+        }
+        // Reservations and licenses are flagged in the detail pane
+        if !columns.contains(&JobColumn::Reservation) {
+            self.squeue_options.format.push_str("|%v");
+        }
+        if !columns.contains(&JobColumn::Licenses) {
+            self.squeue_options.format.push_str("|%W");
+        }
+        // The queue wait is computed from the submit and start times
+        if columns.contains(&JobColumn::QueueWait) && !columns.contains(&JobColumn::StartTime) {
+            self.squeue_options.format.push_str("|%S");
+        }
 
         // Build sort string based on sort columns
         // remove any existing sort columns
@@ -992,6 +4562,10 @@ impl App {
             for sort_col in &self.sort_columns {
                 // get the format code for the column, removing any leading '%'
                 let sort_code = sort_col.column.format_code().trim_start_matches('%');
+                if sort_code.is_empty() {
+                    // Sorted in the app only
+                    continue;
+                }
                 // set the sort order
                 let is_ascending = matches!(sort_col.order, SortOrder::Ascending);
 
@@ -1022,38 +4596,216 @@ impl App {
         }
     }
 
-    fn cancel_selected_jobs(&mut self) {
-        // Get selected job IDs
-        let selected_jobs = self.jobs_list.get_selected_jobs();
-        let selecteed_count = selected_jobs.len();
-        let _ = self
-            .runtime
-            .block_on(async { execute_scancel(selected_jobs).await });
-        // refresh the jobs list after cancellation
-        if let Err(e) = self.refresh_jobs() {
-            self.set_status_message(format!("Failed to refresh after cancel: {}", e), 3);
+    /// Ask for confirmation before applying `action` to the selected jobs;
+    /// destructive actions on protected jobs need the confirmation typed
+    fn request_action(&mut self, action: JobAction) {
+        self.jobs_list.end_visual();
+        let protected = if action.is_destructive() {
+            self.protected_selection()
+        } else {
+            Vec::new()
+        };
+        if protected.is_empty() {
+            self.pending_action = Some(action);
+            return;
+        }
+
+        let phrase = confirmation_phrase(action, protected.len());
+        self.form.show(
+            format!("Really {} protected jobs?", action.verb()),
+            vec![("Confirm".to_string(), String::new())],
+            None,
+        );
+        let mut description: Vec<String> = protected
+            .iter()
+            .map(|(job, reason)| format!("{} ({}): {}", job.id, job.name, reason))
+            .collect();
+        description.push(String::new());
+        description.push(format!(
+            "Type '{}' to {} all {} selected jobs.",
+            phrase,
+            action.verb(),
+            self.jobs_list.get_selected_jobs().len()
+        ));
+        description.push(String::new());
+        self.form.description = description;
+        self.form_purpose = Some(FormPurpose::ConfirmProtected { action, phrase });
+    }
+
+    /// Selected jobs that are protected, with the reason
+    fn protected_selection(&self) -> Vec<(Job, String)> {
+        self.jobs_list
+            .selected_jobs
+            .iter()
+            .filter_map(|&i| self.jobs_list.jobs.get(i))
+            .filter_map(|job| {
+                let note = self.notes.load(&job.id);
+                let reason = self.protection.reason(job, note.as_ref())?;
+                Some((job.clone(), reason))
+            })
+            .collect()
+    }
+
+    /// Widen or narrow the window ended jobs are listed for, to the next of
+    /// `LOOKBACK_STEPS`; sacct is asked again at once
+    fn step_lookback(&mut self, longer: bool) {
+        let hours = self.recent_ended_hours;
+        let next = if longer {
+            LOOKBACK_STEPS.iter().find(|&&h| h > hours)
         } else {
-            self.set_status_message(format!("Cancelled {} job(s)", selecteed_count), 3);
+            LOOKBACK_STEPS.iter().rev().find(|&&h| h < hours)
+        };
+        let Some(&next) = next else {
+            self.set_status_message(
+                format!("Ended jobs already listed for {}", format_window(hours)),
+                3,
+            );
+            return;
+        };
+        self.recent_ended_hours = next;
+        self.active_preset = None;
+        match self.refresh_jobs() {
+            Ok(()) => self.set_status_message(
+                format!("Listing jobs ended in the last {}", format_window(next)),
+                3,
+            ),
+            Err(e) => self.set_status_message(format!("Failed to refresh: {}", e), 3),
         }
     }
-}
 
-fn merge_jobs_prefer_active(active_jobs: Vec<Job>, ended_jobs: Vec<Job>) -> Vec<Job> {
-    let mut by_id: HashMap<String, Job> = HashMap::new();
+    /// Show job times relative to now, or as timestamps
+    fn toggle_relative_times(&mut self) {
+        let times = &mut self.jobs_list.times;
+        times.relative = !times.relative;
+        self.job_detail.times = times.clone();
+        let message = match (times.relative, &times.zone) {
+            (true, _) => "Times shown relative to now".to_string(),
+            (false, Some(zone)) => format!("Times shown in {}", zone),
+            (false, None) => "Times shown on the cluster's clock".to_string(),
+        };
+        self.set_status_message(message, 3);
+    }
+
+    /// List the jobs by account, then by user, then by state, then all
+    /// together again
+    fn cycle_group_by(&mut self) {
+        let group_by = match self.jobs_list.group_by {
+            None => Some(GroupBy::Account),
+            Some(GroupBy::Account) => Some(GroupBy::User),
+            Some(GroupBy::User) => Some(GroupBy::State),
+            Some(GroupBy::State) => None,
+        };
+        self.jobs_list.set_group_by(group_by);
+        let message = match group_by {
+            Some(group_by) => format!("Jobs grouped by {}", group_by.title()),
+            None => "Jobs no longer grouped".to_string(),
+        };
+        self.set_status_message(message, 3);
+    }
 
-    // Prefer active job records.
-    for job in ended_jobs {
-        if !job.id.is_empty() {
-            by_id.insert(job.id.clone(), job);
+    /// Protect the job under the cursor, or lift its protection (the
+    /// `protected` note tag; name patterns come from the config)
+    fn toggle_protected(&mut self) {
+        let Some(job) = self.jobs_list.selected_job().cloned() else {
+            return;
+        };
+        let mut note = self.notes.load(&job.id).unwrap_or_default();
+        let protected = toggle_protected(&mut note);
+        match self.notes.save(&job.id, note) {
+            Ok(_) if protected => {
+                self.set_status_message(format!("Job {} is protected", job.id), 3)
+            }
+            Ok(_) => match self.protection.reason(&job, None) {
+                Some(reason) => self.set_status_message(
+                    format!("Job {} is still protected: {}", job.id, reason),
+                    5,
+                ),
+                None => {
+                    self.set_status_message(format!("Job {} is no longer protected", job.id), 3)
+                }
+            },
+            Err(e) => self.set_status_message(format!("Failed to save note: {:#}", e), 5),
         }
     }
-    for job in active_jobs {
-        if !job.id.is_empty() {
-            by_id.insert(job.id.clone(), job);
+
+    fn run_action_on_selected(&mut self, action: JobAction) {
+        // Get selected job IDs
+        let selected_jobs = self.jobs_list.get_selected_jobs();
+        let selected_count = selected_jobs.len();
+        if selected_count == 0 {
+            return;
+        }
+        // Queued rather than run here, so a failed attempt is retried and
+        // the outcome reported from the tick once it is known
+        if let Err(e) = self.action_queue.enqueue(action, selected_jobs) {
+            self.set_status_message(format!("Failed to queue {}: {}", action.verb(), e), 5);
+            return;
+        }
+        if action == JobAction::Cancel {
+            self.jobs_list.clear_selection();
         }
+        self.set_status_message(
+            format!("Queued {} of {} job(s)", action.verb(), selected_count),
+            3,
+        );
+        let outcomes = self.action_queue.poll(&self.runtime);
+        self.report_action_outcomes(outcomes);
     }
 
-    by_id.into_values().collect()
+    /// Show what became of queued actions, refreshing the list after the
+    /// ones that went through
+    fn report_action_outcomes(&mut self, outcomes: Vec<ActionOutcome>) {
+        let mut refresh = false;
+        for outcome in outcomes {
+            match outcome {
+                ActionOutcome::Done(queued) => {
+                    refresh = true;
+                    self.set_status_message(
+                        format!(
+                            "{} {} job(s)",
+                            queued.action.past_tense(),
+                            queued.job_ids.len()
+                        ),
+                        3,
+                    );
+                }
+                ActionOutcome::Retrying { action, in_secs } => self.set_status_message(
+                    format!(
+                        "Failed to {} {} job(s), retrying in {}s (attempt {}): {}",
+                        action.action.verb(),
+                        action.job_ids.len(),
+                        in_secs,
+                        action.attempts,
+                        action.last_error.unwrap_or_default()
+                    ),
+                    5,
+                ),
+                ActionOutcome::Failed(queued) => self.set_status_message(
+                    format!(
+                        "Failed to {} {} job(s) after {} attempt(s): {}",
+                        queued.action.verb(),
+                        queued.job_ids.len(),
+                        queued.attempts,
+                        queued.last_error.unwrap_or_default()
+                    ),
+                    10,
+                ),
+                ActionOutcome::Expired(queued) => self.set_status_message(
+                    format!(
+                        "Dropped a queued {} of {} job(s) from an earlier session: too old to run now",
+                        queued.action.verb(),
+                        queued.job_ids.len()
+                    ),
+                    10,
+                ),
+            }
+        }
+        if refresh {
+            if let Err(e) = self.refresh_jobs() {
+                self.set_status_message(format!("Failed to refresh after action: {}", e), 3);
+            }
+        }
+    }
 }
 
 fn sort_jobs(jobs: &mut [Job], sort_columns: &[SortColumn]) {
@@ -1090,10 +4842,23 @@ fn cmp_job_by_column(a: &Job, b: &Job, column: JobColumn) -> Ordering {
         JobColumn::State => a.state.to_string().cmp(&b.state.to_string()),
         JobColumn::Partition => a.partition.cmp(&b.partition),
         JobColumn::Nodes => a.nodes.cmp(&b.nodes),
-        JobColumn::Node => a.node.as_deref().unwrap_or("").cmp(b.node.as_deref().unwrap_or("")),
+        JobColumn::Node => a
+            .node
+            .as_deref()
+            .unwrap_or("")
+            .cmp(b.node.as_deref().unwrap_or("")),
         JobColumn::CPUs => a.cpus.cmp(&b.cpus),
         JobColumn::Time => a.time.cmp(&b.time),
-        JobColumn::Memory => a.memory.cmp(&b.memory),
+        JobColumn::TimeLeft => a.time_remaining().cmp(&b.time_remaining()),
+        JobColumn::CpuUsage => a
+            .cpu_efficiency()
+            .partial_cmp(&b.cpu_efficiency())
+            .unwrap_or(Ordering::Equal),
+        JobColumn::Efficiency => overall(a)
+            .partial_cmp(&overall(b))
+            .unwrap_or(Ordering::Equal),
+        JobColumn::Memory => a.memory_per_node().cmp(&b.memory_per_node()),
+        JobColumn::Gpus => a.gpus.cmp(&b.gpus),
         JobColumn::Account => a
             .account
             .as_deref()
@@ -1105,26 +4870,56 @@ fn cmp_job_by_column(a: &Job, b: &Job, column: JobColumn) -> Ordering {
             .as_deref()
             .unwrap_or("")
             .cmp(b.work_dir.as_deref().unwrap_or("")),
-        JobColumn::SubmitTime => a
-            .submit_time
+        JobColumn::SubmitTime => a.submit_time.cmp(&b.submit_time),
+        JobColumn::StartTime => a.start_time.cmp(&b.start_time),
+        JobColumn::EndTime => a.end_time.cmp(&b.end_time),
+        JobColumn::QueueWait => {
+            let now = Local::now();
+            queue_wait(a, now).cmp(&queue_wait(b, now))
+        }
+        // Jobs without an estimate last
+        JobColumn::EstimatedStart => match (&a.estimated_start, &b.estimated_start) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        },
+        JobColumn::PReason => a
+            .pending_reason
             .as_deref()
             .unwrap_or("")
-            .cmp(b.submit_time.as_deref().unwrap_or("")),
-        JobColumn::StartTime => a
-            .start_time
+            .cmp(b.pending_reason.as_deref().unwrap_or("")),
+        JobColumn::Reservation => a
+            .reservation
             .as_deref()
             .unwrap_or("")
-            .cmp(b.start_time.as_deref().unwrap_or("")),
-        JobColumn::EndTime => a
-            .end_time
+            .cmp(b.reservation.as_deref().unwrap_or("")),
+        JobColumn::Licenses => a
+            .licenses
             .as_deref()
             .unwrap_or("")
-            .cmp(b.end_time.as_deref().unwrap_or("")),
-        JobColumn::PReason => a
-            .pending_reason
+            .cmp(b.licenses.as_deref().unwrap_or("")),
+        JobColumn::TmpDisk => a
+            .tmp_disk
+            .map(|m| m.bytes)
+            .cmp(&b.tmp_disk.map(|m| m.bytes)),
+        JobColumn::BurstBuffer => a
+            .burst_buffer
+            .map(|m| m.bytes)
+            .cmp(&b.burst_buffer.map(|m| m.bytes)),
+        JobColumn::Preemption => a
+            .preemption
             .as_deref()
             .unwrap_or("")
-            .cmp(b.pending_reason.as_deref().unwrap_or("")),
+            .cmp(b.preemption.as_deref().unwrap_or("")),
+        JobColumn::Energy => a.energy.cmp(&b.energy),
+    }
+}
+
+/// `24h`, or `7d` for whole days past the first
+fn format_window(hours: u32) -> String {
+    if hours > 24 && hours.is_multiple_of(24) {
+        format!("{}d", hours / 24)
+    } else {
+        format!("{}h", hours)
     }
 }
 
@@ -1136,3 +4931,101 @@ fn parse_job_id_numeric(job_id: &str) -> Option<u64> {
         digits.parse::<u64>().ok()
     }
 }
+
+/// Run the queries of a job poll in `pool`: squeue, `squeue --start` and
+/// sacct when due, and the cluster queries when they are due with it; then
+/// the records of the watched and formerly active jobs squeue did not list
+async fn run_poll(pool: Pool, plan: &PollPlan) -> Vec<Timed<Fetch>> {
+    let mut tasks: Vec<Task<Fetch>> = Vec::new();
+    let squeue_options = plan.options.clone();
+    tasks.push(Box::pin(async move {
+        Fetch::Squeue(run_squeue(&squeue_options).await)
+    }));
+    if plan.estimates {
+        let user = plan.options.user.clone();
+        tasks.push(Box::pin(async move {
+            Fetch::Estimates(fetch_start_estimates(user.as_deref()).await)
+        }));
+    }
+    if let Some(sacct_options) = plan.sacct_options.clone().filter(|_| plan.sacct_due) {
+        tasks.push(Box::pin(async move {
+            Fetch::Sacct(run_sacct(&sacct_options).await)
+        }));
+    }
+    if plan.cluster {
+        tasks.extend(cluster_tasks());
+    }
+    let mut fetches = pool.run(tasks).await;
+
+    let Some(listed) = fetches.iter().find_map(|t| match &t.value {
+        Fetch::Squeue(Ok(jobs)) => Some(jobs.iter().map(|j| j.id.clone()).collect::<Vec<_>>()),
+        _ => None,
+    }) else {
+        return fetches;
+    };
+    let unlisted = |ids: &[String]| -> Vec<String> {
+        ids.iter()
+            .filter(|id| !listed.contains(id))
+            .cloned()
+            .collect()
+    };
+    let mut records: Vec<Task<Fetch>> = Vec::new();
+    let watched = unlisted(&plan.watched);
+    if !watched.is_empty() {
+        let fields = plan.record_fields.clone();
+        records.push(Box::pin(async move {
+            Fetch::Watched(fetch_jobs(&watched, &fields).await)
+        }));
+    }
+    let vanished = unlisted(&plan.active);
+    if !vanished.is_empty() {
+        let fields = plan.record_fields.clone();
+        records.push(Box::pin(async move {
+            Fetch::Ended(fetch_jobs(&vanished, &fields).await)
+        }));
+    }
+    fetches.extend(pool.run(records).await);
+    fetches
+}
+
+/// What [`run_poll`] fetches, from a fixture
+fn poll_mock(mock: &MockJobSource, plan: &PollPlan) -> Vec<Timed<Fetch>> {
+    let now = Instant::now();
+    let timed = |value| Timed {
+        started: now,
+        finished: Instant::now(),
+        value,
+    };
+    let jobs = mock.squeue(&plan.options);
+    let listed: Vec<String> = jobs.iter().map(|j| j.id.clone()).collect();
+    let mut fetches = vec![timed(Fetch::Squeue(Ok(jobs)))];
+    if let Some(sacct_options) = plan.sacct_options.as_ref().filter(|_| plan.sacct_due) {
+        fetches.push(timed(Fetch::Sacct(Ok(mock.sacct(sacct_options)))));
+    }
+    let unlisted = |ids: &[String]| -> Vec<String> {
+        ids.iter()
+            .filter(|id| !listed.contains(id))
+            .cloned()
+            .collect()
+    };
+    let watched = unlisted(&plan.watched);
+    if !watched.is_empty() {
+        fetches.push(timed(Fetch::Watched(Ok(mock.jobs_by_id(&watched)))));
+    }
+    let vanished = unlisted(&plan.active);
+    if !vanished.is_empty() {
+        fetches.push(timed(Fetch::Ended(Ok(mock.jobs_by_id(&vanished)))));
+    }
+    fetches
+}
+
+/// sinfo and the slower-changing cluster data: reservations, licenses and
+/// the QOS preemption rules
+fn cluster_tasks() -> Vec<Task<Fetch>> {
+    vec![
+        Box::pin(async { Fetch::Partitions(get_partitions().await) }),
+        Box::pin(async { Fetch::Reservations(fetch_reservations().await) }),
+        Box::pin(async { Fetch::Licenses(fetch_licenses().await) }),
+        Box::pin(async { Fetch::Preemption(fetch_preemption().await) }),
+    ]
+}
@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::slurm::Job;
+use crate::utils::format_slurm_duration;
+
+/// How much of the end of a log is scanned for metrics
+const LOG_TAIL_BYTES: u64 = 64 * 1024;
+
+/// Metrics compared against the baseline, at most
+const MAX_METRICS: usize = 12;
+
+/// The job other jobs are compared with, as it was when it was pinned
+#[derive(Debug, Clone)]
+pub struct Baseline {
+    pub job: Job,
+    /// Numbers reported in the job's output (see [`extract_metrics`])
+    pub metrics: BTreeMap<String, f64>,
+}
+
+/// One compared figure: `label  baseline -> current  (change)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delta {
+    pub label: String,
+    pub baseline: String,
+    pub current: String,
+    pub change: String,
+    /// Whether the change is an improvement, when that is known (shorter
+    /// elapsed, higher efficiency); `None` for arbitrary metrics
+    pub better: Option<bool>,
+}
+
+impl Baseline {
+    /// Deltas of `job` (with the metrics of its own output) against the baseline
+    pub fn compare(&self, job: &Job, metrics: &BTreeMap<String, f64>) -> Vec<Delta> {
+        let base = &self.job;
+        let mut deltas = Vec::new();
+
+        if !base.time.is_zero() || !job.time.is_zero() {
+            let (b, c) = (base.time.as_secs_f64(), job.time.as_secs_f64());
+            deltas.push(Delta {
+                label: "Elapsed".to_string(),
+                baseline: format_slurm_duration(base.time),
+                current: format_slurm_duration(job.time),
+                change: relative_change(b, c),
+                better: (b != c).then_some(c < b),
+            });
+        }
+        for (label, efficiency) in [
+            (
+                "CPU efficiency",
+                Job::cpu_efficiency as fn(&Job) -> Option<f64>,
+            ),
+            ("Memory efficiency", Job::memory_efficiency),
+        ] {
+            if let (Some(b), Some(c)) = (efficiency(base), efficiency(job)) {
+                deltas.push(Delta {
+                    label: label.to_string(),
+                    baseline: format!("{:.0}%", b * 100.0),
+                    current: format!("{:.0}%", c * 100.0),
+                    change: format!("{:+.0} pts", (c - b) * 100.0),
+                    better: ((c - b).abs() >= 0.005).then_some(c > b),
+                });
+            }
+        }
+        let shared = self
+            .metrics
+            .iter()
+            .filter_map(|(name, b)| Some((name, *b, *metrics.get(name)?)));
+        for (name, b, c) in shared.take(MAX_METRICS) {
+            deltas.push(Delta {
+                label: name.clone(),
+                baseline: format_number(b),
+                current: format_number(c),
+                change: relative_change(b, c),
+                better: None,
+            });
+        }
+        deltas
+    }
+}
+
+/// Numbers a job reports in its output as `name=value` or `name: value`
+/// (`loss=0.231`, `val_acc: 91.2`, `throughput=1520.5`); the last value of
+/// each name wins, since training logs print running values.
+pub fn extract_metrics(text: &str) -> BTreeMap<String, f64> {
+    let mut metrics = BTreeMap::new();
+    for line in text.lines() {
+        let tokens: Vec<&str> = line
+            .split(|c: char| c.is_whitespace() || c == ',' || c == ';' || c == '|')
+            .filter(|t| !t.is_empty())
+            .collect();
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = tokens[i];
+            let (name, value) = match token.split_once('=') {
+                Some((name, value)) => (name, value),
+                // `name:` followed by the value as the next token
+                None => match token.strip_suffix(':') {
+                    Some(name) if i + 1 < tokens.len() => {
+                        i += 1;
+                        (name, tokens[i])
+                    }
+                    _ => match token.split_once(':') {
+                        Some((name, value)) => (name, value),
+                        None => {
+                            i += 1;
+                            continue;
+                        }
+                    },
+                },
+            };
+            i += 1;
+            let value = value.trim_end_matches('%');
+            if let (true, Ok(value)) = (is_metric_name(name), value.parse::<f64>()) {
+                if value.is_finite() {
+                    metrics.insert(name.to_string(), value);
+                }
+            }
+        }
+    }
+    metrics
+}
+
+/// Metrics from the last part of a log file; empty if it cannot be read
+pub fn log_metrics(path: &Path) -> BTreeMap<String, f64> {
+    let Ok(mut file) = File::open(path) else {
+        return BTreeMap::new();
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    if file
+        .seek(SeekFrom::Start(len.saturating_sub(LOG_TAIL_BYTES)))
+        .is_err()
+    {
+        return BTreeMap::new();
+    }
+    let mut raw = Vec::new();
+    if file.read_to_end(&mut raw).is_err() {
+        return BTreeMap::new();
+    }
+    extract_metrics(&String::from_utf8_lossy(&raw))
+}
+
+fn is_metric_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'))
+}
+
+fn relative_change(baseline: f64, current: f64) -> String {
+    if baseline == 0.0 {
+        return if current == 0.0 { "=" } else { "new" }.to_string();
+    }
+    match (current - baseline) / baseline.abs() * 100.0 {
+        pct if pct.abs() < 0.05 => "=".to_string(),
+        pct => format!("{:+.1}%", pct),
+    }
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else if value.abs() >= 100.0 {
+        format!("{:.1}", value)
+    } else {
+        format!("{:.4}", value)
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn metrics_are_extracted_from_output() {
+        let log = "epoch 1 loss=0.90 acc: 0.51\n\
+                   Epoch 2/2 | loss=0.231, val_acc: 91.2% lr=3e-4\n\
+                   step 10: done at 12:30:01\n\
+                   2x=5 =3";
+        let metrics = extract_metrics(log);
+        assert_eq!(metrics["loss"], 0.231);
+        assert_eq!(metrics["acc"], 0.51);
+        assert_eq!(metrics["val_acc"], 91.2);
+        assert_eq!(metrics["lr"], 3e-4);
+        // Times, counters and bare numbers are not metrics
+        assert_eq!(metrics.len(), 4);
+    }
+
+    #[test]
+    fn deltas_against_the_baseline() {
+        let job = |secs, total_cpu| Job {
+            time: Duration::from_secs(secs),
+            total_cpu: Some(Duration::from_secs(total_cpu)),
+            cpus: 4,
+            ..Default::default()
+        };
+        let baseline = Baseline {
+            job: job(1000, 2000),
+            metrics: BTreeMap::from([("loss".to_string(), 0.5), ("acc".to_string(), 80.0)]),
+        };
+        let current = job(800, 2400);
+        let deltas = baseline.compare(&current, &BTreeMap::from([("loss".to_string(), 0.4)]));
+
+        assert_eq!(
+            deltas[0],
+            Delta {
+                label: "Elapsed".to_string(),
+                baseline: "16:40".to_string(),
+                current: "13:20".to_string(),
+                change: "-20.0%".to_string(),
+                better: Some(true),
+            }
+        );
+        assert_eq!(deltas[1].label, "CPU efficiency");
+        assert_eq!(
+            (deltas[1].baseline.as_str(), deltas[1].current.as_str()),
+            ("50%", "75%")
+        );
+        assert_eq!(deltas[1].change, "+25 pts");
+        // Only metrics both jobs report are compared
+        assert_eq!(deltas.len(), 3);
+        assert_eq!(deltas[2].change, "-20.0%");
+        assert_eq!(deltas[2].current, "0.4");
+    }
+}
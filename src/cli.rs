@@ -0,0 +1,1485 @@
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::{Report, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use crate::{
+    config::{config_file_path, load_config, ConfigOverrides},
+    notes::NoteStore,
+    notify::{notify_job_finished, JobFinished},
+    output::{jobs_json, render_record, OutputFormat, Records, EXPORT_FIELDS, LIST_FIELDS},
+    profile::{Profile, ProfileRoots},
+    protect::Protection,
+    schema::{check_config, config_help, config_template, filter_help},
+    serve::serve,
+    slurm::{
+        command::{self, execute_scancel, get_cluster_name},
+        merge_jobs_prefer_active,
+        mock::Fixture,
+        nodefail::node_failures,
+        sacct::{fetch_job_details, fetch_job_states, fetch_jobs, run_sacct, SacctOptions},
+        squeue::{query_job_states, run_squeue, SqueueOptions},
+        Job, JobState,
+    },
+    snapshot::{format_porcelain, load_snapshot, save_snapshot, Snapshot},
+    ui::columns::JobColumn,
+    utils::get_username,
+};
+
+/// Accounting fields of the ended jobs of `list`, `export` and `fixture`
+const JOB_SACCT_FIELDS: &[&str] = &[
+    "JobIDRaw",
+    "JobName",
+    "User",
+    "State",
+    "Elapsed",
+    "Timelimit",
+    "TotalCPU",
+    "NNodes",
+    "NodeList",
+    "AllocCPUS",
+    "ReqMem",
+    "Partition",
+    "QOS",
+    "Account",
+    "WorkDir",
+    "Submit",
+    "Start",
+    "End",
+    "ExitCode",
+    "DerivedExitCode",
+    "AllocTRES",
+];
+
+const USAGE: &str = "\
+Usage: slurmer [COMMAND]
+
+Without a command, starts the interactive TUI. `slurmer --demo` starts it on
+built-in sample jobs and `slurmer --fixture FILE` on the jobs of a fixture,
+without Slurm. `--export-json FILE` writes the jobs of the TUI's initial view
+(default filters and sort order) as JSON to FILE, or to stdout for `-`,
+instead of starting it.
+
+The TUI's initial view comes from [defaults] in config.toml; these options
+override it (and the theme) for one run:
+  --user me|*|USER   --recent-hours N   --partition P,...
+  --columns NAME,... (column titles, e.g. id,name,time left)
+  --theme default|light|solarized|mono
+
+`--record FILE` writes the raw output of every Slurm command the TUI runs
+to the tar archive FILE on exit (`--redact` replaces user names in it), and
+`--replay FILE` runs the TUI on such an archive instead of Slurm, to
+reproduce a parsing or display bug exactly.
+
+Failed Slurm commands are logged to ~/.local/state/slurmer/slurmer.log
+($XDG_STATE_HOME/slurmer). With --debug, which goes with any command,
+every command is logged with its arguments, duration, exit status and
+stderr, and so are sacct records that could not be read.
+
+Commands:
+  status [--porcelain] [--max-age SECS]
+        Print a job count summary for the current user. Uses the snapshot
+        cached by the TUI when it is younger than --max-age (default 120s),
+        otherwise queries squeue. --porcelain prints e.g. `R:3 PD:12 F:1`.
+  wait <ID>... [--all|--any] [--timeout DUR] [--interval DUR]
+        Block until the jobs reach a terminal state, printing each final
+        state. --all (default) waits for every job, --any returns when the
+        first one ends. Exits 0 if the awaited jobs completed, 1 if any of
+        them failed, was cancelled, timed out, ...
+  notify <ID> [--detach] [--interval DUR]
+        Watch a single job and fire the notifications configured under
+        [notify] in config.toml when it ends. --detach returns immediately
+        and keeps watching in the background.
+  serve [--port N] [--bind ADDR] [--interval DUR]
+        Serve a read-only HTML dashboard of your jobs (queue, state counts,
+        queue history) on http://ADDR:N/ (default 127.0.0.1:8080, refreshed
+        every 30s). Use --bind 0.0.0.0 to reach it from other machines.
+        Aggregate OpenMetrics are served on /metrics.
+  nodes [--days N]
+        Report how your jobs of the last N days (default 30) failed per
+        node (FAILED and NODE_FAIL), marking nodes that fail significantly
+        more often than the rest, with their failed job ids for the admins.
+  list [--all-users] [--hours N] [--output FMT]
+        List your active jobs (everyone's with --all-users), and with
+        --hours those that ended in the last N hours too.
+  show <ID> [--output FMT]
+        Print the accounting record of a job.
+  cancel <ID>... [--force]
+        Cancel jobs. Refuses when one of them is protected (tagged
+        `protected` or matching [protect] names) unless --force is given.
+  export [--hours N] [--output FMT]
+        Print every field of your active jobs and those ended in the last N
+        hours (default 24), as CSV by default.
+  fixture [--hours N]
+        Print your active jobs and those ended in the last N hours (default
+        24) as a JSON fixture for --fixture, e.g. to attach to a bug report.
+  profile export [FILE] | profile import FILE [--force]
+        Bundle your config.toml (with its filter presets) and job notes and
+        tags into one file, printed or written to FILE, or install such a
+        bundle, e.g. on a new cluster. Import keeps files that differ from
+        the bundle's unless --force is given.
+  config init [--force]
+        Write a config.toml with every key commented out at its default and
+        described. Keeps an existing file unless --force is given.
+  check-config
+        Check config.toml for syntax errors, mistyped values, unknown keys
+        and unknown job states, printing each with its line and column and
+        the closest valid name. Exits 3 when there is a problem.
+  help [filters|config]
+        Print this message, or the full reference of the job filters
+        (popup fields and preset keys) or of every config.toml key with
+        its default.
+
+FMT is json, csv, tsv or table (the default of list and show). CSV, TSV and
+table values are printed as Slurm prints them. JSON lists every parsed field of
+each job, with durations in seconds.
+
+Durations are seconds, or a number followed by s, m or h (e.g. 90, 5m, 2h).
+
+Exit codes (all commands):
+  0  success
+  1  one or more of the requested jobs ended unsuccessfully
+  2  Slurm is unavailable (command missing, controller error, ...)
+  3  invalid arguments or unparsable Slurm output
+  4  --timeout expired before the jobs finished
+";
+
+/// Process exit codes shared by all headless subcommands, so they compose in
+/// shell pipelines (`slurmer wait 123 && next_step.sh`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    Success = 0,
+    /// At least one of the jobs the command was about ended unsuccessfully
+    JobsFailed = 1,
+    /// A Slurm command could not be run or reported an error
+    SlurmUnavailable = 2,
+    /// Invalid arguments or output we could not parse
+    ParseError = 3,
+    /// A `--timeout` expired before the command's condition was met
+    TimedOut = 4,
+}
+
+impl From<ExitStatus> for ExitCode {
+    fn from(status: ExitStatus) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
+/// An error tagged with the exit status it should produce
+#[derive(Debug)]
+pub struct CliError {
+    pub status: ExitStatus,
+    pub error: Report,
+}
+
+impl CliError {
+    fn slurm(error: impl Into<Report>) -> Self {
+        Self {
+            status: ExitStatus::SlurmUnavailable,
+            error: error.into(),
+        }
+    }
+
+    fn parse(error: impl Into<Report>) -> Self {
+        Self {
+            status: ExitStatus::ParseError,
+            error: error.into(),
+        }
+    }
+}
+
+/// What the binary was asked to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Start the interactive TUI (default), on a fixture's jobs with
+    /// `--demo` or `--fixture`. With `export_json`, write the jobs of its
+    /// initial view there (`-` for stdout) instead.
+    Tui {
+        fixture: Option<JobFixture>,
+        export_json: Option<PathBuf>,
+        /// `--user`, `--partition`, ... over the config files
+        overrides: ConfigOverrides,
+        /// `--record` or `--replay` the raw output of Slurm commands
+        capture: Option<Capture>,
+    },
+    /// Print a job-count summary
+    Status { porcelain: bool, max_age: Duration },
+    /// Block until jobs finish
+    Wait {
+        job_ids: Vec<String>,
+        /// Return as soon as one job ends instead of waiting for all of them
+        any: bool,
+        timeout: Option<Duration>,
+        /// Upper bound of the polling interval
+        interval: Duration,
+    },
+    /// Watch one job and notify when it ends
+    Notify {
+        job_id: String,
+        detach: bool,
+        interval: Duration,
+    },
+    /// Serve the read-only web dashboard
+    Serve {
+        bind: String,
+        port: u16,
+        interval: Duration,
+    },
+    /// Report the user's job failures by node
+    Nodes { days: u32 },
+    /// Validate config.toml
+    CheckConfig,
+    /// Write a commented config.toml; an existing one only with `force`
+    ConfigInit { force: bool },
+    /// Write the user's profile to a file (stdout when `None`)
+    ProfileExport { path: Option<PathBuf> },
+    /// Install a profile; existing files are replaced only with `force`
+    ProfileImport { path: PathBuf, force: bool },
+    /// Record the user's jobs as a fixture
+    Fixture { hours: u32 },
+    /// List jobs, active ones and with `hours` those ended since
+    List {
+        /// Every user's jobs instead of the current user's
+        all_users: bool,
+        hours: Option<u32>,
+        output: OutputFormat,
+    },
+    /// Print the accounting record of one job
+    Show {
+        job_id: String,
+        output: OutputFormat,
+    },
+    /// Cancel jobs; protected ones only with `force`
+    Cancel { job_ids: Vec<String>, force: bool },
+    /// Print every field of the user's active and recently ended jobs
+    Export { hours: u32, output: OutputFormat },
+    /// Print usage, or the reference of one topic
+    Help { topic: Option<HelpTopic> },
+}
+
+/// Jobs the TUI shows in place of Slurm's
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobFixture {
+    /// The built-in demo jobs
+    Demo,
+    File(PathBuf),
+}
+
+/// What the TUI does with the raw output of Slurm commands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Capture {
+    /// Write it to an archive on exit, with user names replaced when
+    /// `redact` is set
+    Record { path: PathBuf, redact: bool },
+    /// Read it from an archive in place of Slurm
+    Replay(PathBuf),
+}
+
+/// Topics of `slurmer help <topic>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HelpTopic {
+    Filters,
+    Config,
+}
+
+/// Options followed by a value, which may itself read `--debug`
+const VALUE_OPTIONS: &[&str] = &[
+    "--user",
+    "--recent-hours",
+    "--partition",
+    "--columns",
+    "--theme",
+    "--fixture",
+    "--export-json",
+    "--record",
+    "--replay",
+    "--hours",
+    "--output",
+    "--max-age",
+    "--timeout",
+    "--interval",
+    "--port",
+    "--bind",
+    "--days",
+];
+
+/// Remove every standalone `--debug` flag from the arguments, leaving one
+/// that is the value of another option. Returns whether the flag was given.
+pub fn take_debug(args: &mut Vec<String>) -> bool {
+    let mut debug = false;
+    let mut kept: Vec<String> = Vec::with_capacity(args.len());
+    for arg in args.drain(..) {
+        let is_value = kept
+            .last()
+            .is_some_and(|prev| VALUE_OPTIONS.contains(&prev.as_str()));
+        if arg == "--debug" && !is_value {
+            debug = true;
+        } else {
+            kept.push(arg);
+        }
+    }
+    *args = kept;
+    debug
+}
+
+/// Parse command-line arguments (without the program name)
+pub fn parse_args(args: &[String]) -> Result<Command> {
+    let Some((first, rest)) = args.split_first() else {
+        return Ok(Command::Tui {
+            fixture: None,
+            export_json: None,
+            overrides: ConfigOverrides::default(),
+            capture: None,
+        });
+    };
+
+    match first.as_str() {
+        "--demo" | "--fixture" | "--export-json" | "--user" | "--recent-hours" | "--partition"
+        | "--columns" | "--theme" | "--record" | "--redact" | "--replay" => {
+            let mut fixture = None;
+            let mut export_json = None;
+            let mut record = None;
+            let mut redact = false;
+            let mut replay = None;
+            let mut overrides = ConfigOverrides::default();
+            let list = |value: &str| -> Vec<String> {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            };
+            let mut iter = args.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--user" => {
+                        let user = iter
+                            .next()
+                            .ok_or_else(|| eyre!("--user requires me, * or a user name"))?;
+                        overrides.user = Some(user.clone());
+                    }
+                    "--recent-hours" => overrides.recent_hours = Some(hours_arg(iter.next())?),
+                    "--partition" => {
+                        let partitions = iter
+                            .next()
+                            .ok_or_else(|| eyre!("--partition requires partitions"))?;
+                        overrides.partitions = Some(list(partitions));
+                    }
+                    "--columns" => {
+                        let columns = iter
+                            .next()
+                            .ok_or_else(|| eyre!("--columns requires column names"))?;
+                        let columns = list(columns);
+                        if let Some(unknown) =
+                            columns.iter().find(|c| JobColumn::from_name(c).is_none())
+                        {
+                            return Err(eyre!("unknown column: {}", unknown));
+                        }
+                        overrides.columns = Some(columns);
+                    }
+                    "--theme" => {
+                        let theme = iter.next().ok_or_else(|| {
+                            eyre!("--theme requires default, light, solarized or mono")
+                        })?;
+                        overrides.theme = Some(theme.parse()?);
+                    }
+                    "--demo" => fixture = Some(JobFixture::Demo),
+                    "--fixture" => {
+                        let path = iter
+                            .next()
+                            .ok_or_else(|| eyre!("--fixture requires a file"))?;
+                        fixture = Some(JobFixture::File(PathBuf::from(path)));
+                    }
+                    "--export-json" => {
+                        let path = iter
+                            .next()
+                            .ok_or_else(|| eyre!("--export-json requires a file or -"))?;
+                        export_json = Some(PathBuf::from(path));
+                    }
+                    "--record" => {
+                        let path = iter
+                            .next()
+                            .ok_or_else(|| eyre!("--record requires a file"))?;
+                        record = Some(PathBuf::from(path));
+                    }
+                    "--redact" => redact = true,
+                    "--replay" => {
+                        let path = iter
+                            .next()
+                            .ok_or_else(|| eyre!("--replay requires a file"))?;
+                        replay = Some(PathBuf::from(path));
+                    }
+                    other => return Err(eyre!("unknown option: {}", other)),
+                }
+            }
+            let capture = match (record, replay) {
+                (Some(_), Some(_)) => {
+                    return Err(eyre!("--record and --replay exclude each other"))
+                }
+                (Some(path), None) => Some(Capture::Record { path, redact }),
+                (None, Some(_)) if fixture.is_some() => {
+                    return Err(eyre!(
+                        "--replay cannot be combined with --demo or --fixture"
+                    ))
+                }
+                (None, Some(path)) => Some(Capture::Replay(path)),
+                (None, None) if redact => return Err(eyre!("--redact only goes with --record")),
+                (None, None) => None,
+            };
+            Ok(Command::Tui {
+                fixture,
+                export_json,
+                overrides,
+                capture,
+            })
+        }
+        "fixture" => {
+            let mut hours = 24;
+            let mut iter = rest.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--hours" => hours = hours_arg(iter.next())?,
+                    other => return Err(eyre!("unknown fixture option: {}", other)),
+                }
+            }
+            Ok(Command::Fixture { hours })
+        }
+        "list" => {
+            let mut all_users = false;
+            let mut hours = None;
+            let mut output = OutputFormat::Table;
+            let mut iter = rest.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--all-users" => all_users = true,
+                    "--hours" => hours = Some(hours_arg(iter.next())?),
+                    "--output" => output = output_arg(iter.next())?,
+                    other => return Err(eyre!("unknown list option: {}", other)),
+                }
+            }
+            Ok(Command::List {
+                all_users,
+                hours,
+                output,
+            })
+        }
+        "show" => {
+            let mut job_id = None;
+            let mut output = OutputFormat::Table;
+            let mut iter = rest.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--output" => output = output_arg(iter.next())?,
+                    id if is_job_id(id) && job_id.is_none() => job_id = Some(id.to_string()),
+                    other => return Err(eyre!("unknown show option or extra job id: {}", other)),
+                }
+            }
+            let job_id = job_id.ok_or_else(|| eyre!("show requires a job id"))?;
+            Ok(Command::Show { job_id, output })
+        }
+        "cancel" => {
+            let mut job_ids = Vec::new();
+            let mut force = false;
+            for arg in rest {
+                match arg.as_str() {
+                    "--force" => force = true,
+                    id if is_job_id(id) => job_ids.push(id.to_string()),
+                    other => return Err(eyre!("unknown cancel option or job id: {}", other)),
+                }
+            }
+            if job_ids.is_empty() {
+                return Err(eyre!("cancel requires at least one job id"));
+            }
+            Ok(Command::Cancel { job_ids, force })
+        }
+        "export" => {
+            let mut hours = 24;
+            let mut output = OutputFormat::Csv;
+            let mut iter = rest.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--hours" => hours = hours_arg(iter.next())?,
+                    "--output" => output = output_arg(iter.next())?,
+                    other => return Err(eyre!("unknown export option: {}", other)),
+                }
+            }
+            Ok(Command::Export { hours, output })
+        }
+        "status" => {
+            let mut porcelain = false;
+            let mut max_age = Duration::from_secs(120);
+            let mut iter = rest.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--porcelain" => porcelain = true,
+                    "--max-age" => max_age = duration_arg(arg, iter.next())?,
+                    other => return Err(eyre!("unknown status option: {}", other)),
+                }
+            }
+            Ok(Command::Status { porcelain, max_age })
+        }
+        "wait" => {
+            let mut job_ids = Vec::new();
+            let mut any = false;
+            let mut timeout = None;
+            let mut interval = Duration::from_secs(30);
+            let mut iter = rest.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--any" => any = true,
+                    "--all" => any = false,
+                    "--timeout" => timeout = Some(duration_arg(arg, iter.next())?),
+                    "--interval" => interval = duration_arg(arg, iter.next())?,
+                    id if is_job_id(id) => job_ids.push(id.to_string()),
+                    other => return Err(eyre!("unknown wait option or job id: {}", other)),
+                }
+            }
+            if job_ids.is_empty() {
+                return Err(eyre!("wait requires at least one job id"));
+            }
+            Ok(Command::Wait {
+                job_ids,
+                any,
+                timeout,
+                interval: interval.max(Duration::from_secs(1)),
+            })
+        }
+        "notify" => {
+            let mut job_id = None;
+            let mut detach = false;
+            let mut interval = Duration::from_secs(30);
+            let mut iter = rest.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--detach" => detach = true,
+                    "--interval" => interval = duration_arg(arg, iter.next())?,
+                    id if is_job_id(id) && job_id.is_none() => job_id = Some(id.to_string()),
+                    other => return Err(eyre!("unknown notify option or extra job id: {}", other)),
+                }
+            }
+            let job_id = job_id.ok_or_else(|| eyre!("notify requires a job id"))?;
+            Ok(Command::Notify {
+                job_id,
+                detach,
+                interval: interval.max(Duration::from_secs(1)),
+            })
+        }
+        "serve" => {
+            let mut bind = "127.0.0.1".to_string();
+            let mut port = 8080;
+            let mut interval = Duration::from_secs(30);
+            let mut iter = rest.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--port" => {
+                        let value = iter
+                            .next()
+                            .ok_or_else(|| eyre!("--port requires a value"))?;
+                        port = value
+                            .parse()
+                            .map_err(|_| eyre!("invalid --port value: {}", value))?;
+                    }
+                    "--bind" => {
+                        bind = iter
+                            .next()
+                            .ok_or_else(|| eyre!("--bind requires a value"))?
+                            .clone();
+                    }
+                    "--interval" => interval = duration_arg(arg, iter.next())?,
+                    other => return Err(eyre!("unknown serve option: {}", other)),
+                }
+            }
+            Ok(Command::Serve {
+                bind,
+                port,
+                interval: interval.max(Duration::from_secs(5)),
+            })
+        }
+        "nodes" => {
+            let mut days = 30;
+            let mut iter = rest.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--days" => {
+                        let value = iter
+                            .next()
+                            .ok_or_else(|| eyre!("--days requires a value"))?;
+                        days = value
+                            .parse()
+                            .ok()
+                            .filter(|&d| d > 0)
+                            .ok_or_else(|| eyre!("invalid --days value: {}", value))?;
+                    }
+                    other => return Err(eyre!("unknown nodes option: {}", other)),
+                }
+            }
+            Ok(Command::Nodes { days })
+        }
+        "profile" => match rest {
+            [action] if action == "export" => Ok(Command::ProfileExport { path: None }),
+            [action, path] if action == "export" => Ok(Command::ProfileExport {
+                path: Some(PathBuf::from(path)),
+            }),
+            [action, path, flags @ ..]
+                if action == "import" && flags.iter().all(|f| f == "--force") =>
+            {
+                Ok(Command::ProfileImport {
+                    path: PathBuf::from(path),
+                    force: !flags.is_empty(),
+                })
+            }
+            _ => Err(eyre!(
+                "usage: slurmer profile export [FILE] | import FILE [--force]"
+            )),
+        },
+        "config" => match rest {
+            [action, flags @ ..] if action == "init" && flags.iter().all(|f| f == "--force") => {
+                Ok(Command::ConfigInit {
+                    force: !flags.is_empty(),
+                })
+            }
+            _ => Err(eyre!("usage: slurmer config init [--force]")),
+        },
+        "check-config" => match rest.first() {
+            None => Ok(Command::CheckConfig),
+            Some(other) => Err(eyre!("unknown check-config option: {}", other)),
+        },
+        "help" | "-h" | "--help" => {
+            let topic = match rest.first().map(String::as_str) {
+                None => None,
+                Some("filters") => Some(HelpTopic::Filters),
+                Some("config") => Some(HelpTopic::Config),
+                Some(other) => {
+                    return Err(eyre!("unknown help topic: {} (filters, config)", other))
+                }
+            };
+            Ok(Command::Help { topic })
+        }
+        other => Err(eyre!("unknown command: {}\n\n{}", other, USAGE)),
+    }
+}
+
+/// Run a non-interactive command, reporting errors on stderr
+pub fn run(command: Command) -> ExitStatus {
+    if let Ok(config) = load_config() {
+        command::set_timeout(Duration::from_secs(config.refresh.command_timeout_secs));
+    }
+    let result = match command {
+        Command::Tui { .. } => unreachable!("the TUI is started by main"),
+        Command::CheckConfig => run_check_config(),
+        Command::ConfigInit { force } => run_config_init(force),
+        Command::ProfileExport { path } => run_profile_export(path.as_deref()),
+        Command::ProfileImport { path, force } => run_profile_import(&path, force),
+        Command::Fixture { hours } => run_fixture(hours),
+        Command::List {
+            all_users,
+            hours,
+            output,
+        } => run_list(all_users, hours, output),
+        Command::Show { job_id, output } => run_show(&job_id, output),
+        Command::Cancel { job_ids, force } => run_cancel(&job_ids, force),
+        Command::Export { hours, output } => run_export(hours, output),
+        Command::Help { topic } => {
+            match topic {
+                None => print!("{}", USAGE),
+                Some(HelpTopic::Filters) => print!("{}", filter_help()),
+                Some(HelpTopic::Config) => print!("{}", config_help()),
+            }
+            Ok(ExitStatus::Success)
+        }
+        Command::Status { porcelain, max_age } => run_status(porcelain, max_age),
+        Command::Wait {
+            job_ids,
+            any,
+            timeout,
+            interval,
+        } => wait_for_jobs(&job_ids, any, timeout, interval, |id, outcome| {
+            println!("{} {}", id, outcome.state());
+        }),
+        Command::Notify {
+            job_id,
+            detach,
+            interval,
+        } => run_notify(&job_id, detach, interval),
+        Command::Serve {
+            bind,
+            port,
+            interval,
+        } => serve(&bind, port, interval)
+            .map(|()| ExitStatus::Success)
+            .map_err(CliError::slurm),
+        Command::Nodes { days } => run_nodes(days),
+    };
+
+    result.unwrap_or_else(|e| {
+        eprintln!("slurmer: {}", e.error);
+        e.status
+    })
+}
+
+/// Active jobs of `user` (everyone's when `None`), and with `hours` those
+/// ended in the last `hours`
+fn fetch_user_jobs(user: Option<String>, hours: Option<u32>) -> Result<Vec<Job>, CliError> {
+    let squeue = SqueueOptions {
+        user: user.clone(),
+        ..Default::default()
+    };
+    let sacct = hours.map(|hours| SacctOptions {
+        user,
+        states: JobState::ended_states(),
+        partitions: Vec::new(),
+        qos: Vec::new(),
+        accounts: Vec::new(),
+        wckeys: Vec::new(),
+        name: None,
+        recent_hours: hours,
+        end_hours: 0,
+        format_fields: JOB_SACCT_FIELDS.to_vec(),
+        max_rows: None,
+        window_hours: None,
+    });
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(CliError::slurm)?;
+    let (active, ended) = runtime
+        .block_on(async {
+            let active = run_squeue(&squeue).await?;
+            let ended = match &sacct {
+                Some(sacct) => run_sacct(sacct).await?.jobs,
+                None => Vec::new(),
+            };
+            Ok::<_, Report>((active, ended))
+        })
+        .map_err(CliError::slurm)?;
+    Ok(merge_jobs_prefer_active(active, ended))
+}
+
+fn run_list(
+    all_users: bool,
+    hours: Option<u32>,
+    output: OutputFormat,
+) -> Result<ExitStatus, CliError> {
+    let user = (!all_users).then(get_username);
+    let mut fields = LIST_FIELDS.to_vec();
+    if all_users {
+        fields.insert(2, "user");
+    }
+    let jobs = fetch_user_jobs(user, hours)?;
+    match output {
+        OutputFormat::Json => print!("{}", jobs_json(&jobs)),
+        _ => print!("{}", Records::of_jobs(&jobs, &fields).render(output)),
+    }
+    Ok(ExitStatus::Success)
+}
+
+fn run_export(hours: u32, output: OutputFormat) -> Result<ExitStatus, CliError> {
+    let jobs = fetch_user_jobs(Some(get_username()), Some(hours))?;
+    match output {
+        OutputFormat::Json => print!("{}", jobs_json(&jobs)),
+        _ => print!("{}", Records::of_jobs(&jobs, EXPORT_FIELDS).render(output)),
+    }
+    Ok(ExitStatus::Success)
+}
+
+fn run_show(job_id: &str, output: OutputFormat) -> Result<ExitStatus, CliError> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(CliError::slurm)?;
+    let details = runtime
+        .block_on(async { fetch_job_details(job_id).await })
+        .map_err(CliError::slurm)?;
+    print!("{}", render_record(&details, output));
+    Ok(ExitStatus::Success)
+}
+
+/// Cancel the jobs, unless one of them is protected (by the `protected`
+/// note tag or a `[protect]` name pattern) and `force` was not given
+fn run_cancel(job_ids: &[String], force: bool) -> Result<ExitStatus, CliError> {
+    let config = load_config().map_err(CliError::parse)?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(CliError::slurm)?;
+
+    if !force {
+        let (protection, _) = Protection::new(&config.protect);
+        let cluster = match &config.notes.cluster {
+            Some(cluster) => cluster.clone(),
+            None => runtime
+                .block_on(async { get_cluster_name().await })
+                .unwrap_or_default(),
+        };
+        let notes = NoteStore::new(&config.notes, &cluster);
+        let jobs = runtime
+            .block_on(async { fetch_jobs(job_ids, &["JobIDRaw", "JobName"]).await })
+            .map_err(CliError::slurm)?;
+        let protected: Vec<String> = jobs
+            .iter()
+            .filter_map(|job| {
+                let reason = protection.reason(job, notes.load(&job.id).as_ref())?;
+                Some(format!("{} ({})", job.id, reason))
+            })
+            .collect();
+        if !protected.is_empty() {
+            return Err(CliError::parse(eyre!(
+                "not cancelling protected jobs {}; pass --force to cancel them",
+                protected.join(", ")
+            )));
+        }
+    }
+
+    runtime
+        .block_on(async { execute_scancel(job_ids.to_vec()).await })
+        .map_err(CliError::slurm)?;
+    println!("Cancelled {}", job_ids.join(" "));
+    Ok(ExitStatus::Success)
+}
+
+/// Print the user's active jobs and those ended in the last `hours` as a
+/// fixture for `slurmer --fixture`
+fn run_fixture(hours: u32) -> Result<ExitStatus, CliError> {
+    let user = get_username();
+    let fixture = Fixture {
+        user: Some(user.clone()),
+        jobs: fetch_user_jobs(Some(user), Some(hours))?,
+        ends: Vec::new(),
+    };
+    let json = serde_json::to_string_pretty(&fixture).map_err(CliError::parse)?;
+    println!("{}", json);
+    Ok(ExitStatus::Success)
+}
+
+/// Write the commented template of every key to the user's config.toml
+fn run_config_init(force: bool) -> Result<ExitStatus, CliError> {
+    let path = config_file_path().map_err(CliError::parse)?;
+    if path.exists() && !force {
+        return Err(CliError::parse(eyre!(
+            "{} exists; use --force to replace it",
+            path.display()
+        )));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .wrap_err_with(|| format!("failed creating {}", dir.display()))
+            .map_err(CliError::parse)?;
+    }
+    fs::write(&path, config_template())
+        .wrap_err_with(|| format!("failed writing {}", path.display()))
+        .map_err(CliError::parse)?;
+    println!("Wrote {}", path.display());
+    Ok(ExitStatus::Success)
+}
+
+fn run_profile_export(path: Option<&Path>) -> Result<ExitStatus, CliError> {
+    let roots = ProfileRoots::user().map_err(CliError::parse)?;
+    let profile = Profile::collect(&roots).map_err(CliError::parse)?;
+    let raw = profile.to_toml().map_err(CliError::parse)?;
+    match path {
+        Some(path) => {
+            fs::write(path, raw)
+                .wrap_err_with(|| format!("failed writing {}", path.display()))
+                .map_err(CliError::parse)?;
+            println!("Wrote {} files to {}", profile.files.len(), path.display());
+        }
+        None => print!("{}", raw),
+    }
+    Ok(ExitStatus::Success)
+}
+
+fn run_profile_import(path: &Path, force: bool) -> Result<ExitStatus, CliError> {
+    let raw = fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed reading {}", path.display()))
+        .map_err(CliError::parse)?;
+    let profile = Profile::parse(&raw).map_err(CliError::parse)?;
+    let roots = ProfileRoots::user().map_err(CliError::parse)?;
+    let report = profile.import(&roots, force).map_err(CliError::parse)?;
+    for key in &report.written {
+        println!("wrote {}", key);
+    }
+    for key in &report.kept {
+        println!("kept {} (differs; use --force to replace it)", key);
+    }
+    // The profile may come from a newer or older slurmer
+    let (config_path, problems) = check_config().map_err(CliError::parse)?;
+    for problem in &problems {
+        eprintln!("slurmer: {}:{}", config_path.display(), problem);
+    }
+    Ok(ExitStatus::Success)
+}
+
+/// Print every problem of config.toml; fails when there is one
+fn run_check_config() -> Result<ExitStatus, CliError> {
+    let (path, problems) = check_config().map_err(CliError::parse)?;
+    for problem in &problems {
+        println!("{}:{}", path.display(), problem);
+    }
+    if problems.is_empty() {
+        println!("{}: ok", path.display());
+        Ok(ExitStatus::Success)
+    } else {
+        Ok(ExitStatus::ParseError)
+    }
+}
+
+fn run_nodes(days: u32) -> Result<ExitStatus, CliError> {
+    let refresh = load_config().map_err(CliError::parse)?.refresh;
+    let user = get_username();
+    let options = SacctOptions {
+        user: Some(user.clone()),
+        states: JobState::ended_states(),
+        partitions: Vec::new(),
+        qos: Vec::new(),
+        accounts: Vec::new(),
+        wckeys: Vec::new(),
+        name: None,
+        recent_hours: days * 24,
+        end_hours: 0,
+        format_fields: vec!["JobIDRaw", "State", "NodeList"],
+        max_rows: None,
+        window_hours: Some(refresh.sacct_window_hours).filter(|&h| h > 0),
+    };
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(CliError::slurm)?;
+    let rows = runtime
+        .block_on(async { run_sacct(&options).await })
+        .map_err(CliError::slurm)?;
+    let report = node_failures(&rows.jobs);
+
+    println!(
+        "Failures of {}'s jobs by node, last {} days: {} of {} ended jobs failed ({:.1}% per node)",
+        user,
+        days,
+        report.failed,
+        report.jobs,
+        report.rate * 100.0
+    );
+    if report.nodes.is_empty() {
+        println!("No failed jobs");
+        return Ok(ExitStatus::Success);
+    }
+    println!();
+    println!(
+        "{:<16} {:>6} {:>7} {:>10} {:>7} {:>9}",
+        "NODE", "JOBS", "FAILED", "NODE_FAIL", "RATE", "P-VALUE"
+    );
+    for node in &report.nodes {
+        println!(
+            "{:<16} {:>6} {:>7} {:>10} {:>6.1}% {:>9.1e}{}",
+            node.node,
+            node.jobs,
+            node.failed,
+            node.node_failed,
+            node.rate() * 100.0,
+            node.p_value,
+            if node.elevated { "  elevated" } else { "" }
+        );
+    }
+
+    let elevated: Vec<_> = report.nodes.iter().filter(|n| n.elevated).collect();
+    println!();
+    if elevated.is_empty() {
+        println!("No node fails significantly more often than the others.");
+        return Ok(ExitStatus::Success);
+    }
+    println!(
+        "Elevated: more failures than a {:.1}% rate explains (one-sided binomial test, \
+         p < 0.05 over {} nodes). Failed jobs:",
+        report.rate * 100.0,
+        report.nodes_seen
+    );
+    for node in elevated {
+        println!("  {}: {}", node.node, node.failed_ids.join(" "));
+    }
+    Ok(ExitStatus::Success)
+}
+
+fn run_status(porcelain: bool, max_age: Duration) -> Result<ExitStatus, CliError> {
+    // A stale or unreadable snapshot is not an error; just fetch fresh data.
+    let snapshot = match load_snapshot().ok().flatten() {
+        Some(s) if s.age() <= max_age => s,
+        _ => {
+            let options = SqueueOptions {
+                user: Some(get_username()),
+                ..Default::default()
+            };
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(CliError::slurm)?;
+            let jobs = runtime
+                .block_on(async { run_squeue(&options).await })
+                .map_err(CliError::slurm)?;
+            let snapshot = Snapshot::from_jobs(&jobs);
+            let _ = save_snapshot(&snapshot);
+            snapshot
+        }
+    };
+
+    let counts = snapshot.state_counts();
+    if porcelain {
+        println!("{}", format_porcelain(&counts));
+    } else if counts.is_empty() {
+        println!("No jobs");
+    } else {
+        let summary = counts
+            .iter()
+            .map(|(state, count)| format!("{} {}", count, state.to_string().to_lowercase()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("{} (as of {}s ago)", summary, snapshot.age().as_secs());
+    }
+    Ok(ExitStatus::Success)
+}
+
+/// Where a job stands from the point of view of `wait`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Outcome {
+    Active,
+    Succeeded(String),
+    Failed(String),
+}
+
+impl Outcome {
+    fn state(&self) -> &str {
+        match self {
+            Outcome::Active => "ACTIVE",
+            Outcome::Succeeded(state) | Outcome::Failed(state) => state,
+        }
+    }
+}
+
+/// States in which a job may still change; anything else is final.
+const ACTIVE_STATES: &[&str] = &[
+    "PENDING",
+    "RUNNING",
+    "CONFIGURING",
+    "COMPLETING",
+    "SUSPENDED",
+    "REQUEUED",
+    "REQUEUE_FED",
+    "REQUEUE_HOLD",
+    "RESIZING",
+    "SIGNALING",
+    "STAGE_OUT",
+    "STOPPED",
+];
+
+/// Combine the rows belonging to `job_id` (the job itself or, for arrays, its
+/// `<id>_<task>` rows) into one outcome. `None` if no row matches.
+fn job_outcome(job_id: &str, rows: &HashMap<String, String>) -> Option<Outcome> {
+    let array_prefix = format!("{}_", job_id);
+    let mut states: Vec<String> = rows
+        .iter()
+        .filter(|(id, _)| *id == job_id || id.starts_with(&array_prefix))
+        .map(|(_, state)| {
+            // sacct prints e.g. "CANCELLED by 1234"
+            state
+                .split_whitespace()
+                .next()
+                .unwrap_or("")
+                .trim_end_matches('+')
+                .to_uppercase()
+        })
+        .collect();
+    if states.is_empty() {
+        return None;
+    }
+    states.sort();
+    states.dedup();
+
+    if states.iter().any(|s| ACTIVE_STATES.contains(&s.as_str())) {
+        Some(Outcome::Active)
+    } else if states.iter().all(|s| s == "COMPLETED") {
+        Some(Outcome::Succeeded(states.join(",")))
+    } else {
+        Some(Outcome::Failed(states.join(",")))
+    }
+}
+
+/// Poll until the jobs end (all of them, or the first one with `any`), calling
+/// `on_finished` as each one reaches a terminal state.
+fn wait_for_jobs(
+    job_ids: &[String],
+    any: bool,
+    timeout: Option<Duration>,
+    max_interval: Duration,
+    mut on_finished: impl FnMut(&str, &Outcome),
+) -> Result<ExitStatus, CliError> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(CliError::slurm)?;
+
+    let started = Instant::now();
+    let mut waiting: Vec<String> = job_ids.to_vec();
+    let mut any_failed = false;
+    // Poll quickly at first (jobs that fail on startup), then back off.
+    let mut interval = Duration::from_secs(2).min(max_interval);
+
+    loop {
+        // One squeue call for everything still waiting; only jobs that have
+        // left the queue are looked up in accounting.
+        let live = runtime
+            .block_on(async { query_job_states(&waiting).await })
+            .map_err(CliError::slurm)?;
+        let gone: Vec<String> = waiting
+            .iter()
+            .filter(|id| job_outcome(id, &live).is_none())
+            .cloned()
+            .collect();
+        let accounted = if gone.is_empty() {
+            HashMap::new()
+        } else {
+            runtime
+                .block_on(async { fetch_job_states(&gone).await })
+                .map_err(CliError::slurm)?
+        };
+
+        let mut finished = false;
+        for id in &gone {
+            match job_outcome(id, &accounted) {
+                // Accounting can lag slightly behind the queue
+                Some(Outcome::Active) => continue,
+                Some(outcome) => {
+                    any_failed |= matches!(outcome, Outcome::Failed(_));
+                    on_finished(id, &outcome);
+                }
+                None => return Err(CliError::parse(eyre!("unknown job id: {}", id))),
+            }
+            waiting.retain(|w| w != id);
+            finished = true;
+        }
+
+        if waiting.is_empty() || (any && finished) {
+            return Ok(if any_failed {
+                ExitStatus::JobsFailed
+            } else {
+                ExitStatus::Success
+            });
+        }
+
+        let mut sleep_for = interval;
+        if let Some(timeout) = timeout {
+            let elapsed = started.elapsed();
+            if elapsed >= timeout {
+                eprintln!("slurmer: timed out waiting for {}", waiting.join(", "));
+                return Ok(ExitStatus::TimedOut);
+            }
+            sleep_for = sleep_for.min(timeout - elapsed);
+        }
+        std::thread::sleep(sleep_for);
+        interval = (interval * 2).min(max_interval);
+    }
+}
+
+fn run_notify(job_id: &str, detach: bool, interval: Duration) -> Result<ExitStatus, CliError> {
+    let config = load_config().map_err(CliError::parse)?.notify;
+
+    if detach {
+        // Re-run ourselves without --detach, detached from the terminal
+        let exe = std::env::current_exe().map_err(CliError::slurm)?;
+        let mut watcher = std::process::Command::new(exe);
+        watcher
+            .args(["notify", job_id, "--interval"])
+            .arg(format!("{}", interval.as_secs()))
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            // Own process group, so closing the terminal does not take it down
+            watcher.process_group(0);
+        }
+        let pid = command::spawn_detached(watcher).map_err(CliError::slurm)?;
+        println!("Watching job {} in the background (pid {})", job_id, pid);
+        return Ok(ExitStatus::Success);
+    }
+
+    wait_for_jobs(
+        &[job_id.to_string()],
+        false,
+        None,
+        interval,
+        |id, outcome| {
+            println!("{} {}", id, outcome.state());
+            let event = JobFinished {
+                job_id: id.to_string(),
+                state: outcome.state().to_string(),
+                ok: matches!(outcome, Outcome::Succeeded(_)),
+            };
+            for e in notify_job_finished(&config, &event) {
+                eprintln!("slurmer: {}", e);
+            }
+        },
+    )
+}
+
+/// A plain job id, array job id or array task id (`123`, `123_4`)
+fn is_job_id(s: &str) -> bool {
+    !s.is_empty()
+        && s.starts_with(|c: char| c.is_ascii_digit())
+        && s.chars().all(|c| c.is_ascii_digit() || c == '_')
+}
+
+/// Parse the value of `--hours`, a positive number of hours
+fn hours_arg(value: Option<&String>) -> Result<u32> {
+    let value = value.ok_or_else(|| eyre!("--hours requires a value"))?;
+    value
+        .parse()
+        .ok()
+        .filter(|&h| h > 0)
+        .ok_or_else(|| eyre!("invalid --hours value: {}", value))
+}
+
+fn output_arg(value: Option<&String>) -> Result<OutputFormat> {
+    value
+        .ok_or_else(|| eyre!("--output requires a value"))?
+        .parse()
+}
+
+/// Parse the value of a duration option: seconds, or a number with an s/m/h suffix
+fn duration_arg(option: &str, value: Option<&String>) -> Result<Duration> {
+    let value = value.ok_or_else(|| eyre!("{} requires a value", option))?;
+    let (number, unit) = match value.char_indices().last() {
+        Some((i, 's')) => (&value[..i], 1),
+        Some((i, 'm')) => (&value[..i], 60),
+        Some((i, 'h')) => (&value[..i], 3600),
+        _ => (value.as_str(), 1),
+    };
+    let n = number
+        .parse::<u64>()
+        .map_err(|_| eyre!("invalid {} value: {}", option, value))?;
+    Ok(Duration::from_secs(n * unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Theme;
+
+    fn args(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn takes_the_debug_flag_but_not_an_option_value() {
+        let mut given = args(&["list", "--debug", "--hours", "6"]);
+        assert!(take_debug(&mut given));
+        assert_eq!(given, args(&["list", "--hours", "6"]));
+
+        let mut given = args(&["--user", "--debug", "--debug"]);
+        assert!(take_debug(&mut given));
+        assert_eq!(given, args(&["--user", "--debug"]));
+
+        let mut given = args(&["--record", "--debug"]);
+        assert!(!take_debug(&mut given));
+        assert_eq!(given, args(&["--record", "--debug"]));
+    }
+
+    #[test]
+    fn parses_status_options() {
+        assert_eq!(
+            parse_args(&[]).unwrap(),
+            Command::Tui {
+                fixture: None,
+                export_json: None,
+                overrides: ConfigOverrides::default(),
+                capture: None,
+            }
+        );
+        assert_eq!(
+            parse_args(&args(&["--fixture", "jobs.json", "--export-json", "-"])).unwrap(),
+            Command::Tui {
+                fixture: Some(JobFixture::File(PathBuf::from("jobs.json"))),
+                export_json: Some(PathBuf::from("-")),
+                overrides: ConfigOverrides::default(),
+                capture: None,
+            }
+        );
+        assert!(parse_args(&args(&["--export-json"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["--record", "session.tar", "--redact"])).unwrap(),
+            Command::Tui {
+                fixture: None,
+                export_json: None,
+                overrides: ConfigOverrides::default(),
+                capture: Some(Capture::Record {
+                    path: PathBuf::from("session.tar"),
+                    redact: true
+                }),
+            }
+        );
+        assert!(parse_args(&args(&["--replay", "session.tar", "--demo"])).is_err());
+        assert!(parse_args(&args(&["--redact"])).is_err());
+        assert_eq!(
+            parse_args(&args(&[
+                "--user",
+                "*",
+                "--partition",
+                "gpu,cpu",
+                "--columns",
+                "id,time left",
+                "--theme",
+                "mono",
+                "--demo"
+            ]))
+            .unwrap(),
+            Command::Tui {
+                fixture: Some(JobFixture::Demo),
+                export_json: None,
+                overrides: ConfigOverrides {
+                    user: Some("*".to_string()),
+                    partitions: Some(vec!["gpu".to_string(), "cpu".to_string()]),
+                    columns: Some(vec!["id".to_string(), "time left".to_string()]),
+                    theme: Some(Theme::Mono),
+                    ..Default::default()
+                },
+                capture: None,
+            }
+        );
+        assert!(parse_args(&args(&["--columns", "id,colour"])).is_err());
+        assert!(parse_args(&args(&["--theme", "neon"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["config", "init", "--force"])).unwrap(),
+            Command::ConfigInit { force: true }
+        );
+        assert_eq!(
+            parse_args(&args(&["fixture", "--hours", "48"])).unwrap(),
+            Command::Fixture { hours: 48 }
+        );
+        assert_eq!(
+            parse_args(&args(&["status", "--porcelain", "--max-age", "30"])).unwrap(),
+            Command::Status {
+                porcelain: true,
+                max_age: Duration::from_secs(30)
+            }
+        );
+        assert!(parse_args(&args(&["status", "--bogus"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["nodes", "--days", "7"])).unwrap(),
+            Command::Nodes { days: 7 }
+        );
+        assert!(parse_args(&args(&["nodes", "--days", "0"])).is_err());
+        assert!(parse_args(&args(&["frobnicate"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["help", "filters"])).unwrap(),
+            Command::Help {
+                topic: Some(HelpTopic::Filters)
+            }
+        );
+        assert!(parse_args(&args(&["help", "columns"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["profile", "import", "team.toml", "--force"])).unwrap(),
+            Command::ProfileImport {
+                path: PathBuf::from("team.toml"),
+                force: true
+            }
+        );
+        assert!(parse_args(&args(&["profile", "import"])).is_err());
+    }
+
+    #[test]
+    fn exit_codes_are_stable() {
+        assert_eq!(ExitStatus::Success as u8, 0);
+        assert_eq!(ExitStatus::JobsFailed as u8, 1);
+        assert_eq!(ExitStatus::SlurmUnavailable as u8, 2);
+        assert_eq!(ExitStatus::ParseError as u8, 3);
+        assert_eq!(ExitStatus::TimedOut as u8, 4);
+    }
+
+    #[test]
+    fn parses_wait_options() {
+        assert_eq!(
+            parse_args(&args(&["wait", "12", "13_2", "--any", "--timeout", "5m"])).unwrap(),
+            Command::Wait {
+                job_ids: vec!["12".into(), "13_2".into()],
+                any: true,
+                timeout: Some(Duration::from_secs(300)),
+                interval: Duration::from_secs(30),
+            }
+        );
+        assert!(parse_args(&args(&["wait"])).is_err());
+        assert!(parse_args(&args(&["wait", "12", "--timeout", "soon"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["notify", "12", "--detach"])).unwrap(),
+            Command::Notify {
+                job_id: "12".into(),
+                detach: true,
+                interval: Duration::from_secs(30),
+            }
+        );
+        assert!(parse_args(&args(&["notify", "12", "13"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["serve", "--port", "9000", "--bind", "0.0.0.0"])).unwrap(),
+            Command::Serve {
+                bind: "0.0.0.0".into(),
+                port: 9000,
+                interval: Duration::from_secs(30),
+            }
+        );
+        assert!(parse_args(&args(&["serve", "--port", "http"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["list", "--hours", "6", "--output", "json"])).unwrap(),
+            Command::List {
+                all_users: false,
+                hours: Some(6),
+                output: OutputFormat::Json,
+            }
+        );
+        assert!(parse_args(&args(&["list", "--output", "yaml"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["cancel", "12", "13_2", "--force"])).unwrap(),
+            Command::Cancel {
+                job_ids: vec!["12".into(), "13_2".into()],
+                force: true,
+            }
+        );
+        assert!(parse_args(&args(&["cancel"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["export"])).unwrap(),
+            Command::Export {
+                hours: 24,
+                output: OutputFormat::Csv,
+            }
+        );
+    }
+
+    #[test]
+    fn job_outcome_combines_array_tasks() {
+        let rows: HashMap<String, String> = [
+            ("7_1", "COMPLETED"),
+            ("7_2", "RUNNING"),
+            ("8", "CANCELLED by 1000"),
+            ("9_1", "COMPLETED"),
+            ("9_2", "OUT_OF_MEMORY"),
+            ("10", "COMPLETED"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        assert_eq!(job_outcome("7", &rows), Some(Outcome::Active));
+        assert_eq!(
+            job_outcome("8", &rows),
+            Some(Outcome::Failed("CANCELLED".into()))
+        );
+        assert_eq!(
+            job_outcome("9", &rows),
+            Some(Outcome::Failed("COMPLETED,OUT_OF_MEMORY".into()))
+        );
+        assert_eq!(
+            job_outcome("10", &rows),
+            Some(Outcome::Succeeded("COMPLETED".into()))
+        );
+        assert_eq!(job_outcome("1", &rows), None);
+    }
+}
@@ -0,0 +1,118 @@
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::Result;
+use std::env;
+use std::io::Write;
+use std::process::Command;
+
+use crate::slurm::command;
+
+/// How text reached the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Via {
+    /// A clipboard tool of the local desktop (`wl-copy`, `xclip`, ...)
+    Tool(&'static str),
+    /// The terminal's OSC 52 sequence
+    Terminal,
+}
+
+/// Copy `text` to the clipboard. Over SSH the desktop's clipboard is the
+/// terminal's, so OSC 52 is used; locally a clipboard tool is tried first,
+/// as not every terminal honours OSC 52.
+pub fn copy(text: &str) -> Result<Via> {
+    if env::var_os("SSH_CONNECTION").is_none() && env::var_os("SSH_TTY").is_none() {
+        for (program, args) in clipboard_tools() {
+            if pipe_to(program, args, text).is_ok() {
+                return Ok(Via::Tool(program));
+            }
+        }
+    }
+    copy_osc52(text)?;
+    Ok(Via::Terminal)
+}
+
+/// Clipboard tools of the local session, most specific first
+fn clipboard_tools() -> Vec<(&'static str, &'static [&'static str])> {
+    let mut tools: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+    if cfg!(target_os = "macos") {
+        tools.push(("pbcopy", &[]));
+    }
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        tools.push(("wl-copy", &[]));
+    }
+    if env::var_os("DISPLAY").is_some() {
+        tools.push(("xclip", &["-selection", "clipboard"]));
+        tools.push(("xsel", &["--clipboard", "--input"]));
+    }
+    tools
+}
+
+fn pipe_to(program: &str, args: &[&str], text: &str) -> Result<()> {
+    let mut tool = Command::new(program);
+    tool.args(args);
+    let status = command::run_local(tool, Some(text.as_bytes()))
+        .wrap_err_with(|| format!("failed running {}", program))?
+        .status;
+    if !status.success() {
+        return Err(eyre!("{} exited with {}", program, status));
+    }
+    Ok(())
+}
+
+/// Write the OSC 52 clipboard sequence, which also works over SSH (the
+/// usual way of reaching a login node)
+fn copy_osc52(text: &str) -> Result<()> {
+    let sequence = osc52(text, env::var_os("TMUX").is_some());
+    let mut stdout = std::io::stdout();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// The OSC 52 sequence setting the clipboard to `text`. Inside tmux it is
+/// wrapped in a DCS passthrough (with its escapes doubled), which tmux hands
+/// to the outer terminal.
+fn osc52(text: &str, tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()));
+    if tmux {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+/// Standard base64 (with padding), for the OSC 52 clipboard sequence
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_matches_reference() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(osc52("fo", false), "\x1b]52;c;Zm8=\x07");
+        assert_eq!(osc52("fo", true), "\x1bPtmux;\x1b\x1b]52;c;Zm8=\x07\x1b\\");
+    }
+}
@@ -1,25 +1,670 @@
-use color_eyre::eyre::WrapErr;
-use color_eyre::Result;
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::{Report, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::keymap::KeyBinding;
+use crate::slurm::{squeue::SqueueOptions, JobState};
+use crate::utils::{parse_zone, TimeDisplay};
 
 const ENV_SLURM_LOGS_DIR: &str = "SLURMER_SLURM_LOGS_DIR";
 
+/// Site-wide config, read before (and overridden by) the user's
+const SYSTEM_CONFIG_PATH: &str = "/etc/slurmer/config.toml";
+/// Overrides `SYSTEM_CONFIG_PATH`, e.g. for installs under a prefix
+const ENV_SYSTEM_CONFIG: &str = "SLURMER_SYSTEM_CONFIG";
+
+/// Command-line overrides of this process, applied over both files
+static OVERRIDES: OnceLock<ConfigOverrides> = OnceLock::new();
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SlurmerConfig {
     pub slurm_logs_dir: Option<String>,
+    /// Mouse clicks and scrolling in the TUI; off when unset, which leaves
+    /// the mouse to the terminal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mouse: Option<bool>,
+    /// Named filter presets, selectable with the number keys (in order).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub presets: Vec<FilterPreset>,
+    /// How finished jobs are announced.
+    #[serde(default, skip_serializing_if = "NotifyConfig::is_default")]
+    pub notify: NotifyConfig,
+    /// Site-specific remediation hints, keyed by state or pending reason
+    /// (replace the built-in text; an empty string hides the hint).
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub hints: HashMap<String, String>,
+    /// Where job notes are kept.
+    #[serde(default, skip_serializing_if = "NotesConfig::is_default")]
+    pub notes: NotesConfig,
+    /// Metrics export for Prometheus.
+    #[serde(default, skip_serializing_if = "MetricsConfig::is_default")]
+    pub metrics: MetricsConfig,
+    /// Cluster operator actions.
+    #[serde(default, skip_serializing_if = "AdminConfig::is_default")]
+    pub admin: AdminConfig,
+    /// Programs the work dir and logs of a job are opened with.
+    #[serde(default, skip_serializing_if = "OpenConfig::is_default")]
+    pub open: OpenConfig,
+    /// Shell commands run on a job from the action menu.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<CustomAction>,
+    /// Jobs that need a typed confirmation before they are cancelled or requeued.
+    #[serde(default, skip_serializing_if = "ProtectConfig::is_default")]
+    pub protect: ProtectConfig,
+    /// How often the job list is polled.
+    #[serde(default, skip_serializing_if = "RefreshConfig::is_default")]
+    pub refresh: RefreshConfig,
+    /// Colors of the TUI.
+    #[serde(default, skip_serializing_if = "Theme::is_default")]
+    pub theme: Theme,
+    /// Colors of UI elements, over the theme's.
+    #[serde(default, skip_serializing_if = "ColorsConfig::is_default")]
+    pub colors: ColorsConfig,
+    /// What the TUI lists when it starts.
+    #[serde(default, skip_serializing_if = "DefaultsConfig::is_default")]
+    pub defaults: DefaultsConfig,
+    /// How the views fit narrow terminals.
+    #[serde(default, skip_serializing_if = "LayoutConfig::is_default")]
+    pub layout: LayoutConfig,
+    /// How job times are shown.
+    #[serde(default, skip_serializing_if = "TimeConfig::is_default")]
+    pub time: TimeConfig,
+    /// Keys of the job list's and detail pane's actions, by action name
+    /// (replace the built-in keys of those actions).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, KeyBinding>,
+}
+
+impl SlurmerConfig {
+    pub fn mouse_enabled(&self) -> bool {
+        self.mouse == Some(true)
+    }
+}
+
+/// Colors of the TUI; `NO_COLOR` in the environment also selects `mono`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// The terminal's own palette, for dark backgrounds
+    #[default]
+    #[serde(alias = "dark")]
+    Default,
+    /// Darker text and state colors, for light backgrounds
+    Light,
+    /// Solarized dark
+    Solarized,
+    /// No colors, only bold, reversed, ... text
+    Mono,
+}
+
+impl Theme {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl FromStr for Theme {
+    type Err = Report;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "default" | "dark" => Ok(Theme::Default),
+            "light" => Ok(Theme::Light),
+            "solarized" => Ok(Theme::Solarized),
+            "mono" => Ok(Theme::Mono),
+            other => Err(eyre!(
+                "invalid theme: {} (default, dark, light, solarized, mono)",
+                other
+            )),
+        }
+    }
+}
+
+/// Colors of UI elements over the theme's: a name (`yellow`, `lightblue`),
+/// a 256-color index (`214`) or `#rrggbb`. Unset elements keep the theme's.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorsConfig {
+    pub pending: Option<String>,
+    pub running: Option<String>,
+    pub completed: Option<String>,
+    pub failed: Option<String>,
+    pub cancelled: Option<String>,
+    pub border: Option<String>,
+    pub text: Option<String>,
+    pub dim: Option<String>,
+    pub header: Option<String>,
+    pub background: Option<String>,
+    pub selection: Option<String>,
+}
+
+impl ColorsConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The TUI's initial view.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DefaultsConfig {
+    /// `"me"` for the current user, `"*"` for all users, or a user name.
+    pub user: String,
+    /// Hours ended jobs are listed for.
+    pub recent_hours: u32,
+    /// Partitions to list; empty lists every partition.
+    pub partitions: Vec<String>,
+    /// Columns of the job list by title (`id`, `name`, `time left`, ...);
+    /// empty keeps the built-in ones.
+    pub columns: Vec<String>,
+    /// Start where the last session was left (filters, columns, sort,
+    /// cursor and detail tab) rather than on this view.
+    pub restore_session: bool,
+}
+
+impl Default for DefaultsConfig {
+    fn default() -> Self {
+        Self {
+            user: "me".to_string(),
+            recent_hours: 24,
+            partitions: Vec::new(),
+            columns: Vec::new(),
+            restore_session: true,
+        }
+    }
+}
+
+impl DefaultsConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The initial view as a preset, for [`FilterPreset::apply_to`]
+    pub fn view(&self) -> FilterPreset {
+        FilterPreset {
+            user: Some(self.user.clone()),
+            partitions: self.partitions.clone(),
+            recent_hours: Some(self.recent_hours),
+            ..Default::default()
+        }
+    }
+}
+
+/// How the views fit narrow terminals (tmux panes, split windows).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    /// Terminal width below which the detail pane is stacked under the job
+    /// list rather than beside it (0: always beside).
+    pub stack_below: u16,
+    /// Column sets of the job list for terminals narrower than their
+    /// `below`; the narrowest that applies wins.
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+/// Columns of the job list below a terminal width
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Breakpoint {
+    pub below: u16,
+    /// Columns by title, as in `[defaults] columns`.
+    pub columns: Vec<String>,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            stack_below: 120,
+            breakpoints: vec![Breakpoint {
+                below: 100,
+                columns: ["id", "name", "state", "time left"]
+                    .map(str::to_string)
+                    .to_vec(),
+            }],
+        }
+    }
+}
+
+impl LayoutConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The column titles for a terminal `width` wide, if a breakpoint
+    /// applies
+    pub fn columns_for(&self, width: u16) -> Option<&[String]> {
+        self.breakpoints
+            .iter()
+            .filter(|b| width < b.below)
+            .min_by_key(|b| b.below)
+            .map(|b| b.columns.as_slice())
+    }
+}
+
+/// Settings given on the command line, which win over both config files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigOverrides {
+    pub user: Option<String>,
+    pub recent_hours: Option<u32>,
+    pub partitions: Option<Vec<String>>,
+    pub columns: Option<Vec<String>>,
+    pub theme: Option<Theme>,
+}
+
+impl ConfigOverrides {
+    pub fn apply(&self, cfg: &mut SlurmerConfig) {
+        let defaults = &mut cfg.defaults;
+        if let Some(user) = &self.user {
+            defaults.user = user.clone();
+        }
+        if let Some(hours) = self.recent_hours {
+            defaults.recent_hours = hours;
+        }
+        if let Some(partitions) = &self.partitions {
+            defaults.partitions = partitions.clone();
+        }
+        if let Some(columns) = &self.columns {
+            defaults.columns = columns.clone();
+        }
+        if let Some(theme) = self.theme {
+            cfg.theme = theme;
+        }
+    }
+}
+
+impl ConfigOverrides {
+    /// Put the filters given on the command line over `options`, leaving
+    /// the others as they are (e.g. restored from the last session)
+    pub fn apply_filters(
+        &self,
+        options: &mut SqueueOptions,
+        recent_hours: &mut u32,
+        current_user: &str,
+    ) {
+        if let Some(user) = &self.user {
+            options.user = resolve_user(Some(user), current_user);
+        }
+        if let Some(hours) = self.recent_hours {
+            *recent_hours = hours.max(1);
+        }
+        if let Some(partitions) = &self.partitions {
+            options.partitions = partitions.clone();
+        }
+    }
+}
+
+/// The command-line overrides of this process
+pub fn overrides() -> ConfigOverrides {
+    OVERRIDES.get().cloned().unwrap_or_default()
+}
+
+/// Apply `overrides` to every later [`load_config`] of this process
+pub fn set_overrides(overrides: ConfigOverrides) {
+    let _ = OVERRIDES.set(overrides);
+}
+
+/// Cluster operator actions, off unless enabled.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdminConfig {
+    /// Drain and resume nodes from the nodes view (needs Slurm operator rights).
+    pub node_actions: bool,
+}
+
+impl AdminConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Programs the work dir and logs of a job are opened with: shell commands
+/// in which `{id}`, `{workdir}` and `{stdout}` stand for the job's.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenConfig {
+    /// Command opening the work dir.
+    pub workdir: String,
+    /// Command opening the stdout log.
+    pub stdout: String,
+    /// Further commands by name, offered under the number keys in name order.
+    pub commands: BTreeMap<String, String>,
+}
+
+impl Default for OpenConfig {
+    fn default() -> Self {
+        Self {
+            workdir: "${EDITOR:-vi} {workdir}".to_string(),
+            stdout: "${PAGER:-less} {stdout}".to_string(),
+            commands: BTreeMap::new(),
+        }
+    }
+}
+
+impl OpenConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A shell command run on a job from the action menu, with the
+/// placeholders of `[open]`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomAction {
+    pub name: String,
+    pub command: String,
+    /// Key running it in the action menu.
+    pub key: Option<char>,
+    /// Start it and return at once (a server, a copy), rather than wait
+    /// for its output.
+    pub background: bool,
+}
+
+/// Protected ("production") jobs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProtectConfig {
+    /// Regexes on the job name; jobs tagged `protected` are protected too.
+    pub names: Vec<String>,
+}
+
+impl ProtectConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Polling of each data source; delays grow while a command fails or is slow.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RefreshConfig {
+    /// Seconds identical squeue/sacct/sstat queries reuse a result (0: never).
+    pub cache_ttl_secs: u64,
+    /// Seconds a Slurm command may run before it is killed, so a hung
+    /// controller fails the refresh instead of stalling it (0: no limit).
+    pub command_timeout_secs: u64,
+    /// Seconds between squeue polls of the job list.
+    pub squeue_secs: u64,
+    /// Seconds between sacct queries for recently ended jobs.
+    pub sacct_secs: u64,
+    /// Seconds between sinfo queries for the partition list.
+    pub sinfo_secs: u64,
+    /// Ended jobs read from sacct at most; the rest are left out (0: all).
+    pub sacct_max_rows: usize,
+    /// Hours covered by each sacct query; longer look backs are split into
+    /// several queries run one after the other (0: a single query).
+    pub sacct_window_hours: u32,
+    /// Seconds between refreshes while the terminal is unfocused (0 pauses
+    /// polling until it is focused again).
+    pub unfocused_secs: u64,
+    /// Seconds between `squeue --start` queries for the expected start of
+    /// pending jobs, which are expensive for the controller.
+    pub start_estimate_secs: u64,
+    /// Slurm commands run at the same time at most, those of a refresh and
+    /// of the detail pane together (1: one after the other).
+    pub max_concurrent: usize,
+    /// Milliseconds between the starts of the commands of one refresh, so
+    /// they never reach slurmctld all at once.
+    pub stagger_ms: u64,
+}
+
+impl Default for RefreshConfig {
+    fn default() -> Self {
+        Self {
+            cache_ttl_secs: 10,
+            command_timeout_secs: 30,
+            squeue_secs: 10,
+            sacct_secs: 120,
+            sinfo_secs: 600,
+            sacct_max_rows: 50_000,
+            sacct_window_hours: 72,
+            unfocused_secs: 120,
+            start_estimate_secs: 300,
+            max_concurrent: 3,
+            stagger_ms: 200,
+        }
+    }
+}
+
+impl RefreshConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How the submit, start and end times of jobs are shown.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeConfig {
+    /// Start with relative times (`13m ago`, `in ~2h`).
+    pub relative: bool,
+    /// Time zone (`Europe/Berlin`, or an offset: `+02:00`, `-0700`, `UTC`)
+    /// timestamps are shown in, when yours differs from the cluster's; the
+    /// cluster's clock when unset.
+    pub zone: Option<String>,
+}
+
+impl TimeConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn display(&self) -> TimeDisplay {
+        TimeDisplay {
+            relative: self.relative,
+            zone: self.zone.as_deref().and_then(parse_zone),
+        }
+    }
+}
+
+/// Job notes storage.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotesConfig {
+    /// Team directory (e.g. on NFS) shared with other Slurmer users.
+    pub shared_dir: Option<String>,
+    /// Cluster name used to key notes; detected from Slurm when unset.
+    pub cluster: Option<String>,
+}
+
+impl NotesConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Metrics export for Prometheus.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// node_exporter textfile-collector file (`*.prom`) rewritten on every refresh.
+    pub textfile: Option<String>,
+}
+
+impl MetricsConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn textfile_path(&self) -> Option<PathBuf> {
+        self.textfile
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from)
+    }
+}
+
+/// Notification channels used when a watched job ends.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// Desktop notification through `notify-send`.
+    pub desktop: bool,
+    /// Ring the terminal bell.
+    pub bell: bool,
+    /// Shell command to run, with `SLURMER_JOB_ID`, `SLURMER_JOB_STATE` and
+    /// `SLURMER_JOB_OK` (`1`/`0`) in its environment.
+    pub command: Option<String>,
+    /// Also notify when one of your listed jobs ends while the TUI is open.
+    pub tui: bool,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            desktop: true,
+            bell: false,
+            command: None,
+            tui: false,
+        }
+    }
+}
+
+impl NotifyConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A named set of query and client-side filters.
+///
+/// `user` accepts `"me"` (or omission) for the current user and `"*"` for all users.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilterPreset {
+    pub name: String,
+    pub user: Option<String>,
+    pub states: Vec<String>,
+    pub partitions: Vec<String>,
+    pub qos: Vec<String>,
+    pub accounts: Vec<String>,
+    pub wckeys: Vec<String>,
+    pub name_filter: Option<String>,
+    pub node_filter: Option<String>,
+    /// Only jobs that use GPUs
+    pub gpu_only: bool,
+    /// Only pending jobs expected to start within this many hours
+    pub start_within_hours: Option<u32>,
+    pub recent_hours: Option<u32>,
 }
 
+/// The `--user` of a user spec: `"me"` (or nothing) for `current_user`, `None`
+/// for `"*"` (all users), else the comma-separated names
+pub fn resolve_user(spec: Option<&str>, current_user: &str) -> Option<String> {
+    match spec.map(str::trim) {
+        None | Some("") | Some("me") => Some(current_user.to_string()),
+        Some("*") => None,
+        Some(users) => Some(
+            users
+                .split(',')
+                .map(str::trim)
+                .filter(|u| !u.is_empty())
+                .map(|u| if u == "me" { current_user } else { u })
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+    }
+}
+
+impl FilterPreset {
+    /// Replace the filters in `options` (and the sacct look-back window) with this preset.
+    pub fn apply_to(
+        &self,
+        options: &mut SqueueOptions,
+        recent_hours: &mut u32,
+        current_user: &str,
+    ) {
+        options.user = resolve_user(self.user.as_deref(), current_user);
+        options.states = self
+            .states
+            .iter()
+            .filter_map(|s| s.parse::<JobState>().ok())
+            .filter(|s| *s != JobState::Other)
+            .collect();
+        options.partitions = self.partitions.clone();
+        options.qos = self.qos.clone();
+        options.accounts = self.accounts.clone();
+        options.wckeys = self.wckeys.clone();
+        options.name_filter = self.name_filter.clone().filter(|s| !s.is_empty());
+        options.node_filter = self.node_filter.clone().filter(|s| !s.is_empty());
+        options.gpu_only = self.gpu_only;
+        options.start_within_hours = self.start_within_hours;
+        if let Some(hours) = self.recent_hours {
+            *recent_hours = hours.max(1);
+        }
+    }
+}
+
+/// The config in effect: the system file, then the user's file over it,
+/// then the command-line overrides
 pub fn load_config() -> Result<SlurmerConfig> {
+    let mut merged = read_layer(&system_config_path())?.unwrap_or_default();
+    if let Some(user) = read_layer(&config_file_path()?)? {
+        merge_tables(&mut merged, user);
+    }
+    let mut cfg: SlurmerConfig = toml::Value::Table(merged)
+        .try_into()
+        .wrap_err("failed parsing config toml")?;
+    if let Some(overrides) = OVERRIDES.get() {
+        overrides.apply(&mut cfg);
+    }
+    Ok(cfg)
+}
+
+/// Only the user's file, for editing it without copying in the system
+/// file's values
+pub fn load_user_config() -> Result<SlurmerConfig> {
     let path = config_file_path()?;
+    match read_layer(&path)? {
+        Some(table) => toml::Value::Table(table)
+            .try_into()
+            .wrap_err("failed parsing config toml"),
+        None => Ok(SlurmerConfig::default()),
+    }
+}
+
+pub fn system_config_path() -> PathBuf {
+    std::env::var_os(ENV_SYSTEM_CONFIG)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(SYSTEM_CONFIG_PATH))
+}
+
+/// A config file as a table, checked on its own so errors point into it;
+/// `None` when there is no such file
+fn read_layer(path: &Path) -> Result<Option<toml::Table>> {
     if !path.exists() {
-        return Ok(SlurmerConfig::default());
+        return Ok(None);
     }
+    let raw =
+        fs::read_to_string(path).wrap_err_with(|| format!("failed reading {}", path.display()))?;
+    toml::from_str::<SlurmerConfig>(&raw)
+        .wrap_err_with(|| format!("failed parsing {}", path.display()))?;
+    let table = raw
+        .parse::<toml::Table>()
+        .wrap_err_with(|| format!("failed parsing {}", path.display()))?;
+    Ok(Some(table))
+}
 
-    let raw = fs::read_to_string(&path).wrap_err("failed reading config file")?;
-    let cfg: SlurmerConfig = toml::from_str(&raw).wrap_err("failed parsing config toml")?;
-    Ok(cfg)
+/// Merge `upper` into `lower`: tables key by key, any other value (arrays
+/// such as `[[presets]]` included) replaced whole
+fn merge_tables(lower: &mut toml::Table, upper: toml::Table) {
+    for (key, value) in upper {
+        match (lower.get_mut(&key), value) {
+            (Some(toml::Value::Table(lower)), toml::Value::Table(upper)) => {
+                merge_tables(lower, upper)
+            }
+            (_, value) => {
+                lower.insert(key, value);
+            }
+        }
+    }
 }
 
 pub fn save_config(cfg: &SlurmerConfig) -> Result<()> {
@@ -32,8 +677,27 @@ pub fn save_config(cfg: &SlurmerConfig) -> Result<()> {
     Ok(())
 }
 
+/// Set by [`use_base_dir`]
+static BASE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Keep the config, data, cache and state directories under `dir` whatever
+/// the environment says, for runs that must not touch the user's own (the
+/// first call wins)
+#[cfg(test)]
+pub fn use_base_dir(dir: PathBuf) {
+    let _ = BASE_DIR.set(dir);
+}
+
+/// `<base>/<name>` once [`use_base_dir`] was called
+fn based(name: &str) -> Option<PathBuf> {
+    BASE_DIR.get().map(|base| base.join(name))
+}
+
 pub fn resolve_slurm_logs_dir(cfg: &SlurmerConfig) -> Option<PathBuf> {
-    if let Ok(v) = std::env::var(ENV_SLURM_LOGS_DIR) {
+    if let Some(v) = std::env::var(ENV_SLURM_LOGS_DIR)
+        .ok()
+        .filter(|_| BASE_DIR.get().is_none())
+    {
         let trimmed = v.trim();
         if !trimmed.is_empty() {
             return Some(PathBuf::from(trimmed));
@@ -47,12 +711,69 @@ pub fn resolve_slurm_logs_dir(cfg: &SlurmerConfig) -> Option<PathBuf> {
         .map(PathBuf::from)
 }
 
-fn config_file_path() -> Result<PathBuf> {
+pub fn config_file_path() -> Result<PathBuf> {
     let base = xdg_config_home()?;
     Ok(base.join("slurmer").join("config.toml"))
 }
 
+/// Directory for disposable cached data (`$XDG_CACHE_HOME/slurmer`).
+pub fn cache_dir() -> Result<PathBuf> {
+    if let Some(dir) = based("cache") {
+        return Ok(dir.join("slurmer"));
+    }
+    if let Ok(v) = std::env::var("XDG_CACHE_HOME") {
+        let p = PathBuf::from(v);
+        if !p.as_os_str().is_empty() {
+            return Ok(p.join("slurmer"));
+        }
+    }
+
+    let home = std::env::var("HOME").wrap_err("HOME is not set")?;
+    Ok(Path::new(&home).join(".cache").join("slurmer"))
+}
+
+/// Directory for state such as logs (`$XDG_STATE_HOME/slurmer`).
+pub fn state_dir() -> Result<PathBuf> {
+    if let Some(dir) = based("state") {
+        return Ok(dir.join("slurmer"));
+    }
+    if let Ok(v) = std::env::var("XDG_STATE_HOME") {
+        let p = PathBuf::from(v);
+        if !p.as_os_str().is_empty() {
+            return Ok(p.join("slurmer"));
+        }
+    }
+
+    let home = std::env::var("HOME").wrap_err("HOME is not set")?;
+    Ok(Path::new(&home)
+        .join(".local")
+        .join("state")
+        .join("slurmer"))
+}
+
+/// Directory for user data worth keeping (`$XDG_DATA_HOME/slurmer`).
+pub fn data_dir() -> Result<PathBuf> {
+    if let Some(dir) = based("data") {
+        return Ok(dir.join("slurmer"));
+    }
+    if let Ok(v) = std::env::var("XDG_DATA_HOME") {
+        let p = PathBuf::from(v);
+        if !p.as_os_str().is_empty() {
+            return Ok(p.join("slurmer"));
+        }
+    }
+
+    let home = std::env::var("HOME").wrap_err("HOME is not set")?;
+    Ok(Path::new(&home)
+        .join(".local")
+        .join("share")
+        .join("slurmer"))
+}
+
 fn xdg_config_home() -> Result<PathBuf> {
+    if let Some(dir) = based("config") {
+        return Ok(dir);
+    }
     if let Ok(v) = std::env::var("XDG_CONFIG_HOME") {
         let p = PathBuf::from(v);
         if !p.as_os_str().is_empty() {
@@ -64,3 +785,112 @@ fn xdg_config_home() -> Result<PathBuf> {
     Ok(Path::new(&home).join(".config"))
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preset_parses_from_toml_and_applies() {
+        let raw = r#"
+            [[presets]]
+            name = "failures-24h"
+            user = "me"
+            states = ["FAILED", "TIMEOUT"]
+            recent_hours = 24
+
+            [[presets]]
+            name = "everyone-gpu"
+            user = "*"
+            partitions = ["gpu"]
+        "#;
+        let cfg: SlurmerConfig = toml::from_str(raw).unwrap();
+        assert_eq!(cfg.presets.len(), 2);
+
+        let mut options = SqueueOptions::default();
+        let mut hours = 6;
+        cfg.presets[0].apply_to(&mut options, &mut hours, "alice");
+        assert_eq!(options.user.as_deref(), Some("alice"));
+        assert_eq!(options.states, vec![JobState::Failed, JobState::Timeout]);
+        assert_eq!(hours, 24);
+
+        cfg.presets[1].apply_to(&mut options, &mut hours, "alice");
+        assert_eq!(options.user, None);
+        assert!(options.states.is_empty());
+        assert_eq!(options.partitions, vec!["gpu".to_string()]);
+        assert_eq!(hours, 24);
+        assert_eq!(
+            resolve_user(Some(" bob, me ,carol "), "alice").as_deref(),
+            Some("bob,alice,carol")
+        );
+    }
+
+    #[test]
+    fn layers_merge_key_by_key() {
+        let mut system: toml::Table = toml::from_str(
+            "theme = \"mono\"\n\
+             [refresh]\nsqueue_secs = 60\nsacct_secs = 600\n\
+             [[presets]]\nname = \"site\"\n",
+        )
+        .unwrap();
+        let user: toml::Table = toml::from_str(
+            "[refresh]\nsqueue_secs = 5\n\
+             [keys]\ncancel = \"k\"\nrefresh = [\"x\", \"g r\"]\n\
+             [[presets]]\nname = \"mine\"\n",
+        )
+        .unwrap();
+        merge_tables(&mut system, user);
+        let mut cfg: SlurmerConfig = toml::Value::Table(system).try_into().unwrap();
+        assert_eq!(cfg.theme, Theme::Mono);
+        assert_eq!((cfg.refresh.squeue_secs, cfg.refresh.sacct_secs), (5, 600));
+        assert_eq!(cfg.presets.len(), 1);
+        assert_eq!(cfg.presets[0].name, "mine");
+
+        assert_eq!(cfg.keys["cancel"], KeyBinding::One("k".to_string()));
+        assert_eq!(
+            cfg.keys["refresh"],
+            KeyBinding::Many(vec!["x".to_string(), "g r".to_string()])
+        );
+
+        ConfigOverrides {
+            user: Some("*".to_string()),
+            theme: Some(Theme::Default),
+            ..Default::default()
+        }
+        .apply(&mut cfg);
+        assert_eq!(cfg.defaults.user, "*");
+        assert_eq!(cfg.defaults.recent_hours, 24);
+        assert_eq!(cfg.theme, Theme::Default);
+    }
+
+    #[test]
+    fn narrowest_breakpoint_wins() {
+        let cfg: SlurmerConfig = toml::from_str(
+            "[layout]\n\
+             breakpoints = [{ below = 140, columns = [\"id\", \"name\", \"state\", \"time\"] },\n\
+             { below = 90, columns = [\"id\", \"state\"] }]\n",
+        )
+        .unwrap();
+        assert_eq!(cfg.layout.stack_below, 120);
+        assert_eq!(
+            cfg.layout.columns_for(80),
+            Some(&["id", "state"].map(String::from)[..])
+        );
+        assert_eq!(cfg.layout.columns_for(100).map(<[String]>::len), Some(4));
+        assert_eq!(cfg.layout.columns_for(200), None);
+    }
+
+    #[test]
+    fn notify_section_is_optional() {
+        let cfg: SlurmerConfig = toml::from_str("slurm_logs_dir = \"/logs\"").unwrap();
+        assert_eq!(cfg.notify, NotifyConfig::default());
+        // The mouse is left to the terminal unless asked for
+        assert!(!cfg.mouse_enabled());
+        // An untouched [notify] section is not written back
+        assert!(!toml::to_string(&cfg).unwrap().contains("notify"));
+
+        let cfg: SlurmerConfig = toml::from_str("[notify]\ndesktop = false\nbell = true").unwrap();
+        assert!(!cfg.notify.desktop);
+        assert!(cfg.notify.bell);
+        assert_eq!(cfg.notify.command, None);
+    }
+}
@@ -0,0 +1,198 @@
+use std::time::Duration;
+
+use crate::slurm::{memory::Memory, Job};
+use crate::utils::format_slurm_duration;
+
+/// Use of the allocation below which it was mostly wasted
+pub const LOW_USE: f64 = 0.25;
+
+/// Use below which the allocation was likely too large
+pub const FAIR_USE: f64 = 0.5;
+
+/// Memory use from which the job risked running out
+pub const NEAR_MEMORY_LIMIT: f64 = 0.95;
+
+/// How worrying a figure of the report is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Concern {
+    /// Much of the allocation was used
+    Fine,
+    /// Below `FAIR_USE`
+    Fair,
+    /// Below `LOW_USE`, or memory at its limit
+    Bad,
+}
+
+impl Concern {
+    fn of_use(fraction: f64) -> Self {
+        match fraction {
+            f if f < LOW_USE => Concern::Bad,
+            f if f < FAIR_USE => Concern::Fair,
+            _ => Concern::Fine,
+        }
+    }
+}
+
+/// What `seff` reports for a job: the CPU time and peak memory it used
+/// against what it was allocated
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub cores: u32,
+    pub nodes: u32,
+    pub cpu_used: Option<Duration>,
+    /// Elapsed × cores
+    pub core_walltime: Duration,
+    pub memory_used: Option<Memory>,
+    /// Per node
+    pub memory_requested: Option<Memory>,
+}
+
+impl Report {
+    pub fn of(job: &Job) -> Self {
+        Self {
+            cores: job.cpus,
+            nodes: job.nodes.max(1),
+            cpu_used: job.total_cpu,
+            core_walltime: job.time * job.cpus,
+            memory_used: job.max_rss.as_deref().and_then(Memory::parse),
+            memory_requested: job.memory_per_node().map(|bytes| Memory {
+                bytes,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// `(label, value)` rows, as `seff` prints them
+    pub fn rows(&self, job: &Job) -> Vec<(String, String)> {
+        let percent = |f: Option<f64>| f.map_or("-".to_string(), |f| format!("{:.1}%", f * 100.0));
+        vec![
+            (
+                "Cores".to_string(),
+                format!("{} on {} node(s)", self.cores, self.nodes),
+            ),
+            (
+                "CPU utilized".to_string(),
+                self.cpu_used.map_or("-".to_string(), format_slurm_duration),
+            ),
+            (
+                "CPU efficiency".to_string(),
+                format!(
+                    "{} of {} core-walltime",
+                    percent(job.cpu_efficiency()),
+                    format_slurm_duration(self.core_walltime)
+                ),
+            ),
+            (
+                "Memory utilized".to_string(),
+                self.memory_used.map_or("-".to_string(), |m| m.to_string()),
+            ),
+            (
+                "Memory efficiency".to_string(),
+                format!(
+                    "{} of {} per node",
+                    percent(job.memory_efficiency()),
+                    self.memory_requested
+                        .map_or("-".to_string(), |m| m.to_string())
+                ),
+            ),
+        ]
+    }
+
+    /// What the figures suggest doing differently, worst first
+    pub fn warnings(&self, job: &Job) -> Vec<(Concern, String)> {
+        let mut warnings = Vec::new();
+        if let Some(memory) = job.memory_efficiency() {
+            if memory >= NEAR_MEMORY_LIMIT {
+                warnings.push((
+                    Concern::Bad,
+                    format!(
+                        "used {:.0}% of requested memory: request more",
+                        memory * 100.0
+                    ),
+                ));
+            } else if Concern::of_use(memory) != Concern::Fine {
+                warnings.push((
+                    Concern::of_use(memory),
+                    format!(
+                        "used {:.0}% of requested memory: request less",
+                        memory * 100.0
+                    ),
+                ));
+            }
+        }
+        if let Some(cpu) = job.cpu_efficiency() {
+            if Concern::of_use(cpu) != Concern::Fine {
+                warnings.push((
+                    Concern::of_use(cpu),
+                    format!(
+                        "used {:.0}% of the allocated CPU time: request fewer cores",
+                        cpu * 100.0
+                    ),
+                ));
+            }
+        }
+        warnings.sort_by_key(|(concern, _)| *concern != Concern::Bad);
+        warnings
+    }
+}
+
+/// The CPU and memory efficiency of a job combined, as the mean of those
+/// known; memory past its request counts as fully used
+pub fn overall(job: &Job) -> Option<f64> {
+    let known: Vec<f64> = [job.cpu_efficiency(), job.memory_efficiency()]
+        .into_iter()
+        .flatten()
+        .map(|e| e.min(1.0))
+        .collect();
+    (!known.is_empty()).then(|| known.iter().sum::<f64>() / known.len() as f64)
+}
+
+/// How the efficiency of a job reads, for coloring it
+pub fn concern(efficiency: f64) -> Concern {
+    Concern::of_use(efficiency)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_warns_about_unused_allocations() {
+        let job = Job {
+            cpus: 16,
+            nodes: 1,
+            time: Duration::from_secs(3600),
+            total_cpu: Some(Duration::from_secs(2 * 3600)),
+            memory: Memory::parse("32G"),
+            max_rss: Some("1000M".to_string()),
+            ..Default::default()
+        };
+        let report = Report::of(&job);
+        let rows = report.rows(&job);
+        assert_eq!(rows[2].1, "12.5% of 16:00:00 core-walltime");
+        assert_eq!(rows[4].1, "3.1% of 32G per node");
+        assert_eq!(
+            report.warnings(&job),
+            [
+                (
+                    Concern::Bad,
+                    "used 3% of requested memory: request less".to_string()
+                ),
+                (
+                    Concern::Bad,
+                    "used 12% of the allocated CPU time: request fewer cores".to_string()
+                ),
+            ]
+        );
+        let combined = overall(&job).unwrap();
+        assert!((combined - (0.125 + 1000.0 / 32768.0) / 2.0).abs() < 1e-9);
+
+        let idle = Job {
+            total_cpu: None,
+            max_rss: None,
+            ..job
+        };
+        assert_eq!(overall(&idle), None);
+        assert!(Report::of(&idle).warnings(&idle).is_empty());
+    }
+}
@@ -0,0 +1,476 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Once;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+use regex::Regex;
+
+use crate::app::App;
+use crate::config::{self, SlurmerConfig};
+use crate::slurm::mock::MockJobSource;
+use crate::utils::event::EventHandler;
+
+/// Size of the emulated terminal, wide enough for the columns to fit: the
+/// layout solver does not settle over-constrained widths the same way twice
+const WIDTH: u16 = 240;
+const HEIGHT: u16 = 40;
+
+/// Set `UPDATE_SNAPSHOTS=1` to rewrite the snapshots from the current
+/// rendering instead of comparing against them
+const UPDATE_VAR: &str = "UPDATE_SNAPSHOTS";
+
+static ISOLATE: Once = Once::new();
+
+/// The app on the demo fixture in an in-memory terminal, driven by
+/// synthetic key presses and compared frame by frame against the snapshots
+/// in `fixtures/snapshots`
+pub struct Harness {
+    pub app: App,
+    terminal: Terminal<TestBackend>,
+}
+
+impl Harness {
+    pub fn demo() -> Self {
+        Self::with_source(MockJobSource::demo())
+    }
+
+    /// The TUI on the jobs of `source`, polled once
+    pub fn with_source(source: MockJobSource) -> Self {
+        Self::with_config(source, SlurmerConfig::default())
+    }
+
+    /// The TUI on the jobs of `source` under `cfg`, polled once
+    pub fn with_config(source: MockJobSource, cfg: SlurmerConfig) -> Self {
+        // The user's config, notes and queue must not leak into frames, nor
+        // tests write into their directories
+        ISOLATE.call_once(|| {
+            config::use_base_dir(
+                std::env::temp_dir().join(format!("slurmer-harness-{}", std::process::id())),
+            )
+        });
+        let mut app = App::with_config(Some(source), EventHandler::detached(), cfg)
+            .expect("the app starts on the fixture");
+        app.handle_resize(WIDTH);
+        app.refresh_jobs().expect("the demo fixture loads");
+        let terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).expect("test terminal");
+        Self { app, terminal }
+    }
+
+    pub fn key(&mut self, code: KeyCode) -> &mut Self {
+        self.app
+            .handle_key_event(KeyEvent::new(code, KeyModifiers::NONE));
+        self
+    }
+
+    /// Resize the emulated terminal, as a tmux pane would be
+    pub fn resize(&mut self, width: u16, height: u16) -> &mut Self {
+        self.terminal.backend_mut().resize(width, height);
+        self.app.handle_resize(width);
+        self
+    }
+
+    /// Click the left mouse button at column `x` of row `y`
+    pub fn click(&mut self, x: u16, y: u16) -> &mut Self {
+        self.app.handle_mouse_event(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: x,
+            row: y,
+            modifiers: KeyModifiers::NONE,
+        });
+        self
+    }
+
+    /// Press each character of `keys` in turn
+    pub fn type_keys(&mut self, keys: &str) -> &mut Self {
+        for c in keys.chars() {
+            let modifiers = if c.is_ascii_uppercase() {
+                KeyModifiers::SHIFT
+            } else {
+                KeyModifiers::NONE
+            };
+            self.app
+                .handle_key_event(KeyEvent::new(KeyCode::Char(c), modifiers));
+        }
+        self
+    }
+
+    /// The current frame as text, one line per terminal row
+    pub fn frame(&mut self) -> String {
+        let app = &mut self.app;
+        self.terminal
+            .draw(|frame| app.render(frame))
+            .expect("drawing to the test terminal");
+        let buffer = self.terminal.backend().buffer();
+        let mut text = String::new();
+        for y in 0..buffer.area.height {
+            let line: String = (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect();
+            text.push_str(&redact(line.trim_end()));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Compare the current frame with snapshot `name`, writing it instead
+    /// when `UPDATE_SNAPSHOTS` is set
+    pub fn assert_snapshot(&mut self, name: &str) {
+        let frame = self.frame();
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/snapshots")
+            .join(format!("{}.txt", name));
+        if std::env::var_os(UPDATE_VAR).is_some() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, &frame).unwrap();
+            return;
+        }
+        let Ok(expected) = fs::read_to_string(&path) else {
+            panic!(
+                "no snapshot {}; rerun with {}=1 to write it\n{}",
+                path.display(),
+                UPDATE_VAR,
+                frame
+            );
+        };
+        if frame != expected {
+            let first = frame
+                .lines()
+                .zip(expected.lines())
+                .position(|(a, b)| a != b)
+                .unwrap_or(0);
+            panic!(
+                "frame differs from {} from row {}; rerun with {}=1 if the change is intended\n\
+                 --- expected\n{}\n--- rendered\n{}",
+                path.display(),
+                first,
+                UPDATE_VAR,
+                expected,
+                frame
+            );
+        }
+    }
+}
+
+/// Blank out what depends on how long the test took: the polling ages in
+/// the header (and the border they are right-aligned on) and the poll
+/// durations of the status bar
+fn redact(line: &str) -> String {
+    if line.contains(" no controller (fixture) ") {
+        let took = Regex::new(r"\b\d+(\.\d)?(ms|s)\b").unwrap();
+        return took.replace_all(line, "#$2").into_owned();
+    }
+    if !line.contains(" ago, next ") {
+        return line.to_string();
+    }
+    let ages = Regex::new(r"\d+([smh])\b").unwrap();
+    let border = Regex::new("─{2,}").unwrap();
+    border
+        .replace_all(&ages.replace_all(line, "#$1"), "──")
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slurm::mock::{Fixture, FixtureEnd};
+    use crate::slurm::JobState;
+
+    #[test]
+    fn main_views_render_as_before() {
+        let mut harness = Harness::demo();
+        harness.assert_snapshot("jobs");
+        harness.key(KeyCode::Down).key(KeyCode::Char(' '));
+        harness.assert_snapshot("jobs_selected");
+        harness.type_keys("f");
+        harness.assert_snapshot("filter");
+        harness.key(KeyCode::Esc).type_keys("c");
+        harness.assert_snapshot("columns");
+        harness.key(KeyCode::Esc).type_keys("d");
+        harness.assert_snapshot("detail");
+    }
+
+    #[test]
+    fn remapped_keys_run_their_actions_in_place_of_the_built_in_ones() {
+        let keys = "detail = \"z\"\nclose_detail = \"Z\"\nnext_tab = \"ctrl-n\"";
+        let cfg = SlurmerConfig {
+            keys: toml::from_str(keys).unwrap(),
+            ..Default::default()
+        };
+        let mut harness = Harness::with_config(MockJobSource::demo(), cfg);
+        harness.type_keys("d");
+        assert!(!harness.app.job_detail.visible, "d is no longer bound");
+        harness.type_keys("z");
+        assert!(harness.app.job_detail.visible);
+
+        let tab = harness.app.job_detail.tab;
+        harness.type_keys("l");
+        assert_eq!(harness.app.job_detail.tab, tab, "l is no longer bound");
+        harness
+            .app
+            .handle_key_event(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL));
+        assert_ne!(harness.app.job_detail.tab, tab);
+        harness.type_keys("qZ");
+        assert!(!harness.app.job_detail.visible);
+    }
+
+    #[test]
+    fn narrow_terminals_get_compact_columns_and_a_stacked_detail_pane() {
+        let mut harness = Harness::demo();
+        harness.resize(80, HEIGHT).type_keys("d");
+        harness.assert_snapshot("narrow_detail");
+
+        // Only the compact columns are asked of Slurm; the popup says why
+        // the others are missing
+        assert!(!harness.app.squeue_options.format.contains("%P"));
+        harness.type_keys("dc");
+        let frame = harness.frame();
+        assert!(frame.contains("User (hidden)"));
+        assert!(frame.contains("too narrow"));
+        harness.key(KeyCode::Esc).resize(WIDTH, HEIGHT);
+        assert!(harness.app.squeue_options.format.contains("%P"));
+    }
+
+    #[test]
+    fn clicks_select_rows_sort_headers_and_open_details() {
+        use crate::ui::columns::{JobColumn, SortOrder};
+
+        let mut harness = Harness::demo();
+        let frame = harness.frame();
+        // Below the header area: the table border, its header, then rows
+        let header = frame.lines().nth(4).unwrap();
+        let name_x = header[..header.find("Name").unwrap()].chars().count() as u16;
+        harness.click(name_x, 4);
+        let first = &harness.app.sort_columns[0];
+        assert_eq!(
+            (first.column, first.order),
+            (JobColumn::Name, SortOrder::Ascending)
+        );
+        harness.click(name_x, 4);
+        assert_eq!(harness.app.sort_columns[0].order, SortOrder::Descending);
+
+        let frame = harness.frame();
+        let second = frame.lines().nth(6).unwrap().split_whitespace().nth(1);
+        harness.click(20, 6);
+        assert_eq!(
+            harness.app.jobs_list.selected_job().map(|j| j.id.as_str()),
+            second
+        );
+        assert!(!harness.app.job_detail.visible);
+        harness.click(20, 6);
+        assert!(
+            harness.app.job_detail.visible,
+            "a double-click opens the detail pane"
+        );
+    }
+
+    #[test]
+    fn the_search_form_lists_matching_jobs_apart_from_the_list() {
+        let mut harness = Harness::demo();
+        harness.type_keys("F");
+        assert!(harness.app.form.visible);
+        // Down to States
+        harness.key(KeyCode::Down).key(KeyCode::Down);
+        harness.type_keys("FAILED").key(KeyCode::Enter);
+        assert!(harness.app.search_view.visible);
+        let frame = harness.frame();
+        assert!(frame.contains("Search: user=me, states=FAILED, from=7d"));
+        assert!(frame.contains("debug_dataloader"));
+        assert!(!frame.contains("RUNNING "));
+
+        harness.type_keys("e");
+        assert!(harness.app.form.visible);
+        harness.key(KeyCode::Esc);
+        assert!(
+            harness.app.search_view.visible,
+            "cancelling returns to the results"
+        );
+
+        // Enter drills into the job under the cursor
+        let found = harness.app.search_view.selected_job().cloned().unwrap();
+        harness.key(KeyCode::Enter);
+        assert!(harness.app.job_detail.visible);
+        assert_eq!(
+            harness.app.job_detail.job.as_ref().map(|j| &j.id),
+            Some(&found.id)
+        );
+        harness.key(KeyCode::Esc);
+        assert!(harness.app.search_view.visible, "Esc closes only the pane");
+
+        // Tab switches to the job list and back, Esc closes the results
+        harness.key(KeyCode::Tab);
+        assert!(!harness.app.search_view.visible);
+        assert!(harness.frame().contains("RUNNING "));
+        harness.key(KeyCode::Tab);
+        assert!(harness.app.search_view.visible);
+        harness.key(KeyCode::Esc);
+        assert!(!harness.app.search_view.open);
+    }
+
+    #[test]
+    fn plus_and_minus_step_the_look_back_window() {
+        let mut harness = Harness::demo();
+        assert_eq!(harness.app.recent_ended_hours, 24);
+        harness.type_keys("+");
+        assert_eq!(harness.app.recent_ended_hours, 72);
+        assert!(harness.frame().contains("(last 3d)"));
+        harness.type_keys("---");
+        assert_eq!(harness.app.recent_ended_hours, 1);
+        harness.type_keys("-");
+        assert_eq!(harness.app.recent_ended_hours, 1);
+    }
+
+    #[test]
+    fn pausing_leaves_no_poll_due_until_resumed() {
+        let mut harness = Harness::demo();
+        assert!(harness.app.refresh_interval().is_some());
+        harness.type_keys("p");
+        assert!(harness.app.polling_paused);
+        assert_eq!(harness.app.refresh_interval(), None);
+        assert!(harness.app.refresh_status().starts_with("squeue "));
+        // A manual refresh runs, and leaves polling paused
+        harness.type_keys("r");
+        assert!(harness.app.polling_paused);
+        harness.type_keys("p");
+        assert!(harness.app.refresh_interval().is_some());
+    }
+
+    #[test]
+    fn watched_jobs_stay_on_top_whatever_the_filters() {
+        let mut harness = Harness::demo();
+        harness.key(KeyCode::Down).key(KeyCode::Down);
+        let id = harness.app.jobs_list.selected_job().unwrap().id.clone();
+        harness.type_keys("w");
+        assert_eq!(harness.app.jobs_list.jobs[0].id, id);
+        assert!(harness.frame().contains(&format!("★ {}", id)));
+
+        harness.app.squeue_options.name_filter = Some("^no such job$".to_string());
+        harness.app.refresh_jobs().unwrap();
+        let listed: Vec<&str> = harness
+            .app
+            .jobs_list
+            .jobs
+            .iter()
+            .map(|j| j.id.as_str())
+            .collect();
+        assert_eq!(listed, [id.as_str()]);
+    }
+
+    #[test]
+    fn the_wckey_filter_keeps_the_jobs_of_its_keys() {
+        let mut harness = Harness::demo();
+        harness.type_keys("f");
+        for _ in 0..6 {
+            harness.key(KeyCode::Right);
+        }
+        harness
+            .key(KeyCode::Enter)
+            .type_keys("llama")
+            .key(KeyCode::Enter);
+        assert!(harness.frame().contains("WCKeys (a,b)"));
+        harness
+            .app
+            .handle_key_event(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL));
+        assert_eq!(harness.app.squeue_options.wckeys, ["llama"]);
+        let listed: Vec<&str> = harness
+            .app
+            .jobs_list
+            .jobs
+            .iter()
+            .map(|j| j.id.as_str())
+            .collect();
+        assert_eq!(listed, ["48213"]);
+    }
+
+    #[test]
+    fn jobs_leaving_squeue_are_read_back_at_once() {
+        let state = |harness: &Harness| {
+            let job = harness.app.jobs_list.jobs.iter().find(|j| j.id == "48231");
+            job.map(|j| j.state)
+        };
+        let mut fixture: Fixture =
+            serde_json::from_str(include_str!("../fixtures/demo.json")).unwrap();
+        fixture.ends.push(FixtureEnd {
+            id: "48231".to_string(),
+            state: "COMPLETED".to_string(),
+            after_polls: 1,
+            sacct_lag: 0,
+        });
+        let source = |fixture: &Fixture| {
+            MockJobSource::parse(&serde_json::to_string(fixture).unwrap()).unwrap()
+        };
+        let mut harness = Harness::with_source(source(&fixture));
+        assert_eq!(state(&harness), Some(JobState::Running));
+        harness.app.refresh_jobs().unwrap();
+        assert_eq!(state(&harness), Some(JobState::Completed));
+
+        // Accounting a poll behind: the job stays as last seen meanwhile
+        fixture.ends[0].sacct_lag = 1;
+        let mut harness = Harness::with_source(source(&fixture));
+        harness.app.refresh_jobs().unwrap();
+        assert_eq!(state(&harness), Some(JobState::Running));
+        harness.app.refresh_jobs().unwrap();
+        assert_eq!(state(&harness), Some(JobState::Completed));
+    }
+
+    #[test]
+    fn table_exports_follow_the_column_selection() {
+        let harness = Harness::demo();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.tsv");
+        let count = harness
+            .app
+            .export_table(&path, crate::output::OutputFormat::Tsv)
+            .unwrap();
+        let raw = fs::read_to_string(&path).unwrap();
+        let mut lines = raw.lines();
+        let titles: Vec<&str> = harness
+            .app
+            .selected_columns
+            .iter()
+            .map(|c| c.title())
+            .collect();
+        assert_eq!(lines.next().unwrap(), titles.join("\t"));
+        assert_eq!(lines.count(), count);
+        assert!(raw.contains("48201\tnotebook\tdemo\tCANCELLED"));
+    }
+
+    #[test]
+    fn key_flows_render_as_before() {
+        let mut harness = Harness::demo();
+        harness.type_keys("V").key(KeyCode::Down).key(KeyCode::Down);
+        harness.key(KeyCode::Esc);
+        assert!(harness.app.running, "Esc leaves visual mode first");
+        harness.assert_snapshot("visual_selection");
+        harness
+            .app
+            .handle_key_event(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL));
+        harness.type_keys("trends");
+        harness.assert_snapshot("palette");
+        harness.key(KeyCode::Enter);
+        assert!(harness.app.trends_view.visible, "Enter runs the command");
+        harness.key(KeyCode::Esc);
+        harness.type_keys("?");
+        harness.assert_snapshot("reference");
+        harness.key(KeyCode::Esc).key(KeyCode::Esc);
+        assert!(!harness.app.running);
+    }
+
+    #[test]
+    fn pastes_go_to_the_input_being_typed_in_only() {
+        let mut harness = Harness::demo();
+        let cursor = harness.app.jobs_list.state.selected();
+        harness.app.handle_paste("xq\n");
+        assert!(
+            harness.app.running,
+            "a paste on the list is not run as keys"
+        );
+        assert_eq!(harness.app.jobs_list.state.selected(), cursor);
+        harness
+            .app
+            .handle_key_event(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL));
+        harness.app.handle_paste("tre\tnds\n");
+        assert_eq!(harness.app.command_palette.query, "tre nds");
+    }
+}
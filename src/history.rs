@@ -0,0 +1,105 @@
+use std::collections::VecDeque;
+
+use crate::slurm::{Job, JobState};
+
+/// Samples kept; older ones are dropped first (3 hours at the default
+/// 30 second refresh)
+const MAX_SAMPLES: usize = 360;
+
+/// Queue depth and allocation of the polled jobs at one refresh
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueSample {
+    /// Unix time of the poll
+    pub at: u64,
+    pub pending: u64,
+    pub running: u64,
+    /// CPUs and GPUs allocated to the running jobs
+    pub cpus: u64,
+    pub gpus: u64,
+}
+
+impl QueueSample {
+    pub fn of(jobs: &[Job], at: u64) -> Self {
+        let mut sample = Self {
+            at,
+            ..Default::default()
+        };
+        for job in jobs {
+            match job.state {
+                JobState::Pending => sample.pending += 1,
+                JobState::Running => {
+                    sample.running += 1;
+                    sample.cpus += u64::from(job.cpus);
+                    sample.gpus += u64::from(job.gpus);
+                }
+                _ => {}
+            }
+        }
+        sample
+    }
+}
+
+/// The samples of this session's polls, oldest first
+#[derive(Debug, Default)]
+pub struct QueueHistory {
+    samples: VecDeque<QueueSample>,
+}
+
+impl QueueHistory {
+    pub fn record(&mut self, jobs: &[Job], at: u64) {
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(QueueSample::of(jobs, at));
+    }
+
+    pub fn samples(&self) -> &VecDeque<QueueSample> {
+        &self.samples
+    }
+
+    /// One value of each of the last `count` samples, oldest first
+    pub fn series(&self, count: usize, value: fn(&QueueSample) -> u64) -> Vec<u64> {
+        let skip = self.samples.len().saturating_sub(count);
+        self.samples.iter().skip(skip).map(value).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_keeps_the_latest_samples() {
+        let job = |state, cpus| Job {
+            state,
+            cpus,
+            gpus: 1,
+            ..Default::default()
+        };
+        let jobs = vec![
+            job(JobState::Running, 8),
+            job(JobState::Running, 4),
+            job(JobState::Pending, 2),
+            job(JobState::Completed, 16),
+        ];
+        let sample = QueueSample::of(&jobs, 7);
+        assert_eq!(
+            sample,
+            QueueSample {
+                at: 7,
+                pending: 1,
+                running: 2,
+                cpus: 12,
+                gpus: 2,
+            }
+        );
+
+        let mut history = QueueHistory::default();
+        for at in 0..MAX_SAMPLES as u64 + 5 {
+            history.record(&jobs[..(at % 3) as usize], at);
+        }
+        assert_eq!(history.samples().len(), MAX_SAMPLES);
+        assert_eq!(history.samples()[0].at, 5);
+        assert_eq!(history.series(3, |s| s.running), vec![2, 0, 1]);
+    }
+}
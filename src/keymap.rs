@@ -0,0 +1,683 @@
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// A key as `(code, modifiers)`; Shift is folded into the character of
+/// letter keys, as terminals do not all report it
+pub type Key = (KeyCode, KeyModifiers);
+
+/// Where an action applies: the job list, the detail pane open over it, or
+/// another view. Text boxes keep their keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyContext {
+    Jobs,
+    Detail,
+    Search,
+    /// The table popups (aggregate, dependencies, stats, ...)
+    Popup,
+    Aggregate,
+    Stats,
+    Cron,
+    Nodes,
+    Actions,
+    /// The job script and the log view
+    Pager,
+    Script,
+    Logs,
+    Filter,
+    Columns,
+    Settings,
+}
+
+impl KeyContext {
+    /// The context whose actions also apply in this one
+    pub fn parent(self) -> Option<KeyContext> {
+        match self {
+            KeyContext::Aggregate
+            | KeyContext::Stats
+            | KeyContext::Cron
+            | KeyContext::Nodes
+            | KeyContext::Actions => Some(KeyContext::Popup),
+            KeyContext::Script | KeyContext::Logs => Some(KeyContext::Pager),
+            _ => None,
+        }
+    }
+
+    /// Whether the actions of `other` apply in this context
+    pub fn includes(self, other: KeyContext) -> bool {
+        self == other || self.parent() == Some(other)
+    }
+
+    /// The view, e.g. `job list`
+    pub fn view(self) -> &'static str {
+        match self {
+            KeyContext::Jobs => "job list",
+            KeyContext::Detail => "detail pane",
+            KeyContext::Search => "search results",
+            KeyContext::Popup => "popups",
+            KeyContext::Aggregate => "queue counts",
+            KeyContext::Stats => "statistics",
+            KeyContext::Cron => "scrontab",
+            KeyContext::Nodes => "node list",
+            KeyContext::Actions => "custom actions",
+            KeyContext::Pager => "script and logs",
+            KeyContext::Script => "job script",
+            KeyContext::Logs => "log view",
+            KeyContext::Filter => "filters",
+            KeyContext::Columns => "column selection",
+            KeyContext::Settings => "settings",
+        }
+    }
+}
+
+/// Keys bound to an action in `[keys]`: one key spec or a list of them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyBinding {
+    fn specs(&self) -> Vec<&str> {
+        match self {
+            KeyBinding::One(spec) => vec![spec.as_str()],
+            KeyBinding::Many(specs) => specs.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+/// Define `Action` with the name, context, description and built-in keys
+/// of each variant, listed in the order of the reference
+macro_rules! actions {
+    ($($action:ident: $name:literal, $context:ident, $description:literal, [$($spec:literal),+];)+) => {
+        /// An action that can be bound to keys
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Action {
+            $($action),+
+        }
+
+        impl Action {
+            /// Every action, in the order of the reference
+            const ALL: &'static [Action] = &[$(Action::$action),+];
+
+            fn entry(self) -> (&'static str, KeyContext, &'static str, &'static [&'static str]) {
+                match self {
+                    $(Action::$action => ($name, KeyContext::$context, $description, &[$($spec),+]),)+
+                }
+            }
+        }
+    };
+}
+
+actions! {
+    Up: "up", Jobs, "Move up the job list", ["up"];
+    Down: "down", Jobs, "Move down the job list", ["down"];
+    Expand: "expand", Jobs, "Expand a het job or a section", ["right"];
+    Collapse: "collapse", Jobs, "Collapse a het job or a section", ["left"];
+    ScrollColumnsRight: "scroll_columns_right", Jobs, "Scroll the columns right of ID and Name", [">"];
+    ScrollColumnsLeft: "scroll_columns_left", Jobs, "Scroll the columns left", ["<"];
+    JobsPageDown: "jobs_page_down", Jobs, "Move a page down the job list", ["pagedown"];
+    JobsPageUp: "jobs_page_up", Jobs, "Move a page up the job list", ["pageup"];
+    FirstJob: "first_job", Jobs, "Go to the first job", ["home"];
+    LastJob: "last_job", Jobs, "Go to the last job", ["end"];
+    User: "user", Jobs, "List other users' jobs", ["u"];
+    Group: "group", Jobs, "Group jobs by account, user or state", ["o"];
+    Select: "select", Jobs, "Select the job", ["space"];
+    SelectAll: "select_all", Jobs, "Select (or deselect) all jobs", ["a"];
+    Visual: "visual", Jobs, "Visual (range) selection", ["V"];
+    Script: "script", Jobs, "View the job script", ["enter"];
+    Refresh: "refresh", Jobs, "Refresh every source now", ["r"];
+    Pause: "pause", Jobs, "Pause or resume background polling", ["p"];
+    Filter: "filter", Jobs, "Open the filters", ["f"];
+    Columns: "columns", Jobs, "Open the column selection", ["c"];
+    Settings: "settings", Jobs, "Open the settings", ["s"];
+    Detail: "detail", Jobs, "Open the job detail pane", ["d"];
+    Logs: "logs", Jobs, "View the job's logs", ["v"];
+    Cancel: "cancel", Jobs, "Cancel the selected jobs", ["x"];
+    Hold: "hold", Jobs, "Hold the selected jobs", ["H"];
+    Release: "release", Jobs, "Release the selected jobs", ["U"];
+    Requeue: "requeue", Jobs, "Requeue the selected jobs", ["Q"];
+    Note: "note", Jobs, "Tag or annotate the job", ["n"];
+    Comment: "comment", Jobs, "Set the Slurm comment of a pending or running job", ["m"];
+    Yank: "yank", Jobs, "Copy the job's ID, nodes, dir or log path", ["Y"];
+    Actions: "actions", Jobs, "Run a custom action on the job", ["!"];
+    OpenWith: "open_with", Jobs, "Open the job's dir or log in a program", ["X"];
+    Protect: "protect", Jobs, "Protect the job, or lift its protection", ["P"];
+    Search: "search", Jobs, "Search squeue and accounting with a form", ["F"];
+    LookbackLonger: "lookback_longer", Jobs, "List ended jobs of a longer window", ["+"];
+    LookbackShorter: "lookback_shorter", Jobs, "List ended jobs of a shorter window", ["-"];
+    RelativeTimes: "relative_times", Jobs, "Show job times relative to now, or as timestamps", ["t"];
+    Watch: "watch", Jobs, "Pin the job to the watchlist, or unpin it", ["w"];
+    Edit: "edit", Jobs, "Edit a pending job", ["e"];
+    Resubmit: "resubmit", Jobs, "Resubmit the job", ["R"];
+    Attach: "attach", Jobs, "Open a shell in the running job (srun --pty)", ["i"];
+    Submit: "submit", Jobs, "Submit from a pasted sbatch command line", ["S"];
+    NewScript: "new_script", Jobs, "Write a new batch script", ["N"];
+    Trace: "trace", Jobs, "Export a Chrome trace of the jobs", ["T"];
+    Export: "export", Jobs, "Export the listed jobs", ["J"];
+    Aggregate: "aggregate", Jobs, "Queue counts per user, partition and state", ["A"];
+    Dependencies: "dependencies", Jobs, "Dependency tree of the pending jobs", ["D"];
+    Pending: "pending", Jobs, "Why jobs are pending", ["W"];
+    Chains: "chains", Jobs, "Requeued and preempted jobs with their later attempts", ["C"];
+    Stats: "stats", Jobs, "Statistics per user, account or partition", ["G"];
+    Trends: "trends", Jobs, "Trends of the session", ["O"];
+    Cron: "cron", Jobs, "Recurring jobs of the scrontab", ["K"];
+    Reservations: "reservations", Jobs, "Active and upcoming reservations", ["M"];
+    Limits: "limits", Jobs, "QOS and association limits", ["L"];
+    Licenses: "licenses", Jobs, "Used and free licenses", ["I"];
+    Cluster: "cluster", Jobs, "The cluster and its nodes, to drain and resume", ["E"];
+    Help: "help", Jobs, "Reference of states, reasons, exit codes and keys", ["?"];
+    Quit: "quit", Jobs, "Leave visual mode, or quit", ["esc"];
+    NextTab: "next_tab", Detail, "Next tab of the detail pane", ["l", "right", "tab"];
+    PrevTab: "prev_tab", Detail, "Previous tab of the detail pane", ["h", "left", "backtab"];
+    NextJob: "next_job", Detail, "Show the next job", ["shift-down"];
+    PrevJob: "prev_job", Detail, "Show the previous job", ["shift-up"];
+    ScrollDown: "scroll_down", Detail, "Scroll the detail pane down", ["down"];
+    ScrollUp: "scroll_up", Detail, "Scroll the detail pane up", ["up"];
+    PageDown: "page_down", Detail, "Scroll the detail pane a page down", ["pagedown"];
+    PageUp: "page_up", Detail, "Scroll the detail pane a page up", ["pageup"];
+    Wider: "wider", Detail, "Widen the detail pane", ["+"];
+    Narrower: "narrower", Detail, "Narrow the detail pane", ["-"];
+    Zoom: "zoom", Detail, "Zoom the detail pane", ["z"];
+    Reload: "reload", Detail, "Reload the detail pane", ["r"];
+    DetailNote: "detail_note", Detail, "Tag or annotate the job", ["n"];
+    DetailComment: "detail_comment", Detail, "Set the Slurm comment of a pending or running job", ["m"];
+    DetailPause: "detail_pause", Detail, "Pause or resume background polling", ["p"];
+    Baseline: "baseline", Detail, "Pin the job as the baseline", ["b"];
+    CloseDetail: "close_detail", Detail, "Close the detail pane", ["q", "d"];
+    SearchUp: "search_up", Search, "Move up the results", ["up"];
+    SearchDown: "search_down", Search, "Move down the results", ["down"];
+    SearchPageUp: "search_page_up", Search, "Move a page up the results", ["pageup"];
+    SearchPageDown: "search_page_down", Search, "Move a page down the results", ["pagedown"];
+    SearchDetail: "search_detail", Search, "Open the result in the detail pane", ["enter"];
+    SearchEdit: "search_edit", Search, "Edit the search", ["F", "e"];
+    SearchRefresh: "search_refresh", Search, "Run the search again", ["r"];
+    SearchTab: "search_tab", Search, "Switch to the job list", ["tab", "backtab"];
+    SearchClose: "search_close", Search, "Close the search", ["esc", "q"];
+    PopupUp: "popup_up", Popup, "Move up the popup", ["up"];
+    PopupDown: "popup_down", Popup, "Move down the popup", ["down"];
+    PopupPageUp: "popup_page_up", Popup, "Scroll the popup a page up", ["pageup"];
+    PopupPageDown: "popup_page_down", Popup, "Scroll the popup a page down", ["pagedown"];
+    PopupRefresh: "popup_refresh", Popup, "Refresh the popup", ["r"];
+    PopupClose: "popup_close", Popup, "Close the popup", ["esc"];
+    DrillDown: "drill_down", Aggregate, "List the jobs of the row", ["enter"];
+    StatsGroup: "stats_group", Stats, "Group by user, account or partition", ["g"];
+    StatsSort: "stats_sort", Stats, "Sort by the next column", ["s"];
+    StatsReverse: "stats_reverse", Stats, "Reverse the sort", ["S"];
+    CronToggle: "cron_toggle", Cron, "Enable or disable the entry", ["space"];
+    CronEdit: "cron_edit", Cron, "Edit the entry", ["enter", "e"];
+    NodeDrain: "node_drain", Nodes, "Drain the node", ["D"];
+    NodeResume: "node_resume", Nodes, "Resume the node", ["R"];
+    RunCustom: "run_action", Actions, "Run the custom action", ["enter"];
+    PagerUp: "pager_up", Pager, "Scroll the script or log up", ["up"];
+    PagerDown: "pager_down", Pager, "Scroll the script or log down", ["down"];
+    PagerPageUp: "pager_page_up", Pager, "Scroll the script or log a page up", ["pageup", "ctrl-u"];
+    PagerPageDown: "pager_page_down", Pager, "Scroll the script or log a page down", ["pagedown", "ctrl-d"];
+    PagerClose: "pager_close", Pager, "Close the script or log", ["q"];
+    ScriptCopy: "script_copy", Script, "Copy the script", ["y"];
+    ScriptSave: "script_save", Script, "Save the script to a file", ["w"];
+    LogStream: "log_stream", Logs, "Switch between stdout and stderr", ["o"];
+    FilterApply: "filter_apply", Filter, "Apply the filters", ["ctrl-a", "f10"];
+    FilterClose: "filter_close", Filter, "Close the filters", ["esc"];
+    ColumnsApply: "columns_apply", Columns, "Apply the columns", ["ctrl-a"];
+    ColumnsFocus: "columns_focus", Columns, "Move to the next list", ["tab"];
+    ColumnsClose: "columns_close", Columns, "Close the column selection", ["esc"];
+    SettingsSave: "settings_save", Settings, "Save the settings", ["ctrl-a"];
+    SettingsEdit: "settings_edit", Settings, "Edit the setting", ["enter"];
+    SettingsClose: "settings_close", Settings, "Close the settings", ["esc"];
+}
+
+impl Action {
+    pub fn all() -> impl Iterator<Item = Action> {
+        Action::ALL.iter().copied()
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::all().find(|a| a.name() == name)
+    }
+
+    /// Its key in `[keys]`
+    pub fn name(self) -> &'static str {
+        self.entry().0
+    }
+
+    pub fn context(self) -> KeyContext {
+        self.entry().1
+    }
+
+    pub fn description(self) -> &'static str {
+        self.entry().2
+    }
+
+    /// The built-in key specs
+    pub fn default_specs(self) -> &'static [&'static str] {
+        self.entry().3
+    }
+}
+
+/// A key press as a view gets it: the action the keymap binds it to, or the
+/// key itself when it is bound to none (or typed into a text box)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Action(Action),
+    Key(KeyEvent),
+}
+
+impl Input {
+    /// Whether this is `code` pressed unbound, Shift aside
+    pub fn is_key(self, code: KeyCode) -> bool {
+        matches!(self, Input::Key(key) if key.code == code)
+    }
+
+    /// Whether this is Ctrl+C, which closes every view
+    pub fn is_ctrl_c(self) -> bool {
+        matches!(self, Input::Key(key)
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+    }
+}
+
+/// What a key press comes to under the keymap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyOutcome {
+    /// The key is bound to this action
+    Action(Action),
+    /// The start of a chord; wait for the next key
+    Pending,
+    /// A built-in key whose action was bound to other keys
+    Unbound,
+    /// Not a key of the keymap: handle it as pressed
+    Other,
+}
+
+/// Key sequences of each action, from the built-in keys and `[keys]`
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Vec<Key>, Action)>,
+    /// Built-in keys of remapped actions, by context
+    unbound: HashSet<(KeyContext, Key)>,
+    /// Keys of the chord typed so far
+    pending: Vec<Key>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&BTreeMap::new()).0
+    }
+}
+
+impl Keymap {
+    /// The keymap of `config`, with a message per unknown action or key spec
+    /// (which are left out) and per sequence that shadows another
+    pub fn new(config: &BTreeMap<String, KeyBinding>) -> (Self, Vec<String>) {
+        let mut problems = Vec::new();
+        let mut bindings = Vec::new();
+        let mut unbound = HashSet::new();
+        for (name, binding) in config {
+            if Action::from_name(name).is_none() {
+                problems.push(format!("unknown action `{}` in [keys]", name));
+            }
+            for spec in binding.specs() {
+                if let Err(e) = parse_sequence(spec) {
+                    problems.push(format!("[keys] {}: {}", name, e));
+                }
+            }
+        }
+
+        for action in Action::all() {
+            let specs: Vec<&str> = match config.get(action.name()) {
+                Some(binding) => {
+                    for spec in action.default_specs() {
+                        if let Ok(key) = parse_key(spec) {
+                            unbound.insert((action.context(), key));
+                        }
+                    }
+                    binding.specs()
+                }
+                None => action.default_specs().to_vec(),
+            };
+            for spec in specs {
+                let Ok(sequence) = parse_sequence(spec) else {
+                    continue;
+                };
+                // A sequence already bound where this action applies is left
+                // to the action listed first
+                let taken = bindings
+                    .iter()
+                    .find(|(bound, other): &&(Vec<Key>, Action)| {
+                        *bound == sequence && overlap(action.context(), other.context())
+                    });
+                match taken {
+                    Some((_, other)) if *other == action => {}
+                    Some((_, other)) => problems.push(format!(
+                        "[keys] `{}` of {} is already bound to {}; it is left out",
+                        format_sequence(&sequence),
+                        action.name(),
+                        other.name()
+                    )),
+                    None => bindings.push((sequence, action)),
+                }
+            }
+        }
+        for (sequence, action) in &bindings {
+            if sequence.len() == 1 {
+                unbound.remove(&(action.context(), sequence[0]));
+            }
+        }
+
+        // A sequence beats the longer ones it starts, as `press` resolves them
+        for (i, (a, action_a)) in bindings.iter().enumerate() {
+            for (b, action_b) in &bindings[i + 1..] {
+                let shorter = a.len().min(b.len());
+                if !overlap(action_a.context(), action_b.context()) || a[..shorter] != b[..shorter]
+                {
+                    continue;
+                }
+                let ((short, short_action), (long, long_action)) = if a.len() < b.len() {
+                    ((a, action_a), (b, action_b))
+                } else {
+                    ((b, action_b), (a, action_a))
+                };
+                problems.push(format!(
+                    "[keys] `{}` of {} cuts off `{}` of {}, which cannot be typed",
+                    format_sequence(short),
+                    short_action.name(),
+                    format_sequence(long),
+                    long_action.name()
+                ));
+            }
+        }
+
+        let keymap = Self {
+            bindings,
+            unbound,
+            pending: Vec::new(),
+        };
+        (keymap, problems)
+    }
+
+    /// Feed a key pressed in `context`
+    pub fn press(&mut self, context: KeyContext, key: KeyEvent) -> KeyOutcome {
+        let key = normalize((key.code, key.modifiers));
+        self.pending.push(key);
+        let mut prefix_of_longer = false;
+        for (sequence, action) in &self.bindings {
+            if !context.includes(action.context()) || !sequence.starts_with(&self.pending) {
+                continue;
+            }
+            if sequence.len() == self.pending.len() {
+                let action = *action;
+                self.pending.clear();
+                return KeyOutcome::Action(action);
+            }
+            prefix_of_longer = true;
+        }
+        if prefix_of_longer {
+            return KeyOutcome::Pending;
+        }
+
+        // A broken chord is dropped; its last key counts on its own
+        let broken = self.pending.len() > 1;
+        self.pending.clear();
+        if broken {
+            return self.press(context, KeyEvent::new(key.0, key.1));
+        }
+        let unbound = |context| self.unbound.contains(&(context, key));
+        if unbound(context) || context.parent().is_some_and(unbound) {
+            KeyOutcome::Unbound
+        } else {
+            KeyOutcome::Other
+        }
+    }
+
+    /// Forget a partly typed chord, e.g. when a popup opens
+    pub fn reset(&mut self) {
+        self.pending.clear();
+    }
+
+    /// The chord typed so far, e.g. `g`
+    pub fn pending(&self) -> Option<String> {
+        (!self.pending.is_empty()).then(|| format_sequence(&self.pending))
+    }
+
+    /// Each action with the keys it is bound to, in the order of the reference
+    pub fn describe(&self) -> Vec<(Action, String)> {
+        Action::all()
+            .map(|action| {
+                let keys = self.keys(action);
+                let keys = if keys.is_empty() {
+                    "unbound".to_string()
+                } else {
+                    keys.join(", ")
+                };
+                (action, keys)
+            })
+            .collect()
+    }
+
+    /// The first keys bound to `action`, e.g. `R`, to name it in messages
+    pub fn label(&self, action: Action) -> String {
+        self.keys(action)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| "unbound".to_string())
+    }
+
+    fn keys(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(sequence, _)| format_sequence(sequence))
+            .collect()
+    }
+}
+
+/// Whether a key can mean actions of both contexts
+fn overlap(a: KeyContext, b: KeyContext) -> bool {
+    a.includes(b) || b.includes(a)
+}
+
+fn normalize((code, modifiers): (KeyCode, KeyModifiers)) -> Key {
+    match code {
+        KeyCode::Char(_) | KeyCode::BackTab => (code, modifiers - KeyModifiers::SHIFT),
+        _ => (code, modifiers),
+    }
+}
+
+/// Keys pressed one after the other, separated by spaces (`g g`)
+pub fn parse_sequence(spec: &str) -> Result<Vec<Key>> {
+    let keys = spec
+        .split_whitespace()
+        .map(parse_key)
+        .collect::<Result<Vec<_>>>()?;
+    if keys.is_empty() {
+        return Err(eyre!("empty key"));
+    }
+    Ok(keys)
+}
+
+/// One key: a character (`x`, `G`, `?`), a named key (`enter`, `esc`, `tab`,
+/// `backtab`, `space`, `up`, `down`, `left`, `right`, `pageup`, `pagedown`,
+/// `home`, `end`, `f1`..`f12`), optionally after `ctrl-`, `alt-` or `shift-`
+pub fn parse_key(spec: &str) -> Result<Key> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = spec;
+    loop {
+        let lower = rest.to_lowercase();
+        let (modifier, len) = if lower.starts_with("ctrl-") {
+            (KeyModifiers::CONTROL, 5)
+        } else if lower.starts_with("alt-") {
+            (KeyModifiers::ALT, 4)
+        } else if lower.starts_with("shift-") {
+            (KeyModifiers::SHIFT, 6)
+        } else {
+            break;
+        };
+        // `-` on its own is a key
+        if rest.len() == len {
+            break;
+        }
+        modifiers |= modifier;
+        rest = &rest[len..];
+    }
+
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match rest.to_lowercase().as_str() {
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n @ 1..=12) => KeyCode::F(n),
+                _ => return Err(eyre!("unknown key `{}`", spec)),
+            },
+        },
+    };
+    // `shift-x` is `X`
+    let code = match code {
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+            KeyCode::Char(c.to_ascii_uppercase())
+        }
+        code => code,
+    };
+    Ok(normalize((code, modifiers)))
+}
+
+pub fn format_key((code, modifiers): Key) -> String {
+    let mut out = String::new();
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        out.push_str("ctrl-");
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        out.push_str("alt-");
+    }
+    if modifiers.contains(KeyModifiers::SHIFT) {
+        out.push_str("shift-");
+    }
+    match code {
+        KeyCode::Char(' ') => out.push_str("space"),
+        KeyCode::Char(c) => out.push(c),
+        KeyCode::F(n) => out.push_str(&format!("f{}", n)),
+        KeyCode::PageUp => out.push_str("pageup"),
+        KeyCode::PageDown => out.push_str("pagedown"),
+        KeyCode::BackTab => out.push_str("backtab"),
+        other => out.push_str(&format!("{:?}", other).to_lowercase()),
+    }
+    out
+}
+
+fn format_sequence(sequence: &[Key]) -> String {
+    sequence
+        .iter()
+        .map(|k| format_key(*k))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(keymap: &mut Keymap, context: KeyContext, spec: &str) -> KeyOutcome {
+        let (code, modifiers) = parse_key(spec).unwrap();
+        keymap.press(context, KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn keymap_remaps_and_chords() {
+        let config: BTreeMap<String, KeyBinding> = toml::from_str(
+            "down = [\"j\", \"down\"]\n\
+             up = [\"k\", \"up\"]\n\
+             cancel = \"g x\"\n\
+             next_tab = \"ctrl-n\"\n\
+             teleport = \"t\"\n\
+             zoom = \"ctrl-\"\n",
+        )
+        .unwrap();
+        let (mut keymap, problems) = Keymap::new(&config);
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].contains("`teleport`"));
+
+        let jobs = KeyContext::Jobs;
+        let named = |name| KeyOutcome::Action(Action::from_name(name).unwrap());
+        assert_eq!(press(&mut keymap, jobs, "j"), named("down"));
+        assert_eq!(press(&mut keymap, jobs, "down"), named("down"));
+        assert_eq!(press(&mut keymap, jobs, "g"), KeyOutcome::Pending);
+        assert_eq!(keymap.pending().as_deref(), Some("g"));
+        assert_eq!(press(&mut keymap, jobs, "x"), named("cancel"));
+        // x alone no longer cancels; a broken chord's last key still counts
+        assert_eq!(press(&mut keymap, jobs, "x"), KeyOutcome::Unbound);
+        assert_eq!(press(&mut keymap, jobs, "g"), KeyOutcome::Pending);
+        assert_eq!(press(&mut keymap, jobs, "r"), named("refresh"));
+        assert_eq!(press(&mut keymap, jobs, "1"), KeyOutcome::Other);
+
+        let detail = KeyContext::Detail;
+        assert_eq!(press(&mut keymap, detail, "ctrl-n"), named("next_tab"));
+        assert_eq!(press(&mut keymap, detail, "l"), KeyOutcome::Unbound);
+        assert_eq!(press(&mut keymap, detail, "H"), KeyOutcome::Other);
+        assert_eq!(press(&mut keymap, detail, "shift-up"), named("prev_job"));
+
+        let cancel = Action::from_name("cancel").unwrap();
+        assert_eq!(cancel, Action::Cancel);
+        let described = keymap.describe();
+        assert!(described.contains(&(cancel, "g x".to_string())));
+        assert_eq!(
+            parse_key("-").unwrap(),
+            (KeyCode::Char('-'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            parse_key("shift-g").unwrap(),
+            (KeyCode::Char('G'), KeyModifiers::NONE)
+        );
+        assert!(parse_key("hyper-x").is_err());
+
+        // The keys shared by the popups apply in each of them
+        let config: BTreeMap<String, KeyBinding> = toml::from_str(
+            "popup_close = \"q\"\n\
+             popup_refresh = \"R\"\n\
+             stats_sort = \"o\"\n",
+        )
+        .unwrap();
+        let (mut popups, problems) = Keymap::new(&config);
+        assert_eq!(
+            problems,
+            ["[keys] `R` of node_resume is already bound to popup_refresh; it is left out"]
+        );
+        let node_resume = Action::from_name("node_resume").unwrap();
+        assert!(popups
+            .describe()
+            .contains(&(node_resume, "unbound".to_string())));
+        let stats = KeyContext::Stats;
+        assert_eq!(press(&mut popups, stats, "q"), named("popup_close"));
+        assert_eq!(press(&mut popups, stats, "esc"), KeyOutcome::Unbound);
+        assert_eq!(press(&mut popups, stats, "o"), named("stats_sort"));
+        assert_eq!(press(&mut popups, stats, "s"), KeyOutcome::Unbound);
+        assert_eq!(
+            press(&mut popups, KeyContext::Nodes, "R"),
+            named("popup_refresh")
+        );
+        assert_eq!(press(&mut popups, KeyContext::Jobs, "q"), KeyOutcome::Other);
+        let config: BTreeMap<String, KeyBinding> =
+            toml::from_str("refresh = \"g\"\ncancel = \"g x\"\n").unwrap();
+        let (_, problems) = Keymap::new(&config);
+        assert_eq!(
+            problems,
+            ["[keys] `g` of refresh cuts off `g x` of cancel, which cannot be typed"]
+        );
+        assert!(Keymap::new(&BTreeMap::new()).1.is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::Result;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::Level;
+
+use crate::config::state_dir;
+
+/// The log is rotated when it grows past this size
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Rotated logs kept besides the current one (`slurmer.log.1` is the newest)
+const KEPT_LOGS: usize = 3;
+
+/// Log to `$XDG_STATE_HOME/slurmer/slurmer.log`: failed Slurm commands, or
+/// with `debug` every command and what could not be parsed. Returns the
+/// path of the log.
+pub fn init(debug: bool) -> Result<PathBuf> {
+    let dir = state_dir()?;
+    fs::create_dir_all(&dir).wrap_err("failed creating state directory")?;
+    let path = dir.join("slurmer.log");
+    let file = RotatingFile::open(&path, MAX_LOG_BYTES, KEPT_LOGS)?;
+    tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .with_target(false)
+        .with_max_level(if debug { Level::DEBUG } else { Level::WARN })
+        .try_init()
+        .map_err(|e| eyre!("failed setting up the log: {}", e))?;
+    Ok(path)
+}
+
+/// A log file that is renamed to `<name>.1` (and older ones to `.2`, ...)
+/// once it holds `max_bytes`, so it never grows without bound
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_bytes: u64,
+    kept: usize,
+}
+
+impl RotatingFile {
+    fn open(path: &Path, max_bytes: u64, kept: usize) -> Result<Self> {
+        let file = append(path).wrap_err_with(|| format!("failed opening {}", path.display()))?;
+        let written = file.metadata().map_or(0, |m| m.len());
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            written,
+            max_bytes,
+            kept,
+        })
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let _ = fs::remove_file(self.rotated(self.kept));
+        for n in (1..self.kept).rev() {
+            let _ = fs::rename(self.rotated(n), self.rotated(n + 1));
+        }
+        if self.kept > 0 {
+            fs::rename(&self.path, self.rotated(1))?;
+        } else {
+            fs::remove_file(&self.path)?;
+        }
+        self.file = append(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_logs_are_rotated_keeping_the_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("slurmer.log");
+        let rotated = |n: usize| dir.path().join(format!("slurmer.log.{}", n));
+        let read = |p: &Path| fs::read_to_string(p).unwrap();
+        let mut log = RotatingFile::open(&path, 10, 2).unwrap();
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            log.write_all(line.as_bytes()).unwrap();
+        }
+        assert_eq!(read(&path), "fourth\n");
+        assert_eq!(read(&rotated(1)), "third\n");
+        assert_eq!(read(&rotated(2)), "second\n");
+        assert!(!rotated(3).exists());
+
+        // A reopened log counts what it already holds
+        let mut log = RotatingFile::open(&path, 10, 2).unwrap();
+        log.write_all(b"fifth\n").unwrap();
+        assert_eq!(read(&path), "fifth\n");
+        assert_eq!(read(&rotated(1)), "fourth\n");
+    }
+}
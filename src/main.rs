@@ -1,40 +1,129 @@
 use color_eyre::Result;
-use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
+use std::process::ExitCode;
 
+mod actions;
 mod app;
+mod baseline;
+mod cli;
+mod clipboard;
 mod config;
+mod efficiency;
+#[cfg(test)]
+mod harness;
+mod history;
+mod keymap;
+mod logging;
+mod metrics;
+mod notes;
+mod notify;
+mod open;
+mod output;
+mod poll;
+mod profile;
+mod protect;
+mod rightsize;
+mod schema;
+mod serve;
+mod session;
 mod slurm;
+mod snapshot;
+mod stats;
+mod timeline;
+mod trace;
 mod ui;
 mod utils;
 
 use app::App;
+use cli::{Capture, Command, ExitStatus, JobFixture};
+use slurm::{mock::MockJobSource, recording};
+use utils::event::{enter_tui, leave_tui, EventConfig, EventHandler};
+
+fn main() -> Result<ExitCode> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // `--debug` goes with any command: it logs every Slurm command run
+    let debug = cli::take_debug(&mut args);
+    // Slurmer works without its log, e.g. on a read-only home
+    let _ = logging::init(debug);
+    let command = match cli::parse_args(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("slurmer: {}", e);
+            return Ok(ExitStatus::ParseError.into());
+        }
+    };
+    let Command::Tui {
+        fixture,
+        export_json,
+        overrides,
+        capture,
+    } = command
+    else {
+        return Ok(cli::run(command).into());
+    };
+    config::set_overrides(overrides);
+    let mock = match fixture {
+        None => None,
+        Some(JobFixture::Demo) => Some(MockJobSource::demo()),
+        Some(JobFixture::File(path)) => match MockJobSource::load(&path) {
+            Ok(mock) => Some(mock),
+            Err(e) => {
+                eprintln!("slurmer: {:#}", e);
+                return Ok(ExitStatus::ParseError.into());
+            }
+        },
+    };
+
+    match &capture {
+        Some(Capture::Record { path, redact }) => {
+            if let Err(e) = recording::start_recording(path, utils::get_username(), *redact) {
+                eprintln!("slurmer: {:#}", e);
+                return Ok(ExitStatus::ParseError.into());
+            }
+        }
+        Some(Capture::Replay(path)) => {
+            if let Err(e) = recording::start_replay(path, utils::get_username()) {
+                eprintln!("slurmer: {:#}", e);
+                return Ok(ExitStatus::ParseError.into());
+            }
+        }
+        None => {}
+    }
+
+    if let Some(path) = export_json {
+        if matches!(capture, Some(Capture::Record { .. })) {
+            recording::finish_on_signal()?;
+        }
+        let result = App::with_events(mock, EventHandler::detached()).and_then(|mut app| {
+            // An unreachable Slurm fails the export rather than writing `[]`
+            app.poll_jobs()?;
+            app.export_json(&path)
+        });
+        finish_recording(&capture);
+        return Ok(match result {
+            Ok(_) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("slurmer: {:#}", e);
+                ExitStatus::SlurmUnavailable.into()
+            }
+        });
+    }
 
-fn main() -> Result<()> {
     // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    let cfg = config::load_config().unwrap_or_default();
+    enter_tui(cfg.mouse_enabled())?;
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
 
     // Create app and run it
-    let mut app = App::new()?;
+    let mut app = App::with_config(mock, EventHandler::new(EventConfig::default()), cfg)?;
     let result = app.run(&mut terminal);
 
     // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    leave_tui()?;
     terminal.show_cursor()?;
+    finish_recording(&capture);
 
     // Handle any errors that occurred during execution
     if let Err(err) = result {
@@ -42,5 +131,19 @@ fn main() -> Result<()> {
         return Err(err);
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Close the archive of `--record`, once the TUI is left
+fn finish_recording(capture: &Option<Capture>) {
+    if let Some(Capture::Record { path, .. }) = capture {
+        match recording::finish() {
+            Ok(runs) => eprintln!(
+                "slurmer: recorded {} Slurm commands to {}",
+                runs,
+                path.display()
+            ),
+            Err(e) => eprintln!("slurmer: {:#}", e),
+        }
+    }
 }
@@ -0,0 +1,293 @@
+use chrono::{DateTime, Local};
+use color_eyre::eyre::WrapErr;
+use color_eyre::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::slurm::hostlist::{expand, jobs_per_node};
+use crate::slurm::{Job, JobState};
+use crate::snapshot::unix_now;
+use crate::timeline::queue_wait;
+
+/// Content type of [`render_metrics`] when served over HTTP
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Totals for one `(user, partition, state)` group
+#[derive(Default)]
+struct Totals {
+    jobs: u64,
+    cpus: u64,
+    gpus: u64,
+    memory_bytes: u64,
+}
+
+/// Metric name, help text and the value it takes from a group
+type Family = (&'static str, &'static str, fn(&Totals) -> u64);
+
+const FAMILIES: [Family; 4] = [
+    ("slurmer_jobs", "Jobs by user, partition and state", |t| {
+        t.jobs
+    }),
+    ("slurmer_cpus", "CPUs requested or allocated", |t| t.cpus),
+    ("slurmer_gpus", "GPUs requested or allocated", |t| t.gpus),
+    (
+        "slurmer_memory_bytes",
+        "Memory requested, summed over nodes",
+        |t| t.memory_bytes,
+    ),
+];
+
+/// Upper bounds, in seconds, of the queue wait histogram buckets
+const QUEUE_WAIT_BUCKETS: [u64; 7] = [60, 300, 900, 3600, 4 * 3600, 12 * 3600, 24 * 3600];
+
+/// Aggregate job metrics in the OpenMetrics text format (also accepted by
+/// Prometheus and the node_exporter textfile collector): job, CPU, GPU and
+/// requested-memory totals per user, partition and state, how long started
+/// jobs waited in the queue per partition, and the running jobs and
+/// allocated CPUs of each node.
+pub fn render_metrics(jobs: &[Job]) -> String {
+    let mut groups: BTreeMap<(&str, &str, String), Totals> = BTreeMap::new();
+    for job in jobs {
+        let totals = groups
+            .entry((&job.user, &job.partition, job.state.to_string()))
+            .or_default();
+        totals.jobs += 1;
+        totals.cpus += u64::from(job.cpus);
+        totals.gpus += u64::from(job.gpus);
+        totals.memory_bytes += job.memory_per_node().unwrap_or(0) * u64::from(job.nodes.max(1));
+    }
+
+    let mut out = String::new();
+    for (name, help, value) in FAMILIES {
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        let _ = writeln!(out, "# HELP {} {}.", name, help);
+        for ((user, partition, state), totals) in &groups {
+            let _ = writeln!(
+                out,
+                "{}{{user=\"{}\",partition=\"{}\",state=\"{}\"}} {}",
+                name,
+                escape_label(user),
+                escape_label(partition),
+                state,
+                value(totals)
+            );
+        }
+    }
+    write_queue_wait(&mut out, jobs, Local::now());
+    write_node_usage(&mut out, jobs);
+    let _ = writeln!(out, "# TYPE slurmer_last_refresh_timestamp_seconds gauge");
+    let _ = writeln!(
+        out,
+        "# HELP slurmer_last_refresh_timestamp_seconds When the jobs were fetched from Slurm."
+    );
+    let _ = writeln!(out, "slurmer_last_refresh_timestamp_seconds {}", unix_now());
+    out.push_str("# EOF\n");
+    out
+}
+
+/// Write [`render_metrics`] to a node_exporter textfile-collector path. The
+/// collector may read at any moment, so write a temp file and rename it.
+pub fn write_textfile(path: &Path, jobs: &[Job]) -> Result<()> {
+    // node_exporter only picks up `*.prom`, so keep the temp file outside that glob
+    let tmp = path.with_extension(format!("prom.{}.tmp", std::process::id()));
+    fs::write(&tmp, render_metrics(jobs))
+        .wrap_err_with(|| format!("failed writing {}", tmp.display()))?;
+    fs::rename(&tmp, path).wrap_err_with(|| format!("failed replacing {}", path.display()))?;
+    Ok(())
+}
+
+/// Histogram of the queue waits of the jobs that started, per partition;
+/// pending jobs have not finished waiting and are left out
+fn write_queue_wait(out: &mut String, jobs: &[Job], now: DateTime<Local>) {
+    let mut waits: BTreeMap<&str, Vec<u64>> = BTreeMap::new();
+    let started = jobs
+        .iter()
+        .filter(|j| j.state != JobState::Pending && j.start_time.is_some());
+    for job in started {
+        if let Some(wait) = queue_wait(job, now) {
+            waits
+                .entry(&job.partition)
+                .or_default()
+                .push(wait.as_secs());
+        }
+    }
+
+    // A family without samples reads as a broken exporter
+    if waits.is_empty() {
+        return;
+    }
+    let name = "slurmer_queue_wait_seconds";
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    let _ = writeln!(
+        out,
+        "# HELP {} Time started jobs waited in the queue.",
+        name
+    );
+    for (partition, waits) in &waits {
+        let partition = escape_label(partition);
+        for bound in QUEUE_WAIT_BUCKETS {
+            let count = waits.iter().filter(|&&w| w <= bound).count();
+            let _ = writeln!(
+                out,
+                "{}_bucket{{partition=\"{}\",le=\"{}\"}} {}",
+                name, partition, bound, count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{partition=\"{}\",le=\"+Inf\"}} {}",
+            name,
+            partition,
+            waits.len()
+        );
+        let _ = writeln!(
+            out,
+            "{}_sum{{partition=\"{}\"}} {}",
+            name,
+            partition,
+            waits.iter().sum::<u64>()
+        );
+        let _ = writeln!(
+            out,
+            "{}_count{{partition=\"{}\"}} {}",
+            name,
+            partition,
+            waits.len()
+        );
+    }
+}
+
+/// Running jobs and their CPUs on each node; a job's CPUs are spread evenly
+/// over its nodes
+fn write_node_usage(out: &mut String, jobs: &[Job]) {
+    let mut cpus: BTreeMap<String, u64> = BTreeMap::new();
+    for job in jobs.iter().filter(|j| j.state == JobState::Running) {
+        let Some(nodes) = &job.node else {
+            continue;
+        };
+        let hosts = expand(nodes);
+        let count = hosts.len().max(1) as u64;
+        let (share, rest) = (u64::from(job.cpus) / count, u64::from(job.cpus) % count);
+        // What does not divide evenly goes to the first nodes, so the
+        // nodes add up to the job's CPUs
+        for (i, host) in hosts.into_iter().enumerate() {
+            *cpus.entry(host).or_default() += share + u64::from((i as u64) < rest);
+        }
+    }
+
+    let counts = jobs_per_node(jobs);
+    let _ = writeln!(out, "# TYPE slurmer_node_jobs gauge");
+    let _ = writeln!(out, "# HELP slurmer_node_jobs Running jobs on the node.");
+    for (node, count) in &counts {
+        let _ = writeln!(
+            out,
+            "slurmer_node_jobs{{node=\"{}\"}} {}",
+            escape_label(node),
+            count
+        );
+    }
+    let _ = writeln!(out, "# TYPE slurmer_node_cpus gauge");
+    let _ = writeln!(
+        out,
+        "# HELP slurmer_node_cpus CPUs allocated to running jobs on the node."
+    );
+    for (node, cpus) in &cpus {
+        let _ = writeln!(
+            out,
+            "slurmer_node_cpus{{node=\"{}\"}} {}",
+            escape_label(node),
+            cpus
+        );
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slurm::{memory::Memory, JobState};
+    use crate::utils::parse_slurm_time;
+
+    #[test]
+    fn metrics_aggregate_per_group() {
+        let job = |state, cpus, gpus| Job {
+            user: "alice".to_string(),
+            partition: "gpu".to_string(),
+            state,
+            cpus,
+            gpus,
+            nodes: 1,
+            memory: Memory::parse("16G"),
+            node: Some("gpu[01-02]".to_string()),
+            submit_time: parse_slurm_time("2024-05-01T10:00:00"),
+            start_time: parse_slurm_time("2024-05-01T10:10:00"),
+            ..Default::default()
+        };
+        let jobs = vec![
+            job(JobState::Running, 8, 2),
+            job(JobState::Running, 4, 1),
+            job(JobState::Pending, 2, 0),
+            Job {
+                user: "we\"ird".to_string(),
+                ..job(JobState::Failed, 1, 0)
+            },
+        ];
+        let text = render_metrics(&jobs);
+
+        assert!(
+            text.contains("slurmer_jobs{user=\"alice\",partition=\"gpu\",state=\"RUNNING\"} 2\n")
+        );
+        assert!(
+            text.contains("slurmer_gpus{user=\"alice\",partition=\"gpu\",state=\"RUNNING\"} 3\n")
+        );
+        assert!(
+            text.contains("slurmer_cpus{user=\"alice\",partition=\"gpu\",state=\"PENDING\"} 2\n")
+        );
+        assert!(text.contains(&format!(
+            "slurmer_memory_bytes{{user=\"alice\",partition=\"gpu\",state=\"RUNNING\"}} {}\n",
+            32u64 << 30
+        )));
+        assert!(text.contains("user=\"we\\\"ird\""));
+
+        // Both running jobs and the failed one waited ten minutes
+        assert!(
+            text.contains("slurmer_queue_wait_seconds_bucket{partition=\"gpu\",le=\"300\"} 0\n")
+        );
+        assert!(
+            text.contains("slurmer_queue_wait_seconds_bucket{partition=\"gpu\",le=\"900\"} 3\n")
+        );
+        assert!(text.contains("slurmer_queue_wait_seconds_sum{partition=\"gpu\"} 1800\n"));
+        assert!(text.contains("slurmer_node_jobs{node=\"gpu02\"} 2\n"));
+        assert!(text.contains("slurmer_node_cpus{node=\"gpu01\"} 6\n"));
+        assert!(text.ends_with("# EOF\n"));
+
+        // Uneven CPUs go to the first node; no started job, no wait family
+        let text = render_metrics(&[job(JobState::Running, 5, 0)]);
+        assert!(text.contains("slurmer_node_cpus{node=\"gpu01\"} 3\n"));
+        assert!(text.contains("slurmer_node_cpus{node=\"gpu02\"} 2\n"));
+        let text = render_metrics(&[job(JobState::Pending, 2, 0)]);
+        assert!(!text.contains("slurmer_queue_wait_seconds"));
+    }
+
+    #[test]
+    fn textfile_is_replaced_whole() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("slurmer.prom");
+        fs::write(&path, "stale").unwrap();
+        write_textfile(&path, &[]).unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("# TYPE slurmer_jobs gauge\n"));
+        // Only the final file is left for the collector to pick up
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
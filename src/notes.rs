@@ -0,0 +1,189 @@
+use color_eyre::eyre::WrapErr;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{data_dir, NotesConfig};
+use crate::snapshot::unix_now;
+
+/// Tags and free-text note attached to a job
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobNote {
+    pub tags: Vec<String>,
+    pub text: String,
+    /// User who last edited the note
+    pub author: String,
+    /// Unix timestamp (seconds) of the last edit; the newest copy wins
+    pub updated: u64,
+}
+
+impl JobNote {
+    /// A cleared note; kept on disk so the clearing wins over older copies
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.text.trim().is_empty()
+    }
+
+    /// Parse a comma- or space-separated tag list
+    pub fn parse_tags(raw: &str) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in raw
+            .split([',', ' '])
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+        {
+            if !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+        tags
+    }
+}
+
+/// Job notes stored one file per job under `<dir>/<cluster>/<job id>.toml`,
+/// in the user's data directory and, optionally, a shared team directory
+/// (e.g. on NFS) so teammates see each other's notes. Reads take the most
+/// recently edited copy (last writer wins).
+pub struct NoteStore {
+    cluster: String,
+    local_dir: Option<PathBuf>,
+    shared_dir: Option<PathBuf>,
+}
+
+impl NoteStore {
+    pub fn new(config: &NotesConfig, cluster: &str) -> Self {
+        let cluster = sanitize(config.cluster.as_deref().unwrap_or(cluster));
+        Self {
+            local_dir: data_dir().ok().map(|d| d.join("notes").join(&cluster)),
+            shared_dir: config
+                .shared_dir
+                .as_deref()
+                .map(str::trim)
+                .filter(|d| !d.is_empty())
+                .map(|d| Path::new(d).join(&cluster)),
+            cluster,
+        }
+    }
+
+    pub fn cluster(&self) -> &str {
+        &self.cluster
+    }
+
+    pub fn is_shared(&self) -> bool {
+        self.shared_dir.is_some()
+    }
+
+    /// The newest non-empty note for `job_id` across the local and shared copies
+    pub fn load(&self, job_id: &str) -> Option<JobNote> {
+        self.dirs()
+            .filter_map(|dir| read_note(&note_path(dir, job_id)))
+            .max_by_key(|note| note.updated)
+            .filter(|note| !note.is_empty())
+    }
+
+    /// Stamp `note` with the current user and time and write it to every store.
+    /// The local copy is written first so an unreachable share loses nothing.
+    pub fn save(&self, job_id: &str, mut note: JobNote) -> Result<JobNote> {
+        note.author = std::env::var("USER").unwrap_or_default();
+        note.updated = unix_now();
+
+        let raw = toml::to_string(&note).wrap_err("failed serializing note")?;
+        for dir in self.dirs() {
+            write_atomic(&note_path(dir, job_id), &raw)
+                .wrap_err_with(|| format!("failed writing note to {}", dir.display()))?;
+        }
+        Ok(note)
+    }
+
+    fn dirs(&self) -> impl Iterator<Item = &PathBuf> {
+        self.local_dir.iter().chain(self.shared_dir.iter())
+    }
+}
+
+/// Keep cluster names and job ids safe as path components
+pub fn sanitize(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match name.trim_start_matches('.') {
+        "" => "default".to_string(),
+        _ => name,
+    }
+}
+
+fn note_path(dir: &Path, job_id: &str) -> PathBuf {
+    dir.join(format!("{}.toml", sanitize(job_id)))
+}
+
+fn read_note(path: &Path) -> Option<JobNote> {
+    let raw = fs::read_to_string(path).ok()?;
+    toml::from_str(&raw).ok()
+}
+
+/// Write through a temp file and rename, so readers on other hosts never see
+/// a half-written note
+pub fn write_atomic(path: &Path, raw: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension(format!("toml.{}.tmp", std::process::id()));
+    fs::write(&tmp, raw)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newest_copy_wins_and_clearing_sticks() {
+        let local = tempfile::tempdir().unwrap();
+        let shared = tempfile::tempdir().unwrap();
+        let store = NoteStore {
+            cluster: "c1".to_string(),
+            local_dir: Some(local.path().to_path_buf()),
+            shared_dir: Some(shared.path().to_path_buf()),
+        };
+        let note = |text: &str, updated: u64| JobNote {
+            text: text.to_string(),
+            author: "someone".to_string(),
+            updated,
+            ..Default::default()
+        };
+
+        // A teammate's newer edit in the shared directory wins over ours
+        let ours = toml::to_string(&note("mine", 100)).unwrap();
+        let theirs = toml::to_string(&note("theirs", 200)).unwrap();
+        write_atomic(&note_path(local.path(), "42"), &ours).unwrap();
+        write_atomic(&note_path(shared.path(), "42"), &theirs).unwrap();
+        assert_eq!(store.load("42").unwrap().text, "theirs");
+
+        // Saving writes both copies, and a cleared note hides older ones
+        let saved = store.save("42", JobNote::default()).unwrap();
+        assert!(saved.updated > 200);
+        assert_eq!(store.load("42"), None);
+        assert_eq!(store.load("43"), None);
+    }
+
+    #[test]
+    fn tags_and_paths_are_cleaned() {
+        assert_eq!(
+            JobNote::parse_tags("baseline, ablation  baseline,,"),
+            vec!["baseline".to_string(), "ablation".to_string()]
+        );
+        assert_eq!(sanitize("../etc"), ".._etc");
+        assert_eq!(sanitize(".."), "default");
+        assert_eq!(sanitize(""), "default");
+        assert_eq!(sanitize("1234_5"), "1234_5");
+    }
+}
@@ -0,0 +1,73 @@
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::{Report, Result};
+use std::io::Write;
+use std::process::Command;
+
+use crate::config::NotifyConfig;
+use crate::slurm::command;
+
+/// A job reaching a terminal state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobFinished {
+    pub job_id: String,
+    /// Raw Slurm state, e.g. `COMPLETED` or `OUT_OF_MEMORY`
+    pub state: String,
+    /// Whether the job completed successfully
+    pub ok: bool,
+}
+
+impl JobFinished {
+    pub fn summary(&self) -> String {
+        format!("Slurm job {} {}", self.job_id, self.state)
+    }
+}
+
+/// Fire every configured notification channel, returning the errors of those that failed.
+pub fn notify_job_finished(config: &NotifyConfig, event: &JobFinished) -> Vec<Report> {
+    let mut errors = Vec::new();
+
+    if config.bell {
+        // stderr keeps the bell out of anything piped from stdout
+        let _ = std::io::stderr().write_all(b"\x07");
+    }
+    if config.desktop {
+        if let Err(e) = desktop_notification(event) {
+            errors.push(e);
+        }
+    }
+    if let Some(command) = config.command.as_deref().filter(|c| !c.trim().is_empty()) {
+        if let Err(e) = run_hook(command, event) {
+            errors.push(e);
+        }
+    }
+
+    errors
+}
+
+fn desktop_notification(event: &JobFinished) -> Result<()> {
+    let urgency = if event.ok { "normal" } else { "critical" };
+    let mut notify_send = Command::new("notify-send");
+    notify_send.args(["--app-name=slurmer", "--urgency", urgency, &event.summary()]);
+    let status = command::run_local(notify_send, None)
+        .wrap_err("desktop notification failed (is notify-send installed?)")?
+        .status;
+    if !status.success() {
+        return Err(eyre!("notify-send exited with {}", status));
+    }
+    Ok(())
+}
+
+fn run_hook(command: &str, event: &JobFinished) -> Result<()> {
+    let mut hook = Command::new("sh");
+    hook.args(["-c", command])
+        .env("SLURMER_JOB_ID", &event.job_id)
+        .env("SLURMER_JOB_STATE", &event.state)
+        .env("SLURMER_JOB_OK", if event.ok { "1" } else { "0" });
+    let status = command::run_local(hook, None)
+        .wrap_err("failed running notify command")?
+        .status;
+    if !status.success() {
+        return Err(eyre!("notify command exited with {}", status));
+    }
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+
+/// What the placeholders of an `[open]` command stand for
+#[derive(Debug, Clone, Default)]
+pub struct OpenTarget {
+    pub id: String,
+    pub workdir: Option<String>,
+    pub stdout: Option<String>,
+}
+
+/// `template` with `{id}`, `{workdir}` and `{stdout}` replaced by the job's,
+/// each quoted for the shell. A placeholder the job has no value for is an
+/// error, rather than a command run on an empty path.
+pub fn expand(template: &str, target: &OpenTarget) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        // A name runs to the next brace; an unclosed `{`, or one opening
+        // another brace first, is the shell's
+        let Some(end) = after
+            .find(['{', '}'])
+            .filter(|&end| after[end..].starts_with('}'))
+        else {
+            out.push('{');
+            rest = after;
+            continue;
+        };
+        let value = match &after[..end] {
+            "id" => Some(target.id.as_str()),
+            "workdir" => Some(
+                target
+                    .workdir
+                    .as_deref()
+                    .ok_or_else(|| eyre!("job {} has no work dir", target.id))?,
+            ),
+            "stdout" => Some(
+                target
+                    .stdout
+                    .as_deref()
+                    .ok_or_else(|| eyre!("job {} has no stdout log", target.id))?,
+            ),
+            // Shell braces (`${EDITOR:-vi}`, `{a,b}`) are left alone
+            _ => None,
+        };
+        match value {
+            Some(value) => out.push_str(&shell_quote(value)),
+            None => {
+                out.push('{');
+                out.push_str(&after[..=end]);
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// `value` in single quotes, for `sh -c`
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_quoted_and_shell_braces_kept() {
+        let target = OpenTarget {
+            id: "42".to_string(),
+            workdir: Some("/scratch/it's here".to_string()),
+            stdout: None,
+        };
+        assert_eq!(
+            expand("${EDITOR:-vi} {workdir}", &target).unwrap(),
+            r"${EDITOR:-vi} '/scratch/it'\''s here'"
+        );
+        assert_eq!(
+            expand("seff {id} | less", &target).unwrap(),
+            "seff '42' | less"
+        );
+        let missing = expand("less {stdout}", &target).unwrap_err();
+        assert_eq!(missing.to_string(), "job 42 has no stdout log");
+    }
+
+    #[test]
+    fn unclosed_and_nested_braces_are_scanned_once() {
+        let target = OpenTarget {
+            id: "42".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(expand("echo { {id}", &target).unwrap(), "echo { '42'");
+        assert_eq!(expand("awk '{print", &target).unwrap(), "awk '{print");
+        assert_eq!(
+            expand("echo ${JOB:-{id}}", &target).unwrap(),
+            "echo ${JOB:-'42'}"
+        );
+        assert_eq!(expand("ls {a,{id}}", &target).unwrap(), "ls {a,'42'}");
+    }
+}
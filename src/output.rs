@@ -0,0 +1,350 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::{DateTime, Local};
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::{Report, Result};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::slurm::memory::MemoryScope;
+use crate::slurm::Job;
+use crate::ui::columns::JobColumn;
+use crate::ui::jobslist::column_value;
+use crate::utils::{format_slurm_duration, format_slurm_time};
+
+/// Fields of `slurmer list`
+pub const LIST_FIELDS: &[&str] = &[
+    "id",
+    "name",
+    "state",
+    "partition",
+    "time",
+    "time_limit",
+    "node",
+    "pending_reason",
+];
+
+/// Fields of `slurmer export`, named as in fixtures
+pub const EXPORT_FIELDS: &[&str] = &[
+    "id",
+    "name",
+    "user",
+    "state",
+    "time",
+    "time_limit",
+    "total_cpu",
+    "nodes",
+    "node",
+    "cpus",
+    "memory",
+    "gpus",
+    "gres",
+    "partition",
+    "qos",
+    "account",
+    "priority",
+    "work_dir",
+    "submit_time",
+    "start_time",
+    "end_time",
+    "pending_reason",
+    "estimated_start",
+    "exit_code",
+    "derived_exit_code",
+    "max_rss",
+];
+
+/// `jobs` as a JSON array, with every parsed field
+pub fn jobs_json(jobs: &[Job]) -> String {
+    json(&jobs)
+}
+
+/// Write [`jobs_json`] to `path`, or to stdout when it is `-`
+pub fn write_jobs_json(path: &Path, jobs: &[Job]) -> Result<()> {
+    write_output(path, &jobs_json(jobs))
+}
+
+/// Write `raw` to `path`, or to stdout when it is `-`
+pub fn write_output(path: &Path, raw: &str) -> Result<()> {
+    if path == Path::new("-") {
+        print!("{}", raw);
+        return Ok(());
+    }
+    fs::write(path, raw).wrap_err_with(|| format!("failed writing {}", path.display()))
+}
+
+/// How the headless subcommands print records (`--output`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    Csv,
+    /// Tab-separated, quoted like CSV
+    Tsv,
+    /// Aligned columns for people
+    Table,
+}
+
+impl FromStr for OutputFormat {
+    type Err = Report;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "table" => Ok(OutputFormat::Table),
+            other => Err(eyre!(
+                "invalid --output value: {} (json, csv, tsv, table)",
+                other
+            )),
+        }
+    }
+}
+
+/// Rows of named fields, one per job (or per field of a job)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Records {
+    pub fields: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Records {
+    /// `fields` of each job, as Slurm prints them; empty when unknown
+    pub fn of_jobs(jobs: &[Job], fields: &[&str]) -> Self {
+        let rows = jobs
+            .iter()
+            .map(|job| fields.iter().map(|f| job_field(job, f)).collect())
+            .collect();
+        Self {
+            fields: fields.iter().map(|f| f.to_string()).collect(),
+            rows,
+        }
+    }
+
+    /// The table columns of each job, titled as in the job list; unknown
+    /// values are empty
+    pub fn of_columns(jobs: &[Job], columns: &[JobColumn]) -> Self {
+        let now = Local::now();
+        let rows = jobs
+            .iter()
+            .map(|job| {
+                columns
+                    .iter()
+                    .map(|&c| column_value(job, c, now).unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+        Self {
+            fields: columns.iter().map(|c| c.title().to_string()).collect(),
+            rows,
+        }
+    }
+
+    /// A JSON array of objects, CSV or TSV with a header line, or a table
+    pub fn render(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Json => {
+                let rows: Vec<Row> = self
+                    .rows
+                    .iter()
+                    .map(|values| Row {
+                        fields: &self.fields,
+                        values,
+                    })
+                    .collect();
+                json(&rows)
+            }
+            OutputFormat::Csv | OutputFormat::Tsv => std::iter::once(&self.fields)
+                .chain(&self.rows)
+                .map(|row| delimited_line(row, format))
+                .collect(),
+            OutputFormat::Table => {
+                let header: Vec<String> = self.fields.iter().map(|f| f.to_uppercase()).collect();
+                table(std::iter::once(&header).chain(&self.rows))
+            }
+        }
+    }
+}
+
+/// One record as `(field, value)` pairs: a JSON object, CSV with a header
+/// line, or one field per line
+pub fn render_record(pairs: &[(String, String)], format: OutputFormat) -> String {
+    let (fields, values): (Vec<String>, Vec<String>) = pairs.iter().cloned().unzip();
+    match format {
+        OutputFormat::Json => json(&Row {
+            fields: &fields,
+            values: &values,
+        }),
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            delimited_line(&fields, format) + &delimited_line(&values, format)
+        }
+        OutputFormat::Table => {
+            let rows: Vec<Vec<String>> = pairs
+                .iter()
+                .map(|(f, v)| vec![f.clone(), v.clone()])
+                .collect();
+            table(&rows)
+        }
+    }
+}
+
+/// A JSON object keeping the field order; unknown values are `null`
+struct Row<'a> {
+    fields: &'a [String],
+    values: &'a [String],
+}
+
+impl Serialize for Row<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.fields.len()))?;
+        for (field, value) in self.fields.iter().zip(self.values) {
+            map.serialize_entry(field, &Some(value).filter(|v| !v.is_empty()))?;
+        }
+        map.end()
+    }
+}
+
+fn json(value: &impl Serialize) -> String {
+    // Strings and maps of strings always serialize
+    serde_json::to_string_pretty(value).unwrap_or_default() + "\n"
+}
+
+/// RFC 4180: values with separators, quotes or line breaks are quoted
+fn delimited_line(values: &[String], format: OutputFormat) -> String {
+    let separator = if format == OutputFormat::Tsv {
+        '\t'
+    } else {
+        ','
+    };
+    let quoted: Vec<String> = values
+        .iter()
+        .map(|v| {
+            if v.contains([separator, '"', '\n', '\r']) {
+                format!("\"{}\"", v.replace('"', "\"\""))
+            } else {
+                v.clone()
+            }
+        })
+        .collect();
+    quoted.join(&separator.to_string()) + "\n"
+}
+
+/// Columns padded to their widest value, two spaces apart
+fn table<'a>(rows: impl IntoIterator<Item = &'a Vec<String>> + Clone) -> String {
+    let mut widths: Vec<usize> = Vec::new();
+    for row in rows.clone() {
+        widths.resize(widths.len().max(row.len()), 0);
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+    let mut out = String::new();
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(value, &width)| format!("{:<width$}", value, width = width))
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// The value of a fixture field, by name, as Slurm prints it
+pub fn job_field(job: &Job, field: &str) -> String {
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    let time =
+        |value: &Option<DateTime<Local>>| value.as_ref().map(format_slurm_time).unwrap_or_default();
+    let duration =
+        |value: Option<std::time::Duration>| value.map(format_slurm_duration).unwrap_or_default();
+    match field {
+        "id" => job.id.clone(),
+        "name" => job.name.clone(),
+        "user" => job.user.clone(),
+        "state" => job.state.to_string(),
+        "time" => duration((!job.time.is_zero()).then_some(job.time)),
+        "time_limit" => duration(job.time_limit),
+        "total_cpu" => duration(job.total_cpu),
+        "nodes" => job.nodes.to_string(),
+        "node" => text(&job.node),
+        "cpus" => job.cpus.to_string(),
+        "memory" => job
+            .memory
+            .map(|m| {
+                let suffix = if m.scope == MemoryScope::Cpu { "c" } else { "" };
+                format!("{}{}", m.to_slurm(), suffix)
+            })
+            .unwrap_or_default(),
+        "gpus" => job.gpus.to_string(),
+        "gres" => text(&job.gres),
+        "partition" => job.partition.clone(),
+        "qos" => job.qos.clone(),
+        "account" => text(&job.account),
+        "priority" => job.priority.map(|p| p.to_string()).unwrap_or_default(),
+        "work_dir" => text(&job.work_dir),
+        "submit_time" => time(&job.submit_time),
+        "start_time" => time(&job.start_time),
+        "end_time" => time(&job.end_time),
+        "pending_reason" => text(&job.pending_reason),
+        "estimated_start" => time(&job.estimated_start),
+        "exit_code" => text(&job.exit_code),
+        "derived_exit_code" => text(&job.derived_exit_code),
+        "max_rss" => text(&job.max_rss),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_render_in_every_format() {
+        let records = Records {
+            fields: vec!["id".to_string(), "name".to_string()],
+            rows: vec![
+                vec!["12".to_string(), "train, \"big\"".to_string()],
+                vec!["7".to_string(), String::new()],
+            ],
+        };
+        assert_eq!(
+            records.render(OutputFormat::Csv),
+            "id,name\n12,\"train, \"\"big\"\"\"\n7,\n"
+        );
+        assert_eq!(
+            records.render(OutputFormat::Table),
+            "ID  NAME\n12  train, \"big\"\n7\n"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&records.render(OutputFormat::Json)).unwrap();
+        assert_eq!(json[0]["name"], "train, \"big\"");
+        assert!(json[1]["name"].is_null());
+
+        let pairs = vec![
+            ("JobID".to_string(), "12".to_string()),
+            ("State".to_string(), "RUNNING".to_string()),
+        ];
+        assert_eq!(
+            render_record(&pairs, OutputFormat::Table),
+            "JobID  12\nState  RUNNING\n"
+        );
+        assert!(render_record(&pairs, OutputFormat::Json).starts_with("{\n  \"JobID\": \"12\""));
+        let job = Job {
+            id: "12".to_string(),
+            time: std::time::Duration::from_secs(90),
+            ..Default::default()
+        };
+        let json: serde_json::Value = serde_json::from_str(&jobs_json(&[job])).unwrap();
+        assert_eq!(json[0]["time_secs"], 90);
+        assert_eq!(json[0]["state"], "OTHER");
+        assert!(json[0].get("het_components").is_none());
+        assert_eq!(
+            records.render(OutputFormat::Tsv),
+            "id\tname\n12\t\"train, \"\"big\"\"\"\n7\t\n"
+        );
+        assert_eq!("csv".parse::<OutputFormat>().unwrap(), OutputFormat::Csv);
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+}
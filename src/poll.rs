@@ -0,0 +1,293 @@
+use std::fmt::Display;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::Semaphore;
+
+/// Consecutive failures after which the delay stops doubling (32x the interval)
+const MAX_BACKOFF_STEPS: u32 = 5;
+
+/// A source is polled at most this often relative to how long it takes, so
+/// a command that took 3s waits at least 30s before it runs again
+const SLOW_FACTOR: u32 = 10;
+
+/// When a data source (squeue, sacct, sinfo) was polled, how that went, and
+/// when it is due again. Delays double with each failure in a row and grow
+/// with slow commands, so a struggling controller is not polled harder.
+#[derive(Debug, Clone)]
+pub struct Poller {
+    pub name: &'static str,
+    last_attempt: Option<Instant>,
+    last_success: Option<Instant>,
+    took: Duration,
+    failures: u32,
+    /// Why the last attempt failed, while it is failing
+    error: Option<String>,
+}
+
+impl Poller {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            last_attempt: None,
+            last_success: None,
+            took: Duration::ZERO,
+            failures: 0,
+            error: None,
+        }
+    }
+
+    /// Time to wait after an attempt, for the configured `interval`
+    pub fn delay(&self, interval: Duration) -> Duration {
+        let backoff = interval.saturating_mul(1 << self.failures.min(MAX_BACKOFF_STEPS));
+        backoff.max(self.took.saturating_mul(SLOW_FACTOR))
+    }
+
+    pub fn due(&self, interval: Duration, now: Instant) -> bool {
+        self.last_attempt
+            .is_none_or(|at| now.saturating_duration_since(at) >= self.delay(interval))
+    }
+
+    /// Make the source due at once (a manual refresh, changed filters)
+    pub fn expire(&mut self) {
+        self.last_attempt = None;
+    }
+
+    /// Note an attempt that ran from `started` until `finished`
+    pub fn record(&mut self, started: Instant, finished: Instant, ok: bool) {
+        self.took = finished.saturating_duration_since(started);
+        self.last_attempt = Some(finished);
+        if ok {
+            self.last_success = Some(finished);
+            self.failures = 0;
+            self.error = None;
+        } else {
+            self.failures += 1;
+        }
+    }
+
+    /// Note an attempt that ran from `started` until now, keeping why it failed
+    pub fn record_result<T, E: Display>(&mut self, started: Instant, result: &Result<T, E>) {
+        self.record_result_between(started, Instant::now(), result);
+    }
+
+    /// Note an attempt that ran from `started` until `finished` (in a pool,
+    /// see [`run_pooled`]), keeping why it failed
+    pub fn record_result_between<T, E: Display>(
+        &mut self,
+        started: Instant,
+        finished: Instant,
+        result: &Result<T, E>,
+    ) {
+        self.record(started, finished, result.is_ok());
+        if let Err(e) = result {
+            self.error = Some(e.to_string());
+        }
+    }
+
+    /// How long the last attempt took; `None` before the first
+    pub fn took(&self) -> Option<Duration> {
+        self.last_attempt.map(|_| self.took)
+    }
+
+    /// Why the last attempt failed, if it did
+    pub fn error(&self) -> Option<&str> {
+        (self.failures > 0).then_some(self.error.as_deref().unwrap_or("failed"))
+    }
+
+    /// `squeue 4s ago, next 6s`, or `sacct failing (2), next 8m`; `interval`
+    /// is `None` while polling of the source is paused
+    pub fn status(&self, interval: Option<Duration>, now: Instant) -> String {
+        let last = match (self.failures, self.last_success) {
+            (0, Some(at)) => format!("{} ago", short(now.saturating_duration_since(at))),
+            (0, None) => "not yet".to_string(),
+            (n, _) => format!("failing ({})", n),
+        };
+        let next = match (interval, self.last_attempt) {
+            (None, _) => "paused".to_string(),
+            (Some(_), None) => "next now".to_string(),
+            (Some(interval), Some(at)) => {
+                let due = at + self.delay(interval);
+                format!("next {}", short(due.saturating_duration_since(now)))
+            }
+        };
+        format!("{} {}, {}", self.name, last, next)
+    }
+}
+
+/// A fetch to run in a pool
+pub type Task<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// What a task returned, and when it ran
+#[derive(Debug)]
+pub struct Timed<T> {
+    pub started: Instant,
+    pub finished: Instant,
+    pub value: T,
+}
+
+/// The fetch pool the Slurm commands of the app run in, polled, fetched off
+/// the UI thread or waited for on a key (all but the cluster lookups at
+/// startup and the log view's blocking path lookups): at most `limit` at
+/// a time, so the controller never gets every query at once, whether they
+/// come from one refresh or from the detail pane besides it
+#[derive(Debug, Clone)]
+pub struct Pool {
+    slots: Arc<Semaphore>,
+    stagger: Duration,
+}
+
+impl Pool {
+    pub fn new(limit: usize, stagger: Duration) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(limit.max(1))),
+            stagger,
+        }
+    }
+
+    /// Run `tasks` side by side in the pool, each starting at least
+    /// `stagger` after the one before it, so a refresh takes about as long
+    /// as its slowest command. Results are in the order of `tasks`; those
+    /// of a task that panicked are missing.
+    pub async fn run<T: Send + 'static>(&self, tasks: Vec<Task<T>>) -> Vec<Timed<T>> {
+        let base = tokio::time::Instant::now();
+        let handles: Vec<_> = tasks
+            .into_iter()
+            .enumerate()
+            .map(|(i, task)| {
+                let slots = Arc::clone(&self.slots);
+                let turn = base + self.stagger * i as u32;
+                tokio::spawn(async move {
+                    tokio::time::sleep_until(turn).await;
+                    let _slot = slots.acquire_owned().await;
+                    timed(task).await
+                })
+            })
+            .collect();
+        let mut results = Vec::with_capacity(handles.len());
+        for handle in handles {
+            if let Ok(timed) = handle.await {
+                results.push(timed);
+            }
+        }
+        results
+    }
+
+    /// Run one task in the pool, when a slot is free
+    pub async fn run_one<T>(&self, task: impl Future<Output = T>) -> Timed<T> {
+        let _slot = self.slots.acquire().await;
+        timed(task).await
+    }
+}
+
+/// `task`'s value, with when it started and finished (on the runtime's
+/// clock, so tests can pause it)
+async fn timed<T>(task: impl Future<Output = T>) -> Timed<T> {
+    let started = tokio::time::Instant::now().into_std();
+    let value = task.await;
+    Timed {
+        started,
+        finished: tokio::time::Instant::now().into_std(),
+        value,
+    }
+}
+
+/// `45s`, `12m`, `3h`
+fn short(duration: Duration) -> String {
+    match duration.as_secs() {
+        s if s < 120 => format!("{}s", s),
+        s if s < 7200 => format!("{}m", s / 60),
+        s => format!("{}h", s / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_on_failures_and_slow_commands() {
+        let interval = Duration::from_secs(10);
+        let start = Instant::now();
+        let mut poller = Poller::new("squeue");
+        assert!(poller.due(interval, start));
+        assert_eq!(
+            poller.status(Some(interval), start),
+            "squeue not yet, next now"
+        );
+
+        let at = |secs| start + Duration::from_secs(secs);
+        poller.record(start, at(1), true);
+        assert_eq!(poller.delay(interval), interval);
+        assert!(!poller.due(interval, at(10)));
+        assert!(poller.due(interval, at(11)));
+
+        poller.record(at(11), at(12), false);
+        poller.record(at(32), at(33), false);
+        assert_eq!(poller.delay(interval), Duration::from_secs(40));
+        assert_eq!(
+            poller.status(Some(interval), at(43)),
+            "squeue failing (2), next 30s"
+        );
+
+        // A 5s command waits at least 50s, even after it succeeds
+        poller.record(at(73), at(78), true);
+        assert_eq!(poller.delay(interval), Duration::from_secs(50));
+        assert_eq!(poller.status(None, at(80)), "squeue 2s ago, paused");
+
+        assert_eq!(poller.took(), Some(Duration::from_secs(5)));
+
+        poller.expire();
+        assert!(poller.due(interval, at(80)));
+
+        poller.record_result(at(80), &Err::<(), _>("slurm_load_jobs error"));
+        assert_eq!(poller.error(), Some("slurm_load_jobs error"));
+        poller.record_result(at(90), &Ok::<_, String>(()));
+        assert_eq!(poller.error(), None);
+    }
+
+    #[test]
+    fn pools_run_tasks_side_by_side_up_to_the_limit() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .unwrap();
+        let sleep = |ms: u64| -> Task<u64> {
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(ms)).await;
+                ms
+            })
+        };
+        let pool = Pool::new(3, Duration::from_millis(20));
+        let (start, results, elapsed) = runtime.block_on(async {
+            let start = tokio::time::Instant::now();
+            let tasks = vec![sleep(200), sleep(200), sleep(200), sleep(10)];
+            let results = pool.run(tasks).await;
+            (start.into_std(), results, start.elapsed())
+        });
+        let values: Vec<u64> = results.iter().map(|t| t.value).collect();
+        assert_eq!(values, [200, 200, 200, 10]);
+        // Three at a time: the last waits for a free slot, not for all
+        assert_eq!(elapsed, Duration::from_millis(240));
+        assert_eq!(results[3].started, results[0].finished);
+        assert!(results[3].finished < results[2].finished);
+        // Each task starts no sooner than its turn
+        assert_eq!(results[2].started, start + Duration::from_millis(40));
+
+        // A task run on its own waits for a slot too
+        let (start, one) = runtime.block_on(async {
+            let start = tokio::time::Instant::now();
+            let batch = pool.run(vec![sleep(100), sleep(100), sleep(100)]);
+            let one = async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                pool.run_one(sleep(10)).await
+            };
+            let (_, one) = futures_lite::future::zip(batch, one).await;
+            (start.into_std(), one)
+        });
+        assert_eq!(one.started, start + Duration::from_millis(100));
+    }
+}
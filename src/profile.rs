@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::config::{config_file_path, data_dir};
+
+/// Version of the profile archive format
+const PROFILE_VERSION: u32 = 1;
+
+/// A user's Slurmer setup in one file: everything under the config
+/// directory (config.toml with its presets, protect patterns, notify
+/// channels, ...) and the job notes and tags of the data directory. State
+/// tied to one machine (the action queue, probed cluster capabilities) is
+/// left out.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub version: u32,
+    /// File contents by archive path, `config/...` or `notes/...`
+    pub files: BTreeMap<String, String>,
+}
+
+/// Where the archive's top-level directories live on this machine
+#[derive(Debug, Clone)]
+pub struct ProfileRoots {
+    pub config: PathBuf,
+    pub notes: PathBuf,
+}
+
+impl ProfileRoots {
+    /// The user's config and data directories
+    pub fn user() -> Result<Self> {
+        let config = config_file_path()?
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| eyre!("config file has no directory"))?;
+        Ok(Self {
+            config,
+            notes: data_dir()?.join("notes"),
+        })
+    }
+
+    fn iter(&self) -> [(&'static str, &Path); 2] {
+        [("config", &self.config), ("notes", &self.notes)]
+    }
+
+    /// The local path of an archive path, refusing anything that would
+    /// escape its root (absolute paths, `..`)
+    fn resolve(&self, archive_path: &str) -> Result<PathBuf> {
+        let (top, rest) = archive_path
+            .split_once('/')
+            .ok_or_else(|| eyre!("invalid profile path: {}", archive_path))?;
+        let root = self
+            .iter()
+            .into_iter()
+            .find(|(name, _)| *name == top)
+            .map(|(_, root)| root)
+            .ok_or_else(|| eyre!("invalid profile path: {}", archive_path))?;
+        let rest = Path::new(rest);
+        if !rest.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(eyre!("invalid profile path: {}", archive_path));
+        }
+        Ok(root.join(rest))
+    }
+}
+
+/// What `import` did with each file of the profile
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportReport {
+    pub written: Vec<String>,
+    /// Existing files that differ from the profile's and were kept
+    pub kept: Vec<String>,
+}
+
+impl Profile {
+    /// Bundle the text files under `roots`
+    pub fn collect(roots: &ProfileRoots) -> Result<Self> {
+        let mut files = BTreeMap::new();
+        for (name, root) in roots.iter() {
+            if !root.is_dir() {
+                continue;
+            }
+            for entry in WalkDir::new(root).sort_by_file_name() {
+                let entry = entry.wrap_err_with(|| format!("failed reading {}", root.display()))?;
+                // Skip what a concurrent writer left half done
+                let path = entry.path();
+                if !entry.file_type().is_file() || path.to_string_lossy().ends_with(".tmp") {
+                    continue;
+                }
+                let Ok(raw) = fs::read_to_string(path) else {
+                    continue;
+                };
+                let relative = path.strip_prefix(root).unwrap_or(path);
+                let key = format!("{}/{}", name, relative.to_string_lossy());
+                files.insert(key, raw);
+            }
+        }
+        Ok(Self {
+            version: PROFILE_VERSION,
+            files,
+        })
+    }
+
+    pub fn parse(raw: &str) -> Result<Self> {
+        let profile: Profile = toml::from_str(raw).wrap_err("failed parsing profile")?;
+        if profile.version > PROFILE_VERSION {
+            return Err(eyre!(
+                "profile version {} is newer than this slurmer supports ({})",
+                profile.version,
+                PROFILE_VERSION
+            ));
+        }
+        Ok(profile)
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string(self).wrap_err("failed serializing profile")
+    }
+
+    /// Write the profile's files under `roots`. Files that exist with other
+    /// contents are kept unless `force`. Every path is checked before
+    /// anything is written.
+    pub fn import(&self, roots: &ProfileRoots, force: bool) -> Result<ImportReport> {
+        let targets = self
+            .files
+            .iter()
+            .map(|(key, raw)| Ok((key, raw, roots.resolve(key)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut report = ImportReport::default();
+        for (key, raw, path) in targets {
+            match fs::read_to_string(&path) {
+                Ok(existing) if existing == *raw => continue,
+                Ok(_) if !force => {
+                    report.kept.push(key.clone());
+                    continue;
+                }
+                _ => {}
+            }
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)
+                    .wrap_err_with(|| format!("failed creating {}", dir.display()))?;
+            }
+            fs::write(&path, raw).wrap_err_with(|| format!("failed writing {}", path.display()))?;
+            report.written.push(key.clone());
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_round_trip_without_clobbering() {
+        let home = tempfile::tempdir().unwrap();
+        let roots = ProfileRoots {
+            config: home.path().join("config"),
+            notes: home.path().join("notes"),
+        };
+        fs::create_dir_all(roots.notes.join("alpha")).unwrap();
+        fs::create_dir_all(&roots.config).unwrap();
+        fs::write(
+            roots.config.join("config.toml"),
+            "[refresh]\nsqueue_secs = 5\n",
+        )
+        .unwrap();
+        fs::write(roots.notes.join("alpha/42.toml"), "tags = [\"baseline\"]\n").unwrap();
+        fs::write(roots.notes.join("alpha/43.toml.7.tmp"), "half").unwrap();
+
+        let profile =
+            Profile::parse(&Profile::collect(&roots).unwrap().to_toml().unwrap()).unwrap();
+        assert_eq!(
+            profile.files.keys().collect::<Vec<_>>(),
+            ["config/config.toml", "notes/alpha/42.toml"]
+        );
+
+        let other = tempfile::tempdir().unwrap();
+        let target = ProfileRoots {
+            config: other.path().join("config"),
+            notes: other.path().join("notes"),
+        };
+        fs::create_dir_all(&target.config).unwrap();
+        fs::write(target.config.join("config.toml"), "# mine\n").unwrap();
+        let report = profile.import(&target, false).unwrap();
+        assert_eq!(report.written, ["notes/alpha/42.toml"]);
+        assert_eq!(report.kept, ["config/config.toml"]);
+        let report = profile.import(&target, true).unwrap();
+        assert_eq!(report.written, ["config/config.toml"]);
+
+        let mut evil = Profile::default();
+        evil.files
+            .insert("config/../../.bashrc".to_string(), String::new());
+        assert!(evil.import(&target, true).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use regex::Regex;
+
+use crate::config::ProtectConfig;
+use crate::notes::JobNote;
+use crate::slurm::{command::JobAction, Job};
+
+/// Note tag that protects a single job
+pub const PROTECTED_TAG: &str = "protected";
+
+/// Which jobs need a typed confirmation before they are cancelled or requeued
+pub struct Protection {
+    names: Vec<Regex>,
+}
+
+impl Protection {
+    /// Name patterns that are not valid regexes are skipped and returned
+    pub fn new(config: &ProtectConfig) -> (Self, Vec<String>) {
+        let mut names = Vec::new();
+        let mut invalid = Vec::new();
+        for pattern in &config.names {
+            match Regex::new(pattern) {
+                Ok(re) => names.push(re),
+                Err(_) => invalid.push(pattern.clone()),
+            }
+        }
+        (Self { names }, invalid)
+    }
+
+    /// Why `job` is protected, if it is
+    pub fn reason(&self, job: &Job, note: Option<&JobNote>) -> Option<String> {
+        if note.is_some_and(|n| n.tags.iter().any(|t| t == PROTECTED_TAG)) {
+            return Some(format!("tagged {}", PROTECTED_TAG));
+        }
+        self.names
+            .iter()
+            .find(|re| re.is_match(&job.name))
+            .map(|re| format!("name matches {}", re.as_str()))
+    }
+}
+
+/// What has to be typed to apply `action` to `count` protected jobs
+pub fn confirmation_phrase(action: JobAction, count: usize) -> String {
+    format!("{} {}", action.verb(), count)
+}
+
+/// Toggle the protected tag of a note; returns whether the job is now protected
+pub fn toggle_protected(note: &mut JobNote) -> bool {
+    if let Some(i) = note.tags.iter().position(|t| t == PROTECTED_TAG) {
+        note.tags.remove(i);
+        false
+    } else {
+        note.tags.push(PROTECTED_TAG.to_string());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jobs_are_protected_by_tag_or_name() {
+        let config = ProtectConfig {
+            names: vec!["^prod-".to_string(), "([".to_string()],
+        };
+        let (protection, invalid) = Protection::new(&config);
+        assert_eq!(invalid, ["(["]);
+
+        let job = |name: &str| Job {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            protection.reason(&job("prod-train"), None).as_deref(),
+            Some("name matches ^prod-")
+        );
+        assert_eq!(protection.reason(&job("debug"), None), None);
+
+        let mut note = JobNote::default();
+        assert!(toggle_protected(&mut note));
+        assert_eq!(
+            protection.reason(&job("debug"), Some(&note)).as_deref(),
+            Some("tagged protected")
+        );
+        assert!(!toggle_protected(&mut note));
+        assert!(note.tags.is_empty());
+        assert_eq!(confirmation_phrase(JobAction::Cancel, 2), "cancel 2");
+    }
+}
@@ -0,0 +1,342 @@
+use color_eyre::eyre::WrapErr;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::data_dir;
+use crate::notes::{sanitize, write_atomic};
+use crate::slurm::{memory::Memory, sacct::PastRun, Job, JobState};
+use crate::utils::format_slurm_duration;
+
+/// Days of accounting read for past runs of a job
+pub const LOOKBACK_DAYS: u32 = 60;
+
+/// Runs needed before anything is suggested
+const MIN_RUNS: usize = 3;
+
+/// Share of runs whose usage the suggestion covers
+const PERCENTILE: f64 = 0.95;
+
+/// Headroom added on top of the percentile
+const MARGIN: f64 = 0.2;
+
+/// Runs kept per job name, newest first
+const MAX_RUNS: usize = 50;
+
+/// What one run of a job used, as sacct recorded it when it ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRun {
+    pub job_id: String,
+    pub name: String,
+    pub state: String,
+    pub elapsed_secs: u64,
+    pub time_limit_secs: Option<u64>,
+    pub total_cpu_secs: Option<u64>,
+    pub cpus: u32,
+    /// Peak RSS of the busiest node over the steps
+    pub max_rss_bytes: Option<u64>,
+    /// Requested per node
+    pub memory_bytes: Option<u64>,
+}
+
+impl UsageRun {
+    /// The run of an ended job; `None` while it is queued or running
+    pub fn of(run: &PastRun) -> Option<Self> {
+        let job = &run.job;
+        if job.state.is_active() || job.id.is_empty() {
+            return None;
+        }
+        Some(Self {
+            job_id: job.id.clone(),
+            name: job.name.clone(),
+            state: job.state.to_string(),
+            elapsed_secs: job.time.as_secs(),
+            time_limit_secs: job.time_limit.map(|l| l.as_secs()),
+            total_cpu_secs: job.total_cpu.map(|t| t.as_secs()),
+            cpus: job.cpus,
+            max_rss_bytes: run.node_rss_bytes,
+            memory_bytes: job.memory_per_node(),
+        })
+    }
+
+    fn completed(&self) -> bool {
+        self.state == JobState::Completed.to_string()
+    }
+
+    fn timed_out(&self) -> bool {
+        self.state == JobState::Timeout.to_string()
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct HistoryFile {
+    runs: Vec<UsageRun>,
+}
+
+/// Usage of past runs, kept under the data directory in
+/// `usage/<cluster>.toml` so suggestions outlive sacct's retention
+pub struct UsageHistory {
+    path: Option<PathBuf>,
+    runs: Vec<UsageRun>,
+}
+
+impl UsageHistory {
+    pub fn load(cluster: &str) -> Self {
+        let path = data_dir()
+            .ok()
+            .map(|d| d.join("usage").join(format!("{}.toml", sanitize(cluster))));
+        let runs = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|raw| toml::from_str::<HistoryFile>(&raw).ok())
+            .map(|file| file.runs)
+            .unwrap_or_default();
+        Self { path, runs }
+    }
+
+    /// Add the runs not recorded yet, dropping the oldest of a name past
+    /// `MAX_RUNS`; returns how many were new
+    pub fn merge(&mut self, runs: Vec<UsageRun>) -> usize {
+        let mut added = 0;
+        for run in runs {
+            if !self.runs.iter().any(|r| r.job_id == run.job_id) {
+                self.runs.push(run);
+                added += 1;
+            }
+        }
+        // Job ids grow, so the largest are the newest
+        self.runs
+            .sort_by_key(|r| std::cmp::Reverse(job_number(&r.job_id)));
+        let mut kept = std::collections::HashMap::new();
+        self.runs.retain(|r| {
+            let count = kept.entry(r.name.clone()).or_insert(0);
+            *count += 1;
+            *count <= MAX_RUNS
+        });
+        added
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let file = HistoryFile {
+            runs: self.runs.clone(),
+        };
+        let raw = toml::to_string(&file).wrap_err("failed serializing usage history")?;
+        write_atomic(path, &raw).wrap_err_with(|| format!("failed writing {}", path.display()))
+    }
+
+    pub fn runs_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a UsageRun> {
+        self.runs.iter().filter(move |r| r.name == name)
+    }
+}
+
+fn job_number(id: &str) -> u64 {
+    let digits: String = id.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().unwrap_or(0)
+}
+
+/// Requests that would have fit past runs: the 95th percentile of what they
+/// used plus 20%, and a time limit past those that runs were killed at
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    /// Completed runs it is based on
+    pub runs: usize,
+    /// Runs killed at their time limit, which only show the limit was short;
+    /// the suggested limit is above theirs
+    pub timeouts: usize,
+    pub time_limit: Option<Duration>,
+    /// Per node
+    pub memory: Option<Memory>,
+    pub cpus: Option<u32>,
+}
+
+impl Suggestion {
+    /// What to request next time, given past runs of the job
+    pub fn from_runs<'a>(runs: impl IntoIterator<Item = &'a UsageRun>) -> Option<Self> {
+        let runs: Vec<&UsageRun> = runs.into_iter().collect();
+        let completed: Vec<&&UsageRun> = runs.iter().filter(|r| r.completed()).collect();
+        if completed.len() < MIN_RUNS {
+            return None;
+        }
+        let with_margin = |values: Vec<f64>| percentile(values).map(|v| v * (1.0 + MARGIN));
+
+        let timeouts: Vec<&&UsageRun> = runs.iter().filter(|r| r.timed_out()).collect();
+        // A run killed at its limit needed more, so the margin goes on the limit
+        let timed_out_at = timeouts
+            .iter()
+            .map(|r| {
+                r.time_limit_secs
+                    .unwrap_or(r.elapsed_secs)
+                    .max(r.elapsed_secs)
+            })
+            .max()
+            .map(|secs| secs as f64 * (1.0 + MARGIN));
+        let time_limit = with_margin(completed.iter().map(|r| r.elapsed_secs as f64).collect())
+            .into_iter()
+            .chain(timed_out_at)
+            .reduce(f64::max)
+            .map(|secs| Duration::from_secs(round_up(secs.round() as u64, 5 * 60)));
+        let memory = with_margin(
+            completed
+                .iter()
+                .filter_map(|r| r.max_rss_bytes)
+                .map(|b| b as f64)
+                .collect(),
+        )
+        .map(|bytes| {
+            let step = if bytes >= 1024.0 * 1024.0 * 1024.0 {
+                1024 * 1024 * 1024
+            } else {
+                256 * 1024 * 1024
+            };
+            Memory {
+                bytes: round_up(bytes as u64, step),
+                ..Default::default()
+            }
+        });
+        let cpus = with_margin(
+            completed
+                .iter()
+                .filter(|r| r.elapsed_secs > 0)
+                .filter_map(|r| Some(r.total_cpu_secs? as f64 / r.elapsed_secs as f64))
+                .collect(),
+        )
+        .map(|cores| (cores.ceil() as u32).max(1));
+
+        Some(Self {
+            runs: completed.len(),
+            timeouts: timeouts.len(),
+            time_limit,
+            memory,
+            cpus,
+        })
+    }
+
+    /// Lines for the resubmit form, comparing with what `job` asked for
+    pub fn describe(&self, job: &Job) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Suggested from {} past runs (p95 + {:.0}%):",
+            self.runs,
+            MARGIN * 100.0
+        )];
+        let was =
+            |current: Option<String>| current.map_or(String::new(), |c| format!(" (was {})", c));
+        if let Some(limit) = self.time_limit {
+            lines.push(format!(
+                "  Time limit {}{}",
+                format_slurm_duration(limit),
+                was(job.time_limit.map(format_slurm_duration))
+            ));
+        }
+        if let Some(memory) = self.memory {
+            let current = job.memory_per_node().map(|bytes| {
+                Memory {
+                    bytes,
+                    ..Default::default()
+                }
+                .to_string()
+            });
+            lines.push(format!(
+                "  Memory {} per node{}",
+                memory.to_slurm(),
+                was(current)
+            ));
+        }
+        if let Some(cpus) = self.cpus {
+            let current = (job.cpus > 0).then(|| job.cpus.to_string());
+            lines.push(format!("  CPUs {}{}", cpus, was(current)));
+        }
+        if self.timeouts > 0 {
+            lines.push(format!(
+                "  {} run(s) hit the time limit; the limit is raised past theirs",
+                self.timeouts
+            ));
+        }
+        lines
+    }
+}
+
+/// The nearest-rank percentile `PERCENTILE` of `values`
+fn percentile(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let rank = (PERCENTILE * values.len() as f64).ceil() as usize;
+    Some(values[rank.clamp(1, values.len()) - 1])
+}
+
+fn round_up(value: u64, step: u64) -> u64 {
+    value.div_ceil(step).max(1) * step
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(id: &str, state: &str, elapsed_mins: u64, rss_gib: u64) -> UsageRun {
+        UsageRun {
+            job_id: id.to_string(),
+            name: "train".to_string(),
+            state: state.to_string(),
+            elapsed_secs: elapsed_mins * 60,
+            time_limit_secs: Some(24 * 3600),
+            total_cpu_secs: Some(elapsed_mins * 60 * 3),
+            cpus: 16,
+            max_rss_bytes: Some(rss_gib * 1024 * 1024 * 1024),
+            memory_bytes: Some(64 * 1024 * 1024 * 1024),
+        }
+    }
+
+    #[test]
+    fn suggests_the_p95_of_past_runs_with_a_margin() {
+        let mut history = UsageHistory {
+            path: None,
+            runs: Vec::new(),
+        };
+        let added = history.merge(vec![
+            run("101", "COMPLETED", 100, 10),
+            run("102", "COMPLETED", 90, 8),
+            run("103", "COMPLETED", 110, 12),
+        ]);
+        assert_eq!(added, 3);
+        let suggestion = Suggestion::from_runs(history.runs_named("train")).unwrap();
+        assert_eq!(suggestion.runs, 3);
+        // p95 of 90, 100 and 110 minutes is 110; +20% is 132, up to 135
+        assert_eq!(suggestion.time_limit, Some(Duration::from_secs(135 * 60)));
+        assert_eq!(
+            history.merge(vec![
+                run("103", "COMPLETED", 110, 12),
+                run("104", "TIMEOUT", 1440, 9)
+            ]),
+            1
+        );
+
+        let suggestion = Suggestion::from_runs(history.runs_named("train")).unwrap();
+        // Killed at a day, so a day plus 20% (28:48), up to 28:50
+        assert_eq!(
+            suggestion.time_limit,
+            Some(Duration::from_secs((28 * 60 + 50) * 60))
+        );
+        assert_eq!(suggestion.memory.unwrap().to_slurm(), "15G");
+        assert_eq!(suggestion.cpus, Some(4));
+        assert_eq!(suggestion.timeouts, 1);
+
+        let job = Job {
+            cpus: 16,
+            memory: Memory::parse("64G"),
+            time_limit: Some(Duration::from_secs(24 * 3600)),
+            ..Default::default()
+        };
+        let lines = suggestion.describe(&job);
+        assert_eq!(lines[1], "  Time limit 1-04:50:00 (was 1-00:00:00)");
+        assert_eq!(lines[2], "  Memory 15G per node (was 64G)");
+        assert_eq!(lines[3], "  CPUs 4 (was 16)");
+
+        assert_eq!(Suggestion::from_runs(history.runs_named("eval")), None);
+    }
+}
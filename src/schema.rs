@@ -0,0 +1,966 @@
+use serde::de::{self, DeserializeOwned, Deserializer, Visitor};
+use serde::Serialize;
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::config::{
+    config_file_path, AdminConfig, ColorsConfig, CustomAction, DefaultsConfig, FilterPreset,
+    LayoutConfig, MetricsConfig, NotesConfig, NotifyConfig, OpenConfig, ProtectConfig,
+    RefreshConfig, SlurmerConfig, TimeConfig,
+};
+use crate::keymap::{parse_sequence, Action, KeyBinding};
+use crate::slurm::JobState;
+use crate::ui::columns::JobColumn;
+use crate::ui::theme::parse_color;
+use crate::utils::parse_zone;
+
+/// What each filter accepts, by field of `FilterPreset`. The field names
+/// themselves come from serde, so a field missing here fails the tests.
+const FILTER_DOCS: &[(&str, &str, &str)] = &[
+    ("name", "TEXT", "Name of the preset (presets only)."),
+    (
+        "user",
+        "me | * | USER",
+        "Whose jobs are queried: \"me\" (or unset) for you, \"*\" for everyone.",
+    ),
+    (
+        "states",
+        "[STATE, ...]",
+        "Job states to list; any of the states below, by name or short code.",
+    ),
+    ("partitions", "[PARTITION, ...]", "Partitions to list."),
+    ("qos", "[QOS, ...]", "Quality of service levels to list."),
+    (
+        "accounts",
+        "[ACCOUNT, ...]",
+        "Accounts (projects) whose jobs are listed.",
+    ),
+    (
+        "wckeys",
+        "[WCKEY, ...]",
+        "Workload characterization keys whose jobs are listed.",
+    ),
+    (
+        "name_filter",
+        "REGEX",
+        "Regular expression matched anywhere in the job name.",
+    ),
+    (
+        "node_filter",
+        "REGEX",
+        "Regular expression matched anywhere in the node list.",
+    ),
+    (
+        "gpu_only",
+        "true | false",
+        "Only jobs that request or use GPUs.",
+    ),
+    (
+        "start_within_hours",
+        "HOURS",
+        "Only pending jobs expected to start within this many hours.",
+    ),
+    (
+        "recent_hours",
+        "HOURS",
+        "How far back ended jobs are read from sacct.",
+    ),
+];
+
+/// Config keys by dotted path; `[[presets]]` fields are in `FILTER_DOCS`
+const CONFIG_DOCS: &[(&str, &str)] = &[
+    (
+        "slurm_logs_dir",
+        "Directory searched for job logs; SLURMER_SLURM_LOGS_DIR overrides it.",
+    ),
+    (
+        "mouse",
+        "Mouse clicks, double-clicks and scrolling in the TUI; off by default, which leaves the mouse to the terminal, to select text.",
+    ),
+    (
+        "presets",
+        "Named filter presets, bound to the number keys in order.",
+    ),
+    (
+        "hints",
+        "Remediation hints by state or pending reason; replace the built-in text, an empty string hides it.",
+    ),
+    (
+        "actions",
+        "Shell commands run on the job under the cursor from the action menu (!), with the placeholders of [open].",
+    ),
+    ("actions.name", "Name shown in the menu and the command palette."),
+    (
+        "actions.command",
+        "Shell command; {id}, {workdir} and {stdout} stand for the job's.",
+    ),
+    ("actions.key", "Key running it in the action menu."),
+    (
+        "actions.background",
+        "Start it and return at once (a server, a copy) rather than wait to show its output.",
+    ),
+    ("notify", "How finished jobs are announced."),
+    ("notify.desktop", "Desktop notification through notify-send."),
+    ("notify.bell", "Ring the terminal bell."),
+    (
+        "notify.command",
+        "Shell command to run, with SLURMER_JOB_ID, SLURMER_JOB_STATE and SLURMER_JOB_OK (1/0) set.",
+    ),
+    (
+        "notify.tui",
+        "Also notify when one of your listed jobs ends while the TUI is open.",
+    ),
+    ("notes", "Where job notes are kept."),
+    (
+        "notes.shared_dir",
+        "Team directory (e.g. on NFS) shared with other Slurmer users.",
+    ),
+    (
+        "notes.cluster",
+        "Cluster name used to key notes; detected from Slurm when unset.",
+    ),
+    ("metrics", "Metrics export for Prometheus."),
+    (
+        "metrics.textfile",
+        "node_exporter textfile-collector file (*.prom) rewritten on every refresh.",
+    ),
+    ("admin", "Cluster operator actions, off unless enabled."),
+    (
+        "admin.node_actions",
+        "Drain and resume nodes from the nodes view (needs Slurm operator rights).",
+    ),
+    (
+        "open",
+        "Programs a job's work dir and logs are opened with (X): shell commands in which {id}, {workdir} and {stdout} stand for the job's, run with the TUI suspended.",
+    ),
+    ("open.workdir", "Command opening the work dir."),
+    ("open.stdout", "Command opening the stdout log."),
+    (
+        "open.commands",
+        "Further commands by name, offered under the number keys in name order.",
+    ),
+    (
+        "protect",
+        "Jobs that need a typed confirmation before they are cancelled or requeued.",
+    ),
+    (
+        "protect.names",
+        "Regexes on the job name; jobs tagged `protected` are protected too.",
+    ),
+    (
+        "refresh",
+        "Polling of each data source; delays grow while a command fails or is slow.",
+    ),
+    (
+        "refresh.cache_ttl_secs",
+        "Seconds identical squeue/sacct/sstat queries reuse a result (0: never).",
+    ),
+    (
+        "refresh.command_timeout_secs",
+        "Seconds a Slurm command may run before it is killed (0: no limit).",
+    ),
+    ("refresh.squeue_secs", "Seconds between squeue polls of the job list."),
+    (
+        "refresh.sacct_secs",
+        "Seconds between sacct queries for recently ended jobs.",
+    ),
+    (
+        "refresh.sinfo_secs",
+        "Seconds between sinfo queries for the partition list.",
+    ),
+    (
+        "refresh.sacct_max_rows",
+        "Ended jobs read from sacct at most; the rest are left out (0: all).",
+    ),
+    (
+        "refresh.sacct_window_hours",
+        "Hours covered by each sacct query; longer look backs are split (0: one query).",
+    ),
+    (
+        "refresh.unfocused_secs",
+        "Seconds between refreshes while the terminal is unfocused (0 pauses polling).",
+    ),
+    (
+        "refresh.start_estimate_secs",
+        "Seconds between `squeue --start` queries for the Est. Start column.",
+    ),
+    (
+        "refresh.max_concurrent",
+        "Slurm commands of one refresh run at the same time at most (1: one after the other).",
+    ),
+    (
+        "refresh.stagger_ms",
+        "Milliseconds between the starts of the commands of one refresh.",
+    ),
+    (
+        "theme",
+        "Colors of the TUI: \"default\" (or \"dark\") for the terminal's, \"light\", \"solarized\", or \"mono\" for none (also when NO_COLOR is set).",
+    ),
+    (
+        "colors",
+        "Colors of UI elements over the theme's: a name (\"yellow\", \"lightblue\"), a 256-color index (\"214\") or \"#rrggbb\"; unset keeps the theme's. Colors the terminal cannot show fall back to the closest it can.",
+    ),
+    ("colors.pending", "Pending and other waiting states, and warnings."),
+    ("colors.running", "Running states."),
+    ("colors.completed", "Completed jobs."),
+    ("colors.failed", "Failed states and errors."),
+    ("colors.cancelled", "Cancelled jobs."),
+    ("colors.border", "Borders, titles and highlights."),
+    ("colors.text", "Bright text."),
+    ("colors.dim", "Help lines and secondary text."),
+    ("colors.header", "Background of table headers."),
+    ("colors.background", "Background of popups."),
+    ("colors.selection", "Background of the selected row (instead of reversed colors)."),
+    ("defaults", "What the TUI lists when it starts, and 0 returns to."),
+    (
+        "defaults.user",
+        "Whose jobs are listed: \"me\" for you, \"*\" for everyone, or a user name.",
+    ),
+    ("defaults.recent_hours", "Hours ended jobs are listed for."),
+    (
+        "defaults.partitions",
+        "Partitions to list; empty lists every partition.",
+    ),
+    (
+        "defaults.columns",
+        "Columns of the job list by title (\"id\", \"name\", \"time left\", ...); empty keeps the built-in ones.",
+    ),
+    ("layout", "How the views fit narrow terminals (tmux panes, split windows)."),
+    (
+        "layout.stack_below",
+        "Terminal width below which the detail pane is stacked under the job list rather than beside it (0: always beside).",
+    ),
+    (
+        "layout.breakpoints",
+        "Column sets of the job list for narrower terminals, each a table of `below` (a width) and `columns` (as in [defaults]); the narrowest that applies wins over the chosen columns.",
+    ),
+    ("time", "How the submit, start and end times of jobs are shown (t switches)."),
+    (
+        "time.relative",
+        "Start with relative times (\"13m ago\", \"in ~2h\") rather than timestamps.",
+    ),
+    (
+        "time.zone",
+        "Time zone (\"Europe/Berlin\", which follows daylight saving time, or \"+02:00\", \"-0700\", \"UTC\") timestamps are shown in, when yours differs from the cluster's; unset shows the cluster's clock.",
+    ),
+    (
+        "defaults.restore_session",
+        "Start where the last session was left (filters, columns, sort, cursor and detail tab); command-line filters and columns still win.",
+    ),
+    (
+        "keys",
+        "Keys of each action of the job list and the detail pane: a key, a chord of keys separated by spaces (\"g g\"), or a list of them. Keys are characters or enter, esc, tab, backtab, space, up, down, left, right, pageup, pagedown, home, end and f1 to f12, optionally after ctrl-, alt- or shift-. Binding an action replaces its built-in keys.",
+    ),
+];
+
+/// A table of the config, with the field names serde expects and the
+/// values it defaults to
+struct Section {
+    key: &'static str,
+    /// `[[key]]`, a list of tables
+    array: bool,
+    fields: &'static [&'static str],
+    defaults: toml::Table,
+}
+
+fn sections() -> Vec<Section> {
+    fn section<T: DeserializeOwned + Serialize + Default>(key: &'static str) -> Section {
+        Section {
+            key,
+            array: false,
+            fields: field_names::<T>(),
+            defaults: toml::Value::try_from(T::default())
+                .ok()
+                .and_then(|v| v.as_table().cloned())
+                .unwrap_or_default(),
+        }
+    }
+    vec![
+        Section {
+            array: true,
+            ..section::<FilterPreset>("presets")
+        },
+        Section {
+            array: true,
+            ..section::<CustomAction>("actions")
+        },
+        section::<NotifyConfig>("notify"),
+        section::<NotesConfig>("notes"),
+        section::<MetricsConfig>("metrics"),
+        section::<AdminConfig>("admin"),
+        section::<OpenConfig>("open"),
+        section::<ProtectConfig>("protect"),
+        section::<RefreshConfig>("refresh"),
+        section::<ColorsConfig>("colors"),
+        section::<DefaultsConfig>("defaults"),
+        section::<LayoutConfig>("layout"),
+        section::<TimeConfig>("time"),
+        keys_section(),
+    ]
+}
+
+/// `[keys]` has a field per action, bound to its built-in keys
+fn keys_section() -> Section {
+    static FIELDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+    let fields = FIELDS.get_or_init(|| Action::all().map(Action::name).collect());
+    let defaults = Action::all()
+        .map(|action| {
+            let specs: Vec<String> = action
+                .default_specs()
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            let binding = match specs.as_slice() {
+                [spec] => KeyBinding::One(spec.clone()),
+                _ => KeyBinding::Many(specs),
+            };
+            let value = toml::Value::try_from(binding).expect("key bindings serialize");
+            (action.name().to_string(), value)
+        })
+        .collect();
+    Section {
+        key: "keys",
+        array: false,
+        fields,
+        defaults,
+    }
+}
+
+/// Default of a top-level key that is not a section
+fn top_level_default(field: &str) -> &'static str {
+    match field {
+        "hints" => "{}",
+        "theme" => "\"default\"",
+        "mouse" => "false",
+        _ => "unset",
+    }
+}
+
+fn config_doc(key: &str) -> &'static str {
+    if let Some(action) = key.strip_prefix("keys.").and_then(Action::from_name) {
+        return action.description();
+    }
+    CONFIG_DOCS
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map_or("", |(_, doc)| doc)
+}
+
+fn filter_doc(field: &str) -> (&'static str, &'static str) {
+    FILTER_DOCS
+        .iter()
+        .find(|(f, _, _)| *f == field)
+        .map_or(("", ""), |(_, syntax, doc)| (syntax, doc))
+}
+
+/// `slurmer help config`: every key of config.toml with its default
+pub fn config_help() -> String {
+    let path = config_file_path()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| "~/.config/slurmer/config.toml".to_string());
+    let mut out = format!(
+        "CONFIGURATION\n    Read from {}. Every key is optional; unset keys take the defaults shown.\n",
+        path
+    );
+    let sections = sections();
+    let _ = write!(out, "\nTop level\n");
+    for field in field_names::<SlurmerConfig>() {
+        if sections.iter().any(|s| s.key == *field) {
+            continue;
+        }
+        let _ = write!(
+            out,
+            "    {} = {}\n        {}\n",
+            field,
+            top_level_default(field),
+            config_doc(field)
+        );
+    }
+    for section in &sections {
+        let header = if section.array {
+            format!("[[{}]]", section.key)
+        } else {
+            format!("[{}]", section.key)
+        };
+        let _ = write!(out, "\n{}\n    {}\n", header, config_doc(section.key));
+        for field in section.fields {
+            let doc = if section.key == "presets" {
+                filter_doc(field).1
+            } else {
+                config_doc(&format!("{}.{}", section.key, field))
+            };
+            let default = section
+                .defaults
+                .get(*field)
+                .map_or_else(|| "unset".to_string(), |v| v.to_string());
+            let _ = write!(out, "    {} = {}\n        {}\n", field, default, doc);
+        }
+    }
+    out
+}
+
+/// `slurmer config init`: a config.toml with every key described and
+/// commented out at its default, to uncomment and edit
+pub fn config_template() -> String {
+    let mut out = String::from(
+        "# Slurmer configuration. Every key is optional: the commented-out values\n\
+         # are the defaults. `slurmer help config` describes each key and\n\
+         # `slurmer check-config` checks this file.\n",
+    );
+    let sections = sections();
+    for field in field_names::<SlurmerConfig>() {
+        if sections.iter().any(|s| s.key == *field) {
+            continue;
+        }
+        let _ = write!(out, "\n# {}\n", config_doc(field));
+        let default = match top_level_default(field) {
+            "unset" => "\"\"",
+            default => default,
+        };
+        let _ = writeln!(out, "# {} = {}", field, default);
+    }
+    for section in &sections {
+        if section.array {
+            // A list of tables has no default; show one entry
+            let example = match section.key {
+                "presets" => "# name = \"mine\"\n# user = \"me\"\n",
+                _ => "# name = \"tensorboard\"\n# command = \"tensorboard --logdir {workdir}/logs\"\n# key = \"t\"\n# background = true\n",
+            };
+            let _ = write!(
+                out,
+                "\n# {}\n# [[{}]]\n{}",
+                config_doc(section.key),
+                section.key,
+                example
+            );
+            continue;
+        }
+        let _ = write!(out, "\n# {}\n[{}]\n", config_doc(section.key), section.key);
+        for field in section.fields {
+            let default = section
+                .defaults
+                .get(*field)
+                .map_or_else(|| "\"\"".to_string(), |v| v.to_string());
+            let _ = write!(
+                out,
+                "# {}\n# {} = {}\n",
+                config_doc(&format!("{}.{}", section.key, field)),
+                field,
+                default
+            );
+        }
+    }
+    out
+}
+
+/// `slurmer help filters`: what each filter (popup field or preset key)
+/// accepts
+pub fn filter_help() -> String {
+    let mut out = String::from(
+        "FILTERS\n    \
+         The filter popup (f) and [[presets]] in config.toml take the same filters.\n    \
+         Lists are comma separated in the popup and TOML arrays in presets; empty\n    \
+         or unset filters match every job.\n\n",
+    );
+    for field in field_names::<FilterPreset>() {
+        let (syntax, doc) = filter_doc(field);
+        let _ = writeln!(out, "    {} = {}\n        {}", field, syntax, doc);
+    }
+    let states: Vec<String> = JobState::get_available_states()
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let _ = write!(out, "\nSTATE\n");
+    for line in wrap(&states.join(" "), 72) {
+        let _ = writeln!(out, "    {}", line);
+    }
+    out.push_str(
+        "\nREGEX\n    \
+         Rust regex syntax: `.` any character, `^`/`$` anchors, `a|b` either,\n    \
+         `[0-9]+` a number, `(?i)` to ignore case.\n",
+    );
+    out
+}
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.len() + 1 + word.len() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+/// A mistake in config.toml, at a 1-based line and column
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigProblem {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    /// The closest valid key or value
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean `{}`?)", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Problems of the config file, with its path; none when there is no file
+pub fn check_config() -> color_eyre::Result<(PathBuf, Vec<ConfigProblem>)> {
+    let path = config_file_path()?;
+    let problems = match fs::read_to_string(&path) {
+        Ok(raw) => validate_config(&raw),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    Ok((path, problems))
+}
+
+/// Check `raw` against the config types: syntax errors and mistyped values
+/// (which stop the file from loading), then keys Slurmer does not read and
+/// state names it does not know (which would be ignored)
+pub fn validate_config(raw: &str) -> Vec<ConfigProblem> {
+    if let Err(e) = toml::from_str::<SlurmerConfig>(raw) {
+        let offset = e.span().map_or(0, |s| s.start);
+        let (line, column) = line_column(raw, offset);
+        return vec![ConfigProblem {
+            line,
+            column,
+            message: e.message().trim().to_string(),
+            suggestion: None,
+        }];
+    }
+    let Ok(table) = raw.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+
+    let top = field_names::<SlurmerConfig>();
+    let sections = sections();
+    let mut locator = Locator::new(raw);
+    let mut problems = Vec::new();
+    let mut unknown = |locator: &mut Locator, section: Option<&str>, key: &str, known: &[&str]| {
+        let (line, column) = locator.key(section, key);
+        let message = match section {
+            Some(section) if sections.iter().any(|s| s.key == section && s.array) => {
+                format!("unknown key `{}` in [[{}]]", key, section)
+            }
+            Some(section) => format!("unknown key `{}` in [{}]", key, section),
+            None => format!("unknown key `{}`", key),
+        };
+        problems.push(ConfigProblem {
+            line,
+            column,
+            message,
+            suggestion: closest(key, known.iter().copied()),
+        });
+    };
+
+    for (key, value) in &table {
+        if !top.contains(&key.as_str()) {
+            unknown(&mut locator, None, key, top);
+            continue;
+        }
+        let Some(section) = sections.iter().find(|s| s.key == key) else {
+            continue;
+        };
+        let tables: Vec<&toml::Table> = match value {
+            toml::Value::Table(t) => vec![t],
+            toml::Value::Array(items) => items.iter().filter_map(|v| v.as_table()).collect(),
+            _ => Vec::new(),
+        };
+        for table in tables {
+            for field in table.keys() {
+                if !section.fields.contains(&field.as_str()) {
+                    unknown(&mut locator, Some(section.key), field, section.fields);
+                }
+            }
+        }
+    }
+
+    let states: Vec<String> = JobState::get_available_states()
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let presets = table.get("presets").and_then(|p| p.as_array());
+    for state in presets
+        .into_iter()
+        .flatten()
+        .filter_map(|p| p.get("states")?.as_array())
+        .flatten()
+        .filter_map(|s| s.as_str())
+    {
+        if state
+            .parse::<JobState>()
+            .is_ok_and(|s| s != JobState::Other)
+        {
+            continue;
+        }
+        let (line, column) = locator.value("presets", "states", state);
+        problems.push(ConfigProblem {
+            line,
+            column,
+            message: format!("unknown job state `{}` in [[presets]] states", state),
+            suggestion: closest(&state.to_uppercase(), states.iter().map(String::as_str)),
+        });
+    }
+
+    let titles: Vec<String> = JobColumn::all()
+        .iter()
+        .map(|c| c.title().to_lowercase())
+        .collect();
+    let breakpoint_columns = table
+        .get("layout")
+        .and_then(|l| l.get("breakpoints")?.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|b| b.get("columns")?.as_array())
+        .flatten()
+        .map(|c| ("layout", c));
+    for (section, column) in table
+        .get("defaults")
+        .and_then(|d| d.get("columns")?.as_array())
+        .into_iter()
+        .flatten()
+        .map(|c| ("defaults", c))
+        .chain(breakpoint_columns)
+        .filter_map(|(section, c)| Some((section, c.as_str()?)))
+    {
+        if JobColumn::from_name(column).is_some() {
+            continue;
+        }
+        let (line, column_at) = locator.value(section, "columns", column);
+        let key = if section == "layout" {
+            "breakpoints"
+        } else {
+            "columns"
+        };
+        problems.push(ConfigProblem {
+            line,
+            column: column_at,
+            message: format!("unknown column `{}` in [{}] {}", column, section, key),
+            suggestion: closest(&column.to_lowercase(), titles.iter().map(String::as_str)),
+        });
+    }
+
+    let zone = table.get("time").and_then(|t| t.get("zone")?.as_str());
+    if let Some(zone) = zone.filter(|z| parse_zone(z).is_none()) {
+        let (line, column) = locator.value("time", "zone", zone);
+        problems.push(ConfigProblem {
+            line,
+            column,
+            message: format!("unknown time zone `{}` in [time] zone", zone),
+            suggestion: None,
+        });
+    }
+
+    let colors = table.get("colors").and_then(|c| c.as_table());
+    for (element, value) in colors.into_iter().flatten() {
+        let Some(value) = value.as_str().filter(|v| !v.trim().is_empty()) else {
+            continue;
+        };
+        if parse_color(value).is_none() {
+            let (line, column) = locator.value("colors", element, value);
+            problems.push(ConfigProblem {
+                line,
+                column,
+                message: format!("invalid color `{}` in [colors] {}", value, element),
+                suggestion: None,
+            });
+        }
+    }
+
+    let keys = table.get("keys").and_then(|k| k.as_table());
+    for (action, binding) in keys.into_iter().flatten() {
+        let specs: Vec<&str> = match binding {
+            toml::Value::Array(specs) => specs.iter().filter_map(|s| s.as_str()).collect(),
+            binding => binding.as_str().into_iter().collect(),
+        };
+        for spec in specs {
+            if let Err(e) = parse_sequence(spec) {
+                let (line, column) = locator.value("keys", action, spec);
+                problems.push(ConfigProblem {
+                    line,
+                    column,
+                    message: format!("{} in [keys] {}", e, action),
+                    suggestion: None,
+                });
+            }
+        }
+    }
+
+    problems.sort_by_key(|p| (p.line, p.column));
+    problems
+}
+
+fn line_column(raw: &str, offset: usize) -> (usize, usize) {
+    let before = &raw[..offset.min(raw.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    (line, column)
+}
+
+/// Finds where keys and values were written, as the parsed table does not
+/// keep positions. Repeated lookups of one key move on to its next
+/// occurrence, for keys repeated across `[[presets]]`.
+struct Locator<'a> {
+    /// Each line with the table header it is under
+    lines: Vec<(&'a str, &'a str)>,
+    /// Next line to search from, by section and key
+    next: std::collections::HashMap<(String, String), usize>,
+}
+
+impl<'a> Locator<'a> {
+    fn new(raw: &'a str) -> Self {
+        let mut header = "";
+        let lines = raw
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim();
+                if trimmed.starts_with('[') {
+                    header = trimmed.trim_matches(|c| c == '[' || c == ']').trim();
+                }
+                (header, line)
+            })
+            .collect();
+        Self {
+            lines,
+            next: Default::default(),
+        }
+    }
+
+    /// The line of `key` in `section` (top level for `None`) and its column
+    fn key(&mut self, section: Option<&str>, key: &str) -> (usize, usize) {
+        let section = section.unwrap_or("");
+        let start = self
+            .next
+            .get(&(section.to_string(), key.to_string()))
+            .copied()
+            .unwrap_or(0);
+        let found = self
+            .lines
+            .iter()
+            .enumerate()
+            .skip(start)
+            .find(|(_, (header, line))| {
+                let Some((name, _)) = line.split_once('=') else {
+                    return false;
+                };
+                let name = name.trim().trim_matches('"');
+                (*header == section && name == key)
+                    || (header.is_empty() && name == format!("{}.{}", section, key))
+            });
+        match found {
+            Some((i, (_, line))) => {
+                self.next
+                    .insert((section.to_string(), key.to_string()), i + 1);
+                (i + 1, line.len() - line.trim_start().len() + 1)
+            }
+            None => (1, 1),
+        }
+    }
+
+    /// Where `value` is written in the value of `key`
+    fn value(&mut self, section: &str, key: &str, value: &str) -> (usize, usize) {
+        let quoted = format!("\"{}\"", value);
+        let found = self.lines.iter().enumerate().find(|(_, (header, line))| {
+            *header == section && line.contains(key) && line.contains(&quoted)
+        });
+        match found {
+            Some((i, (_, line))) => (i + 1, line.find(&quoted).unwrap_or(0) + 1),
+            None => self.key(Some(section), key),
+        }
+    }
+}
+
+/// The candidate closest to `word` by edit distance, if it is close enough
+/// to be a typo
+fn closest<'a>(word: &str, candidates: impl Iterator<Item = &'a str>) -> Option<String> {
+    let limit = (word.chars().count() / 3).max(2);
+    candidates
+        .map(|c| (edit_distance(&word.to_lowercase(), &c.to_lowercase()), c))
+        .filter(|(d, _)| *d <= limit)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c.to_string())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substituted = previous + usize::from(ca != *cb);
+            previous = row[j + 1];
+            row[j + 1] = substituted.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The field names serde expects for `T`, as its derived `Deserialize`
+/// passes them to `deserialize_struct`
+pub fn field_names<T: DeserializeOwned>() -> &'static [&'static str] {
+    match T::deserialize(FieldNames) {
+        Err(Found(fields)) => fields,
+        Ok(_) => &[],
+    }
+}
+
+/// A deserializer that only records the fields of the struct asked for
+struct FieldNames;
+
+#[derive(Debug)]
+struct Found(&'static [&'static str]);
+
+impl fmt::Display for Found {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "fields {:?}", self.0)
+    }
+}
+
+impl std::error::Error for Found {}
+
+impl de::Error for Found {
+    fn custom<T: fmt::Display>(_: T) -> Self {
+        Found(&[])
+    }
+}
+
+impl<'de> Deserializer<'de> for FieldNames {
+    type Error = Found;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Found> {
+        Err(Found(&[]))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value, Found> {
+        Err(Found(fields))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::Keymap;
+
+    #[test]
+    fn every_config_key_and_filter_is_documented() {
+        let mut keys: Vec<String> = field_names::<SlurmerConfig>()
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+        for section in sections().iter().filter(|s| s.key != "presets") {
+            keys.extend(
+                section
+                    .fields
+                    .iter()
+                    .map(|f| format!("{}.{}", section.key, f)),
+            );
+        }
+        for key in &keys {
+            assert!(!config_doc(key).is_empty(), "{} is not documented", key);
+        }
+        for (key, _) in CONFIG_DOCS {
+            assert!(keys.iter().any(|k| k == key), "{} is not a config key", key);
+        }
+
+        let filters = field_names::<FilterPreset>();
+        assert!(filters.contains(&"node_filter"));
+        for field in filters {
+            assert!(
+                !filter_doc(field).1.is_empty(),
+                "{} is not documented",
+                field
+            );
+        }
+        assert_eq!(FILTER_DOCS.len(), filters.len());
+
+        let help = config_help();
+        assert!(help.contains("[refresh]\n"));
+        assert!(help.contains("    squeue_secs = 10\n"));
+        assert!(help.contains("    command = unset\n"));
+        assert!(filter_help().contains("PENDING RUNNING"));
+
+        // The template parses, and so does every key of it uncommented
+        let template = config_template();
+        assert!(toml::from_str::<SlurmerConfig>(&template).is_ok());
+        let uncommented: String = template
+            .lines()
+            .map(|line| match line.strip_prefix("# ") {
+                Some(setting) if setting.contains(" = ") || setting.starts_with('[') => setting,
+                _ => line,
+            })
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let cfg: SlurmerConfig = toml::from_str(&uncommented).unwrap();
+        assert_eq!(cfg.refresh, RefreshConfig::default());
+        assert_eq!(cfg.keys.len(), Action::all().count());
+        assert!(Keymap::new(&cfg.keys).1.is_empty());
+        assert_eq!(cfg.presets.len(), 1);
+        assert_eq!(cfg.actions[0].key, Some('t'));
+    }
+
+    #[test]
+    fn config_problems_are_located() {
+        let raw = "slurm_logs_dir = \"/logs\"\n\
+                   [refresh]\n\
+                   squeue_sec = 5\n\
+                   \n\
+                   [[presets]]\n\
+                   name = \"gpu\"\n\
+                   states = [\"RUNNING\", \"RUNING\"]\n\
+                   [[presets]]\n\
+                   name = \"cpu\"\n\
+                   gpu = true\n";
+        let problems: Vec<String> = validate_config(raw).iter().map(|p| p.to_string()).collect();
+        assert_eq!(
+            problems,
+            [
+                "3:1: unknown key `squeue_sec` in [refresh] (did you mean `squeue_secs`?)",
+                "7:22: unknown job state `RUNING` in [[presets]] states (did you mean `RUNNING`?)",
+                "10:1: unknown key `gpu` in [[presets]]",
+            ]
+        );
+
+        let problems = validate_config("[refresh]\nsqueue_secs = \"often\"\n");
+        assert_eq!((problems[0].line, problems[0].column), (2, 15));
+        assert!(problems[0].message.contains("expected u64"));
+        assert!(validate_config("[notify]\nbell = true\n").is_empty());
+        let problems = validate_config("[colors]\nfailed = \"#ff00\"\n");
+        assert_eq!(
+            problems[0].to_string(),
+            "2:10: invalid color `#ff00` in [colors] failed"
+        );
+        let problems = validate_config("[keys]\ncancel = [\"x\", \"ctrl-meta-x\"]\n");
+        assert_eq!(
+            problems[0].to_string(),
+            "2:16: unknown key `ctrl-meta-x` in [keys] cancel"
+        );
+    }
+}
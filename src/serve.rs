@@ -0,0 +1,434 @@
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::Result;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::config::load_config;
+use crate::metrics::{render_metrics, write_textfile, OPENMETRICS_CONTENT_TYPE};
+use crate::slurm::{
+    hostlist::{compress, jobs_per_node},
+    merge_jobs_prefer_active,
+    sacct::{run_sacct, SacctOptions},
+    squeue::{run_squeue, SqueueOptions},
+    Job, JobState,
+};
+use crate::snapshot::{save_snapshot, unix_now, Snapshot};
+use crate::utils::{format_slurm_duration, get_username};
+
+/// Queue samples kept for the history chart (a day at the default interval)
+const HISTORY_LEN: usize = 24 * 60;
+
+/// squeue columns the dashboard shows
+const SQUEUE_FORMAT: &str = "%i|%j|%u|%T|%M|%D|%N|%C|%m|%P|%b|%R";
+
+/// sacct fields for the recently ended jobs, matching `SQUEUE_FORMAT`
+const SACCT_FIELDS: &[&str] = &[
+    "JobID",
+    "JobName",
+    "User",
+    "State",
+    "Elapsed",
+    "NNodes",
+    "NodeList",
+    "AllocCPUS",
+    "ReqMem",
+    "Partition",
+    "AllocTRES",
+];
+
+/// What the dashboard renders, refreshed by the poller thread
+#[derive(Default)]
+struct Dashboard {
+    user: String,
+    jobs: Vec<Job>,
+    /// Unix time of the last successful fetch
+    fetched_at: u64,
+    error: Option<String>,
+    /// `(unix time, running, pending)` per poll
+    history: VecDeque<(u64, usize, usize)>,
+}
+
+/// Serve a read-only HTML dashboard of the current user's jobs on `bind:port`,
+/// polling Slurm every `interval`
+pub fn serve(bind: &str, port: u16, interval: Duration) -> Result<()> {
+    let listener = TcpListener::bind((bind, port))
+        .wrap_err_with(|| format!("failed to listen on {}:{}", bind, port))?;
+    eprintln!(
+        "slurmer: serving the dashboard on http://{}:{}/ (Ctrl+C to stop)",
+        bind, port
+    );
+
+    let dashboard = Arc::new(Mutex::new(Dashboard {
+        user: get_username(),
+        ..Default::default()
+    }));
+
+    let poller = Arc::clone(&dashboard);
+    std::thread::spawn(move || poll(poller, interval));
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let dashboard = Arc::clone(&dashboard);
+        let refresh = interval.as_secs().max(5);
+        std::thread::spawn(move || {
+            let _ = handle_connection(stream, &dashboard, refresh);
+        });
+    }
+    Ok(())
+}
+
+/// Fetch jobs like the TUI does (queue plus jobs ended in the last day) forever
+fn poll(dashboard: Arc<Mutex<Dashboard>>, interval: Duration) {
+    let Ok(runtime) = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    else {
+        return;
+    };
+    let textfile = load_config().unwrap_or_default().metrics.textfile_path();
+    let user = get_username();
+    let squeue_options = SqueueOptions {
+        user: Some(user.clone()),
+        format: SQUEUE_FORMAT.to_string(),
+        ..Default::default()
+    };
+    let sacct_options = SacctOptions {
+        user: Some(user),
+        states: JobState::ended_states(),
+        partitions: Vec::new(),
+        qos: Vec::new(),
+        accounts: Vec::new(),
+        wckeys: Vec::new(),
+        name: None,
+        recent_hours: 24,
+        end_hours: 0,
+        format_fields: SACCT_FIELDS.to_vec(),
+        max_rows: None,
+        window_hours: None,
+    };
+
+    loop {
+        let result = runtime.block_on(async {
+            let active = run_squeue(&squeue_options).await?;
+            // Accounting may be disabled; the queue alone is still useful
+            let ended = run_sacct(&sacct_options)
+                .await
+                .map(|rows| rows.jobs)
+                .unwrap_or_default();
+            Ok::<_, color_eyre::Report>(merge_jobs_prefer_active(active, ended))
+        });
+
+        if let Ok(mut dashboard) = dashboard.lock() {
+            match result {
+                Ok(mut jobs) => {
+                    // Newest first
+                    jobs.sort_by(|a, b| b.id.len().cmp(&a.id.len()).then(b.id.cmp(&a.id)));
+                    let _ = save_snapshot(&Snapshot::from_jobs(&jobs));
+                    if let Some(path) = &textfile {
+                        if let Err(e) = write_textfile(path, &jobs) {
+                            eprintln!("slurmer: {:#}", e);
+                        }
+                    }
+                    let count = |state| jobs.iter().filter(|j| j.state == state).count();
+                    let now = unix_now();
+                    dashboard.history.push_back((
+                        now,
+                        count(JobState::Running),
+                        count(JobState::Pending),
+                    ));
+                    while dashboard.history.len() > HISTORY_LEN {
+                        dashboard.history.pop_front();
+                    }
+                    dashboard.jobs = jobs;
+                    dashboard.fetched_at = now;
+                    dashboard.error = None;
+                }
+                Err(e) => dashboard.error = Some(e.to_string()),
+            }
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+/// Answer one HTTP request; only `GET /` and `GET /metrics` are served
+fn handle_connection(
+    mut stream: TcpStream,
+    dashboard: &Mutex<Dashboard>,
+    refresh_secs: u64,
+) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let (status, content_type, body) = match parse_request_line(&request_line) {
+        Some(("GET", "/")) | Some(("GET", "/index.html")) => {
+            let dashboard = dashboard
+                .lock()
+                .map_err(|_| eyre!("dashboard lock poisoned"))?;
+            (
+                "200 OK",
+                "text/html; charset=utf-8",
+                render_dashboard(&dashboard, refresh_secs),
+            )
+        }
+        Some(("GET", "/metrics")) => {
+            let dashboard = dashboard
+                .lock()
+                .map_err(|_| eyre!("dashboard lock poisoned"))?;
+            (
+                "200 OK",
+                OPENMETRICS_CONTENT_TYPE,
+                render_metrics(&dashboard.jobs),
+            )
+        }
+        Some(("GET", _)) => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+        Some(_) => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Read-only dashboard\n".to_string(),
+        ),
+        None => ("400 Bad Request", "text/plain", "Bad request\n".to_string()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
+
+/// Method and path (without query string) of an HTTP request line
+fn parse_request_line(line: &str) -> Option<(&str, &str)> {
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+    parts.next().filter(|v| v.starts_with("HTTP/"))?;
+    Some((method, target.split('?').next().unwrap_or(target)))
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn state_class(state: JobState) -> &'static str {
+    match state {
+        JobState::Running => "run",
+        JobState::Pending => "pend",
+        JobState::Completed => "done",
+        JobState::Failed
+        | JobState::Timeout
+        | JobState::NodeFail
+        | JobState::Boot
+        | JobState::OutOfMemory
+        | JobState::Deadline
+        | JobState::SpecialExit
+        | JobState::LaunchFailed => "fail",
+        _ => "other",
+    }
+}
+
+/// Running/pending history as an inline SVG line chart
+fn history_chart(history: &VecDeque<(u64, usize, usize)>) -> String {
+    if history.len() < 2 {
+        return "<p class=\"muted\">History appears after a few refreshes.</p>".to_string();
+    }
+    let (width, height) = (600.0, 120.0);
+    let max = history
+        .iter()
+        .map(|(_, r, p)| (*r).max(*p))
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let step = width / (history.len() - 1) as f64;
+    let points = |pick: fn(&(u64, usize, usize)) -> usize| {
+        history
+            .iter()
+            .enumerate()
+            .map(|(i, sample)| {
+                format!(
+                    "{:.1},{:.1}",
+                    i as f64 * step,
+                    height - pick(sample) as f64 / max * (height - 4.0)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let span_mins = (history.back().map_or(0, |s| s.0) - history.front().map_or(0, |s| s.0)) / 60;
+    format!(
+        "<svg viewBox=\"0 0 {w} {h}\" preserveAspectRatio=\"none\" class=\"chart\">\
+         <polyline class=\"run\" points=\"{run}\"/><polyline class=\"pend\" points=\"{pend}\"/></svg>\
+         <p class=\"muted\">Last {mins} min, peak {max}: <span class=\"run\">running</span> / \
+         <span class=\"pend\">pending</span></p>",
+        w = width,
+        h = height,
+        run = points(|s| s.1),
+        pend = points(|s| s.2),
+        mins = span_mins,
+        max = max,
+    )
+}
+
+fn render_dashboard(dashboard: &Dashboard, refresh_secs: u64) -> String {
+    let jobs = &dashboard.jobs;
+    let snapshot = Snapshot::from_jobs(jobs);
+    let stats = snapshot
+        .state_counts()
+        .iter()
+        .map(|(state, count)| {
+            format!(
+                "<div class=\"stat {}\"><b>{}</b>{}</div>",
+                state_class(*state),
+                count,
+                state.to_string().to_lowercase()
+            )
+        })
+        .collect::<String>();
+    let gpus_in_use: u32 = jobs
+        .iter()
+        .filter(|j| j.state == JobState::Running)
+        .map(|j| j.gpus)
+        .sum();
+
+    let rows = jobs
+        .iter()
+        .map(|job| {
+            let detail = if job.state == JobState::Pending {
+                job.pending_reason.clone().unwrap_or_default()
+            } else {
+                job.node.clone().unwrap_or_default()
+            };
+            format!(
+                "<tr><td>{}</td><td>{}</td><td class=\"{}\">{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                html_escape(&job.id),
+                html_escape(&job.name),
+                state_class(job.state),
+                job.state,
+                format_slurm_duration(job.time),
+                html_escape(&job.partition),
+                job.cpus,
+                if job.gpus > 0 { job.gpus.to_string() } else { String::new() },
+                html_escape(&detail),
+            )
+        })
+        .collect::<String>();
+
+    // Nodes grouped by how many of the jobs run there: `2 jobs: gpu[01-03]`
+    let mut by_count: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for (host, count) in jobs_per_node(jobs) {
+        by_count.entry(count).or_default().push(host);
+    }
+    let nodes = by_count
+        .iter()
+        .rev()
+        .map(|(count, hosts)| {
+            format!(
+                "<div class=\"stat\"><b>{}</b>{} job{}</div>",
+                html_escape(&compress(hosts)),
+                count,
+                if *count == 1 { "" } else { "s" }
+            )
+        })
+        .collect::<String>();
+
+    let status = match (&dashboard.error, dashboard.fetched_at) {
+        (Some(e), _) => format!("<p class=\"fail\">Slurm error: {}</p>", html_escape(e)),
+        (None, 0) => "<p class=\"muted\">Waiting for the first refresh...</p>".to_string(),
+        (None, at) => format!(
+            "<p class=\"muted\">Updated {}s ago</p>",
+            unix_now().saturating_sub(at)
+        ),
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta http-equiv="refresh" content="{refresh}">
+<title>slurmer - {user}</title>
+<style>
+body {{ font-family: sans-serif; margin: 1em; background: #111; color: #ddd; }}
+table {{ border-collapse: collapse; width: 100%; font-size: 0.9em; }}
+td, th {{ padding: 0.25em 0.5em; border-bottom: 1px solid #333; text-align: left; }}
+.stats {{ display: flex; flex-wrap: wrap; gap: 0.5em; }}
+.stat {{ background: #222; padding: 0.5em 1em; border-radius: 4px; }}
+.stat b {{ display: block; font-size: 1.5em; }}
+.chart {{ width: 100%; height: 120px; background: #1a1a1a; }}
+.chart polyline {{ fill: none; stroke-width: 2; }}
+.chart .run {{ stroke: #4c4; }} .chart .pend {{ stroke: #cc4; }}
+.run {{ color: #4c4; }} .pend {{ color: #cc4; }} .done {{ color: #6af; }}
+.fail {{ color: #e55; }} .muted {{ color: #888; }}
+</style></head><body>
+<h2>Jobs of {user}</h2>
+{status}
+<div class="stats">{stats}<div class="stat"><b>{gpus}</b>GPUs in use</div></div>
+<h3>Nodes in use</h3>
+<div class="stats">{nodes}</div>
+<h3>Queue history</h3>
+{chart}
+<h3>{count} jobs</h3>
+<table><tr><th>ID</th><th>Name</th><th>State</th><th>Time</th><th>Partition</th><th>CPUs</th><th>GPUs</th><th>Nodes / Reason</th></tr>
+{rows}</table>
+</body></html>
+"#,
+        refresh = refresh_secs,
+        user = html_escape(&dashboard.user),
+        status = status,
+        stats = stats,
+        gpus = gpus_in_use,
+        nodes = nodes,
+        chart = history_chart(&dashboard.history),
+        count = jobs.len(),
+        rows = rows,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_lines_are_parsed() {
+        assert_eq!(
+            parse_request_line("GET /?x=1 HTTP/1.1\r\n"),
+            Some(("GET", "/"))
+        );
+        assert_eq!(
+            parse_request_line("POST /cancel HTTP/1.1"),
+            Some(("POST", "/cancel"))
+        );
+        assert_eq!(parse_request_line("garbage"), None);
+    }
+
+    #[test]
+    fn dashboard_escapes_job_names() {
+        let dashboard = Dashboard {
+            user: "alice".to_string(),
+            jobs: vec![Job {
+                id: "1".to_string(),
+                name: "<script>alert(1)</script>".to_string(),
+                state: JobState::Running,
+                gpus: 2,
+                ..Default::default()
+            }],
+            fetched_at: unix_now(),
+            ..Default::default()
+        };
+        let html = render_dashboard(&dashboard, 30);
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<b>2</b>GPUs in use"));
+    }
+}
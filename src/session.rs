@@ -0,0 +1,187 @@
+use color_eyre::eyre::WrapErr;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::{cache_dir, FilterPreset};
+use crate::notes::write_atomic;
+use crate::slurm::squeue::SqueueOptions;
+use crate::ui::columns::{JobColumn, SortColumn, SortOrder};
+use crate::ui::detail::DetailTab;
+use crate::ui::jobslist::GroupBy;
+
+/// Where the TUI was left: written on exit and read on the next launch, so
+/// a restart (e.g. after an ssh drop) resumes the same view
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// The filters, as a preset
+    pub filters: FilterPreset,
+    /// Column titles, in order
+    pub columns: Vec<String>,
+    /// Sort columns by title, `-` first when descending
+    pub sort: Vec<String>,
+    /// `account`, `user` or `state` when the list was grouped
+    pub group_by: Option<String>,
+    /// Job under the cursor
+    pub selected: Option<String>,
+    /// First table row on screen
+    pub offset: usize,
+    /// Tab of the detail pane, when it was open
+    pub detail_tab: Option<String>,
+    /// Pinned jobs, kept even when the view is not restored
+    pub watchlist: Vec<String>,
+}
+
+impl Session {
+    /// `options` as a preset, to apply on the next launch
+    pub fn filters_of(options: &SqueueOptions, recent_hours: u32) -> FilterPreset {
+        FilterPreset {
+            user: Some(options.user.clone().unwrap_or_else(|| "*".to_string())),
+            states: options.states.iter().map(|s| s.to_string()).collect(),
+            partitions: options.partitions.clone(),
+            qos: options.qos.clone(),
+            accounts: options.accounts.clone(),
+            wckeys: options.wckeys.clone(),
+            name_filter: options.name_filter.clone(),
+            node_filter: options.node_filter.clone(),
+            gpu_only: options.gpu_only,
+            start_within_hours: options.start_within_hours,
+            recent_hours: Some(recent_hours),
+            ..Default::default()
+        }
+    }
+
+    pub fn set_columns(&mut self, columns: &[JobColumn], sort: &[SortColumn]) {
+        self.columns = columns.iter().map(|c| c.title().to_string()).collect();
+        self.sort = sort
+            .iter()
+            .map(|s| match s.order {
+                SortOrder::Ascending => s.column.title().to_string(),
+                SortOrder::Descending => format!("-{}", s.column.title()),
+            })
+            .collect();
+    }
+
+    /// The saved columns; unknown titles are left out
+    pub fn columns(&self) -> Vec<JobColumn> {
+        self.columns
+            .iter()
+            .filter_map(|name| JobColumn::from_name(name))
+            .collect()
+    }
+
+    pub fn sort_columns(&self) -> Vec<SortColumn> {
+        self.sort
+            .iter()
+            .filter_map(|name| {
+                let (name, order) = match name.strip_prefix('-') {
+                    Some(name) => (name, SortOrder::Descending),
+                    None => (name.as_str(), SortOrder::Ascending),
+                };
+                Some(SortColumn {
+                    column: JobColumn::from_name(name)?,
+                    order,
+                })
+            })
+            .collect()
+    }
+
+    pub fn group_by(&self) -> Option<GroupBy> {
+        match self.group_by.as_deref() {
+            Some("account") => Some(GroupBy::Account),
+            Some("user") => Some(GroupBy::User),
+            Some("state") => Some(GroupBy::State),
+            _ => None,
+        }
+    }
+
+    pub fn detail_tab(&self) -> Option<DetailTab> {
+        let title = self.detail_tab.as_deref()?;
+        DetailTab::all().into_iter().find(|t| t.title() == title)
+    }
+}
+
+/// The session left last, if any and readable
+pub fn load_session() -> Option<Session> {
+    let raw = fs::read_to_string(session_path().ok()?).ok()?;
+    toml::from_str(&raw).ok()
+}
+
+/// Write `session` through a temp file and rename, so another instance
+/// reading it never sees half of it
+pub fn save_session(session: &Session) -> Result<()> {
+    let path = session_path()?;
+    let raw = toml::to_string(session).wrap_err("failed serializing session")?;
+    write_atomic(&path, &raw).wrap_err("failed writing session")
+}
+
+fn session_path() -> Result<PathBuf> {
+    Ok(cache_dir()?.join("session.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::slurm::JobState;
+
+    #[test]
+    fn views_round_trip_through_the_state_file() {
+        let options = SqueueOptions {
+            user: None,
+            states: vec![JobState::Running, JobState::Failed],
+            partitions: vec!["gpu".to_string()],
+            ..Default::default()
+        };
+        let mut session = Session {
+            filters: Session::filters_of(&options, 48),
+            group_by: Some("user".to_string()),
+            selected: Some("48213".to_string()),
+            offset: 3,
+            detail_tab: Some("Logs".to_string()),
+            watchlist: vec!["48190".to_string()],
+            ..Default::default()
+        };
+        session.set_columns(
+            &[JobColumn::Id, JobColumn::TimeLeft],
+            &[SortColumn {
+                column: JobColumn::TimeLeft,
+                order: SortOrder::Descending,
+            }],
+        );
+        let restored: Session = toml::from_str(&toml::to_string(&session).unwrap()).unwrap();
+        assert_eq!(restored, session);
+
+        let mut applied = SqueueOptions::default();
+        let mut hours = 24;
+        restored.filters.apply_to(&mut applied, &mut hours, "demo");
+        assert_eq!(applied.user, None);
+        assert_eq!(applied.states, options.states);
+        assert_eq!(
+            (applied.partitions.as_slice(), hours),
+            (&["gpu".to_string()][..], 48)
+        );
+        assert_eq!(restored.columns(), [JobColumn::Id, JobColumn::TimeLeft]);
+        let sort = restored.sort_columns();
+        assert_eq!(
+            (sort[0].column, sort[0].order),
+            (JobColumn::TimeLeft, SortOrder::Descending)
+        );
+        assert_eq!(restored.group_by(), Some(GroupBy::User));
+        assert_eq!(restored.detail_tab(), Some(DetailTab::Logs));
+
+        // `--partition` replaces only the restored partitions
+        let overrides = ConfigOverrides {
+            partitions: Some(vec!["cpu".to_string()]),
+            ..Default::default()
+        };
+        overrides.apply_filters(&mut applied, &mut hours, "demo");
+        assert_eq!(applied.partitions, ["cpu"]);
+        assert_eq!(
+            (applied.states, applied.user, hours),
+            (options.states, None, 48)
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use color_eyre::Result;
+use std::collections::HashMap;
+
+use super::cache;
+use super::error::checked;
+use super::squeue::SqueueOptions;
+use super::JobState;
+
+/// Minimal squeue format for aggregate mode: only what the counts need, so
+/// huge queues transfer and parse quickly
+pub const AGGREGATE_FORMAT: &str = "%u|%P|%T|%C";
+
+/// Jobs of one user in one partition and state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueGroup {
+    pub user: String,
+    pub partition: String,
+    pub state: JobState,
+    pub jobs: usize,
+    pub cpus: u64,
+}
+
+/// Count the queue per user, partition and state, with the same filters as
+/// the job list but without fetching the jobs themselves
+pub async fn run_squeue_aggregate(options: &SqueueOptions) -> Result<Vec<QueueGroup>> {
+    let options = SqueueOptions {
+        format: AGGREGATE_FORMAT.to_string(),
+        // Sorting 200k rows on the controller is wasted work here
+        sorts: HashMap::new(),
+        ..options.clone()
+    };
+    let output = checked("squeue", cache::query("squeue", options.to_args()).await)?;
+
+    Ok(aggregate(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Group `AGGREGATE_FORMAT` rows, largest groups first
+pub fn aggregate(stdout: &str) -> Vec<QueueGroup> {
+    let mut groups: HashMap<(&str, &str, &str), (usize, u64)> = HashMap::new();
+    for line in stdout.lines() {
+        let mut fields = line.split('|').map(str::trim);
+        let (Some(user), Some(partition), Some(state)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if user.is_empty() {
+            continue;
+        }
+        let cpus = fields
+            .next()
+            .and_then(|c| c.parse::<u64>().ok())
+            .unwrap_or(0);
+        let group = groups.entry((user, partition, state)).or_default();
+        group.0 += 1;
+        group.1 += cpus;
+    }
+
+    let mut groups: Vec<QueueGroup> = groups
+        .into_iter()
+        .map(|((user, partition, state), (jobs, cpus))| QueueGroup {
+            user: user.to_string(),
+            partition: partition.to_string(),
+            state: state.parse().unwrap_or(JobState::Other),
+            jobs,
+            cpus,
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.jobs
+            .cmp(&a.jobs)
+            .then_with(|| a.user.cmp(&b.user))
+            .then_with(|| a.partition.cmp(&b.partition))
+            .then_with(|| a.state.short_code().cmp(b.state.short_code()))
+    });
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_jobs_per_user_partition_and_state() {
+        let stdout = "alice|gpu|PENDING|8\n\
+                      bob|cpu|RUNNING|4\n\
+                      alice|gpu|PENDING|8\n\
+                      alice|gpu|RUNNING|16\n\
+                      \n\
+                      alice|gpu|PENDING|n/a\n";
+        let groups = aggregate(stdout);
+        assert_eq!(
+            groups[0],
+            QueueGroup {
+                user: "alice".to_string(),
+                partition: "gpu".to_string(),
+                state: JobState::Pending,
+                jobs: 3,
+                cpus: 16,
+            }
+        );
+        assert_eq!(groups.len(), 3);
+        assert_eq!((groups[1].user.as_str(), groups[1].cpus), ("alice", 16));
+        assert_eq!(groups[2].user, "bob");
+    }
+}
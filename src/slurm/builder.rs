@@ -0,0 +1,250 @@
+use super::submit::SubmitSpec;
+
+/// Labels of the builder form's fields, in form order
+pub const BUILDER_FIELDS: &[&str] = &[
+    "Job name",
+    "Partition",
+    "Time limit",
+    "Nodes",
+    "Tasks",
+    "CPUs per task",
+    "GPUs per node",
+    "Memory",
+    "Modules",
+    "Command",
+    "Save as",
+];
+
+/// A batch script described by its resources, for users who do not know the
+/// `#SBATCH` directives yet. Every directive is written with a comment on
+/// what it asks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptBuilder {
+    pub job_name: String,
+    pub partition: String,
+    pub time_limit: String,
+    pub nodes: String,
+    pub ntasks: String,
+    pub cpus_per_task: String,
+    /// A count (`2`) or a type and count (`a100:2`)
+    pub gpus: String,
+    /// Per-node memory (`16G`), or per-CPU memory with a `/cpu` suffix (`4G/cpu`)
+    pub memory: String,
+    /// Environment modules to load, separated by spaces or commas
+    pub modules: String,
+    pub command: String,
+    /// Where to write the script; `<job name>.sbatch` when empty
+    pub path: String,
+}
+
+impl Default for ScriptBuilder {
+    fn default() -> Self {
+        Self {
+            job_name: "my_job".to_string(),
+            partition: String::new(),
+            time_limit: "01:00:00".to_string(),
+            nodes: "1".to_string(),
+            ntasks: "1".to_string(),
+            cpus_per_task: "1".to_string(),
+            gpus: String::new(),
+            memory: "4G".to_string(),
+            modules: String::new(),
+            command: String::new(),
+            path: String::new(),
+        }
+    }
+}
+
+impl ScriptBuilder {
+    /// Value of the field labelled `label` (see `BUILDER_FIELDS`)
+    pub fn field(&self, label: &str) -> &str {
+        match label {
+            "Job name" => &self.job_name,
+            "Partition" => &self.partition,
+            "Time limit" => &self.time_limit,
+            "Nodes" => &self.nodes,
+            "Tasks" => &self.ntasks,
+            "CPUs per task" => &self.cpus_per_task,
+            "GPUs per node" => &self.gpus,
+            "Memory" => &self.memory,
+            "Modules" => &self.modules,
+            "Command" => &self.command,
+            "Save as" => &self.path,
+            _ => "",
+        }
+    }
+
+    pub fn set_field(&mut self, label: &str, value: String) {
+        let slot = match label {
+            "Job name" => &mut self.job_name,
+            "Partition" => &mut self.partition,
+            "Time limit" => &mut self.time_limit,
+            "Nodes" => &mut self.nodes,
+            "Tasks" => &mut self.ntasks,
+            "CPUs per task" => &mut self.cpus_per_task,
+            "GPUs per node" => &mut self.gpus,
+            "Memory" => &mut self.memory,
+            "Modules" => &mut self.modules,
+            "Command" => &mut self.command,
+            "Save as" => &mut self.path,
+            _ => return,
+        };
+        *slot = value.trim().to_string();
+    }
+
+    /// Validate a field value as it is typed
+    pub fn validate_field(label: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        match label {
+            "GPUs per node" if !value.is_empty() => {
+                let count = value.rsplit(':').next().unwrap_or("");
+                match count.parse::<u32>() {
+                    Ok(n) if n >= 1 => Ok(()),
+                    _ => Err(format!(
+                        "invalid GPU request: {} (use e.g. 2 or a100:2)",
+                        value
+                    )),
+                }
+            }
+            "Job name" if value.chars().any(char::is_whitespace) => {
+                Err("job name cannot contain spaces".to_string())
+            }
+            _ => SubmitSpec::validate_field(label, value),
+        }
+    }
+
+    /// Where the script is saved
+    pub fn path(&self) -> String {
+        if !self.path.is_empty() {
+            return self.path.clone();
+        }
+        let name = match self.job_name.as_str() {
+            "" => "job",
+            name => name,
+        };
+        format!("{}.sbatch", name)
+    }
+
+    /// The complete batch script, one commented directive per resource
+    pub fn render(&self) -> String {
+        let mut script = vec![
+            "#!/bin/bash".to_string(),
+            "# Batch script written by Slurmer's script builder. Submit it with".to_string(),
+            format!("#   sbatch {}", self.path()),
+            "# Options given to sbatch on the command line override these lines.".to_string(),
+        ];
+        let mut directive = |comment: &str, option: &str, value: &str| {
+            if !value.is_empty() {
+                script.push(String::new());
+                script.push(format!("# {}", comment));
+                script.push(format!("#SBATCH --{}={}", option, value));
+            }
+        };
+
+        directive(
+            "Name shown by squeue and used in the log file names",
+            "job-name",
+            &self.job_name,
+        );
+        directive(
+            "Partition (queue) to run in; `sinfo -s` lists them",
+            "partition",
+            &self.partition,
+        );
+        directive(
+            "Wall-clock limit ([D-]HH:MM:SS); the job is stopped when it runs out",
+            "time",
+            &self.time_limit,
+        );
+        directive("Number of nodes (machines)", "nodes", &self.nodes);
+        directive(
+            "Number of tasks (processes, e.g. MPI ranks) in total; srun starts this many",
+            "ntasks",
+            &self.ntasks,
+        );
+        directive(
+            "CPU cores for each task, for its threads",
+            "cpus-per-task",
+            &self.cpus_per_task,
+        );
+        if !self.gpus.is_empty() {
+            directive("GPUs on each node", "gres", &format!("gpu:{}", self.gpus));
+        }
+        match self.memory.strip_suffix("/cpu") {
+            Some(per_cpu) => directive("Memory for each CPU core", "mem-per-cpu", per_cpu),
+            None => directive("Memory on each node", "mem", &self.memory),
+        }
+        directive(
+            "Output and errors of the job; %x is the job name and %j the job id",
+            "output",
+            "%x_%j.out",
+        );
+
+        let modules: Vec<&str> = self
+            .modules
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|m| !m.is_empty())
+            .collect();
+        if !modules.is_empty() {
+            script.push(String::new());
+            script
+                .push("# Software environment; `module avail` lists what is installed".to_string());
+            script.push("module purge".to_string());
+            script.push(format!("module load {}", modules.join(" ")));
+        }
+
+        script.push(String::new());
+        // After `module load`: module scripts often use unset variables
+        script.push("# Stop at the first failing command".to_string());
+        script.push("set -euo pipefail".to_string());
+        script.push(String::new());
+        script.push("# Let threaded programs use the cores given to each task".to_string());
+        script.push("export OMP_NUM_THREADS=\"${SLURM_CPUS_PER_TASK:-1}\"".to_string());
+        script.push(String::new());
+        script.push("echo \"Job $SLURM_JOB_ID on $(hostname), started $(date)\"".to_string());
+        script.push(String::new());
+        script.push("# The program; srun starts it once per task".to_string());
+        if self.command.is_empty() {
+            script.push("srun hostname  # replace with your program".to_string());
+        } else {
+            script.push(format!("srun {}", self.command));
+        }
+        script.push(String::new());
+        script.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_annotated_script() {
+        let builder = ScriptBuilder {
+            job_name: "train".to_string(),
+            partition: "gpu".to_string(),
+            gpus: "a100:2".to_string(),
+            memory: "8G/cpu".to_string(),
+            modules: "cuda/12.1, python/3.11".to_string(),
+            command: "python train.py".to_string(),
+            ..Default::default()
+        };
+        let script = builder.render();
+        assert!(script.starts_with("#!/bin/bash\n"));
+        assert!(script.contains("#   sbatch train.sbatch\n"));
+        assert!(script.contains("# GPUs on each node\n#SBATCH --gres=gpu:a100:2\n"));
+        assert!(script.contains("#SBATCH --mem-per-cpu=8G\n"));
+        assert!(script.contains("module load cuda/12.1 python/3.11\n"));
+        assert!(script.ends_with("srun python train.py\n"));
+        // Every directive comes with a comment
+        let lines: Vec<&str> = script.lines().collect();
+        for (i, line) in lines.iter().enumerate() {
+            if line.starts_with("#SBATCH") {
+                assert!(lines[i - 1].starts_with("# "), "{}", line);
+            }
+        }
+
+        assert!(ScriptBuilder::validate_field("GPUs per node", "a100:0").is_err());
+        assert!(ScriptBuilder::validate_field("Time limit", "4:00:00").is_ok());
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::process::Output;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::command;
+
+/// Command lines kept at most; the oldest result goes first
+const MAX_ENTRIES: usize = 64;
+
+/// Seconds a query result is reused for; 0 runs every query
+static TTL_SECS: AtomicU64 = AtomicU64::new(10);
+
+/// A command line: the program and its arguments
+type Key = (String, Vec<String>);
+
+/// Output of read-only Slurm queries by command line
+#[derive(Debug, Default)]
+struct Cache {
+    entries: HashMap<Key, (Instant, Output)>,
+}
+
+impl Cache {
+    fn get(&self, key: &Key, ttl: Duration, now: Instant) -> Option<Output> {
+        let (at, output) = self.entries.get(key)?;
+        (now.saturating_duration_since(*at) < ttl).then(|| output.clone())
+    }
+
+    fn put(&mut self, key: Key, output: Output, ttl: Duration, now: Instant) {
+        self.entries
+            .retain(|_, (at, _)| now.saturating_duration_since(*at) < ttl);
+        if self.entries.len() >= MAX_ENTRIES {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (at, _))| *at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, (now, output));
+    }
+}
+
+fn cache() -> &'static Mutex<Cache> {
+    static CACHE: OnceLock<Mutex<Cache>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// How long identical queries reuse a result (`[refresh] cache_ttl_secs`)
+pub fn set_ttl(ttl: Duration) {
+    TTL_SECS.store(ttl.as_secs(), Ordering::Relaxed);
+}
+
+/// Forget every result, after something changed the jobs (cancel, submit, ...)
+pub fn invalidate() {
+    if let Ok(mut cache) = cache().lock() {
+        cache.entries.clear();
+    }
+}
+
+/// Run a read-only Slurm query, or reuse the output of the same command line
+/// if it ran within the TTL, so redrawing, sorting or switching tabs does not
+/// reach the controller. Failed runs are not kept.
+pub async fn query<I, S>(program: &str, args: I) -> std::io::Result<Output>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let ttl = Duration::from_secs(TTL_SECS.load(Ordering::Relaxed));
+    let key = (
+        program.to_string(),
+        args.into_iter().map(|a| a.as_ref().to_string()).collect(),
+    );
+    if let Some(output) = cache()
+        .lock()
+        .ok()
+        .and_then(|c| c.get(&key, ttl, Instant::now()))
+    {
+        return Ok(output);
+    }
+
+    let output = command::output(program, &key.1).await?;
+    if output.status.success() && !ttl.is_zero() {
+        if let Ok(mut cache) = cache().lock() {
+            cache.put(key, output.clone(), ttl, Instant::now());
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    #[test]
+    fn results_are_reused_within_the_ttl() {
+        let ttl = Duration::from_secs(10);
+        let start = Instant::now();
+        let output = |stdout: &str| Output {
+            status: ExitStatus::from_raw(0),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        };
+        let key = |args: &[&str]| {
+            (
+                "squeue".to_string(),
+                args.iter().map(|a| a.to_string()).collect(),
+            )
+        };
+
+        let mut cache = Cache::default();
+        cache.put(key(&["--me"]), output("1|RUNNING"), ttl, start);
+        let hit = cache.get(&key(&["--me"]), ttl, start + Duration::from_secs(9));
+        assert_eq!(hit.map(|o| o.stdout), Some(b"1|RUNNING".to_vec()));
+        assert!(cache.get(&key(&["--all"]), ttl, start).is_none());
+        assert!(cache
+            .get(&key(&["--me"]), ttl, start + Duration::from_secs(10))
+            .is_none());
+
+        // Expired entries are dropped on the next insert
+        cache.put(
+            key(&["--all"]),
+            output(""),
+            ttl,
+            start + Duration::from_secs(11),
+        );
+        assert_eq!(cache.entries.len(), 1);
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use super::command;
+
+/// What a cluster's Slurm was found to support, as of `version`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// `sinfo --version` without the program name, e.g. `23.02.7`
+    pub version: String,
+    /// Fields `sacct --format` accepts (`sacct --helpformat`); empty when
+    /// unknown
+    pub sacct_fields: Vec<String>,
+}
+
+impl Capabilities {
+    /// Whether sacct can be asked for `field`; fields are assumed to be
+    /// there until the probe says otherwise
+    pub fn supports_sacct_field(&self, field: &str) -> bool {
+        self.sacct_fields.is_empty()
+            || self
+                .sacct_fields
+                .iter()
+                .any(|f| f.eq_ignore_ascii_case(field))
+    }
+
+    /// Whether `srun --overlap` is known (Slurm 20.11 on); without it a step
+    /// in a running job waits for the resources its first step holds. An
+    /// unknown version is taken as a current one.
+    pub fn supports_overlap(&self) -> bool {
+        let mut parts = self.version.split('.').map(|p| p.parse::<u32>().ok());
+        match (parts.next().flatten(), parts.next().flatten()) {
+            (Some(major), Some(minor)) => (major, minor) >= (20, 11),
+            _ => true,
+        }
+    }
+}
+
+/// The Slurm version changed since the cluster was last seen
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionChange {
+    pub cluster: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct CapabilityFile {
+    #[serde(default)]
+    clusters: BTreeMap<String, Capabilities>,
+}
+
+fn load(path: &Path) -> CapabilityFile {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|raw| toml::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save(path: &Path, file: &CapabilityFile) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, toml::to_string(file)?)?;
+    Ok(())
+}
+
+/// The version number out of `slurm 23.02.7` (or `slurm-wlm 21.08.5`)
+fn parse_version(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+async fn detect_version() -> Result<String> {
+    let output = command::output("sinfo", ["--version"]).await?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_version(&stdout)
+        .filter(|_| output.status.success())
+        .ok_or_else(|| eyre!("sinfo --version printed no version"))
+}
+
+/// Ask Slurm what it supports
+async fn probe(version: String) -> Capabilities {
+    let sacct_fields = match command::output("sacct", ["--helpformat"]).await {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    };
+    Capabilities {
+        version,
+        sacct_fields,
+    }
+}
+
+/// The capabilities of `cluster`, probed again only when its Slurm version
+/// differs from the one last seen (stored in `path`), so upgrades do not
+/// leave stale assumptions behind. Reports the change when there was one.
+pub async fn check(path: Option<&Path>, cluster: &str) -> (Capabilities, Option<VersionChange>) {
+    let mut file = path.map(load).unwrap_or_default();
+    let stored = file.clusters.get(cluster).cloned();
+    let version = match detect_version().await {
+        Ok(version) => version,
+        Err(_) => return (stored.unwrap_or_default(), None),
+    };
+    if let Some(stored) = stored.as_ref().filter(|s| s.version == version) {
+        return (stored.clone(), None);
+    }
+
+    let capabilities = probe(version.clone()).await;
+    file.clusters
+        .insert(cluster.to_string(), capabilities.clone());
+    if let Some(path) = path {
+        let _ = save(path, &file);
+    }
+    let change = stored.map(|s| VersionChange {
+        cluster: cluster.to_string(),
+        from: s.version,
+        to: version,
+    });
+    (capabilities, change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_and_capabilities_are_kept_per_cluster() {
+        assert_eq!(parse_version("slurm 23.02.7\n").as_deref(), Some("23.02.7"));
+        assert_eq!(
+            parse_version("slurm-wlm 21.08.5").as_deref(),
+            Some("21.08.5")
+        );
+        assert_eq!(parse_version("command not found"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clusters.toml");
+        let mut file = CapabilityFile::default();
+        file.clusters.insert(
+            "alpha".to_string(),
+            Capabilities {
+                version: "23.02.7".to_string(),
+                sacct_fields: vec!["JobID".to_string(), "SubmitLine".to_string()],
+            },
+        );
+        save(&path, &file).unwrap();
+        let alpha = &load(&path).clusters["alpha"];
+        assert_eq!(alpha.version, "23.02.7");
+        assert!(alpha.supports_sacct_field("submitline"));
+        assert!(!alpha.supports_sacct_field("Planned"));
+        assert!(Capabilities::default().supports_sacct_field("Planned"));
+        assert!(alpha.supports_overlap());
+        let old = Capabilities {
+            version: "20.02.3".to_string(),
+            ..Default::default()
+        };
+        assert!(!old.supports_overlap());
+    }
+}
@@ -1,14 +1,305 @@
-use async_process::{Command, Output};
+use std::ffi::OsStr;
+use std::future::Future;
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use async_process::{Command, ExitStatus, Output};
+use color_eyre::eyre::eyre;
 use color_eyre::Result;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use super::error::{checked, SlurmError};
+use super::{cache, recording};
+
+/// Seconds a Slurm command may run before it is killed; 0 waits forever
+static TIMEOUT_SECS: AtomicU64 = AtomicU64::new(30);
+
+/// Bytes of a command's stderr kept in the log
+const LOGGED_STDERR_BYTES: usize = 2048;
+
+/// Set how long Slurm commands may run (`[refresh] command_timeout_secs`)
+pub fn set_timeout(timeout: Duration) {
+    TIMEOUT_SECS.store(timeout.as_secs(), Ordering::Relaxed);
+}
+
+fn timeout() -> Option<Duration> {
+    match TIMEOUT_SECS.load(Ordering::Relaxed) {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
+
+/// Log a run of `program` that took `took`: failures as warnings, every
+/// run with `--debug`. `result` is the exit status and stderr, or why the
+/// command never finished (it could not start, or timed out).
+pub fn log_run(
+    program: &str,
+    args: &[String],
+    took: Duration,
+    result: std::result::Result<(&ExitStatus, &[u8]), &io::Error>,
+) {
+    let argv = argv_line(program, args);
+    let took_ms = took.as_millis() as u64;
+    match result {
+        Ok((status, stderr)) => {
+            let stderr = String::from_utf8_lossy(&stderr[..stderr.len().min(LOGGED_STDERR_BYTES)]);
+            let stderr = stderr.trim();
+            if status.success() {
+                tracing::debug!(%argv, took_ms, %status, stderr, "command finished");
+            } else {
+                tracing::warn!(%argv, took_ms, %status, stderr, "command failed");
+            }
+        }
+        Err(error) => tracing::warn!(%argv, took_ms, %error, "command did not finish"),
+    }
+}
+
+/// The command line of a run, quoted to be pasted into a shell
+fn argv_line(program: &str, args: &[String]) -> String {
+    std::iter::once(program)
+        .chain(args.iter().map(String::as_str))
+        .map(quote_arg)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `arg` as a shell would need it, so logged command lines can be pasted
+fn quote_arg(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_=,.:/%+@[]".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+fn timed_out(program: &str, limit: Duration) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::TimedOut,
+        format!("{} timed out after {}s", program, limit.as_secs()),
+    )
+}
+
+/// Wait for `future`, a run of `program`, no longer than the command timeout.
+/// The child should be spawned with `kill_on_drop`, so giving up on it (or
+/// dropping the future, as when the app quits) kills it.
+pub async fn with_timeout<T, E: From<io::Error>>(
+    program: &str,
+    future: impl Future<Output = std::result::Result<T, E>>,
+) -> std::result::Result<T, E> {
+    match timeout() {
+        None => future.await,
+        Some(limit) => tokio::time::timeout(limit, future)
+            .await
+            .unwrap_or_else(|_| Err(timed_out(program, limit).into())),
+    }
+}
+
+/// Run a Slurm command to completion, killing it when it hangs past the
+/// command timeout or the caller stops waiting
+pub async fn output<I, S>(program: &str, args: I) -> io::Result<Output>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let args = lossy_args(args);
+    if let Some(replayed) = recording::replayed(program, &args) {
+        return replayed;
+    }
+    let mut command = Command::new(program);
+    command.args(&args).kill_on_drop(true);
+    let started = Instant::now();
+    let result = with_timeout(program, command.output()).await;
+    let logged = result.as_ref().map(|o| (&o.status, o.stderr.as_slice()));
+    log_run(program, &args, started.elapsed(), logged);
+    if let Ok(output) = &result {
+        recording::record(program, &args, output);
+    }
+    result
+}
+
+fn lossy_args<I, S>(args: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    args.into_iter()
+        .map(|a| a.as_ref().to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Blocking `output`, for the few lookups made outside the runtime
+pub fn output_blocking<I, S>(program: &str, args: I) -> io::Result<std::process::Output>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let args = lossy_args(args);
+    if let Some(replayed) = recording::replayed(program, &args) {
+        return replayed;
+    }
+    let started = Instant::now();
+    let result = run_blocking(program, &args);
+    let logged = result.as_ref().map(|o| (&o.status, o.stderr.as_slice()));
+    log_run(program, &args, started.elapsed(), logged);
+    if let Ok(output) = &result {
+        recording::record(program, &args, output);
+    }
+    result
+}
+
+fn run_blocking(program: &str, args: &[String]) -> io::Result<std::process::Output> {
+    let mut command = std::process::Command::new(program);
+    command.args(args);
+    run_child(command, None, timeout())
+}
+
+/// Run `command` with piped output, feeding it `input`, and kill it past
+/// `limit` (the command timeout)
+fn run_child(
+    mut command: std::process::Command,
+    input: Option<Vec<u8>>,
+    limit: Option<Duration>,
+) -> io::Result<std::process::Output> {
+    use std::process::Stdio;
+
+    let program = command.get_program().to_string_lossy().into_owned();
+    let stdin = if input.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    };
+    let mut child = command
+        .stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Write and drain the pipes meanwhile, or a chatty command blocks on a
+    // full pipe
+    if let (Some(mut pipe), Some(input)) = (child.stdin.take(), input) {
+        // Dropping stdin after writing closes it so the command can finish
+        std::thread::spawn(move || {
+            let _ = pipe.write_all(&input);
+        });
+    }
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buffer);
+            }
+            buffer
+        })
+    };
+    let stdout = drain(
+        child
+            .stdout
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+    let stderr = drain(
+        child
+            .stderr
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if let Some(limit) = limit.filter(|limit| started.elapsed() >= *limit) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(timed_out(&program, limit));
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    Ok(std::process::Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// The program and arguments of `command`, as logged
+fn argv_of(command: &std::process::Command) -> (String, Vec<String>) {
+    (
+        command.get_program().to_string_lossy().into_owned(),
+        lossy_args(command.get_args()),
+    )
+}
+
+/// Run a local helper (a clipboard tool, a notification hook) set up by
+/// the caller, with `input` on its stdin: logged and killed past the
+/// command timeout like the Slurm commands, but neither recorded nor
+/// replayed
+pub fn run_local(
+    command: std::process::Command,
+    input: Option<&[u8]>,
+) -> io::Result<std::process::Output> {
+    let (program, args) = argv_of(&command);
+    let started = Instant::now();
+    let result = run_child(command, input.map(<[u8]>::to_vec), timeout());
+    let logged = result.as_ref().map(|o| (&o.status, o.stderr.as_slice()));
+    log_run(&program, &args, started.elapsed(), logged);
+    result
+}
+
+/// Run `command` on the user's terminal until it exits, however long that
+/// takes (a shell, an editor), and log it
+pub fn run_interactive(mut command: std::process::Command) -> io::Result<ExitStatus> {
+    let (program, args) = argv_of(&command);
+    let started = Instant::now();
+    let result = command.status();
+    log_run(
+        &program,
+        &args,
+        started.elapsed(),
+        result.as_ref().map(|status| (status, &[][..])),
+    );
+    result
+}
+
+/// Start `command` to run on its own, logging that it started (it is not
+/// waited for); returns its pid
+pub fn spawn_detached(mut command: std::process::Command) -> io::Result<u32> {
+    let (program, args) = argv_of(&command);
+    let argv = argv_line(&program, &args);
+    match command.spawn() {
+        Ok(child) => {
+            tracing::debug!(%argv, pid = child.id(), "command started");
+            Ok(child.id())
+        }
+        Err(error) => {
+            tracing::warn!(%argv, %error, "command did not start");
+            Err(error)
+        }
+    }
+}
 
 /// Execute a Slurm command asynchronously and return the output
 pub async fn execute_command(cmd: &str, args: Vec<String>) -> Result<Output> {
-    let output = Command::new(cmd).args(args).output().await?;
+    let output = output(cmd, args).await?;
 
     Ok(output)
 }
 
+/// Execute a Slurm command that changes jobs, nodes or schedules. Cached
+/// query results are dropped whether or not it succeeded, as even a
+/// failed run may have changed some of them.
+pub async fn execute_update<I, S>(cmd: &str, args: I) -> io::Result<Output>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output = output(cmd, args).await;
+    cache::invalidate();
+    output
+}
+
 /// Execute the squeue command to get job information
 pub async fn _execute_squeue(args: Vec<String>) -> Result<String> {
     let output = execute_command("squeue", args).await?;
@@ -37,24 +328,133 @@ pub async fn execute_scancel(job_ids: Vec<String>) -> Result<()> {
         .map(|chunk| chunk.to_vec())
         .collect();
     for chunk in chunks {
-        let _ = execute_command("scancel", chunk).await?;
+        let output = execute_update("scancel", chunk)
+            .await
+            .map_err(|e| SlurmError::from_io("scancel", &e))?;
+        if output.status.success() {
+            continue;
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let errors = scancel_errors(&stderr);
+        if !errors.is_empty() {
+            return Err(SlurmError::from_stderr("scancel", &errors.join("; ")).into());
+        }
+    }
+
+    Ok(())
+}
+
+/// The lines of scancel's stderr that are errors. Jobs that are completing
+/// meanwhile need no cancelling; an invalid id (a typo, or a job long gone)
+/// is reported, so a cancellation is never taken for done by mistake.
+fn scancel_errors(stderr: &str) -> Vec<&str> {
+    stderr
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .filter(|l| !l.contains("already completing or completed"))
+        .collect()
+}
+
+/// Bulk actions that can be applied to a set of selected jobs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobAction {
+    Cancel,
+    Hold,
+    Release,
+    Requeue,
+}
+
+impl JobAction {
+    /// Verb used in confirmation prompts ("cancel 3 jobs?")
+    pub fn verb(&self) -> &'static str {
+        match self {
+            JobAction::Cancel => "cancel",
+            JobAction::Hold => "hold",
+            JobAction::Release => "release",
+            JobAction::Requeue => "requeue",
+        }
+    }
+
+    /// Whether the action can lose work (protected jobs need a typed confirmation)
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, JobAction::Cancel | JobAction::Requeue)
+    }
+
+    /// Past tense used in status messages ("Held 3 job(s)")
+    pub fn past_tense(&self) -> &'static str {
+        match self {
+            JobAction::Cancel => "Cancelled",
+            JobAction::Hold => "Held",
+            JobAction::Release => "Released",
+            JobAction::Requeue => "Requeued",
+        }
+    }
+}
+
+/// Apply a bulk action to the given jobs
+pub async fn execute_job_action(action: JobAction, job_ids: Vec<String>) -> Result<()> {
+    let subcommand = match action {
+        JobAction::Cancel => return execute_scancel(job_ids).await,
+        JobAction::Hold => "hold",
+        JobAction::Release => "release",
+        JobAction::Requeue => "requeue",
+    };
+
+    // scontrol takes a comma separated job list; chunk it like scancel does.
+    for chunk in job_ids.chunks(200) {
+        let args = vec![subcommand.to_string(), chunk.join(",")];
+        let program = format!("scontrol {}", subcommand);
+        checked(&program, execute_update("scontrol", args).await)?;
     }
 
     Ok(())
 }
 
-/// Execute a command to modify a job (scontrol update)
-pub async fn _modify_job(job_id: &str, parameters: HashMap<String, String>) -> Result<()> {
+/// Change parameters of a job (`scontrol update JobId=<id> Key=Value...`)
+pub async fn modify_job(job_id: &str, parameters: &[(String, String)]) -> Result<()> {
     let mut args = vec!["update".to_string(), format!("JobId={}", job_id)];
 
     for (key, value) in parameters {
         args.push(format!("{}={}", key, value));
     }
 
-    let _ = execute_command("scontrol", args).await?;
+    checked("scontrol update", execute_update("scontrol", args).await)?;
     Ok(())
 }
 
+/// Arguments of `srun` for an interactive shell in a running job's
+/// allocation; `overlap` lets the shell share the resources of the job's
+/// steps instead of waiting for them
+pub fn attach_args(job_id: &str, overlap: bool) -> Vec<String> {
+    let mut args = vec!["--jobid".to_string(), job_id.to_string()];
+    if overlap {
+        args.push("--overlap".to_string());
+    }
+    args.extend(["--pty".to_string(), "bash".to_string()]);
+    args
+}
+
+/// Name of the cluster (`ClusterName` of `scontrol show config`)
+pub async fn get_cluster_name() -> Result<String> {
+    if let Ok(name) = std::env::var("SLURM_CLUSTER_NAME") {
+        if !name.trim().is_empty() {
+            return Ok(name.trim().to_string());
+        }
+    }
+
+    let output =
+        execute_command("scontrol", vec!["show".to_string(), "config".to_string()]).await?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "ClusterName")
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| eyre!("ClusterName not found in scontrol show config"))
+}
+
 /// Get available partitions
 pub async fn get_partitions() -> Result<Vec<String>> {
     let output = execute_command(
@@ -101,3 +501,92 @@ pub async fn get_qos() -> Result<Vec<String>> {
         Ok(qos_list)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn kills_commands_past_the_timeout() {
+        let output = output_blocking("sh", ["-c", "echo done"]).unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "done\n");
+
+        // The limit is passed in, leaving the global timeout to other tests
+        let mut sleep = std::process::Command::new("sleep");
+        sleep.arg("10");
+        let started = Instant::now();
+        let error = run_child(sleep, None, Some(Duration::from_secs(1))).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(error.to_string(), "sleep timed out after 1s");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn scancel_reports_invalid_ids_but_not_completing_jobs() {
+        let stderr = "scancel: error: Kill job error on job id 41: Job/step already completing or completed\n\
+                      scancel: error: Invalid job id 4x2\n";
+        assert_eq!(
+            scancel_errors(stderr),
+            ["scancel: error: Invalid job id 4x2"]
+        );
+    }
+
+    #[test]
+    fn quotes_only_the_arguments_a_shell_would_split() {
+        assert_eq!(quote_arg("--format=%i,%j"), "--format=%i,%j");
+        assert_eq!(quote_arg("gpu[01-04]"), "gpu[01-04]");
+        assert_eq!(quote_arg(""), "''");
+        assert_eq!(quote_arg("my job"), "'my job'");
+        assert_eq!(quote_arg("it's"), r"'it'\''s'");
+        assert_eq!(
+            argv_line("squeue", &["-u".to_string(), "a b".to_string()]),
+            "squeue -u 'a b'"
+        );
+    }
+
+    #[test]
+    fn logs_runs_with_their_command_line_and_stderr() {
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<Mutex<Vec<u8>>>);
+        impl io::Write for Buffer {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .with_max_level(tracing::Level::WARN)
+            .finish();
+        let failed = output_blocking("sh", ["-c", "echo oops >&2; exit 3"]).unwrap();
+        let ok = output_blocking("true", [] as [&str; 0]).unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            log_run(
+                "sh",
+                &["-c".to_string(), "exit 3".to_string()],
+                Duration::from_millis(12),
+                Ok((&failed.status, &failed.stderr)),
+            );
+            log_run("true", &[], Duration::ZERO, Ok((&ok.status, b"")));
+            let error = io::Error::new(io::ErrorKind::NotFound, "no such file");
+            log_run("scontrol", &[], Duration::ZERO, Err(&error));
+        });
+        let logged = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = logged.lines().collect();
+        assert_eq!(lines.len(), 2, "{}", logged);
+        assert!(lines[0].contains("command failed"), "{}", lines[0]);
+        assert!(lines[0].contains("argv=sh -c 'exit 3'"), "{}", lines[0]);
+        assert!(lines[0].contains("took_ms=12"), "{}", lines[0]);
+        assert!(lines[0].contains("stderr=\"oops\""), "{}", lines[0]);
+        assert!(lines[1].contains("command did not finish"), "{}", lines[1]);
+        assert!(lines[1].contains("no such file"), "{}", lines[1]);
+    }
+}
@@ -0,0 +1,303 @@
+use color_eyre::Result;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::cache;
+use super::error::checked;
+use super::{Job, JobState};
+
+/// One condition of a job's `Dependency` (`afterok:123(unfulfilled)`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    /// `afterok`, `afterany`, `afternotok`, `aftercorr`, `after`, `singleton`, ...
+    pub kind: String,
+    /// Job waited on (`123`, `123_4`, `123_*` for a whole array); empty for `singleton`
+    pub job_id: String,
+    pub status: DependencyStatus,
+}
+
+/// How a dependency stands, as reported in parentheses after it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyStatus {
+    Unfulfilled,
+    Fulfilled,
+    /// Can never be satisfied (the job is stuck in `DependencyNeverSatisfied`)
+    Failed,
+    Unknown,
+}
+
+/// Parse a `Dependency` value as printed by `squeue -o %E` or `scontrol show
+/// job` (`afterok:1:2,afterany:3_*(failed)`, `singleton`, `(null)`)
+pub fn parse_dependencies(raw: &str) -> Vec<Dependency> {
+    let mut deps = Vec::new();
+    for item in raw.split([',', '?']).map(str::trim) {
+        let (spec, status) = match item.split_once('(') {
+            Some((spec, status)) => (spec, status.trim_end_matches(')')),
+            None => (item, ""),
+        };
+        let status = match status.to_lowercase().as_str() {
+            "unfulfilled" => DependencyStatus::Unfulfilled,
+            "fulfilled" => DependencyStatus::Fulfilled,
+            "failed" => DependencyStatus::Failed,
+            _ => DependencyStatus::Unknown,
+        };
+        let mut parts = spec.split(':');
+        let kind = parts.next().unwrap_or("").trim();
+        if kind.is_empty() || kind == "null" {
+            continue;
+        }
+        let ids: Vec<&str> = parts
+            // `afterok:123+30`: the `+` minutes delay is not part of the id
+            .map(|id| id.split('+').next().unwrap_or(id).trim())
+            .filter(|id| !id.is_empty())
+            .collect();
+        if ids.is_empty() {
+            deps.push(Dependency {
+                kind: kind.to_string(),
+                job_id: String::new(),
+                status,
+            });
+        }
+        for id in ids {
+            deps.push(Dependency {
+                kind: kind.to_string(),
+                job_id: id.to_string(),
+                status,
+            });
+        }
+    }
+    deps
+}
+
+/// Dependencies of the pending jobs of `user` (everyone's when `None`),
+/// keyed by job id
+pub async fn fetch_dependencies(user: Option<&str>) -> Result<HashMap<String, Vec<Dependency>>> {
+    let mut args = vec!["--noheader", "--states", "PENDING", "--format", "%i|%E"];
+    if let Some(user) = user {
+        args.extend(["--user", user]);
+    }
+    let output = checked("squeue", cache::query("squeue", &args).await)?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (id, raw) = line.split_once('|')?;
+            let deps = parse_dependencies(raw);
+            (!deps.is_empty()).then(|| (id.trim().to_string(), deps))
+        })
+        .collect())
+}
+
+/// One row of the dependency tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeLine {
+    /// Box-drawing prefix (`│  └─ `)
+    pub prefix: String,
+    pub job_id: String,
+    /// How this job depends on the one above it; `None` for the roots
+    pub dependency: Option<Dependency>,
+    pub name: Option<String>,
+    pub state: Option<JobState>,
+    /// Shown before under another job it waits on; its dependents are not repeated
+    pub repeated: bool,
+}
+
+/// The jobs waiting on other jobs, as a tree from the jobs that wait on
+/// nothing down to their dependents. A job waiting on several jobs appears
+/// under each of them. Jobs not in `jobs` (ended, or someone else's) are
+/// shown by id only.
+pub fn dependency_tree(jobs: &[Job], deps: &HashMap<String, Vec<Dependency>>) -> Vec<TreeLine> {
+    let mut downstream: BTreeMap<&str, Vec<(&str, &Dependency)>> = BTreeMap::new();
+    for (job_id, job_deps) in deps {
+        for dep in job_deps.iter().filter(|d| !d.job_id.is_empty()) {
+            downstream
+                .entry(dep.job_id.as_str())
+                .or_default()
+                .push((job_id.as_str(), dep));
+        }
+    }
+    for children in downstream.values_mut() {
+        children.sort_by_key(|(id, _)| job_id_key(id));
+    }
+
+    let mut roots: Vec<&str> = downstream
+        .keys()
+        .copied()
+        .filter(|id| {
+            deps.get(*id)
+                .is_none_or(|d| d.iter().all(|d| d.job_id.is_empty()))
+        })
+        .collect();
+    roots.sort_by_key(|id| job_id_key(id));
+
+    let tree = Tree {
+        jobs,
+        downstream,
+        lines: Vec::new(),
+        shown: HashSet::new(),
+    };
+    tree.build(&roots)
+}
+
+/// Numeric job ids sort by number, not as text
+fn job_id_key(id: &str) -> (usize, &str) {
+    let digits = id.find(|c: char| !c.is_ascii_digit()).unwrap_or(id.len());
+    (digits, id)
+}
+
+struct Tree<'a> {
+    jobs: &'a [Job],
+    downstream: BTreeMap<&'a str, Vec<(&'a str, &'a Dependency)>>,
+    lines: Vec<TreeLine>,
+    shown: HashSet<&'a str>,
+}
+
+impl<'a> Tree<'a> {
+    fn build(mut self, roots: &[&'a str]) -> Vec<TreeLine> {
+        for root in roots {
+            self.visit(root, None, "", "");
+        }
+        self.lines
+    }
+
+    fn visit(
+        &mut self,
+        id: &'a str,
+        dependency: Option<&Dependency>,
+        prefix: &str,
+        child_prefix: &str,
+    ) {
+        let job = self.find(id);
+        let repeated = !self.shown.insert(id);
+        self.lines.push(TreeLine {
+            prefix: prefix.to_string(),
+            job_id: id.to_string(),
+            dependency: dependency.cloned(),
+            name: job.map(|j| j.name.clone()),
+            state: job.map(|j| j.state),
+            repeated,
+        });
+        if repeated {
+            return;
+        }
+
+        let children = self.downstream.get(id).cloned().unwrap_or_default();
+        for (i, (child, dep)) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            let (branch, rest) = if last {
+                ("└─ ", "   ")
+            } else {
+                ("├─ ", "│  ")
+            };
+            self.visit(
+                child,
+                Some(dep),
+                &format!("{}{}", child_prefix, branch),
+                &format!("{}{}", child_prefix, rest),
+            );
+        }
+    }
+
+    /// The listed job with this id; `123_*` stands for any task of array 123
+    fn find(&self, id: &str) -> Option<&'a Job> {
+        let jobs = self.jobs;
+        match id.strip_suffix("_*") {
+            Some(array) => jobs.iter().find(|j| {
+                j.id == array || j.id.strip_prefix(array).is_some_and(|t| t.starts_with('_'))
+            }),
+            None => jobs.iter().find(|j| j.id == id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dependency_lists() {
+        assert_eq!(
+            parse_dependencies("afterok:12:13+5,afterany:9_*(failed)"),
+            vec![
+                Dependency {
+                    kind: "afterok".to_string(),
+                    job_id: "12".to_string(),
+                    status: DependencyStatus::Unknown,
+                },
+                Dependency {
+                    kind: "afterok".to_string(),
+                    job_id: "13".to_string(),
+                    status: DependencyStatus::Unknown,
+                },
+                Dependency {
+                    kind: "afterany".to_string(),
+                    job_id: "9_*".to_string(),
+                    status: DependencyStatus::Failed,
+                },
+            ]
+        );
+        assert_eq!(parse_dependencies("singleton(unfulfilled)")[0].job_id, "");
+        assert!(parse_dependencies("(null)").is_empty());
+        assert!(parse_dependencies("").is_empty());
+    }
+
+    #[test]
+    fn tree_runs_from_upstream_jobs_to_their_dependents() {
+        let job = |id: &str, state| Job {
+            id: id.to_string(),
+            name: format!("step{}", id),
+            state,
+            ..Default::default()
+        };
+        let jobs = vec![
+            job("10", JobState::Running),
+            job("11", JobState::Pending),
+            job("12", JobState::Pending),
+            job("13", JobState::Pending),
+        ];
+        let deps = HashMap::from([
+            (
+                "11".to_string(),
+                parse_dependencies("afterok:10(unfulfilled)"),
+            ),
+            (
+                "12".to_string(),
+                parse_dependencies("afterok:10(unfulfilled)"),
+            ),
+            (
+                "13".to_string(),
+                parse_dependencies("afterok:11(unfulfilled),afterok:12(unfulfilled)"),
+            ),
+            ("14".to_string(), parse_dependencies("afternotok:9(failed)")),
+        ]);
+        let lines: Vec<String> = dependency_tree(&jobs, &deps)
+            .iter()
+            .map(|l| {
+                format!(
+                    "{}{}{}",
+                    l.prefix,
+                    l.job_id,
+                    if l.repeated { "*" } else { "" }
+                )
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "9",
+                "└─ 14",
+                "10",
+                "├─ 11",
+                "│  └─ 13",
+                "└─ 12",
+                "   └─ 13*",
+            ]
+        );
+        let tree = dependency_tree(&jobs, &deps);
+        assert_eq!(tree[0].state, None, "job 9 has ended");
+        assert_eq!(
+            tree[1].dependency.as_ref().map(|d| d.status),
+            Some(DependencyStatus::Failed)
+        );
+        assert_eq!(tree[2].name.as_deref(), Some("step10"));
+    }
+}
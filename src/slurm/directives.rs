@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+
+use super::submit::{split_shell_words, SbatchOption, SubmitSpec};
+
+/// Options that each set the job's memory; sbatch accepts only one of them
+const MEMORY_OPTIONS: &[&str] = &["mem", "mem-per-cpu", "mem-per-gpu"];
+
+/// Options that request GPUs besides `--gres=gpu:...`
+const GPU_OPTIONS: &[&str] = &["gpus", "gpus-per-node", "gpus-per-task", "gpus-per-socket"];
+
+/// `#SBATCH` options of a script in order, plus problems with the lines
+/// themselves: sbatch stops reading directives at the first command.
+pub fn script_directives(script: &str) -> (Vec<SbatchOption>, Vec<String>) {
+    let mut options = Vec::new();
+    let mut warnings = Vec::new();
+    let mut commands_started = false;
+    for (i, line) in script.lines().enumerate() {
+        let line = line.trim();
+        let Some(rest) = line.strip_prefix("#SBATCH") else {
+            if !line.is_empty() && !line.starts_with('#') {
+                commands_started = true;
+            }
+            continue;
+        };
+        if commands_started {
+            warnings.push(format!(
+                "line {}: #SBATCH after the first command is ignored by sbatch",
+                i + 1
+            ));
+            continue;
+        }
+        // Like sbatch, stop at a comment after the options
+        let rest = rest.split(" #").next().unwrap_or("");
+        let mut words = match split_shell_words(rest) {
+            Ok(words) => words.into_iter(),
+            Err(e) => {
+                warnings.push(format!("line {}: {}", i + 1, e));
+                continue;
+            }
+        };
+        while let Some(word) = words.next() {
+            match SbatchOption::next(&word, &mut words) {
+                Ok(Some(option)) => options.push(option),
+                Ok(None) => {}
+                Err(e) => warnings.push(format!("line {}: {}", i + 1, e)),
+            }
+        }
+    }
+    (options, warnings)
+}
+
+/// Conflicting or redundant resource directives of a script
+pub fn script_warnings(script: &str) -> Vec<String> {
+    let (options, mut warnings) = script_directives(script);
+    warnings.extend(repeated(&options));
+    warnings.extend(conflicts(&effective(&options)));
+    warnings
+}
+
+/// Conflicts of a submission: the script's directives with the form's
+/// options, which replace directives of the same name
+pub fn submission_warnings(spec: &SubmitSpec) -> Vec<String> {
+    let (directives, mut warnings) = script_directives(&spec.script);
+    warnings.extend(repeated(&directives));
+
+    let mut args = spec.to_sbatch_args().into_iter();
+    let mut command_line = Vec::new();
+    while let Some(word) = args.next() {
+        if let Ok(Some(option)) = SbatchOption::next(&word, &mut args) {
+            command_line.push(option);
+        }
+    }
+    let mut options = effective(&directives);
+    options.extend(effective(&command_line));
+    warnings.extend(conflicts(&options));
+    warnings
+}
+
+/// The value of each option by long name; the last one given wins
+fn effective(options: &[SbatchOption]) -> BTreeMap<String, String> {
+    options
+        .iter()
+        .map(|o| {
+            (
+                o.long_name().to_string(),
+                o.value.clone().unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+/// Options given more than once, of which only the last counts
+fn repeated(options: &[SbatchOption]) -> Vec<String> {
+    let mut values: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for option in options {
+        values
+            .entry(option.long_name())
+            .or_default()
+            .push(option.value.as_deref().unwrap_or(""));
+    }
+    values
+        .into_iter()
+        .filter(|(_, values)| values.len() > 1)
+        .map(|(name, values)| {
+            if values.iter().all(|v| *v == values[0]) {
+                format!("--{} is given {} times", name, values.len())
+            } else {
+                format!(
+                    "--{} is given as {}; only the last counts",
+                    name,
+                    values.join(", then ")
+                )
+            }
+        })
+        .collect()
+}
+
+fn conflicts(options: &BTreeMap<String, String>) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let given = |name: &str| options.get(name).map(String::as_str);
+    let count = |name: &str| given(name).and_then(|v| v.trim().parse::<u32>().ok());
+
+    let memory: Vec<String> = MEMORY_OPTIONS
+        .iter()
+        .filter(|name| given(name).is_some())
+        .map(|name| format!("--{}", name))
+        .collect();
+    if memory.len() > 1 {
+        warnings.push(format!(
+            "{} are mutually exclusive; sbatch rejects the job",
+            memory.join(" and ")
+        ));
+    }
+
+    let gpus: Vec<&str> = GPU_OPTIONS
+        .iter()
+        .copied()
+        .filter(|name| given(name).is_some())
+        .collect();
+    if let (Some(gres), Some(other)) = (given("gres"), gpus.first()) {
+        if gres
+            .split(',')
+            .any(|g| g.trim_start_matches("gres/").starts_with("gpu"))
+        {
+            warnings.push(format!(
+                "GPUs are requested by both --gres={} and --{}; use one",
+                gres, other
+            ));
+        }
+    }
+
+    // A node range (`2-4`) has no single count to check against
+    match (count("ntasks"), count("nodes"), count("ntasks-per-node")) {
+        (Some(tasks), Some(nodes), Some(per_node)) if tasks != nodes * per_node => {
+            warnings.push(format!(
+                "--ntasks={} does not match --nodes={} x --ntasks-per-node={} ({}); \
+                 --ntasks wins and --ntasks-per-node becomes a maximum",
+                tasks,
+                nodes,
+                per_node,
+                nodes * per_node
+            ));
+        }
+        (Some(tasks), Some(nodes), None) if tasks < nodes => {
+            warnings.push(format!(
+                "--ntasks={} is fewer than --nodes={}; Slurm lowers the node count",
+                tasks, nodes
+            ));
+        }
+        _ => {}
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_conflicting_directives() {
+        let script = "#!/bin/bash\n\
+                      #SBATCH -N 2 --ntasks-per-node=2\n\
+                      #SBATCH --ntasks=8\n\
+                      #SBATCH --mem=16G  # per node\n\
+                      #SBATCH -t 1:00:00\n\
+                      #SBATCH --time=2:00:00\n\
+                      \n\
+                      module load cuda\n\
+                      #SBATCH --gres=gpu:1\n\
+                      srun ./a.out\n";
+        assert_eq!(
+            script_warnings(script),
+            vec![
+                "line 9: #SBATCH after the first command is ignored by sbatch",
+                "--time is given as 1:00:00, then 2:00:00; only the last counts",
+                "--ntasks=8 does not match --nodes=2 x --ntasks-per-node=2 (4); \
+                 --ntasks wins and --ntasks-per-node becomes a maximum",
+            ]
+        );
+
+        // The form's per-CPU memory does not replace the script's --mem
+        let spec = SubmitSpec {
+            script: "#!/bin/bash\n#SBATCH --mem=16G\n#SBATCH -G 1\n".to_string(),
+            memory: "4G/cpu".to_string(),
+            gres: "gpu:2".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            submission_warnings(&spec),
+            vec![
+                "--mem and --mem-per-cpu are mutually exclusive; sbatch rejects the job",
+                "GPUs are requested by both --gres=gpu:2 and --gpus; use one",
+            ]
+        );
+    }
+}
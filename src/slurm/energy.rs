@@ -0,0 +1,58 @@
+/// Joules in a kilowatt-hour
+const JOULES_PER_KWH: f64 = 3_600_000.0;
+
+/// Energy in joules as sacct prints it: `ConsumedEnergyRaw` (`5400000`) or
+/// `ConsumedEnergy` with a unit suffix in powers of 1024 (`5.15M`). `None`
+/// when accounting gathers no energy (0 or empty).
+pub fn parse_energy(raw: &str) -> Option<u64> {
+    let raw = raw.trim();
+    let (number, multiplier) = match raw.char_indices().last()? {
+        (i, 'K') => (&raw[..i], 1024f64),
+        (i, 'M') => (&raw[..i], 1024f64.powi(2)),
+        (i, 'G') => (&raw[..i], 1024f64.powi(3)),
+        (i, 'T') => (&raw[..i], 1024f64.powi(4)),
+        _ => (raw, 1.0),
+    };
+    let joules = (number.parse::<f64>().ok()? * multiplier).round() as u64;
+    (joules > 0).then_some(joules)
+}
+
+pub fn kwh(joules: u64) -> f64 {
+    joules as f64 / JOULES_PER_KWH
+}
+
+/// Energy for display: `Wh` below a kilowatt-hour, `kWh` above
+pub fn format_energy(joules: u64) -> String {
+    let kwh = kwh(joules);
+    if kwh < 1.0 {
+        format!("{:.0} Wh", kwh * 1000.0)
+    } else {
+        format!("{:.2} kWh", kwh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slurm::{sacct::apply_sacct_field, Job};
+
+    #[test]
+    fn energy_parses_with_and_without_units() {
+        assert_eq!(parse_energy("5400000"), Some(5_400_000));
+        assert_eq!(parse_energy("5.15M"), Some(5_400_166));
+        assert_eq!(parse_energy("12K"), Some(12_288));
+        // Not gathered
+        assert_eq!(parse_energy("0"), None);
+        assert_eq!(parse_energy(""), None);
+        assert_eq!(parse_energy("n/a"), None);
+
+        // Only the raw field sets a job's energy, not the rounded one
+        let mut job = Job::default();
+        apply_sacct_field(&mut job, "ConsumedEnergyRaw", "5400000");
+        apply_sacct_field(&mut job, "ConsumedEnergy", "5.15M");
+        assert_eq!(job.energy, Some(5_400_000));
+
+        assert_eq!(format_energy(5_400_000), "1.50 kWh");
+        assert_eq!(format_energy(1_800_000), "500 Wh");
+    }
+}
@@ -0,0 +1,176 @@
+use std::fmt;
+use std::io;
+use std::process::Output;
+
+/// Controller messages meaning Slurm is there but could not be reached
+const UNREACHABLE: &[&str] = &[
+    "unable to contact slurm controller",
+    "not responding",
+    "socket timed out",
+    "connection refused",
+    "connection reset",
+    "zero bytes were transmitted",
+];
+
+const PERMISSION: &[&str] = &[
+    "permission denied",
+    "access denied",
+    "not authorized",
+    "invalid user",
+    "authentication failure",
+];
+
+/// Why a Slurm command gave no answer. The UI keeps running on these and
+/// shows them as a warning while polling retries.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlurmError {
+    /// The program is not installed or not on `PATH`
+    Missing {
+        program: String,
+    },
+    PermissionDenied {
+        program: String,
+        detail: String,
+    },
+    /// slurmctld (or slurmdbd for sacct) did not answer
+    Unreachable {
+        program: String,
+        detail: String,
+    },
+    /// The command ran past the command timeout and was killed
+    TimedOut {
+        program: String,
+    },
+    Failed {
+        program: String,
+        detail: String,
+    },
+}
+
+impl SlurmError {
+    /// Classify an error starting or waiting for `program`
+    pub fn from_io(program: &str, error: &io::Error) -> Self {
+        let program = program.to_string();
+        match error.kind() {
+            io::ErrorKind::NotFound => SlurmError::Missing { program },
+            io::ErrorKind::PermissionDenied => SlurmError::PermissionDenied {
+                program,
+                detail: error.to_string(),
+            },
+            io::ErrorKind::TimedOut => SlurmError::TimedOut { program },
+            _ => SlurmError::Failed {
+                program,
+                detail: error.to_string(),
+            },
+        }
+    }
+
+    /// Classify a failed run of `program` by what it printed
+    pub fn from_stderr(program: &str, stderr: &str) -> Self {
+        let program = program.to_string();
+        let detail = stderr.trim().to_string();
+        let lower = detail.to_lowercase();
+        if UNREACHABLE.iter().any(|m| lower.contains(m)) {
+            SlurmError::Unreachable { program, detail }
+        } else if PERMISSION.iter().any(|m| lower.contains(m)) {
+            SlurmError::PermissionDenied { program, detail }
+        } else {
+            SlurmError::Failed { program, detail }
+        }
+    }
+
+    /// What the user can do about it
+    pub fn hint(&self) -> &'static str {
+        match self {
+            SlurmError::Missing { .. } => "load the Slurm module or add its bin directory to PATH",
+            SlurmError::PermissionDenied { .. } => "check munge and your cluster account",
+            SlurmError::Unreachable { .. } | SlurmError::TimedOut { .. } => {
+                "the controller may be restarting; retrying"
+            }
+            SlurmError::Failed { .. } => "retrying",
+        }
+    }
+}
+
+impl fmt::Display for SlurmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SlurmError::Missing { program } => write!(f, "{} not found", program),
+            SlurmError::PermissionDenied { program, detail } => {
+                write!(f, "{} permission denied: {}", program, detail)
+            }
+            SlurmError::Unreachable { program, detail } => {
+                write!(f, "{}: controller not responding ({})", program, detail)
+            }
+            SlurmError::TimedOut { program } => write!(f, "{} timed out", program),
+            SlurmError::Failed { program, detail } => write!(f, "{} failed: {}", program, detail),
+        }
+    }
+}
+
+impl std::error::Error for SlurmError {}
+
+/// The output of a run of `program` that succeeded, or why it gave none:
+/// the error running it, or what it printed when it failed
+pub fn checked(program: &str, result: io::Result<Output>) -> Result<Output, SlurmError> {
+    let output = result.map_err(|e| SlurmError::from_io(program, &e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SlurmError::from_stderr(program, &stderr));
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_are_classified() {
+        let missing = io::Error::from(io::ErrorKind::NotFound);
+        assert_eq!(
+            SlurmError::from_io("squeue", &missing),
+            SlurmError::Missing {
+                program: "squeue".to_string()
+            }
+        );
+        let timed_out = io::Error::new(io::ErrorKind::TimedOut, "squeue timed out after 30s");
+        assert!(matches!(
+            SlurmError::from_io("squeue", &timed_out),
+            SlurmError::TimedOut { .. }
+        ));
+        assert!(matches!(
+            SlurmError::from_stderr(
+                "squeue",
+                "slurm_load_jobs error: Unable to contact slurm controller (connect failure)\n"
+            ),
+            SlurmError::Unreachable { .. }
+        ));
+        assert!(matches!(
+            SlurmError::from_stderr("sacct", "sacct: error: Access/permission denied"),
+            SlurmError::PermissionDenied { .. }
+        ));
+        let unknown_user = SlurmError::from_stderr("squeue", "squeue: error: Invalid user: bob");
+        assert!(matches!(unknown_user, SlurmError::PermissionDenied { .. }));
+        assert_eq!(
+            SlurmError::from_stderr("squeue", " bad option \n").to_string(),
+            "squeue failed: bad option"
+        );
+
+        use std::os::unix::process::ExitStatusExt;
+        let output = |code: i32, stderr: &str| Output {
+            status: ExitStatusExt::from_raw(code << 8),
+            stdout: b"42\n".to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        };
+        assert_eq!(checked("sstat", Ok(output(0, ""))).unwrap().stdout, b"42\n");
+        assert!(matches!(
+            checked("sstat", Ok(output(1, "sstat: error: Socket timed out"))),
+            Err(SlurmError::Unreachable { .. })
+        ));
+        assert!(matches!(
+            checked("sstat", Err(missing)),
+            Err(SlurmError::Missing { .. })
+        ));
+    }
+}
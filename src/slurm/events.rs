@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Local};
+use color_eyre::Result;
+
+use super::error::checked;
+use super::sacct::{format_arg, parse_sacct_output, SACCT_DELIMITER};
+use super::{cache, command, parse_scontrol_output, Job, JobState};
+use crate::utils::parse_slurm_time;
+
+/// Fields of each run; `-D` makes sacct list every requeued run
+const RUN_FIELDS: &[&str] = &[
+    "JobIDRaw",
+    "State",
+    "Start",
+    "End",
+    "NNodes",
+    "NodeList",
+    "DerivedExitCode",
+];
+
+/// The end of the slurmctld log that is searched, as the log can be huge
+const LOG_TAIL_BYTES: u64 = 8 * 1024 * 1024;
+
+/// What happened to a job besides the states it went through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobEventKind {
+    NodeFailure,
+    Requeue,
+    Resize,
+    /// A step ended with an error (`DerivedExitCode`)
+    StepFailure,
+}
+
+impl JobEventKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            JobEventKind::NodeFailure => "Node failure",
+            JobEventKind::Requeue => "Requeue",
+            JobEventKind::Resize => "Resize",
+            JobEventKind::StepFailure => "Step failure",
+        }
+    }
+}
+
+/// One event of a job, as accounting, slurmctld or its log tells it
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobEvent {
+    /// When it happened, if known
+    pub at: Option<DateTime<Local>>,
+    pub kind: JobEventKind,
+    pub detail: String,
+    /// `sacct`, `scontrol` or `slurmctld log`
+    pub source: &'static str,
+}
+
+/// The job's node failures, requeues and resizes, oldest first, from its
+/// runs in accounting, `scontrol show job` and the slurmctld log where it
+/// is readable
+pub async fn fetch_job_events(job_id: &str) -> Result<Vec<JobEvent>> {
+    let output = cache::query(
+        "sacct",
+        [
+            "-n",
+            "-P",
+            &format!("--delimiter={}", SACCT_DELIMITER),
+            "-X",
+            "-D",
+            "-j",
+            job_id,
+            "--format",
+            &format_arg(RUN_FIELDS),
+        ],
+    )
+    .await;
+    let output = checked("sacct", output)?;
+    let runs = parse_sacct_output(&String::from_utf8_lossy(&output.stdout), RUN_FIELDS)?;
+    let mut events = run_events(&runs);
+
+    // Jobs that left slurmctld have no record there; accounting is enough
+    if let Ok(output) = command::output("scontrol", ["show", "job", job_id, "-o"]).await {
+        if output.status.success() {
+            let fields = parse_scontrol_output(&String::from_utf8_lossy(&output.stdout));
+            events = merge(events, scontrol_events(&fields));
+        }
+    }
+    if let Some(log) = slurmctld_log_tail().await {
+        events = merge(events, log_events(&log, job_id));
+    }
+    Ok(events)
+}
+
+/// Events of the runs of one job, oldest first: a run other than the last
+/// was requeued (or resized, which also starts a new record), and the
+/// nodes of `NODE_FAIL` runs failed
+pub fn run_events(runs: &[Job]) -> Vec<JobEvent> {
+    let mut events = Vec::new();
+    for (i, run) in runs.iter().enumerate() {
+        let nodes = run.node.as_deref().unwrap_or("?");
+        let event = |kind, detail: String| JobEvent {
+            at: run.end_time,
+            kind,
+            detail,
+            source: "sacct",
+        };
+        if run.state == JobState::NodeFail {
+            events.push(event(
+                JobEventKind::NodeFailure,
+                format!("node failure on {}", nodes),
+            ));
+        }
+        if let Some(code) = run.derived_exit_code.as_deref().filter(|c| *c != "0:0") {
+            events.push(event(
+                JobEventKind::StepFailure,
+                format!("a step exited with {}", code),
+            ));
+        }
+        let Some(next) = runs.get(i + 1) else {
+            continue;
+        };
+        if run.state == JobState::Resizing {
+            events.push(event(
+                JobEventKind::Resize,
+                format!(
+                    "resized from {} to {} nodes ({})",
+                    run.nodes,
+                    next.nodes,
+                    next.node.as_deref().unwrap_or("?")
+                ),
+            ));
+        } else {
+            events.push(event(
+                JobEventKind::Requeue,
+                format!("requeued after {} on {}", run.state, nodes),
+            ));
+        }
+    }
+    events
+}
+
+/// What `scontrol show job` adds to accounting: restarts and the last
+/// resize, for clusters that keep no per-run records
+pub fn scontrol_events(fields: &HashMap<String, String>) -> Vec<JobEvent> {
+    let known = |key: &str| {
+        fields
+            .get(key)
+            .filter(|v| !matches!(v.as_str(), "" | "None" | "Unknown" | "(null)"))
+    };
+    let mut events = Vec::new();
+    if let Some(restarts) = known("Restarts").and_then(|r| r.parse::<u32>().ok()) {
+        if restarts > 0 {
+            events.push(JobEvent {
+                at: None,
+                kind: JobEventKind::Requeue,
+                detail: format!(
+                    "restarted {} time{}",
+                    restarts,
+                    if restarts == 1 { "" } else { "s" }
+                ),
+                source: "scontrol",
+            });
+        }
+    }
+    if let Some(at) = known("ResizeTime") {
+        events.push(JobEvent {
+            at: parse_slurm_time(at),
+            kind: JobEventKind::Resize,
+            detail: format!(
+                "resized to {} nodes",
+                known("NumNodes").map_or("?", |n| n.as_str())
+            ),
+            source: "scontrol",
+        });
+    }
+    if known("Reason").is_some_and(|r| r.contains("NodeDown") || r.contains("NodeFail")) {
+        events.push(JobEvent {
+            at: None,
+            kind: JobEventKind::NodeFailure,
+            detail: format!("reason {}", fields["Reason"]),
+            source: "scontrol",
+        });
+    }
+    events
+}
+
+/// Lines of the slurmctld log about `job_id` that tell of a requeue, a
+/// resize or a failed node, e.g.
+/// `[2024-05-14T09:12:40.311] requeue JobId=48213 due to failure of node gpu07`
+pub fn log_events(log: &str, job_id: &str) -> Vec<JobEvent> {
+    let tag = format!("JobId={}", job_id);
+    log.lines()
+        .filter_map(|line| {
+            let start = line.find(&tag)?;
+            // `JobId=4821` must not match `JobId=48213`
+            let after = &line[start + tag.len()..];
+            if after.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_') {
+                return None;
+            }
+            let lower = line.to_lowercase();
+            let kind = if lower.contains("resiz") {
+                JobEventKind::Resize
+            } else if lower.contains("node_fail")
+                || lower.contains("failed node")
+                || lower.contains("failure of node")
+                || lower.contains("not responding")
+            {
+                JobEventKind::NodeFailure
+            } else if lower.contains("requeu") {
+                JobEventKind::Requeue
+            } else {
+                return None;
+            };
+            let (at, message) = match line.strip_prefix('[').and_then(|l| l.split_once(']')) {
+                Some((at, message)) => (
+                    parse_slurm_time(at.split('.').next().unwrap_or(at)),
+                    message,
+                ),
+                None => (None, line),
+            };
+            Some(JobEvent {
+                at,
+                kind,
+                detail: message.trim().to_string(),
+                source: "slurmctld log",
+            })
+        })
+        .collect()
+}
+
+/// `extra` added to `events`, oldest first, leaving out those of a kind
+/// already seen within a minute of them; events of unknown time go last
+fn merge(mut events: Vec<JobEvent>, extra: Vec<JobEvent>) -> Vec<JobEvent> {
+    for event in extra {
+        let seen = events.iter().any(|e| {
+            e.kind == event.kind
+                && match (e.at, event.at) {
+                    (Some(a), Some(b)) => (a - b).num_seconds().abs() <= 60,
+                    // A count of restarts repeats what the runs tell
+                    (_, None) => event.source == "scontrol" && e.source == "sacct",
+                    _ => false,
+                }
+        });
+        if !seen {
+            events.push(event);
+        }
+    }
+    events.sort_by_key(|e| (e.at.is_none(), e.at));
+    events
+}
+
+/// The end of the slurmctld log, if `SlurmctldLogFile` is readable here
+async fn slurmctld_log_tail() -> Option<String> {
+    let path = slurmctld_log_path().await?;
+    // The log may sit on a slow filesystem; keep the runtime's threads free
+    tokio::task::spawn_blocking(move || read_tail(&path, LOG_TAIL_BYTES))
+        .await
+        .ok()?
+}
+
+/// `SlurmctldLogFile` of `scontrol show config`, asked once a session
+async fn slurmctld_log_path() -> Option<PathBuf> {
+    static PATH: OnceLock<Option<PathBuf>> = OnceLock::new();
+    if let Some(path) = PATH.get() {
+        return path.clone();
+    }
+    let output = command::output("scontrol", ["show", "config"]).await.ok()?;
+    if !output.status.success() {
+        // Asked again next time, the controller may be back by then
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let path = stdout
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "SlurmctldLogFile")
+        .map(|(_, value)| value.trim())
+        .filter(|path| path.starts_with('/'))
+        .map(PathBuf::from);
+    PATH.get_or_init(|| path).clone()
+}
+
+/// The last `bytes` of the file at `path`, seeking past the rest
+fn read_tail(path: &Path, bytes: u64) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let length = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(length.saturating_sub(bytes)))
+        .ok()?;
+    let mut raw = Vec::new();
+    file.read_to_end(&mut raw).ok()?;
+    Some(String::from_utf8_lossy(&raw).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_scontrol_and_the_log_make_one_event_list() {
+        let run = |state: JobState, end: &str, nodes: u32, node: &str| Job {
+            id: "48213".to_string(),
+            state,
+            end_time: parse_slurm_time(end),
+            nodes,
+            node: Some(node.to_string()),
+            ..Default::default()
+        };
+        let runs = vec![
+            run(JobState::NodeFail, "2024-05-14T09:12:40", 2, "gpu[07-08]"),
+            run(JobState::Resizing, "2024-05-14T10:00:00", 2, "gpu[05-06]"),
+            Job {
+                derived_exit_code: Some("1:0".to_string()),
+                ..run(JobState::Failed, "2024-05-14T11:30:00", 1, "gpu05")
+            },
+        ];
+        let events = run_events(&runs);
+        let kinds: Vec<JobEventKind> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                JobEventKind::NodeFailure,
+                JobEventKind::Requeue,
+                JobEventKind::Resize,
+                JobEventKind::StepFailure,
+            ]
+        );
+        assert_eq!(events[2].detail, "resized from 2 to 1 nodes (gpu05)");
+
+        let fields = parse_scontrol_output(
+            "JobId=48213 Restarts=1 ResizeTime=2024-05-14T10:00:20 NumNodes=1 Reason=None",
+        );
+        let log = "\
+[2024-05-14T09:12:41.102] requeue JobId=48213 due to failure of node gpu07
+[2024-05-14T09:12:41.300] _job_complete: JobId=482130 WEXITSTATUS 0
+[2024-05-14T09:40:02.877] Requeuing JobId=48213 after an admin request
+";
+        let merged = merge(
+            merge(events, scontrol_events(&fields)),
+            log_events(log, "48213"),
+        );
+        // The restart count, the resize and the first log line repeat the
+        // runs; the admin requeue is new
+        assert_eq!(merged.len(), 5);
+        assert_eq!(merged[2].source, "slurmctld log");
+        assert_eq!(merged[2].at, parse_slurm_time("2024-05-14T09:40:02"));
+        assert_eq!(
+            merged[2].detail,
+            "Requeuing JobId=48213 after an admin request"
+        );
+
+        // Only the end of the log is read
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("slurmctld.log");
+        std::fs::write(&path, log).unwrap();
+        let tail = read_tail(&path, 60).unwrap();
+        assert_eq!(tail.len(), 60);
+        assert!(log.ends_with(&tail));
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/// Built-in remediation hints, keyed by job state or pending reason;
+/// `{resubmit}` and `{release}` stand for the keys of those actions
+const HINTS: &[(&str, &str)] = &[
+    ("OUT_OF_MEMORY", "Ran out of memory: increase --mem (or --mem-per-cpu), or reduce the batch size / per-process memory."),
+    ("TIMEOUT", "Hit the time limit: increase --time, or checkpoint and resubmit from the last checkpoint."),
+    ("NODE_FAIL", "A node failed, not the job: resubmit ({resubmit}); if it repeats, --exclude the failing node."),
+    ("BOOT_FAIL", "A node failed to boot, not the job: resubmit ({resubmit})."),
+    ("FAILED", "Non-zero exit: check the end of the stderr log (Logs tab) for the first error."),
+    ("DEADLINE", "Could not finish before --deadline: move the deadline or request less time."),
+    ("AssocMaxJobsLimit", "Your running-job limit is reached: wait, or bundle work into a job array / fewer bigger jobs."),
+    ("QOSMaxJobsPerUserLimit", "The QOS job limit is reached: wait for running jobs, or use another --qos."),
+    ("AssocGrpCpuLimit", "Your account's CPU quota is in use: wait for running jobs or submit under another --account."),
+    ("AssocGrpGRES", "Your account's GPU quota is in use: wait for running jobs or request fewer GPUs."),
+    ("InvalidAccount", "Invalid account: list yours with `sacctmgr show assoc user=$USER format=account` and pass --account."),
+    ("InvalidQOS", "Invalid QOS for your association: list allowed ones with `sacctmgr show assoc user=$USER format=qos`."),
+    ("PartitionTimeLimit", "--time exceeds the partition maximum (see `sinfo -o '%P %l'`): lower it or change partition."),
+    ("QOSMaxWallDurationPerJobLimit", "--time exceeds the QOS maximum: lower it or use a QOS that allows longer jobs."),
+    ("PartitionNodeLimit", "Node count is outside the partition limits: adjust --nodes or change partition."),
+    ("DependencyNeverSatisfied", "A dependency failed, so this job will never start: cancel it, or fix the dependency with `scontrol update Dependency=`."),
+    ("ReqNodeNotAvail", "A requested node is unavailable: drop --nodelist/--exclude constraints or wait for maintenance."),
+    ("BadConstraints", "No node matches --constraint/--gres: check feature names with `sinfo -o '%N %f %G'`."),
+    ("JobHeldUser", "You held this job: release it with {release}."),
+];
+
+/// A failure of the node around the job rather than of the job itself: its
+/// prolog or epilog script, or the launch. Users rarely recognize these and
+/// cannot fix them; the admins can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupFailure {
+    /// The prolog failed before the job ran; Slurm requeues and holds it
+    Prolog,
+    /// The epilog failed after the job ran
+    Epilog,
+    /// Slurm could not start the job on its node
+    Launch,
+}
+
+impl fmt::Display for SetupFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupFailure::Prolog => write!(f, "The node's prolog failed"),
+            SetupFailure::Epilog => write!(f, "The node's epilog failed"),
+            SetupFailure::Launch => write!(f, "Slurm could not launch the job on its node"),
+        }
+    }
+}
+
+/// The keys hints name, as the keymap binds them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HintKeys {
+    pub resubmit: String,
+    pub release: String,
+}
+
+impl Default for HintKeys {
+    fn default() -> Self {
+        Self {
+            resubmit: "R".to_string(),
+            release: "U".to_string(),
+        }
+    }
+}
+
+impl HintKeys {
+    /// `text` with the keys in place of `{resubmit}` and `{release}`
+    fn fill(&self, text: &str) -> String {
+        text.replace("{resubmit}", &self.resubmit)
+            .replace("{release}", &self.release)
+    }
+}
+
+impl SetupFailure {
+    /// What the user can do about it, with `{resubmit}` and `{release}`
+    /// for the keys
+    fn action(self) -> &'static str {
+        match self {
+            SetupFailure::Prolog => "Not caused by your job: the node's setup failed, so Slurm requeued and held the job and likely drained the node. Release the hold with {release} to retry on another node (ask your admins if that is refused), and report the node to them.",
+            SetupFailure::Epilog => "Not caused by your job: the node's cleanup after it failed, so its output is likely complete. Report the node to your admins; it is likely drained.",
+            SetupFailure::Launch => "Not caused by your script: resubmit ({resubmit}), and report the node to your admins if it happens again.",
+        }
+    }
+}
+
+/// Recognize a prolog, epilog or launch failure from a job's state and
+/// reason (`launch failed requeued held`, `Prolog error`, `JobLaunchFailure`)
+pub fn setup_failure(state: &str, reason: Option<&str>) -> Option<SetupFailure> {
+    let reason = reason.unwrap_or("").to_lowercase();
+    // A plain `Prolog` reason is a job waiting for its prolog to finish
+    let failed = reason.contains("fail") || reason.contains("error");
+    if reason.contains("launch failed requeued held") || (reason.contains("prolog") && failed) {
+        Some(SetupFailure::Prolog)
+    } else if reason.contains("epilog") && failed {
+        Some(SetupFailure::Epilog)
+    } else if reason.contains("joblaunchfailure") || state.starts_with("LAUNCH_FAILED") {
+        Some(SetupFailure::Launch)
+    } else {
+        None
+    }
+}
+
+/// Remediation hint for a job: for a prolog, epilog or launch failure, what
+/// to do about the node, else looked up by pending reason first, then state.
+/// Site overrides (the `[hints]` config table) replace built-in texts or add
+/// new keys; an empty override silences a hint. The keys hints name are
+/// those of `keys`.
+pub fn hint_for(
+    state: &str,
+    reason: Option<&str>,
+    overrides: &HashMap<String, String>,
+    keys: &HintKeys,
+) -> Option<String> {
+    // sacct states look like "CANCELLED by 1234" or "FAILED+"
+    let state = state
+        .split_whitespace()
+        .next()
+        .unwrap_or("")
+        .trim_end_matches('+')
+        .to_uppercase();
+    let reason = reason
+        .map(|r| r.trim_matches(|c| c == '(' || c == ')').trim())
+        .filter(|r| !r.is_empty() && *r != "None");
+
+    // A FAILED or NODE_FAIL state says less than the node failing around it
+    if let Some(failure) = setup_failure(&state, reason) {
+        return Some(keys.fill(failure.action()));
+    }
+    reason
+        .into_iter()
+        .chain(std::iter::once(state.as_str()))
+        .find_map(|key| match overrides.get(key) {
+            Some(text) => Some(text.clone()),
+            None => HINTS
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, text)| text.to_string()),
+        })
+        .filter(|text| !text.trim().is_empty())
+        .map(|text| keys.fill(&text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reason_takes_precedence_and_overrides_apply() {
+        let none = HashMap::new();
+        let keys = HintKeys::default();
+        let hint_for = |state, reason, overrides| hint_for(state, reason, overrides, &keys);
+        assert!(hint_for("PENDING", Some("AssocMaxJobsLimit"), &none)
+            .unwrap()
+            .contains("job array"));
+        assert!(hint_for("OUT_OF_MEMORY", None, &none)
+            .unwrap()
+            .contains("--mem"));
+        assert_eq!(hint_for("COMPLETED", None, &none), None);
+        assert!(
+            hint_for("PENDING", Some("(launch failed requeued held)"), &none)
+                .unwrap()
+                .contains("Release the hold")
+        );
+        // The node's failure beats the state's generic hint
+        assert!(hint_for("NODE_FAIL", Some("JobLaunchFailure"), &none)
+            .unwrap()
+            .contains("Not caused by your script"));
+        assert_eq!(setup_failure("PENDING", Some("Prolog")), None);
+        assert_eq!(
+            setup_failure("COMPLETED", Some("Epilog error")),
+            Some(SetupFailure::Epilog)
+        );
+
+        let overrides: HashMap<String, String> = [
+            (
+                "OUT_OF_MEMORY".to_string(),
+                "Use the bigmem partition".to_string(),
+            ),
+            ("TIMEOUT".to_string(), String::new()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            hint_for("OUT_OF_MEMORY", None, &overrides).as_deref(),
+            Some("Use the bigmem partition")
+        );
+        assert_eq!(hint_for("TIMEOUT", None, &overrides), None);
+
+        // Hints name the keys as remapped
+        let remapped = HintKeys {
+            resubmit: "g r".to_string(),
+            release: "ctrl-u".to_string(),
+        };
+        let held = super::hint_for("PENDING", Some("JobHeldUser"), &none, &remapped);
+        assert_eq!(
+            held.as_deref(),
+            Some("You held this job: release it with ctrl-u.")
+        );
+    }
+}
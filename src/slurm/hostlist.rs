@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use super::{Job, JobState};
+
+/// Largest expansion we are willing to produce from one expression, so a
+/// malformed range like `n[0-999999999]` cannot exhaust memory
+const MAX_HOSTS: usize = 1 << 16;
+
+/// Expand a Slurm hostlist expression (`gpu[01-03,07],login1`,
+/// `rack[1-2]-n[1-2]`) into individual host names, in order. Malformed
+/// pieces are kept verbatim.
+pub fn expand(hostlist: &str) -> Vec<String> {
+    let mut hosts = Vec::new();
+    for item in split_top_level(hostlist) {
+        match expand_item(item) {
+            Some(expanded) if hosts.len() + expanded.len() <= MAX_HOSTS => hosts.extend(expanded),
+            _ => hosts.push(item.to_string()),
+        }
+    }
+    hosts
+}
+
+/// Compress host names into a hostlist expression (`gpu01,gpu02,gpu03` ->
+/// `gpu[01-03]`), the inverse of [`expand`] for names with a numeric suffix
+pub fn compress<S: AsRef<str>>(hosts: &[S]) -> String {
+    let split: Vec<(&str, &str)> = hosts.iter().map(|h| split_number(h.as_ref())).collect();
+
+    // Zero-padded numbers fix the width of their prefix; unpadded ones of
+    // the same length (`09`, `10`) belong to the same range
+    let mut padded: HashMap<&str, BTreeSet<usize>> = HashMap::new();
+    for (prefix, digits) in &split {
+        if digits.len() > 1 && digits.starts_with('0') {
+            padded.entry(prefix).or_default().insert(digits.len());
+        }
+    }
+
+    let mut groups: Vec<((&str, usize), BTreeSet<u64>)> = Vec::new();
+    let mut plain: Vec<&str> = Vec::new();
+    for (i, (prefix, digits)) in split.iter().enumerate() {
+        let Ok(number) = digits.parse::<u64>() else {
+            plain.push(hosts[i].as_ref());
+            continue;
+        };
+        let width = if padded
+            .get(prefix)
+            .is_some_and(|w| w.contains(&digits.len()))
+        {
+            digits.len()
+        } else {
+            0
+        };
+        match groups.iter_mut().find(|(key, _)| *key == (*prefix, width)) {
+            Some((_, numbers)) => {
+                numbers.insert(number);
+            }
+            None => groups.push(((prefix, width), BTreeSet::from([number]))),
+        }
+    }
+
+    let mut parts: Vec<String> = groups
+        .into_iter()
+        .map(|((prefix, width), numbers)| {
+            let ranges = ranges(&numbers, width);
+            if numbers.len() == 1 {
+                format!("{}{}", prefix, ranges)
+            } else {
+                format!("{}[{}]", prefix, ranges)
+            }
+        })
+        .collect();
+    for host in plain {
+        if !parts.iter().any(|p| p == host) {
+            parts.push(host.to_string());
+        }
+    }
+    parts.join(",")
+}
+
+/// Number of running jobs on each node
+pub fn jobs_per_node(jobs: &[Job]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for job in jobs.iter().filter(|j| j.state == JobState::Running) {
+        let Some(nodes) = &job.node else {
+            continue;
+        };
+        // A job lists each of its nodes once
+        for host in expand(nodes).into_iter().collect::<BTreeSet<_>>() {
+            *counts.entry(host).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Split on the commas that are not inside brackets
+fn split_top_level(hostlist: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let (mut depth, mut start) = (0usize, 0);
+    for (i, c) in hostlist.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.push(&hostlist[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&hostlist[start..]);
+    items
+        .into_iter()
+        .map(str::trim)
+        .filter(|i| !i.is_empty())
+        .collect()
+}
+
+/// Expand one item, which may hold several bracket groups; `None` if malformed
+fn expand_item(item: &str) -> Option<Vec<String>> {
+    let Some(open) = item.find('[') else {
+        return Some(vec![item.to_string()]);
+    };
+    let close = open + item[open..].find(']')?;
+    let (prefix, ranges, rest) = (&item[..open], &item[open + 1..close], &item[close + 1..]);
+
+    let mut values = Vec::new();
+    for range in ranges.split(',').map(str::trim) {
+        match range.split_once('-') {
+            Some((low, high)) => {
+                let (from, to) = (low.parse::<u64>().ok()?, high.parse::<u64>().ok()?);
+                if to < from || (to - from) as usize >= MAX_HOSTS {
+                    return None;
+                }
+                let width = low.len();
+                values.extend((from..=to).map(|n| format!("{:0width$}", n, width = width)));
+            }
+            None if !range.is_empty() && range.chars().all(|c| c.is_ascii_digit()) => {
+                values.push(range.to_string());
+            }
+            None => return None,
+        }
+    }
+
+    let tails = expand_item(rest)?;
+    if values.len().saturating_mul(tails.len()) > MAX_HOSTS {
+        return None;
+    }
+    Some(
+        values
+            .iter()
+            .flat_map(|v| tails.iter().map(move |t| format!("{}{}{}", prefix, v, t)))
+            .collect(),
+    )
+}
+
+/// `gpu07` -> (`gpu`, `07`); names without a numeric suffix have no digits
+fn split_number(host: &str) -> (&str, &str) {
+    let digits = host.len() - host.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    host.split_at(host.len() - digits)
+}
+
+/// `1,2,3,5` -> `1-3,5`, zero-padded to `width`
+fn ranges(numbers: &BTreeSet<u64>, width: usize) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut iter = numbers.iter().copied().peekable();
+    while let Some(start) = iter.next() {
+        let mut end = start;
+        while iter.peek() == Some(&(end + 1)) {
+            end += 1;
+            iter.next();
+        }
+        if start == end {
+            out.push(format!("{:0width$}", start, width = width));
+        } else {
+            out.push(format!("{:0width$}-{:0width$}", start, end, width = width));
+        }
+    }
+    out.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_hostlist_expressions() {
+        assert_eq!(expand("node[1-2,5]"), vec!["node1", "node2", "node5"]);
+        assert_eq!(
+            expand("gpu[08-10],login1"),
+            vec!["gpu08", "gpu09", "gpu10", "login1"]
+        );
+        assert_eq!(
+            expand("r[1-2]-n[1-2]"),
+            vec!["r1-n1", "r1-n2", "r2-n1", "r2-n2"]
+        );
+        assert_eq!(expand("node7"), vec!["node7"]);
+        assert_eq!(expand("bad[1-"), vec!["bad[1-"]);
+        assert_eq!(expand("huge[0-999999999]"), vec!["huge[0-999999999]"]);
+        assert!(expand("").is_empty());
+    }
+
+    #[test]
+    fn compresses_back_to_ranges() {
+        for list in [
+            "node[1-2,5]",
+            "gpu[08-10],login1",
+            "node7",
+            "a[1-3],b[01-02]",
+        ] {
+            assert_eq!(compress(&expand(list)), list);
+        }
+        assert_eq!(compress(&["n3", "n1", "n2", "n1"]), "n[1-3]");
+        assert_eq!(compress(&["n9", "n10"]), "n[9-10]");
+    }
+
+    #[test]
+    fn counts_running_jobs_per_node() {
+        let job = |state, nodes: &str| Job {
+            state,
+            node: Some(nodes.to_string()),
+            ..Default::default()
+        };
+        let counts = jobs_per_node(&[
+            job(JobState::Running, "gpu[01-02]"),
+            job(JobState::Running, "gpu02"),
+            job(JobState::Completed, "gpu[01-02]"),
+        ]);
+        assert_eq!(counts["gpu01"], 1);
+        assert_eq!(counts["gpu02"], 2);
+        assert_eq!(counts.len(), 2);
+    }
+}
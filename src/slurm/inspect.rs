@@ -0,0 +1,181 @@
+use color_eyre::Result;
+
+use super::command;
+use super::error::SlurmError;
+
+/// Processes listed per node
+pub const TOP_PROCESSES: usize = 8;
+
+/// Shell run once per node of the job: the host, its GPUs and the busiest
+/// processes of the job's other steps (`scontrol listpids`, leaving out the
+/// probe's own), each section after a `@` marker line
+const PROBE: &str = "echo \"@node $(hostname -s)\"; \
+echo @gpu; nvidia-smi --query-gpu=index,name,utilization.gpu,memory.used,memory.total \
+--format=csv,noheader,nounits 2>/dev/null; \
+echo @ps; pids=$(scontrol listpids \"$SLURM_JOB_ID\" 2>/dev/null \
+| awk -v step=\"$SLURM_STEP_ID\" 'NR > 1 && $3 != step { print $1 }' | paste -sd, -); \
+[ -n \"$pids\" ] && ps -o pid=,pcpu=,pmem=,etime=,comm= --sort=-pcpu -p \"$pids\" | head -n ";
+
+/// One GPU of a node, as `nvidia-smi` reports it
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuUsage {
+    pub index: String,
+    pub name: String,
+    /// Percent of the last sample period a kernel ran
+    pub utilization: Option<u32>,
+    /// MiB
+    pub memory_used: Option<u64>,
+    /// MiB
+    pub memory_total: Option<u64>,
+}
+
+/// One process of the job on a node, as `ps` reports it
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeProcess {
+    pub pid: String,
+    pub cpu: String,
+    pub mem: String,
+    pub elapsed: String,
+    pub command: String,
+}
+
+/// What runs on one node of a job
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeSnapshot {
+    pub node: String,
+    /// Empty on nodes without GPUs or `nvidia-smi`
+    pub gpus: Vec<GpuUsage>,
+    /// Busiest first
+    pub processes: Vec<NodeProcess>,
+}
+
+/// Arguments of `srun` running the probe once on each of the job's `nodes`
+fn inspect_args(job_id: &str, nodes: u32, overlap: bool) -> Vec<String> {
+    let mut args = vec!["--jobid".to_string(), job_id.to_string()];
+    if overlap {
+        args.push("--overlap".to_string());
+    }
+    args.extend([
+        format!("--nodes={}", nodes.max(1)),
+        "--ntasks-per-node=1".to_string(),
+        "--label".to_string(),
+        "sh".to_string(),
+        "-c".to_string(),
+        format!("{}{}", PROBE, TOP_PROCESSES),
+    ]);
+    args
+}
+
+/// Snapshot the GPUs and top processes on each node of a running job,
+/// through a job step (`srun --jobid`)
+pub async fn inspect_job(job_id: &str, nodes: u32, overlap: bool) -> Result<Vec<NodeSnapshot>> {
+    let output = command::output("srun", inspect_args(job_id, nodes, overlap))
+        .await
+        .map_err(|e| SlurmError::from_io("srun", &e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let snapshots = parse_probe_output(&stdout);
+    if snapshots.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SlurmError::from_stderr("srun", &stderr).into());
+    }
+    Ok(snapshots)
+}
+
+/// Parse the `--label`ed output of the probe: lines are `<task>: <text>`,
+/// and tasks' lines may interleave
+fn parse_probe_output(stdout: &str) -> Vec<NodeSnapshot> {
+    let mut tasks: Vec<(String, NodeSnapshot, &str)> = Vec::new();
+    for line in stdout.lines() {
+        let Some((task, text)) = line.split_once(": ") else {
+            continue;
+        };
+        let task = task.trim().to_string();
+        let text = text.trim();
+        let at = match tasks.iter().position(|(t, _, _)| *t == task) {
+            Some(at) => at,
+            None => {
+                tasks.push((task, NodeSnapshot::default(), ""));
+                tasks.len() - 1
+            }
+        };
+        let (_, snapshot, section) = &mut tasks[at];
+        match text.split_once(' ') {
+            Some(("@node", node)) => snapshot.node = node.to_string(),
+            _ if text.starts_with('@') => *section = if text == "@gpu" { "gpu" } else { "ps" },
+            _ if text.is_empty() => {}
+            _ if *section == "gpu" => snapshot.gpus.extend(parse_gpu(text)),
+            _ if *section == "ps" => snapshot.processes.extend(parse_process(text)),
+            _ => {}
+        }
+    }
+    let mut snapshots: Vec<NodeSnapshot> = tasks
+        .into_iter()
+        .map(|(_, snapshot, _)| snapshot)
+        .filter(|s| !s.node.is_empty())
+        .collect();
+    snapshots.sort_by(|a, b| a.node.cmp(&b.node));
+    snapshots
+}
+
+/// `0, NVIDIA A100-SXM4-80GB, 97, 40511, 81920`
+fn parse_gpu(line: &str) -> Option<GpuUsage> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let [index, name, utilization, used, total] = fields[..] else {
+        return None;
+    };
+    Some(GpuUsage {
+        index: index.to_string(),
+        name: name.to_string(),
+        utilization: utilization.parse().ok(),
+        memory_used: used.parse().ok(),
+        memory_total: total.parse().ok(),
+    })
+}
+
+/// `12345 398.0  2.1    01:02:03 python`
+fn parse_process(line: &str) -> Option<NodeProcess> {
+    let mut fields = line.split_whitespace();
+    Some(NodeProcess {
+        pid: fields.next()?.to_string(),
+        cpu: fields.next()?.to_string(),
+        mem: fields.next()?.to_string(),
+        elapsed: fields.next()?.to_string(),
+        command: fields.collect::<Vec<_>>().join(" "),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_interleaved_node_probes() {
+        let stdout = "\
+1: @node gpu02
+0: @node gpu01
+0: @gpu
+1: @gpu
+0: 0, NVIDIA A100-SXM4-80GB, 97, 40511, 81920
+1: 0, NVIDIA A100-SXM4-80GB, [N/A], 12, 81920
+0: @ps
+0:   4242 398.0  2.1    01:02:03 python train.py
+1: @ps
+0:   4300   0.5  0.0       00:10 bash
+";
+        let nodes = parse_probe_output(stdout);
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].node, "gpu01");
+        assert_eq!(nodes[0].gpus[0].utilization, Some(97));
+        assert_eq!(nodes[0].gpus[0].memory_used, Some(40511));
+        assert_eq!(nodes[0].processes.len(), 2);
+        assert_eq!(nodes[0].processes[0].command, "python train.py");
+        assert_eq!(nodes[1].gpus[0].utilization, None);
+        assert!(nodes[1].processes.is_empty());
+
+        let args = inspect_args("77", 2, true);
+        assert_eq!(&args[..4], ["--jobid", "77", "--overlap", "--nodes=2"]);
+        // Only the job's processes, not every one of the user on the node
+        assert!(args[args.len() - 1].contains("scontrol listpids"));
+        assert!(!args[args.len() - 1].contains("ps -U"));
+    }
+}
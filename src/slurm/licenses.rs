@@ -0,0 +1,118 @@
+use color_eyre::Result;
+
+use super::error::checked;
+use super::{command, parse_scontrol_output, Job, JobState};
+
+/// A cluster license, as `scontrol show licenses` prints it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct License {
+    /// e.g. `matlab`, or `ansys@flexlm` for a remote one
+    pub name: String,
+    pub total: u32,
+    pub used: u32,
+    pub free: u32,
+    /// Held for reservations
+    pub reserved: u32,
+    /// Tracked by the database rather than slurm.conf
+    pub remote: bool,
+}
+
+/// Parse `scontrol -o show licenses`: one license per line
+pub fn parse_licenses(stdout: &str) -> Vec<License> {
+    stdout
+        .lines()
+        .filter(|line| line.trim_start().starts_with("LicenseName="))
+        .map(|line| {
+            let fields = parse_scontrol_output(line);
+            let count = |key: &str| fields.get(key).and_then(|v| v.parse().ok()).unwrap_or(0);
+            License {
+                name: fields.get("LicenseName").cloned().unwrap_or_default(),
+                total: count("Total"),
+                used: count("Used"),
+                free: count("Free"),
+                reserved: count("Reserved"),
+                remote: fields.get("Remote").is_some_and(|r| r == "yes"),
+            }
+        })
+        .collect()
+}
+
+/// Licenses of a job's `Licenses` field (`matlab:2,ansys`, or `matlab*2` as
+/// newer releases print it) as `(name, count)`
+pub fn requested(licenses: &str) -> Vec<(String, u32)> {
+    licenses
+        .split(',')
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && *l != "(null)")
+        .map(|l| match l.rsplit_once([':', '*']) {
+            Some((name, count)) if count.parse::<u32>().is_ok() => {
+                (name.to_string(), count.parse().unwrap_or(1))
+            }
+            _ => (l.to_string(), 1),
+        })
+        .collect()
+}
+
+/// Licenses a pending job asks for more of than are free, with how many it
+/// asks for
+pub fn shortfalls<'a>(job: &Job, licenses: &'a [License]) -> Vec<(&'a License, u32)> {
+    if job.state != JobState::Pending {
+        return Vec::new();
+    }
+    let Some(wanted) = &job.licenses else {
+        return Vec::new();
+    };
+    requested(wanted)
+        .into_iter()
+        .filter_map(|(name, count)| {
+            let license = licenses.iter().find(|l| l.name == name)?;
+            (count > license.free).then_some((license, count))
+        })
+        .collect()
+}
+
+/// The cluster's licenses
+pub async fn fetch_licenses() -> Result<Vec<License>> {
+    let output = command::output("scontrol", ["-o", "show", "licenses"]).await;
+    let output = checked("scontrol", output)?;
+    Ok(parse_licenses(&String::from_utf8_lossy(&output.stdout)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_pending_jobs_short_of_licenses() {
+        let licenses = parse_licenses(
+            "LicenseName=matlab Total=10 Used=9 Free=1 Reserved=0 Remote=no\n\
+             LicenseName=ansys@flexlm Total=4 Used=0 Free=4 Reserved=0 Remote=yes LastConsumed=0\n",
+        );
+        assert_eq!(licenses.len(), 2);
+        assert_eq!((licenses[0].used, licenses[0].free), (9, 1));
+        assert!(licenses[1].remote);
+        assert!(parse_licenses("No licenses configured in Slurm.\n").is_empty());
+
+        assert_eq!(
+            requested("matlab:2,ansys@flexlm,comsol*3"),
+            [
+                ("matlab".to_string(), 2),
+                ("ansys@flexlm".to_string(), 1),
+                ("comsol".to_string(), 3)
+            ]
+        );
+        let job = Job {
+            state: JobState::Pending,
+            licenses: Some("matlab:2,ansys@flexlm:1".to_string()),
+            ..Default::default()
+        };
+        let short = shortfalls(&job, &licenses);
+        assert_eq!(short.len(), 1);
+        assert_eq!((short[0].0.name.as_str(), short[0].1), ("matlab", 2));
+        let running = Job {
+            state: JobState::Running,
+            ..job
+        };
+        assert!(shortfalls(&running, &licenses).is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+const KIB: u64 = 1024;
+const MIB: u64 = 1024 * KIB;
+const GIB: u64 = 1024 * MIB;
+const TIB: u64 = 1024 * GIB;
+
+/// What a memory request is counted against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryScope {
+    /// `--mem`: per allocated node
+    #[default]
+    Node,
+    /// `--mem-per-cpu`: per allocated CPU
+    Cpu,
+}
+
+/// A memory amount as Slurm reports it, normalized to bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Memory {
+    pub bytes: u64,
+    pub scope: MemoryScope,
+}
+
+impl Memory {
+    /// Parse the forms Slurm prints: `16G`, `4000M`, `1523412K`, `4000Mc` /
+    /// `16Gn` (older sacct `ReqMem` with a per-CPU/per-node suffix), and TRES
+    /// strings (`cpu=4,mem=64G,node=1`). Bare numbers are megabytes, as in
+    /// `squeue %m` and `sbatch --mem`.
+    pub fn parse(raw: &str) -> Option<Memory> {
+        let raw = raw.trim();
+        if raw.contains('=') {
+            let value = raw.split(',').find_map(|t| t.trim().strip_prefix("mem="))?;
+            return Memory::parse(value);
+        }
+
+        let (raw, scope) = match raw.strip_suffix('c') {
+            Some(rest) => (rest, MemoryScope::Cpu),
+            None => (raw.strip_suffix('n').unwrap_or(raw), MemoryScope::Node),
+        };
+        let (number, unit) = match raw.char_indices().last()? {
+            (i, 'K') => (&raw[..i], KIB),
+            (i, 'M') => (&raw[..i], MIB),
+            (i, 'G') => (&raw[..i], GIB),
+            (i, 'T') => (&raw[..i], TIB),
+            _ => (raw, MIB),
+        };
+        let number: f64 = number.parse().ok()?;
+        if !number.is_finite() || number < 0.0 {
+            return None;
+        }
+        Some(Memory {
+            bytes: (number * unit as f64).round() as u64,
+            scope,
+        })
+    }
+
+    /// Memory per node, given the job's allocation
+    pub fn per_node(self, cpus: u32, nodes: u32) -> u64 {
+        match self.scope {
+            MemoryScope::Node => self.bytes,
+            MemoryScope::Cpu => self.bytes * u64::from(cpus.max(1)) / u64::from(nodes.max(1)),
+        }
+    }
+
+    /// Exact amount in the largest whole unit, as `sbatch --mem` accepts it (`16G`, `4000M`)
+    pub fn to_slurm(self) -> String {
+        match self.bytes {
+            b if b >= TIB && b % TIB == 0 => format!("{}T", b / TIB),
+            b if b >= GIB && b % GIB == 0 => format!("{}G", b / GIB),
+            b if b % MIB == 0 => format!("{}M", b / MIB),
+            b => format!("{}K", b.div_ceil(KIB)),
+        }
+    }
+}
+
+/// Read back as serialized, or as Slurm prints it (`"16G"`, `"4000Mc"`)
+impl<'de> Deserialize<'de> for Memory {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Slurm(String),
+            Bytes {
+                bytes: u64,
+                #[serde(default)]
+                scope: MemoryScope,
+            },
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Slurm(raw) => Memory::parse(&raw)
+                .ok_or_else(|| de::Error::custom(format!("invalid size {:?}", raw))),
+            Raw::Bytes { bytes, scope } => Ok(Memory { bytes, scope }),
+        }
+    }
+}
+
+impl fmt::Display for Memory {
+    /// Human-readable with binary units: `16G`, `3.9G/cpu`, `512M`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (value, unit) = match self.bytes {
+            b if b >= TIB => (b as f64 / TIB as f64, "T"),
+            b if b >= GIB => (b as f64 / GIB as f64, "G"),
+            b if b >= MIB => (b as f64 / MIB as f64, "M"),
+            b => (b as f64 / KIB as f64, "K"),
+        };
+        // One decimal for small values, none once it would be noise
+        if value < 10.0 && value.fract() >= 0.05 {
+            write!(f, "{:.1}{}", value, unit)?;
+        } else {
+            write!(f, "{:.0}{}", value, unit)?;
+        }
+        if self.scope == MemoryScope::Cpu {
+            write!(f, "/cpu")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_slurm_memory_forms() {
+        let mem = |raw| Memory::parse(raw).unwrap();
+        assert_eq!(mem("16G").bytes, 16 * GIB);
+        assert_eq!(mem("16Gn").scope, MemoryScope::Node);
+        assert_eq!(
+            mem("4000Mc"),
+            Memory {
+                bytes: 4000 * MIB,
+                scope: MemoryScope::Cpu
+            }
+        );
+        assert_eq!(mem("2048").bytes, 2 * GIB);
+        assert_eq!(mem("1523412K").bytes, 1523412 * KIB);
+        assert_eq!(mem("billing=4,cpu=4,mem=64G,node=1").bytes, 64 * GIB);
+        assert_eq!(mem("0.5G").bytes, 512 * MIB);
+        assert_eq!(Memory::parse(""), None);
+        assert_eq!(Memory::parse("lots"), None);
+        assert_eq!(Memory::parse("cpu=4"), None);
+    }
+
+    #[test]
+    fn renders_and_compares_consistently() {
+        let mem = |raw| Memory::parse(raw).unwrap();
+        assert_eq!(mem("16384M").to_string(), "16G");
+        assert_eq!(mem("4000Mc").to_string(), "3.9G/cpu");
+        assert_eq!(mem("512M").to_string(), "512M");
+        assert_eq!(mem("16384M").to_slurm(), "16G");
+        assert_eq!(mem("4000M").to_slurm(), "4000M");
+
+        // 4000M per CPU on 8 CPUs outweighs 16G per node
+        assert!(mem("4000Mc").per_node(8, 1) > mem("16G").per_node(8, 1));
+        assert_eq!(mem("4G").per_node(8, 2), 4 * GIB);
+        assert_eq!(mem("4Gc").per_node(8, 2), 16 * GIB);
+    }
+}
@@ -0,0 +1,237 @@
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use color_eyre::eyre::WrapErr;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use super::sacct::{name_regex, SacctOptions, SacctRows};
+use super::squeue::SqueueOptions;
+use super::{Job, JobState};
+
+/// Jobs of `slurmer --demo`
+const DEMO_FIXTURE: &str = include_str!("../../fixtures/demo.json");
+
+/// A recorded set of jobs, as written by `slurmer fixture`. Values may also
+/// be written as Slurm prints them (`RUNNING`, `1-00:00:00`, `32G`); see
+/// [`Job`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Fixture {
+    /// The user the TUI lists jobs of; the first job's user when unset
+    pub user: Option<String>,
+    pub jobs: Vec<Job>,
+    /// Jobs that end while the fixture is served
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ends: Vec<FixtureEnd>,
+}
+
+/// A job of the fixture that leaves squeue after `after_polls` polls of
+/// it, and that accounting reports in `state` `sacct_lag` polls later
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FixtureEnd {
+    pub id: String,
+    pub state: String,
+    pub after_polls: u32,
+    pub sacct_lag: u32,
+}
+
+/// Serves the jobs of a fixture in place of squeue and sacct, so the TUI
+/// runs (and can be tested and screenshotted) without Slurm. The queries'
+/// user, state, partition and QOS filters apply; time windows do not, so a
+/// fixture replays the same way whenever it is loaded.
+#[derive(Debug, Clone)]
+pub struct MockJobSource {
+    pub user: String,
+    jobs: Vec<Job>,
+    ends: Vec<FixtureEnd>,
+    /// squeue polls served so far
+    polls: Arc<AtomicU32>,
+}
+
+impl MockJobSource {
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed reading fixture {}", path.display()))?;
+        Self::parse(&raw).wrap_err_with(|| format!("failed parsing fixture {}", path.display()))
+    }
+
+    /// The built-in fixture of `slurmer --demo`
+    pub fn demo() -> Self {
+        Self::parse(DEMO_FIXTURE).expect("the demo fixture is valid")
+    }
+
+    pub fn parse(raw: &str) -> Result<Self> {
+        let fixture: Fixture = serde_json::from_str(raw)?;
+        let jobs = fixture.jobs;
+        let user = fixture
+            .user
+            .or_else(|| jobs.first().map(|j| j.user.clone()))
+            .unwrap_or_default();
+        Ok(Self {
+            user,
+            jobs,
+            ends: fixture.ends,
+            polls: Arc::new(AtomicU32::new(0)),
+        })
+    }
+
+    /// Whether squeue still lists `job`, and the state accounting reports
+    /// for it, after the polls served so far
+    fn seen(&self, job: &Job) -> (bool, JobState) {
+        let polls = self.polls.load(Ordering::Relaxed);
+        match self.ends.iter().find(|end| end.id == job.id) {
+            Some(end) => (
+                polls <= end.after_polls,
+                if polls > end.after_polls + end.sacct_lag {
+                    end.state.parse().unwrap_or(JobState::Other)
+                } else {
+                    job.state
+                },
+            ),
+            None => (true, job.state),
+        }
+    }
+
+    /// The jobs as accounting has them
+    fn accounted(&self) -> impl Iterator<Item = Job> + '_ {
+        self.jobs.iter().map(|job| Job {
+            state: self.seen(job).1,
+            ..job.clone()
+        })
+    }
+
+    /// Partitions of the fixture's jobs, for the filters
+    pub fn partitions(&self) -> Vec<String> {
+        let mut partitions: Vec<String> = self.jobs.iter().map(|j| j.partition.clone()).collect();
+        partitions.sort();
+        partitions.dedup();
+        partitions
+    }
+
+    pub fn qos(&self) -> Vec<String> {
+        let mut qos: Vec<String> = self.jobs.iter().map(|j| j.qos.clone()).collect();
+        qos.sort();
+        qos.dedup();
+        qos
+    }
+
+    fn matches(
+        job: &Job,
+        user: &Option<String>,
+        partitions: &[String],
+        qos: &[String],
+        accounts: &[String],
+    ) -> bool {
+        user.as_ref()
+            .is_none_or(|u| u.is_empty() || u.split(',').any(|u| u == job.user))
+            && (partitions.is_empty() || partitions.contains(&job.partition))
+            && (qos.is_empty() || qos.contains(&job.qos))
+            && (accounts.is_empty() || job.account.as_ref().is_some_and(|a| accounts.contains(a)))
+    }
+
+    /// What `run_squeue` would list: the active jobs
+    pub fn squeue(&self, options: &SqueueOptions) -> Vec<Job> {
+        self.polls.fetch_add(1, Ordering::Relaxed);
+        self.jobs
+            .iter()
+            .filter(|j| j.state.is_active() && self.seen(j).0)
+            .filter(|j| options.states.is_empty() || options.states.contains(&j.state.kind()))
+            .filter(|j| {
+                Self::matches(
+                    j,
+                    &options.user,
+                    &options.partitions,
+                    &options.qos,
+                    &options.accounts,
+                )
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// What `run_sacct` would read: the ended jobs
+    pub fn sacct(&self, options: &SacctOptions) -> SacctRows {
+        let name = options.name.as_deref().and_then(|p| name_regex(p).ok());
+        let mut jobs: Vec<Job> = self
+            .accounted()
+            .filter(|j| !j.state.is_active())
+            .filter(|j| options.states.is_empty() || options.states.contains(&j.state.kind()))
+            .filter(|j| name.as_ref().is_none_or(|re| re.is_match(&j.name)))
+            .filter(|j| {
+                options.wckeys.is_empty()
+                    || j.wckey.as_ref().is_some_and(|k| options.wckeys.contains(k))
+            })
+            .filter(|j| {
+                Self::matches(
+                    j,
+                    &options.user,
+                    &options.partitions,
+                    &options.qos,
+                    &options.accounts,
+                )
+            })
+            .collect();
+        let max_rows = options.max_rows.unwrap_or(usize::MAX);
+        let truncated = jobs.len() > max_rows;
+        jobs.truncate(max_rows);
+        SacctRows { jobs, truncated }
+    }
+
+    /// The jobs with these ids, whatever their state, as `sacct -j` lists them
+    pub fn jobs_by_id(&self, ids: &[String]) -> Vec<Job> {
+        self.accounted().filter(|j| ids.contains(&j.id)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixtures_stand_in_for_squeue_and_sacct() {
+        let source = MockJobSource::demo();
+        assert_eq!(source.user, "demo");
+        let squeue = SqueueOptions {
+            user: Some(source.user.clone()),
+            ..Default::default()
+        };
+        let active = source.squeue(&squeue);
+        assert!(!active.is_empty());
+        assert!(active
+            .iter()
+            .all(|j| j.state.is_active() && j.user == "demo"));
+
+        let pending = source.squeue(&SqueueOptions {
+            states: vec![JobState::Pending],
+            ..squeue.clone()
+        });
+        assert!(pending.iter().all(|j| j.state == JobState::Pending));
+
+        let sacct = SacctOptions {
+            user: None,
+            states: JobState::ended_states(),
+            partitions: vec!["gpu".to_string()],
+            qos: Vec::new(),
+            accounts: Vec::new(),
+            wckeys: Vec::new(),
+            name: None,
+            recent_hours: 24,
+            end_hours: 0,
+            format_fields: Vec::new(),
+            max_rows: Some(1),
+            window_hours: None,
+        };
+        let rows = source.sacct(&sacct);
+        assert_eq!(rows.jobs.len(), 1);
+        assert!(rows.truncated);
+
+        // Jobs survive a round trip through the fixture format
+        let job = &active[0];
+        let exported = serde_json::to_string(job).unwrap();
+        assert_eq!(serde_json::from_str::<Job>(&exported).unwrap(), *job);
+    }
+}
@@ -1,21 +1,81 @@
+pub mod aggregate;
+pub mod builder;
+pub mod cache;
+pub mod capabilities;
 pub mod command;
+pub mod dependency;
+pub mod directives;
+pub mod energy;
+pub mod error;
+pub mod events;
+pub mod hints;
+pub mod hostlist;
+pub mod inspect;
+pub mod licenses;
+pub mod memory;
+pub mod mock;
+pub mod nodefail;
+pub mod nodes;
+pub mod pending;
+pub mod preempt;
+pub mod query;
+pub mod recording;
+pub mod reference;
+pub mod requeue;
+pub mod reservation;
 pub mod sacct;
+pub mod sacctmgr;
+pub mod script;
+pub mod scrontab;
 pub mod squeue;
+pub mod sstat;
+pub mod store;
+pub mod submit;
+pub mod tres;
+pub mod whatif;
 
+use std::collections::HashMap;
 use std::fmt;
 use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::utils::{format_slurm_time, parse_slurm_duration, parse_slurm_time, username_for_uid};
+use hints::{setup_failure, SetupFailure};
+use memory::Memory;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum JobState {
     Pending,
     Running,
+    Suspended,
+    Completing,
     Completed,
+    Configuring,
+    Resizing,
+    Requeued,
+    RequeueHold,
+    RequeueFed,
+    Signaling,
+    StageOut,
+    Stopped,
+    ResvDelHold,
     Failed,
-    Cancelled,
+    /// `CANCELLED by <uid>` in sacct, with the uid of whoever cancelled it
+    Cancelled {
+        by: Option<u32>,
+    },
     Timeout,
     NodeFail,
     Preempted,
     Boot,
+    OutOfMemory,
+    Deadline,
+    Revoked,
+    SpecialExit,
+    LaunchFailed,
     Other,
 }
 
@@ -24,15 +84,101 @@ impl JobState {
         vec![
             JobState::Pending,
             JobState::Running,
+            JobState::Suspended,
+            JobState::Completing,
             JobState::Completed,
             JobState::Failed,
-            JobState::Cancelled,
+            JobState::Cancelled { by: None },
             JobState::Timeout,
             JobState::NodeFail,
             JobState::Preempted,
             JobState::Boot,
+            JobState::OutOfMemory,
+            JobState::Deadline,
+            JobState::Requeued,
+            JobState::Revoked,
+            JobState::SpecialExit,
         ]
     }
+
+    /// States a job can end in, as understood by `sacct --state`
+    pub fn ended_states() -> Vec<JobState> {
+        vec![
+            JobState::Completed,
+            JobState::Failed,
+            JobState::Cancelled { by: None },
+            JobState::Timeout,
+            JobState::NodeFail,
+            JobState::Preempted,
+            JobState::Boot,
+            JobState::OutOfMemory,
+            JobState::Deadline,
+            JobState::Revoked,
+        ]
+    }
+
+    /// The state without its details (who cancelled it), for comparing
+    /// against filters
+    pub fn kind(self) -> JobState {
+        match self {
+            JobState::Cancelled { .. } => JobState::Cancelled { by: None },
+            state => state,
+        }
+    }
+
+    /// Whether the job has not ended yet (queued, running or in between)
+    pub fn is_active(self) -> bool {
+        matches!(
+            self,
+            JobState::Pending
+                | JobState::Running
+                | JobState::Suspended
+                | JobState::Completing
+                | JobState::Configuring
+                | JobState::Resizing
+                | JobState::Requeued
+                | JobState::RequeueHold
+                | JobState::RequeueFed
+                | JobState::Signaling
+                | JobState::StageOut
+                | JobState::Stopped
+                | JobState::ResvDelHold
+        )
+    }
+}
+
+impl JobState {
+    /// Compact state code as printed by `squeue -t` (e.g. `PD`, `R`, `CA`)
+    pub fn short_code(&self) -> &'static str {
+        match self {
+            JobState::Pending => "PD",
+            JobState::Running => "R",
+            JobState::Suspended => "S",
+            JobState::Completing => "CG",
+            JobState::Completed => "CD",
+            JobState::Configuring => "CF",
+            JobState::Resizing => "RS",
+            JobState::Requeued => "RQ",
+            JobState::RequeueHold => "RH",
+            JobState::RequeueFed => "RF",
+            JobState::Signaling => "SI",
+            JobState::StageOut => "SO",
+            JobState::Stopped => "ST",
+            JobState::ResvDelHold => "RD",
+            JobState::Failed => "F",
+            JobState::Cancelled { .. } => "CA",
+            JobState::Timeout => "TO",
+            JobState::NodeFail => "NF",
+            JobState::Preempted => "PR",
+            JobState::Boot => "BF",
+            JobState::OutOfMemory => "OOM",
+            JobState::Deadline => "DL",
+            JobState::Revoked => "RV",
+            JobState::SpecialExit => "SE",
+            JobState::LaunchFailed => "LF",
+            JobState::Other => "O",
+        }
+    }
 }
 
 impl fmt::Display for JobState {
@@ -40,26 +186,55 @@ impl fmt::Display for JobState {
         let state_str = match self {
             JobState::Pending => "PENDING",
             JobState::Running => "RUNNING",
+            JobState::Suspended => "SUSPENDED",
+            JobState::Completing => "COMPLETING",
             JobState::Completed => "COMPLETED",
+            JobState::Configuring => "CONFIGURING",
+            JobState::Resizing => "RESIZING",
+            JobState::Requeued => "REQUEUED",
+            JobState::RequeueHold => "REQUEUE_HOLD",
+            JobState::RequeueFed => "REQUEUE_FED",
+            JobState::Signaling => "SIGNALING",
+            JobState::StageOut => "STAGE_OUT",
+            JobState::Stopped => "STOPPED",
+            JobState::ResvDelHold => "RESV_DEL_HOLD",
             JobState::Failed => "FAILED",
-            JobState::Cancelled => "CANCELLED",
+            JobState::Cancelled { .. } => "CANCELLED",
             JobState::Timeout => "TIMEOUT",
             JobState::NodeFail => "NODE_FAIL",
             JobState::Preempted => "PREEMPTED",
             JobState::Boot => "BOOT_FAIL",
+            JobState::OutOfMemory => "OUT_OF_MEMORY",
+            JobState::Deadline => "DEADLINE",
+            JobState::Revoked => "REVOKED",
+            JobState::SpecialExit => "SPECIAL_EXIT",
+            JobState::LaunchFailed => "LAUNCH_FAILED",
             JobState::Other => "OTHER",
         };
         write!(f, "{}", state_str)
     }
 }
 
+/// As Slurm prints it, e.g. `RUNNING`
+impl Serialize for JobState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for JobState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        Ok(raw.parse().unwrap_or(JobState::Other))
+    }
+}
+
 impl FromStr for JobState {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let normalized = s
-            .trim()
-            .split_whitespace()
+        let mut words = s.split_whitespace();
+        let normalized = words
             .next()
             .unwrap_or("")
             .trim_end_matches('+')
@@ -68,53 +243,327 @@ impl FromStr for JobState {
         match normalized.as_str() {
             "PENDING" | "PD" => Ok(JobState::Pending),
             "RUNNING" | "R" => Ok(JobState::Running),
-            "COMPLETED" | "CD" | "COMPLETING" | "CG" => Ok(JobState::Completed),
+            "SUSPENDED" | "S" => Ok(JobState::Suspended),
+            "COMPLETING" | "CG" => Ok(JobState::Completing),
+            "COMPLETED" | "CD" => Ok(JobState::Completed),
+            "CONFIGURING" | "CF" => Ok(JobState::Configuring),
+            "RESIZING" | "RS" => Ok(JobState::Resizing),
+            "REQUEUED" | "RQ" => Ok(JobState::Requeued),
+            "REQUEUE_HOLD" | "RH" => Ok(JobState::RequeueHold),
+            "REQUEUE_FED" | "RF" => Ok(JobState::RequeueFed),
+            "SIGNALING" | "SI" => Ok(JobState::Signaling),
+            "STAGE_OUT" | "SO" => Ok(JobState::StageOut),
+            "STOPPED" | "ST" => Ok(JobState::Stopped),
+            "RESV_DEL_HOLD" | "RD" => Ok(JobState::ResvDelHold),
             "FAILED" | "F" => Ok(JobState::Failed),
-            "CANCELLED" | "CA" => Ok(JobState::Cancelled),
+            "CANCELLED" | "CA" => {
+                let by = match (words.next(), words.next()) {
+                    (Some("by"), Some(uid)) => uid.parse().ok(),
+                    _ => None,
+                };
+                Ok(JobState::Cancelled { by })
+            }
             "TIMEOUT" | "TO" => Ok(JobState::Timeout),
             "NODE_FAIL" | "NF" => Ok(JobState::NodeFail),
             "PREEMPTED" | "PR" => Ok(JobState::Preempted),
             "BOOT_FAIL" | "BF" => Ok(JobState::Boot),
+            "OUT_OF_MEMORY" | "OOM" => Ok(JobState::OutOfMemory),
+            "DEADLINE" | "DL" => Ok(JobState::Deadline),
+            "REVOKED" | "RV" => Ok(JobState::Revoked),
+            "SPECIAL_EXIT" | "SE" => Ok(JobState::SpecialExit),
+            "LAUNCH_FAILED" | "LF" => Ok(JobState::LaunchFailed),
             _ => Ok(JobState::Other),
         }
     }
 }
 
+/// Parse the `Key=Value` pairs of `scontrol show job -o` output
+pub fn parse_scontrol_output(output: &str) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+
+    for part in output.split_whitespace() {
+        if let Some(index) = part.find('=') {
+            let key = &part[0..index];
+            let value = &part[(index + 1)..];
+            result.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    result
+}
+
+/// Parse `<job id>|<state>` rows (squeue `%i|%T`, sacct `JobID,State`) into a map
+fn parse_state_rows(stdout: &str) -> HashMap<String, String> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let (id, state) = line.trim().split_once('|')?;
+            Some((id.trim().to_string(), state.trim().to_string()))
+        })
+        .filter(|(id, _)| !id.is_empty())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn failure_reason_classifies_common_deaths() {
+        let job = |state, exit: &str, rss: Option<&str>| Job {
+            state,
+            exit_code: Some(exit.to_string()),
+            memory: Memory::parse("4000Mc"),
+            cpus: 4,
+            nodes: 1,
+            max_rss: rss.map(str::to_string),
+            ..Default::default()
+        };
+        assert_eq!(
+            job(JobState::OutOfMemory, "0:125", None).failure_reason(),
+            Some(FailureReason::OutOfMemory)
+        );
+        // SIGKILL at 15.9G of a 16000M request: the OOM killer
+        assert_eq!(
+            job(JobState::Failed, "0:9", Some("16300000K")).failure_reason(),
+            Some(FailureReason::OutOfMemory)
+        );
+        assert_eq!(
+            job(JobState::Failed, "0:9", Some("2000000K")).failure_reason(),
+            Some(FailureReason::Signal(9))
+        );
+        assert_eq!(
+            job(JobState::Timeout, "0:15", None).failure_reason(),
+            Some(FailureReason::Timeout)
+        );
+        assert_eq!(
+            job(JobState::NodeFail, "0:0", None).failure_reason(),
+            Some(FailureReason::NodeFailure)
+        );
+        assert_eq!(
+            job(JobState::Failed, "2:0", None).failure_reason(),
+            Some(FailureReason::ExitCode(2))
+        );
+        assert_eq!(job(JobState::Completed, "0:0", None).failure_reason(), None);
+
+        let mut step_failed = job(JobState::Completed, "0:0", None);
+        step_failed.derived_exit_code = Some("1:0".to_string());
+        assert_eq!(
+            step_failed.failure_reason(),
+            Some(FailureReason::ExitCode(1))
+        );
+    }
+
+    #[test]
+    fn het_components_are_grouped_under_their_job() {
+        let part = |id: &str, cpus, node: &str, partition: &str| Job {
+            id: id.to_string(),
+            cpus,
+            nodes: 1,
+            node: Some(node.to_string()),
+            partition: partition.to_string(),
+            memory: Memory::parse("4G"),
+            ..Default::default()
+        };
+        let jobs = group_het_jobs(vec![
+            part("7", 1, "n9", "cpu"),
+            part("12+1", 8, "gpu02", "gpu"),
+            part("12+0", 2, "n1", "cpu"),
+            // The other component was filtered out
+            part("20+1", 4, "n2", "cpu"),
+        ]);
+
+        let ids: Vec<&str> = jobs.iter().map(|j| j.id.as_str()).collect();
+        assert_eq!(ids, ["7", "12", "20+1"]);
+        let het = &jobs[1];
+        assert_eq!((het.cpus, het.nodes), (10, 2));
+        assert_eq!(het.node.as_deref(), Some("n1,gpu02"));
+        assert_eq!(het.partition, "cpu,gpu");
+        assert_eq!(het.memory_per_node(), Some(4 << 30));
+        assert_eq!(het.het_components[0].id, "12+0");
+        assert!(jobs[2].het_components.is_empty());
+    }
+
     #[test]
     fn job_state_parses_sacct_suffixes() {
-        assert_eq!("CANCELLED+".parse::<JobState>().unwrap(), JobState::Cancelled);
+        assert_eq!(
+            "CANCELLED+".parse::<JobState>().unwrap(),
+            JobState::Cancelled { by: None }
+        );
         assert_eq!("FAILED+".parse::<JobState>().unwrap(), JobState::Failed);
         assert_eq!(
             "CANCELLED by 1234".parse::<JobState>().unwrap(),
-            JobState::Cancelled
+            JobState::Cancelled { by: Some(1234) }
         );
+        // Completing is still running its epilog, not done
+        assert_eq!("CG".parse::<JobState>().unwrap(), JobState::Completing);
+        assert!("COMPLETING".parse::<JobState>().unwrap().is_active());
+        for state in ["DEADLINE", "REVOKED", "SPECIAL_EXIT", "RESV_DEL_HOLD"] {
+            assert_eq!(state.parse::<JobState>().unwrap().to_string(), state);
+        }
     }
 }
 
-#[derive(Debug, Clone)]
+/// Serialized (`slurmer --export-json`) with durations in seconds. Read
+/// back from that, or from fixtures written as Slurm prints the values
+/// (`"time": "05:12:40"`, `"memory": "256G"`); missing fields are left at
+/// their defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Job {
     pub id: String,
     pub name: String,
     pub user: String,
     pub state: JobState,
-    pub time: String,
+    /// Elapsed run time (`%M` / `Elapsed`)
+    #[serde(
+        rename = "time_secs",
+        alias = "time",
+        serialize_with = "secs",
+        deserialize_with = "de_secs"
+    )]
+    pub time: Duration,
+    /// Wall-clock limit (`%l` / `Timelimit`); `None` when unlimited or unknown
+    #[serde(
+        rename = "time_limit_secs",
+        alias = "time_limit",
+        serialize_with = "opt_secs",
+        deserialize_with = "de_opt_secs"
+    )]
+    pub time_limit: Option<Duration>,
+    /// CPU time used by all tasks (`TotalCPU`)
+    #[serde(
+        rename = "total_cpu_secs",
+        alias = "total_cpu",
+        serialize_with = "opt_secs",
+        deserialize_with = "de_opt_secs"
+    )]
+    pub total_cpu: Option<Duration>,
     pub nodes: u32,
     pub node: Option<String>,
     pub cpus: u32,
-    pub memory: String,
+    /// Requested memory (`%m` / `ReqMem`)
+    pub memory: Option<Memory>,
     pub partition: String,
     pub qos: String,
     pub account: Option<String>,
     pub priority: Option<u32>,
     pub work_dir: Option<String>,
-    pub submit_time: Option<String>,
-    pub start_time: Option<String>,
-    pub end_time: Option<String>,
+    #[serde(serialize_with = "opt_time", deserialize_with = "de_opt_time")]
+    pub submit_time: Option<DateTime<Local>>,
+    /// When the job started, or the scheduler's expected start while pending
+    #[serde(serialize_with = "opt_time", deserialize_with = "de_opt_time")]
+    pub start_time: Option<DateTime<Local>>,
+    /// When the job ended, or its expected end while active
+    #[serde(serialize_with = "opt_time", deserialize_with = "de_opt_time")]
+    pub end_time: Option<DateTime<Local>>,
     pub pending_reason: Option<String>,
+    /// The scheduler's expected start of a pending job (`squeue --start`)
+    #[serde(serialize_with = "opt_time", deserialize_with = "de_opt_time")]
+    pub estimated_start: Option<DateTime<Local>>,
+    /// When the job could first start (sacct `Eligible`)
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_time",
+        deserialize_with = "de_opt_time"
+    )]
+    pub eligible_time: Option<DateTime<Local>>,
+    /// Generic resources per node, e.g. `gpu:a100:2`
+    pub gres: Option<String>,
+    /// GPUs across all nodes of the job
+    pub gpus: u32,
+    /// sacct `ExitCode` of the batch script, as `<code>:<signal>`
+    pub exit_code: Option<String>,
+    /// sacct `DerivedExitCode`: the highest exit code of any job step
+    pub derived_exit_code: Option<String>,
+    /// Peak resident memory of any step (sacct/sstat `MaxRSS`)
+    pub max_rss: Option<String>,
+    /// Advanced reservation the job runs in (`%v` / `Reservation`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reservation: Option<String>,
+    /// Local scratch per node: the `%d` minimum, or the `fs/disk` TRES
+    pub tmp_disk: Option<Memory>,
+    /// Burst buffer space (`bb/...` TRES)
+    pub burst_buffer: Option<Memory>,
+    /// Licenses requested (`%W`), e.g. `matlab:2,ansys`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub licenses: Option<String>,
+    /// Workload characterization key (`%w` / `WCKey`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wckey: Option<String>,
+    /// What the user noted on the job (`%k` / `Comment`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// What the admins noted on the job (sacct `AdminComment`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admin_comment: Option<String>,
+    /// Joules consumed, where Slurm gathers energy (sacct `ConsumedEnergyRaw`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub energy: Option<u64>,
+    /// How a running job could be preempted, from the QOS preemption
+    /// settings (see [`preempt::PreemptionRisk::summary`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preemption: Option<String>,
+    /// Components of a het job (`1234+0`, `1234+1`, ...), when this row is
+    /// the het job itself; see [`group_het_jobs`]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub het_components: Vec<Job>,
+}
+
+fn secs<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_secs())
+}
+
+fn opt_secs<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => secs(duration, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Seconds, or a duration as Slurm prints it (`1-00:00:00`)
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawDuration {
+    Secs(u64),
+    Slurm(String),
+}
+
+impl RawDuration {
+    fn duration<E: de::Error>(self) -> Result<Duration, E> {
+        match self {
+            RawDuration::Secs(secs) => Ok(Duration::from_secs(secs)),
+            RawDuration::Slurm(raw) => parse_slurm_duration(&raw)
+                .ok_or_else(|| E::custom(format!("invalid duration {:?}", raw))),
+        }
+    }
+}
+
+fn de_secs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    RawDuration::deserialize(deserializer)?.duration()
+}
+
+fn de_opt_secs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    Option::<RawDuration>::deserialize(deserializer)?
+        .map(RawDuration::duration)
+        .transpose()
+}
+
+/// A time the way Slurm prints it
+fn opt_time<S: Serializer>(at: &Option<DateTime<Local>>, serializer: S) -> Result<S::Ok, S::Error> {
+    match at {
+        Some(at) => serializer.serialize_str(&format_slurm_time(at)),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// A time as Slurm prints it; `None` for `Unknown` and the like
+fn de_opt_time<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DateTime<Local>>, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?
+        .as_deref()
+        .and_then(parse_slurm_time))
 }
 
 impl Default for Job {
@@ -124,11 +573,13 @@ impl Default for Job {
             name: String::new(),
             user: String::new(),
             state: JobState::Other,
-            time: String::new(),
+            time: Duration::ZERO,
+            time_limit: None,
+            total_cpu: None,
             nodes: 0,
             node: None,
             cpus: 0,
-            memory: String::new(),
+            memory: None,
             partition: String::new(),
             qos: String::new(),
             account: None,
@@ -138,6 +589,256 @@ impl Default for Job {
             start_time: None,
             end_time: None,
             pending_reason: None,
+            estimated_start: None,
+            eligible_time: None,
+            gres: None,
+            gpus: 0,
+            exit_code: None,
+            derived_exit_code: None,
+            max_rss: None,
+            reservation: None,
+            tmp_disk: None,
+            burst_buffer: None,
+            licenses: None,
+            wckey: None,
+            comment: None,
+            admin_comment: None,
+            energy: None,
+            preemption: None,
+            het_components: Vec::new(),
+        }
+    }
+}
+
+/// Why a job ended unsuccessfully, as far as accounting can tell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureReason {
+    OutOfMemory,
+    Timeout,
+    NodeFailure,
+    /// With the uid of whoever cancelled the job, if sacct names one
+    Cancelled(Option<u32>),
+    Deadline,
+    Signal(u32),
+    ExitCode(u32),
+}
+
+impl fmt::Display for FailureReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailureReason::OutOfMemory => write!(f, "Out of memory (OOM-killed)"),
+            FailureReason::Timeout => write!(f, "Hit its time limit"),
+            FailureReason::NodeFailure => write!(f, "Node failure"),
+            FailureReason::Cancelled(None) => write!(f, "Cancelled"),
+            FailureReason::Cancelled(Some(uid)) => match username_for_uid(*uid) {
+                Some(name) => write!(f, "Cancelled by {}", name),
+                None => write!(f, "Cancelled by uid {}", uid),
+            },
+            FailureReason::Deadline => write!(f, "Reached its deadline (--deadline)"),
+            FailureReason::Signal(9) => write!(f, "Killed by signal 9 (SIGKILL)"),
+            FailureReason::Signal(15) => write!(f, "Killed by signal 15 (SIGTERM)"),
+            FailureReason::Signal(sig) => write!(f, "Killed by signal {}", sig),
+            FailureReason::ExitCode(code) => write!(f, "Exited with code {}", code),
+        }
+    }
+}
+
+/// Split an `ExitCode` value (`<code>:<signal>`) into its parts
+fn parse_exit_code(value: &str) -> Option<(u32, u32)> {
+    let (code, signal) = value.split_once(':').unwrap_or((value, "0"));
+    Some((code.trim().parse().ok()?, signal.trim().parse().ok()?))
+}
+
+impl Job {
+    /// Why the job died, or `None` if it has not ended or ended successfully.
+    /// A SIGKILL with peak RSS at (or within 5% of) the requested memory is
+    /// reported as an OOM kill, as the kernel OOM killer does not always mark
+    /// the job `OUT_OF_MEMORY`.
+    pub fn failure_reason(&self) -> Option<FailureReason> {
+        match self.state {
+            JobState::OutOfMemory => return Some(FailureReason::OutOfMemory),
+            JobState::Timeout => return Some(FailureReason::Timeout),
+            JobState::Deadline => return Some(FailureReason::Deadline),
+            JobState::NodeFail | JobState::Boot | JobState::LaunchFailed => {
+                return Some(FailureReason::NodeFailure)
+            }
+            state if state.is_active() => return None,
+            _ => {}
+        }
+
+        let (code, signal) = self
+            .exit_code
+            .as_deref()
+            .and_then(parse_exit_code)
+            .unwrap_or((0, 0));
+        if signal == 9 && self.near_memory_limit() {
+            return Some(FailureReason::OutOfMemory);
+        }
+        if let JobState::Cancelled { by } = self.state {
+            return Some(FailureReason::Cancelled(by));
+        }
+        if signal != 0 {
+            return Some(FailureReason::Signal(signal));
+        }
+        if code != 0 {
+            return Some(FailureReason::ExitCode(code));
+        }
+        // The script exited 0 but a step failed (e.g. `srun` without `set -e`)
+        match self.derived_exit_code.as_deref().and_then(parse_exit_code) {
+            Some((_, 9)) if self.near_memory_limit() => Some(FailureReason::OutOfMemory),
+            Some((_, signal)) if signal != 0 => Some(FailureReason::Signal(signal)),
+            Some((code, _)) if code != 0 => Some(FailureReason::ExitCode(code)),
+            _ => None,
+        }
+    }
+
+    /// A failure of the node around the job (prolog, epilog, launch)
+    pub fn setup_failure(&self) -> Option<SetupFailure> {
+        setup_failure(&self.state.to_string(), self.pending_reason.as_deref())
+    }
+
+    /// Wall-clock time left before a running job hits its time limit
+    pub fn time_remaining(&self) -> Option<Duration> {
+        if self.state != JobState::Running {
+            return None;
         }
+        Some(self.time_limit?.saturating_sub(self.time))
     }
+
+    /// Share of the time limit a running job has used, from 0.0 to 1.0
+    pub fn time_used_fraction(&self) -> Option<f64> {
+        let limit = self.time_limit.filter(|l| !l.is_zero())?;
+        self.time_remaining()?;
+        Some((self.time.as_secs_f64() / limit.as_secs_f64()).min(1.0))
+    }
+
+    /// CPU time used over CPU time allocated (elapsed × CPUs)
+    pub fn cpu_efficiency(&self) -> Option<f64> {
+        let used = self.total_cpu?.as_secs_f64();
+        let allocated = self.time.as_secs_f64() * f64::from(self.cpus);
+        (allocated > 0.0).then(|| used / allocated)
+    }
+
+    /// Peak RSS over the memory requested per node
+    pub fn memory_efficiency(&self) -> Option<f64> {
+        let rss = Memory::parse(self.max_rss.as_deref()?)?.bytes;
+        let requested = self.memory_per_node().filter(|r| *r > 0)?;
+        Some(rss as f64 / requested as f64)
+    }
+
+    /// Requested memory per node in bytes, for comparing jobs
+    pub fn memory_per_node(&self) -> Option<u64> {
+        self.memory.map(|m| m.per_node(self.cpus, self.nodes))
+    }
+
+    /// Whether the peak RSS reached the per-node memory request
+    fn near_memory_limit(&self) -> bool {
+        let (Some(rss), Some(requested)) = (
+            self.max_rss.as_deref().and_then(Memory::parse),
+            self.memory_per_node(),
+        ) else {
+            return false;
+        };
+        requested > 0 && rss.bytes as f64 >= requested as f64 * 0.95
+    }
+}
+
+/// Combine queue and accounting rows, keeping the queue's record for jobs in both
+pub fn merge_jobs_prefer_active(active_jobs: Vec<Job>, ended_jobs: Vec<Job>) -> Vec<Job> {
+    let mut by_id: HashMap<String, Job> = HashMap::new();
+
+    // Prefer active job records.
+    for job in ended_jobs {
+        if !job.id.is_empty() {
+            by_id.insert(job.id.clone(), job);
+        }
+    }
+    for job in active_jobs {
+        if !job.id.is_empty() {
+            by_id.insert(job.id.clone(), job);
+        }
+    }
+
+    by_id.into_values().collect()
+}
+
+/// Het job id and component offset of a het job component (`1234+1` ->
+/// `("1234", 1)`)
+pub fn het_component(id: &str) -> Option<(&str, u32)> {
+    let (base, offset) = id.split_once('+')?;
+    Some((base, offset.parse().ok()?))
+}
+
+/// Replace the components of each het job with one row for the het job,
+/// holding the components and their combined resources. A lone component
+/// (the others filtered out) stays as it is.
+pub fn group_het_jobs(jobs: Vec<Job>) -> Vec<Job> {
+    let mut components: HashMap<String, Vec<Job>> = HashMap::new();
+    for job in &jobs {
+        if let Some((base, _)) = het_component(&job.id) {
+            components
+                .entry(base.to_string())
+                .or_default()
+                .push(job.clone());
+        }
+    }
+
+    let mut grouped = Vec::with_capacity(jobs.len());
+    for job in jobs {
+        let Some((base, _)) = het_component(&job.id) else {
+            grouped.push(job);
+            continue;
+        };
+        match components.remove(base) {
+            Some(parts) if parts.len() > 1 => grouped.push(het_job(base, parts)),
+            Some(mut parts) => grouped.append(&mut parts),
+            // Already grouped at its first component
+            None => {}
+        }
+    }
+    grouped
+}
+
+/// The het job row: the leader's (`+0`) fields with the resources of all
+/// components. Memory becomes the per-node average, so that the total over
+/// all nodes adds up.
+fn het_job(base: &str, mut parts: Vec<Job>) -> Job {
+    parts.sort_by_key(|p| het_component(&p.id).map_or(0, |(_, offset)| offset));
+    let mut job = Job {
+        id: base.to_string(),
+        het_components: Vec::new(),
+        ..parts[0].clone()
+    };
+
+    job.cpus = parts.iter().map(|p| p.cpus).sum();
+    job.gpus = parts.iter().map(|p| p.gpus).sum();
+    job.nodes = parts.iter().map(|p| p.nodes).sum();
+    let total_memory: Option<u64> = parts
+        .iter()
+        .map(|p| Some(p.memory_per_node()? * u64::from(p.nodes.max(1))))
+        .sum();
+    job.memory = total_memory.map(|bytes| Memory {
+        bytes: bytes / u64::from(job.nodes.max(1)),
+        ..Default::default()
+    });
+
+    let hosts: Vec<String> = parts
+        .iter()
+        .filter_map(|p| p.node.as_deref())
+        .flat_map(hostlist::expand)
+        .collect();
+    job.node = (!hosts.is_empty()).then(|| hostlist::compress(&hosts));
+    let mut partitions: Vec<&str> = Vec::new();
+    for partition in parts.iter().map(|p| p.partition.as_str()) {
+        if !partitions.contains(&partition) {
+            partitions.push(partition);
+        }
+    }
+    job.partition = partitions.join(",");
+    let gres: Vec<&str> = parts.iter().filter_map(|p| p.gres.as_deref()).collect();
+    job.gres = (!gres.is_empty()).then(|| gres.join(","));
+    job.time = parts.iter().map(|p| p.time).max().unwrap_or_default();
+
+    job.het_components = parts;
+    job
 }
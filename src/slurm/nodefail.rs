@@ -0,0 +1,190 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::hostlist::expand;
+use super::{Job, JobState};
+
+/// Failures on a node below this are never flagged, whatever the rate
+const MIN_FAILURES: usize = 2;
+
+/// Family-wise significance level, split over the nodes tested (Bonferroni)
+const SIGNIFICANCE: f64 = 0.05;
+
+/// How the user's ended jobs fared on one node
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeFailures {
+    pub node: String,
+    /// Ended jobs that ran on the node
+    pub jobs: usize,
+    /// Of those, jobs that ended `FAILED` or `NODE_FAIL`
+    pub failed: usize,
+    /// Of the failed, jobs that ended `NODE_FAIL`
+    pub node_failed: usize,
+    pub failed_ids: Vec<String>,
+    /// Chance of at least this many failures if the node failed as often as
+    /// all nodes do (one-sided binomial test)
+    pub p_value: f64,
+    /// Whether the node fails significantly more often than the others
+    pub elevated: bool,
+}
+
+impl NodeFailures {
+    pub fn rate(&self) -> f64 {
+        self.failed as f64 / self.jobs.max(1) as f64
+    }
+}
+
+/// Failures of ended jobs by node, with the overall failure rate
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeFailureReport {
+    pub jobs: usize,
+    pub failed: usize,
+    /// Failures per job on a node over all nodes, the rate each node is
+    /// tested against
+    pub rate: f64,
+    /// Nodes tested; the significance level is split over them
+    pub nodes_seen: usize,
+    /// Nodes with at least one failure, elevated first, then by failures
+    pub nodes: Vec<NodeFailures>,
+}
+
+fn is_failure(state: JobState) -> bool {
+    matches!(state, JobState::Failed | JobState::NodeFail)
+}
+
+/// Count failures per node. A job that failed on several nodes counts
+/// against each of them, as accounting cannot tell which one was at fault.
+pub fn node_failures(jobs: &[Job]) -> NodeFailureReport {
+    let mut per_node: BTreeMap<String, NodeFailures> = BTreeMap::new();
+    let mut ended = 0;
+    let mut failed = 0;
+    for job in jobs.iter().filter(|j| !j.state.is_active()) {
+        let Some(nodes) = job.node.as_deref().filter(|n| !n.starts_with("None")) else {
+            continue;
+        };
+        ended += 1;
+        let failure = is_failure(job.state);
+        failed += usize::from(failure);
+        for host in expand(nodes).into_iter().collect::<BTreeSet<_>>() {
+            let stats = per_node.entry(host.clone()).or_insert(NodeFailures {
+                node: host,
+                jobs: 0,
+                failed: 0,
+                node_failed: 0,
+                failed_ids: Vec::new(),
+                p_value: 1.0,
+                elevated: false,
+            });
+            stats.jobs += 1;
+            if failure {
+                stats.failed += 1;
+                stats.node_failed += usize::from(job.state == JobState::NodeFail);
+                stats.failed_ids.push(job.id.clone());
+            }
+        }
+    }
+
+    // Job-node pairs, so multi-node jobs weigh like on the nodes themselves
+    let pairs: usize = per_node.values().map(|n| n.jobs).sum();
+    let failed_pairs: usize = per_node.values().map(|n| n.failed).sum();
+    let rate = failed_pairs as f64 / pairs.max(1) as f64;
+    let nodes_seen = per_node.len();
+    let threshold = SIGNIFICANCE / nodes_seen.max(1) as f64;
+    let mut nodes: Vec<NodeFailures> = per_node
+        .into_values()
+        .filter(|n| n.failed > 0)
+        .map(|mut n| {
+            n.p_value = binomial_upper_tail(n.jobs, n.failed, rate);
+            n.elevated = n.failed >= MIN_FAILURES && n.p_value < threshold;
+            n
+        })
+        .collect();
+    nodes.sort_by(|a, b| {
+        b.elevated
+            .cmp(&a.elevated)
+            .then(a.p_value.total_cmp(&b.p_value))
+            .then(b.failed.cmp(&a.failed))
+    });
+    NodeFailureReport {
+        jobs: ended,
+        failed,
+        rate,
+        nodes_seen,
+        nodes,
+    }
+}
+
+/// P(X >= k) for X ~ Binomial(n, p), summed in log space so large `n` does
+/// not underflow
+fn binomial_upper_tail(n: usize, k: usize, p: f64) -> f64 {
+    if k == 0 || p >= 1.0 {
+        return 1.0;
+    }
+    if p <= 0.0 || k > n {
+        return 0.0;
+    }
+    let (ln_p, ln_q) = (p.ln(), (1.0 - p).ln());
+    // ln P(X = i), stepping from i = 0 with P(i+1)/P(i) = (n-i)/(i+1) * p/q
+    let mut ln_pmf = n as f64 * ln_q;
+    let mut terms = Vec::with_capacity(n - k + 1);
+    for i in 0..n {
+        if i >= k {
+            terms.push(ln_pmf);
+        }
+        ln_pmf += ((n - i) as f64 / (i + 1) as f64).ln() + ln_p - ln_q;
+    }
+    terms.push(ln_pmf);
+    let max = terms.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let sum: f64 = terms.iter().map(|t| (t - max).exp()).sum();
+    (max + sum.ln()).exp().min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_nodes_that_fail_more_than_chance() {
+        let job = |id: usize, node: &str, state| Job {
+            id: id.to_string(),
+            node: Some(node.to_string()),
+            state,
+            ..Default::default()
+        };
+        let mut jobs = Vec::new();
+        for i in 0..200 {
+            let state = if i % 50 == 0 {
+                JobState::Failed
+            } else {
+                JobState::Completed
+            };
+            jobs.push(job(i, &format!("cn{:02}", i % 10), state));
+        }
+        for i in 200..210 {
+            let state = if i < 206 {
+                JobState::NodeFail
+            } else {
+                JobState::Completed
+            };
+            jobs.push(job(i, "gpu[07-08]", state));
+        }
+        jobs.push(job(300, "None assigned", JobState::Cancelled { by: None }));
+
+        let report = node_failures(&jobs);
+        assert_eq!((report.jobs, report.failed), (210, 10));
+        let flagged: Vec<&str> = report
+            .nodes
+            .iter()
+            .filter(|n| n.elevated)
+            .map(|n| n.node.as_str())
+            .collect();
+        assert_eq!(flagged, ["gpu07", "gpu08"]);
+        assert_eq!(report.nodes[0].node_failed, 6);
+        assert_eq!(report.nodes[0].failed_ids.len(), 6);
+        // cn00 failed 4 of its 20 jobs: not enough to stand out
+        assert!(report.nodes.iter().any(|n| n.node == "cn00" && !n.elevated));
+
+        assert!((binomial_upper_tail(10, 0, 0.3) - 1.0).abs() < 1e-12);
+        assert!((binomial_upper_tail(2, 2, 0.5) - 0.25).abs() < 1e-12);
+        assert!((binomial_upper_tail(10, 3, 0.1) - 0.0701908264).abs() < 1e-9);
+    }
+}
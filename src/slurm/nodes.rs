@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use color_eyre::Result;
+
+use super::error::checked;
+use super::tres::{gpus_from_tres, gpus_per_node, normalize_gres};
+use super::{command, parse_scontrol_output};
+
+/// A compute node, as `scontrol show node` prints it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Node {
+    pub name: String,
+    /// e.g. `MIXED`, `IDLE+DRAIN`, `DOWN+NOT_RESPONDING`
+    pub state: String,
+    pub cpus_alloc: u32,
+    pub cpus_total: u32,
+    /// MB
+    pub memory_alloc: u64,
+    pub memory_total: u64,
+    pub gpus_alloc: u32,
+    pub gpus_total: u32,
+    pub partitions: String,
+    /// Why it was drained or set down, without who did it when
+    pub reason: Option<String>,
+    /// `user@time` of the reason
+    pub reason_by: Option<String>,
+    /// Power draw, where an energy plugin (RAPL, IPMI) reports it
+    pub current_watts: Option<u32>,
+}
+
+impl Node {
+    /// Drained, or draining its last jobs
+    pub fn is_drained(&self) -> bool {
+        self.state.split('+').any(|s| s.starts_with("DRAIN"))
+    }
+
+    pub fn is_down(&self) -> bool {
+        self.state
+            .split('+')
+            .any(|s| matches!(s, "DOWN" | "FAIL" | "FAILING" | "NOT_RESPONDING"))
+    }
+}
+
+/// Parse `scontrol -o show nodes`: one node per line. `Reason` may hold
+/// spaces, so it is read up to the end of the line.
+pub fn parse_nodes(stdout: &str) -> Vec<Node> {
+    stdout
+        .lines()
+        .filter(|line| line.trim_start().starts_with("NodeName="))
+        .map(|line| {
+            let (line, reason) = match line.split_once(" Reason=") {
+                Some((fields, reason)) => (fields, Some(reason.trim())),
+                None => (line, None),
+            };
+            let fields = parse_scontrol_output(line);
+            let field = |key: &str| fields.get(key).cloned().unwrap_or_default();
+            let count = |key: &str| fields.get(key).and_then(|v| v.parse().ok()).unwrap_or(0);
+            // The TRES, or the GRES of Slurm versions without them
+            let gpus = |tres: &str, gres: &str| match fields.get(tres) {
+                Some(tres) if !tres.is_empty() => gpus_from_tres(tres),
+                _ => fields
+                    .get(gres)
+                    .and_then(|g| normalize_gres(g))
+                    .map_or(0, |g| gpus_per_node(&g)),
+            };
+            let (reason, reason_by) = match reason.filter(|r| !r.is_empty() && *r != "(null)") {
+                Some(reason) => match reason.rsplit_once(" [") {
+                    Some((reason, by)) if by.ends_with(']') => (
+                        Some(reason.to_string()),
+                        Some(by.trim_end_matches(']').to_string()),
+                    ),
+                    _ => (Some(reason.to_string()), None),
+                },
+                None => (None, None),
+            };
+            Node {
+                name: field("NodeName"),
+                state: field("State"),
+                cpus_alloc: count("CPUAlloc") as u32,
+                cpus_total: count("CPUTot") as u32,
+                memory_alloc: count("AllocMem"),
+                memory_total: count("RealMemory"),
+                gpus_alloc: gpus("AllocTRES", "GresUsed"),
+                gpus_total: gpus("CfgTRES", "Gres"),
+                partitions: field("Partitions"),
+                reason,
+                reason_by,
+                current_watts: fields
+                    .get("CurrentWatts")
+                    .and_then(|w| w.parse().ok())
+                    .filter(|w| *w > 0),
+            }
+        })
+        .collect()
+}
+
+/// GPUs allocated and in all per partition, as `(partition, allocated,
+/// total)` by name, of the partitions with GPUs
+pub fn partition_gpus(nodes: &[Node]) -> Vec<(String, u32, u32)> {
+    let mut partitions: BTreeMap<&str, (u32, u32)> = BTreeMap::new();
+    for node in nodes.iter().filter(|n| n.gpus_total > 0) {
+        for partition in node.partitions.split(',').filter(|p| !p.is_empty()) {
+            let gpus = partitions.entry(partition).or_default();
+            gpus.0 += node.gpus_alloc;
+            gpus.1 += node.gpus_total;
+        }
+    }
+    partitions
+        .into_iter()
+        .map(|(partition, (alloc, total))| (partition.to_string(), alloc, total))
+        .collect()
+}
+
+/// The cluster's nodes, by name
+pub async fn fetch_nodes() -> Result<Vec<Node>> {
+    let output = command::output("scontrol", ["-o", "show", "nodes"]).await;
+    let output = checked("scontrol", output)?;
+    let mut nodes = parse_nodes(&String::from_utf8_lossy(&output.stdout));
+    nodes.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(nodes)
+}
+
+/// An operator's change to a node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeUpdate {
+    /// Let its jobs finish but start no new ones; draining a drained node
+    /// again replaces its reason
+    Drain { node: String, reason: String },
+    /// Return it to service
+    Resume { node: String },
+}
+
+impl NodeUpdate {
+    pub fn node(&self) -> &str {
+        match self {
+            NodeUpdate::Drain { node, .. } | NodeUpdate::Resume { node } => node,
+        }
+    }
+
+    /// Arguments of `scontrol`
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec!["update".to_string(), format!("NodeName={}", self.node())];
+        match self {
+            NodeUpdate::Drain { reason, .. } => {
+                args.push("State=DRAIN".to_string());
+                args.push(format!("Reason={}", reason));
+            }
+            NodeUpdate::Resume { .. } => args.push("State=RESUME".to_string()),
+        }
+        args
+    }
+
+    pub fn past_tense(&self) -> &'static str {
+        match self {
+            NodeUpdate::Drain { .. } => "Drained",
+            NodeUpdate::Resume { .. } => "Resumed",
+        }
+    }
+}
+
+/// Drain or resume a node (`scontrol update NodeName=<node> State=...`)
+pub async fn update_node(update: &NodeUpdate) -> Result<()> {
+    checked(
+        "scontrol update",
+        command::execute_update("scontrol", update.args()).await,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nodes_parse_with_their_drain_reason() {
+        let nodes = parse_nodes(
+            "NodeName=gpu01 Arch=x86_64 CPUAlloc=16 CPUTot=64 RealMemory=512000 AllocMem=128000 \
+             CfgTRES=cpu=64,mem=500G,gres/gpu=4,gres/gpu:a100=4 AllocTRES=cpu=16,gres/gpu=3 \
+             State=MIXED+DRAIN Partitions=gpu,debug CurrentWatts=412 Reason=bad fan on tray 2 [root@2026-10-12T09:30:00]\n\
+             NodeName=cpu07 CPUAlloc=0 CPUTot=32 RealMemory=192000 AllocMem=0 State=IDLE Partitions=cpu\n\
+             NodeName=gpu02 Gres=gpu:v100:2(S:0) GresUsed=gpu:v100:1(IDX:0) State=MIXED Partitions=gpu\n",
+        );
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(nodes[0].reason.as_deref(), Some("bad fan on tray 2"));
+        assert_eq!(
+            nodes[0].reason_by.as_deref(),
+            Some("root@2026-10-12T09:30:00")
+        );
+        assert_eq!((nodes[0].cpus_alloc, nodes[0].memory_total), (16, 512000));
+        assert!(nodes[0].is_drained() && !nodes[0].is_down());
+        assert_eq!(nodes[0].current_watts, Some(412));
+        assert_eq!(nodes[1].reason, None);
+        assert_eq!(nodes[1].current_watts, None);
+        assert!(!nodes[1].is_drained());
+        assert_eq!((nodes[0].gpus_alloc, nodes[0].gpus_total), (3, 4));
+        assert_eq!((nodes[2].gpus_alloc, nodes[2].gpus_total), (1, 2));
+        assert_eq!(
+            partition_gpus(&nodes),
+            [("debug".to_string(), 3, 4), ("gpu".to_string(), 4, 6)]
+        );
+
+        let drain = NodeUpdate::Drain {
+            node: "gpu01".to_string(),
+            reason: "fan replaced, burn-in".to_string(),
+        };
+        assert_eq!(
+            drain.args(),
+            [
+                "update",
+                "NodeName=gpu01",
+                "State=DRAIN",
+                "Reason=fan replaced, burn-in"
+            ]
+        );
+        let resume = NodeUpdate::Resume {
+            node: "gpu01".to_string(),
+        };
+        assert_eq!(
+            resume.args().last().map(String::as_str),
+            Some("State=RESUME")
+        );
+    }
+}
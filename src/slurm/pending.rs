@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, Local};
+use color_eyre::Result;
+
+use super::cache;
+use super::error::checked;
+use super::squeue::SqueueOptions;
+use super::JobState;
+use crate::utils::parse_slurm_time;
+
+/// squeue format for the pending-reason summary; the reason goes last as it
+/// may hold anything (`ReqNodeNotAvail, UnavailableNodes:gpu[01-02]`)
+pub const PENDING_FORMAT: &str = "%i|%V|%r";
+
+/// What kind of obstacle a pending reason is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReasonKind {
+    /// Limits of the user, account or QOS (`QOSMaxGRESPerUser`, `AssocGrpCPULimit`)
+    Limit,
+    /// Waiting for resources or for higher priority jobs to go first
+    Load,
+    Dependency,
+    /// Held, or not to start before a given time
+    Held,
+    Other,
+}
+
+impl ReasonKind {
+    pub fn of(reason: &str) -> Self {
+        let reason = reason.trim_matches(|c| c == '(' || c == ')').trim();
+        let name = reason.split([',', ' ']).next().unwrap_or("");
+        match name {
+            "Resources" | "Priority" | "Prolog" | "Reservation" | "Licenses" | "NodeDown"
+            | "ReqNodeNotAvail" | "Nodes" => ReasonKind::Load,
+            "Dependency" | "DependencyNeverSatisfied" => ReasonKind::Dependency,
+            "JobHeldUser" | "JobHeldAdmin" | "BeginTime" => ReasonKind::Held,
+            _ if name.starts_with("QOS")
+                || name.starts_with("Assoc")
+                || name.starts_with("Max")
+                || name.ends_with("Limit") =>
+            {
+                ReasonKind::Limit
+            }
+            _ => ReasonKind::Other,
+        }
+    }
+}
+
+impl fmt::Display for ReasonKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReasonKind::Limit => "limits",
+            ReasonKind::Load => "cluster load",
+            ReasonKind::Dependency => "dependencies",
+            ReasonKind::Held => "holds",
+            ReasonKind::Other => "other",
+        })
+    }
+}
+
+/// Pending jobs that wait for the same reason
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReasonGroup {
+    pub reason: String,
+    pub kind: ReasonKind,
+    pub jobs: usize,
+    /// The job waiting longest, and when it was submitted
+    pub oldest: Option<(String, DateTime<Local>)>,
+}
+
+/// Group the pending jobs (with the job list's filters) by reason
+pub async fn run_squeue_pending(options: &SqueueOptions) -> Result<Vec<ReasonGroup>> {
+    let options = SqueueOptions {
+        states: vec![JobState::Pending],
+        format: PENDING_FORMAT.to_string(),
+        sorts: HashMap::new(),
+        ..options.clone()
+    };
+    let output = checked("squeue", cache::query("squeue", options.to_args()).await)?;
+
+    Ok(pending_reasons(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Group `PENDING_FORMAT` rows by reason, most common first
+pub fn pending_reasons(stdout: &str) -> Vec<ReasonGroup> {
+    let mut groups: HashMap<&str, ReasonGroup> = HashMap::new();
+    for line in stdout.lines() {
+        let mut fields = line.splitn(3, '|').map(str::trim);
+        let (Some(id), Some(submit), Some(reason)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if id.is_empty() {
+            continue;
+        }
+        let reason = match reason {
+            "" | "(null)" => "None",
+            reason => reason,
+        };
+        let group = groups.entry(reason).or_insert_with(|| ReasonGroup {
+            reason: reason.to_string(),
+            kind: ReasonKind::of(reason),
+            jobs: 0,
+            oldest: None,
+        });
+        group.jobs += 1;
+        let Some(submit) = parse_slurm_time(submit) else {
+            continue;
+        };
+        if group
+            .oldest
+            .as_ref()
+            .is_none_or(|(_, oldest)| submit < *oldest)
+        {
+            group.oldest = Some((id.to_string(), submit));
+        }
+    }
+
+    let mut groups: Vec<ReasonGroup> = groups.into_values().collect();
+    groups.sort_by(|a, b| b.jobs.cmp(&a.jobs).then_with(|| a.reason.cmp(&b.reason)));
+    groups
+}
+
+/// Jobs per kind of reason, largest first (`8 on cluster load, 3 on limits`)
+pub fn summary(groups: &[ReasonGroup]) -> Vec<(ReasonKind, usize)> {
+    let mut kinds: Vec<(ReasonKind, usize)> = Vec::new();
+    for group in groups {
+        match kinds.iter_mut().find(|(kind, _)| *kind == group.kind) {
+            Some((_, jobs)) => *jobs += group.jobs,
+            None => kinds.push((group.kind, group.jobs)),
+        }
+    }
+    kinds.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    kinds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_pending_jobs_by_reason() {
+        let stdout = "11|2024-05-01T10:00:00|Resources\n\
+                      12|2024-05-01T09:00:00|Resources\n\
+                      13|2024-05-01T08:00:00|QOSMaxGRESPerUser\n\
+                      14|2024-05-01T11:00:00|AssocGrpCPULimit\n\
+                      15|N/A|ReqNodeNotAvail, UnavailableNodes:gpu[01-02]\n\
+                      \n\
+                      16|2024-05-01T12:00:00|Dependency\n";
+        let groups = pending_reasons(stdout);
+        assert_eq!(
+            groups[0],
+            ReasonGroup {
+                reason: "Resources".to_string(),
+                kind: ReasonKind::Load,
+                jobs: 2,
+                oldest: Some((
+                    "12".to_string(),
+                    parse_slurm_time("2024-05-01T09:00:00").unwrap()
+                )),
+            }
+        );
+        assert_eq!(groups.len(), 5);
+        let unavailable = groups
+            .iter()
+            .find(|g| g.reason.starts_with("ReqNode"))
+            .unwrap();
+        assert_eq!(
+            (unavailable.kind, &unavailable.oldest),
+            (ReasonKind::Load, &None)
+        );
+        assert_eq!(
+            summary(&groups),
+            vec![
+                (ReasonKind::Load, 3),
+                (ReasonKind::Limit, 2),
+                (ReasonKind::Dependency, 1)
+            ]
+        );
+        assert_eq!(ReasonKind::of("(JobHeldUser)"), ReasonKind::Held);
+        assert_eq!(ReasonKind::of("PartitionDown"), ReasonKind::Other);
+    }
+}
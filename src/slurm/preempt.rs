@@ -0,0 +1,171 @@
+use color_eyre::Result;
+use std::collections::HashMap;
+
+use super::error::checked;
+use super::{command, parse_scontrol_output, sacctmgr::sacctmgr, Job, JobState};
+
+/// sacctmgr format of the QOS preemption settings
+const QOS_FORMAT: &str = "format=Name,Preempt,PreemptMode";
+
+/// Which QOSs a QOS preempts, and what happens to its own jobs when they are
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QosPreemption {
+    pub name: String,
+    /// QOSs whose running jobs this one's pending jobs may preempt
+    pub preempts: Vec<String>,
+    /// `requeue`, `cancel`, `suspend`, `off`, or `cluster` for the
+    /// cluster's `PreemptMode`
+    pub mode: String,
+}
+
+/// Parse `sacctmgr -n -P show qos format=Name,Preempt,PreemptMode`
+pub fn parse_qos_preemption(stdout: &str) -> Vec<QosPreemption> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('|').map(str::trim);
+            let name = fields.next().filter(|n| !n.is_empty())?;
+            let preempts = fields
+                .next()
+                .unwrap_or_default()
+                .split(',')
+                // `+high` / `-high` when printed as a change
+                .map(|q| q.trim_start_matches(['+', '-']))
+                .filter(|q| !q.is_empty())
+                .map(str::to_string)
+                .collect();
+            Some(QosPreemption {
+                name: name.to_string(),
+                preempts,
+                mode: fields.next().unwrap_or_default().to_lowercase(),
+            })
+        })
+        .collect()
+}
+
+/// A running job's exposure to preemption
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreemptionRisk {
+    /// QOSs whose pending jobs may preempt it
+    pub by: Vec<String>,
+    /// Jobs pending in those QOSs, queue-wide
+    pub pending: usize,
+    /// What would happen to it
+    pub mode: String,
+}
+
+impl PreemptionRisk {
+    /// Short form, for the Preempt column
+    pub fn summary(&self) -> String {
+        if self.pending > 0 {
+            format!("at risk ({})", self.mode)
+        } else {
+            format!("preemptable ({})", self.mode)
+        }
+    }
+}
+
+/// How `job`, running, could be preempted: by the QOSs that may preempt
+/// its QOS, with the jobs pending in them. `None` when nothing may.
+pub fn preemption_risk(
+    job: &Job,
+    qos: &[QosPreemption],
+    pending_by_qos: &HashMap<String, usize>,
+) -> Option<PreemptionRisk> {
+    if job.state != JobState::Running {
+        return None;
+    }
+    let own = qos.iter().find(|q| q.name == job.qos)?;
+    if own.mode == "off" {
+        return None;
+    }
+    let by: Vec<String> = qos
+        .iter()
+        .filter(|q| q.preempts.contains(&job.qos))
+        .map(|q| q.name.clone())
+        .collect();
+    if by.is_empty() {
+        return None;
+    }
+    let pending = by.iter().filter_map(|q| pending_by_qos.get(q)).sum();
+    let mode = match own.mode.as_str() {
+        "" | "cluster" => "cluster default".to_string(),
+        mode => mode.to_string(),
+    };
+    Some(PreemptionRisk { by, pending, mode })
+}
+
+/// Count pending jobs per QOS from `squeue -h -t PD -o %q`
+pub fn count_by_qos(stdout: &str) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for qos in stdout.lines().map(str::trim).filter(|q| !q.is_empty()) {
+        *counts.entry(qos.to_string()).or_default() += 1;
+    }
+    counts
+}
+
+/// The QOS preemption settings, with the jobs pending in each QOS
+pub async fn fetch_preemption() -> Result<(Vec<QosPreemption>, HashMap<String, usize>)> {
+    let qos = parse_qos_preemption(&sacctmgr(&["-n", "-P", "show", "qos", QOS_FORMAT]).await?);
+    if qos.iter().all(|q| q.preempts.is_empty()) {
+        return Ok((qos, HashMap::new()));
+    }
+    let output = command::output("squeue", ["-h", "-t", "PD", "-o", "%q"]).await;
+    let output = checked("squeue", output)?;
+    Ok((qos, count_by_qos(&String::from_utf8_lossy(&output.stdout))))
+}
+
+/// When a running job that is being preempted will be stopped
+/// (`PreemptTime` of `scontrol show job`); `None` when it is not
+pub async fn fetch_preempt_time(job_id: &str) -> Result<Option<String>> {
+    let output = command::output("scontrol", ["show", "job", job_id, "-o"]).await;
+    let output = checked("scontrol", output)?;
+    let fields = parse_scontrol_output(&String::from_utf8_lossy(&output.stdout));
+    Ok(fields
+        .get("PreemptTime")
+        .filter(|t| !matches!(t.as_str(), "None" | "Unknown" | "(null)"))
+        .cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn running_jobs_in_preemptable_qos_are_at_risk() {
+        let qos = parse_qos_preemption(
+            "normal||cluster\n\
+             scavenger||REQUEUE\n\
+             high|scavenger,normal|off\n\
+             urgent|scavenger|cancel\n",
+        );
+        assert_eq!(qos[2].preempts, ["scavenger", "normal"]);
+        let pending = count_by_qos("high\nhigh\nnormal\n\n");
+        assert_eq!(pending.get("high"), Some(&2));
+
+        let job = |qos: &str, state| Job {
+            qos: qos.to_string(),
+            state,
+            ..Default::default()
+        };
+        let risk = preemption_risk(&job("scavenger", JobState::Running), &qos, &pending).unwrap();
+        assert_eq!(risk.by, ["high", "urgent"]);
+        assert_eq!((risk.pending, risk.mode.as_str()), (2, "requeue"));
+        assert_eq!(risk.summary(), "at risk (requeue)");
+
+        let normal = preemption_risk(&job("normal", JobState::Running), &qos, &HashMap::new());
+        assert_eq!(
+            normal.map(|r| r.summary()).as_deref(),
+            Some("preemptable (cluster default)")
+        );
+        // Nothing may preempt high, and pending jobs are not preempted
+        assert_eq!(
+            preemption_risk(&job("high", JobState::Running), &qos, &pending),
+            None
+        );
+        assert_eq!(
+            preemption_risk(&job("scavenger", JobState::Pending), &qos, &pending),
+            None
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local, TimeDelta};
+use regex::Regex;
+
+use super::sacct::{name_regex, SacctOptions};
+use super::squeue::SqueueOptions;
+use super::{Job, JobState};
+use crate::utils::parse_slurm_time;
+
+/// squeue format of the search results: the fields the results view shows
+const SEARCH_FORMAT: &str = "%i|%j|%u|%T|%M|%N|%C|%m|%P|%V|%S|%e|%a";
+
+/// sacct fields of the search results, as `SEARCH_FORMAT`
+pub const SEARCH_FIELDS: &[&str] = &[
+    "JobIDRaw",
+    "JobName",
+    "User",
+    "State",
+    "Elapsed",
+    "NodeList",
+    "AllocCPUS",
+    "ReqMem",
+    "Partition",
+    "Submit",
+    "Start",
+    "End",
+    "Account",
+];
+
+/// An ad-hoc search of squeue and accounting, as filled in the search form
+#[derive(Debug, Clone)]
+pub struct JobQuery {
+    /// `None` searches everyone's jobs
+    pub user: Option<String>,
+    /// Job name glob or regex, as `name_regex` reads it
+    pub name: Option<Regex>,
+    /// Any state when empty
+    pub states: Vec<JobState>,
+    pub partitions: Vec<String>,
+    pub accounts: Vec<String>,
+    /// Hours back from now that accounting is searched
+    pub since_hours: u32,
+    /// Jobs submitted after this time are left out
+    pub until: Option<DateTime<Local>>,
+    /// Whole hours back from now that accounting is searched up to, so
+    /// `until` falls inside it
+    pub until_hours: u32,
+    /// Node name regex, matched against the node list
+    pub node: Option<Regex>,
+}
+
+impl JobQuery {
+    /// The query of the form's values (`value` of a label): lists are
+    /// separated by commas, `From` and `To` are a timestamp
+    /// (`2024-05-01`, `2024-05-01T13:45`) or a span back from now (`12h`,
+    /// `7d`).
+    pub fn parse(
+        value: impl Fn(&str) -> String,
+        me: &str,
+        now: DateTime<Local>,
+    ) -> Result<Self, String> {
+        let list = |label: &str| -> Vec<String> {
+            value(label)
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        };
+        let regex = |label: &str| -> Result<Option<Regex>, String> {
+            match value(label).trim() {
+                "" => Ok(None),
+                pattern if label == "Name" => name_regex(pattern)
+                    .map(Some)
+                    .map_err(|e| format!("Name is not a valid glob or regex: {}", e)),
+                pattern => Regex::new(pattern)
+                    .map(Some)
+                    .map_err(|e| format!("{} is not a valid regex: {}", label, e)),
+            }
+        };
+        let user = match value("User").trim() {
+            "*" => None,
+            "" | "me" => Some(me.to_string()),
+            user => Some(user.to_string()),
+        };
+        let states = list("States")
+            .iter()
+            .map(|s| match s.to_uppercase().parse::<JobState>() {
+                Ok(JobState::Other) | Err(_) => Err(format!("unknown job state `{}`", s)),
+                Ok(state) => Ok(state),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let since = match value("From").trim() {
+            "" => now - TimeDelta::hours(24),
+            from => {
+                parse_point(from, now).ok_or_else(|| format!("From: cannot read `{}`", from))?
+            }
+        };
+        let until = match value("To").trim() {
+            "" | "now" => None,
+            to => Some(parse_point(to, now).ok_or_else(|| format!("To: cannot read `{}`", to))?),
+        };
+        if until.is_some_and(|until| until < since) {
+            return Err("To is before From".to_string());
+        }
+        Ok(Self {
+            user,
+            name: regex("Name")?,
+            states,
+            partitions: list("Partitions"),
+            accounts: list("Accounts"),
+            since_hours: ((now - since).num_seconds().max(0) as u64)
+                .div_ceil(3600)
+                .max(1) as u32,
+            until,
+            until_hours: until.map_or(0, |until| ((now - until).num_hours().max(0)) as u32),
+            node: regex("Node")?,
+        })
+    }
+
+    /// The squeue side of the search, unless only ended states are searched
+    pub fn squeue_options(&self) -> Option<SqueueOptions> {
+        let states: Vec<JobState> = self
+            .states
+            .iter()
+            .copied()
+            .filter(|s| s.is_active())
+            .collect();
+        if !self.states.is_empty() && states.is_empty() {
+            return None;
+        }
+        Some(SqueueOptions {
+            user: self.user.clone(),
+            states,
+            partitions: self.partitions.clone(),
+            accounts: self.accounts.clone(),
+            format: SEARCH_FORMAT.to_string(),
+            sorts: HashMap::new(),
+            ..SqueueOptions::default()
+        })
+    }
+
+    /// The accounting side of the search, unless only active states are
+    /// searched
+    pub fn sacct_options(&self) -> Option<SacctOptions> {
+        let states: Vec<JobState> = self
+            .states
+            .iter()
+            .copied()
+            .filter(|s| !s.is_active())
+            .collect();
+        if !self.states.is_empty() && states.is_empty() {
+            return None;
+        }
+        Some(SacctOptions {
+            user: self.user.clone(),
+            states: if states.is_empty() {
+                JobState::ended_states()
+            } else {
+                states
+            },
+            partitions: self.partitions.clone(),
+            qos: Vec::new(),
+            accounts: self.accounts.clone(),
+            wckeys: Vec::new(),
+            name: self.name.as_ref().map(|re| re.as_str().to_string()),
+            recent_hours: self.since_hours,
+            end_hours: self.until_hours,
+            format_fields: SEARCH_FIELDS.to_vec(),
+            max_rows: None,
+            window_hours: None,
+        })
+    }
+
+    /// What neither command filters: the name and node patterns and the
+    /// end of the date range
+    pub fn matches(&self, job: &Job) -> bool {
+        self.name.as_ref().is_none_or(|re| re.is_match(&job.name))
+            && self
+                .node
+                .as_ref()
+                .is_none_or(|re| job.node.as_deref().is_some_and(|n| re.is_match(n)))
+            && self
+                .until
+                .is_none_or(|until| job.submit_time.is_none_or(|submitted| submitted <= until))
+    }
+}
+
+/// A point in time of the form: a Slurm timestamp, a date, or a span back
+/// from `now`
+fn parse_point(raw: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    if let Some(at) = parse_slurm_time(raw) {
+        return Some(at);
+    }
+    if let Some(at) = parse_slurm_time(&format!("{}:00", raw)) {
+        return Some(at);
+    }
+    if let Some(at) = parse_slurm_time(&format!("{}T00:00:00", raw)) {
+        return Some(at);
+    }
+    let (number, unit) = raw.split_at(raw.len().checked_sub(1)?);
+    let number = i64::try_from(number.trim().parse::<u64>().ok()?).ok()?;
+    let span = match unit {
+        "m" => TimeDelta::try_minutes(number)?,
+        "h" => TimeDelta::try_hours(number)?,
+        "d" => TimeDelta::try_days(number)?,
+        "w" => TimeDelta::try_weeks(number)?,
+        _ => return None,
+    };
+    now.checked_sub_signed(span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forms_become_a_squeue_and_sacct_pair() {
+        let now = parse_slurm_time("2024-05-14T12:00:00").unwrap();
+        let form = |label: &str| -> String {
+            match label {
+                "User" => "*",
+                "Name" => "^train_",
+                "States" => "running, failed",
+                "Partitions" => "gpu",
+                "From" => "2024-05-10",
+                "To" => "2d",
+                _ => "",
+            }
+            .to_string()
+        };
+        let query = JobQuery::parse(form, "demo", now).unwrap();
+        assert_eq!(query.user, None);
+        assert_eq!(query.since_hours, 4 * 24 + 12);
+
+        let squeue = query.squeue_options().unwrap();
+        assert_eq!(squeue.states, [JobState::Running]);
+        assert_eq!(squeue.partitions, ["gpu"]);
+        let sacct = query.sacct_options().unwrap();
+        assert_eq!(sacct.states, [JobState::Failed]);
+        assert_eq!(sacct.recent_hours, 108);
+        assert_eq!(sacct.end_hours, 48, "To bounds the accounting query");
+        assert!(sacct
+            .to_args(sacct.windows()[0])
+            .contains(&"now-48hours".to_string()));
+        assert!(sacct.to_args((108, 48)).contains(&"--allusers".to_string()));
+
+        let job = |name: &str, submitted: &str| Job {
+            name: name.to_string(),
+            submit_time: parse_slurm_time(submitted),
+            ..Default::default()
+        };
+        assert!(query.matches(&job("train_a", "2024-05-11T09:00:00")));
+        assert!(!query.matches(&job("eval_a", "2024-05-11T09:00:00")));
+        // Submitted after To, two days back
+        assert!(!query.matches(&job("train_b", "2024-05-13T09:00:00")));
+
+        let pending = |label: &str| match label {
+            "States" => "PENDING".to_string(),
+            _ => String::new(),
+        };
+        let query = JobQuery::parse(pending, "demo", now).unwrap();
+        assert_eq!(query.user.as_deref(), Some("demo"));
+        assert!(query.sacct_options().is_none());
+        let glob = |label: &str| match label {
+            "Name" => "sweep_lr*".to_string(),
+            _ => String::new(),
+        };
+        let sacct = JobQuery::parse(glob, "demo", now)
+            .unwrap()
+            .sacct_options()
+            .unwrap();
+        assert_eq!(sacct.name.as_deref(), Some("^sweep_lr.*$"));
+        let bad = |label: &str| match label {
+            "States" => "RUNING".to_string(),
+            _ => String::new(),
+        };
+        assert_eq!(
+            JobQuery::parse(bad, "demo", now).unwrap_err(),
+            "unknown job state `RUNING`"
+        );
+    }
+}
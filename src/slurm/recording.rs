@@ -0,0 +1,579 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output};
+use std::sync::{LazyLock, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::Result;
+use regex::bytes::Regex as BytesRegex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+
+/// Blocks of a tar archive
+const BLOCK: usize = 512;
+
+/// Name of the description of the session in the archive
+const MANIFEST: &str = "session.toml";
+
+/// Dates in arguments, which differ from one session to the next
+static DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\d{4}-\d{2}-\d{2}(?:T\d{2}(?::\d{2}){0,2})?").expect("valid regex")
+});
+
+/// One run of a Slurm command, as recorded (`runs/<n>.toml` in the archive)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRun {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Exit code; `None` when the command was killed
+    pub code: Option<i32>,
+    /// Kept in the archive as `runs/<n>.out`
+    #[serde(skip)]
+    pub stdout: Vec<u8>,
+    /// Kept in the archive as `runs/<n>.err`
+    #[serde(skip)]
+    pub stderr: Vec<u8>,
+}
+
+impl RecordedRun {
+    pub fn output(&self) -> Output {
+        Output {
+            status: match self.code {
+                Some(code) => ExitStatus::from_raw(code << 8),
+                None => ExitStatus::from_raw(9),
+            },
+            stdout: self.stdout.clone(),
+            stderr: self.stderr.clone(),
+        }
+    }
+
+    /// The archive entries of the `n`th run
+    fn entries(&self, n: usize) -> Result<Vec<u8>> {
+        let mut raw = tar_entry(
+            &format!("runs/{:04}.toml", n),
+            toml::to_string(self)
+                .wrap_err("failed serializing a recorded run")?
+                .as_bytes(),
+        );
+        raw.extend(tar_entry(&format!("runs/{:04}.out", n), &self.stdout));
+        raw.extend(tar_entry(&format!("runs/{:04}.err", n), &self.stderr));
+        Ok(raw)
+    }
+}
+
+/// The raw output of every Slurm command of a session, in the order they
+/// ran (`slurmer --record`), to be fed back through the parsers and the UI
+/// (`slurmer --replay`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    /// Slurmer version that recorded
+    pub version: String,
+    /// The user that recorded, `user1` once redacted
+    pub user: String,
+    /// Kept in the archive as `runs/<n>.toml`, `.out` and `.err`
+    #[serde(skip)]
+    pub runs: Vec<RecordedRun>,
+}
+
+impl Recording {
+    pub fn new(user: String) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            user,
+            runs: Vec::new(),
+        }
+    }
+
+    /// Replace `names` by `user1`, `user2`, ... (the recording user first)
+    /// as whole words in every argument and output
+    pub fn redact(&mut self, names: &BTreeSet<String>) {
+        let mut names: Vec<&String> = names.iter().filter(|n| **n != self.user).collect();
+        names.insert(0, &self.user);
+        names.retain(|n| !n.is_empty());
+        if names.is_empty() {
+            return;
+        }
+        let aliases: HashMap<&str, String> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), format!("user{}", i + 1)))
+            .collect();
+        let pattern = format!(
+            r"\b(?:{})\b",
+            names
+                .iter()
+                .map(|n| regex::escape(n))
+                .collect::<Vec<_>>()
+                .join("|")
+        );
+        let (Ok(text), Ok(bytes)) = (Regex::new(&pattern), BytesRegex::new(&pattern)) else {
+            return;
+        };
+        let alias = |name: &str| aliases.get(name).cloned().unwrap_or_default();
+        for run in &mut self.runs {
+            for arg in &mut run.args {
+                *arg = text
+                    .replace_all(arg, |c: &regex::Captures| alias(&c[0]))
+                    .into_owned();
+            }
+            for raw in [&mut run.stdout, &mut run.stderr] {
+                *raw = bytes
+                    .replace_all(raw, |c: &regex::bytes::Captures| {
+                        alias(&String::from_utf8_lossy(&c[0])).into_bytes()
+                    })
+                    .into_owned();
+            }
+        }
+        self.user = alias(&self.user);
+    }
+
+    /// The archive entries of the session description
+    fn manifest(&self) -> Result<Vec<u8>> {
+        let manifest = toml::to_string(self).wrap_err("failed serializing the recording")?;
+        Ok(tar_entry(MANIFEST, manifest.as_bytes()))
+    }
+
+    /// Replace the archive at `path` by this recording
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mut raw = self.manifest()?;
+        for (i, run) in self.runs.iter().enumerate() {
+            raw.extend(run.entries(i + 1)?);
+        }
+        raw.extend_from_slice(&TAR_END);
+        let dir = path.parent().filter(|d| !d.as_os_str().is_empty());
+        let mut file = tempfile::NamedTempFile::new_in(dir.unwrap_or(Path::new(".")))
+            .and_then(|mut file| file.write_all(&raw).map(|_| file))
+            .wrap_err_with(|| format!("failed writing {}", path.display()))?;
+        file.flush()?;
+        file.persist(path)
+            .wrap_err_with(|| format!("failed writing {}", path.display()))?;
+        Ok(())
+    }
+
+    /// The recording at `path`, including one cut short by a crash, which
+    /// holds the runs written until then
+    pub fn read(path: &Path) -> Result<Self> {
+        let raw = fs::read(path).wrap_err_with(|| format!("failed reading {}", path.display()))?;
+        let mut files: HashMap<String, Vec<u8>> = read_tar(&raw)?.into_iter().collect();
+        let manifest = files
+            .remove(MANIFEST)
+            .ok_or_else(|| eyre!("{} has no {}", path.display(), MANIFEST))?;
+        let mut recording: Recording = toml::from_str(&String::from_utf8_lossy(&manifest))
+            .wrap_err_with(|| format!("failed parsing {} of {}", MANIFEST, path.display()))?;
+        for n in 1.. {
+            let Some(run) = files.remove(&format!("runs/{:04}.toml", n)) else {
+                break;
+            };
+            let mut run: RecordedRun = toml::from_str(&String::from_utf8_lossy(&run))
+                .wrap_err_with(|| format!("failed parsing run {} of {}", n, path.display()))?;
+            run.stdout = files
+                .remove(&format!("runs/{:04}.out", n))
+                .unwrap_or_default();
+            run.stderr = files
+                .remove(&format!("runs/{:04}.err", n))
+                .unwrap_or_default();
+            recording.runs.push(run);
+        }
+        Ok(recording)
+    }
+}
+
+/// A recording written to its archive run by run, so a long session holds
+/// none of the output in memory and a crash loses none of it. Names are
+/// redacted once it is finished, as the users of listed jobs come in late.
+pub struct Recorder {
+    path: PathBuf,
+    file: File,
+    runs: usize,
+    /// Users of the listed jobs, redacted with the recording user
+    users: Option<BTreeSet<String>>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, user: String, redact: bool) -> Result<Self> {
+        let mut file =
+            File::create(path).wrap_err_with(|| format!("failed creating {}", path.display()))?;
+        file.write_all(&Recording::new(user).manifest()?)
+            .wrap_err_with(|| format!("failed writing {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            runs: 0,
+            users: redact.then(BTreeSet::new),
+        })
+    }
+
+    /// Append a finished run to the archive
+    pub fn push(&mut self, run: &RecordedRun) -> Result<()> {
+        let raw = run.entries(self.runs + 1)?;
+        self.file
+            .write_all(&raw)
+            .wrap_err_with(|| format!("failed writing {}", self.path.display()))?;
+        self.runs += 1;
+        Ok(())
+    }
+
+    /// Close the archive, redacted if asked; returns the number of runs
+    pub fn finish(mut self) -> Result<usize> {
+        self.file
+            .write_all(&TAR_END)
+            .and_then(|_| self.file.sync_all())
+            .wrap_err_with(|| format!("failed writing {}", self.path.display()))?;
+        if let Some(users) = &self.users {
+            let mut recording = Recording::read(&self.path)?;
+            recording.redact(users);
+            recording.write(&self.path)?;
+        }
+        Ok(self.runs)
+    }
+}
+
+/// A recording served in place of Slurm. Each command gets the recorded
+/// runs of the same command in turn, and the last of them again once they
+/// are used up, so a replay goes through the session's polls as they were.
+pub struct Replay {
+    recording: Recording,
+    /// Who replays, whose name stands for the recording user in arguments
+    user: String,
+    /// Runs served so far, by command
+    served: HashMap<(String, Vec<String>), usize>,
+}
+
+impl Replay {
+    pub fn new(recording: Recording, user: String) -> Self {
+        Self {
+            recording,
+            user,
+            served: HashMap::new(),
+        }
+    }
+
+    /// The next recorded run of `program` with `args`; dates in the
+    /// arguments (`-S 2024-05-01`) need not match
+    pub fn next(&mut self, program: &str, args: &[String]) -> Option<&RecordedRun> {
+        let key = (program.to_string(), self.normalize(args));
+        let recorded: Vec<usize> = self
+            .recording
+            .runs
+            .iter()
+            .enumerate()
+            .filter(|(_, run)| run.program == program && normalize_dates(&run.args) == key.1)
+            .map(|(i, _)| i)
+            .collect();
+        let served = self.served.entry(key).or_insert(0);
+        let run = *recorded.get(*served).or(recorded.last())?;
+        *served += 1;
+        self.recording.runs.get(run)
+    }
+
+    fn normalize(&self, args: &[String]) -> Vec<String> {
+        if self.user.is_empty() || self.user == self.recording.user {
+            return normalize_dates(args);
+        }
+        let Ok(name) = Regex::new(&format!(r"\b{}\b", regex::escape(&self.user))) else {
+            return normalize_dates(args);
+        };
+        let args: Vec<String> = args
+            .iter()
+            .map(|a| {
+                name.replace_all(a, self.recording.user.as_str())
+                    .into_owned()
+            })
+            .collect();
+        normalize_dates(&args)
+    }
+}
+
+fn normalize_dates(args: &[String]) -> Vec<String> {
+    args.iter()
+        .map(|a| DATE.replace_all(a, "<date>").into_owned())
+        .collect()
+}
+
+/// What this process does with Slurm commands: records them, or serves
+/// them from a recording
+enum Session {
+    Record(Recorder),
+    Replay(Replay),
+}
+
+static SESSION: Mutex<Option<Session>> = Mutex::new(None);
+
+/// Record every Slurm command from now on to the archive at `path`, as
+/// `user`; with `redact`, user names are replaced once it is finished
+pub fn start_recording(path: &Path, user: String, redact: bool) -> Result<()> {
+    let recorder = Recorder::create(path, user, redact)?;
+    if let Ok(mut session) = SESSION.lock() {
+        *session = Some(Session::Record(recorder));
+    }
+    Ok(())
+}
+
+/// Finish the recording and exit when the process is told to stop, for
+/// commands run without the TUI, which stops on these signals by itself
+pub fn finish_on_signal() -> Result<()> {
+    let mut signals = Signals::new([SIGINT, SIGTERM, SIGHUP])?;
+    std::thread::spawn(move || {
+        if let Some(signal) = signals.forever().next() {
+            let _ = finish();
+            std::process::exit(128 + signal);
+        }
+    });
+    Ok(())
+}
+
+/// Serve Slurm commands from the recording at `path` from now on; commands
+/// it does not hold fail rather than reach the cluster
+pub fn start_replay(path: &Path, user: String) -> Result<()> {
+    let recording = Recording::read(path)?;
+    if let Ok(mut session) = SESSION.lock() {
+        *session = Some(Session::Replay(Replay::new(recording, user)));
+    }
+    Ok(())
+}
+
+/// Note the users of listed jobs, to redact their names too
+pub fn note_users<'a>(names: impl IntoIterator<Item = &'a str>) {
+    if let Ok(mut session) = SESSION.lock() {
+        if let Some(Session::Record(Recorder {
+            users: Some(users), ..
+        })) = session.as_mut()
+        {
+            users.extend(names.into_iter().map(str::to_string));
+        }
+    }
+}
+
+/// Close the archive being recorded and stop recording; returns the
+/// number of runs written
+pub fn finish() -> Result<usize> {
+    let session = SESSION.lock().ok().and_then(|mut s| s.take());
+    match session {
+        Some(Session::Record(recorder)) => recorder.finish(),
+        _ => Ok(0),
+    }
+}
+
+/// Note a finished run of a Slurm command while recording
+pub fn record(program: &str, args: &[String], output: &Output) {
+    if let Ok(mut session) = SESSION.lock() {
+        if let Some(Session::Record(recorder)) = session.as_mut() {
+            let run = RecordedRun {
+                program: program.to_string(),
+                args: args.to_vec(),
+                code: output.status.code(),
+                stdout: output.stdout.clone(),
+                stderr: output.stderr.clone(),
+            };
+            if let Err(e) = recorder.push(&run) {
+                tracing::warn!("{:#}", e);
+            }
+        }
+    }
+}
+
+/// Whether Slurm commands are being recorded
+pub fn is_recording() -> bool {
+    SESSION
+        .lock()
+        .is_ok_and(|s| matches!(s.as_ref(), Some(Session::Record(_))))
+}
+
+/// Whether Slurm commands are served from a recording
+pub fn is_replaying() -> bool {
+    SESSION
+        .lock()
+        .is_ok_and(|s| matches!(s.as_ref(), Some(Session::Replay(_))))
+}
+
+/// While replaying, what the recording holds for a run of `program`, or
+/// an error when it holds none; `None` when not replaying
+pub fn replayed(program: &str, args: &[String]) -> Option<io::Result<Output>> {
+    let mut session = SESSION.lock().ok()?;
+    let Some(Session::Replay(replay)) = session.as_mut() else {
+        return None;
+    };
+    Some(match replay.next(program, args) {
+        Some(run) => Ok(run.output()),
+        None => {
+            tracing::warn!(program, ?args, "command not in the recording");
+            Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} {} is not in the recording", program, args.join(" ")),
+            ))
+        }
+    })
+}
+
+/// The two empty blocks that end a tar archive
+const TAR_END: [u8; 2 * BLOCK] = [0; 2 * BLOCK];
+
+/// A ustar archive entry of the file `name` with `data`, padded to blocks
+fn tar_entry(name: &str, data: &[u8]) -> Vec<u8> {
+    let mtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let mut header = [0u8; BLOCK];
+    let mut field = |offset: usize, value: &[u8]| {
+        header[offset..offset + value.len()].copy_from_slice(value);
+    };
+    field(0, &name.as_bytes()[..name.len().min(99)]);
+    field(100, b"0000644\0");
+    field(108, b"0000000\0");
+    field(116, b"0000000\0");
+    field(124, format!("{:011o}\0", data.len()).as_bytes());
+    field(136, format!("{:011o}\0", mtime).as_bytes());
+    field(148, b"        ");
+    field(156, b"0");
+    field(257, b"ustar\0");
+    field(263, b"00");
+    let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    let mut out = header.to_vec();
+    out.extend_from_slice(data);
+    out.resize(out.len().div_ceil(BLOCK) * BLOCK, 0);
+    out
+}
+
+/// The files of a tar archive, in order
+fn read_tar(raw: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::new();
+    let mut offset = 0;
+    while offset + BLOCK <= raw.len() {
+        let header = &raw[offset..offset + BLOCK];
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let text = |range: std::ops::Range<usize>| {
+            let field = &header[range];
+            let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+            String::from_utf8_lossy(&field[..end]).trim().to_string()
+        };
+        let size = usize::from_str_radix(&text(124..136), 8)
+            .map_err(|_| eyre!("not a tar archive (bad size at byte {})", offset))?;
+        let start = offset + BLOCK;
+        // A recording cut short by a crash keeps the entries before
+        let Some(data) = raw.get(start..start + size) else {
+            break;
+        };
+        // Regular files only; directories and links hold no output
+        if matches!(header[156], b'0' | 0) {
+            let name = match text(345..500) {
+                prefix if prefix.is_empty() => text(0..100),
+                prefix => format!("{}/{}", prefix, text(0..100)),
+            };
+            files.push((name, data.to_vec()));
+        }
+        offset = start + size.div_ceil(BLOCK) * BLOCK;
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recordings_are_redacted_archived_and_replayed() {
+        let run = |program: &str, args: &[&str], stdout: &str| RecordedRun {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            code: Some(0),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        };
+        let mut recording = Recording::new("alice".to_string());
+        recording.runs = vec![
+            run(
+                "squeue",
+                &["--user", "alice"],
+                "48213|train|alice|RUNNING\n",
+            ),
+            run(
+                "squeue",
+                &["--user", "alice"],
+                "48213|train|alice|COMPLETING\n",
+            ),
+            run(
+                "sacct",
+                &["-S", "2024-05-01T00:00:00", "-j", "48190"],
+                "48190|bob\n",
+            ),
+        ];
+        recording.runs.push(RecordedRun {
+            code: Some(1),
+            stderr: b"scontrol: error: Invalid job id".to_vec(),
+            ..run("scontrol", &["show", "job", "1"], "")
+        });
+        recording.redact(&BTreeSet::from(["bob".to_string(), "alice".to_string()]));
+        assert_eq!(recording.user, "user1");
+        assert_eq!(recording.runs[0].args, ["--user", "user1"]);
+        assert_eq!(recording.runs[2].stdout, b"48190|user2\n");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.tar");
+        recording.write(&path).unwrap();
+        let read = Recording::read(&path).unwrap();
+        assert_eq!(read, recording);
+
+        // Replayed by carol: her name stands for the recording user
+        let mut replay = Replay::new(read, "carol".to_string());
+        let squeue = ["--user".to_string(), "carol".to_string()];
+        let states: Vec<Vec<u8>> = (0..3)
+            .map(|_| replay.next("squeue", &squeue).unwrap().stdout.clone())
+            .collect();
+        assert_eq!(
+            states,
+            [
+                b"48213|train|user1|RUNNING\n".to_vec(),
+                b"48213|train|user1|COMPLETING\n".to_vec(),
+                b"48213|train|user1|COMPLETING\n".to_vec(),
+            ]
+        );
+        let sacct: Vec<String> = ["-S", "2024-06-30T08:15:00", "-j", "48190"]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        assert!(replay.next("sacct", &sacct).is_some());
+        let failed = replay
+            .next("scontrol", &["show".into(), "job".into(), "1".into()])
+            .unwrap()
+            .output();
+        assert_eq!(failed.status.code(), Some(1));
+        assert!(replay.next("scancel", &["48213".to_string()]).is_none());
+    }
+
+    #[test]
+    fn runs_are_on_disk_as_they_finish_and_redacted_at_the_end() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.tar");
+        let mut recorder = Recorder::create(&path, "alice".to_string(), true).unwrap();
+        let run = |stdout: &str| RecordedRun {
+            program: "squeue".to_string(),
+            args: vec!["--user".to_string(), "alice".to_string()],
+            code: Some(0),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        };
+        recorder.push(&run("1|alice\n")).unwrap();
+        recorder.push(&run("1|alice\n2|bob\n")).unwrap();
+
+        // Readable before it is finished, as after a crash
+        let crashed = Recording::read(&path).unwrap();
+        assert_eq!(crashed.user, "alice");
+        assert_eq!(crashed.runs, [run("1|alice\n"), run("1|alice\n2|bob\n")]);
+
+        recorder.users.as_mut().unwrap().insert("bob".to_string());
+        assert_eq!(recorder.finish().unwrap(), 2);
+        let finished = Recording::read(&path).unwrap();
+        assert_eq!(finished.user, "user1");
+        assert_eq!(finished.runs[1].args, ["--user", "user1"]);
+        assert_eq!(finished.runs[1].stdout, b"1|user1\n2|user2\n");
+    }
+}
@@ -0,0 +1,137 @@
+/// What a reference entry describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topic {
+    State,
+    Reason,
+    ExitCode,
+}
+
+impl Topic {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Topic::State => "state",
+            Topic::Reason => "pending reason",
+            Topic::ExitCode => "exit code",
+        }
+    }
+}
+
+/// One term of the built-in Slurm reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub topic: Topic,
+    pub term: &'static str,
+    pub text: &'static str,
+}
+
+const fn entry(topic: Topic, term: &'static str, text: &'static str) -> Entry {
+    Entry { topic, term, text }
+}
+
+/// Job states, common pending reasons and exit/signal conventions
+pub const ENTRIES: &[Entry] = &[
+    // Job states
+    entry(Topic::State, "PENDING", "Queued and waiting for resources or a scheduling condition; the Reason column says which."),
+    entry(Topic::State, "CONFIGURING", "Resources are allocated and nodes are booting or being set up before the job starts."),
+    entry(Topic::State, "RUNNING", "The job has an allocation and its batch script is executing."),
+    entry(Topic::State, "COMPLETING", "The job finished and Slurm is cleaning up (epilogs, killing leftover processes). Usually brief; long stalls point at a stuck node."),
+    entry(Topic::State, "COMPLETED", "All processes exited with code 0."),
+    entry(Topic::State, "FAILED", "The batch script or a step exited with a non-zero code. Check ExitCode and the stderr log."),
+    entry(Topic::State, "CANCELLED", "Cancelled by a user or administrator (scancel). sacct shows who: `CANCELLED by <uid>`."),
+    entry(Topic::State, "TIMEOUT", "Killed because it reached its time limit (--time)."),
+    entry(Topic::State, "OUT_OF_MEMORY", "A process exceeded the memory requested (--mem / --mem-per-cpu) and was killed by the OOM handler."),
+    entry(Topic::State, "NODE_FAIL", "A node of the allocation failed. Not caused by the job; resubmitting is usually enough."),
+    entry(Topic::State, "BOOT_FAIL", "A node failed to boot for the job. Not caused by the job."),
+    entry(Topic::State, "PREEMPTED", "Stopped to make room for a higher-priority job. Depending on site policy it is requeued, suspended or cancelled."),
+    entry(Topic::State, "SUSPENDED", "Paused: an administrator suspended it, or it was preempted in suspend mode. It keeps its allocation."),
+    entry(Topic::State, "REQUEUED", "Put back into the queue (by scontrol requeue, node failure or preemption) and will run again."),
+    entry(Topic::State, "REQUEUE_HOLD", "Requeued and held; it will not start until released (scontrol release)."),
+    entry(Topic::State, "DEADLINE", "Cancelled because it could not finish before its --deadline."),
+    entry(Topic::State, "REVOKED", "Federation only: the sibling job was revoked because another cluster started it."),
+    entry(Topic::State, "SPECIAL_EXIT", "Exited with a site-defined special exit code and was requeued held."),
+    entry(Topic::State, "STOPPED", "Received SIGSTOP; it keeps its allocation but does not run."),
+    // Pending reasons
+    entry(Topic::Reason, "Priority", "Higher-priority jobs are ahead in the queue for the same resources. Waiting is all you can do; `sprio` shows the factors."),
+    entry(Topic::Reason, "Resources", "This job is next in line, but the requested nodes/CPUs/GPUs/memory are not free yet."),
+    entry(Topic::Reason, "Dependency", "Waiting for the jobs listed in --dependency to reach the required state."),
+    entry(Topic::Reason, "DependencyNeverSatisfied", "A dependency can no longer be satisfied (e.g. afterok on a job that failed). The job will never start; cancel it or fix the dependency."),
+    entry(Topic::Reason, "BeginTime", "The job was submitted with --begin and its start time has not been reached yet."),
+    entry(Topic::Reason, "JobHeldUser", "Held by its owner (scontrol hold). Release it with `scontrol release <id>` or U in slurmer."),
+    entry(Topic::Reason, "JobHeldAdmin", "Held by an administrator. Only an administrator can release it."),
+    entry(Topic::Reason, "ReqNodeNotAvail", "A requested node is down, drained or reserved. Check --nodelist/--exclude or wait for maintenance to end."),
+    entry(Topic::Reason, "Reservation", "Waiting for its advanced reservation to become active."),
+    entry(Topic::Reason, "PartitionTimeLimit", "--time is above the partition's maximum; the job will never start there. Lower the limit or pick another partition."),
+    entry(Topic::Reason, "PartitionNodeLimit", "The node count is outside the partition's limits."),
+    entry(Topic::Reason, "PartitionDown", "The partition is down; jobs stay queued until it comes back up."),
+    entry(Topic::Reason, "NodeDown", "A node required by the job is down."),
+    entry(Topic::Reason, "BadConstraints", "The --constraint/--gres combination cannot be satisfied by any node."),
+    entry(Topic::Reason, "Licenses", "Waiting for licenses requested with --licenses."),
+    entry(Topic::Reason, "InvalidAccount", "The account does not exist or you are not a member of it. Check --account with `sacctmgr show assoc user=$USER`."),
+    entry(Topic::Reason, "InvalidQOS", "The QOS does not exist or your association may not use it."),
+    entry(Topic::Reason, "AssocMaxJobsLimit", "Your association already has its maximum number of running jobs."),
+    entry(Topic::Reason, "AssocGrpCpuLimit", "Your account's group CPU limit is in use by running jobs; waits until some finish."),
+    entry(Topic::Reason, "AssocGrpGRES", "Your account's group GPU/GRES limit is in use by running jobs."),
+    entry(Topic::Reason, "QOSMaxJobsPerUserLimit", "You already run the maximum number of jobs allowed by the QOS."),
+    entry(Topic::Reason, "QOSMaxWallDurationPerJobLimit", "--time exceeds the QOS wall-time limit; the job will never start with this QOS."),
+    entry(Topic::Reason, "QOSGrpCpuLimit", "The QOS group CPU limit is reached by running jobs."),
+    entry(Topic::Reason, "launch failed requeued held", "Launching failed (often a node problem), so the job was requeued and held. Release it to retry."),
+    entry(Topic::Reason, "None", "No reason recorded yet, typically right after submission before the scheduler has looked at it."),
+    // Exit codes and signals
+    entry(Topic::ExitCode, "0:0", "Slurm prints ExitCode as <exit status>:<signal>. 0:0 means a clean exit."),
+    entry(Topic::ExitCode, "1:0", "Generic failure reported by the script or program itself."),
+    entry(Topic::ExitCode, "2:0", "Misuse of a shell builtin or bad arguments, by convention."),
+    entry(Topic::ExitCode, "126", "Command found but not executable (permissions, wrong architecture)."),
+    entry(Topic::ExitCode, "127", "Command not found: check PATH and loaded modules."),
+    entry(Topic::ExitCode, "128+N", "A process killed by signal N is reported by the shell as exit status 128+N."),
+    entry(Topic::ExitCode, "0:9", "Killed by SIGKILL (9): usually the time limit or memory limit enforcement (also shows as 137)."),
+    entry(Topic::ExitCode, "0:15", "Terminated by SIGTERM (15): scancel, or the grace period before a time-limit kill (also shows as 143)."),
+    entry(Topic::ExitCode, "0:6", "SIGABRT (6): the program aborted itself, e.g. a failed assertion (also shows as 134)."),
+    entry(Topic::ExitCode, "0:11", "SIGSEGV (11): segmentation fault, a crash in the program (also shows as 139)."),
+];
+
+/// Entries matching `query` (case-insensitive), exact term matches first,
+/// then term prefix matches, then matches anywhere in the description.
+pub fn search(query: &str) -> Vec<&'static Entry> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return ENTRIES.iter().collect();
+    }
+
+    let mut ranked: Vec<(u8, &'static Entry)> = ENTRIES
+        .iter()
+        .filter_map(|e| {
+            let term = e.term.to_lowercase();
+            let rank = if term == query {
+                0
+            } else if term.starts_with(&query) {
+                1
+            } else if term.contains(&query) {
+                2
+            } else if e.text.to_lowercase().contains(&query) {
+                3
+            } else {
+                return None;
+            };
+            Some((rank, e))
+        })
+        .collect();
+    // Stable sort keeps the curated order within each rank
+    ranked.sort_by_key(|(rank, _)| *rank);
+    ranked.into_iter().map(|(_, e)| e).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_ranks_exact_term_first() {
+        let results = search("dependency");
+        assert_eq!(results[0].term, "Dependency");
+        assert_eq!(results[1].term, "DependencyNeverSatisfied");
+
+        assert_eq!(search("begintime")[0].term, "BeginTime");
+        assert_eq!(search("").len(), ENTRIES.len());
+        assert!(search("no such thing").is_empty());
+    }
+}
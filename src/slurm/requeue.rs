@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use color_eyre::Result;
+
+use super::cache;
+use super::error::checked;
+use super::sacct::{format_arg, parse_sacct_output, SACCT_DELIMITER};
+use super::{Job, JobState};
+
+/// How far back attempts are looked up
+pub const CHAIN_WINDOW_HOURS: u32 = 7 * 24;
+
+/// Fields of each attempt; `-D` makes sacct list every requeued run
+const ATTEMPT_FIELDS: &[&str] = &[
+    "JobIDRaw", "JobName", "User", "QOS", "State", "Submit", "Start", "End", "Elapsed",
+];
+
+/// Runs of the same logical work: the requeues of one job id, and new jobs
+/// of the same name submitted after a run was preempted
+#[derive(Debug, Clone)]
+pub struct AttemptChain {
+    /// Every run, oldest first
+    pub attempts: Vec<Job>,
+}
+
+impl AttemptChain {
+    pub fn first(&self) -> &Job {
+        &self.attempts[0]
+    }
+
+    pub fn last(&self) -> &Job {
+        &self.attempts[self.attempts.len() - 1]
+    }
+
+    /// Time run over all attempts
+    pub fn runtime(&self) -> Duration {
+        self.attempts.iter().map(|a| a.time).sum()
+    }
+
+    /// Time run by attempts that were preempted or requeued, whose work is
+    /// lost unless the job checkpoints
+    pub fn wasted(&self) -> Duration {
+        self.attempts
+            .iter()
+            .filter(|a| is_interrupted(a.state))
+            .map(|a| a.time)
+            .sum()
+    }
+
+    /// Whether the work was interrupted at least once
+    pub fn was_interrupted(&self) -> bool {
+        self.attempts.iter().any(|a| is_interrupted(a.state))
+    }
+}
+
+/// Totals of the chains of one QOS, to weigh a preemptible QOS's faster
+/// starts against the runtime it throws away
+#[derive(Debug, Clone, PartialEq)]
+pub struct QosSummary {
+    pub qos: String,
+    pub chains: usize,
+    pub interrupted: usize,
+    pub runtime: Duration,
+    pub wasted: Duration,
+}
+
+impl QosSummary {
+    pub fn wasted_fraction(&self) -> Option<f64> {
+        let runtime = self.runtime.as_secs_f64();
+        (runtime > 0.0).then(|| self.wasted.as_secs_f64() / runtime)
+    }
+}
+
+fn is_interrupted(state: JobState) -> bool {
+    matches!(state, JobState::Preempted | JobState::Requeued)
+}
+
+/// Every attempt of the user's jobs of the last `hours`
+pub async fn fetch_attempts(user: Option<&str>, hours: u32) -> Result<Vec<Job>> {
+    let mut args = vec![
+        "-n".to_string(),
+        "-P".to_string(),
+        format!("--delimiter={}", SACCT_DELIMITER),
+        "-X".to_string(),
+        "-D".to_string(),
+        "-S".to_string(),
+        format!("now-{}hours", hours.max(1)),
+        "-E".to_string(),
+        "now".to_string(),
+        "--format".to_string(),
+        format_arg(ATTEMPT_FIELDS),
+    ];
+    if let Some(user) = user {
+        args.extend(["--user".to_string(), user.to_string()]);
+    }
+    let output = checked("sacct", cache::query("sacct", &args).await)?;
+    parse_sacct_output(&String::from_utf8_lossy(&output.stdout), ATTEMPT_FIELDS)
+}
+
+/// Link attempts into chains, most wasted runtime first. A job continues the
+/// chain of a preempted job of the same user and name when it was submitted
+/// after that job ended.
+pub fn attempt_chains(mut attempts: Vec<Job>) -> Vec<AttemptChain> {
+    attempts.sort_by_key(|a| (a.submit_time, a.start_time));
+
+    let mut chains: Vec<AttemptChain> = Vec::new();
+    let mut by_id: HashMap<String, usize> = HashMap::new();
+    // Chains whose latest run was preempted, by user and job name
+    let mut preempted: HashMap<(String, String), usize> = HashMap::new();
+    for attempt in attempts {
+        let key = (attempt.user.clone(), attempt.name.clone());
+        let resumes = preempted.get(&key).copied().filter(|&i| {
+            let ended = chains[i].last().end_time;
+            matches!((ended, attempt.submit_time), (Some(e), Some(s)) if s >= e)
+        });
+        let index = match by_id.get(&attempt.id).copied().or(resumes) {
+            Some(i) => {
+                chains[i].attempts.push(attempt);
+                i
+            }
+            None => {
+                chains.push(AttemptChain {
+                    attempts: vec![attempt],
+                });
+                chains.len() - 1
+            }
+        };
+        let last = chains[index].last();
+        by_id.insert(last.id.clone(), index);
+        if last.state == JobState::Preempted {
+            preempted.insert(key, index);
+        } else {
+            preempted.remove(&key);
+        }
+    }
+
+    chains.sort_by(|a, b| {
+        b.wasted()
+            .cmp(&a.wasted())
+            .then_with(|| b.attempts.len().cmp(&a.attempts.len()))
+    });
+    chains
+}
+
+/// Totals per QOS (of the first attempt), largest runtime first
+pub fn qos_summary(chains: &[AttemptChain]) -> Vec<QosSummary> {
+    let mut summaries: Vec<QosSummary> = Vec::new();
+    for chain in chains {
+        let qos = &chain.first().qos;
+        let index = match summaries.iter().position(|s| &s.qos == qos) {
+            Some(i) => i,
+            None => {
+                summaries.push(QosSummary {
+                    qos: qos.clone(),
+                    chains: 0,
+                    interrupted: 0,
+                    runtime: Duration::ZERO,
+                    wasted: Duration::ZERO,
+                });
+                summaries.len() - 1
+            }
+        };
+        let summary = &mut summaries[index];
+        summary.chains += 1;
+        summary.interrupted += usize::from(chain.was_interrupted());
+        summary.runtime += chain.runtime();
+        summary.wasted += chain.wasted();
+    }
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.runtime));
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_requeues_and_resubmissions() {
+        let d = SACCT_DELIMITER;
+        let rows = [
+            "10|train|10|ann|preempt|PREEMPTED|2024-05-01T08:00:00|2024-05-01T08:05:00|2024-05-01T10:05:00|02:00:00",
+            "10|train|10|ann|preempt|REQUEUED|2024-05-01T10:06:00|2024-05-01T10:10:00|2024-05-01T10:40:00|00:30:00",
+            "10|train|10|ann|preempt|PREEMPTED|2024-05-01T10:41:00|2024-05-01T11:00:00|2024-05-01T12:00:00|01:00:00",
+            "20|train|20|ann|preempt|COMPLETED|2024-05-01T12:30:00|2024-05-01T12:31:00|2024-05-01T15:31:00|03:00:00",
+            "30|eval|30|ann|normal|COMPLETED|2024-05-01T09:00:00|2024-05-01T09:30:00|2024-05-01T10:00:00|00:30:00",
+        ];
+        let stdout = rows.join("\n").replace('|', &d.to_string());
+        let attempts = parse_sacct_output(&stdout, ATTEMPT_FIELDS).unwrap();
+        let chains = attempt_chains(attempts);
+
+        assert_eq!(chains.len(), 2);
+        let train = &chains[0];
+        let ids: Vec<&str> = train.attempts.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["10", "10", "10", "20"]);
+        assert_eq!(train.runtime(), Duration::from_secs(6 * 3600 + 1800));
+        assert_eq!(train.wasted(), Duration::from_secs(3 * 3600 + 1800));
+        assert_eq!(train.last().state, JobState::Completed);
+
+        let summary = qos_summary(&chains);
+        assert_eq!(
+            (summary[0].qos.as_str(), summary[0].interrupted),
+            ("preempt", 1)
+        );
+        assert_eq!(summary[0].wasted_fraction(), Some(3.5 / 6.5));
+        assert_eq!(summary[1].wasted, Duration::ZERO);
+    }
+}
@@ -0,0 +1,222 @@
+use chrono::{DateTime, Local, TimeDelta};
+use color_eyre::Result;
+
+use super::error::checked;
+use super::{command, hostlist, parse_scontrol_output, Job, JobState};
+use crate::utils::parse_slurm_time;
+
+/// An advanced reservation, as `scontrol show reservation` prints it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reservation {
+    pub name: String,
+    /// `ACTIVE` or `INACTIVE` (not started yet)
+    pub state: String,
+    pub start_time: Option<DateTime<Local>>,
+    pub end_time: Option<DateTime<Local>>,
+    /// Compressed node list (`gpu[01-04]`), `ALL` for the whole cluster
+    pub nodes: String,
+    pub node_count: u32,
+    /// Empty when it is not tied to a partition
+    pub partition: String,
+    pub users: String,
+    pub accounts: String,
+    /// e.g. `MAINT,IGNORE_JOBS,SPEC_NODES`
+    pub flags: String,
+    /// Partitions holding some of its nodes, `None` when the partitions
+    /// could not be read
+    pub node_partitions: Option<Vec<String>>,
+}
+
+impl Reservation {
+    pub fn is_active(&self, now: DateTime<Local>) -> bool {
+        self.state == "ACTIVE" || self.start_time.is_some_and(|s| s <= now)
+    }
+
+    /// Whether it is a maintenance window rather than a block of nodes for
+    /// some users
+    pub fn is_maintenance(&self) -> bool {
+        self.flags.split(',').any(|f| f == "MAINT")
+    }
+
+    /// Whether some node `job` may run on is reserved: one of its
+    /// partitions (a pending job may list several) holds a reserved node
+    fn covers_partition(&self, job: &Job) -> bool {
+        if self.nodes == "ALL" {
+            return true;
+        }
+        let mut partitions = job.partition.split(',');
+        match &self.node_partitions {
+            Some(reserved) => partitions.any(|p| reserved.iter().any(|r| r == p)),
+            // Without the partitions' nodes, only its own partition is known
+            None => partitions.any(|p| p == self.partition),
+        }
+    }
+
+    /// Whether `job`, pending, cannot start on the reserved nodes before the
+    /// reservation takes them: started now, it would still be running when
+    /// the reservation begins
+    pub fn blocks(&self, job: &Job, now: DateTime<Local>) -> bool {
+        if job.state != JobState::Pending
+            || job.reservation.as_deref() == Some(self.name.as_str())
+            || !self.covers_partition(job)
+        {
+            return false;
+        }
+        let (Some(start), Some(limit)) = (self.start_time, job.time_limit) else {
+            return false;
+        };
+        start > now && TimeDelta::from_std(limit).is_ok_and(|limit| now + limit > start)
+    }
+}
+
+/// Parse `scontrol -o show reservation`: one reservation per line
+pub fn parse_reservations(stdout: &str) -> Vec<Reservation> {
+    stdout
+        .lines()
+        .filter(|line| line.trim_start().starts_with("ReservationName="))
+        .map(|line| {
+            let fields = parse_scontrol_output(line);
+            let field = |key: &str| {
+                fields
+                    .get(key)
+                    .filter(|v| *v != "(null)")
+                    .cloned()
+                    .unwrap_or_default()
+            };
+            Reservation {
+                name: field("ReservationName"),
+                state: field("State"),
+                start_time: parse_slurm_time(&field("StartTime")),
+                end_time: parse_slurm_time(&field("EndTime")),
+                nodes: field("Nodes"),
+                node_count: field("NodeCnt").parse().unwrap_or(0),
+                partition: field("PartitionName"),
+                users: field("Users"),
+                accounts: field("Accounts"),
+                flags: field("Flags"),
+                node_partitions: None,
+            }
+        })
+        .collect()
+}
+
+/// Parse `scontrol -o show partition` into each partition's nodes
+pub fn parse_partition_nodes(stdout: &str) -> Vec<(String, Vec<String>)> {
+    stdout
+        .lines()
+        .filter(|line| line.trim_start().starts_with("PartitionName="))
+        .map(|line| {
+            let fields = parse_scontrol_output(line);
+            let nodes = fields
+                .get("Nodes")
+                .filter(|v| *v != "(null)")
+                .map(|v| hostlist::expand(v))
+                .unwrap_or_default();
+            (
+                fields.get("PartitionName").cloned().unwrap_or_default(),
+                nodes,
+            )
+        })
+        .collect()
+}
+
+/// Fill in which of `partitions` hold nodes of each reservation
+pub fn link_partitions(reservations: &mut [Reservation], partitions: &[(String, Vec<String>)]) {
+    for reservation in reservations {
+        let reserved = hostlist::expand(&reservation.nodes);
+        reservation.node_partitions = Some(
+            partitions
+                .iter()
+                .filter(|(_, nodes)| nodes.iter().any(|n| reserved.contains(n)))
+                .map(|(name, _)| name.clone())
+                .collect(),
+        );
+    }
+}
+
+/// The cluster's reservations, soonest first
+pub async fn fetch_reservations() -> Result<Vec<Reservation>> {
+    let (output, partitions) = futures_lite::future::zip(
+        command::output("scontrol", ["-o", "show", "reservation"]),
+        command::output("scontrol", ["-o", "show", "partition"]),
+    )
+    .await;
+    let output = checked("scontrol", output)?;
+    let mut reservations = parse_reservations(&String::from_utf8_lossy(&output.stdout));
+    reservations.sort_by_key(|r| r.start_time);
+    if let Some(partitions) = partitions.ok().filter(|o| o.status.success()) {
+        let partitions = parse_partition_nodes(&String::from_utf8_lossy(&partitions.stdout));
+        link_partitions(&mut reservations, &partitions);
+    }
+    Ok(reservations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn parses_reservations_and_finds_blocked_jobs() {
+        let stdout = "\
+ReservationName=maint StartTime=2026-10-15T08:00:00 EndTime=2026-10-15T20:00:00 Duration=12:00:00 Nodes=gpu[01-04] NodeCnt=4 CoreCnt=256 Features=(null) PartitionName=gpu Flags=MAINT,SPEC_NODES TRES=cpu=256 Users=root Groups=(null) Accounts=(null) Licenses=(null) State=INACTIVE BurstBuffer=(null) Watts=n/a MaxStartDelay=(null)
+ReservationName=course StartTime=2026-10-14T09:00:00 EndTime=2026-10-21T09:00:00 Duration=7-00:00:00 Nodes=cpu[10-11] NodeCnt=2 CoreCnt=64 Features=(null) PartitionName=(null) Flags=IGNORE_JOBS TRES=cpu=64 Users=(null) Groups=(null) Accounts=teaching Licenses=(null) State=ACTIVE BurstBuffer=(null) Watts=n/a MaxStartDelay=(null)
+";
+        let reservations = parse_reservations(stdout);
+        assert_eq!(reservations.len(), 2);
+        let (maint, course) = (&reservations[0], &reservations[1]);
+        assert_eq!(maint.nodes, "gpu[01-04]");
+        assert!(maint.is_maintenance());
+        assert_eq!(course.partition, "");
+        assert_eq!(course.accounts, "teaching");
+
+        let now = parse_slurm_time("2026-10-14T20:00:00").unwrap();
+        assert!(!maint.is_active(now));
+        assert!(course.is_active(now));
+        let pending = |limit_hours: u64| Job {
+            state: JobState::Pending,
+            partition: "gpu,cpu".to_string(),
+            time_limit: Some(Duration::from_secs(limit_hours * 3600)),
+            ..Default::default()
+        };
+        // Twelve hours until the window: a 1-day job cannot fit before it
+        assert!(maint.blocks(&pending(24), now));
+        assert!(!maint.blocks(&pending(4), now));
+        let inside = Job {
+            reservation: Some("maint".to_string()),
+            ..pending(24)
+        };
+        assert!(!maint.blocks(&inside, now));
+    }
+
+    #[test]
+    fn reservations_without_a_partition_cover_the_partitions_of_their_nodes() {
+        let partitions = parse_partition_nodes(
+            "PartitionName=cpu AllowGroups=ALL Nodes=cpu[01-11] State=UP\n\
+             PartitionName=gpu AllowGroups=ALL Nodes=gpu[01-04] State=UP\n\
+             PartitionName=debug AllowGroups=ALL Nodes=(null) State=UP\n",
+        );
+        assert_eq!(partitions[0].1.len(), 11);
+        let mut reservations = vec![Reservation {
+            nodes: "cpu[10-11]".to_string(),
+            start_time: parse_slurm_time("2026-10-15T08:00:00"),
+            ..Default::default()
+        }];
+        let now = parse_slurm_time("2026-10-14T20:00:00").unwrap();
+        let pending = |partition: &str| Job {
+            state: JobState::Pending,
+            partition: partition.to_string(),
+            time_limit: Some(Duration::from_secs(24 * 3600)),
+            ..Default::default()
+        };
+        // Not knowing the partitions' nodes, it covers no partition
+        assert!(!reservations[0].blocks(&pending("gpu"), now));
+        link_partitions(&mut reservations, &partitions);
+        assert_eq!(
+            reservations[0].node_partitions,
+            Some(vec!["cpu".to_string()])
+        );
+        assert!(reservations[0].blocks(&pending("gpu,cpu"), now));
+        assert!(!reservations[0].blocks(&pending("gpu"), now));
+    }
+}
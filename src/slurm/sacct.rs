@@ -1,14 +1,39 @@
-use async_process::Command;
+use async_process::{Child, Command, Output, Stdio};
 use color_eyre::eyre::eyre;
 use color_eyre::Result;
-use std::collections::HashSet;
+use futures_lite::future;
+use futures_lite::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, BufReader};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 
-use super::{Job, JobState};
+use super::error::{checked, SlurmError};
+use super::{cache, command, recording};
+use super::{memory::Memory, Job, JobState};
+use crate::utils::{parse_slurm_duration, parse_slurm_time};
+
+/// Field separator passed to `sacct --delimiter`: the ASCII unit separator,
+/// which unlike `|` does not turn up in job names or paths
+pub const SACCT_DELIMITER: char = '\x1f';
+
+/// Fields holding free text, which may contain newlines or the delimiter.
+/// `columns()` asks for the job id again after them, marking where they end.
+const FREE_TEXT_FIELDS: &[&str] = &[
+    "JobName",
+    "WorkDir",
+    "Comment",
+    "AdminComment",
+    "SystemComment",
+    "SubmitLine",
+    "Constraints",
+    "Reason",
+];
 
 /// Options for querying recent-ended jobs from Slurm accounting (`sacct`).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SacctOptions {
-    /// Limit to this user (recommended to match the default `squeue` behavior).
+    /// Limit to this user (recommended to match the default `squeue` behavior);
+    /// `None` lists every user's jobs.
     pub user: Option<String>,
     /// Terminal states to include.
     pub states: Vec<JobState>,
@@ -16,35 +41,72 @@ pub struct SacctOptions {
     pub partitions: Vec<String>,
     /// Limit to these QoS values (optional).
     pub qos: Vec<String>,
+    /// Limit to these accounts (optional).
+    pub accounts: Vec<String>,
+    /// Limit to these WCKeys (optional).
+    pub wckeys: Vec<String>,
+    /// Job name pattern, as [`name_regex`] reads it (optional). Exact names
+    /// (`^sweep_a$`, `^(sweep_a|sweep_b)$`) are passed as `--name`; other
+    /// patterns are matched on the rows as they are read.
+    pub name: Option<String>,
     /// Look back window in hours.
     pub recent_hours: u32,
+    /// Hours before now the look back window ends at (0 is now).
+    pub end_hours: u32,
     /// Which sacct fields to request, in order.
     pub format_fields: Vec<&'static str>,
+    /// Stop reading after this many jobs (optional).
+    pub max_rows: Option<usize>,
+    /// Split the look back window into queries of this many hours (optional).
+    pub window_hours: Option<u32>,
 }
 
 impl SacctOptions {
-    pub fn to_args(&self) -> Vec<String> {
+    /// The queries covering the look back window, as `(start, end)` hours
+    /// before now, newest first. Queried in this order, each one overlaps
+    /// the next a little as `now` moves on, so no job falls between them.
+    pub fn windows(&self) -> Vec<(u32, u32)> {
+        let hours = self.recent_hours.max(self.end_hours + 1);
+        let span = hours - self.end_hours;
+        let step = self
+            .window_hours
+            .filter(|&h| h > 0 && h < span)
+            .unwrap_or(span);
+        (self.end_hours..hours)
+            .step_by(step as usize)
+            .map(|end| ((end + step).min(hours), end))
+            .collect()
+    }
+
+    /// Arguments of the query for one of the `windows()`
+    pub fn to_args(&self, (start, end): (u32, u32)) -> Vec<String> {
         let mut args = Vec::new();
 
         // Output format and shape.
         args.push("-n".to_string()); // no header
-        args.push("-P".to_string()); // parsable2, '|' delimited
+        args.push("-P".to_string()); // parsable2
+        args.push(format!("--delimiter={}", SACCT_DELIMITER));
         args.push("-X".to_string()); // allocations only (avoid job steps)
 
         // Time window.
         // We want jobs that were in the selected states during the window.
         // We'll still optionally filter by end-time in-app if needed later.
         args.push("-S".to_string());
-        args.push(format!("now-{}hours", self.recent_hours.max(1)));
+        args.push(format!("now-{}hours", start.max(1)));
         args.push("-E".to_string());
-        args.push("now".to_string());
+        args.push(match end {
+            0 => "now".to_string(),
+            end => format!("now-{}hours", end),
+        });
 
-        // Filters.
-        if let Some(user) = &self.user {
-            if !user.is_empty() {
+        // Filters. Without --user sacct reports only the invoker's jobs, so
+        // no user means asking for everyone's.
+        match self.user.as_deref().filter(|user| !user.is_empty()) {
+            Some(user) => {
                 args.push("--user".to_string());
-                args.push(user.clone());
+                args.push(user.to_string());
             }
+            None => args.push("--allusers".to_string()),
         }
 
         if !self.partitions.is_empty() {
@@ -57,6 +119,21 @@ impl SacctOptions {
             args.push(self.qos.join(","));
         }
 
+        if !self.accounts.is_empty() {
+            args.push("--accounts".to_string());
+            args.push(self.accounts.join(","));
+        }
+
+        if !self.wckeys.is_empty() {
+            args.push("--wckeys".to_string());
+            args.push(self.wckeys.join(","));
+        }
+
+        if let Some(names) = self.name.as_deref().and_then(exact_names) {
+            args.push("--name".to_string());
+            args.push(names.join(","));
+        }
+
         if !self.states.is_empty() {
             let states = self
                 .states
@@ -68,46 +145,365 @@ impl SacctOptions {
             args.push(states);
         }
 
-        // Format fields.
-        let mut unique = HashSet::new();
-        let mut fields = Vec::new();
-        for f in &self.format_fields {
-            if unique.insert(*f) {
-                fields.push(*f);
+        args.push("--format".to_string());
+        args.push(format_arg(&unique_fields(&self.format_fields)));
+
+        args
+    }
+}
+
+/// Jobs read from `sacct`, possibly cut short at `SacctOptions::max_rows`
+#[derive(Debug, Clone, Default)]
+pub struct SacctRows {
+    pub jobs: Vec<Job>,
+    /// Whether sacct had more rows than were read
+    pub truncated: bool,
+}
+
+impl SacctRows {
+    /// Keep `job` unless one of its id was kept already; once `max_rows`
+    /// are kept, the rows are truncated and `false` is returned
+    fn push(&mut self, job: Job, seen: &mut HashSet<String>, max_rows: usize) -> bool {
+        if seen.contains(&job.id) {
+            return true;
+        }
+        if self.jobs.len() == max_rows {
+            self.truncated = true;
+            return false;
+        }
+        seen.insert(job.id.clone());
+        self.jobs.push(job);
+        true
+    }
+}
+
+/// Run `sacct` and parse its output into `Job` rows line by line as it is
+/// read, so tens of thousands of jobs are never held as one string. A long
+/// look back is queried one window after the other, dropping jobs already
+/// read from a newer window. Past `max_rows` jobs, reading stops and sacct is
+/// killed.
+pub async fn run_sacct(options: &SacctOptions) -> Result<SacctRows> {
+    let fields = unique_fields(&options.format_fields);
+    let mut rows = SacctRows::default();
+    let mut seen = HashSet::new();
+    for window in options.windows() {
+        let started = Instant::now();
+        let read = read_window(options, window, &fields, &mut rows, &mut seen);
+        command::with_timeout("sacct", read).await.map_err(|e| {
+            match e.downcast::<std::io::Error>() {
+                Ok(e) => {
+                    let args = options.to_args(window);
+                    command::log_run("sacct", &args, started.elapsed(), Err(&e));
+                    SlurmError::from_io("sacct", &e).into()
+                }
+                Err(e) => e,
             }
+        })?;
+        if rows.truncated {
+            break;
         }
-        if fields.is_empty() {
-            // Keep this explicit to avoid surprising default output shapes.
-            fields = vec!["JobIDRaw", "JobName", "User", "State", "Elapsed", "NodeList", "AllocCPUS"];
+    }
+    Ok(rows)
+}
+
+/// A job name pattern. One without glob metacharacters (`*`, `?`, `[...]`)
+/// is a regex matching anywhere in the name, so `lr` finds `sweep_lr0.1`; a
+/// glob (`sweep_lr*`, `run[0-3]`: letters, digits, `_`, `-` and the
+/// metacharacters) matches whole names
+pub fn name_regex(pattern: &str) -> std::result::Result<Regex, regex::Error> {
+    let glob_char =
+        |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '*' | '?' | '[' | ']' | '!');
+    if pattern.contains(['*', '?', '[']) && pattern.chars().all(glob_char) {
+        let body = pattern
+            .replace('*', ".*")
+            .replace('?', ".")
+            .replace("[!", "[^");
+        if let Ok(glob) = Regex::new(&format!("^{}$", body)) {
+            return Ok(glob);
         }
+    }
+    Regex::new(pattern)
+}
 
-        args.push("--format".to_string());
-        args.push(fields.join(","));
+/// The names a pattern matches when it matches exact names only
+/// (`^train$`, `^(train|eval)$`), which `sacct --name` filters on
+fn exact_names(pattern: &str) -> Option<Vec<String>> {
+    let body = pattern.strip_prefix('^')?.strip_suffix('$')?;
+    let body = match body.strip_prefix('(') {
+        Some(group) => group.strip_suffix(')')?,
+        None => body,
+    };
+    let names: Vec<String> = body.split('|').map(str::to_string).collect();
+    let name_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-');
+    names
+        .iter()
+        .all(|n| !n.is_empty() && n.chars().all(name_char))
+        .then_some(names)
+}
 
-        args
+async fn read_window(
+    options: &SacctOptions,
+    window: (u32, u32),
+    fields: &[&str],
+    rows: &mut SacctRows,
+    seen: &mut HashSet<String>,
+) -> Result<()> {
+    let max_rows = options.max_rows.unwrap_or(usize::MAX);
+    let name = options.name.as_deref().and_then(|p| name_regex(p).ok());
+    let mut add = |record: Vec<String>| -> bool {
+        let Some(job) = job_from_record(fields, &record) else {
+            return true;
+        };
+        if name.as_ref().is_some_and(|re| !re.is_match(&job.name)) {
+            return true;
+        }
+        rows.push(job, seen, max_rows)
+    };
+
+    let args = options.to_args(window);
+    if let Some(replayed) = recording::replayed("sacct", &args) {
+        let output = replayed.map_err(|e| SlurmError::from_io("sacct", &e))?;
+        let complete = read_records(&output.stdout[..], fields, &mut add, None).await?;
+        if complete && !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(SlurmError::from_stderr("sacct", &stderr).into());
+        }
+        return Ok(());
+    }
+
+    let started = Instant::now();
+    let mut child = Command::new("sacct")
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            command::log_run("sacct", &args, started.elapsed(), Err(&e));
+            SlurmError::from_io("sacct", &e)
+        })?;
+    let mut captured = recording::is_recording().then(Vec::new);
+    let (complete, stderr) = read_child(&mut child, fields, &mut add, captured.as_mut()).await?;
+    if !complete {
+        let status = child.status().await;
+        if let (Some(stdout), Ok(status)) = (captured, status) {
+            let output = Output {
+                status,
+                stdout,
+                stderr: Vec::new(),
+            };
+            recording::record("sacct", &args, &output);
+        }
+        return Ok(());
     }
+
+    let status = child.status().await?;
+    let stderr = String::from_utf8_lossy(&stderr).into_owned();
+    command::log_run(
+        "sacct",
+        &args,
+        started.elapsed(),
+        Ok((&status, stderr.as_bytes())),
+    );
+    if let Some(stdout) = captured {
+        let output = Output {
+            status,
+            stdout,
+            stderr: stderr.clone().into_bytes(),
+        };
+        recording::record("sacct", &args, &output);
+    }
+    if !status.success() {
+        return Err(SlurmError::from_stderr("sacct", &stderr).into());
+    }
+    Ok(())
 }
 
-/// Run `sacct` and parse its output into `Job` rows.
-pub async fn run_sacct(options: &SacctOptions) -> Result<Vec<Job>> {
-    let args = options.to_args();
-    let output = Command::new("sacct").args(&args).output().await?;
+/// Feed the records a running sacct writes to `add`, killing it once `add`
+/// has enough; returns whether every record was read, with sacct's stderr
+async fn read_child(
+    child: &mut Child,
+    fields: &[&str],
+    add: &mut impl FnMut(Vec<String>) -> bool,
+    captured: Option<&mut Vec<u8>>,
+) -> Result<(bool, Vec<u8>)> {
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| eyre!("sacct has no stdout"))?;
+    let stderr_pipe = child.stderr.take();
+
+    // Both pipes are drained at once: sacct blocks on a full stderr pipe
+    // as much as on a full stdout one
+    let records = async {
+        let complete = read_records(BufReader::new(stdout), fields, add, captured).await;
+        if !matches!(complete, Ok(true)) {
+            // Enough rows, or unreadable ones: sacct need not finish
+            let _ = child.kill();
+        }
+        complete
+    };
+    let errors = async move {
+        let mut stderr = Vec::new();
+        if let Some(mut pipe) = stderr_pipe {
+            let _ = pipe.read_to_end(&mut stderr).await;
+        }
+        stderr
+    };
+    let (complete, stderr) = future::zip(records, errors).await;
+    Ok((complete?, stderr))
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(eyre!("sacct failed: {}", stderr.trim()));
+/// Feed the records of sacct's output to `add` as they are read, until it
+/// returns `false`; returns whether every record was read. The raw output
+/// read is also kept in `captured`, when given.
+async fn read_records(
+    mut reader: impl AsyncBufRead + Unpin,
+    fields: &[&str],
+    add: &mut impl FnMut(Vec<String>) -> bool,
+    mut captured: Option<&mut Vec<u8>>,
+) -> Result<bool> {
+    let mut splitter = RecordSplitter::new(fields);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).await? == 0 {
+            break;
+        }
+        if let Some(captured) = captured.as_mut() {
+            captured.extend_from_slice(&line);
+        }
+        let text = String::from_utf8_lossy(&line);
+        if let Some(record) = splitter.push_line(text.trim_end_matches(['\n', '\r'])) {
+            if !add(record) {
+                return Ok(false);
+            }
+        }
     }
+    for record in splitter.finish() {
+        if !add(record) {
+            break;
+        }
+    }
+    Ok(true)
+}
+
+/// Fields shown in the job detail Overview tab, in display order.
+pub const DETAIL_FIELDS: &[&str] = &[
+    "JobID",
+    "JobName",
+    "User",
+    "Account",
+    "Partition",
+    "QOS",
+    "State",
+    "ExitCode",
+    "DerivedExitCode",
+    "Submit",
+    "Eligible",
+    "Start",
+    "End",
+    "Elapsed",
+    "Timelimit",
+    "TotalCPU",
+    "ConsumedEnergyRaw",
+    "NNodes",
+    "NodeList",
+    "AllocCPUS",
+    "ReqMem",
+    "AllocTRES",
+    "ReqTRES",
+    "WCKey",
+    "WorkDir",
+    "Reason",
+    "Comment",
+    "AdminComment",
+];
+
+/// Fetch the allocation record of a single job as `(field, value)` pairs.
+pub async fn fetch_job_details(job_id: &str) -> Result<Vec<(String, String)>> {
+    let args = vec![
+        "-n".to_string(),
+        "-P".to_string(),
+        format!("--delimiter={}", SACCT_DELIMITER),
+        "-X".to_string(),
+        "-j".to_string(),
+        job_id.to_string(),
+        "--format".to_string(),
+        format_arg(DETAIL_FIELDS),
+    ];
+    let output = checked("sacct", cache::query("sacct", &args).await)?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    parse_sacct_output(&stdout, &options.format_fields)
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let record = split_records(&stdout, DETAIL_FIELDS)
+        .into_iter()
+        .next()
+        .ok_or_else(|| eyre!("no accounting record for job {}", job_id))?;
+
+    Ok(DETAIL_FIELDS
+        .iter()
+        .zip(record)
+        .map(|(field, value)| (field.to_string(), value.trim().to_string()))
+        .collect())
+}
+
+/// Accounting records of specific jobs (any age), with the given fields
+pub async fn fetch_jobs(job_ids: &[String], fields: &[&'static str]) -> Result<Vec<Job>> {
+    let output = cache::query(
+        "sacct",
+        [
+            "-n",
+            "-P",
+            &format!("--delimiter={}", SACCT_DELIMITER),
+            "-X",
+            "-j",
+            &job_ids.join(","),
+            "--format",
+            &format_arg(fields),
+        ],
+    )
+    .await;
+    let output = checked("sacct", output)?;
+
+    parse_sacct_output(&String::from_utf8_lossy(&output.stdout), fields)
+}
+
+/// Accounting state of specific jobs, keyed by `JobID` (array tasks as `<id>_<task>`).
+pub async fn fetch_job_states(job_ids: &[String]) -> Result<HashMap<String, String>> {
+    let output = command::output(
+        "sacct",
+        [
+            "-n",
+            "-P",
+            "-X",
+            "-j",
+            &job_ids.join(","),
+            "--format",
+            "JobID,State",
+        ],
+    )
+    .await;
+    let output = checked("sacct", output)?;
+
+    Ok(super::parse_state_rows(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
 }
 
-fn parse_sacct_output(stdout: &str, format_fields: &[&'static str]) -> Result<Vec<Job>> {
+pub fn parse_sacct_output(stdout: &str, format_fields: &[&'static str]) -> Result<Vec<Job>> {
     if stdout.trim().is_empty() {
         return Ok(Vec::new());
     }
 
-    // Ensure we dedupe fields exactly as `to_args()` does, so indexes match.
+    let fields = unique_fields(format_fields);
+    Ok(split_records(stdout, &fields)
+        .iter()
+        .filter_map(|record| job_from_record(&fields, record))
+        .collect())
+}
+
+/// The requested fields without repeats, or the default ones
+fn unique_fields(format_fields: &[&'static str]) -> Vec<&'static str> {
     let mut unique = HashSet::new();
     let mut fields = Vec::new();
     for f in format_fields {
@@ -116,72 +512,461 @@ fn parse_sacct_output(stdout: &str, format_fields: &[&'static str]) -> Result<Ve
         }
     }
     if fields.is_empty() {
-        fields = vec!["JobIDRaw", "JobName", "User", "State", "Elapsed", "NodeList", "AllocCPUS"];
+        fields = vec![
+            "JobIDRaw",
+            "JobName",
+            "User",
+            "State",
+            "Elapsed",
+            "NodeList",
+            "AllocCPUS",
+        ];
     }
+    fields
+}
 
-    let mut jobs = Vec::new();
-    for line in stdout.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
+/// The job of one record, unless it has no id
+fn job_from_record(fields: &[&str], record: &[String]) -> Option<Job> {
+    let mut job = Job::default();
+    for (field, raw_value) in fields.iter().zip(record) {
+        apply_sacct_field(&mut job, field, raw_value);
+    }
+    if job.id.is_empty() {
+        // What a site's sacct prints that cannot be read is worth seeing
+        tracing::debug!(?record, "sacct record without a job id left out");
+        return None;
+    }
+    Some(job)
+}
+
+/// Split `sacct -P --delimiter` output into records of one value per field.
+fn split_records(stdout: &str, fields: &[&str]) -> Vec<Vec<String>> {
+    let mut splitter = RecordSplitter::new(fields);
+    let mut records: Vec<Vec<String>> = stdout
+        .lines()
+        .filter_map(|line| splitter.push_line(line))
+        .collect();
+    records.extend(splitter.finish());
+    records
+}
+
+/// The value of `--format` to read `fields` with `split_records()`
+pub fn format_arg(fields: &[&str]) -> String {
+    columns(fields).join(",")
+}
+
+/// The columns sacct is asked for to read `fields`: a free-text field but
+/// the last is followed by the job id, which marks where its value ends
+/// when it holds the delimiter. The id is asked for first when it is not.
+fn columns<'a>(fields: &[&'a str]) -> Vec<&'a str> {
+    let mut columns = Vec::new();
+    if id_first(fields) {
+        columns.push("JobIDRaw");
+    }
+    for (i, field) in fields.iter().enumerate() {
+        columns.push(*field);
+        if FREE_TEXT_FIELDS.contains(field) && i + 1 < fields.len() {
+            columns.push("JobIDRaw");
         }
+    }
+    columns
+}
 
-        let parts: Vec<&str> = line.split('|').collect();
-        if parts.is_empty() {
-            continue;
+/// Whether `columns()` puts a job id before `fields`
+fn id_first(fields: &[&str]) -> bool {
+    let ends_marked = fields
+        .iter()
+        .rev()
+        .skip(1)
+        .any(|f| FREE_TEXT_FIELDS.contains(f));
+    ends_marked && fields.first() != Some(&"JobIDRaw")
+}
+
+/// Joins lines of `sacct -P --delimiter` output into records as they are
+/// read. A newline inside a job name cuts a record short, so lines are joined
+/// until they hold a value for every column.
+struct RecordSplitter<'a> {
+    fields: &'a [&'a str],
+    separators: usize,
+    /// Lines of a record that still lacks values
+    pending: Option<String>,
+    /// The last complete record, held back while the next line may still
+    /// continue its last value
+    last: Option<Vec<String>>,
+}
+
+impl<'a> RecordSplitter<'a> {
+    fn new(fields: &'a [&'a str]) -> Self {
+        Self {
+            fields,
+            separators: columns(fields).len().saturating_sub(1),
+            pending: None,
+            last: None,
         }
+    }
 
-        let mut job = Job::default();
+    /// Take the next line; returns the previous record once no later line
+    /// can change it
+    fn push_line(&mut self, line: &str) -> Option<Vec<String>> {
+        let record = match self.pending.take() {
+            // A complete line after a fragment without separators: the
+            // fragment was the end of the previous record's last value
+            Some(head)
+                if count_separators(&head) == 0 && count_separators(line) >= self.separators =>
+            {
+                self.continue_last_value(&head);
+                line.to_string()
+            }
+            Some(head) => format!("{}\n{}", head, line),
+            None if line.trim().is_empty() => return None,
+            None => line.to_string(),
+        };
+        if count_separators(&record) < self.separators {
+            self.pending = Some(record);
+            return None;
+        }
+        self.last.replace(split_record(&record, self.fields))
+    }
 
-        for (idx, raw_value) in parts.iter().enumerate() {
-            if idx >= fields.len() {
-                break;
+    /// The records still held at the end of the output
+    fn finish(mut self) -> Vec<Vec<String>> {
+        let mut records = Vec::new();
+        match self.pending.take() {
+            Some(rest) if count_separators(&rest) == 0 && self.last.is_some() => {
+                self.continue_last_value(&rest);
+                records.extend(self.last);
             }
-            let value = raw_value.trim();
-            if value.is_empty() || value == "Unknown" || value == "N/A" {
-                continue;
+            Some(rest) => {
+                records.extend(self.last);
+                records.push(split_record(&rest, self.fields));
             }
+            None => records.extend(self.last),
+        }
+        records
+    }
 
-            match fields[idx] {
-                "JobIDRaw" | "JobID" => job.id = value.to_string(),
-                "JobName" => job.name = value.to_string(),
-                "User" => job.user = value.to_string(),
-                "State" => job.state = value.parse().unwrap_or(JobState::Other),
-                "Elapsed" => job.time = value.to_string(),
-                "NNodes" => job.nodes = value.parse::<u32>().unwrap_or(0),
-                "NodeList" => job.node = Some(value.to_string()),
-                "AllocCPUS" | "NCPUS" => job.cpus = value.parse::<u32>().unwrap_or(0),
-                "ReqMem" => job.memory = value.to_string(),
-                "Partition" => job.partition = value.to_string(),
-                "QOS" => job.qos = value.to_string(),
-                "Account" => job.account = Some(value.to_string()),
-                "Priority" => job.priority = value.parse::<u32>().ok(),
-                "WorkDir" => job.work_dir = Some(value.to_string()),
-                "Submit" => job.submit_time = Some(value.to_string()),
-                "Start" => job.start_time = Some(value.to_string()),
-                "End" => job.end_time = Some(value.to_string()),
-                "Reason" => job.pending_reason = Some(value.to_string()),
-                _ => {}
+    fn continue_last_value(&mut self, tail: &str) {
+        if let Some(value) = self.last.as_mut().and_then(|r| r.last_mut()) {
+            value.push('\n');
+            value.push_str(tail);
+        }
+    }
+}
+
+fn count_separators(s: &str) -> usize {
+    s.matches(SACCT_DELIMITER).count()
+}
+
+/// The values of one record of `columns(fields)`, one per field. A free-text
+/// value runs up to the job id that follows it, the last value to the end.
+fn split_record(record: &str, fields: &[&str]) -> Vec<String> {
+    let delimiter = SACCT_DELIMITER.to_string();
+    let mut rest = record;
+    let next = |rest: &mut &'_ str| match rest.split_once(SACCT_DELIMITER) {
+        Some((value, after)) => {
+            *rest = after;
+            value.to_string()
+        }
+        None => std::mem::take(rest).to_string(),
+    };
+    let mut id = if id_first(fields) {
+        next(&mut rest)
+    } else {
+        String::new()
+    };
+    let mut values = Vec::with_capacity(fields.len());
+    for (i, field) in fields.iter().enumerate() {
+        let value = if i + 1 == fields.len() {
+            std::mem::take(&mut rest).to_string()
+        } else if FREE_TEXT_FIELDS.contains(field) {
+            let end = format!("{delimiter}{id}{delimiter}");
+            match rest.find(&end) {
+                Some(at) => {
+                    let value = rest[..at].to_string();
+                    rest = &rest[at + end.len()..];
+                    value
+                }
+                None => next(&mut rest),
             }
+        } else {
+            next(&mut rest)
+        };
+        if i == 0 && *field == "JobIDRaw" {
+            id = value.clone();
         }
+        values.push(value);
+    }
+    values
+}
 
-        if job.id.is_empty() {
-            continue;
+/// Store one sacct field value in the matching `Job` field (unknown fields are ignored)
+pub fn apply_sacct_field(job: &mut Job, field: &str, value: &str) {
+    let value = value.trim();
+    if value.is_empty() || value == "Unknown" || value == "N/A" {
+        return;
+    }
+
+    match field {
+        "JobIDRaw" | "JobID" => job.id = value.to_string(),
+        "JobName" => job.name = value.to_string(),
+        "User" => job.user = value.to_string(),
+        "State" => job.state = value.parse().unwrap_or(JobState::Other),
+        "Elapsed" => job.time = parse_slurm_duration(value).unwrap_or_default(),
+        "Timelimit" => job.time_limit = parse_slurm_duration(value),
+        // `MM:SS.mmm`; the milliseconds do not matter here
+        "TotalCPU" => {
+            job.total_cpu = parse_slurm_duration(value.split('.').next().unwrap_or(value))
+        }
+        "NNodes" => job.nodes = value.parse::<u32>().unwrap_or(0),
+        "NodeList" => job.node = Some(value.to_string()),
+        "AllocCPUS" | "NCPUS" => job.cpus = value.parse::<u32>().unwrap_or(0),
+        "ReqMem" => job.memory = Memory::parse(value),
+        "Partition" => job.partition = value.to_string(),
+        "QOS" => job.qos = value.to_string(),
+        "Account" => job.account = Some(value.to_string()),
+        "Priority" => job.priority = value.parse::<u32>().ok(),
+        "WorkDir" => job.work_dir = Some(value.to_string()),
+        "Submit" => job.submit_time = parse_slurm_time(value),
+        "Start" => job.start_time = parse_slurm_time(value),
+        "End" => job.end_time = parse_slurm_time(value),
+        "Eligible" => job.eligible_time = parse_slurm_time(value),
+        "Reason" => job.pending_reason = Some(value.to_string()),
+        "ExitCode" => job.exit_code = Some(value.to_string()),
+        "DerivedExitCode" => job.derived_exit_code = Some(value.to_string()),
+        "MaxRSS" => job.max_rss = Some(value.to_string()),
+        "Reservation" => job.reservation = Some(value.to_string()),
+        "WCKey" => job.wckey = Some(value.to_string()),
+        "Comment" => job.comment = Some(value.to_string()),
+        "AdminComment" => job.admin_comment = Some(value.to_string()),
+        "ConsumedEnergyRaw" => job.energy = super::energy::parse_energy(value),
+        // Allocated resources win over requested ones; ReqTRES only
+        // fills in for jobs that never got an allocation
+        "AllocTRES" => {
+            job.gres = super::tres::gres_from_tres(value);
+            job.gpus = super::tres::gpus_from_tres(value);
+            job.tmp_disk = super::tres::tmp_disk_from_tres(value).or(job.tmp_disk);
+            job.burst_buffer = super::tres::burst_buffer_from_tres(value).or(job.burst_buffer);
+        }
+        "ReqTRES" => {
+            if job.gres.is_none() {
+                job.gres = super::tres::gres_from_tres(value);
+                job.gpus = super::tres::gpus_from_tres(value);
+            }
+            job.tmp_disk = job.tmp_disk.or(super::tres::tmp_disk_from_tres(value));
+            job.burst_buffer = job
+                .burst_buffer
+                .or(super::tres::burst_buffer_from_tres(value));
         }
+        _ => {}
+    }
+}
 
-        jobs.push(job);
+/// Peak `MaxRSS` over the steps of a finished job (the allocation record has none)
+pub async fn fetch_max_rss(job_id: &str) -> Result<Option<String>> {
+    let output = cache::query("sacct", ["-n", "-P", "-j", job_id, "--format", "MaxRSS"]).await;
+    let output = checked("sacct", output)?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .map(str::trim)
+        .filter_map(|value| Some((Memory::parse(value)?.bytes, value)))
+        .max_by_key(|(bytes, _)| *bytes)
+        .map(|(_, value)| value.to_string()))
+}
+
+/// Fields of past runs read by `fetch_past_runs`, in order
+pub const PAST_RUN_FIELDS: &[&str] = &[
+    "JobIDRaw",
+    "JobName",
+    "State",
+    "Elapsed",
+    "Timelimit",
+    "TotalCPU",
+    "AllocCPUS",
+    "NNodes",
+    "ReqMem",
+    "MaxRSS",
+    "NTasks",
+];
+
+/// An ended job with the memory its busiest node held at peak
+#[derive(Debug, Clone, PartialEq)]
+pub struct PastRun {
+    pub job: Job,
+    /// The largest over the steps of `MaxRSS`, the peak of one task, times
+    /// the tasks the step ran on its most loaded node
+    pub node_rss_bytes: Option<u64>,
+}
+
+/// The user's jobs named `name` that ended in the last `days`, with the peak
+/// memory per node of their steps
+pub async fn fetch_past_runs(name: &str, days: u32) -> Result<Vec<PastRun>> {
+    let output = cache::query(
+        "sacct",
+        [
+            "-n",
+            "-P",
+            &format!("--delimiter={}", SACCT_DELIMITER),
+            "-S",
+            &format!("now-{}days", days),
+            "--name",
+            name,
+            "--format",
+            &format_arg(PAST_RUN_FIELDS),
+        ],
+    )
+    .await;
+    let output = checked("sacct", output)?;
+
+    Ok(fold_steps(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Allocation rows of `sacct` output with steps (`123.batch`, `123.0`),
+/// each taking the largest `MaxRSS` of its steps and their peak per node
+fn fold_steps(stdout: &str) -> Vec<PastRun> {
+    let tasks_field = PAST_RUN_FIELDS.iter().position(|f| *f == "NTasks");
+    let mut runs: Vec<PastRun> = Vec::new();
+    for record in split_records(stdout, PAST_RUN_FIELDS) {
+        let Some(row) = job_from_record(PAST_RUN_FIELDS, &record) else {
+            continue;
+        };
+        let Some((id, _step)) = row.id.split_once('.') else {
+            runs.push(PastRun {
+                job: row,
+                node_rss_bytes: None,
+            });
+            continue;
+        };
+        let Some(run) = runs.iter_mut().find(|r| r.job.id == id) else {
+            continue;
+        };
+        let bytes = |rss: &Option<String>| rss.as_deref().and_then(Memory::parse).map(|m| m.bytes);
+        let Some(rss) = bytes(&row.max_rss) else {
+            continue;
+        };
+        // Tasks are spread evenly, so the most loaded node has the rounded-up share
+        let tasks = tasks_field
+            .and_then(|i| record.get(i))
+            .and_then(|t| t.trim().parse::<u64>().ok())
+            .unwrap_or(1)
+            .max(1);
+        let per_node = rss * tasks.div_ceil(u64::from(row.nodes.max(1)));
+        if Some(rss) > bytes(&run.job.max_rss) {
+            run.job.max_rss = row.max_rss;
+        }
+        run.node_rss_bytes = run.node_rss_bytes.max(Some(per_node));
     }
+    runs
+}
 
-    Ok(jobs)
+/// Fields of job steps read by `fetch_job_steps`, in order
+pub const STEP_FIELDS: &[&str] = &[
+    "JobIDRaw", "JobName", "Elapsed", "State", "ExitCode", "MaxRSS", "NodeList",
+];
+
+/// The steps of a job (`123.batch`, `123.0`, ...), without its allocation row
+pub async fn fetch_job_steps(job_id: &str) -> Result<Vec<Job>> {
+    let output = cache::query(
+        "sacct",
+        [
+            "-n",
+            "-P",
+            &format!("--delimiter={}", SACCT_DELIMITER),
+            "-j",
+            job_id,
+            "--format",
+            &format_arg(STEP_FIELDS),
+        ],
+    )
+    .await;
+    let output = checked("sacct", output)?;
+
+    Ok(parse_job_steps(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Step rows of `sacct` output with steps; the allocation rows are dropped
+pub fn parse_job_steps(stdout: &str) -> Vec<Job> {
+    split_records(stdout, STEP_FIELDS)
+        .iter()
+        .filter_map(|record| job_from_record(STEP_FIELDS, record))
+        .filter(|step| step.id.contains('.'))
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// sacct output written with `|` for readability
+    fn sacct(rows: &str) -> String {
+        rows.replace('|', &SACCT_DELIMITER.to_string())
+    }
+
+    #[test]
+    fn splits_long_look_backs_into_windows() {
+        let mut options = SacctOptions {
+            user: None,
+            states: Vec::new(),
+            partitions: Vec::new(),
+            qos: Vec::new(),
+            accounts: Vec::new(),
+            wckeys: Vec::new(),
+            name: None,
+            recent_hours: 24 * 7,
+            end_hours: 0,
+            format_fields: vec!["JobIDRaw"],
+            max_rows: None,
+            window_hours: Some(72),
+        };
+        assert_eq!(options.windows(), [(72, 0), (144, 72), (168, 144)]);
+        let args = options.to_args((144, 72));
+        let start = args.iter().position(|a| a == "-S").unwrap();
+        assert_eq!(
+            args[start..start + 4],
+            ["-S", "now-144hours", "-E", "now-72hours"]
+        );
+
+        options.window_hours = None;
+        assert_eq!(options.windows(), [(168, 0)]);
+        assert!(options.to_args((168, 0)).contains(&"now".to_string()));
+        options.end_hours = 48;
+        assert_eq!(options.windows(), [(168, 48)]);
+
+        // No user lists everyone's jobs, as squeue does
+        assert!(args.contains(&"--allusers".to_string()));
+        options.user = Some("alice".to_string());
+        let args = options.to_args((168, 0));
+        assert!(!args.contains(&"--allusers".to_string()));
+        assert!(args.windows(2).any(|w| w == ["--user", "alice"]));
+    }
+
+    #[test]
+    fn exact_names_go_to_sacct_and_globs_match_whole_names() {
+        assert_eq!(
+            exact_names("^(sweep_a|sweep-b)$"),
+            Some(vec!["sweep_a".to_string(), "sweep-b".to_string()])
+        );
+        assert_eq!(exact_names("^train$"), Some(vec!["train".to_string()]));
+        assert_eq!(exact_names("^sweep_lr.*$"), None);
+        assert_eq!(exact_names("train"), None);
+
+        let glob = name_regex("sweep_lr*").unwrap();
+        assert!(glob.is_match("sweep_lr0.1"));
+        assert!(!glob.is_match("old_sweep_lr0.1"));
+        let class = name_regex("run[!0-3]").unwrap();
+        assert!(class.is_match("run7"));
+        assert!(!class.is_match("run2") && !class.is_match("my_run7"));
+        // Not a glob: `.` and anchors make it a regex
+        assert!(name_regex("^train.*").unwrap().is_match("train_a"));
+        // Without glob metacharacters a pattern matches anywhere in the name
+        assert!(name_regex("lr").unwrap().is_match("sweep_lr0.1"));
+        assert!(name_regex("lr0.1").unwrap().is_match("sweep_lr0.1"));
+    }
+
     #[test]
     fn parse_sacct_output_basic() {
-        let stdout = "123|myjob|alice|COMPLETED|00:10:00|2|node[1-2]|16|2048Mc|part|normal|proj|1000|/tmp|2026-01-01T00:00:00|2026-01-01T00:00:01|2026-01-01T00:10:01|None\n";
+        let stdout = "123|myjob|123|alice|COMPLETED|00:10:00|2|node[1-2]|16|2048Mc|part|normal|proj|1000|/tmp|123|2026-01-01T00:00:00|2026-01-01T00:00:01|2026-01-01T00:10:01|None\n";
         let fields = vec![
             "JobIDRaw",
             "JobName",
@@ -202,26 +987,197 @@ mod tests {
             "End",
             "Reason",
         ];
-        let jobs = parse_sacct_output(stdout, &fields).unwrap();
+        let jobs = parse_sacct_output(&sacct(stdout), &fields).unwrap();
         assert_eq!(jobs.len(), 1);
         let j = &jobs[0];
         assert_eq!(j.id, "123");
         assert_eq!(j.name, "myjob");
         assert_eq!(j.user, "alice");
         assert_eq!(j.state, JobState::Completed);
-        assert_eq!(j.time, "00:10:00");
+        assert_eq!(j.time, std::time::Duration::from_secs(600));
         assert_eq!(j.nodes, 2);
         assert_eq!(j.node.as_deref(), Some("node[1-2]"));
         assert_eq!(j.cpus, 16);
-        assert_eq!(j.memory, "2048Mc");
+        assert_eq!(j.memory, Memory::parse("2048Mc"));
+        assert_eq!(j.memory_per_node(), Some(8 * 2048 * 1024 * 1024));
         assert_eq!(j.partition, "part");
         assert_eq!(j.qos, "normal");
     }
 
+    #[test]
+    fn parse_sacct_output_reads_exit_codes() {
+        let stdout = "9|FAILED|0:9|1:0\n";
+        let fields = vec!["JobIDRaw", "State", "ExitCode", "DerivedExitCode"];
+        let jobs = parse_sacct_output(&sacct(stdout), &fields).unwrap();
+        assert_eq!(jobs[0].exit_code.as_deref(), Some("0:9"));
+        assert_eq!(jobs[0].derived_exit_code.as_deref(), Some("1:0"));
+    }
+
+    #[test]
+    fn parse_sacct_output_reads_gpus_from_tres() {
+        let stdout = "1|cpu=4,gres/gpu=2|billing=4,cpu=4,gres/gpu=2,gres/gpu:a100=2\n\
+                      2|cpu=4,gres/gpu=1|\n";
+        let jobs =
+            parse_sacct_output(&sacct(stdout), &["JobIDRaw", "ReqTRES", "AllocTRES"]).unwrap();
+        assert_eq!(jobs[0].gres.as_deref(), Some("gpu:a100:2"));
+        assert_eq!(jobs[0].gpus, 2);
+        // Pending: nothing allocated yet, so the request is used
+        assert_eq!(jobs[1].gres.as_deref(), Some("gpu:1"));
+        assert_eq!(jobs[1].gpus, 1);
+    }
+
+    #[test]
+    fn parse_sacct_output_reads_storage_from_tres() {
+        let stdout = "1|cpu=8,fs/disk=400G,node=2|cpu=8,fs/disk=300G,bb/datawarp=1T,node=2\n\
+                      2|cpu=4,fs/disk=50G,bb/lua=100G,node=1|\n";
+        let jobs =
+            parse_sacct_output(&sacct(stdout), &["JobIDRaw", "ReqTRES", "AllocTRES"]).unwrap();
+        // The allocation wins, shared out over the nodes
+        assert_eq!(jobs[0].tmp_disk.unwrap().to_string(), "150G");
+        assert_eq!(jobs[0].burst_buffer.unwrap().to_string(), "1T");
+        assert_eq!(jobs[1].tmp_disk.unwrap().to_string(), "50G");
+        assert_eq!(jobs[1].burst_buffer.unwrap().to_string(), "100G");
+    }
+
     #[test]
     fn parse_sacct_output_skips_empty_lines() {
         let stdout = "\n\n";
         let jobs = parse_sacct_output(stdout, &["JobIDRaw"]).unwrap();
         assert!(jobs.is_empty());
     }
+    #[test]
+    fn parse_sacct_output_survives_hostile_job_names() {
+        let fields = ["JobIDRaw", "JobName", "State", "WorkDir", "AllocCPUS"];
+        let d = SACCT_DELIMITER;
+        let stdout = format!(
+            "1{d}a|b || c{d}1{d}COMPLETED{d}/tmp/x|y{d}1{d}4\n\
+             2{d}two\nlines\n{d}2{d}FAILED{d}/tmp{d}2{d}2\n\
+             3{d}has{d}delim{d}3{d}RUNNING{d}/home{d}3{d}8\n\
+             4{d}last{d}4{d}PENDING{d}/work/a\nb{d}4{d}1\n\
+             5{d}five{d}5{d}COMPLETED{d}/data{d}x{d}5{d}2\n"
+        );
+        let jobs = parse_sacct_output(&stdout, &fields).unwrap();
+        let names: Vec<&str> = jobs.iter().map(|j| j.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "a|b || c",
+                "two\nlines",
+                &format!("has{d}delim"),
+                "last",
+                "five"
+            ]
+        );
+        assert_eq!(jobs[0].work_dir.as_deref(), Some("/tmp/x|y"));
+        assert_eq!(jobs[1].state, JobState::Failed);
+        assert_eq!(jobs[2].cpus, 8);
+        assert_eq!(jobs[3].work_dir.as_deref(), Some("/work/a\nb"));
+        // A delimiter in a later free-text field stays in that field
+        assert_eq!(jobs[4].work_dir, Some(format!("/data{d}x")));
+        assert_eq!(jobs[4].cpus, 2);
+    }
+
+    #[test]
+    fn records_are_held_until_no_later_line_can_change_them() {
+        let d = SACCT_DELIMITER;
+        let fields = ["JobIDRaw", "Reason"];
+        let mut splitter = RecordSplitter::new(&fields);
+        assert_eq!(splitter.push_line(&format!("1{d}x")), None);
+        // A newline in the last value is carried over to that record
+        assert_eq!(splitter.push_line("y"), None);
+        assert_eq!(
+            splitter.push_line(&format!("2{d}z")),
+            Some(vec!["1".to_string(), "x\ny".to_string()])
+        );
+        assert_eq!(splitter.finish(), vec![vec!["2", "z"]]);
+    }
+
+    #[test]
+    fn records_are_read_across_chunks_and_up_to_a_cap() {
+        let fields = ["JobIDRaw", "JobName", "State"];
+        let stdout = sacct("1|one|1|RUNNING\n2|two\nlines|2|FAILED\n3|three|3|PENDING\n");
+        // A few bytes per read, so records and their values span reads
+        let reader = BufReader::with_capacity(4, stdout.as_bytes());
+        let mut names = Vec::new();
+        let mut add = |record: Vec<String>| {
+            names.push(record[1].clone());
+            true
+        };
+        let mut captured = Vec::new();
+        let complete =
+            future::block_on(read_records(reader, &fields, &mut add, Some(&mut captured)));
+        assert!(complete.unwrap());
+        assert_eq!(names, ["one", "two\nlines", "three"]);
+        assert_eq!(captured, stdout.as_bytes());
+
+        let mut read = 0;
+        let mut add = |_| {
+            read += 1;
+            read < 2
+        };
+        let complete = future::block_on(read_records(stdout.as_bytes(), &fields, &mut add, None));
+        assert!(!complete.unwrap());
+        assert_eq!(read, 2);
+    }
+
+    #[test]
+    fn sacct_is_killed_once_the_rows_are_truncated() {
+        // Rows without end, as a huge look back would print
+        let mut child = Command::new("sh")
+            .args([
+                "-c",
+                "i=0; while :; do i=$((i+1)); printf '%s\\037job\\n' $i; done",
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        let fields = ["JobIDRaw", "JobName"];
+        let mut rows = SacctRows::default();
+        let mut seen = HashSet::new();
+        let mut add = |record: Vec<String>| {
+            let job = job_from_record(&fields, &record).unwrap();
+            rows.push(job, &mut seen, 3)
+        };
+        let (complete, _) =
+            future::block_on(read_child(&mut child, &fields, &mut add, None)).unwrap();
+        assert!(!complete);
+        assert!(rows.truncated);
+        assert_eq!(rows.jobs.len(), 3);
+        assert!(!future::block_on(child.status()).unwrap().success());
+    }
+
+    #[test]
+    fn job_steps_leave_out_the_allocation() {
+        let stdout = sacct(
+            "42|mpi_run|42|00:12:00|FAILED|1:0||node[01-04]\n\
+             42.batch|batch|42.batch|00:12:00|FAILED|1:0|2100K|node01\n\
+             42.0|hostname|42.0|00:00:01|COMPLETED|0:0|900K|node[01-04]\n\
+             42.1|solver|42.1|00:11:50|FAILED|139:0|31000000K|node[01-04]\n",
+        );
+        let steps = parse_job_steps(&stdout);
+        let ids: Vec<&str> = steps.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["42.batch", "42.0", "42.1"]);
+        assert_eq!(steps[2].name, "solver");
+        assert_eq!(steps[2].state, JobState::Failed);
+        assert_eq!(steps[2].exit_code.as_deref(), Some("139:0"));
+        assert_eq!(steps[2].max_rss.as_deref(), Some("31000000K"));
+        assert_eq!(steps[1].node.as_deref(), Some("node[01-04]"));
+    }
+
+    #[test]
+    fn past_runs_take_the_peak_per_node_of_their_steps() {
+        let stdout = sacct(
+            "7|train|7|COMPLETED|01:00:00|02:00:00|02:00:00|32|2|64G||\n\
+             7.batch|batch|7.batch|COMPLETED|01:00:00||00:01:00|16|1||3145728K|1\n\
+             7.0|train|7.0|COMPLETED|00:59:00||01:59:00|32|2||2097152K|8\n",
+        );
+        let runs = fold_steps(&stdout);
+        assert_eq!(runs.len(), 1);
+        // The batch step holds the largest task, the 8 tasks of step 0 the
+        // largest node: 4 tasks of up to 2 GiB each
+        assert_eq!(runs[0].job.max_rss.as_deref(), Some("3145728K"));
+        assert_eq!(runs[0].node_rss_bytes, Some(8 * 1024 * 1024 * 1024));
+    }
 }
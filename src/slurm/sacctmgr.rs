@@ -0,0 +1,394 @@
+use color_eyre::Result;
+use std::time::Duration;
+
+use super::error::checked;
+use super::{
+    command,
+    memory::Memory,
+    squeue::{run_squeue, SqueueOptions},
+    Job, JobState,
+};
+use crate::utils::{format_slurm_duration, parse_slurm_duration};
+
+/// `sacctmgr show qos` fields, in the order they are parsed
+const QOS_FORMAT: &str = "format=Name,MaxTRESPerUser,MaxJobsPerUser,MaxSubmitJobsPerUser,MaxWall";
+
+/// `sacctmgr show assoc` fields, in the order they are parsed
+const ASSOC_FORMAT: &str = "format=Account,Partition,QOS,MaxTRES,MaxJobs,MaxSubmitJobs";
+
+/// Limits of a QOS that apply to each user
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QosLimits {
+    pub name: String,
+    /// TRES string, e.g. `cpu=256,gres/gpu=8`
+    pub max_tres_per_user: String,
+    pub max_jobs_per_user: Option<u64>,
+    pub max_submit_per_user: Option<u64>,
+    pub max_wall: Option<Duration>,
+}
+
+/// Limits of one of the user's associations (account, maybe partition)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Association {
+    pub account: String,
+    /// Empty when it covers every partition
+    pub partition: String,
+    /// QOSs the association may use
+    pub qos: Vec<String>,
+    /// TRES string, per job
+    pub max_tres: String,
+    pub max_jobs: Option<u64>,
+    pub max_submit: Option<u64>,
+}
+
+/// Where a limit is set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    Qos(String),
+    Account { account: String, partition: String },
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scope::Qos(name) => write!(f, "QOS {}", name),
+            Scope::Account { account, partition } if partition.is_empty() => {
+                write!(f, "Account {}", account)
+            }
+            Scope::Account { account, partition } => {
+                write!(f, "Account {} ({})", account, partition)
+            }
+        }
+    }
+}
+
+impl Scope {
+    /// Whether `job` counts against limits set here
+    pub fn covers(&self, job: &Job) -> bool {
+        match self {
+            Scope::Qos(name) => &job.qos == name,
+            Scope::Account { account, partition } => {
+                job.account.as_deref() == Some(account.as_str())
+                    && (partition.is_empty() || job.partition.split(',').any(|p| p == partition))
+            }
+        }
+    }
+}
+
+/// How a limit's figures print
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Count,
+    Bytes,
+    Seconds,
+}
+
+/// One limit against what the user's jobs hold of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limit {
+    pub scope: Scope,
+    /// `MaxJobs`, `MaxSubmit`, `MaxWall` or a TRES name (`cpu`, `gres/gpu`)
+    pub name: String,
+    /// Whether it bounds each job rather than all of them together; `used`
+    /// is then the largest job
+    pub per_job: bool,
+    /// `None` when slurmer cannot tell (e.g. `billing`)
+    pub used: Option<u64>,
+    pub max: u64,
+    pub unit: Unit,
+}
+
+impl Limit {
+    pub fn format(&self, value: u64) -> String {
+        match self.unit {
+            Unit::Count => value.to_string(),
+            Unit::Bytes => Memory {
+                bytes: value,
+                ..Default::default()
+            }
+            .to_string(),
+            Unit::Seconds => format_slurm_duration(Duration::from_secs(value)),
+        }
+    }
+
+    /// Share of the limit in use
+    pub fn fraction(&self) -> Option<f64> {
+        let used = self.used?;
+        (self.max > 0).then(|| used as f64 / self.max as f64)
+    }
+
+    /// Whether a pending reason (`QOSMaxCpuPerUserLimit`) names this limit
+    pub fn explains(&self, reason: &str) -> bool {
+        let reason = reason.trim_matches(|c| c == '(' || c == ')').trim();
+        let Some((qos, name)) = reason_limit(reason) else {
+            return false;
+        };
+        qos == matches!(self.scope, Scope::Qos(_)) && name == self.name
+    }
+}
+
+/// The limit a pending reason refers to, as (set on a QOS, limit name)
+fn reason_limit(reason: &str) -> Option<(bool, &'static str)> {
+    Some(match reason {
+        "QOSMaxCpuPerUserLimit" => (true, "cpu"),
+        "QOSMaxMemoryPerUser" => (true, "mem"),
+        "QOSMaxGRESPerUser" => (true, "gres/gpu"),
+        "QOSMaxNodePerUserLimit" => (true, "node"),
+        "QOSMaxJobsPerUserLimit" => (true, "MaxJobs"),
+        "QOSMaxSubmitJobPerUserLimit" => (true, "MaxSubmit"),
+        "QOSMaxWallDurationPerJobLimit" => (true, "MaxWall"),
+        "AssocMaxJobsLimit" => (false, "MaxJobs"),
+        "AssocMaxSubmitJobLimit" => (false, "MaxSubmit"),
+        "AssocMaxCpuPerJobLimit" => (false, "cpu"),
+        "AssocMaxMemPerJob" => (false, "mem"),
+        "AssocMaxGRESPerJob" => (false, "gres/gpu"),
+        "AssocMaxNodePerJobLimit" => (false, "node"),
+        _ => return None,
+    })
+}
+
+/// What a job holds of a TRES, `None` for those slurmer does not track
+fn tres_of(job: &Job, name: &str) -> Option<u64> {
+    Some(match name {
+        "cpu" => job.cpus as u64,
+        "mem" => job.memory_per_node()? * job.nodes.max(1) as u64,
+        "node" => job.nodes as u64,
+        "gres/gpu" => job.gpus as u64,
+        _ => return None,
+    })
+}
+
+/// `name=value` pairs of a TRES limit string, with the value in the unit
+/// `tres_of` uses
+fn parse_tres_limits(tres: &str) -> Vec<(String, u64, Unit)> {
+    tres.split(',')
+        .filter_map(|entry| {
+            let (name, value) = entry.trim().split_once('=')?;
+            if name == "mem" {
+                return Some((name.to_string(), Memory::parse(value)?.bytes, Unit::Bytes));
+            }
+            Some((name.to_string(), value.parse().ok()?, Unit::Count))
+        })
+        .collect()
+}
+
+/// Which of the user's jobs a limit counts, and how
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Counted {
+    /// Summed over running jobs, which is what Slurm holds against a total
+    Running,
+    /// Summed over queued and running jobs (submission limits)
+    Queued,
+    /// The largest queued or running job, for per-job limits
+    Largest,
+}
+
+/// The limits of `qos` and `associations` against the user's queued and
+/// running `jobs`
+pub fn limits(qos: &[QosLimits], associations: &[Association], jobs: &[Job]) -> Vec<Limit> {
+    let mut limits = Vec::new();
+    let mut push = |scope: &Scope,
+                    name: &str,
+                    counted: Counted,
+                    max: u64,
+                    unit: Unit,
+                    value: &dyn Fn(&Job) -> Option<u64>| {
+        // Unknown as soon as one job's share is
+        let values: Option<Vec<u64>> = jobs
+            .iter()
+            .filter(|j| scope.covers(j))
+            .filter(|j| counted != Counted::Running || j.state == JobState::Running)
+            .map(value)
+            .collect();
+        let used = values.map(|values| match counted {
+            Counted::Largest => values.into_iter().max().unwrap_or(0),
+            _ => values.into_iter().sum(),
+        });
+        limits.push(Limit {
+            scope: scope.clone(),
+            name: name.to_string(),
+            per_job: counted == Counted::Largest,
+            used,
+            max,
+            unit,
+        });
+    };
+    let one = |_: &Job| Some(1);
+    for q in qos {
+        let scope = Scope::Qos(q.name.clone());
+        for (name, max, unit) in parse_tres_limits(&q.max_tres_per_user) {
+            push(&scope, &name, Counted::Running, max, unit, &|j: &Job| {
+                tres_of(j, &name)
+            });
+        }
+        if let Some(max) = q.max_jobs_per_user {
+            push(&scope, "MaxJobs", Counted::Running, max, Unit::Count, &one);
+        }
+        if let Some(max) = q.max_submit_per_user {
+            push(&scope, "MaxSubmit", Counted::Queued, max, Unit::Count, &one);
+        }
+        if let Some(max) = q.max_wall {
+            push(
+                &scope,
+                "MaxWall",
+                Counted::Largest,
+                max.as_secs(),
+                Unit::Seconds,
+                &|j: &Job| Some(j.time_limit.map_or(0, |l| l.as_secs())),
+            );
+        }
+    }
+    for a in associations {
+        let scope = Scope::Account {
+            account: a.account.clone(),
+            partition: a.partition.clone(),
+        };
+        for (name, max, unit) in parse_tres_limits(&a.max_tres) {
+            push(&scope, &name, Counted::Largest, max, unit, &|j: &Job| {
+                tres_of(j, &name)
+            });
+        }
+        if let Some(max) = a.max_jobs {
+            push(&scope, "MaxJobs", Counted::Running, max, Unit::Count, &one);
+        }
+        if let Some(max) = a.max_submit {
+            push(&scope, "MaxSubmit", Counted::Queued, max, Unit::Count, &one);
+        }
+    }
+    limits
+}
+
+fn number(value: &str) -> Option<u64> {
+    value.trim().parse().ok()
+}
+
+/// Parse `sacctmgr -n -P show qos` with `QOS_FORMAT`, keeping QOSs that
+/// set any per-user limit
+pub fn parse_qos(stdout: &str) -> Vec<QosLimits> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('|').collect();
+            let field = |i: usize| fields.get(i).map_or("", |f| f.trim());
+            let qos = QosLimits {
+                name: field(0).to_string(),
+                max_tres_per_user: field(1).to_string(),
+                max_jobs_per_user: number(field(2)),
+                max_submit_per_user: number(field(3)),
+                max_wall: parse_slurm_duration(field(4)).filter(|_| !field(4).is_empty()),
+            };
+            let limited = !qos.max_tres_per_user.is_empty()
+                || qos.max_jobs_per_user.is_some()
+                || qos.max_submit_per_user.is_some()
+                || qos.max_wall.is_some();
+            (!qos.name.is_empty() && limited).then_some(qos)
+        })
+        .collect()
+}
+
+/// Parse `sacctmgr -n -P show assoc` with `ASSOC_FORMAT`
+pub fn parse_associations(stdout: &str) -> Vec<Association> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('|').collect();
+            let field = |i: usize| fields.get(i).map_or("", |f| f.trim());
+            let account = field(0);
+            (!account.is_empty()).then(|| Association {
+                account: account.to_string(),
+                partition: field(1).to_string(),
+                qos: field(2)
+                    .split(',')
+                    .filter(|q| !q.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                max_tres: field(3).to_string(),
+                max_jobs: number(field(4)),
+                max_submit: number(field(5)),
+            })
+        })
+        .collect()
+}
+
+/// Run `sacctmgr`, failing on a non-zero exit
+pub async fn sacctmgr(args: &[&str]) -> Result<String> {
+    let output = checked("sacctmgr", command::output("sacctmgr", args).await)?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// squeue format of the user's jobs measured against the limits
+const JOBS_FORMAT: &str = "%i|%T|%q|%a|%P|%C|%D|%m|%b|%l|%R";
+
+/// The limits of the user's associations and of the QOSs they may use,
+/// with the user's queued and running jobs they were measured against
+pub async fn fetch_limits(user: &str) -> Result<(Vec<Limit>, Vec<Job>)> {
+    let user_filter = format!("user={}", user);
+    let associations = parse_associations(
+        &sacctmgr(&["-n", "-P", "show", "assoc", &user_filter, ASSOC_FORMAT]).await?,
+    );
+    let jobs = run_squeue(&SqueueOptions {
+        user: Some(user.to_string()),
+        states: vec![JobState::Pending, JobState::Running],
+        format: JOBS_FORMAT.to_string(),
+        ..Default::default()
+    })
+    .await?;
+    let mut qos = parse_qos(&sacctmgr(&["-n", "-P", "show", "qos", QOS_FORMAT]).await?);
+    qos.retain(|q| {
+        associations.iter().any(|a| a.qos.contains(&q.name)) || jobs.iter().any(|j| j.qos == q.name)
+    });
+    Ok((limits(&qos, &associations, &jobs), jobs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_jobs_against_qos_and_account_limits() {
+        let qos = parse_qos(
+            "normal|cpu=64,gres/gpu=4,billing=100|10|20|2-00:00:00\n\
+             long||||7-00:00:00\n\
+             free|||||\n",
+        );
+        assert_eq!(qos.len(), 2);
+        assert_eq!(qos[0].max_jobs_per_user, Some(10));
+        let associations = parse_associations("proj||normal,long|mem=500G||\n");
+        assert_eq!(associations[0].qos, ["normal", "long"]);
+
+        let job = |state: JobState, cpus: u32, hours: u64| Job {
+            state,
+            cpus,
+            nodes: 1,
+            gpus: 2,
+            memory: Memory::parse("100G"),
+            qos: "normal".to_string(),
+            account: Some("proj".to_string()),
+            time_limit: Some(Duration::from_secs(hours * 3600)),
+            ..Default::default()
+        };
+        let jobs = [
+            job(JobState::Running, 32, 24),
+            job(JobState::Running, 16, 12),
+            job(JobState::Pending, 32, 48),
+        ];
+        let limits = limits(&qos[..1], &associations, &jobs);
+        let find = |name: &str| limits.iter().find(|l| l.name == name).unwrap();
+        assert_eq!((find("cpu").used, find("cpu").max), (Some(48), 64));
+        assert_eq!(find("gres/gpu").used, Some(4));
+        assert_eq!(find("billing").used, None);
+        assert_eq!(find("MaxJobs").used, Some(2));
+        assert_eq!(find("MaxSubmit").used, Some(3));
+        assert_eq!(
+            find("MaxWall").format(find("MaxWall").used.unwrap()),
+            "2-00:00:00"
+        );
+        let mem = find("mem");
+        assert!(mem.per_job);
+        assert_eq!(mem.format(mem.used.unwrap()), "100G");
+
+        assert!(find("cpu").explains("(QOSMaxCpuPerUserLimit)"));
+        assert!(!mem.explains("QOSMaxMemoryPerUser"));
+        assert!(mem.explains("AssocMaxMemPerJob"));
+    }
+}
@@ -0,0 +1,98 @@
+use super::command::output_blocking;
+use super::parse_scontrol_output;
+
+/// Where a batch script was retrieved from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptSource {
+    /// The controller's copy (`scontrol write batch_script`), exactly as submitted
+    Controller,
+    /// The copy stored by accounting (`sacct -B`)
+    Accounting,
+    /// The file the job was submitted from, which may have changed since
+    File(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchScript {
+    pub content: String,
+    pub source: ScriptSource,
+}
+
+/// Get a job's batch script from the controller, then accounting, then the submitted file on disk
+pub fn fetch_batch_script(job_id: &str) -> Option<BatchScript> {
+    // The controller keeps the exact submitted script while the job is known to it.
+    if let Ok(output) = output_blocking("scontrol", ["write", "batch_script", job_id, "-"]) {
+        let script = String::from_utf8_lossy(&output.stdout).to_string();
+        if output.status.success() && !script.trim().is_empty() {
+            return Some(BatchScript {
+                content: script,
+                source: ScriptSource::Controller,
+            });
+        }
+    }
+
+    // Accounting stores it for finished jobs on clusters that enable it.
+    if let Ok(output) = output_blocking("sacct", ["-B", "-j", job_id]) {
+        if output.status.success() {
+            if let Some(script) =
+                strip_sacct_script_header(&String::from_utf8_lossy(&output.stdout))
+            {
+                return Some(BatchScript {
+                    content: script,
+                    source: ScriptSource::Accounting,
+                });
+            }
+        }
+    }
+
+    // Last resort: the script file the job was submitted from.
+    let output = output_blocking("scontrol", ["show", "job", job_id, "-o"]).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let key_value_pairs = parse_scontrol_output(&String::from_utf8_lossy(&output.stdout));
+    let script_path = key_value_pairs.get("Command")?;
+    let content = std::fs::read_to_string(script_path).ok()?;
+    Some(BatchScript {
+        content,
+        source: ScriptSource::File(script_path.to_string()),
+    })
+}
+
+/// `sacct -B` prints a "Batch Script for <id>" banner and a dashed rule before the script
+fn strip_sacct_script_header(output: &str) -> Option<String> {
+    let mut lines = output.lines().peekable();
+    while let Some(line) = lines.peek() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("Batch Script for")
+            || (!trimmed.is_empty() && trimmed.chars().all(|c| c == '-'))
+        {
+            lines.next();
+        } else {
+            break;
+        }
+    }
+    let script = lines.collect::<Vec<_>>().join("\n");
+    // Jobs without a stored script show up as "NONE"
+    if script.trim().is_empty() || script.trim() == "NONE" {
+        None
+    } else {
+        Some(script + "\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sacct_banner_is_stripped() {
+        let out =
+            "Batch Script for 42\n----------------------\n#!/bin/bash\n#SBATCH -t 1:00\necho hi\n";
+        assert_eq!(
+            strip_sacct_script_header(out).unwrap(),
+            "#!/bin/bash\n#SBATCH -t 1:00\necho hi\n"
+        );
+        assert!(strip_sacct_script_header("Batch Script for 42\n-----\nNONE\n").is_none());
+    }
+}
@@ -0,0 +1,366 @@
+use chrono::{DateTime, Datelike, Local, TimeDelta, TimeZone, Timelike};
+use color_eyre::Result;
+use std::io::Write;
+
+use super::command;
+use super::error::{checked, SlurmError};
+
+/// Prefix Slurm puts before entries it disabled (or that were disabled by hand)
+const DISABLED: &str = "#DISABLED:";
+
+/// Prefix of the `sbatch` options of the next entry
+const OPTIONS: &str = "#SCRON";
+
+/// Days searched for the next run of a schedule
+const SEARCH_DAYS: usize = 366 * 5;
+
+/// One recurring job of the scrontab
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronEntry {
+    /// Line of the file it is on
+    line: usize,
+    /// The five cron fields, or a shorthand such as `@daily`
+    pub schedule: String,
+    pub command: String,
+    /// `#SCRON` options above the entry, e.g. `-p debug -t 10`
+    pub options: Vec<String>,
+    pub enabled: bool,
+}
+
+/// The user's scrontab file, kept line by line so writing it back only
+/// changes the edited entries
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scrontab {
+    lines: Vec<String>,
+    pub entries: Vec<CronEntry>,
+}
+
+impl Scrontab {
+    pub fn parse(raw: &str) -> Self {
+        let lines: Vec<String> = raw.lines().map(str::to_string).collect();
+        let mut entries = Vec::new();
+        let mut options = Vec::new();
+        for (line, text) in lines.iter().enumerate() {
+            let text = text.trim();
+            if let Some(option) = text.strip_prefix(OPTIONS) {
+                options.push(option.trim().to_string());
+                continue;
+            }
+            let (enabled, text) = match text.strip_prefix(DISABLED) {
+                Some(rest) => (false, rest.trim()),
+                None => (true, text),
+            };
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+            // Environment assignments are not entries
+            if text
+                .split_whitespace()
+                .next()
+                .is_some_and(|t| t.contains('='))
+            {
+                continue;
+            }
+            if let Some((schedule, command)) = split_entry(text) {
+                entries.push(CronEntry {
+                    line,
+                    schedule,
+                    command,
+                    options: std::mem::take(&mut options),
+                    enabled,
+                });
+            }
+        }
+        Self { lines, entries }
+    }
+
+    /// The file with the entries as they are now
+    pub fn render(&self) -> String {
+        let mut raw = self.lines.join("\n");
+        raw.push('\n');
+        raw
+    }
+
+    /// Comment entry `index` out with `#DISABLED:`, or uncomment it
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        if let Some(entry) = self.entries.get_mut(index) {
+            entry.enabled = enabled;
+            self.rewrite(index);
+        }
+    }
+
+    fn rewrite(&mut self, index: usize) {
+        let entry = &self.entries[index];
+        let text = format!("{} {}", entry.schedule, entry.command);
+        self.lines[entry.line] = if entry.enabled {
+            text
+        } else {
+            format!("{} {}", DISABLED, text)
+        };
+    }
+
+    /// Change the schedule and command of entry `index`
+    pub fn edit(&mut self, index: usize, schedule: &str, command: &str) -> Result<(), String> {
+        let schedule = schedule.split_whitespace().collect::<Vec<_>>().join(" ");
+        Schedule::parse(&schedule)?;
+        if command.trim().is_empty() {
+            return Err("the command is empty".to_string());
+        }
+        let entry = self
+            .entries
+            .get_mut(index)
+            .ok_or_else(|| "no such entry".to_string())?;
+        entry.schedule = schedule;
+        entry.command = command.trim().to_string();
+        self.rewrite(index);
+        Ok(())
+    }
+}
+
+/// `*/10 * * * * cmd` or `@daily cmd`, split into schedule and command
+fn split_entry(text: &str) -> Option<(String, String)> {
+    let fields = if text.starts_with('@') { 1 } else { 5 };
+    let mut rest = text;
+    let mut schedule = Vec::new();
+    for _ in 0..fields {
+        let (field, tail) = rest.trim_start().split_once(char::is_whitespace)?;
+        schedule.push(field);
+        rest = tail;
+    }
+    let schedule = schedule.join(" ");
+    Schedule::parse(&schedule).ok()?;
+    let command = rest.trim();
+    (!command.is_empty()).then(|| (schedule, command.to_string()))
+}
+
+/// A parsed cron schedule: the minutes, hours, days, months and weekdays it
+/// runs at, as bit sets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether the day of month or of the week were `*`; when neither was,
+    /// a day matching either runs (as in cron)
+    any_day: bool,
+    any_weekday: bool,
+}
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+impl Schedule {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = match spec.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other if other.starts_with('@') => return Err(format!("unknown schedule {}", other)),
+            other => other,
+        };
+        let fields: Vec<&str> = spec.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("expected 5 fields, got {}", fields.len()));
+        };
+        let weekdays = parse_field(weekday, 0, 7, &WEEKDAYS)?;
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[])?,
+            hours: parse_field(hour, 0, 23, &[])?,
+            days: parse_field(day, 1, 31, &[])?,
+            months: parse_field(month, 1, 12, &MONTHS)?,
+            // 7 is Sunday too
+            weekdays: (weekdays | weekdays >> 7) & 0x7f,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+
+    fn runs_on(&self, month: u32, day: u32, weekday: u32) -> bool {
+        if self.months & 1 << month == 0 {
+            return false;
+        }
+        let by_day = self.days & 1 << day != 0;
+        let by_weekday = self.weekdays & 1 << weekday != 0;
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (false, true) => by_day,
+            (true, false) => by_weekday,
+            (false, false) => by_day || by_weekday,
+        }
+    }
+
+    /// The first time after `now` it runs
+    pub fn next_after(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = now.naive_local().with_second(0)?.with_nanosecond(0)? + TimeDelta::minutes(1);
+        for date in start.date().iter_days().take(SEARCH_DAYS) {
+            let weekday = date.weekday().num_days_from_sunday();
+            if !self.runs_on(date.month(), date.day(), weekday) {
+                continue;
+            }
+            for hour in 0..24 {
+                if self.hours & 1 << hour == 0 {
+                    continue;
+                }
+                for minute in 0..60 {
+                    let at = date.and_hms_opt(hour, minute, 0)?;
+                    if self.minutes & 1 << minute == 0 || at < start {
+                        continue;
+                    }
+                    // A minute skipped when the clocks go forward never comes
+                    if let Some(at) = Local.from_local_datetime(&at).earliest() {
+                        return Some(at);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// A cron field as a bit set: `*`, values, ranges and steps, comma-separated
+fn parse_field(field: &str, min: u64, max: u64, names: &[&str]) -> Result<u64, String> {
+    let value = |raw: &str| -> Result<u64, String> {
+        let lower = raw.to_ascii_lowercase();
+        if let Some(i) = names.iter().position(|n| *n == lower) {
+            // Month names start at 1, weekday names at 0
+            return Ok(i as u64 + min);
+        }
+        raw.parse::<u64>()
+            .ok()
+            .filter(|v| (min..=max).contains(v))
+            .ok_or_else(|| format!("`{}` is not in {}-{}", raw, min, max))
+    };
+    let mut bits = 0u64;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u64>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("bad step in `{}`", item))?,
+            ),
+            None => (item, 1),
+        };
+        let (from, to) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((from, to)) => (value(from)?, value(to)?),
+                // `5/15` runs from 5 to the end
+                None if step > 1 => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if from > to {
+            return Err(format!("`{}` is an empty range", item));
+        }
+        for v in (from..=to).step_by(step as usize) {
+            bits |= 1 << v;
+        }
+    }
+    Ok(bits)
+}
+
+/// `2026-10-15 03:00`
+pub fn format_minute(at: &DateTime<Local>) -> String {
+    at.format("%Y-%m-%d %H:%M").to_string()
+}
+
+/// The user's scrontab (`scrontab -l`); empty when there is none
+pub async fn fetch_scrontab() -> Result<Scrontab> {
+    let output = command::output("scrontab", ["-l"])
+        .await
+        .map_err(|e| SlurmError::from_io("scrontab", &e))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        if stderr.to_lowercase().contains("no crontab") {
+            return Ok(Scrontab::default());
+        }
+        return Err(SlurmError::from_stderr("scrontab", &stderr).into());
+    }
+    Ok(Scrontab::parse(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Replace the user's scrontab with `scrontab`
+pub async fn write_scrontab(scrontab: &Scrontab) -> Result<()> {
+    // Created exclusively and readable by the user only, so nobody else on
+    // a shared login node can swap the file before scrontab reads it
+    let mut file = tempfile::Builder::new()
+        .prefix("slurmer-scrontab-")
+        .tempfile()?;
+    file.write_all(scrontab.render().as_bytes())?;
+    file.flush()?;
+    checked(
+        "scrontab",
+        command::execute_update("scrontab", [file.path()]).await,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::parse_slurm_time;
+
+    #[test]
+    fn parses_entries_and_writes_them_back() {
+        let raw = "\
+# nightly jobs
+#SCRON -p debug
+#SCRON -t 10
+*/15 9-17 * * mon-fri $HOME/poll.sh --quiet
+SHELL=/bin/bash
+#DISABLED: @daily $HOME/cleanup.sh
+";
+        let mut crontab = Scrontab::parse(raw);
+        assert_eq!(crontab.entries.len(), 2);
+        assert_eq!(crontab.entries[0].schedule, "*/15 9-17 * * mon-fri");
+        assert_eq!(crontab.entries[0].command, "$HOME/poll.sh --quiet");
+        assert_eq!(crontab.entries[0].options, ["-p debug", "-t 10"]);
+        assert!(!crontab.entries[1].enabled);
+        assert_eq!(crontab.render(), raw);
+
+        crontab.set_enabled(1, true);
+        crontab.set_enabled(0, false);
+        crontab
+            .edit(1, "0  3 * * *", "$HOME/cleanup.sh -v")
+            .unwrap();
+        assert!(crontab.edit(1, "0 25 * * *", "x").is_err());
+        assert_eq!(
+            crontab.render().lines().skip(3).collect::<Vec<_>>(),
+            [
+                "#DISABLED: */15 9-17 * * mon-fri $HOME/poll.sh --quiet",
+                "SHELL=/bin/bash",
+                "0 3 * * * $HOME/cleanup.sh -v",
+            ]
+        );
+    }
+
+    #[test]
+    fn next_run_follows_the_schedule() {
+        // 2026-10-14 was a Wednesday
+        let now = parse_slurm_time("2026-10-14T17:50:30").unwrap();
+        let next = |spec: &str| {
+            Schedule::parse(spec)
+                .unwrap()
+                .next_after(now)
+                .as_ref()
+                .map(format_minute)
+        };
+        assert_eq!(next("*/15 9-17 * * mon-fri").unwrap(), "2026-10-15 09:00");
+        assert_eq!(next("55 17 * * *").unwrap(), "2026-10-14 17:55");
+        assert_eq!(next("@monthly").unwrap(), "2026-11-01 00:00");
+        assert_eq!(next("0 12 * * 7").unwrap(), "2026-10-18 12:00");
+        // Day of month or weekday, when both are given
+        assert_eq!(next("0 0 20 * fri").unwrap(), "2026-10-16 00:00");
+        assert_eq!(next("0 0 29 feb *").unwrap(), "2028-02-29 00:00");
+        assert!(Schedule::parse("* * *").is_err());
+        assert!(Schedule::parse("@sometimes").is_err());
+    }
+}
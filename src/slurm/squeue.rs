@@ -1,20 +1,30 @@
-use async_process::{Command, Output};
-use color_eyre::eyre::Error;
+use async_process::Output;
+use chrono::{DateTime, Local};
 use color_eyre::Result;
 use std::collections::HashMap;
 use std::str::FromStr;
 
+use super::error::{checked, SlurmError};
 use super::Job;
 use super::JobState;
+use super::{cache, command};
+use crate::utils::{parse_slurm_duration, parse_slurm_time};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SqueueOptions {
     pub user: Option<String>,
     pub states: Vec<JobState>,
     pub partitions: Vec<String>,
     pub qos: Vec<String>,
+    pub accounts: Vec<String>,
+    /// Only keep jobs of these WCKeys (applied in-app: squeue has no filter)
+    pub wckeys: Vec<String>,
     pub name_filter: Option<String>,
     pub node_filter: Option<String>,
+    /// Only keep jobs that use GPUs (applied in-app like the regex filters)
+    pub gpu_only: bool,
+    /// Only keep pending jobs expected to start within this many hours
+    pub start_within_hours: Option<u32>,
     pub format: String,
     pub sorts: HashMap<String, bool>, // Map of field to sort direction (true for ascending, false for descending)
 }
@@ -33,8 +43,12 @@ impl Default for SqueueOptions {
             states: Vec::new(),
             partitions: Vec::new(),
             qos: Vec::new(),
+            accounts: Vec::new(),
+            wckeys: Vec::new(),
             name_filter: None,
             node_filter: None,
+            gpu_only: false,
+            start_within_hours: None,
             format: "%i|%j|%u|%T|%M|%N|%C|%m|%P|%V|%e".to_string(), // JobID|Name|User|State|Time|NodeList|CPUs|Memory|Partition|Submit|End
             sorts,
         }
@@ -90,6 +104,12 @@ impl SqueueOptions {
             args.push(qos);
         }
 
+        // Account filter
+        if !self.accounts.is_empty() {
+            args.push("--account".to_string());
+            args.push(self.accounts.join(","));
+        }
+
         // Name filter is now handled internally by the application
         // so we don't pass it to squeue
 
@@ -131,29 +151,80 @@ pub async fn run_squeue(options: &SqueueOptions) -> Result<Vec<Job>> {
         return Ok(Vec::new());
     }
 
-    let output = match Command::new("squeue").args(&args).output().await {
-        Ok(output) => {
-            // eprintln!("Running squeue command completed");
-            output
-        }
-        Err(e) => {
-            // eprintln!("Error running squeue command: {}", e);
-            // return Ok(Vec::new());
-            return Err(Error::new(e));
-        }
-    };
+    let output = cache::query("squeue", &args)
+        .await
+        .map_err(|e| SlurmError::from_io("squeue", &e))?;
 
-    // // Check if squeue returned an error
-    // if !output.status.success() {
-    //     let stderr = String::from_utf8_lossy(&output.stderr);
-    //     eprintln!("squeue returned an error: {}", stderr);
-    //     return Ok(Vec::new());
-    // }
+    // Check if squeue returned an error (e.g. controller not responding), so callers
+    // don't mistake it for an empty queue.
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SlurmError::from_stderr("squeue", &stderr).into());
+    }
 
     // Pass the format options with the output to ensure correct parsing
     parse_squeue_output(&output, &options.format)
 }
 
+/// Current raw state (e.g. `RUNNING`) of specific jobs, keyed by job ID as squeue prints
+/// it (array tasks appear as `<id>_<task>`). Jobs squeue no longer knows about are absent.
+pub async fn query_job_states(job_ids: &[String]) -> Result<HashMap<String, String>> {
+    let output = command::output(
+        "squeue",
+        [
+            "--noheader",
+            "--jobs",
+            &job_ids.join(","),
+            "--format",
+            "%i|%T",
+        ],
+    )
+    .await
+    .map_err(|e| SlurmError::from_io("squeue", &e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // squeue rejects the whole query once every listed job has left the queue
+        if stderr.contains("Invalid job id") {
+            return Ok(HashMap::new());
+        }
+        return Err(SlurmError::from_stderr("squeue", &stderr).into());
+    }
+
+    Ok(super::parse_state_rows(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// The scheduler's expected start times of pending jobs (`squeue --start`),
+/// by job id. Working these out is expensive for the controller, so the app
+/// asks on a slower cadence than the job list.
+pub async fn fetch_start_estimates(user: Option<&str>) -> Result<HashMap<String, DateTime<Local>>> {
+    let mut args = vec!["--start", "--noheader", "--format", "%i|%S"];
+    if let Some(user) = user {
+        args.extend(["--user", user]);
+    }
+    let output = checked("squeue --start", cache::query("squeue", &args).await)?;
+    Ok(parse_start_estimates(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// `id|start` rows, without the jobs that have no estimate (`N/A`)
+fn parse_start_estimates(stdout: &str) -> HashMap<String, DateTime<Local>> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let (id, start) = line.split_once('|')?;
+            let id = id.trim();
+            if id.is_empty() {
+                return None;
+            }
+            Some((id.to_string(), parse_slurm_time(start)?))
+        })
+        .collect()
+}
+
 /// Dynamic parsing of squeue output based on the provided format string
 fn parse_squeue_output(output: &Output, format: &str) -> Result<Vec<Job>> {
     let stdout = String::from_utf8_lossy(&output.stdout);
@@ -214,47 +285,94 @@ fn parse_squeue_output(output: &Output, format: &str) -> Result<Vec<Job>> {
                 "%j" => job.name = value,
                 "%u" => job.user = value,
                 "%T" => {
-                    job.state = JobState::from_str(&value).unwrap_or_else(|_| {
+                    job.state = JobState::from_str(&value).unwrap_or({
                         // eprintln!("Failed to parse job state: {}", value);
                         JobState::Other
                     })
                 }
-                "%M" => job.time = value,
+                "%M" => job.time = parse_slurm_duration(&value).unwrap_or_default(),
+                "%l" => job.time_limit = parse_slurm_duration(&value),
                 "%D" => {
-                    job.nodes = value.parse::<u32>().unwrap_or_else(|_| {
+                    job.nodes = value.parse::<u32>().unwrap_or({
                         // eprintln!("Failed to parse node count: {}", value);
                         0
                     })
                 }
                 "%N" => job.node = Some(value),
                 "%C" => {
-                    job.cpus = value.parse::<u32>().unwrap_or_else(|_| {
+                    job.cpus = value.parse::<u32>().unwrap_or({
                         // eprintln!("Failed to parse CPU count: {}", value);
                         0
                     })
                 }
-                "%m" => job.memory = value,
+                "%m" => job.memory = super::memory::Memory::parse(&value),
                 "%P" => job.partition = value,
                 "%q" => job.qos = value,
                 "%a" => job.account = Some(value),
                 "%Q" => {
-                    job.priority = value.parse::<u32>().ok().or_else(|| {
+                    job.priority = value.parse::<u32>().ok().or({
                         // eprintln!("Failed to parse priority: {}", value);
                         None
                     })
                 }
                 "%Z" => job.work_dir = Some(value),
-                "%V" => job.submit_time = Some(value),
-                "%S" => job.start_time = Some(value),
-                "%e" => job.end_time = Some(value),
+                "%V" => job.submit_time = parse_slurm_time(&value),
+                "%S" => job.start_time = parse_slurm_time(&value),
+                "%e" => job.end_time = parse_slurm_time(&value),
+                "%b" => job.gres = super::tres::normalize_gres(&value),
+                "%v" if value != "(null)" && !value.is_empty() => job.reservation = Some(value),
+                "%d" => job.tmp_disk = super::memory::Memory::parse(&value).filter(|m| m.bytes > 0),
+                "%W" if value != "(null)" && !value.is_empty() => job.licenses = Some(value),
+                "%w" if value != "(null)" => job.wckey = Some(value),
+                "%k" if value != "(null)" => job.comment = Some(value),
+                // Pending jobs print `(Reason)`; running ones their node list
+                "%R" if value.starts_with('(') => job.pending_reason = Some(value),
+                "%R" => job.node = job.node.take().or(Some(value)),
                 _ => {
                     // eprintln!("Unknown format code: {}", format_codes[i]);
                 }
             }
         }
 
+        // %b is per node; without %D assume a single node
+        if let Some(gres) = &job.gres {
+            job.gpus = super::tres::gpus_per_node(gres) * job.nodes.max(1);
+        }
+
         jobs.push(job);
     }
 
     Ok(jobs)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::format_slurm_time;
+
+    #[test]
+    fn start_estimates_skip_jobs_without_one() {
+        let estimates = parse_start_estimates(
+            "101|2024-05-01T18:30:00\n102|N/A\n103_[1-4]|2024-05-02T02:00:00\n\n",
+        );
+        assert_eq!(format_slurm_time(&estimates["101"]), "2024-05-01T18:30:00");
+        assert_eq!(
+            format_slurm_time(&estimates["103_[1-4]"]),
+            "2024-05-02T02:00:00"
+        );
+        assert_eq!(estimates.len(), 2);
+    }
+
+    #[test]
+    fn tmp_disk_is_read_from_the_minimum_per_node() {
+        use std::os::unix::process::ExitStatusExt;
+        let output = Output {
+            status: std::process::ExitStatus::from_raw(0),
+            stdout: b"101|200G\n102|0\n".to_vec(),
+            stderr: Vec::new(),
+        };
+        let jobs = parse_squeue_output(&output, "%i|%d").unwrap();
+        assert_eq!(jobs[0].tmp_disk.unwrap().to_string(), "200G");
+        assert_eq!(jobs[1].tmp_disk, None);
+    }
+}
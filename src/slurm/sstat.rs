@@ -0,0 +1,78 @@
+use color_eyre::Result;
+
+use super::cache;
+use super::error::checked;
+
+/// Live usage fields requested from `sstat`, in order.
+pub const SSTAT_FIELDS: &[&str] = &[
+    "JobID",
+    "NTasks",
+    "AveCPU",
+    "AveRSS",
+    "MaxRSS",
+    "MaxRSSNode",
+    "AveVMSize",
+    "MaxDiskRead",
+    "MaxDiskWrite",
+];
+
+/// Live resource usage of one job step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepUsage {
+    /// `(field, value)` pairs in `SSTAT_FIELDS` order
+    pub fields: Vec<(String, String)>,
+}
+
+impl StepUsage {
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == field)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Query live usage of all steps of a running job (`sstat -a`).
+pub async fn run_sstat(job_id: &str) -> Result<Vec<StepUsage>> {
+    let args = vec![
+        "-a".to_string(),
+        "-n".to_string(),
+        "-P".to_string(),
+        "-j".to_string(),
+        job_id.to_string(),
+        "--format".to_string(),
+        SSTAT_FIELDS.join(","),
+    ];
+    let output = checked("sstat", cache::query("sstat", &args).await)?;
+
+    Ok(parse_sstat_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_sstat_output(stdout: &str) -> Vec<StepUsage> {
+    stdout
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|line| StepUsage {
+            fields: SSTAT_FIELDS
+                .iter()
+                .zip(line.split('|'))
+                .map(|(field, value)| (field.to_string(), value.trim().to_string()))
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_one_usage_row_per_step() {
+        let stdout = "123.batch|1|00:10:00|1024K|2048K|node01|4096K|1M|2M\n123.0|4|01:00:00|10G|12G|node02|20G|0|0\n";
+        let steps = parse_sstat_output(stdout);
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].get("JobID"), Some("123.batch"));
+        assert_eq!(steps[1].get("MaxRSS"), Some("12G"));
+        assert_eq!(steps[1].get("MaxRSSNode"), Some("node02"));
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use super::{Job, JobState};
+
+/// A job seen in another state than on the previous poll
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateChange {
+    pub id: String,
+    pub from: JobState,
+    pub to: JobState,
+}
+
+impl StateChange {
+    /// Whether the job ended with this change
+    pub fn finished(&self) -> bool {
+        self.from.is_active() && !self.to.is_active()
+    }
+}
+
+/// What a poll changed, by job id
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct JobDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Jobs still listed whose record differs, running jobs' elapsed time
+    /// included
+    pub changed: Vec<String>,
+    /// The changed jobs that are in another state
+    pub transitions: Vec<StateChange>,
+}
+
+/// The jobs of the last poll by id, in the order they were polled. Each
+/// poll is applied as a diff, so jobs keep their identity across refreshes.
+#[derive(Debug, Default)]
+pub struct JobStore {
+    jobs: HashMap<String, Job>,
+    order: Vec<String>,
+}
+
+impl JobStore {
+    /// Replace the stored jobs with the polled ones, returning what changed
+    pub fn apply(&mut self, polled: Vec<Job>) -> JobDiff {
+        let mut diff = JobDiff::default();
+        let mut jobs = HashMap::with_capacity(polled.len());
+        let mut order = Vec::with_capacity(polled.len());
+        for job in polled {
+            match self.jobs.remove(&job.id) {
+                None => diff.added.push(job.id.clone()),
+                Some(old) if old != job => {
+                    if old.state != job.state {
+                        diff.transitions.push(StateChange {
+                            id: job.id.clone(),
+                            from: old.state,
+                            to: job.state,
+                        });
+                    }
+                    diff.changed.push(job.id.clone());
+                }
+                Some(_) => {}
+            }
+            order.push(job.id.clone());
+            jobs.insert(job.id.clone(), job);
+        }
+        // What is left was not polled again
+        diff.removed = self
+            .order
+            .iter()
+            .filter(|id| self.jobs.contains_key(*id))
+            .cloned()
+            .collect();
+
+        self.jobs = jobs;
+        self.order = order;
+        diff
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Job> {
+        self.jobs.get(id)
+    }
+
+    /// The stored jobs in poll order
+    pub fn jobs(&self) -> Vec<Job> {
+        self.order
+            .iter()
+            .filter_map(|id| self.jobs.get(id))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_polls_as_diffs() {
+        let job = |id: &str, state| Job {
+            id: id.to_string(),
+            state,
+            ..Default::default()
+        };
+        let mut store = JobStore::default();
+        let diff = store.apply(vec![
+            job("1", JobState::Pending),
+            job("2", JobState::Running),
+        ]);
+        assert_eq!(diff.added, ["1", "2"]);
+
+        let diff = store.apply(vec![
+            job("3", JobState::Pending),
+            job("2", JobState::Completed),
+            job("1", JobState::Pending),
+        ]);
+        assert_eq!(diff.added, ["3"]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed, ["2"]);
+        assert_eq!(diff.transitions.len(), 1);
+        assert!(diff.transitions[0].finished());
+        let ids: Vec<String> = store.jobs().into_iter().map(|j| j.id).collect();
+        assert_eq!(ids, ["3", "2", "1"]);
+
+        let diff = store.apply(vec![job("1", JobState::Pending)]);
+        assert_eq!(diff.removed, ["3", "2"]);
+        assert!(diff.changed.is_empty());
+        assert_eq!(
+            store.apply(vec![job("1", JobState::Pending)]),
+            JobDiff::default()
+        );
+        assert_eq!(store.get("1").map(|j| j.state), Some(JobState::Pending));
+    }
+}
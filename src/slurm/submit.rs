@@ -0,0 +1,688 @@
+use async_process::{Command, Output, Stdio};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use futures_lite::io::AsyncWriteExt;
+use std::collections::HashMap;
+use std::time::Instant;
+
+use super::error::SlurmError;
+use super::memory::{Memory, MemoryScope};
+use super::parse_scontrol_output;
+use super::recording;
+use super::sacct::fetch_job_details;
+use super::tres::{gres_from_tres, normalize_gres};
+use super::{cache, command};
+
+/// Everything needed to submit a batch job again: the script plus the key
+/// `sbatch` options of the original submission. Command-line options take
+/// precedence over `#SBATCH` directives, so edited values win.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubmitSpec {
+    pub script: String,
+    pub job_name: String,
+    pub partition: String,
+    pub time_limit: String,
+    pub nodes: String,
+    pub ntasks: String,
+    pub cpus_per_task: String,
+    /// Per-node memory (`16G`), or per-CPU memory with a `/cpu` suffix (`4G/cpu`)
+    pub memory: String,
+    /// Generic resources, e.g. `gpu:2` or `gpu:a100:1`
+    pub gres: String,
+    pub account: String,
+    pub qos: String,
+    pub work_dir: String,
+    /// Any other sbatch options, as shell words (`--mail-type=END --exclusive`)
+    pub other: String,
+}
+
+/// Labels of the editable fields, in form order
+pub const SUBMIT_FIELDS: &[&str] = &[
+    "Job name",
+    "Partition",
+    "Time limit",
+    "Nodes",
+    "Tasks",
+    "CPUs per task",
+    "Memory",
+    "GRES",
+    "Account",
+    "QOS",
+    "Working dir",
+    "Other options",
+];
+
+/// sbatch options given as `--name value` or `--name=value` that have a field
+const FIELD_OPTIONS: &[(&str, Option<char>, &str)] = &[
+    ("job-name", Some('J'), "Job name"),
+    ("partition", Some('p'), "Partition"),
+    ("time", Some('t'), "Time limit"),
+    ("nodes", Some('N'), "Nodes"),
+    ("ntasks", Some('n'), "Tasks"),
+    ("cpus-per-task", Some('c'), "CPUs per task"),
+    ("mem", None, "Memory"),
+    ("gres", None, "GRES"),
+    ("account", Some('A'), "Account"),
+    ("qos", Some('q'), "QOS"),
+    ("chdir", Some('D'), "Working dir"),
+];
+
+/// Long sbatch options that take no value
+const LONG_FLAGS: &[&str] = &[
+    "contiguous",
+    "exclusive",
+    "get-user-env",
+    "hold",
+    "ignore-pbs",
+    "no-kill",
+    "no-requeue",
+    "overcommit",
+    "oversubscribe",
+    "parsable",
+    "quiet",
+    "reboot",
+    "requeue",
+    "spread-job",
+    "test-only",
+    "use-min-nodes",
+    "verbose",
+    "wait",
+];
+
+/// Short sbatch options that take no value
+const SHORT_FLAGS: &[char] = &['H', 'h', 'k', 'O', 'Q', 's', 'V', 'v', 'W'];
+
+/// Short forms of sbatch options without a field, by long name
+const SHORT_OPTIONS: &[(char, &str)] = &[
+    ('a', "array"),
+    ('C', "constraint"),
+    ('d', "dependency"),
+    ('e', "error"),
+    ('G', "gpus"),
+    ('o', "output"),
+    ('w', "nodelist"),
+    ('x', "exclude"),
+];
+
+/// One sbatch option as given (`-N 2`, `--mem=4G`, `--exclusive`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SbatchOption {
+    /// The name without dashes, a single letter for short options
+    pub name: String,
+    pub short: bool,
+    /// `None` for options that take no value
+    pub value: Option<String>,
+}
+
+impl SbatchOption {
+    /// Take apart the option `word`, reading its value from `words` when it
+    /// is a separate word; `None` when `word` is no option (the script)
+    pub fn next(
+        word: &str,
+        words: &mut impl Iterator<Item = String>,
+    ) -> Result<Option<Self>, String> {
+        // `--name=value`, `--name value`, `-Xvalue` or `-X value`
+        let (name, inline, short) = if let Some(long) = word.strip_prefix("--") {
+            match long.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string()), false),
+                None => (long.to_string(), None, false),
+            }
+        } else if word.len() > 1 && word.starts_with('-') {
+            let mut chars = word[1..].chars();
+            let flag = chars.next().unwrap_or_default();
+            let rest: String = chars.collect();
+            (flag.to_string(), (!rest.is_empty()).then_some(rest), true)
+        } else {
+            return Ok(None);
+        };
+
+        let takes_value = if short {
+            !name.chars().all(|c| SHORT_FLAGS.contains(&c))
+        } else {
+            !LONG_FLAGS.contains(&name.as_str())
+        };
+        let value = match (takes_value, inline) {
+            (false, _) => None,
+            (true, Some(value)) => Some(value),
+            (true, None) => Some(
+                words
+                    .next()
+                    .ok_or_else(|| format!("option {} needs a value", word))?,
+            ),
+        };
+        Ok(Some(Self { name, short, value }))
+    }
+
+    /// The long name, also for short options (`-N` is `nodes`)
+    pub fn long_name(&self) -> &str {
+        if !self.short {
+            return &self.name;
+        }
+        let flag = self.name.chars().next();
+        FIELD_OPTIONS
+            .iter()
+            .find(|(_, short, _)| *short == flag)
+            .map(|(long, _, _)| *long)
+            .or_else(|| {
+                SHORT_OPTIONS
+                    .iter()
+                    .find(|(short, _)| Some(*short) == flag)
+                    .map(|(_, long)| *long)
+            })
+            .unwrap_or(&self.name)
+    }
+}
+
+/// An `sbatch` command line taken apart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SbatchCommand {
+    /// Options in [`SubmitSpec`] fields; the script is not read yet
+    pub spec: SubmitSpec,
+    /// The batch script path, or `None` for `--wrap`
+    pub script_path: Option<String>,
+}
+
+impl SubmitSpec {
+    /// Rebuild the options from `scontrol show job -o` fields
+    pub fn from_scontrol(fields: &HashMap<String, String>) -> Self {
+        let get = |key: &str| {
+            fields
+                .get(key)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty() && v != "(null)" && v != "N/A")
+                .unwrap_or_default()
+        };
+
+        let memory = match (fields.get("MinMemoryNode"), fields.get("MinMemoryCPU")) {
+            (Some(node), _) if node != "0" => node.clone(),
+            (_, Some(cpu)) if cpu != "0" => format!("{}/cpu", cpu),
+            _ => String::new(),
+        };
+        // Newer Slurm reports `TresPerNode=gres/gpu:2`, older `Gres=gpu:2`
+        let gres = fields
+            .get("TresPerNode")
+            .or_else(|| fields.get("Gres"))
+            .and_then(|g| normalize_gres(g))
+            .unwrap_or_default();
+
+        Self {
+            job_name: get("JobName"),
+            partition: get("Partition"),
+            time_limit: get("TimeLimit"),
+            nodes: get("NumNodes"),
+            ntasks: get("NumTasks"),
+            cpus_per_task: get("CPUs/Task"),
+            memory,
+            gres,
+            account: get("Account"),
+            qos: get("QOS"),
+            work_dir: get("WorkDir"),
+            ..Default::default()
+        }
+    }
+
+    /// Rebuild the options from the sacct record, for jobs the controller has forgotten
+    pub fn from_sacct(fields: &[(String, String)]) -> Self {
+        let get = |key: &str| {
+            fields
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.trim().to_string())
+                .unwrap_or_default()
+        };
+
+        let memory = match Memory::parse(&get("ReqMem")) {
+            Some(m) if m.bytes == 0 => String::new(),
+            Some(m) if m.scope == MemoryScope::Cpu => format!("{}/cpu", m.to_slurm()),
+            Some(m) => m.to_slurm(),
+            None => String::new(),
+        };
+        let gres = gres_from_tres(&get("AllocTRES")).unwrap_or_default();
+
+        Self {
+            job_name: get("JobName"),
+            partition: get("Partition"),
+            time_limit: get("Timelimit"),
+            nodes: get("NNodes"),
+            memory,
+            gres,
+            account: get("Account"),
+            qos: get("QOS"),
+            work_dir: get("WorkDir"),
+            ..Default::default()
+        }
+    }
+
+    /// Value of the field labelled `label` (see `SUBMIT_FIELDS`)
+    pub fn field(&self, label: &str) -> &str {
+        match label {
+            "Job name" => &self.job_name,
+            "Partition" => &self.partition,
+            "Time limit" => &self.time_limit,
+            "Nodes" => &self.nodes,
+            "Tasks" => &self.ntasks,
+            "CPUs per task" => &self.cpus_per_task,
+            "Memory" => &self.memory,
+            "GRES" => &self.gres,
+            "Account" => &self.account,
+            "QOS" => &self.qos,
+            "Working dir" => &self.work_dir,
+            "Other options" => &self.other,
+            _ => "",
+        }
+    }
+
+    pub fn set_field(&mut self, label: &str, value: String) {
+        let slot = match label {
+            "Job name" => &mut self.job_name,
+            "Partition" => &mut self.partition,
+            "Time limit" => &mut self.time_limit,
+            "Nodes" => &mut self.nodes,
+            "Tasks" => &mut self.ntasks,
+            "CPUs per task" => &mut self.cpus_per_task,
+            "Memory" => &mut self.memory,
+            "GRES" => &mut self.gres,
+            "Account" => &mut self.account,
+            "QOS" => &mut self.qos,
+            "Working dir" => &mut self.work_dir,
+            "Other options" => &mut self.other,
+            _ => return,
+        };
+        *slot = value.trim().to_string();
+    }
+
+    /// Validate a field value before submission
+    pub fn validate_field(label: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(());
+        }
+        match label {
+            "Time limit" => validate_time_limit(value),
+            "Nodes" => {
+                // A count or a min-max range
+                let ok = value
+                    .split('-')
+                    .all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+                if ok {
+                    Ok(())
+                } else {
+                    Err(format!("invalid node count: {}", value))
+                }
+            }
+            "Tasks" | "CPUs per task" => value
+                .parse::<u32>()
+                .map(|_| ())
+                .map_err(|_| format!("{} must be a number", label)),
+            "Other options" => split_shell_words(value).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    /// `sbatch` options for this spec, empty fields omitted
+    pub fn to_sbatch_args(&self) -> Vec<String> {
+        let mut args = vec!["--parsable".to_string()];
+        let mut push = |flag: &str, value: &str| {
+            if !value.is_empty() {
+                args.push(format!("--{}={}", flag, value));
+            }
+        };
+        push("job-name", &self.job_name);
+        push("partition", &self.partition);
+        push("time", &self.time_limit);
+        push("nodes", &self.nodes);
+        push("ntasks", &self.ntasks);
+        push("cpus-per-task", &self.cpus_per_task);
+        match self.memory.strip_suffix("/cpu") {
+            Some(per_cpu) => push("mem-per-cpu", per_cpu),
+            None => push("mem", &self.memory),
+        }
+        push("gres", &self.gres);
+        push("account", &self.account);
+        push("qos", &self.qos);
+        push("chdir", &self.work_dir);
+        args.extend(split_shell_words(&self.other).unwrap_or_default());
+        args
+    }
+
+    /// Take apart a pasted `sbatch [options] script.sh` command line. Options
+    /// without a field go to "Other options"; `--wrap` becomes the script.
+    pub fn from_command_line(line: &str) -> Result<SbatchCommand, String> {
+        let words = split_shell_words(line)?;
+        let mut words = words.into_iter().peekable();
+        // Environment assignments (`FOO=1 sbatch ...`) cannot be kept
+        while words.peek().is_some_and(|w| {
+            w.split_once('=')
+                .is_some_and(|(name, _)| !name.is_empty() && !name.starts_with('-'))
+        }) {
+            words.next();
+        }
+        if words
+            .peek()
+            .is_some_and(|w| w == "sbatch" || w.ends_with("/sbatch"))
+        {
+            words.next();
+        }
+
+        let mut spec = SubmitSpec::default();
+        let mut other: Vec<String> = Vec::new();
+        let mut wrap = None;
+        let mut script_path = None;
+        while let Some(word) = words.next() {
+            let Some(SbatchOption { name, short, value }) = SbatchOption::next(&word, &mut words)?
+            else {
+                script_path = Some(word);
+                break;
+            };
+
+            let field = FIELD_OPTIONS.iter().find(|(long, flag, _)| {
+                if short {
+                    flag.is_some_and(|f| name == f.to_string())
+                } else {
+                    *long == name
+                }
+            });
+            match (field, value) {
+                (Some((_, _, label)), Some(value)) => spec.set_field(label, value),
+                (None, Some(value)) if !short && name == "mem-per-cpu" => {
+                    spec.memory = format!("{}/cpu", value.trim());
+                }
+                (None, Some(value)) if !short && name == "wrap" => wrap = Some(value),
+                // Added by Slurmer itself
+                (None, None) if !short && name == "parsable" => {}
+                (None, Some(value)) if short => {
+                    other.extend([format!("-{}", name), value]);
+                }
+                (None, Some(value)) => other.push(format!("--{}={}", name, value)),
+                _ => other.push(word),
+            }
+        }
+
+        if words.next().is_some() {
+            return Err("script arguments cannot be passed on; put them in the script".to_string());
+        }
+        match (wrap, &script_path) {
+            (Some(command), None) => spec.script = format!("#!/bin/sh\n{}\n", command),
+            (Some(_), Some(_)) => return Err("use either --wrap or a script, not both".to_string()),
+            (None, None) => return Err("no batch script given".to_string()),
+            (None, Some(_)) => {}
+        }
+        spec.other = join_shell_words(&other);
+        Ok(SbatchCommand { spec, script_path })
+    }
+}
+
+/// Split a command line into words like a POSIX shell: single and double
+/// quotes, backslash escapes and `\`-newline continuations
+pub fn split_shell_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let text = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => text.push(c),
+                        None => return Err("unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let text = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => text.push(c),
+                            Some('\n') => {}
+                            Some(c) => text.extend(['\\', c]),
+                            None => return Err("unterminated double quote".to_string()),
+                        },
+                        Some(c) => text.push(c),
+                        None => return Err("unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => {}
+            },
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// The inverse of [`split_shell_words`], quoting only where needed
+fn join_shell_words(words: &[String]) -> String {
+    words
+        .iter()
+        .map(|w| {
+            let plain = !w.is_empty()
+                && w.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_=:,./@%+".contains(c));
+            if plain {
+                w.clone()
+            } else {
+                format!("'{}'", w.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Accept the time formats `sbatch --time` does: `MM`, `MM:SS`, `HH:MM:SS`,
+/// `D-HH`, `D-HH:MM`, `D-HH:MM:SS`, plus `UNLIMITED`/`INFINITE`.
+pub fn validate_time_limit(value: &str) -> Result<(), String> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("unlimited") || value.eq_ignore_ascii_case("infinite") {
+        return Ok(());
+    }
+    let invalid = || {
+        Err(format!(
+            "invalid time limit: {} (use e.g. 90, 4:00:00 or 1-12:00)",
+            value
+        ))
+    };
+    let is_num = |s: &str| !s.is_empty() && s.len() <= 4 && s.chars().all(|c| c.is_ascii_digit());
+
+    let (days, rest) = match value.split_once('-') {
+        Some((d, rest)) => (Some(d), rest),
+        None => (None, value),
+    };
+    if days.is_some_and(|d| !is_num(d)) {
+        return invalid();
+    }
+    let parts: Vec<&str> = rest.split(':').collect();
+    if !parts.iter().all(|p| is_num(p)) {
+        return invalid();
+    }
+    let max_parts = 3;
+    if parts.len() > max_parts {
+        return invalid();
+    }
+    // Minutes/seconds fields after the first must be < 60
+    if parts
+        .iter()
+        .skip(1)
+        .any(|p| p.parse::<u32>().unwrap_or(60) >= 60)
+    {
+        return invalid();
+    }
+    Ok(())
+}
+
+/// Reconstruct the submission of `job_id` (without its script) from the
+/// controller, or from accounting once the controller has forgotten the job.
+pub async fn fetch_submit_spec(job_id: &str) -> Result<SubmitSpec> {
+    let output = command::output("scontrol", ["show", "job", job_id, "-o"])
+        .await
+        .map_err(|e| SlurmError::from_io("scontrol", &e))?;
+    if output.status.success() {
+        let fields = parse_scontrol_output(&String::from_utf8_lossy(&output.stdout));
+        if !fields.is_empty() {
+            return Ok(SubmitSpec::from_scontrol(&fields));
+        }
+    }
+
+    let fields = fetch_job_details(job_id).await?;
+    Ok(SubmitSpec::from_sacct(&fields))
+}
+
+/// Submit `spec` with sbatch (script on stdin) and return the new job id
+pub async fn submit(spec: &SubmitSpec) -> Result<String> {
+    if spec.script.trim().is_empty() {
+        return Err(eyre!("no batch script to submit"));
+    }
+
+    let args = spec.to_sbatch_args();
+    // A replay never submits for real
+    if let Some(replayed) = recording::replayed("sbatch", &args) {
+        return job_id_of(&replayed.map_err(|e| SlurmError::from_io("sbatch", &e))?);
+    }
+    let started = Instant::now();
+    let mut child = Command::new("sbatch")
+        .args(&args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| SlurmError::from_io("sbatch", &e))?;
+    let stdin = child.stdin.take();
+    let output = command::with_timeout("sbatch", async move {
+        if let Some(mut stdin) = stdin {
+            stdin.write_all(spec.script.as_bytes()).await?;
+            // Dropping stdin closes it so sbatch starts reading the script
+        }
+        child.output().await
+    })
+    .await;
+    cache::invalidate();
+    let logged = output.as_ref().map(|o| (&o.status, o.stderr.as_slice()));
+    command::log_run("sbatch", &args, started.elapsed(), logged);
+    let output = output.map_err(|e| SlurmError::from_io("sbatch", &e))?;
+    recording::record("sbatch", &args, &output);
+    job_id_of(&output)
+}
+
+/// The id of the job sbatch submitted, from what it printed
+fn job_id_of(output: &Output) -> Result<String> {
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SlurmError::from_stderr("sbatch", &stderr).into());
+    }
+
+    // --parsable prints `<jobid>` or `<jobid>;<cluster>`
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let job_id = stdout.trim().split(';').next().unwrap_or("").to_string();
+    if job_id.is_empty() {
+        return Err(eyre!("sbatch did not report a job id"));
+    }
+    Ok(job_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebuilds_options_from_scontrol() {
+        let fields = parse_scontrol_output(
+            "JobId=7 JobName=train Partition=gpu TimeLimit=1-00:00:00 NumNodes=1 NumTasks=1 \
+             CPUs/Task=8 MinMemoryNode=32G TresPerNode=gres/gpu:2 Account=lab QOS=normal \
+             WorkDir=/home/u/proj",
+        );
+        let spec = SubmitSpec::from_scontrol(&fields);
+        assert_eq!(
+            spec.to_sbatch_args(),
+            vec![
+                "--parsable",
+                "--job-name=train",
+                "--partition=gpu",
+                "--time=1-00:00:00",
+                "--nodes=1",
+                "--ntasks=1",
+                "--cpus-per-task=8",
+                "--mem=32G",
+                "--gres=gpu:2",
+                "--account=lab",
+                "--qos=normal",
+                "--chdir=/home/u/proj",
+            ]
+        );
+    }
+
+    #[test]
+    fn rebuilds_memory_and_gres_from_sacct() {
+        let fields: Vec<(String, String)> = [
+            ("ReqMem", "4000Mc"),
+            ("AllocTRES", "billing=8,cpu=8,gres/gpu=2,mem=32G,node=1"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let spec = SubmitSpec::from_sacct(&fields);
+        assert_eq!(spec.memory, "4000M/cpu");
+        assert_eq!(spec.gres, "gpu:2");
+        assert!(spec
+            .to_sbatch_args()
+            .contains(&"--mem-per-cpu=4000M".to_string()));
+    }
+
+    #[test]
+    fn parses_pasted_sbatch_command() {
+        let command = SubmitSpec::from_command_line(
+            "sbatch -p gpu --gres=gpu:a100:2 -c8 --mem-per-cpu 4G \\\n  \
+             -J 'my run' --mail-type=END -o logs/%j.out --exclusive train.sh",
+        )
+        .unwrap();
+        let spec = &command.spec;
+        assert_eq!(command.script_path.as_deref(), Some("train.sh"));
+        assert_eq!(
+            (
+                spec.partition.as_str(),
+                spec.gres.as_str(),
+                spec.job_name.as_str()
+            ),
+            ("gpu", "gpu:a100:2", "my run")
+        );
+        assert_eq!(
+            (spec.cpus_per_task.as_str(), spec.memory.as_str()),
+            ("8", "4G/cpu")
+        );
+        assert_eq!(spec.other, "--mail-type=END -o logs/%j.out --exclusive");
+        assert!(spec
+            .to_sbatch_args()
+            .ends_with(&["--mail-type=END", "-o", "logs/%j.out", "--exclusive"].map(String::from)));
+
+        let wrapped = SubmitSpec::from_command_line("sbatch -t 10 --wrap=\"hostname -f\"").unwrap();
+        assert_eq!(wrapped.spec.script, "#!/bin/sh\nhostname -f\n");
+        assert!(SubmitSpec::from_command_line("sbatch job.sh --epochs 3").is_err());
+        assert!(SubmitSpec::from_command_line("sbatch -p").is_err());
+    }
+
+    #[test]
+    fn time_limit_validation() {
+        for ok in [
+            "90",
+            "30:00",
+            "4:00:00",
+            "1-12",
+            "1-12:30",
+            "2-00:00:00",
+            "UNLIMITED",
+        ] {
+            assert!(validate_time_limit(ok).is_ok(), "{}", ok);
+        }
+        for bad in ["", "1h", "4:70:00", "1-2-3", "1:2:3:4", "-5"] {
+            assert!(validate_time_limit(bad).is_err(), "{}", bad);
+        }
+    }
+}
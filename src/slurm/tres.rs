@@ -0,0 +1,230 @@
+use color_eyre::Result;
+
+use super::error::checked;
+use super::memory::Memory;
+use super::{command, parse_scontrol_output};
+
+/// Normalize a per-node GRES string as squeue `%b` or scontrol `TresPerNode`
+/// print it (`gres/gpu:a100:2(IDX:0-1)` -> `gpu:a100:2`); `None` when the job
+/// requests no generic resources.
+pub fn normalize_gres(raw: &str) -> Option<String> {
+    let entries: Vec<&str> = raw
+        .split(',')
+        .map(|e| e.trim().trim_start_matches("gres/"))
+        .map(|e| e.split('(').next().unwrap_or("").trim())
+        .filter(|e| !e.is_empty() && *e != "N/A" && *e != "(null)")
+        .collect();
+    if entries.is_empty() {
+        None
+    } else {
+        Some(entries.join(","))
+    }
+}
+
+/// GPUs per node requested by a normalized GRES string (`gpu:a100:2` -> 2)
+pub fn gpus_per_node(gres: &str) -> u32 {
+    gres.split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(':');
+            if parts.next()? != "gpu" {
+                return None;
+            }
+            // `gpu`, `gpu:<type>`, `gpu:<count>` or `gpu:<type>:<count>`
+            Some(
+                parts
+                    .next_back()
+                    .and_then(|count| count.parse::<u32>().ok())
+                    .unwrap_or(1),
+            )
+        })
+        .sum()
+}
+
+/// `gres/...` entries of a TRES string (sacct `AllocTRES`/`ReqTRES`, job
+/// totals like `cpu=8,gres/gpu=2,gres/gpu:a100=2`) as `(name, count)`, e.g.
+/// `("gpu:a100", 2)`. Untyped totals (`gres/gpu=2`) are dropped when typed
+/// entries of the same resource are present, so nothing is counted twice.
+fn gres_entries(tres: &str) -> Vec<(&str, u32)> {
+    let entries: Vec<(&str, u32)> = tres
+        .split(',')
+        .filter_map(|t| t.trim().strip_prefix("gres/"))
+        .filter_map(|t| {
+            let (name, count) = t.split_once('=')?;
+            Some((name, count.parse::<u32>().unwrap_or(0)))
+        })
+        .collect();
+    entries
+        .iter()
+        .filter(|(name, _)| {
+            name.contains(':')
+                || !entries
+                    .iter()
+                    .any(|(other, _)| other.starts_with(&format!("{}:", name)))
+        })
+        .copied()
+        .collect()
+}
+
+/// GRES of a TRES string in GRES notation (`gres/gpu:a100=2` -> `gpu:a100:2`)
+pub fn gres_from_tres(tres: &str) -> Option<String> {
+    let entries: Vec<String> = gres_entries(tres)
+        .into_iter()
+        .map(|(name, count)| format!("{}:{}", name, count))
+        .collect();
+    if entries.is_empty() {
+        None
+    } else {
+        Some(entries.join(","))
+    }
+}
+
+/// Total GPUs in a TRES string
+pub fn gpus_from_tres(tres: &str) -> u32 {
+    gres_entries(tres)
+        .into_iter()
+        .filter(|(name, _)| name.split(':').next() == Some("gpu"))
+        .map(|(_, count)| count)
+        .sum()
+}
+
+/// Sum of the `<prefix>...=<size>` entries of a TRES string
+fn storage_from_tres(tres: &str, prefix: &str) -> Option<Memory> {
+    let bytes: Vec<u64> = tres
+        .split(',')
+        .filter_map(|t| t.trim().strip_prefix(prefix))
+        .filter_map(|t| Memory::parse(t.split_once('=')?.1))
+        .map(|m| m.bytes)
+        .collect();
+    (!bytes.is_empty()).then(|| Memory {
+        bytes: bytes.iter().sum(),
+        ..Default::default()
+    })
+}
+
+/// Burst buffer space of a TRES string (`bb/datawarp=1T`)
+pub fn burst_buffer_from_tres(tres: &str) -> Option<Memory> {
+    storage_from_tres(tres, "bb/")
+}
+
+/// Local scratch per node of a TRES string. `fs/disk` is the total of the
+/// job (`fs/disk=400G,node=2` is 200G on each node).
+pub fn tmp_disk_from_tres(tres: &str) -> Option<Memory> {
+    let total = storage_from_tres(tres, "fs/disk")?;
+    let nodes = tres
+        .split(',')
+        .find_map(|t| t.trim().strip_prefix("node=")?.parse::<u64>().ok())
+        .unwrap_or(1)
+        .max(1);
+    Some(Memory {
+        bytes: total.bytes / nodes,
+        ..total
+    })
+}
+
+/// Local scratch per node and burst buffer space a job asked for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Storage {
+    pub tmp_disk: Option<Memory>,
+    pub burst_buffer: Option<Memory>,
+}
+
+/// What a `scontrol show job -o` line says of the job's storage:
+/// `MinTmpDiskNode`, and the `capacity=` options of its `BurstBuffer` spec
+/// (`BurstBuffer=#DW jobdw capacity=1TiB access_mode=striped`), which runs
+/// on over spaces up to the next field
+pub fn storage_from_scontrol(output: &str) -> Storage {
+    let fields = parse_scontrol_output(output);
+    let tmp_disk = fields
+        .get("MinTmpDiskNode")
+        .and_then(|v| Memory::parse(v))
+        .filter(|m| m.bytes > 0);
+
+    let mut tokens = output.split_whitespace();
+    let spec: Vec<&str> =
+        tokens
+            .find_map(|t| t.strip_prefix("BurstBuffer="))
+            .into_iter()
+            .chain(tokens.take_while(|t| {
+                !(t.starts_with(|c: char| c.is_ascii_uppercase()) && t.contains('='))
+            }))
+            .collect();
+    let bytes: Vec<u64> = spec
+        .iter()
+        .filter_map(|t| t.strip_prefix("capacity="))
+        .map(|c| c.trim_end_matches("iB").trim_end_matches('B'))
+        .filter_map(|c| Some(Memory::parse(c)?.bytes))
+        .collect();
+    let burst_buffer = (!bytes.is_empty()).then(|| Memory {
+        bytes: bytes.iter().sum(),
+        ..Default::default()
+    });
+    Storage {
+        tmp_disk,
+        burst_buffer,
+    }
+}
+
+/// The storage of a pending or running job, from `scontrol show job`
+pub async fn fetch_storage(job_id: &str) -> Result<Storage> {
+    let output = command::output("scontrol", ["show", "job", job_id, "-o"]).await;
+    let output = checked("scontrol", output)?;
+    Ok(storage_from_scontrol(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gres_strings_normalize_and_count() {
+        assert_eq!(normalize_gres("N/A"), None);
+        assert_eq!(normalize_gres("(null)"), None);
+        assert_eq!(
+            normalize_gres("gres/gpu:a100:2(IDX:0-1),gres/shard:4").as_deref(),
+            Some("gpu:a100:2,shard:4")
+        );
+        assert_eq!(gpus_per_node("gpu:a100:2,shard:4"), 2);
+        assert_eq!(gpus_per_node("gpu:4"), 4);
+        assert_eq!(gpus_per_node("gpu"), 1);
+        assert_eq!(gpus_per_node("gpu:v100"), 1);
+        assert_eq!(gpus_per_node("shard:4"), 0);
+    }
+
+    #[test]
+    fn tres_totals_are_not_double_counted() {
+        let tres = "billing=8,cpu=8,gres/gpu=2,gres/gpu:a100=2,mem=32G,node=1";
+        assert_eq!(gpus_from_tres(tres), 2);
+        assert_eq!(gres_from_tres(tres).as_deref(), Some("gpu:a100:2"));
+        assert_eq!(gpus_from_tres("cpu=4,gres/gpu=1"), 1);
+        assert_eq!(gres_from_tres("cpu=4,mem=8G"), None);
+        assert_eq!(gpus_from_tres(""), 0);
+
+        let storage = "cpu=4,mem=8G,fs/disk=200G,bb/datawarp=1T,bb/lua=512G";
+        assert_eq!(tmp_disk_from_tres(storage).unwrap().to_string(), "200G");
+        assert_eq!(
+            burst_buffer_from_tres(storage).unwrap().bytes,
+            1536 * 1024 * 1024 * 1024
+        );
+        assert_eq!(burst_buffer_from_tres(tres), None);
+
+        // The fs/disk total is shared out over the nodes
+        let total = "cpu=8,fs/disk=400G,mem=16G,node=2";
+        assert_eq!(tmp_disk_from_tres(total).unwrap().to_string(), "200G");
+    }
+
+    #[test]
+    fn scontrol_storage_reads_the_burst_buffer_spec() {
+        let line = "JobId=7 JobName=sim MinCPUsNode=1 MinMemoryNode=4G \
+                    MinTmpDiskNode=100G Features=(null) \
+                    BurstBuffer=#DW jobdw capacity=1TiB access_mode=striped type=scratch \
+                    BurstBufferState=allocated Power=";
+        let storage = storage_from_scontrol(line);
+        assert_eq!(storage.tmp_disk.unwrap().to_string(), "100G");
+        assert_eq!(storage.burst_buffer.unwrap().to_string(), "1T");
+
+        let none = storage_from_scontrol("JobId=8 MinTmpDiskNode=0 BurstBuffer=(null) Power=");
+        assert_eq!(none, Storage::default());
+    }
+}
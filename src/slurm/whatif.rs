@@ -0,0 +1,168 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::memory::Memory;
+use super::{Job, JobState};
+
+/// CPUs, GPUs and memory held by running jobs or asked for by pending ones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Resources {
+    pub cpus: u64,
+    pub gpus: u64,
+    pub memory: u64,
+}
+
+impl Resources {
+    /// Everything `job` holds or requests, over all its nodes
+    pub fn of(job: &Job) -> Self {
+        Self {
+            cpus: u64::from(job.cpus),
+            gpus: u64::from(job.gpus),
+            memory: job.memory_per_node().unwrap_or(0) * u64::from(job.nodes.max(1)),
+        }
+    }
+
+    fn fits(&self, need: &Resources) -> bool {
+        need.cpus <= self.cpus && need.gpus <= self.gpus && need.memory <= self.memory
+    }
+
+    fn add(&mut self, other: &Resources) {
+        self.cpus += other.cpus;
+        self.gpus += other.gpus;
+        self.memory += other.memory;
+    }
+
+    fn take(&mut self, other: &Resources) {
+        self.cpus -= other.cpus;
+        self.gpus -= other.gpus;
+        self.memory -= other.memory;
+    }
+}
+
+impl fmt::Display for Resources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} CPUs", self.cpus)?;
+        if self.gpus > 0 {
+            write!(f, ", {} GPUs", self.gpus)?;
+        }
+        if self.memory > 0 {
+            let memory = Memory {
+                bytes: self.memory,
+                ..Default::default()
+            };
+            write!(f, ", {}", memory)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of cancelling a set of running jobs
+#[derive(Debug, Default)]
+pub struct CancelWhatIf {
+    /// Resources given back, per partition
+    pub freed: BTreeMap<String, Resources>,
+    /// Pending jobs that fit into the freed resources, as `(job id, partition)`
+    pub startable: Vec<(String, String)>,
+}
+
+/// Simulate cancelling `cancelled`: add up what the running ones hold per
+/// partition, then place `pending` jobs greedily (highest priority first,
+/// then oldest) into the first of their partitions with enough left over.
+/// Only the freed resources are counted, not capacity that is idle already
+/// or claimed by other users' jobs, so this is a rough lower bound.
+pub fn simulate_cancel(cancelled: &[&Job], pending: &[&Job]) -> CancelWhatIf {
+    let mut freed: BTreeMap<String, Resources> = BTreeMap::new();
+    for job in cancelled.iter().filter(|j| j.state == JobState::Running) {
+        freed
+            .entry(job.partition.clone())
+            .or_default()
+            .add(&Resources::of(job));
+    }
+
+    let mut queue: Vec<&Job> = pending
+        .iter()
+        .copied()
+        .filter(|p| p.state == JobState::Pending)
+        .filter(|p| !cancelled.iter().any(|c| c.id == p.id))
+        .collect();
+    queue.sort_by_key(|j| (Reverse(j.priority), j.submit_time, j.id.clone()));
+
+    let mut left = freed.clone();
+    let mut startable = Vec::new();
+    for job in queue {
+        let need = Resources::of(job);
+        let partition = job
+            .partition
+            .split(',')
+            .find(|p| left.get(*p).is_some_and(|pool| pool.fits(&need)));
+        if let Some(partition) = partition {
+            if let Some(pool) = left.get_mut(partition) {
+                pool.take(&need);
+            }
+            startable.push((job.id.clone(), partition.to_string()));
+        }
+    }
+
+    CancelWhatIf { freed, startable }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: &str, state: JobState, partition: &str, cpus: u32, gpus: u32) -> Job {
+        Job {
+            id: id.to_string(),
+            state,
+            partition: partition.to_string(),
+            cpus,
+            gpus,
+            nodes: 1,
+            memory: Memory::parse("4Gc"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn freed_resources_go_to_highest_priority_first() {
+        let big = job("1", JobState::Running, "gpu", 16, 4);
+        let small = job("2", JobState::Running, "cpu", 4, 0);
+        let wants_gpus = Job {
+            priority: Some(10),
+            ..job("10", JobState::Pending, "gpu", 8, 2)
+        };
+        let wants_more = Job {
+            priority: Some(5),
+            ..job("11", JobState::Pending, "gpu", 8, 4)
+        };
+        let either = Job {
+            priority: Some(1),
+            ..job("12", JobState::Pending, "cpu,gpu", 8, 2)
+        };
+
+        let what_if = simulate_cancel(&[&big, &small], &[&wants_gpus, &wants_more, &either]);
+        assert_eq!(
+            what_if.freed["gpu"],
+            Resources {
+                cpus: 16,
+                gpus: 4,
+                memory: 64 << 30
+            }
+        );
+        assert_eq!(what_if.freed["gpu"].to_string(), "16 CPUs, 4 GPUs, 64G");
+        // 11 no longer fits after 10 took half the GPUs; 12 is too big for
+        // cpu but takes the rest of gpu
+        assert_eq!(
+            what_if.startable,
+            vec![
+                ("10".to_string(), "gpu".to_string()),
+                ("12".to_string(), "gpu".to_string())
+            ]
+        );
+
+        // Cancelling pending jobs frees nothing
+        let what_if = simulate_cancel(&[&wants_gpus], &[&wants_gpus, &either]);
+        assert!(what_if.freed.is_empty() && what_if.startable.is_empty());
+    }
+}
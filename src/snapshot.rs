@@ -0,0 +1,133 @@
+use color_eyre::eyre::WrapErr;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::cache_dir;
+use crate::notes::write_atomic;
+use crate::slurm::{Job, JobState};
+
+/// The most recently fetched job list, cached on disk so headless commands
+/// (e.g. `slurmer status`) can answer without querying Slurm again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Unix timestamp (seconds) of when the jobs were fetched
+    pub taken_at: u64,
+    pub jobs: Vec<SnapshotJob>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotJob {
+    pub id: String,
+    pub state: String,
+}
+
+impl Snapshot {
+    pub fn from_jobs(jobs: &[Job]) -> Self {
+        Self {
+            taken_at: unix_now(),
+            jobs: jobs
+                .iter()
+                .map(|j| SnapshotJob {
+                    id: j.id.clone(),
+                    state: j.state.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    /// How long ago the snapshot was taken
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(unix_now().saturating_sub(self.taken_at))
+    }
+
+    /// Job counts per state, in the usual display order, omitting empty states
+    pub fn state_counts(&self) -> Vec<(JobState, usize)> {
+        let mut order = JobState::get_available_states();
+        order.push(JobState::Other);
+
+        order
+            .into_iter()
+            .map(|state| {
+                let count = self
+                    .jobs
+                    .iter()
+                    .filter(|j| {
+                        j.state
+                            .parse::<JobState>()
+                            .map_or(JobState::Other, JobState::kind)
+                            == state
+                    })
+                    .count();
+                (state, count)
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+}
+
+/// Format state counts for shell prompts, e.g. `R:3 PD:12 F:1`
+pub fn format_porcelain(counts: &[(JobState, usize)]) -> String {
+    counts
+        .iter()
+        .map(|(state, count)| format!("{}:{}", state.short_code(), count))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn load_snapshot() -> Result<Option<Snapshot>> {
+    let path = snapshot_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = fs::read_to_string(&path).wrap_err("failed reading snapshot")?;
+    let snapshot = toml::from_str(&raw).wrap_err("failed parsing snapshot")?;
+    Ok(Some(snapshot))
+}
+
+pub fn save_snapshot(snapshot: &Snapshot) -> Result<()> {
+    let raw = toml::to_string(snapshot).wrap_err("failed serializing snapshot")?;
+    // Readers, and other sessions writing it, never see a partial snapshot
+    write_atomic(&snapshot_path()?, &raw).wrap_err("failed writing snapshot")
+}
+
+fn snapshot_path() -> Result<PathBuf> {
+    Ok(cache_dir()?.join("snapshot.toml"))
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn porcelain_counts_skip_empty_states() {
+        let jobs = vec![
+            Job {
+                id: "1".into(),
+                state: JobState::Running,
+                ..Default::default()
+            },
+            Job {
+                id: "2".into(),
+                state: JobState::Pending,
+                ..Default::default()
+            },
+            Job {
+                id: "3".into(),
+                state: JobState::Pending,
+                ..Default::default()
+            },
+        ];
+        let snapshot = Snapshot::from_jobs(&jobs);
+        assert_eq!(format_porcelain(&snapshot.state_counts()), "PD:2 R:1");
+    }
+}
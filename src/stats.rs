@@ -0,0 +1,218 @@
+use chrono::{DateTime, Local};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::slurm::{energy::kwh, FailureReason, Job, JobState};
+use crate::timeline::queue_wait;
+
+/// What the statistics are aggregated over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    User,
+    Account,
+    Partition,
+}
+
+impl GroupBy {
+    pub fn next(self) -> Self {
+        match self {
+            GroupBy::User => GroupBy::Account,
+            GroupBy::Account => GroupBy::Partition,
+            GroupBy::Partition => GroupBy::User,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            GroupBy::User => "User",
+            GroupBy::Account => "Account",
+            GroupBy::Partition => "Partition",
+        }
+    }
+
+    fn key(self, job: &Job) -> &str {
+        match self {
+            GroupBy::User => &job.user,
+            GroupBy::Account => job.account.as_deref().unwrap_or("-"),
+            GroupBy::Partition => &job.partition,
+        }
+    }
+}
+
+/// Totals of the jobs of one user, account or partition
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GroupStats {
+    pub key: String,
+    pub jobs: usize,
+    pub pending: usize,
+    pub running: usize,
+    pub completed: usize,
+    /// Ended jobs with a [`FailureReason`] other than being cancelled
+    pub failed: usize,
+    pub cancelled: usize,
+    pub cpu_hours: f64,
+    pub gpu_hours: f64,
+    /// Energy consumed, where Slurm gathers it (0 otherwise)
+    pub kwh: f64,
+    /// Over the jobs that started
+    pub median_wait: Option<Duration>,
+}
+
+impl GroupStats {
+    /// Share of the ended jobs that failed
+    pub fn failure_rate(&self) -> Option<f64> {
+        let ended = self.completed + self.failed + self.cancelled;
+        (ended > 0).then(|| self.failed as f64 / ended as f64)
+    }
+}
+
+/// Column the statistics table is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsSort {
+    Key,
+    Jobs,
+    CpuHours,
+    GpuHours,
+    Energy,
+    MedianWait,
+    FailureRate,
+}
+
+impl StatsSort {
+    pub fn next(self) -> Self {
+        match self {
+            StatsSort::Key => StatsSort::Jobs,
+            StatsSort::Jobs => StatsSort::CpuHours,
+            StatsSort::CpuHours => StatsSort::GpuHours,
+            StatsSort::GpuHours => StatsSort::Energy,
+            StatsSort::Energy => StatsSort::MedianWait,
+            StatsSort::MedianWait => StatsSort::FailureRate,
+            StatsSort::FailureRate => StatsSort::Key,
+        }
+    }
+
+    /// Order `stats` by this column, ascending; unknown values first
+    pub fn sort(self, stats: &mut [GroupStats]) {
+        let by_f64 = |a: Option<f64>, b: Option<f64>| a.partial_cmp(&b).unwrap();
+        stats.sort_by(|a, b| {
+            let order = match self {
+                StatsSort::Key => a.key.cmp(&b.key),
+                StatsSort::Jobs => a.jobs.cmp(&b.jobs),
+                StatsSort::CpuHours => by_f64(Some(a.cpu_hours), Some(b.cpu_hours)),
+                StatsSort::GpuHours => by_f64(Some(a.gpu_hours), Some(b.gpu_hours)),
+                StatsSort::Energy => by_f64(Some(a.kwh), Some(b.kwh)),
+                StatsSort::MedianWait => a.median_wait.cmp(&b.median_wait),
+                StatsSort::FailureRate => by_f64(a.failure_rate(), b.failure_rate()),
+            };
+            order.then_with(|| a.key.cmp(&b.key))
+        });
+    }
+}
+
+/// Job counts per state, CPU- and GPU-hours used (elapsed time times the
+/// allocation), energy consumed, median queue wait and failure rate of `jobs`, per group
+/// and ordered by group
+pub fn group_stats(jobs: &[Job], by: GroupBy, now: DateTime<Local>) -> Vec<GroupStats> {
+    let mut groups: BTreeMap<&str, (GroupStats, Vec<Duration>)> = BTreeMap::new();
+    for job in jobs {
+        let (stats, waits) = groups.entry(by.key(job)).or_default();
+        stats.jobs += 1;
+        match job.state {
+            JobState::Pending => stats.pending += 1,
+            JobState::Running => stats.running += 1,
+            state if state.is_active() => {}
+            _ => match job.failure_reason() {
+                None => stats.completed += 1,
+                Some(FailureReason::Cancelled(_)) => stats.cancelled += 1,
+                Some(_) => stats.failed += 1,
+            },
+        }
+        let hours = job.time.as_secs_f64() / 3600.0;
+        stats.cpu_hours += hours * f64::from(job.cpus);
+        stats.gpu_hours += hours * f64::from(job.gpus);
+        stats.kwh += job.energy.map_or(0.0, kwh);
+        if job.state != JobState::Pending && job.start_time.is_some() {
+            waits.extend(queue_wait(job, now));
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(key, (mut stats, mut waits))| {
+            waits.sort();
+            stats.key = key.to_string();
+            stats.median_wait = waits.get(waits.len() / 2).copied();
+            stats
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::parse_slurm_time;
+
+    #[test]
+    fn statistics_per_group() {
+        let job = |user: &str, state, wait_mins: u32| Job {
+            user: user.to_string(),
+            partition: "gpu".to_string(),
+            state,
+            cpus: 4,
+            gpus: 1,
+            time: Duration::from_secs(1800),
+            exit_code: Some(
+                if state == JobState::Failed {
+                    "1:0"
+                } else {
+                    "0:0"
+                }
+                .to_string(),
+            ),
+            submit_time: parse_slurm_time("2024-05-01T10:00:00"),
+            start_time: parse_slurm_time(&format!("2024-05-01T10:{:02}:00", wait_mins)),
+            energy: (state != JobState::Pending).then_some(1_800_000),
+            ..Default::default()
+        };
+        let jobs = vec![
+            job("alice", JobState::Completed, 1),
+            job("alice", JobState::Failed, 5),
+            job("alice", JobState::Cancelled { by: None }, 30),
+            job("alice", JobState::Running, 10),
+            Job {
+                time: Duration::ZERO,
+                ..job("bob", JobState::Pending, 0)
+            },
+        ];
+
+        let mut stats = group_stats(&jobs, GroupBy::User, Local::now());
+        let alice = &stats[0];
+        assert_eq!(
+            (
+                alice.jobs,
+                alice.running,
+                alice.completed,
+                alice.failed,
+                alice.cancelled
+            ),
+            (4, 1, 1, 1, 1)
+        );
+        assert_eq!(alice.cpu_hours, 8.0);
+        assert_eq!(alice.gpu_hours, 2.0);
+        assert_eq!(alice.kwh, 2.0);
+        assert_eq!(alice.median_wait, Some(Duration::from_secs(600)));
+        assert_eq!(alice.failure_rate(), Some(1.0 / 3.0));
+        let bob = &stats[1];
+        assert_eq!(
+            (bob.pending, bob.median_wait, bob.failure_rate()),
+            (1, None, None)
+        );
+
+        StatsSort::FailureRate.sort(&mut stats);
+        assert_eq!(stats[0].key, "bob");
+        assert_eq!(
+            group_stats(&jobs, GroupBy::Partition, Local::now()).len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+
+use crate::slurm::{Job, JobState};
+
+/// States kept per job; older ones are dropped first
+const MAX_TRANSITIONS: usize = 32;
+
+/// A state a job was seen in while polling, and when it was first seen in it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    pub state: JobState,
+    pub at: DateTime<Local>,
+}
+
+/// The states the listed jobs went through while this session watched them
+#[derive(Debug, Default)]
+pub struct StateHistory {
+    jobs: HashMap<String, Vec<Transition>>,
+}
+
+impl StateHistory {
+    /// Note the state of each listed job, forgetting the jobs no longer listed
+    pub fn observe(&mut self, jobs: &[Job], now: DateTime<Local>) {
+        let listed: HashSet<&str> = jobs.iter().map(|j| j.id.as_str()).collect();
+        self.jobs.retain(|id, _| listed.contains(id.as_str()));
+        for job in jobs {
+            let seen = self.jobs.entry(job.id.clone()).or_default();
+            if seen.last().map(|t| t.state.kind()) == Some(job.state.kind()) {
+                continue;
+            }
+            if seen.len() == MAX_TRANSITIONS {
+                seen.remove(0);
+            }
+            seen.push(Transition {
+                state: job.state,
+                at: now,
+            });
+        }
+    }
+
+    /// States of the job in the order they were seen; the first is only
+    /// when the session first saw the job, not a change
+    pub fn transitions(&self, job_id: &str) -> &[Transition] {
+        self.jobs.get(job_id).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+/// When a job was submitted, started and ended. Estimates
+/// (the expected start of a pending job, the expected end of a running
+/// one) are left out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeline {
+    pub submit: Option<DateTime<Local>>,
+    pub start: Option<DateTime<Local>>,
+    pub end: Option<DateTime<Local>>,
+    pub now: DateTime<Local>,
+}
+
+impl Timeline {
+    pub fn new(
+        state: JobState,
+        submit: Option<DateTime<Local>>,
+        start: Option<DateTime<Local>>,
+        end: Option<DateTime<Local>>,
+        now: DateTime<Local>,
+    ) -> Self {
+        let pending = state == JobState::Pending;
+        Self {
+            submit,
+            start: start.filter(|_| !pending),
+            end: end.filter(|_| !state.is_active()),
+            now,
+        }
+    }
+
+    pub fn of(job: &Job, now: DateTime<Local>) -> Self {
+        Self::new(
+            job.state,
+            job.submit_time,
+            job.start_time,
+            job.end_time,
+            now,
+        )
+    }
+
+    /// How long the job waited (or has been waiting) to start
+    pub fn queued(&self) -> Option<Duration> {
+        let until = self.start.or(self.end).unwrap_or(self.now);
+        Some((until - self.submit?).to_std().unwrap_or_default())
+    }
+
+    /// How long the job ran (or has been running)
+    pub fn running(&self) -> Option<Duration> {
+        let until = self.end.unwrap_or(self.now);
+        Some((until - self.start?).to_std().unwrap_or_default())
+    }
+
+    /// Cells of a `width` wide bar spent queued and running, in proportion
+    pub fn bar(&self, width: usize) -> (usize, usize) {
+        let queued = self.queued().unwrap_or_default().as_secs_f64();
+        let running = self.running().unwrap_or_default().as_secs_f64();
+        let total = queued + running;
+        if total <= 0.0 {
+            return (0, 0);
+        }
+        // A phase that happened at all gets at least one cell
+        let mut running_cells = (running / total * width as f64).round() as usize;
+        if running > 0.0 {
+            running_cells = running_cells.max(1);
+        }
+        if queued > 0.0 {
+            running_cells = running_cells.min(width.saturating_sub(1));
+        }
+        (width - running_cells, running_cells)
+    }
+}
+
+/// How long a job waited (or has been waiting) in the queue
+pub fn queue_wait(job: &Job, now: DateTime<Local>) -> Option<Duration> {
+    Timeline::of(job, now).queued()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::parse_slurm_time;
+
+    #[test]
+    fn timeline_of_queued_and_running_time() {
+        let at = parse_slurm_time;
+        let submit = at("2024-05-01T10:00:00");
+        let start = at("2024-05-01T10:15:00");
+        let now = at("2024-05-01T10:45:00").unwrap();
+
+        let running = Timeline::new(
+            JobState::Running,
+            submit,
+            start,
+            at("2024-05-02T10:00:00"),
+            now,
+        );
+        assert_eq!(running.queued(), Some(Duration::from_secs(900)));
+        // The expected end is not when it ended
+        assert_eq!(running.running(), Some(Duration::from_secs(1800)));
+        assert_eq!(running.bar(12), (4, 8));
+
+        // A pending job's start is only an estimate
+        let pending = Timeline::new(JobState::Pending, submit, start, None, now);
+        assert_eq!(pending.queued(), Some(Duration::from_secs(2700)));
+        assert_eq!((pending.running(), pending.bar(10)), (None, (10, 0)));
+
+        let cancelled = Timeline::new(
+            JobState::Cancelled { by: None },
+            submit,
+            None,
+            at("2024-05-01T10:05:00"),
+            now,
+        );
+        assert_eq!(cancelled.queued(), Some(Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn history_records_state_changes() {
+        let job = |id: &str, state| Job {
+            id: id.to_string(),
+            state,
+            ..Default::default()
+        };
+        let at = |secs| {
+            DateTime::from_timestamp(secs, 0)
+                .unwrap()
+                .with_timezone(&Local)
+        };
+        let mut history = StateHistory::default();
+        history.observe(
+            &[job("1", JobState::Pending), job("2", JobState::Pending)],
+            at(10),
+        );
+        history.observe(&[job("1", JobState::Pending)], at(20));
+        history.observe(&[job("1", JobState::Running)], at(30));
+
+        assert_eq!(
+            history.transitions("1"),
+            [
+                Transition {
+                    state: JobState::Pending,
+                    at: at(10)
+                },
+                Transition {
+                    state: JobState::Running,
+                    at: at(30)
+                }
+            ]
+        );
+        assert!(history.transitions("2").is_empty());
+    }
+}
@@ -0,0 +1,250 @@
+use chrono::{DateTime, Local, TimeDelta};
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::Result;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::slurm::{hostlist, Job};
+
+/// sacct fields needed to place jobs on the trace
+pub const TRACE_FIELDS: &[&str] = &[
+    "JobID",
+    "JobName",
+    "User",
+    "State",
+    "Partition",
+    "NNodes",
+    "NodeList",
+    "AllocCPUS",
+    "AllocTRES",
+    "ExitCode",
+    "Submit",
+    "Start",
+    "End",
+    "Elapsed",
+];
+
+/// What the trace groups jobs by (a Perfetto process track each)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceLanes {
+    Partition,
+    Node,
+}
+
+impl FromStr for TraceLanes {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "partition" | "p" => Ok(TraceLanes::Partition),
+            "node" | "n" => Ok(TraceLanes::Node),
+            other => Err(format!("lanes must be partition or node, not '{}'", other)),
+        }
+    }
+}
+
+/// When a job was submitted, started and ended (or would have by now, for
+/// running jobs: start + elapsed)
+struct Span {
+    submit: Option<DateTime<Local>>,
+    start: Option<DateTime<Local>>,
+    end: Option<DateTime<Local>>,
+}
+
+impl Span {
+    fn of(job: &Job) -> Self {
+        let start = job.start_time;
+        let end = job
+            .end_time
+            .or_else(|| Some(start? + TimeDelta::from_std(job.time).ok()?))
+            .filter(|end| start.is_some_and(|start| *end >= start));
+        Self {
+            submit: job.submit_time,
+            start,
+            end,
+        }
+    }
+}
+
+/// The jobs' queue wait and run time as a Chrome trace (JSON Object Format,
+/// opened by Perfetto and chrome://tracing). Each partition or node is a
+/// process track and each job a thread within it, so overlapping jobs stack.
+pub fn render_trace(jobs: &[Job], lanes: TraceLanes) -> Value {
+    let spans: Vec<(&Job, Span)> = jobs.iter().map(|j| (j, Span::of(j))).collect();
+    let origin = spans.iter().filter_map(|(_, s)| s.submit.or(s.start)).min();
+    let micros = |t: DateTime<Local>| {
+        origin.map_or(0, |origin| {
+            (t - origin).num_seconds().max(0) as u64 * 1_000_000
+        })
+    };
+
+    let mut lane_ids: BTreeMap<String, usize> = BTreeMap::new();
+    let mut events = Vec::new();
+    for (tid, (job, span)) in spans.iter().enumerate() {
+        let tid = tid + 1;
+        let lane_names = match lanes {
+            TraceLanes::Partition => vec![job.partition.clone()],
+            TraceLanes::Node => match job.node.as_deref().map(hostlist::expand) {
+                Some(hosts) if !hosts.is_empty() => hosts,
+                _ => vec!["(no node)".to_string()],
+            },
+        };
+        let args = json!({
+            "job_id": job.id,
+            "user": job.user,
+            "state": job.state.to_string(),
+            "partition": job.partition,
+            "nodes": job.node,
+            "cpus": job.cpus,
+            "gpus": job.gpus,
+            "exit_code": job.exit_code,
+        });
+
+        for lane in lane_names {
+            let next_id = lane_ids.len() + 1;
+            let pid = *lane_ids.entry(lane).or_insert(next_id);
+            events.push(json!({
+                "ph": "M",
+                "name": "thread_name",
+                "pid": pid,
+                "tid": tid,
+                "args": { "name": format!("{} ({})", job.id, job.name) },
+            }));
+            if let (Some(submit), Some(start)) = (span.submit, span.start) {
+                events.push(json!({
+                    "ph": "X",
+                    "cat": "pending",
+                    "name": "pending",
+                    "pid": pid,
+                    "tid": tid,
+                    "ts": micros(submit),
+                    "dur": micros(start) - micros(submit.min(start)),
+                    "args": args,
+                }));
+            } else if let Some(submit) = span.submit {
+                events.push(json!({
+                    "ph": "i",
+                    "s": "t",
+                    "cat": "pending",
+                    "name": "submitted",
+                    "pid": pid,
+                    "tid": tid,
+                    "ts": micros(submit),
+                    "args": args,
+                }));
+            }
+            if let (Some(start), Some(end)) = (span.start, span.end) {
+                events.push(json!({
+                    "ph": "X",
+                    "cat": "running",
+                    "name": job.name,
+                    "pid": pid,
+                    "tid": tid,
+                    "ts": micros(start),
+                    "dur": micros(end) - micros(start),
+                    "args": args,
+                }));
+            }
+        }
+    }
+
+    let kind = match lanes {
+        TraceLanes::Partition => "partition",
+        TraceLanes::Node => "node",
+    };
+    for (lane, pid) in &lane_ids {
+        events.push(json!({
+            "ph": "M",
+            "name": "process_name",
+            "pid": pid,
+            "args": { "name": format!("{} {}", kind, lane) },
+        }));
+    }
+
+    json!({ "traceEvents": events, "displayTimeUnit": "ms" })
+}
+
+/// Write the trace of `jobs` to `path`
+pub fn write_trace(path: &Path, jobs: &[Job], lanes: TraceLanes) -> Result<()> {
+    if jobs.is_empty() {
+        return Err(eyre!("no accounting records for the selected jobs"));
+    }
+    let raw = serde_json::to_string(&render_trace(jobs, lanes))?;
+    fs::write(path, raw).wrap_err_with(|| format!("failed writing {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slurm::JobState;
+    use crate::utils::parse_slurm_time;
+
+    fn job(id: &str, partition: &str, times: [Option<&str>; 3], elapsed: u64) -> Job {
+        let [submit, start, end] = times.map(|t| t.and_then(parse_slurm_time));
+        Job {
+            id: id.to_string(),
+            name: format!("step{}", id),
+            partition: partition.to_string(),
+            state: JobState::Completed,
+            time: std::time::Duration::from_secs(elapsed),
+            submit_time: submit,
+            start_time: start,
+            end_time: end,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn trace_has_pending_and_running_slices_per_lane() {
+        let jobs = vec![
+            job(
+                "1",
+                "gpu",
+                [
+                    Some("2024-05-01T10:00:00"),
+                    Some("2024-05-01T10:00:30"),
+                    Some("2024-05-01T10:10:30"),
+                ],
+                600,
+            ),
+            // Still running: the end is start + elapsed
+            job(
+                "2",
+                "cpu",
+                [
+                    Some("2024-05-01T10:05:00"),
+                    Some("2024-05-01T10:06:00"),
+                    None,
+                ],
+                60,
+            ),
+            job("3", "gpu", [Some("2024-05-01T10:07:00"), None, None], 0),
+        ];
+        let trace = render_trace(&jobs, TraceLanes::Partition);
+        let events = trace["traceEvents"].as_array().unwrap();
+        let slice = |cat: &str, tid: u64| {
+            events
+                .iter()
+                .find(|e| e["cat"] == cat && e["tid"] == tid)
+                .unwrap()
+        };
+
+        assert_eq!(slice("pending", 1)["ts"], 0);
+        assert_eq!(slice("pending", 1)["dur"], 30_000_000);
+        assert_eq!(slice("running", 1)["dur"], 600_000_000);
+        assert_eq!(slice("running", 2)["ts"], 360_000_000);
+        assert_eq!(slice("running", 2)["dur"], 60_000_000);
+        assert_eq!(slice("pending", 3)["ph"], "i");
+        // Jobs 1 and 3 share the gpu lane
+        assert_eq!(slice("pending", 1)["pid"], slice("pending", 3)["pid"]);
+        assert_ne!(slice("pending", 1)["pid"], slice("pending", 2)["pid"]);
+        assert!(events
+            .iter()
+            .any(|e| e["name"] == "process_name" && e["args"]["name"] == "partition gpu"));
+        assert_eq!("node".parse(), Ok(TraceLanes::Node));
+        assert!("rack".parse::<TraceLanes>().is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use std::io;
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use async_process::Output;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::config::CustomAction;
+use crate::keymap::{Action, Input};
+use crate::ui::theme;
+
+/// What the app should do after a key press in the action menu
+pub enum MenuAction {
+    None,
+    Close,
+    /// Run the `[[actions]]` entry at this index
+    Run(usize),
+}
+
+/// What a command printed, shown in place of the menu
+struct CommandOutput {
+    title: String,
+    /// e.g. `exited with 1`
+    status: Option<String>,
+    lines: Vec<String>,
+    scroll: usize,
+}
+
+/// The `[[actions]]` of the config, run on one job, and what the last one
+/// printed
+pub struct ActionMenu {
+    pub visible: bool,
+    actions: Vec<CustomAction>,
+    job_id: String,
+    output: Option<CommandOutput>,
+    /// Where the run of a foreground action still going reports
+    running: Option<Receiver<io::Result<Output>>>,
+    list_state: ListState,
+}
+
+impl ActionMenu {
+    pub fn new(actions: Vec<CustomAction>) -> Self {
+        Self {
+            visible: false,
+            actions,
+            job_id: String::new(),
+            output: None,
+            running: None,
+            list_state: ListState::default(),
+        }
+    }
+
+    pub fn actions(&self) -> &[CustomAction] {
+        &self.actions
+    }
+
+    /// Whether `key` picks one of the actions in the menu
+    pub fn has_key(&self, key: KeyEvent) -> bool {
+        self.output.is_none()
+            && matches!(key.code, KeyCode::Char(c) if self.actions.iter().any(|a| a.key == Some(c)))
+    }
+
+    pub fn show(&mut self, job_id: String) {
+        self.job_id = job_id;
+        self.output = None;
+        self.list_state
+            .select((!self.actions.is_empty()).then_some(0));
+        self.visible = true;
+    }
+
+    /// Show what a command printed (stdout, then stderr)
+    pub fn show_output(&mut self, title: String, status: Option<String>, text: &str) {
+        self.output = Some(CommandOutput {
+            title,
+            status,
+            lines: text.lines().map(str::to_string).collect(),
+            scroll: 0,
+        });
+        self.visible = true;
+    }
+
+    /// Show `title` as running until `finished` reports, on `poll`
+    pub fn wait_for(&mut self, title: String, finished: Receiver<io::Result<Output>>) {
+        self.show_output(title, Some("running".to_string()), "");
+        self.running = Some(finished);
+    }
+
+    /// Show the output of the running action once it has finished; call
+    /// this often (on every tick)
+    pub fn poll(&mut self) {
+        let Some(running) = &self.running else {
+            return;
+        };
+        let result = match running.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err(io::Error::other("the run was abandoned")),
+        };
+        self.running = None;
+        let (status, text) = match result {
+            Ok(output) => {
+                let status = (!output.status.success()).then(|| match output.status.code() {
+                    Some(code) => format!("exited with {}", code),
+                    None => "killed".to_string(),
+                });
+                let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+                text.push_str(&String::from_utf8_lossy(&output.stderr));
+                (status, text)
+            }
+            Err(e) => (Some("not run".to_string()), e.to_string()),
+        };
+        if let Some(output) = &mut self.output {
+            let title = std::mem::take(&mut output.title);
+            self.show_output(title, status, &text);
+        }
+    }
+
+    pub fn handle_key(&mut self, input: Input) -> MenuAction {
+        if let Some(output) = &mut self.output {
+            match input {
+                Input::Action(Action::PopupClose) => return MenuAction::Close,
+                _ if input.is_key(KeyCode::Char('q')) => return MenuAction::Close,
+                Input::Action(Action::PopupUp) => output.scroll = output.scroll.saturating_sub(1),
+                Input::Action(Action::PopupDown) => output.scroll += 1,
+                Input::Action(Action::PopupPageUp) => {
+                    output.scroll = output.scroll.saturating_sub(10)
+                }
+                Input::Action(Action::PopupPageDown) => output.scroll += 10,
+                _ => {}
+            }
+            output.scroll = output.scroll.min(output.lines.len().saturating_sub(1));
+            return MenuAction::None;
+        }
+
+        match input {
+            Input::Action(Action::PopupClose) => MenuAction::Close,
+            _ if input.is_ctrl_c() => MenuAction::Close,
+            Input::Action(Action::PopupUp) => {
+                let i = self.list_state.selected().unwrap_or(0);
+                self.list_state.select(Some(i.saturating_sub(1)));
+                MenuAction::None
+            }
+            Input::Action(Action::PopupDown) => {
+                let i = self.list_state.selected().unwrap_or(0);
+                if i + 1 < self.actions.len() {
+                    self.list_state.select(Some(i + 1));
+                }
+                MenuAction::None
+            }
+            Input::Action(Action::RunCustom) => match self.list_state.selected() {
+                Some(i) if i < self.actions.len() => MenuAction::Run(i),
+                _ => MenuAction::None,
+            },
+            Input::Key(KeyEvent {
+                code: KeyCode::Char(c),
+                ..
+            }) => match self.actions.iter().position(|a| a.key == Some(c)) {
+                Some(i) => MenuAction::Run(i),
+                None => MenuAction::None,
+            },
+            _ => MenuAction::None,
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        if !self.visible {
+            return;
+        }
+        frame.render_widget(Clear, area);
+
+        let title = match &self.output {
+            Some(output) => match &output.status {
+                Some(status) => format!("{} ({})", output.title, status),
+                None => output.title.clone(),
+            },
+            None => format!("Actions on job {}", self.job_id),
+        };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(inner);
+
+        let help = match &self.output {
+            Some(output) => {
+                let lines: Vec<Line> = output
+                    .lines
+                    .iter()
+                    .skip(output.scroll)
+                    .map(|l| Line::from(l.as_str()))
+                    .collect();
+                let text = if output.lines.is_empty() {
+                    vec![Line::styled(
+                        "No output",
+                        Style::default().fg(Color::DarkGray),
+                    )]
+                } else {
+                    lines
+                };
+                frame.render_widget(Paragraph::new(text), chunks[0]);
+                "[↑/↓/PgUp/PgDn] Scroll | [Esc/q] Close"
+            }
+            None if self.actions.is_empty() => {
+                frame.render_widget(
+                    Paragraph::new("No actions defined; add [[actions]] to config.toml"),
+                    chunks[0],
+                );
+                "[Esc] Close"
+            }
+            None => {
+                let items: Vec<ListItem> = self
+                    .actions
+                    .iter()
+                    .map(|action| {
+                        let key = action.key.map(|k| format!("[{}] ", k)).unwrap_or_default();
+                        ListItem::new(Line::from(vec![
+                            Span::styled(format!("{:<5}", key), Style::default().fg(Color::Yellow)),
+                            Span::styled(
+                                format!("{:<20} ", action.name),
+                                Style::default().add_modifier(Modifier::BOLD),
+                            ),
+                            Span::styled(
+                                action.command.clone(),
+                                Style::default().fg(Color::DarkGray),
+                            ),
+                        ]))
+                    })
+                    .collect();
+                let list = List::new(items).highlight_style(theme::selection());
+                frame.render_stateful_widget(list, chunks[0], &mut self.list_state);
+                "[↑/↓] Select | [Enter/key] Run | [Esc] Close"
+            }
+        };
+        frame.render_widget(
+            Paragraph::new(help).style(Style::default().fg(Color::Gray)),
+            chunks[1],
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::{KeyContext, KeyOutcome, Keymap};
+    use std::sync::mpsc;
+
+    fn menu() -> ActionMenu {
+        let action = |name: &str, key| CustomAction {
+            name: name.to_string(),
+            command: format!("echo {}", name),
+            key,
+            background: false,
+        };
+        ActionMenu::new(vec![action("tail", Some('t')), action("top", None)])
+    }
+
+    #[test]
+    fn runs_by_key_or_cursor_and_shows_the_output_when_done() {
+        // As the app hands keys on: the actions' own keys first, then the keymap
+        let press = |menu: &mut ActionMenu, code| {
+            let key = KeyEvent::from(code);
+            let input = match Keymap::default().press(KeyContext::Actions, key) {
+                KeyOutcome::Action(action) if !menu.has_key(key) => Input::Action(action),
+                _ => Input::Key(key),
+            };
+            menu.handle_key(input)
+        };
+        let mut menu = menu();
+        menu.show("42".to_string());
+        assert!(matches!(
+            press(&mut menu, KeyCode::Char('t')),
+            MenuAction::Run(0)
+        ));
+        assert!(matches!(
+            press(&mut menu, KeyCode::Char('x')),
+            MenuAction::None
+        ));
+        press(&mut menu, KeyCode::Down);
+        assert!(matches!(
+            press(&mut menu, KeyCode::Enter),
+            MenuAction::Run(1)
+        ));
+
+        let (tx, rx) = mpsc::channel();
+        menu.wait_for("top on job 42".to_string(), rx);
+        menu.poll();
+        let status = |menu: &ActionMenu| menu.output.as_ref().and_then(|o| o.status.clone());
+        assert_eq!(status(&menu).as_deref(), Some("running"));
+
+        tx.send(Err(io::Error::other("sh: not found"))).unwrap();
+        menu.poll();
+        let output = menu.output.as_ref().unwrap();
+        assert_eq!(output.title, "top on job 42");
+        assert_eq!(output.status.as_deref(), Some("not run"));
+        assert_eq!(output.lines, ["sh: not found"]);
+        // Scrolling stops at the last line; Esc closes
+        press(&mut menu, KeyCode::PageDown);
+        assert_eq!(menu.output.as_ref().unwrap().scroll, 0);
+        assert!(matches!(press(&mut menu, KeyCode::Esc), MenuAction::Close));
+    }
+}
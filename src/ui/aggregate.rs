@@ -0,0 +1,143 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::keymap::{Action, Input};
+use crate::slurm::aggregate::QueueGroup;
+use crate::ui::jobslist::state_color;
+use crate::ui::theme;
+
+/// What the app should do after a key press in the aggregate view
+pub enum AggregateAction {
+    None,
+    Close,
+    Refresh,
+    /// List the jobs of this group
+    DrillDown(QueueGroup),
+}
+
+/// Queue counts per user, partition and state, for clusters too busy to
+/// list every job
+pub struct AggregateView {
+    pub visible: bool,
+    pub groups: Vec<QueueGroup>,
+    table_state: TableState,
+}
+
+impl AggregateView {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            groups: Vec::new(),
+            table_state: TableState::default(),
+        }
+    }
+
+    /// Replace the counts, keeping the cursor on the same group if it is still there
+    pub fn update(&mut self, groups: Vec<QueueGroup>) {
+        let current = self
+            .selected()
+            .map(|g| (g.user.clone(), g.partition.clone(), g.state));
+        let index = current
+            .and_then(|(user, partition, state)| {
+                groups
+                    .iter()
+                    .position(|g| g.user == user && g.partition == partition && g.state == state)
+            })
+            .or((!groups.is_empty()).then_some(0));
+        self.groups = groups;
+        self.table_state.select(index);
+    }
+
+    fn selected(&self) -> Option<&QueueGroup> {
+        self.groups.get(self.table_state.selected()?)
+    }
+
+    pub fn handle_key(&mut self, input: Input) -> AggregateAction {
+        match input {
+            Input::Action(Action::PopupClose) => AggregateAction::Close,
+            _ if input.is_key(KeyCode::Char('A')) || input.is_ctrl_c() => AggregateAction::Close,
+            Input::Action(Action::PopupRefresh) => AggregateAction::Refresh,
+            Input::Action(Action::DrillDown) => match self.selected() {
+                Some(group) => AggregateAction::DrillDown(group.clone()),
+                None => AggregateAction::None,
+            },
+            Input::Action(Action::PopupUp) => {
+                let i = self.table_state.selected().unwrap_or(0);
+                self.table_state.select(Some(i.saturating_sub(1)));
+                AggregateAction::None
+            }
+            Input::Action(Action::PopupDown) => {
+                let i = self.table_state.selected().unwrap_or(0);
+                if i + 1 < self.groups.len() {
+                    self.table_state.select(Some(i + 1));
+                }
+                AggregateAction::None
+            }
+            _ => AggregateAction::None,
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        if !self.visible {
+            return;
+        }
+        frame.render_widget(Clear, area);
+
+        let jobs: usize = self.groups.iter().map(|g| g.jobs).sum();
+        let cpus: u64 = self.groups.iter().map(|g| g.cpus).sum();
+        let block = Block::default()
+            .title(format!(
+                "Queue by user/partition/state: {} jobs, {} CPUs",
+                jobs, cpus
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(1)])
+            .split(inner);
+
+        let header = Row::new(["User", "Partition", "State", "Jobs", "CPUs"])
+            .style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .height(1);
+        let rows = self.groups.iter().map(|g| {
+            Row::new(vec![
+                Cell::from(g.user.clone()),
+                Cell::from(g.partition.clone()),
+                Cell::from(g.state.to_string()).style(Style::default().fg(state_color(g.state))),
+                Cell::from(g.jobs.to_string()),
+                Cell::from(g.cpus.to_string()),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(16),
+                Constraint::Length(20),
+                Constraint::Length(14),
+                Constraint::Length(9),
+                Constraint::Length(9),
+            ],
+        )
+        .header(header)
+        .row_highlight_style(theme::selection());
+        frame.render_stateful_widget(table, chunks[0], &mut self.table_state);
+
+        let help =
+            Paragraph::new("[↑/↓] Select | [Enter] List these jobs | [r] Refresh | [Esc/A] Close")
+                .style(Style::default().fg(Color::Gray));
+        frame.render_widget(help, chunks[1]);
+    }
+}
@@ -1,4 +1,3 @@
-use crossterm::event::KeyModifiers;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
@@ -7,6 +6,8 @@ use ratatui::{
     Frame,
 };
 
+use crate::keymap::{Action, Input};
+
 /// Available columns for display in job list
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum JobColumn {
@@ -19,14 +20,36 @@ pub enum JobColumn {
     Node,
     CPUs,
     Time,
+    TimeLeft,
+    /// CPU time used over CPU time allocated (elapsed × CPUs) of ended jobs
+    CpuUsage,
+    /// CPU and memory efficiency combined, of ended jobs
+    Efficiency,
     Memory,
+    Gpus,
     Account,
     Priority,
     WorkDir,
     SubmitTime,
     StartTime,
     EndTime,
+    /// How long the job waited (or has been waiting) to start
+    QueueWait,
+    /// The scheduler's expected start of pending jobs, from `squeue --start`
+    EstimatedStart,
     PReason,
+    /// Advanced reservation the job runs in
+    Reservation,
+    /// Licenses the job requested
+    Licenses,
+    /// Local scratch per node
+    TmpDisk,
+    /// Burst buffer space
+    BurstBuffer,
+    /// Whether the job was preempted, or could be
+    Preemption,
+    /// Energy consumed, where Slurm gathers it
+    Energy,
 }
 
 impl JobColumn {
@@ -42,44 +65,68 @@ impl JobColumn {
             JobColumn::Node => "Node",
             JobColumn::CPUs => "CPUs",
             JobColumn::Time => "Time",
+            JobColumn::TimeLeft => "Time Left",
+            JobColumn::CpuUsage => "CPU Use",
+            JobColumn::Efficiency => "Efficiency",
             JobColumn::Memory => "Memory",
+            JobColumn::Gpus => "GPUs",
             JobColumn::Account => "Account",
             JobColumn::Priority => "Priority",
             JobColumn::WorkDir => "WorkDir",
             JobColumn::SubmitTime => "Submit",
             JobColumn::StartTime => "Start",
             JobColumn::EndTime => "End",
+            JobColumn::QueueWait => "Wait",
+            JobColumn::EstimatedStart => "Est. Start",
             JobColumn::PReason => "Reason", // Pending reason
+            JobColumn::Reservation => "Reservation",
+            JobColumn::Licenses => "Licenses",
+            JobColumn::TmpDisk => "Tmp Disk",
+            JobColumn::BurstBuffer => "Burst Buffer",
+            JobColumn::Preemption => "Preempt",
+            JobColumn::Energy => "Energy",
         }
     }
 
-    /// Get the format code for this column
+    /// Get the format code for this column; empty for columns squeue does not print
     pub fn format_code(&self) -> &'static str {
         match self {
-            JobColumn::Id => "%i",         // Job ID - using %A for array job ID
-            JobColumn::Name => "%j",       // Job name
-            JobColumn::User => "%u",       // User name
-            JobColumn::State => "%T",      // Job state
-            JobColumn::Partition => "%P",  // Partition
-            JobColumn::Nodes => "%D",      // Node count
-            JobColumn::Node => "%N",       // Node list
-            JobColumn::CPUs => "%C",       // CPU count
-            JobColumn::Time => "%M",       // Time limit
-            JobColumn::Memory => "%m",     // Memory
-            JobColumn::Account => "%a",    // Account
-            JobColumn::Priority => "%Q",   // Priority
-            JobColumn::WorkDir => "%Z",    // Working directory
-            JobColumn::SubmitTime => "%V", // Submission time
-            JobColumn::StartTime => "%S",  // Start time
-            JobColumn::EndTime => "%e",    // End time
-            JobColumn::PReason => "%R",    // Pending reason
+            JobColumn::Id => "%i",           // Job ID - using %A for array job ID
+            JobColumn::Name => "%j",         // Job name
+            JobColumn::User => "%u",         // User name
+            JobColumn::State => "%T",        // Job state
+            JobColumn::Partition => "%P",    // Partition
+            JobColumn::Nodes => "%D",        // Node count
+            JobColumn::Node => "%N",         // Node list
+            JobColumn::CPUs => "%C",         // CPU count
+            JobColumn::Time => "%M",         // Time used
+            JobColumn::TimeLeft => "%l",     // Time limit (remaining is computed)
+            JobColumn::CpuUsage => "",       // From sacct, once the job has ended
+            JobColumn::Efficiency => "",     // From sacct, once the job has ended
+            JobColumn::Memory => "%m",       // Memory
+            JobColumn::Gpus => "%b",         // GRES per node (tres_per_node)
+            JobColumn::Account => "%a",      // Account
+            JobColumn::Priority => "%Q",     // Priority
+            JobColumn::WorkDir => "%Z",      // Working directory
+            JobColumn::SubmitTime => "%V",   // Submission time
+            JobColumn::StartTime => "%S",    // Start time
+            JobColumn::EndTime => "%e",      // End time
+            JobColumn::QueueWait => "%V",    // Submission time (start time is added)
+            JobColumn::EstimatedStart => "", // Fetched separately, less often
+            JobColumn::PReason => "%R",      // Pending reason
+            JobColumn::Reservation => "%v",  // Reservation
+            JobColumn::Licenses => "%W",     // Licenses
+            JobColumn::TmpDisk => "%d",      // MinTmpDisk
+            JobColumn::BurstBuffer => "",
+            JobColumn::Preemption => "", // From the QOS preemption settings
+            JobColumn::Energy => "",     // From sacct
         }
     }
 
-    /// Get the `sacct --format` field name for this column.
+    /// Get the `sacct --format` field name for this column (empty if sacct has none).
     pub fn sacct_field(&self) -> &'static str {
         match self {
-            JobColumn::Id => "JobIDRaw",
+            JobColumn::Id => "JobID",
             JobColumn::Name => "JobName",
             JobColumn::User => "User",
             JobColumn::State => "State",
@@ -88,14 +135,26 @@ impl JobColumn {
             JobColumn::Node => "NodeList",
             JobColumn::CPUs => "AllocCPUS",
             JobColumn::Time => "Elapsed",
+            JobColumn::TimeLeft => "Timelimit",
+            JobColumn::CpuUsage => "TotalCPU",
+            JobColumn::Efficiency => "TotalCPU",
             JobColumn::Memory => "ReqMem",
+            JobColumn::Gpus => "AllocTRES",
             JobColumn::Account => "Account",
             JobColumn::Priority => "Priority",
             JobColumn::WorkDir => "WorkDir",
             JobColumn::SubmitTime => "Submit",
             JobColumn::StartTime => "Start",
             JobColumn::EndTime => "End",
+            JobColumn::QueueWait => "Submit",
+            JobColumn::EstimatedStart => "",
             JobColumn::PReason => "Reason",
+            JobColumn::Reservation => "Reservation",
+            JobColumn::Licenses => "",
+            JobColumn::TmpDisk => "ReqTRES",
+            JobColumn::BurstBuffer => "ReqTRES",
+            JobColumn::Preemption => "",
+            JobColumn::Energy => "ConsumedEnergyRaw",
         }
     }
 
@@ -111,14 +170,26 @@ impl JobColumn {
             JobColumn::Node => Constraint::Percentage(12), // Node list can be long
             JobColumn::CPUs => Constraint::Length(6),
             JobColumn::Time => Constraint::Length(12),
+            JobColumn::TimeLeft => Constraint::Length(20),
+            JobColumn::CpuUsage => Constraint::Length(8),
+            JobColumn::Efficiency => Constraint::Length(10),
             JobColumn::Memory => Constraint::Length(10),
+            JobColumn::Gpus => Constraint::Length(6),
             JobColumn::Account => Constraint::Length(12),
             JobColumn::Priority => Constraint::Length(10),
             JobColumn::WorkDir => Constraint::Percentage(15),
             JobColumn::SubmitTime => Constraint::Length(19),
             JobColumn::StartTime => Constraint::Length(19),
             JobColumn::EndTime => Constraint::Length(19),
+            JobColumn::QueueWait => Constraint::Length(11),
+            JobColumn::EstimatedStart => Constraint::Length(19),
             JobColumn::PReason => Constraint::Percentage(20), // Pending reason can be long
+            JobColumn::Reservation => Constraint::Length(14),
+            JobColumn::Licenses => Constraint::Length(14),
+            JobColumn::TmpDisk => Constraint::Length(10),
+            JobColumn::BurstBuffer => Constraint::Length(12),
+            JobColumn::Preemption => Constraint::Length(20),
+            JobColumn::Energy => Constraint::Length(10),
         }
     }
 
@@ -134,20 +205,47 @@ impl JobColumn {
             JobColumn::Node,
             JobColumn::CPUs,
             JobColumn::Time,
+            JobColumn::TimeLeft,
+            JobColumn::CpuUsage,
+            JobColumn::Efficiency,
             JobColumn::Memory,
+            JobColumn::Gpus,
             JobColumn::Account,
             JobColumn::Priority,
             JobColumn::WorkDir,
             JobColumn::SubmitTime,
             JobColumn::StartTime,
             JobColumn::EndTime,
+            JobColumn::QueueWait,
+            JobColumn::EstimatedStart,
             JobColumn::PReason,
+            JobColumn::Reservation,
+            JobColumn::Licenses,
+            JobColumn::TmpDisk,
+            JobColumn::BurstBuffer,
+            JobColumn::Preemption,
+            JobColumn::Energy,
         ]
     }
 
+    /// The column titled `name`, ignoring case, spaces and punctuation
+    /// (`time left`, `TimeLeft` and `time_left` are all Time Left)
+    pub fn from_name(name: &str) -> Option<JobColumn> {
+        let squash = |s: &str| -> String {
+            s.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect()
+        };
+        let name = squash(name);
+        JobColumn::all()
+            .into_iter()
+            .find(|c| squash(c.title()) == name)
+    }
+
     /// Default columns to display
     pub fn defaults() -> Vec<JobColumn> {
-        // These MUST match the defaults in App::new()
+        // These MUST match the defaults in App::with_config()
         // "%i|%j|%u|%T|%M|%N|%C|%m|%P|%V|%e".to_string(),
         vec![
             JobColumn::Id,
@@ -157,6 +255,7 @@ impl JobColumn {
             JobColumn::Time,
             JobColumn::Node,
             JobColumn::CPUs,
+            JobColumn::CpuUsage,
             JobColumn::Memory,
             JobColumn::Partition,
             JobColumn::SubmitTime,
@@ -226,6 +325,9 @@ pub struct ColumnsPopup {
     pub sort_columns: Vec<SortColumn>,
     /// If show
     pub visible: bool,
+    /// The columns a `[layout]` breakpoint shows instead, on a terminal
+    /// this narrow; set by the app
+    pub layout_columns: Option<Vec<JobColumn>>,
 }
 
 impl ColumnsPopup {
@@ -258,6 +360,7 @@ impl ColumnsPopup {
             selected_columns,
             sort_columns,
             visible: false,
+            layout_columns: None,
         }
     }
 
@@ -341,7 +444,13 @@ impl ColumnsPopup {
         let selected_items: Vec<ListItem> = self
             .selected_columns
             .iter()
-            .map(|col| ListItem::new(col.title()))
+            .map(|col| match &self.layout_columns {
+                Some(shown) if !shown.contains(col) => {
+                    ListItem::new(format!("{} (hidden)", col.title()))
+                        .style(Style::default().fg(Color::DarkGray))
+                }
+                _ => ListItem::new(col.title()),
+            })
             .collect();
 
         let selected_list = List::new(selected_items)
@@ -393,7 +502,10 @@ impl ColumnsPopup {
             _ => "",
         };
 
-        let full_help_text = format!("{} | Ctrl+a: Apply | Esc: Close", base_help_text);
+        let mut full_help_text = format!("{} | Ctrl+a: Apply | Esc: Close", base_help_text);
+        if self.layout_columns.is_some() {
+            full_help_text.insert_str(0, "(hidden): too narrow, see [layout] | ");
+        }
 
         let help = Paragraph::new(full_help_text)
             .style(Style::default().fg(Color::Gray))
@@ -403,16 +515,21 @@ impl ColumnsPopup {
     }
 
     /// Handle key events
-    pub fn handle_key(&mut self, key: crossterm::event::KeyEvent) -> ColumnsAction {
+    pub fn handle_key(&mut self, input: Input) -> ColumnsAction {
         use crossterm::event::KeyCode;
 
         // Handle global keys first
-        match key.code {
-            KeyCode::Esc => return ColumnsAction::Close,
-            KeyCode::Tab => {
+        let key = match input {
+            Input::Action(Action::ColumnsClose) => return ColumnsAction::Close,
+            Input::Action(Action::ColumnsApply) => return ColumnsAction::Apply,
+            Input::Action(Action::ColumnsFocus) => {
                 self.cycle_focus();
                 return ColumnsAction::None;
             }
+            Input::Action(_) => return ColumnsAction::None,
+            Input::Key(key) => key,
+        };
+        match key.code {
             KeyCode::Left => {
                 // Navigate left between columns
                 match self.focus {
@@ -446,11 +563,6 @@ impl ColumnsPopup {
                 }
             }
 
-            // Ctrl+A to apply changes
-            KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                return ColumnsAction::Apply;
-            }
-
             // F10 or Ctrl+S to save settings
             // KeyCode::F(10) => return ColumnsAction::SaveAndApply,
             // KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
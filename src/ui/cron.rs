@@ -0,0 +1,132 @@
+use chrono::Local;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Cell, Row},
+    Frame,
+};
+
+use crate::keymap::{Action, Input};
+use crate::slurm::scrontab::{format_minute, Schedule, Scrontab};
+use crate::ui::table_popup::{TableAction, TablePopup, TableView};
+use crate::utils::format_slurm_duration;
+
+/// The user's recurring jobs (scrontab entries) with their next run
+pub struct CronView {
+    pub popup: TablePopup,
+    pub crontab: Scrontab,
+}
+
+impl CronView {
+    pub fn new() -> Self {
+        Self {
+            popup: TablePopup::new("scrontab", 'K'),
+            crontab: Scrontab::default(),
+        }
+    }
+
+    pub fn show(&mut self, crontab: Result<Scrontab, String>) {
+        let error = match crontab {
+            Ok(crontab) => {
+                self.crontab = crontab;
+                None
+            }
+            Err(e) => Some(e),
+        };
+        self.popup.show(error, self.crontab.entries.len());
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let overview = if let Some(unavailable) = self.popup.unavailable() {
+            unavailable
+        } else if self.crontab.entries.is_empty() {
+            Line::from("No scrontab entries (scrontab -e adds some)")
+        } else {
+            let disabled = self.crontab.entries.iter().filter(|e| !e.enabled).count();
+            Line::styled(
+                format!(
+                    "{} enabled, {} disabled",
+                    self.crontab.entries.len() - disabled,
+                    disabled
+                ),
+                Style::default().add_modifier(Modifier::BOLD),
+            )
+        };
+
+        let now = Local::now();
+        let rows = self.crontab.entries.iter().map(|entry| {
+            let next = Schedule::parse(&entry.schedule)
+                .ok()
+                .and_then(|s| s.next_after(now))
+                .filter(|_| entry.enabled)
+                .map(|at| {
+                    format!(
+                        "{} (in {})",
+                        format_minute(&at),
+                        format_slurm_duration((at - now).to_std().unwrap_or_default())
+                    )
+                })
+                .unwrap_or_else(|| "-".to_string());
+            let (on, style) = if entry.enabled {
+                ("✓", Style::default().fg(Color::Green))
+            } else {
+                ("✗", Style::default().fg(Color::DarkGray))
+            };
+            Row::new(vec![
+                Cell::from(on).style(style),
+                Cell::from(entry.schedule.clone()),
+                Cell::from(next),
+                Cell::from(entry.options.join(" ")),
+                Cell::from(entry.command.clone()),
+            ])
+            .style(if entry.enabled {
+                Style::default()
+            } else {
+                Style::default().fg(Color::DarkGray)
+            })
+        });
+        let table = TablePopup::table(
+            &["On", "Schedule", "Next run", "Options", "Command"],
+            rows.collect(),
+            &[
+                Constraint::Length(3),
+                Constraint::Length(20),
+                Constraint::Length(32),
+                Constraint::Percentage(20),
+                Constraint::Min(20),
+            ],
+        );
+        self.popup.render(
+            frame,
+            area,
+            format!("Recurring jobs (scrontab): {}", self.crontab.entries.len()),
+            vec![overview],
+            table,
+            "[↑/↓] Select | [Space] Enable/disable | [Enter/e] Edit | [r] Refresh | [Esc/K] Close",
+        );
+    }
+}
+
+impl TableView for CronView {
+    fn popup(&self) -> &TablePopup {
+        &self.popup
+    }
+
+    fn popup_mut(&mut self) -> &mut TablePopup {
+        &mut self.popup
+    }
+
+    fn handle_key(&mut self, input: Input) -> TableAction {
+        let selected = self.popup.selected();
+        match input {
+            Input::Action(Action::CronToggle) => {
+                selected.map_or(TableAction::None, TableAction::Toggle)
+            }
+            Input::Action(Action::CronEdit) => {
+                selected.map_or(TableAction::None, TableAction::Edit)
+            }
+            _ => self.popup.handle_key(input, self.crontab.entries.len()),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::keymap::{Action, Input};
+use crate::slurm::dependency::{DependencyStatus, TreeLine};
+use crate::ui::jobslist::state_color;
+
+/// What the app should do after a key press in the dependency view
+pub enum DependencyAction {
+    None,
+    Close,
+    Refresh,
+}
+
+/// Tree of which jobs wait on which
+pub struct DependencyView {
+    pub visible: bool,
+    pub lines: Vec<TreeLine>,
+    scroll: usize,
+}
+
+impl DependencyView {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            lines: Vec::new(),
+            scroll: 0,
+        }
+    }
+
+    pub fn show(&mut self, lines: Vec<TreeLine>) {
+        self.lines = lines;
+        self.scroll = self.scroll.min(self.lines.len().saturating_sub(1));
+        self.visible = true;
+    }
+
+    pub fn handle_key(&mut self, input: Input) -> DependencyAction {
+        match input {
+            Input::Action(Action::PopupClose) => DependencyAction::Close,
+            _ if input.is_key(KeyCode::Char('D')) || input.is_ctrl_c() => DependencyAction::Close,
+            Input::Action(Action::PopupRefresh) => DependencyAction::Refresh,
+            Input::Action(Action::PopupUp) => {
+                self.scroll = self.scroll.saturating_sub(1);
+                DependencyAction::None
+            }
+            Input::Action(Action::PopupDown) => {
+                if self.scroll + 1 < self.lines.len() {
+                    self.scroll += 1;
+                }
+                DependencyAction::None
+            }
+            Input::Action(Action::PopupPageUp) => {
+                self.scroll = self.scroll.saturating_sub(10);
+                DependencyAction::None
+            }
+            Input::Action(Action::PopupPageDown) => {
+                self.scroll = (self.scroll + 10).min(self.lines.len().saturating_sub(1));
+                DependencyAction::None
+            }
+            _ => DependencyAction::None,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        if !self.visible {
+            return;
+        }
+        frame.render_widget(Clear, area);
+
+        let failed = self
+            .lines
+            .iter()
+            .filter(|l| {
+                l.dependency
+                    .as_ref()
+                    .is_some_and(|d| d.status == DependencyStatus::Failed)
+            })
+            .count();
+        let title = match failed {
+            0 => "Job dependencies".to_string(),
+            n => format!("Job dependencies ({} never satisfied)", n),
+        };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(inner);
+
+        let lines: Vec<Line> = if self.lines.is_empty() {
+            vec![Line::from(
+                "None of the listed pending jobs waits on another job.",
+            )]
+        } else {
+            self.lines.iter().skip(self.scroll).map(tree_line).collect()
+        };
+        frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+        let help = Paragraph::new("[↑/↓/PgUp/PgDn] Scroll | [r] Refresh | [Esc/D] Close")
+            .style(Style::default().fg(Color::Gray));
+        frame.render_widget(help, chunks[1]);
+    }
+}
+
+fn tree_line(line: &TreeLine) -> Line<'_> {
+    let mut spans = vec![Span::styled(
+        line.prefix.as_str(),
+        Style::default().fg(Color::DarkGray),
+    )];
+    if let Some(dep) = &line.dependency {
+        spans.push(Span::styled(
+            format!("{} ", dep.kind),
+            Style::default().fg(Color::Cyan),
+        ));
+    }
+    spans.push(Span::styled(
+        line.job_id.as_str(),
+        Style::default().add_modifier(Modifier::BOLD),
+    ));
+    if let Some(name) = &line.name {
+        spans.push(Span::raw(format!(" {}", name)));
+    }
+    match line.state {
+        Some(state) => spans.push(Span::styled(
+            format!(" {}", state),
+            Style::default().fg(state_color(state)),
+        )),
+        None => spans.push(Span::styled(
+            " (not listed)",
+            Style::default().fg(Color::DarkGray),
+        )),
+    }
+    match line.dependency.as_ref().map(|d| d.status) {
+        Some(DependencyStatus::Failed) => spans.push(Span::styled(
+            "  ✗ dependency can never be satisfied",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        Some(DependencyStatus::Fulfilled) => {
+            spans.push(Span::styled("  ✓", Style::default().fg(Color::Green)))
+        }
+        _ => {}
+    }
+    if line.repeated {
+        spans.push(Span::styled(
+            "  (see above)",
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    Line::from(spans)
+}
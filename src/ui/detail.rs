@@ -0,0 +1,1177 @@
+use chrono::{DateTime, Local};
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Tabs, Wrap},
+    Frame,
+};
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::baseline::Baseline;
+use crate::efficiency::{Concern, Report};
+use crate::keymap::{Action, Input};
+use crate::notes::JobNote;
+use crate::slurm::{
+    energy::format_energy,
+    events::{JobEvent, JobEventKind},
+    hints::{hint_for, HintKeys},
+    hostlist::expand,
+    inspect::{GpuUsage, NodeSnapshot},
+    licenses::{shortfalls, License},
+    preempt::PreemptionRisk,
+    reservation::Reservation,
+    sacct::apply_sacct_field,
+    scrontab::format_minute,
+    sstat::StepUsage,
+    tres::Storage,
+    Job, JobState,
+};
+use crate::timeline::{Timeline, Transition};
+use crate::ui::{jobscript::JobScript, jobslist::state_color, logview::LogView};
+use crate::utils::{format_slurm_duration, format_slurm_time, TimeDisplay};
+
+/// Width of the queued/running bar on the Events tab
+const TIMELINE_WIDTH: usize = 40;
+
+/// Tabs of the job detail pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetailTab {
+    Overview,
+    Resources,
+    Nodes,
+    Steps,
+    Script,
+    Logs,
+    Events,
+}
+
+impl DetailTab {
+    pub fn all() -> Vec<DetailTab> {
+        vec![
+            DetailTab::Overview,
+            DetailTab::Resources,
+            DetailTab::Nodes,
+            DetailTab::Steps,
+            DetailTab::Script,
+            DetailTab::Logs,
+            DetailTab::Events,
+        ]
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            DetailTab::Overview => "Overview",
+            DetailTab::Resources => "Resources",
+            DetailTab::Nodes => "Nodes",
+            DetailTab::Steps => "Steps",
+            DetailTab::Script => "Script",
+            DetailTab::Logs => "Logs",
+            DetailTab::Events => "Events",
+        }
+    }
+
+    fn index(&self) -> usize {
+        Self::all().iter().position(|t| t == self).unwrap_or(0)
+    }
+
+    fn next(&self) -> Self {
+        let all = Self::all();
+        all[(self.index() + 1) % all.len()]
+    }
+
+    fn previous(&self) -> Self {
+        let all = Self::all();
+        all[(self.index() + all.len() - 1) % all.len()]
+    }
+}
+
+/// Data that must be fetched by the app (it owns the async runtime)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetailLoad {
+    /// sacct allocation record (Overview and Events tabs)
+    Accounting,
+    /// sstat live usage (Resources tab)
+    Usage,
+    /// GPUs and top processes on the job's nodes (Nodes tab)
+    Nodes,
+    /// sacct records of the job's steps (Steps tab)
+    Steps,
+    /// Node failures, requeues and resizes (Events tab)
+    Events,
+}
+
+/// Action to take after handling a key in the detail pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetailAction {
+    None,
+    Close,
+}
+
+/// Resizable side pane with tabbed details of the selected job
+pub struct JobDetail {
+    pub visible: bool,
+    /// Whether the pane takes the whole content area instead of sharing it with the table
+    pub zoomed: bool,
+    /// Width of the pane as a percentage of the content area
+    pub width_percent: u16,
+    pub tab: DetailTab,
+    pub job: Option<Job>,
+    /// sacct `(field, value)` pairs, or the error that prevented fetching them
+    accounting: Option<Result<Vec<(String, String)>, String>>,
+    /// sstat rows per step, or the error that prevented fetching them
+    usage: Option<Result<Vec<StepUsage>, String>>,
+    /// What runs on each node, or the error that prevented inspecting them
+    nodes: Option<Result<Vec<NodeSnapshot>, String>>,
+    /// sacct rows of the job's steps, or the error that prevented fetching them
+    steps: Option<Result<Vec<Job>, String>>,
+    /// Node failures, requeues and resizes, or why they could not be read
+    events: Option<Result<Vec<JobEvent>, String>>,
+    /// Loads the app runs off the UI thread, not asked for again meanwhile
+    loading: Vec<DetailLoad>,
+    pub script: JobScript,
+    pub logs: LogView,
+    /// Site overrides for remediation hints
+    pub hints: HashMap<String, String>,
+    /// The keys hints name
+    pub hint_keys: HintKeys,
+    /// Tags and note of the job, loaded by the app with the accounting record
+    pub note: Option<JobNote>,
+    /// Job pinned for comparison; kept when switching jobs
+    pub baseline: Option<Baseline>,
+    /// Metrics reported in the job's output, loaded while a baseline is pinned
+    pub metrics: BTreeMap<String, f64>,
+    /// States the job was seen in while polling, set by the app
+    pub transitions: Vec<Transition>,
+    /// The cluster's reservations, set by the app with the accounting record
+    pub reservations: Vec<Reservation>,
+    /// The cluster's licenses, set by the app with the accounting record
+    pub licenses: Vec<License>,
+    /// How the job could be preempted, set by the app with the accounting record
+    pub preemption: Option<PreemptionRisk>,
+    /// When the job, being preempted, will be stopped
+    pub preempt_time: Option<String>,
+    /// What `scontrol` says of the storage of a pending or running job
+    pub storage: Storage,
+    /// How event times are shown, as in the job list
+    pub times: TimeDisplay,
+    scroll: usize,
+}
+
+impl JobDetail {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            zoomed: false,
+            width_percent: 50,
+            tab: DetailTab::Overview,
+            job: None,
+            accounting: None,
+            usage: None,
+            nodes: None,
+            steps: None,
+            events: None,
+            loading: Vec::new(),
+            script: JobScript::new(),
+            logs: LogView::new(),
+            hints: HashMap::new(),
+            hint_keys: HintKeys::default(),
+            note: None,
+            baseline: None,
+            metrics: BTreeMap::new(),
+            transitions: Vec::new(),
+            reservations: Vec::new(),
+            licenses: Vec::new(),
+            preemption: None,
+            preempt_time: None,
+            storage: Storage::default(),
+            times: TimeDisplay::default(),
+            scroll: 0,
+        }
+    }
+
+    /// Show the pane for a job
+    pub fn show(&mut self, job: Job) {
+        self.visible = true;
+        self.change_job(job);
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+        self.logs.hide();
+        self.script.hide();
+    }
+
+    /// Switch to another job, dropping everything fetched for the previous one
+    pub fn change_job(&mut self, job: Job) {
+        self.job = Some(job);
+        self.accounting = None;
+        self.usage = None;
+        self.nodes = None;
+        self.steps = None;
+        self.events = None;
+        self.loading.clear();
+        self.note = None;
+        self.metrics.clear();
+        self.transitions.clear();
+        self.storage = Storage::default();
+        self.scroll = 0;
+        self.activate_tab();
+    }
+
+    /// Take the polled record of the shown job. What was fetched for it is
+    /// kept unless the job changed state, when it is fetched again.
+    pub fn update_job(&mut self, mut job: Job) {
+        let Some(old) = self.job.as_ref().filter(|j| j.id == job.id) else {
+            return;
+        };
+        if old.state != job.state {
+            self.accounting = None;
+            self.usage = None;
+            self.nodes = None;
+            self.steps = None;
+        } else if let Some(Ok(fields)) = &self.accounting {
+            for (field, value) in fields {
+                apply_sacct_field(&mut job, field, value);
+            }
+            job.max_rss = job.max_rss.or_else(|| old.max_rss.clone());
+        }
+        self.job = Some(job);
+    }
+
+    /// What the app still needs to fetch for the current tab, if anything
+    pub fn pending_load(&self) -> Option<DetailLoad> {
+        if !self.visible || self.job.is_none() {
+            return None;
+        }
+        let load = match self.tab {
+            DetailTab::Overview | DetailTab::Events if self.accounting.is_none() => {
+                Some(DetailLoad::Accounting)
+            }
+            DetailTab::Resources if self.usage.is_none() && self.job_running() => {
+                Some(DetailLoad::Usage)
+            }
+            DetailTab::Nodes if self.nodes.is_none() && self.job_running() => {
+                Some(DetailLoad::Nodes)
+            }
+            DetailTab::Steps if self.steps.is_none() && !self.job_pending() => {
+                Some(DetailLoad::Steps)
+            }
+            DetailTab::Events if self.events.is_none() && !self.job_pending() => {
+                Some(DetailLoad::Events)
+            }
+            _ => None,
+        };
+        load.filter(|load| !self.loading.contains(load))
+    }
+
+    /// Note that the app fetches `load` off the UI thread
+    pub fn start_loading(&mut self, load: DetailLoad) {
+        self.loading.push(load);
+    }
+
+    /// Store the sacct record; its fields also refresh the job (exit codes,
+    /// final state) so the failure reason can be derived from it
+    pub fn set_accounting(&mut self, accounting: Result<Vec<(String, String)>, String>) {
+        if let (Some(job), Ok(fields)) = (self.job.as_mut(), &accounting) {
+            for (field, value) in fields {
+                apply_sacct_field(job, field, value);
+            }
+        }
+        self.accounting = Some(accounting);
+    }
+
+    /// Peak memory of the job's steps, used to recognize OOM kills
+    pub fn set_max_rss(&mut self, max_rss: Option<String>) {
+        if let Some(job) = self.job.as_mut() {
+            job.max_rss = max_rss;
+        }
+    }
+
+    pub fn set_usage(&mut self, usage: Result<Vec<StepUsage>, String>) {
+        self.loading.retain(|l| *l != DetailLoad::Usage);
+        self.usage = Some(usage);
+    }
+
+    pub fn set_nodes(&mut self, nodes: Result<Vec<NodeSnapshot>, String>) {
+        self.loading.retain(|l| *l != DetailLoad::Nodes);
+        self.nodes = Some(nodes);
+    }
+
+    pub fn set_steps(&mut self, steps: Result<Vec<Job>, String>) {
+        self.steps = Some(steps);
+    }
+
+    pub fn set_events(&mut self, events: Result<Vec<JobEvent>, String>) {
+        self.loading.retain(|l| *l != DetailLoad::Events);
+        self.events = Some(events);
+    }
+
+    fn job_pending(&self) -> bool {
+        self.job
+            .as_ref()
+            .is_some_and(|j| j.state == JobState::Pending)
+    }
+
+    fn job_running(&self) -> bool {
+        self.job
+            .as_ref()
+            .is_some_and(|j| j.state == JobState::Running)
+    }
+
+    /// Look up a field of the sacct record, if it has been fetched
+    pub fn accounting_field(&self, field: &str) -> Option<&str> {
+        match &self.accounting {
+            Some(Ok(fields)) => fields
+                .iter()
+                .find(|(k, _)| k == field)
+                .map(|(_, v)| v.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Start or stop the embedded widgets when the tab changes
+    fn activate_tab(&mut self) {
+        self.scroll = 0;
+        let Some(job) = self.job.clone() else {
+            return;
+        };
+
+        if self.tab == DetailTab::Script {
+            self.script.show(job.id.clone(), job.name.clone());
+        } else {
+            self.script.hide();
+        }
+
+        if self.tab == DetailTab::Logs {
+            self.logs.show(job.id.clone());
+        } else {
+            self.logs.hide();
+        }
+    }
+
+    pub fn handle_key(&mut self, input: Input) -> DetailAction {
+        match input {
+            Input::Action(Action::CloseDetail) => return DetailAction::Close,
+            Input::Action(Action::PrevTab) => {
+                self.tab = self.tab.previous();
+                self.activate_tab();
+            }
+            Input::Action(Action::NextTab) => {
+                self.tab = self.tab.next();
+                self.activate_tab();
+            }
+            Input::Action(Action::Wider) => self.width_percent = (self.width_percent + 10).min(80),
+            Input::Action(Action::Narrower) => {
+                self.width_percent = self.width_percent.saturating_sub(10).max(20)
+            }
+            Input::Action(Action::Zoom) => self.zoomed = !self.zoomed,
+            Input::Action(Action::Reload) => {
+                // Re-fetch the current tab
+                self.accounting = None;
+                self.usage = None;
+                self.nodes = None;
+                self.steps = None;
+                self.activate_tab();
+            }
+            _ => match (self.tab, pane_action(input)) {
+                (DetailTab::Script, Some(action)) => self.script.handle_key(Input::Action(action)),
+                (DetailTab::Logs, Some(action)) => self.logs.handle_key(Input::Action(action)),
+                (_, Some(Action::PagerUp)) => self.scroll = self.scroll.saturating_sub(1),
+                (_, Some(Action::PagerDown)) => self.scroll += 1,
+                (_, Some(Action::PagerPageUp)) => self.scroll = self.scroll.saturating_sub(10),
+                (_, Some(Action::PagerPageDown)) => self.scroll += 10,
+                _ => {}
+            },
+        }
+        DetailAction::None
+    }
+
+    /// Called periodically so the Logs tab follows the file
+    pub fn check_refresh(&mut self) {
+        if self.visible && self.tab == DetailTab::Logs {
+            self.logs.check_refresh();
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        if !self.visible {
+            return;
+        }
+        frame.render_widget(Clear, area);
+
+        let title = match &self.job {
+            Some(job) => format!("Job {} ({})", job.id, job.name),
+            None => "Job details".to_string(),
+        };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1), // Tabs
+                Constraint::Min(1),    // Content
+                Constraint::Length(1), // Help
+            ])
+            .split(inner);
+
+        let titles: Vec<Line> = DetailTab::all()
+            .iter()
+            .map(|t| Line::from(t.title()))
+            .collect();
+        let tabs = Tabs::new(titles).select(self.tab.index()).highlight_style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+        );
+        frame.render_widget(tabs, chunks[0]);
+
+        match self.tab {
+            DetailTab::Script => self.script.render(frame, chunks[1]),
+            DetailTab::Logs => self.logs.render(frame, chunks[1]),
+            _ => {
+                let lines = match self.tab {
+                    DetailTab::Overview => self.overview_lines(),
+                    DetailTab::Resources => self.resources_lines(),
+                    DetailTab::Nodes => self.nodes_lines(),
+                    DetailTab::Steps => self.steps_lines(),
+                    _ => self.events_lines(),
+                };
+                let content = Paragraph::new(lines)
+                    .wrap(Wrap { trim: false })
+                    .scroll((self.scroll as u16, 0));
+                frame.render_widget(content, chunks[1]);
+            }
+        }
+
+        let help = Paragraph::new(
+            "[h/l] Tab | [↑/↓] Scroll | [Shift+↑/↓] Job | [n] Note | [b] Baseline | [+/-] Resize | [z] Zoom | [r] Reload | [q] Close",
+        )
+        .style(Style::default().fg(Color::Gray));
+        frame.render_widget(help, chunks[2]);
+    }
+
+    /// Remediation hint for the job's state or pending reason, if there is one
+    fn hint(&self) -> Option<String> {
+        let job = self.job.as_ref()?;
+        let state = self
+            .accounting_field("State")
+            .map(str::to_string)
+            .unwrap_or_else(|| job.state.to_string());
+        let reason = self
+            .accounting_field("Reason")
+            .or(job.pending_reason.as_deref());
+        hint_for(&state, reason, &self.hints, &self.hint_keys)
+    }
+
+    fn overview_lines(&self) -> Vec<Line<'_>> {
+        let mut lines = Vec::new();
+        if let Some(reason) = self.job.as_ref().and_then(Job::failure_reason) {
+            lines.push(Line::styled(
+                format!("Why it died: {}", reason),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ));
+        }
+        if let Some(failure) = self.job.as_ref().and_then(Job::setup_failure) {
+            lines.push(Line::styled(
+                format!("Node problem: {}", failure),
+                Style::default()
+                    .fg(Color::LightMagenta)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        if let Some(hint) = self.hint() {
+            lines.push(Line::styled(
+                format!("Hint: {}", hint),
+                Style::default().fg(Color::Yellow),
+            ));
+            lines.push(Line::default());
+        }
+        lines.extend(self.blocker_lines());
+        if let Some(note) = &self.note {
+            let mut spans = vec![Span::styled("Note", Style::default().fg(Color::Cyan))];
+            if !note.tags.is_empty() {
+                spans.push(Span::styled(
+                    format!(" [{}]", note.tags.join(", ")),
+                    Style::default().fg(Color::Magenta),
+                ));
+            }
+            spans.push(Span::raw(format!(": {}", note.text)));
+            if !note.author.is_empty() {
+                spans.push(Span::styled(
+                    format!(" ({})", note.author),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            lines.push(Line::from(spans));
+            lines.push(Line::default());
+        }
+        lines.extend(self.baseline_lines());
+        lines.extend(self.efficiency_lines());
+        lines.extend(self.overview_fields());
+        lines
+    }
+
+    /// The reservation the job runs in, the reservations it cannot start
+    /// before and the licenses it waits for
+    fn blocker_lines(&self) -> Vec<Line<'_>> {
+        let Some(job) = &self.job else {
+            return Vec::new();
+        };
+        let now = Local::now();
+        let mut lines = Vec::new();
+        if let Some(name) = &job.reservation {
+            let ends = self
+                .reservations
+                .iter()
+                .find(|r| &r.name == name)
+                .and_then(|r| r.end_time)
+                .map_or(String::new(), |end| {
+                    format!(" (ends {})", format_minute(&end))
+                });
+            let verb = if job.state == JobState::Running {
+                "Runs inside"
+            } else {
+                "Uses"
+            };
+            lines.push(Line::styled(
+                format!("{} reservation {}{}", verb, name, ends),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
+        for reservation in self.reservations.iter().filter(|r| r.blocks(job, now)) {
+            let Some(start) = reservation.start_time else {
+                continue;
+            };
+            let fits = (start - now).to_std().unwrap_or_default();
+            lines.push(Line::styled(
+                format!(
+                    "⚠ Cannot start before reservation {} takes nodes of {} at {}; a time limit under {} would fit",
+                    reservation.name,
+                    if reservation.partition.is_empty() {
+                        reservation.nodes.as_str()
+                    } else {
+                        reservation.partition.as_str()
+                    },
+                    format_minute(&start),
+                    format_slurm_duration(fits)
+                ),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        for (license, wanted) in shortfalls(job, &self.licenses) {
+            lines.push(Line::styled(
+                format!(
+                    "⚠ Waiting for licenses: {} wants {}, {} of {} free",
+                    license.name, wanted, license.free, license.total
+                ),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        lines.extend(self.preemption_lines(job));
+        if !lines.is_empty() {
+            lines.push(Line::default());
+        }
+        lines
+    }
+
+    /// Whether the job was preempted or could be, to judge how often it
+    /// should checkpoint
+    fn preemption_lines(&self, job: &Job) -> Vec<Line<'_>> {
+        let warn = Style::default().fg(Color::Yellow);
+        let mut lines = Vec::new();
+        if job.state == JobState::Preempted {
+            lines.push(Line::styled(
+                "⚠ Preempted by a higher-priority job; checkpoints let a rerun resume",
+                warn,
+            ));
+        }
+        if let Some(time) = &self.preempt_time {
+            lines.push(Line::styled(
+                format!("⚠ Being preempted: stops at {}", time),
+                warn.add_modifier(Modifier::BOLD),
+            ));
+        }
+        if let Some(risk) = &self.preemption {
+            let outcome = match risk.mode.as_str() {
+                "requeue" => "requeued".to_string(),
+                "cancel" => "cancelled".to_string(),
+                "suspend" => "suspended".to_string(),
+                mode => format!("handled per the {}", mode),
+            };
+            let line = format!(
+                "Preemptable by QOS {} ({} job(s) pending there): it would be {}",
+                risk.by.join(", "),
+                risk.pending,
+                outcome
+            );
+            lines.push(if risk.pending > 0 {
+                Line::styled(format!("⚠ {}; checkpoint often", line), warn)
+            } else {
+                Line::styled(line, Style::default().fg(Color::Cyan))
+            });
+        }
+        lines
+    }
+
+    /// Deltas of the current job against the pinned baseline
+    fn baseline_lines(&self) -> Vec<Line<'_>> {
+        let (Some(baseline), Some(job)) = (&self.baseline, &self.job) else {
+            return Vec::new();
+        };
+        let title = Style::default()
+            .fg(Color::Magenta)
+            .add_modifier(Modifier::BOLD);
+        if baseline.job.id == job.id {
+            return vec![
+                Line::styled("Pinned as the baseline ([b] to unpin)", title),
+                Line::default(),
+            ];
+        }
+
+        let mut lines = vec![Line::styled(
+            format!(
+                "Compared with baseline {} ({})",
+                baseline.job.id, baseline.job.name
+            ),
+            title,
+        )];
+        let deltas = baseline.compare(job, &self.metrics);
+        let width = deltas.iter().map(|d| d.label.len()).max().unwrap_or(0);
+        for delta in &deltas {
+            let color = match delta.better {
+                Some(true) => Color::Green,
+                Some(false) => Color::Red,
+                None => Color::Gray,
+            };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {:<width$} : ", delta.label),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::raw(format!("{} → {} ", delta.baseline, delta.current)),
+                Span::styled(format!("({})", delta.change), Style::default().fg(color)),
+            ]));
+        }
+        if deltas.is_empty() {
+            lines.push(Line::from("  Nothing to compare yet"));
+        }
+        lines.push(Line::default());
+        lines
+    }
+
+    /// `seff`-style CPU and memory efficiency, once sacct has the figures
+    fn efficiency_lines(&self) -> Vec<Line<'_>> {
+        let Some(job) = self
+            .job
+            .as_ref()
+            .filter(|_| matches!(self.accounting, Some(Ok(_))))
+        else {
+            return Vec::new();
+        };
+        if job.cpu_efficiency().is_none() && job.memory_efficiency().is_none() {
+            return Vec::new();
+        }
+        let report = Report::of(job);
+        let mut lines = vec![Line::styled(
+            "Efficiency",
+            Style::default().add_modifier(Modifier::BOLD),
+        )];
+        lines.extend(key_value_lines(&report.rows(job)));
+        for (concern, warning) in report.warnings(job) {
+            let color = match concern {
+                Concern::Bad => Color::Red,
+                Concern::Fair => Color::Yellow,
+                Concern::Fine => Color::Green,
+            };
+            lines.push(Line::styled(
+                format!("⚠ {}", warning),
+                Style::default().fg(color),
+            ));
+        }
+        if job.state == JobState::Running {
+            lines.push(Line::styled(
+                "Figures of a running job cover the steps that have finished",
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        lines.push(Line::default());
+        lines
+    }
+
+    fn overview_fields(&self) -> Vec<Line<'_>> {
+        match &self.accounting {
+            Some(Ok(fields)) => {
+                // Joules, shown in Wh or kWh instead
+                let mut fields: Vec<(String, String)> = fields
+                    .iter()
+                    .filter(|(field, _)| field != "ConsumedEnergyRaw")
+                    .cloned()
+                    .collect();
+                if let Some(joules) = self.job.as_ref().and_then(|j| j.energy) {
+                    fields.push(("ConsumedEnergy".to_string(), format_energy(joules)));
+                }
+                fields.extend(self.storage_fields());
+                key_value_lines(&with_hosts(fields))
+            }
+            Some(Err(e)) => {
+                // Fall back to what the job list already knows.
+                let mut lines = vec![Line::styled(
+                    format!("sacct unavailable: {}", e),
+                    Style::default().fg(Color::Yellow),
+                )];
+                lines.push(Line::default());
+                let mut fields = self.list_fields();
+                fields.extend(self.storage_fields());
+                lines.extend(key_value_lines(&with_hosts(fields)));
+                lines
+            }
+            None => vec![Line::from("Loading...")],
+        }
+    }
+
+    fn resources_lines(&self) -> Vec<Line<'_>> {
+        if !self.job_running() {
+            return vec![Line::from(
+                "Live usage (sstat) is only available while the job is running.",
+            )];
+        }
+
+        match &self.usage {
+            Some(Ok(steps)) if steps.is_empty() => vec![Line::from("sstat returned no steps")],
+            Some(Ok(steps)) => {
+                let mut lines = Vec::new();
+                for step in steps {
+                    lines.push(Line::styled(
+                        format!("Step {}", step.get("JobID").unwrap_or("?")),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ));
+                    let fields: Vec<(String, String)> = step
+                        .fields
+                        .iter()
+                        .filter(|(k, _)| k != "JobID")
+                        .cloned()
+                        .collect();
+                    lines.extend(key_value_lines(&fields));
+                    lines.push(Line::default());
+                }
+                lines
+            }
+            Some(Err(e)) => vec![Line::styled(
+                format!("sstat unavailable: {}", e),
+                Style::default().fg(Color::Yellow),
+            )],
+            None => vec![Line::from("Loading...")],
+        }
+    }
+
+    fn nodes_lines(&self) -> Vec<Line<'_>> {
+        if !self.job_running() {
+            return vec![Line::from(
+                "Node processes can only be inspected while the job is running.",
+            )];
+        }
+
+        match &self.nodes {
+            Some(Ok(nodes)) => {
+                let mut lines = Vec::new();
+                for node in nodes {
+                    lines.push(Line::styled(
+                        node.node.clone(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ));
+                    for gpu in &node.gpus {
+                        lines.push(gpu_line(gpu));
+                    }
+                    if node.processes.is_empty() {
+                        lines.push(Line::from("  No processes"));
+                    } else {
+                        lines.push(Line::styled(
+                            format!(
+                                "  {:>8} {:>6} {:>5} {:>11}  COMMAND",
+                                "PID", "%CPU", "%MEM", "ELAPSED"
+                            ),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                    }
+                    for p in &node.processes {
+                        lines.push(Line::from(format!(
+                            "  {:>8} {:>6} {:>5} {:>11}  {}",
+                            p.pid, p.cpu, p.mem, p.elapsed, p.command
+                        )));
+                    }
+                    lines.push(Line::default());
+                }
+                lines.push(Line::styled(
+                    "[r] Inspect again",
+                    Style::default().fg(Color::DarkGray),
+                ));
+                lines
+            }
+            Some(Err(e)) => vec![Line::styled(
+                format!("Could not inspect the nodes: {}", e),
+                Style::default().fg(Color::Yellow),
+            )],
+            None => vec![Line::from("Inspecting the nodes...")],
+        }
+    }
+
+    fn steps_lines(&self) -> Vec<Line<'_>> {
+        if self.job_pending() {
+            return vec![Line::from("The job has no steps until it starts.")];
+        }
+
+        match &self.steps {
+            Some(Ok(steps)) if steps.is_empty() => vec![Line::from("No steps recorded.")],
+            Some(Ok(steps)) => {
+                let width = steps.iter().map(|s| s.id.len()).max().unwrap_or(0).max(4);
+                let name_width = steps.iter().map(|s| s.name.len()).max().unwrap_or(0).max(4);
+                let mut lines = vec![Line::styled(
+                    format!(
+                        "{:<width$}  {:<name_width$}  {:>11}  {:<13} {:>6} {:>10}  NODES",
+                        "STEP", "NAME", "ELAPSED", "STATE", "EXIT", "MAXRSS"
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                )];
+                for step in steps {
+                    let row = format!(
+                        "{:<width$}  {:<name_width$}  {:>11}  {:<13} {:>6} {:>10}  {}",
+                        step.id,
+                        step.name,
+                        format_slurm_duration(step.time),
+                        step.state.to_string(),
+                        step.exit_code.as_deref().unwrap_or("-"),
+                        step.max_rss.as_deref().unwrap_or("-"),
+                        step.node.as_deref().unwrap_or("")
+                    );
+                    let style = if step_failed(step) {
+                        Style::default().fg(Color::Red)
+                    } else {
+                        Style::default().fg(state_color(step.state))
+                    };
+                    lines.push(Line::styled(row, style));
+                }
+
+                let failed: Vec<&str> = steps
+                    .iter()
+                    .filter(|s| step_failed(s))
+                    .map(|s| s.id.as_str())
+                    .collect();
+                lines.push(Line::default());
+                if failed.is_empty() {
+                    lines.push(Line::from("No step failed."));
+                } else {
+                    lines.push(Line::styled(
+                        format!("Failed: {}", failed.join(", ")),
+                        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                    ));
+                }
+                lines
+            }
+            Some(Err(e)) => vec![Line::styled(
+                format!("Steps unavailable: {}", e),
+                Style::default().fg(Color::Yellow),
+            )],
+            None => vec![Line::from("Loading...")],
+        }
+    }
+
+    fn events_lines(&self) -> Vec<Line<'_>> {
+        if self.accounting.is_none() {
+            return vec![Line::from("Loading...")];
+        }
+
+        let mut lines = Vec::new();
+        // The job holds the record's times, once it has loaded
+        if let (Some(job), Some(Ok(_))) = (&self.job, &self.accounting) {
+            let timeline = Timeline::of(job, Local::now());
+            for (event, at) in [
+                ("Submitted", timeline.submit),
+                ("Eligible", job.eligible_time),
+                ("Started", timeline.start),
+                ("Ended", timeline.end),
+            ] {
+                if let Some(at) = at {
+                    lines.push(Line::from(vec![
+                        Span::styled(
+                            format!("{:<20}", self.times.format(&at)),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                        Span::raw(event),
+                    ]));
+                }
+            }
+        }
+        if let Some(state) = self.accounting_field("State").filter(is_known) {
+            lines.push(Line::default());
+            lines.push(Line::from(format!("Current state: {}", state)));
+        }
+        if lines.is_empty() {
+            lines.push(Line::from("No events recorded"));
+        }
+        lines.extend(self.slurm_event_lines());
+        lines.extend(self.timeline_lines());
+        lines
+    }
+
+    /// Node failures, requeues and resizes, with where each was read
+    fn slurm_event_lines(&self) -> Vec<Line<'_>> {
+        let title = Line::styled(
+            "Failures, requeues and resizes",
+            Style::default().add_modifier(Modifier::BOLD),
+        );
+        let mut lines = vec![Line::default(), title];
+        match &self.events {
+            None if self.job_pending() => return Vec::new(),
+            None => lines.push(Line::from("Loading...")),
+            Some(Err(e)) => lines.push(Line::styled(
+                format!("Unavailable: {}", e),
+                Style::default().fg(Color::Yellow),
+            )),
+            Some(Ok(events)) if events.is_empty() => lines.push(Line::styled(
+                "None recorded",
+                Style::default().fg(Color::DarkGray),
+            )),
+            Some(Ok(events)) => {
+                for event in events {
+                    let at = event
+                        .at
+                        .map_or("-".to_string(), |at| self.times.format(&at));
+                    let color = match event.kind {
+                        JobEventKind::NodeFailure | JobEventKind::StepFailure => Color::Red,
+                        JobEventKind::Requeue => Color::Yellow,
+                        JobEventKind::Resize => Color::Cyan,
+                    };
+                    lines.push(Line::from(vec![
+                        Span::styled(format!("{:<20}", at), Style::default().fg(Color::DarkGray)),
+                        Span::styled(
+                            format!("{:<14}", event.kind.label()),
+                            Style::default().fg(color),
+                        ),
+                        Span::raw(event.detail.clone()),
+                        Span::styled(
+                            format!("  ({})", event.source),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                    ]));
+                }
+            }
+        }
+        lines
+    }
+
+    /// Time spent queued and running, as durations and a bar, followed by
+    /// the states seen while polling
+    fn timeline_lines(&self) -> Vec<Line<'_>> {
+        let Some(job) = &self.job else {
+            return Vec::new();
+        };
+        let now = Local::now();
+        // sacct's times of jobs that have left squeue are in the job, once
+        // the record has loaded
+        let timeline = Timeline::of(job, now);
+
+        let title = Style::default().add_modifier(Modifier::BOLD);
+        let mut lines = vec![Line::default(), Line::styled("Timeline", title)];
+        let duration = |d: Option<std::time::Duration>| {
+            d.map(format_slurm_duration)
+                .unwrap_or_else(|| "-".to_string())
+        };
+        lines.extend(key_value_lines(&[
+            ("Queued".to_string(), duration(timeline.queued())),
+            ("Running".to_string(), duration(timeline.running())),
+        ]));
+        let (queued, running) = timeline.bar(TIMELINE_WIDTH);
+        if queued + running > 0 {
+            lines.push(Line::from(vec![
+                Span::raw("["),
+                Span::styled("░".repeat(queued), Style::default().fg(Color::Yellow)),
+                Span::styled("█".repeat(running), Style::default().fg(Color::Green)),
+                Span::raw("]"),
+            ]));
+        }
+
+        if !self.transitions.is_empty() {
+            lines.push(Line::default());
+            lines.push(Line::styled("Seen while polling", title));
+            for (i, transition) in self.transitions.iter().enumerate() {
+                let ago = format!(
+                    "{} ago",
+                    format_slurm_duration((now - transition.at).to_std().unwrap_or_default())
+                );
+                let event = if i == 0 {
+                    format!("first seen {}", transition.state)
+                } else {
+                    transition.state.to_string()
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{:<20}", ago), Style::default().fg(Color::DarkGray)),
+                    Span::styled(event, Style::default().fg(state_color(transition.state))),
+                ]));
+            }
+        }
+        lines
+    }
+
+    /// Local scratch and burst buffer, which the TRES strings and the
+    /// `scontrol` record bury
+    fn storage_fields(&self) -> Vec<(String, String)> {
+        let Some(job) = &self.job else {
+            return Vec::new();
+        };
+        [
+            (
+                "Tmp disk (per node)",
+                job.tmp_disk.or(self.storage.tmp_disk),
+            ),
+            (
+                "Burst buffer",
+                job.burst_buffer.or(self.storage.burst_buffer),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(label, size)| Some((label.to_string(), size?.to_string())))
+        .collect()
+    }
+
+    /// Fields from the job list row, used when sacct has nothing to offer
+    fn list_fields(&self) -> Vec<(String, String)> {
+        let Some(job) = &self.job else {
+            return Vec::new();
+        };
+        let opt = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+        let time =
+            |t: &Option<DateTime<Local>>| t.as_ref().map_or("-".to_string(), format_slurm_time);
+        let mut fields = vec![
+            ("JobID".to_string(), job.id.clone()),
+            ("JobName".to_string(), job.name.clone()),
+            ("User".to_string(), job.user.clone()),
+            ("State".to_string(), job.state.to_string()),
+            ("Partition".to_string(), job.partition.clone()),
+            ("Elapsed".to_string(), format_slurm_duration(job.time)),
+            (
+                "Timelimit".to_string(),
+                job.time_limit
+                    .map(format_slurm_duration)
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            ("NodeList".to_string(), opt(&job.node)),
+            ("AllocCPUS".to_string(), job.cpus.to_string()),
+            (
+                "ReqMem".to_string(),
+                job.memory.map(|m| m.to_string()).unwrap_or_default(),
+            ),
+            ("Submit".to_string(), time(&job.submit_time)),
+            ("End".to_string(), time(&job.end_time)),
+        ];
+        for (field, value) in [
+            ("WCKey", &job.wckey),
+            ("Comment", &job.comment),
+            ("AdminComment", &job.admin_comment),
+        ] {
+            if let Some(value) = value {
+                fields.push((field.to_string(), value.clone()));
+            }
+        }
+        fields
+    }
+}
+
+/// `GPU 0 A100  [█████░░░░░]  52%  40511/81920 MiB`
+/// A step that ended in anything but success
+fn step_failed(step: &Job) -> bool {
+    !step.state.is_active() && step.state != JobState::Completed
+}
+
+fn gpu_line(gpu: &GpuUsage) -> Line<'static> {
+    let percent = gpu.utilization.unwrap_or(0).min(100) as usize;
+    let filled = percent / 10;
+    let color = match percent {
+        0..=19 => Color::Red,
+        20..=69 => Color::Yellow,
+        _ => Color::Green,
+    };
+    let utilization = gpu
+        .utilization
+        .map(|u| format!("{:>3}%", u))
+        .unwrap_or_else(|| "   ?".to_string());
+    let memory = match (gpu.memory_used, gpu.memory_total) {
+        (Some(used), Some(total)) => format!("{}/{} MiB", used, total),
+        _ => "memory ?".to_string(),
+    };
+    Line::from(vec![
+        Span::styled(
+            format!("  GPU {} {} ", gpu.index, gpu.name),
+            Style::default().fg(Color::Cyan),
+        ),
+        Span::raw("["),
+        Span::styled("█".repeat(filled), Style::default().fg(color)),
+        Span::raw("░".repeat(10 - filled)),
+        Span::raw(format!("] {}  {}", utilization, memory)),
+    ])
+}
+
+/// Follow a compressed `NodeList` (`gpu[01-03]`) with its individual hosts
+fn with_hosts(fields: Vec<(String, String)>) -> Vec<(String, String)> {
+    let mut out = Vec::with_capacity(fields.len() + 1);
+    for (key, value) in fields {
+        let hosts = (key == "NodeList").then(|| expand(&value));
+        out.push((key, value));
+        if let Some(hosts) = hosts.filter(|h| h.len() > 1) {
+            out.push((format!("Nodes ({})", hosts.len()), hosts.join(" ")));
+        }
+    }
+    out
+}
+
+fn is_known(value: &&str) -> bool {
+    !value.is_empty() && *value != "Unknown" && *value != "None" && *value != "N/A"
+}
+
+fn key_value_lines(fields: &[(String, String)]) -> Vec<Line<'static>> {
+    let width = fields.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+    fields
+        .iter()
+        .map(|(k, v)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<width$} : ", k),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::raw(if v.is_empty() {
+                    "-".to_string()
+                } else {
+                    v.clone()
+                }),
+            ])
+        })
+        .collect()
+}
+
+/// What a key of the detail pane does in a tab showing a script or log:
+/// it scrolls them, and the keys of the script and log views, which the
+/// pane does not bind, work as in those views
+fn pane_action(input: Input) -> Option<Action> {
+    match input {
+        Input::Action(Action::ScrollUp) => Some(Action::PagerUp),
+        Input::Action(Action::ScrollDown) => Some(Action::PagerDown),
+        Input::Action(Action::PageUp) => Some(Action::PagerPageUp),
+        Input::Action(Action::PageDown) => Some(Action::PagerPageDown),
+        Input::Key(key) => match (key.modifiers, key.code) {
+            (KeyModifiers::CONTROL, KeyCode::Char('u')) => Some(Action::PagerPageUp),
+            (KeyModifiers::CONTROL, KeyCode::Char('d')) => Some(Action::PagerPageDown),
+            (_, KeyCode::Char('y')) => Some(Action::ScriptCopy),
+            (_, KeyCode::Char('w')) => Some(Action::ScriptSave),
+            (_, KeyCode::Char('o')) => Some(Action::LogStream),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -8,7 +8,8 @@ use ratatui::{
 };
 use regex::Regex;
 
-use crate::slurm::{squeue::SqueueOptions, JobState};
+use crate::keymap::{Action, Input};
+use crate::slurm::{sacct::name_regex, squeue::SqueueOptions, JobState};
 
 /// Filter popup state
 pub struct FilterPopup {
@@ -26,14 +27,22 @@ pub struct FilterPopup {
     pub partition_list_state: ListState,
     /// QoS filter list state
     pub qos_list_state: ListState,
-    /// Job name regex filter
+    /// Job name glob or regex filter
     pub name_filter: String,
     /// Node regex filter
     pub node_filter: String,
+    /// Accounts, comma-separated
+    pub accounts: String,
+    /// WCKeys, comma-separated
+    pub wckeys: String,
     /// Recent-ended jobs lookback window (hours), as input text
     pub ended_hours: String,
     /// Whether the ended-hours input is valid
     pub ended_hours_valid: Option<bool>,
+    /// Expected-start window (hours) for pending jobs, as input text
+    pub start_within: String,
+    /// Whether the start window is valid (`None` when empty: no filter)
+    pub start_within_valid: Option<bool>,
     /// Whether the name regex is valid
     pub name_regex_valid: Option<bool>,
     /// Whether the node regex is valid
@@ -47,11 +56,14 @@ pub struct FilterPopup {
 pub enum FilterFocus {
     Username,
     EndedHours,
+    StartWithin,
     States,
     Partitions,
     QoS,
     NameFilter,
     NodeFilter,
+    Accounts,
+    WCKeys,
 }
 
 impl FilterPopup {
@@ -76,8 +88,12 @@ impl FilterPopup {
             qos_list_state,
             name_filter: String::new(),
             node_filter: String::new(),
+            accounts: String::new(),
+            wckeys: String::new(),
             ended_hours: String::new(),
             ended_hours_valid: None,
+            start_within: String::new(),
+            start_within_valid: None,
             name_regex_valid: None,
             node_regex_valid: None,
             visible: false,
@@ -89,8 +105,15 @@ impl FilterPopup {
         self.username = options.user.clone().unwrap_or_default();
         self.name_filter = options.name_filter.clone().unwrap_or_default();
         self.node_filter = options.node_filter.clone().unwrap_or_default();
+        self.accounts = options.accounts.join(",");
+        self.wckeys = options.wckeys.join(",");
         self.ended_hours = recent_ended_hours.to_string();
         self.validate_ended_hours();
+        self.start_within = options
+            .start_within_hours
+            .map(|h| h.to_string())
+            .unwrap_or_default();
+        self.validate_start_within();
 
         // Validate regex if name_filter is not empty
         if !self.name_filter.is_empty() {
@@ -114,7 +137,7 @@ impl FilterPopup {
             return;
         }
 
-        match Regex::new(&self.name_filter) {
+        match name_regex(&self.name_filter) {
             Ok(_) => self.name_regex_valid = Some(true),
             Err(_) => self.name_regex_valid = Some(false),
         }
@@ -145,6 +168,15 @@ impl FilterPopup {
         };
     }
 
+    fn validate_start_within(&mut self) {
+        let trimmed = self.start_within.trim();
+        self.start_within_valid = match trimmed.parse::<u32>() {
+            _ if trimmed.is_empty() => None,
+            Ok(v) if v >= 1 => Some(true),
+            _ => Some(false),
+        };
+    }
+
     /// Render the filter popup
     pub fn render(
         &mut self,
@@ -195,7 +227,10 @@ impl FilterPopup {
         self.render_qos_tab(frame, bottom_chunks[2], options, all_qos);
 
         // Render help text at the bottom
-        let help_text = "↑/↓: Navigate | ←/→: Switch Filters | Enter: Select/Input | Ctrl+a: Apply | Esc: Close";
+        let help_text = format!(
+            "↑/↓: Navigate | ←/→: Switch Filters | Enter: Select/Input | g: GPU jobs only [{}] | Ctrl+a: Apply | Esc: Close",
+            if options.gpu_only { "X" } else { " " }
+        );
         let help = Paragraph::new(help_text)
             .style(Style::default().fg(Color::Gray))
             .block(Block::default().borders(Borders::ALL));
@@ -209,10 +244,13 @@ impl FilterPopup {
             .direction(Direction::Horizontal)
             .margin(1)
             .constraints([
-                Constraint::Ratio(1, 4), // Username
-                Constraint::Ratio(1, 4), // Ended hours
-                Constraint::Ratio(1, 4), // Job name filter
-                Constraint::Ratio(1, 4), // Node filter
+                Constraint::Ratio(1, 7), // Username
+                Constraint::Ratio(1, 7), // Ended hours
+                Constraint::Ratio(1, 7), // Expected start window
+                Constraint::Ratio(1, 7), // Job name filter
+                Constraint::Ratio(1, 7), // Node filter
+                Constraint::Ratio(1, 7), // Accounts
+                Constraint::Ratio(1, 7), // WCKeys
             ])
             .split(area);
 
@@ -236,8 +274,10 @@ impl FilterPopup {
             Some(false) => "Ended last (hours) ✗ Invalid",
             None => "Ended last (hours)",
         };
-        let ended_block_style = match (self.focus == FilterFocus::EndedHours, self.ended_hours_valid)
-        {
+        let ended_block_style = match (
+            self.focus == FilterFocus::EndedHours,
+            self.ended_hours_valid,
+        ) {
             (true, _) => Style::default().fg(Color::Cyan),
             (false, Some(false)) => Style::default().fg(Color::Red),
             _ => Style::default(),
@@ -249,6 +289,27 @@ impl FilterPopup {
         let ended_text = Paragraph::new(self.ended_hours.clone()).block(ended_block);
         frame.render_widget(ended_text, chunks[1]);
 
+        // Expected start window field
+        let start_title = match self.start_within_valid {
+            Some(true) => "Starts within (hours) ✓",
+            Some(false) => "Starts within (hours) ✗ Invalid",
+            None => "Starts within (hours)",
+        };
+        let start_block_style = match (
+            self.focus == FilterFocus::StartWithin,
+            self.start_within_valid,
+        ) {
+            (true, _) => Style::default().fg(Color::Cyan),
+            (false, Some(false)) => Style::default().fg(Color::Red),
+            _ => Style::default(),
+        };
+        let start_block = Block::default()
+            .title(start_title)
+            .borders(Borders::ALL)
+            .style(start_block_style);
+        let start_text = Paragraph::new(self.start_within.clone()).block(start_block);
+        frame.render_widget(start_text, chunks[2]);
+
         // Job name filter field
         // Show validation status in the title for name filter
         let name_title = match self.name_regex_valid {
@@ -273,7 +334,7 @@ impl FilterPopup {
 
         let name_filter_text = Paragraph::new(self.name_filter.clone()).block(name_filter_block);
 
-        frame.render_widget(name_filter_text, chunks[2]);
+        frame.render_widget(name_filter_text, chunks[3]);
 
         // Node filter field
         // Show validation status in the title for node filter
@@ -299,7 +360,31 @@ impl FilterPopup {
 
         let node_filter_text = Paragraph::new(self.node_filter.clone()).block(node_filter_block);
 
-        frame.render_widget(node_filter_text, chunks[3]);
+        frame.render_widget(node_filter_text, chunks[4]);
+
+        // Accounts field
+        let accounts_block = Block::default()
+            .title("Accounts (a,b)")
+            .borders(Borders::ALL)
+            .style(if self.focus == FilterFocus::Accounts {
+                Style::default().fg(Color::Cyan)
+            } else {
+                Style::default()
+            });
+        let accounts_text = Paragraph::new(self.accounts.clone()).block(accounts_block);
+        frame.render_widget(accounts_text, chunks[5]);
+
+        // WCKeys field
+        let wckeys_block = Block::default()
+            .title("WCKeys (a,b)")
+            .borders(Borders::ALL)
+            .style(if self.focus == FilterFocus::WCKeys {
+                Style::default().fg(Color::Cyan)
+            } else {
+                Style::default()
+            });
+        let wckeys_text = Paragraph::new(self.wckeys.clone()).block(wckeys_block);
+        frame.render_widget(wckeys_text, chunks[6]);
 
         // Show cursor when in input mode
         if self.input_mode {
@@ -312,14 +397,25 @@ impl FilterPopup {
                     chunks[1].x + 1 + self.ended_hours.len() as u16,
                     chunks[1].y + 1,
                 ),
-                FilterFocus::NameFilter => (
-                    chunks[2].x + 1 + self.name_filter.len() as u16,
+                FilterFocus::StartWithin => (
+                    chunks[2].x + 1 + self.start_within.len() as u16,
                     chunks[2].y + 1,
                 ),
-                FilterFocus::NodeFilter => (
-                    chunks[3].x + 1 + self.node_filter.len() as u16,
+                FilterFocus::NameFilter => (
+                    chunks[3].x + 1 + self.name_filter.len() as u16,
                     chunks[3].y + 1,
                 ),
+                FilterFocus::NodeFilter => (
+                    chunks[4].x + 1 + self.node_filter.len() as u16,
+                    chunks[4].y + 1,
+                ),
+                FilterFocus::Accounts => (
+                    chunks[5].x + 1 + self.accounts.len() as u16,
+                    chunks[5].y + 1,
+                ),
+                FilterFocus::WCKeys => {
+                    (chunks[6].x + 1 + self.wckeys.len() as u16, chunks[6].y + 1)
+                }
                 _ => (0, 0),
             };
 
@@ -452,7 +548,7 @@ impl FilterPopup {
     /// Handle key events for the filter popup
     pub fn handle_key(
         &mut self,
-        key: crossterm::event::KeyEvent,
+        input: Input,
         options: &mut SqueueOptions,
         all_states: &[JobState],
         all_partitions: &[String],
@@ -461,7 +557,13 @@ impl FilterPopup {
     ) -> FilterAction {
         use crossterm::event::KeyCode;
 
-        // Handle global keys first
+        let key = match input {
+            Input::Action(Action::FilterClose) => return FilterAction::Close,
+            Input::Action(Action::FilterApply) => return FilterAction::Apply,
+            Input::Action(_) => return FilterAction::None,
+            Input::Key(key) => key,
+        };
+        // Handle global keys first; while typing they are not bound
         match key.code {
             KeyCode::Esc => return FilterAction::Close,
             KeyCode::Tab => {
@@ -490,8 +592,11 @@ impl FilterPopup {
                 match self.focus {
                     FilterFocus::Username
                     | FilterFocus::EndedHours
+                    | FilterFocus::StartWithin
                     | FilterFocus::NameFilter
-                    | FilterFocus::NodeFilter => {
+                    | FilterFocus::NodeFilter
+                    | FilterFocus::Accounts
+                    | FilterFocus::WCKeys => {
                         self.input_mode = true;
                         FilterAction::None
                     }
@@ -608,32 +713,36 @@ impl FilterPopup {
                 }
                 FilterAction::None
             }
+            KeyCode::Char('g') => {
+                options.gpu_only = !options.gpu_only;
+                FilterAction::None
+            }
             KeyCode::Left => {
                 // Change tab
                 if self.tab_index > 0 {
                     self.tab_index -= 1;
                     self.update_focus_for_tab();
-                    return FilterAction::None;
+                    FilterAction::None
                 } else if self.tab_index == 0 {
-                    self.tab_index = 6; // Wrap around to last tab
+                    self.tab_index = 9; // Wrap around to last tab
                     self.update_focus_for_tab();
-                    return FilterAction::None;
+                    FilterAction::None
                 } else {
-                    return FilterAction::None; // No change if already at first tab
+                    FilterAction::None // No change if already at first tab
                 }
             }
             KeyCode::Right => {
                 // Change tab
-                if self.tab_index < 6 {
+                if self.tab_index < 9 {
                     self.tab_index += 1;
                     self.update_focus_for_tab();
-                    return FilterAction::None;
-                } else if self.tab_index == 6 {
+                    FilterAction::None
+                } else if self.tab_index == 9 {
                     self.tab_index = 0; // Wrap around to first tab
                     self.update_focus_for_tab();
-                    return FilterAction::None;
+                    FilterAction::None
                 } else {
-                    return FilterAction::None; // No change if already at last tab
+                    FilterAction::None // No change if already at last tab
                 }
             }
             _ => FilterAction::None,
@@ -660,15 +769,21 @@ impl FilterPopup {
                             options.user = Some(self.username.clone());
                         }
                     }
-                    FilterFocus::EndedHours => {
-                        if self.ended_hours_valid == Some(true) {
-                            if let Ok(v) = self.ended_hours.trim().parse::<u32>() {
-                                if v >= 1 {
-                                    *recent_ended_hours = v;
-                                }
+                    FilterFocus::EndedHours if self.ended_hours_valid == Some(true) => {
+                        if let Ok(v) = self.ended_hours.trim().parse::<u32>() {
+                            if v >= 1 {
+                                *recent_ended_hours = v;
                             }
                         }
                     }
+                    FilterFocus::StartWithin => match self.start_within_valid {
+                        None => options.start_within_hours = None,
+                        Some(true) => {
+                            options.start_within_hours = self.start_within.trim().parse().ok()
+                        }
+                        // If invalid, leave the existing filter unchanged
+                        Some(false) => {}
+                    },
                     FilterFocus::NameFilter => {
                         // Only set name_filter if regex is valid or empty
                         if self.name_filter.is_empty() {
@@ -689,6 +804,24 @@ impl FilterPopup {
                         }
                         // If invalid, leave the existing filter unchanged
                     }
+                    FilterFocus::Accounts => {
+                        options.accounts = self
+                            .accounts
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|a| !a.is_empty())
+                            .map(str::to_string)
+                            .collect();
+                    }
+                    FilterFocus::WCKeys => {
+                        options.wckeys = self
+                            .wckeys
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|k| !k.is_empty())
+                            .map(str::to_string)
+                            .collect();
+                    }
                     _ => {}
                 }
                 self.input_mode = false;
@@ -704,6 +837,10 @@ impl FilterPopup {
                         self.ended_hours.push(c);
                         self.validate_ended_hours();
                     }
+                    FilterFocus::StartWithin => {
+                        self.start_within.push(c);
+                        self.validate_start_within();
+                    }
                     FilterFocus::NameFilter => {
                         self.name_filter.push(c);
                         self.validate_name_regex();
@@ -712,6 +849,8 @@ impl FilterPopup {
                         self.node_filter.push(c);
                         self.validate_node_regex();
                     }
+                    FilterFocus::Accounts => self.accounts.push(c),
+                    FilterFocus::WCKeys => self.wckeys.push(c),
                     _ => {}
                 }
                 FilterAction::None
@@ -726,6 +865,10 @@ impl FilterPopup {
                         let _ = self.ended_hours.pop();
                         self.validate_ended_hours();
                     }
+                    FilterFocus::StartWithin => {
+                        let _ = self.start_within.pop();
+                        self.validate_start_within();
+                    }
                     FilterFocus::NameFilter => {
                         let _ = self.name_filter.pop();
                         self.validate_name_regex();
@@ -734,6 +877,12 @@ impl FilterPopup {
                         let _ = self.node_filter.pop();
                         self.validate_node_regex();
                     }
+                    FilterFocus::Accounts => {
+                        let _ = self.accounts.pop();
+                    }
+                    FilterFocus::WCKeys => {
+                        let _ = self.wckeys.pop();
+                    }
                     _ => {}
                 }
                 FilterAction::None
@@ -747,11 +896,14 @@ impl FilterPopup {
         match self.tab_index {
             0 => self.focus = FilterFocus::Username,
             1 => self.focus = FilterFocus::EndedHours,
-            2 => self.focus = FilterFocus::NameFilter,
-            3 => self.focus = FilterFocus::NodeFilter,
-            4 => self.focus = FilterFocus::States,
-            5 => self.focus = FilterFocus::Partitions,
-            6 => self.focus = FilterFocus::QoS,
+            2 => self.focus = FilterFocus::StartWithin,
+            3 => self.focus = FilterFocus::NameFilter,
+            4 => self.focus = FilterFocus::NodeFilter,
+            5 => self.focus = FilterFocus::Accounts,
+            6 => self.focus = FilterFocus::WCKeys,
+            7 => self.focus = FilterFocus::States,
+            8 => self.focus = FilterFocus::Partitions,
+            9 => self.focus = FilterFocus::QoS,
             _ => {}
         }
     }
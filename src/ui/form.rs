@@ -0,0 +1,223 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+/// Validates one field value; the error is shown under the form
+pub type Validator = fn(&str, &str) -> Result<(), String>;
+
+/// One labelled text field of a form
+#[derive(Debug, Clone)]
+pub struct FormField {
+    pub label: String,
+    pub value: String,
+}
+
+/// Action to take after handling a key in the form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormAction {
+    None,
+    Cancel,
+    Submit,
+}
+
+/// A generic popup form of labelled text fields
+pub struct FormPopup {
+    pub visible: bool,
+    pub title: String,
+    pub fields: Vec<FormField>,
+    pub selected: usize,
+    /// Extra lines shown above the fields (e.g. what will be submitted)
+    pub description: Vec<String>,
+    /// Conflicts found across fields, shown under the description
+    pub warnings: Vec<String>,
+    /// Validation or submission error of the last attempt
+    pub error: Option<String>,
+    validator: Option<Validator>,
+}
+
+impl FormPopup {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            title: String::new(),
+            fields: Vec::new(),
+            selected: 0,
+            description: Vec::new(),
+            warnings: Vec::new(),
+            error: None,
+            validator: None,
+        }
+    }
+
+    /// Open the form with the given `(label, value)` fields
+    pub fn show(
+        &mut self,
+        title: impl Into<String>,
+        fields: Vec<(String, String)>,
+        validator: Option<Validator>,
+    ) {
+        self.title = title.into();
+        self.fields = fields
+            .into_iter()
+            .map(|(label, value)| FormField { label, value })
+            .collect();
+        self.selected = 0;
+        self.description.clear();
+        self.warnings.clear();
+        self.error = None;
+        self.validator = validator;
+        self.visible = true;
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Run the validator over every field, stopping at the first error
+    fn validate(&self) -> Result<(), String> {
+        let Some(validator) = self.validator else {
+            return Ok(());
+        };
+        self.fields
+            .iter()
+            .try_for_each(|f| validator(&f.label, &f.value))
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> FormAction {
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc) => return FormAction::Cancel,
+            (_, KeyCode::Enter) => match self.validate() {
+                Ok(()) => {
+                    self.error = None;
+                    return FormAction::Submit;
+                }
+                Err(e) => self.error = Some(e),
+            },
+            (_, KeyCode::Up) | (KeyModifiers::SHIFT, KeyCode::BackTab) => {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            (_, KeyCode::Down) | (_, KeyCode::Tab) if self.selected + 1 < self.fields.len() => {
+                self.selected += 1;
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('u')) => {
+                if let Some(field) = self.fields.get_mut(self.selected) {
+                    field.value.clear();
+                }
+            }
+            (_, KeyCode::Backspace) => {
+                if let Some(field) = self.fields.get_mut(self.selected) {
+                    field.value.pop();
+                }
+            }
+            (_, KeyCode::Char(c)) => {
+                if let Some(field) = self.fields.get_mut(self.selected) {
+                    field.value.push(c);
+                }
+            }
+            _ => {}
+        }
+        FormAction::None
+    }
+
+    /// Insert pasted text into the selected field, on one line (shell
+    /// `\`-newline continuations are joined)
+    pub fn paste(&mut self, text: &str) {
+        if let Some(field) = self.fields.get_mut(self.selected) {
+            let text = text.replace("\\\r\n", " ").replace("\\\n", " ");
+            field
+                .value
+                .push_str(text.trim_end().replace(['\r', '\n'], " ").as_str());
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        if !self.visible {
+            return;
+        }
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(self.title.as_str())
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        // Warnings wrap; count the lines they take at this width
+        let width = inner.width.max(1) as usize;
+        let warning_lines = self
+            .warnings
+            .iter()
+            .map(|w| (w.chars().count() + 2).div_ceil(width) as u16)
+            .sum::<u16>();
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(self.description.len() as u16),
+                Constraint::Length(warning_lines),
+                Constraint::Min(1),    // Fields
+                Constraint::Length(2), // Error
+                Constraint::Length(1), // Help
+            ])
+            .split(inner);
+
+        let description: Vec<Line> = self
+            .description
+            .iter()
+            .map(|l| Line::styled(l.as_str(), Style::default().fg(Color::Gray)))
+            .collect();
+        frame.render_widget(Paragraph::new(description), chunks[0]);
+
+        let warnings: Vec<Line> = self
+            .warnings
+            .iter()
+            .map(|w| Line::styled(format!("! {}", w), Style::default().fg(Color::Yellow)))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(warnings).wrap(Wrap { trim: false }),
+            chunks[1],
+        );
+
+        let width = self.fields.iter().map(|f| f.label.len()).max().unwrap_or(0);
+        let lines: Vec<Line> = self
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                let selected = i == self.selected;
+                let label_style = if selected {
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Cyan)
+                };
+                let cursor = if selected { "▏" } else { "" };
+                Line::from(vec![
+                    Span::raw(if selected { "▶ " } else { "  " }),
+                    Span::styled(format!("{:<width$} : ", f.label), label_style),
+                    Span::raw(format!("{}{}", f.value, cursor)),
+                ])
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), chunks[2]);
+
+        if let Some(error) = &self.error {
+            let error = Paragraph::new(error.as_str())
+                .style(Style::default().fg(Color::Red))
+                .wrap(Wrap { trim: true });
+            frame.render_widget(error, chunks[3]);
+        }
+
+        let help = Paragraph::new(
+            "[↑/↓/Tab] Field | [type] Edit | [Ctrl+u] Clear | [Enter] Submit | [Esc] Cancel",
+        )
+        .style(Style::default().fg(Color::Gray));
+        frame.render_widget(help, chunks[4]);
+    }
+}
@@ -1,12 +1,17 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
-    layout::Rect,
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
-use std::{collections::HashMap, process::Command};
+use std::process::Command;
+
+use crate::clipboard;
+use crate::keymap::{Action, Input};
+use crate::slurm::command;
+use crate::slurm::directives::script_warnings;
+use crate::slurm::script::{fetch_batch_script, ScriptSource};
 
 /// JobScript viewer widget for displaying job batch scripts with syntax highlighting
 pub struct JobScript {
@@ -17,6 +22,14 @@ pub struct JobScript {
     pub scroll_position: usize,
     pub script_path: Option<String>,
     pub use_bat: bool, // If bat exists, use it for syntax highlighting
+    /// bat's ANSI output for `content`, when bat is available and succeeded
+    highlighted: Option<String>,
+    /// Whether `content` is the script itself (not an error message)
+    has_script: bool,
+    /// Result of the last copy/dump, shown in the title bar
+    notice: Option<String>,
+    /// Conflicting or redundant `#SBATCH` directives of the script
+    warnings: Vec<String>,
 }
 
 impl JobScript {
@@ -30,6 +43,10 @@ impl JobScript {
             scroll_position: 0,
             script_path: None,
             use_bat,
+            highlighted: None,
+            has_script: false,
+            notice: None,
+            warnings: Vec::new(),
         }
     }
 
@@ -110,8 +127,13 @@ impl JobScript {
 
         let title = format!("Job Script for {}/{}", job_name, job_id);
 
-        let help_text =
-            " [↑/↓] Scroll | [Ctrl+u/d] PageUp/Down | [Shift+↑/↓] Toggle Job| [q] Close ";
+        let help_text = match &self.notice {
+            Some(notice) => format!(" {} ", notice),
+            None => {
+                " [↑/↓] Scroll | [Ctrl+u/d] PageUp/Down | [Shift+↑/↓] Toggle Job | [y] Copy | [w] Save | [q] Close "
+                    .to_string()
+            }
+        };
 
         // Create text with line numbers if enabled
         let text = self.create_display_text();
@@ -128,45 +150,124 @@ impl JobScript {
             .wrap(Wrap { trim: false })
             .scroll((self.scroll_position as u16, 0));
 
-        frame.render_widget(script_paragraph, area);
+        if self.warnings.is_empty() {
+            frame.render_widget(script_paragraph, area);
+            return;
+        }
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(3),
+                Constraint::Length(self.warnings.len() as u16 + 2),
+            ])
+            .split(area);
+        frame.render_widget(script_paragraph, chunks[0]);
+        let warnings: Vec<Line> = self
+            .warnings
+            .iter()
+            .map(|w| Line::raw(w.as_str()))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(warnings)
+                .style(Style::default().fg(Color::Yellow))
+                .block(
+                    Block::default()
+                        .title("Directive warnings")
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Yellow)),
+                ),
+            chunks[1],
+        );
     }
 
-    pub fn handle_key(&mut self, key: KeyEvent) -> () {
-        match (key.modifiers, key.code) {
-            (_, KeyCode::Char('q')) => {
+    pub fn handle_key(&mut self, input: Input) {
+        match input {
+            Input::Action(Action::PagerClose) => {
                 // Close the script view
                 self.hide();
             }
-            (_, KeyCode::Up) => {
+            Input::Action(Action::PagerUp) => {
                 // Scroll up
                 self.scroll_up();
             }
-            (_, KeyCode::Down) => {
+            Input::Action(Action::PagerDown) => {
                 // Scroll down
                 self.scroll_down();
             }
-            (_, KeyCode::PageUp) | (KeyModifiers::CONTROL, KeyCode::Char('u')) => {
+            Input::Action(Action::PagerPageUp) => {
                 // Page up
                 self.page_up();
             }
-            (_, KeyCode::PageDown) | (KeyModifiers::CONTROL, KeyCode::Char('d')) => {
+            Input::Action(Action::PagerPageDown) => {
                 // Page down
                 self.page_down();
             }
+            Input::Action(Action::ScriptCopy) => {
+                // Copy the script to the clipboard
+                self.copy_to_clipboard();
+            }
+            Input::Action(Action::ScriptSave) => {
+                // Save the script for resubmission
+                self.dump_to_file();
+            }
             _ => {
                 // Ignore other keys
             }
         }
     }
 
+    fn copy_to_clipboard(&mut self) {
+        if !self.has_script {
+            self.notice = Some("No script to copy".to_string());
+            return;
+        }
+        self.notice = match clipboard::copy(&self.content) {
+            Ok(_) => Some("Script copied to clipboard".to_string()),
+            Err(e) => Some(format!("Copy failed: {}", e)),
+        };
+    }
+
+    /// Write the script to `<name>_<id>.sbatch` in the current directory
+    fn dump_to_file(&mut self) {
+        if !self.has_script {
+            self.notice = Some("No script to save".to_string());
+            return;
+        }
+        let name: String = self
+            .job_name
+            .as_deref()
+            .unwrap_or("job")
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let path = format!(
+            "{}_{}.sbatch",
+            name,
+            self.job_id.as_deref().unwrap_or("unknown")
+        );
+        self.notice = match std::fs::write(&path, &self.content) {
+            Ok(()) => Some(format!("Script saved to {}", path)),
+            Err(e) => Some(format!("Failed to save {}: {}", path, e)),
+        };
+    }
+
     /// Create display text with optional line numbers
-    fn create_display_text(&self) -> Text {
-        if self.use_bat {
-            let lines = parse_ansi_to_spans(&self.content);
+    fn create_display_text(&self) -> Text<'_> {
+        if let Some(highlighted) = &self.highlighted {
+            let lines = parse_ansi_to_spans(highlighted);
             return Text::from(lines);
         }
 
         let content_lines: Vec<&str> = self.content.lines().collect();
+        if !self.has_script {
+            return Text::from(self.content.as_str());
+        }
         let total_lines = content_lines.len();
 
         // Calculate the width needed for line numbers
@@ -177,92 +278,170 @@ impl JobScript {
 
         for (i, line) in content_lines.iter().enumerate() {
             let line_num = i + 1;
-            let mut spans = Vec::new();
-
-            spans.push(Span::styled(
+            let mut spans = vec![Span::styled(
                 format!("{:>width$} ", line_num, width = line_num_width - 1),
                 Style::default().fg(Color::DarkGray),
-            ));
-
-            spans.push(Span::raw(*line));
+            )];
+            spans.extend(highlight_shell_line(line));
             numbered_lines.push(Line::from(spans));
         }
 
         Text::from(numbered_lines)
     }
 
-    /// Fetch the job script content using scontrol
+    /// Fetch the job's batch script and prepare it for display
     fn fetch_script_content(&mut self) {
-        if let Some(job_id) = &self.job_id {
-            // First get job details to find BatchScript path
-            let output = Command::new("scontrol")
-                .args(["show", "job", job_id, "-o"])
-                .output();
-
-            if let Ok(output) = output {
-                if output.status.success() {
-                    let output_str = String::from_utf8_lossy(&output.stdout);
-                    let key_value_pairs = parse_scontrol_output(&output_str);
-
-                    // Get the BatchScript path
-                    if let Some(script_path) = key_value_pairs.get("Command") {
-                        self.script_path = Some(script_path.to_string());
-
-                        if self.use_bat {
-                            // If bat is installed, use it to create a syntax-highlighted version
-                            if let Some(bat_output) = create_bat_out_string(script_path) {
-                                self.content = bat_output;
-                                return;
-                            }
+        self.highlighted = None;
+        self.has_script = false;
+        self.notice = None;
+        self.warnings.clear();
+
+        let Some(job_id) = self.job_id.clone() else {
+            self.content = String::new();
+            return;
+        };
+
+        match fetch_batch_script(&job_id) {
+            Some(script) => {
+                if let ScriptSource::File(path) = &script.source {
+                    self.script_path = Some(path.clone());
+                }
+                if self.use_bat {
+                    self.highlighted = create_bat_out_string(&script.content);
+                }
+                self.warnings = script_warnings(&script.content);
+                self.content = script.content;
+                self.has_script = true;
+            }
+            None => {
+                self.content = format!(
+                    "No batch script available for job {}.\n\n\
+                     It may have been submitted with --wrap, or Slurm no longer keeps it \
+                     (scontrol forgets finished jobs; sacct only stores scripts when \
+                     AccountingStoreFlags=job_script is set).",
+                    job_id
+                );
+            }
+        }
+    }
+}
+
+const SHELL_KEYWORDS: &[&str] = &[
+    "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac",
+    "in", "function", "return", "export", "local", "source", "exit",
+];
+
+/// Minimal shell highlighting used when bat is not installed: `#SBATCH`
+/// directives, comments, keywords, variables and quoted strings.
+fn highlight_shell_line(line: &str) -> Vec<Span<'_>> {
+    let trimmed = line.trim_start();
+    if let Some(rest) = trimmed.strip_prefix("#SBATCH") {
+        let indent = &line[..line.len() - trimmed.len()];
+        return vec![
+            Span::raw(indent),
+            Span::styled(
+                "#SBATCH",
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(rest, Style::default().fg(Color::LightMagenta)),
+        ];
+    }
+    if trimmed.starts_with('#') {
+        return vec![Span::styled(line, Style::default().fg(Color::DarkGray))];
+    }
+
+    let mut spans = Vec::new();
+    let mut word_start = 0;
+    let mut chars = line.char_indices().peekable();
+    let flush_word = |spans: &mut Vec<Span<'_>>, word: &'_ str| {
+        if word.is_empty() {
+            return;
+        }
+        let style = if SHELL_KEYWORDS.contains(&word) {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        spans.push(Span::styled(word.to_string(), style));
+    };
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                flush_word(&mut spans, &line[word_start..i]);
+                let mut end = line.len();
+                for (j, d) in chars.by_ref() {
+                    if d == c {
+                        end = j + 1;
+                        break;
+                    }
+                }
+                spans.push(Span::styled(
+                    line[i..end].to_string(),
+                    Style::default().fg(Color::Green),
+                ));
+                word_start = end;
+            }
+            '$' => {
+                flush_word(&mut spans, &line[word_start..i]);
+                let mut end = i + 1;
+                if let Some(&(_, '{')) = chars.peek() {
+                    for (j, d) in chars.by_ref() {
+                        end = j + 1;
+                        if d == '}' {
+                            break;
                         }
-                        // If bat is not available, read the script directly
-                        self.use_bat = false;
-                        // Now read the script content
-                        if let Ok(script_content) = std::fs::read_to_string(script_path) {
-                            self.content = script_content;
+                    }
+                } else {
+                    while let Some(&(j, d)) = chars.peek() {
+                        if d.is_ascii_alphanumeric() || d == '_' {
+                            end = j + 1;
+                            chars.next();
                         } else {
-                            self.content =
-                                format!("Failed to read script from path: {}", script_path);
+                            break;
                         }
-                    } else {
-                        self.content =
-                            String::from("No script found for this job. Maybe it's wrapped");
                     }
-                } else {
-                    self.content = String::from("Error retrieving job information");
                 }
-            } else {
-                self.content = String::from("Failed to execute scontrol command");
+                spans.push(Span::styled(
+                    line[i..end].to_string(),
+                    Style::default().fg(Color::Cyan),
+                ));
+                word_start = end;
             }
-        } else {
-            self.content = String::new();
+            c if c.is_whitespace() || ";|&()".contains(c) => {
+                flush_word(&mut spans, &line[word_start..i]);
+                spans.push(Span::raw(line[i..i + c.len_utf8()].to_string()));
+                word_start = i + c.len_utf8();
+            }
+            _ => {}
         }
     }
+    flush_word(&mut spans, &line[word_start..]);
+    spans
 }
 
 /// Use bat to create a syntax-highlighted version of the script
-fn create_bat_out_string(path: &str) -> Option<String> {
-    let output = Command::new("bat")
-        .arg("--style=numbers,grid")
+fn create_bat_out_string(script: &str) -> Option<String> {
+    let mut bat = Command::new("bat");
+    bat.arg("--style=numbers,grid")
         .arg("--color=always")
+        .arg("--language=bash")
         .arg("--theme")
         .arg("Solarized (light)")
         .arg("--terminal-width=100")
-        .arg(path)
-        .output();
-    if let Ok(output) = output {
-        if output.status.success() {
-            Some(String::from_utf8_lossy(&output.stdout).to_string())
-        } else {
-            None
-        }
+        .arg("-");
+    let output = command::run_local(bat, Some(script.as_bytes())).ok()?;
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).to_string())
     } else {
         None
     }
 }
 
 /// Parse ANSI escape sequences into ratatui spans
-fn parse_ansi_to_spans(ansi_text: &str) -> Vec<Line> {
+fn parse_ansi_to_spans(ansi_text: &str) -> Vec<Line<'_>> {
     use regex::Regex;
 
     // Regex to match ANSI color escape sequences
@@ -394,31 +573,32 @@ fn parse_ansi_code(code: &str, mut style: Style) -> Style {
     style
 }
 
-fn parse_scontrol_output(output: &str) -> HashMap<String, String> {
-    let mut result = HashMap::new();
-
-    for part in output.split_whitespace() {
-        if let Some(index) = part.find('=') {
-            let key = &part[0..index];
-            let value = &part[(index + 1)..];
-            result.insert(key.to_string(), value.to_string());
-        }
-    }
-
-    result
-}
-
 /// Check if bat is installed on the system
 fn is_bat_installed() -> bool {
-    let output = Command::new("which").arg("bat").output();
-
-    match output {
+    let mut which = Command::new("which");
+    which.arg("bat");
+    match command::run_local(which, None) {
         Ok(output) => output.status.success(),
         Err(_) => {
             // Try the Windows "where" command as fallback
-            let windows_output = Command::new("where").arg("bat").output();
-
-            matches!(windows_output, Ok(output) if output.status.success())
+            let mut windows_where = Command::new("where");
+            windows_where.arg("bat");
+            matches!(command::run_local(windows_where, None), Ok(output) if output.status.success())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_highlighting_keeps_text_intact() {
+        let line = r#"if [ "$X" = 'a b' ]; then echo ${HOME}/out; fi"#;
+        let rebuilt: String = highlight_shell_line(line)
+            .iter()
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert_eq!(rebuilt, line);
+    }
+}
@@ -1,12 +1,196 @@
+use chrono::{DateTime, Local};
 use ratatui::{
-    layout::{Constraint, Rect},
+    layout::{Constraint, Flex, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
     Frame,
 };
 
-use crate::slurm::{Job, JobState};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::efficiency::{concern, overall, Concern};
+use crate::slurm::{energy::format_energy, het_component, Job, JobState};
+use crate::timeline::queue_wait;
 use crate::ui::columns::{JobColumn, SortColumn};
+use crate::ui::theme;
+use crate::utils::{format_slurm_duration, format_slurm_time, TimeDisplay};
+
+/// `text` cut to `width` characters, ending in `…` when cut
+fn ellipsize(text: String, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text;
+    }
+    let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    if width > 0 {
+        cut.push('…');
+    }
+    cut
+}
+
+/// `text` laid over cells of these widths, a word at a time so that the
+/// gaps between cells fall between words; a word wider than a cell of its
+/// own is cut
+fn spread(text: &str, widths: &[usize]) -> Vec<String> {
+    let mut cells = vec![String::new(); widths.len()];
+    let mut words = text.split(' ').peekable();
+    for (cell, &width) in cells.iter_mut().zip(widths) {
+        while let Some(word) = words.peek() {
+            let used = cell.chars().count();
+            let fits = if used == 0 {
+                word.chars().count() <= width
+            } else {
+                used + 1 + word.chars().count() <= width
+            };
+            if !fits && used > 0 {
+                break;
+            }
+            if used > 0 {
+                cell.push(' ');
+            }
+            cell.push_str(word);
+            words.next();
+            if !fits {
+                *cell = ellipsize(std::mem::take(cell), width);
+                break;
+            }
+        }
+    }
+    cells
+}
+
+/// Which columns fit in `available` cells: the pinned ones, then the others
+/// from the `offset`th on, in their order. Also the largest offset still
+/// worth scrolling to, from which the last column is shown.
+fn fit_columns(
+    widths: &[u16],
+    pinned: &[bool],
+    offset: usize,
+    available: u16,
+) -> (Vec<usize>, usize) {
+    // Each column but the first is spaced from the one before
+    let width_of = |i: usize| u32::from(widths[i]) + 1;
+    let budget = (u32::from(available) + 1)
+        .saturating_sub((0..widths.len()).filter(|&i| pinned[i]).map(width_of).sum());
+    let scrolled: Vec<usize> = (0..widths.len()).filter(|&i| !pinned[i]).collect();
+
+    let mut max_offset = scrolled.len();
+    let mut used = 0;
+    while max_offset > 0 && used + width_of(scrolled[max_offset - 1]) <= budget {
+        max_offset -= 1;
+        used += width_of(scrolled[max_offset]);
+    }
+    // At least one column scrolls into view, cut off if need be
+    let max_offset = max_offset.min(scrolled.len().saturating_sub(1));
+    let offset = offset.min(max_offset);
+
+    let mut shown: Vec<usize> = Vec::new();
+    let mut used = 0;
+    for &i in &scrolled[offset..] {
+        if !shown.is_empty() && used + width_of(i) > budget {
+            break;
+        }
+        used += width_of(i);
+        shown.push(i);
+    }
+    shown.extend((0..widths.len()).filter(|&i| pinned[i]));
+    shown.sort_unstable();
+    (shown, max_offset)
+}
+
+/// Share of the time limit used from which the time left is highlighted
+const NEAR_LIMIT: f64 = 0.9;
+
+/// What the grouped table mode puts jobs in sections by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Account,
+    User,
+    /// Running, pending, then (recently) ended
+    State,
+}
+
+/// Sections of `GroupBy::State`, in table order
+const STATE_SECTIONS: [&str; 4] = ["RUNNING", "PENDING", "SUSPENDED", "ENDED"];
+
+impl GroupBy {
+    /// The section a job belongs to
+    pub fn key(self, job: &Job) -> String {
+        match self {
+            GroupBy::Account => job.account.clone().unwrap_or_else(|| "(none)".to_string()),
+            GroupBy::User => job.user.clone(),
+            GroupBy::State => {
+                let section = match job.state {
+                    JobState::Running
+                    | JobState::Completing
+                    | JobState::Configuring
+                    | JobState::Resizing => STATE_SECTIONS[0],
+                    JobState::Pending => STATE_SECTIONS[1],
+                    JobState::Suspended => STATE_SECTIONS[2],
+                    _ => STATE_SECTIONS[3],
+                };
+                section.to_string()
+            }
+        }
+    }
+
+    /// Where the section goes: by state in lifecycle order, else by name
+    fn rank(self, key: &str) -> usize {
+        match self {
+            GroupBy::State => STATE_SECTIONS.iter().position(|s| *s == key).unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            GroupBy::Account => "account",
+            GroupBy::User => "user",
+            GroupBy::State => "state",
+        }
+    }
+}
+
+/// A section of the grouped table, summing up all of its jobs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Group {
+    pub key: String,
+    pub jobs: usize,
+    pub running: usize,
+    pub pending: usize,
+    /// CPUs held by its running jobs
+    pub cpus: u32,
+    /// CPU time its jobs have used so far, in hours
+    pub cpu_hours: f64,
+}
+
+impl Group {
+    fn add(&mut self, job: &Job) {
+        self.jobs += 1;
+        match job.state {
+            JobState::Running => {
+                self.running += 1;
+                self.cpus += job.cpus;
+            }
+            JobState::Pending => self.pending += 1,
+            _ => {}
+        }
+        self.cpu_hours += job.cpus as f64 * job.time.as_secs_f64() / 3600.0;
+    }
+}
+
+/// A row of the table: a section header or a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListRow {
+    Group(usize),
+    Job(usize),
+}
+
+/// What a table row shows, to find it again after the rows change
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RowKey {
+    Group(String),
+    Job(String),
+}
 
 /// Struct to manage the jobs list view
 pub struct JobsList {
@@ -15,6 +199,38 @@ pub struct JobsList {
     pub selected_jobs: Vec<usize>,
     pub sort_column: usize,
     pub sort_ascending: bool,
+    /// Row where visual (range) selection started, if visual mode is active
+    pub visual_anchor: Option<usize>,
+    /// Selection that existed before visual mode started; the range is added to it
+    visual_base: Vec<usize>,
+    /// Het jobs whose components are listed below them
+    pub expanded: HashSet<String>,
+    /// The jobs as given to `update_jobs`; `jobs` adds the components of
+    /// expanded het jobs, and keeps those of collapsed sections though
+    /// no row shows them
+    listed: Vec<Job>,
+    /// Jobs are listed in sections by this, when set
+    pub group_by: Option<GroupBy>,
+    /// Sections whose jobs are hidden
+    pub collapsed: HashSet<String>,
+    /// Sections of the grouped table, in order
+    pub groups: Vec<Group>,
+    /// The table rows; `state` indexes these, `selected_jobs` indexes `jobs`
+    layout: Vec<ListRow>,
+    /// Rows that fit on screen at the last render: the step of page up/down
+    page_rows: usize,
+    /// First of the unpinned columns shown, when they do not all fit
+    column_offset: usize,
+    /// Largest useful `column_offset` at the last render
+    max_column_offset: usize,
+    /// Where the table was drawn last, for mouse clicks
+    area: Rect,
+    /// Screen x and width of each column shown at the last render
+    column_spans: Vec<(u16, u16, JobColumn)>,
+    /// Watched jobs (ids), listed first whatever the filters
+    pub watchlist: Vec<String>,
+    /// How the submit, start and end times are shown
+    pub times: TimeDisplay,
 }
 
 impl JobsList {
@@ -25,27 +241,237 @@ impl JobsList {
             selected_jobs: Vec::new(),
             sort_column: 0, // Default sort by job ID
             sort_ascending: true,
+            visual_anchor: None,
+            visual_base: Vec::new(),
+            expanded: HashSet::new(),
+            listed: Vec::new(),
+            group_by: None,
+            collapsed: HashSet::new(),
+            groups: Vec::new(),
+            layout: Vec::new(),
+            page_rows: 10,
+            column_offset: 0,
+            max_column_offset: 0,
+            area: Rect::default(),
+            column_spans: Vec::new(),
+            watchlist: Vec::new(),
+            times: TimeDisplay::default(),
+        }
+    }
+
+    pub fn is_watched(&self, id: &str) -> bool {
+        self.watchlist.iter().any(|p| p == id)
+    }
+
+    /// Index in `jobs` of the job on a table row
+    fn job_at(&self, row: usize) -> Option<usize> {
+        match self.layout.get(row) {
+            Some(ListRow::Job(i)) => Some(*i),
+            _ => None,
+        }
+    }
+
+    fn key_at(&self, row: usize) -> Option<RowKey> {
+        match self.layout.get(row)? {
+            ListRow::Group(g) => Some(RowKey::Group(self.groups[*g].key.clone())),
+            ListRow::Job(i) => Some(RowKey::Job(self.jobs[*i].id.clone())),
         }
     }
 
+    fn row_with(&self, key: &RowKey) -> Option<usize> {
+        (0..self.layout.len()).find(|&row| self.key_at(row).as_ref() == Some(key))
+    }
+
+    /// Index in `jobs` of the job under the cursor
+    fn cursor_job(&self) -> Option<usize> {
+        self.state.selected().and_then(|row| self.job_at(row))
+    }
+
+    /// List the jobs in sections by `group_by`, or all together
+    pub fn set_group_by(&mut self, group_by: Option<GroupBy>) {
+        let cursor = self.selected_job().map(|j| RowKey::Job(j.id.clone()));
+        let selected = self.get_selected_jobs();
+        self.end_visual();
+        self.group_by = group_by;
+        self.collapsed.clear();
+        self.rebuild();
+        self.select_ids(&selected);
+        let row = cursor
+            .and_then(|key| self.row_with(&key))
+            .or((!self.layout.is_empty()).then_some(0));
+        self.state.select(row);
+    }
+
+    /// Number of table rows
+    fn row_count(&self) -> usize {
+        self.layout.len()
+    }
+
     /// Update the list of jobs
     pub fn update_jobs(&mut self, jobs: Vec<Job>) {
-        self.jobs = jobs;
-        // Jobs are already sorted by the squeue command
+        // Rows shift as jobs come and go, so the cursor, selection and visual
+        // range stay on the same jobs, and the cursor on the same screen row
+        let id_at = |rows: &[Job], i: usize| rows.get(i).map(|j| j.id.clone());
+        let cursor = self.state.selected();
+        let cursor_key = cursor.and_then(|row| self.key_at(row));
+        let anchor_id = self.visual_anchor.and_then(|i| id_at(&self.jobs, i));
+        let selected = self.get_selected_jobs();
+        let base: Vec<String> = self
+            .visual_base
+            .iter()
+            .filter_map(|&i| id_at(&self.jobs, i))
+            .collect();
+
+        self.listed = jobs;
+        self.rebuild();
+        let rows_of = |ids: &[String]| -> Vec<usize> {
+            (0..self.jobs.len())
+                .filter(|&i| ids.contains(&self.jobs[i].id))
+                .collect()
+        };
+        self.selected_jobs = rows_of(&selected);
+        self.visual_base = rows_of(&base);
+        self.visual_anchor = anchor_id.and_then(|id| self.jobs.iter().position(|j| j.id == id));
+        if self.visual_anchor.is_none() {
+            self.visual_base.clear();
+        }
 
-        // Reset selection if out of bounds
-        if let Some(selected) = self.state.selected() {
-            if selected >= self.jobs.len() {
-                self.state.select(Some(0));
+        // When the cursor's job left the list, the cursor keeps its row
+        let row = match (cursor_key.and_then(|key| self.row_with(&key)), cursor) {
+            _ if self.layout.is_empty() => None,
+            (Some(row), _) => Some(row),
+            (None, Some(row)) => Some(row.min(self.layout.len() - 1)),
+            (None, None) => Some(0),
+        };
+        if let (Some(old), Some(new)) = (cursor, row) {
+            let offset = self.state.offset_mut();
+            *offset = (*offset + new).saturating_sub(old);
+        }
+        self.state.select(row);
+    }
+
+    /// The listed jobs without the rows of het job components
+    pub fn listed_jobs(&self) -> &[Job] {
+        &self.listed
+    }
+
+    /// Rebuild `jobs`, `groups` and the table rows from the listed jobs
+    fn rebuild(&mut self) {
+        let with_components = |job: &Job, rows: &mut Vec<Job>| {
+            rows.push(job.clone());
+            if self.expanded.contains(&job.id) {
+                rows.extend(job.het_components.iter().cloned());
+            }
+        };
+        let mut jobs = Vec::with_capacity(self.listed.len());
+        self.groups.clear();
+        self.layout.clear();
+        match self.group_by {
+            None => {
+                for job in &self.listed {
+                    with_components(job, &mut jobs);
+                }
+                self.layout = (0..jobs.len()).map(ListRow::Job).collect();
             }
-        } else if !self.jobs.is_empty() {
-            self.state.select(Some(0));
+            Some(group_by) => {
+                // Sections in rank then name order; jobs keep their order
+                // within one
+                let mut sections: BTreeMap<(usize, String), Vec<&Job>> = BTreeMap::new();
+                for job in &self.listed {
+                    let key = group_by.key(job);
+                    sections
+                        .entry((group_by.rank(&key), key))
+                        .or_default()
+                        .push(job);
+                }
+                for ((_, key), members) in sections {
+                    let mut group = Group {
+                        key,
+                        ..Default::default()
+                    };
+                    for job in &members {
+                        group.add(job);
+                    }
+                    self.layout.push(ListRow::Group(self.groups.len()));
+                    let collapsed = self.collapsed.contains(&group.key);
+                    for job in members {
+                        let first = jobs.len();
+                        with_components(job, &mut jobs);
+                        if !collapsed {
+                            self.layout.extend((first..jobs.len()).map(ListRow::Job));
+                        }
+                    }
+                    self.groups.push(group);
+                }
+            }
+        }
+        self.jobs = jobs;
+    }
+
+    /// Select the jobs with these ids
+    fn select_ids(&mut self, ids: &[String]) {
+        self.selected_jobs = (0..self.jobs.len())
+            .filter(|&i| ids.contains(&self.jobs[i].id))
+            .collect();
+    }
+
+    /// Collapse or expand the section whose header is under the cursor;
+    /// returns whether anything changed
+    fn set_group_expanded(&mut self, expanded: bool) -> bool {
+        let Some(ListRow::Group(g)) = self.state.selected().and_then(|r| self.layout.get(r)) else {
+            return false;
+        };
+        let key = self.groups[*g].key.clone();
+        let changed = if expanded {
+            self.collapsed.remove(&key)
+        } else {
+            self.collapsed.insert(key.clone())
+        };
+        if changed {
+            // The jobs stay in `jobs` in the same order, and so selected
+            self.end_visual();
+            self.rebuild();
+            self.state.select(self.row_with(&RowKey::Group(key)));
         }
+        changed
+    }
+
+    /// Show or hide the components of the het job under the cursor (or of
+    /// the component under it), or the jobs of the section under it;
+    /// returns whether anything changed
+    pub fn set_expanded(&mut self, expanded: bool) -> bool {
+        if self.set_group_expanded(expanded) {
+            return true;
+        }
+        let Some(job) = self.selected_job() else {
+            return false;
+        };
+        let het_job = match het_component(&job.id) {
+            Some((base, _)) if self.expanded.contains(base) => base.to_string(),
+            _ if !job.het_components.is_empty() => job.id.clone(),
+            _ => return false,
+        };
+        let changed = if expanded {
+            self.expanded.insert(het_job.clone())
+        } else {
+            self.expanded.remove(&het_job)
+        };
+        if !changed {
+            return false;
+        }
+
+        // Rows shift, so keep the selection and cursor by job id
+        let selected: Vec<String> = self.get_selected_jobs();
+        self.end_visual();
+        self.rebuild();
+        self.select_ids(&selected);
+        self.state.select(self.row_with(&RowKey::Job(het_job)));
+        true
     }
 
     /// Toggle job selection
     pub fn toggle_select(&mut self) {
-        if let Some(selected) = self.state.selected() {
+        if let Some(selected) = self.cursor_job() {
             if self.selected_jobs.contains(&selected) {
                 self.selected_jobs.retain(|&i| i != selected);
             } else {
@@ -69,6 +495,44 @@ impl JobsList {
         self.selected_jobs.clear();
     }
 
+    /// Start visual mode: rows between the anchor and the cursor become selected
+    pub fn start_visual(&mut self) {
+        if let Some(cursor) = self.cursor_job() {
+            self.visual_anchor = Some(cursor);
+            self.visual_base = self.selected_jobs.clone();
+            self.update_visual();
+        }
+    }
+
+    /// Leave visual mode, keeping the current selection
+    pub fn end_visual(&mut self) {
+        self.visual_anchor = None;
+        self.visual_base.clear();
+    }
+
+    pub fn in_visual_mode(&self) -> bool {
+        self.visual_anchor.is_some()
+    }
+
+    /// Recompute the selection from the visual range after the cursor moved
+    fn update_visual(&mut self) {
+        // On a section header the range stays as it was
+        let (Some(anchor), Some(cursor)) = (self.visual_anchor, self.cursor_job()) else {
+            return;
+        };
+        let (lo, hi) = (anchor.min(cursor), anchor.max(cursor));
+        let mut selected = self.visual_base.clone();
+        // Jobs of collapsed sections within the range are not on screen
+        for row in &self.layout {
+            if let ListRow::Job(i) = *row {
+                if (lo..=hi).contains(&i) && !selected.contains(&i) {
+                    selected.push(i);
+                }
+            }
+        }
+        self.selected_jobs = selected;
+    }
+
     /// Update sort configuration based on SortColumn settings
     pub fn update_sort(&mut self, columns: &[JobColumn], sort_columns: &[SortColumn]) {
         if let Some(first_sort) = sort_columns.first() {
@@ -90,14 +554,14 @@ impl JobsList {
     /// Navigate to next job
     /// Returns true if selection changed, false otherwise
     pub fn next(&mut self) -> bool {
-        if self.jobs.is_empty() {
+        if self.row_count() == 0 {
             return false;
         }
 
         let old_selection = self.state.selected();
         let i = match old_selection {
             Some(i) => {
-                if i >= self.jobs.len().saturating_sub(1) {
+                if i >= self.row_count().saturating_sub(1) {
                     0
                 } else {
                     i + 1
@@ -106,13 +570,14 @@ impl JobsList {
             None => 0,
         };
         self.state.select(Some(i));
+        self.update_visual();
         old_selection != Some(i)
     }
 
     /// Navigate to previous job
     /// Returns true if selection changed, false otherwise
     pub fn previous(&mut self) -> bool {
-        if self.jobs.is_empty() {
+        if self.row_count() == 0 {
             return false;
         }
 
@@ -120,7 +585,7 @@ impl JobsList {
         let i = match old_selection {
             Some(i) => {
                 if i == 0 {
-                    self.jobs.len().saturating_sub(1)
+                    self.row_count().saturating_sub(1)
                 } else {
                     i - 1
                 }
@@ -128,9 +593,88 @@ impl JobsList {
             None => 0,
         };
         self.state.select(Some(i));
+        self.update_visual();
         old_selection != Some(i)
     }
 
+    /// Move the cursor to `row`, or the last row past the end; returns
+    /// whether it moved
+    pub fn move_to(&mut self, row: usize) -> bool {
+        if self.row_count() == 0 {
+            return false;
+        }
+        let row = row.min(self.row_count() - 1);
+        let old_selection = self.state.selected();
+        self.state.select(Some(row));
+        self.update_visual();
+        old_selection != Some(row)
+    }
+
+    /// Move a screen of rows down, stopping at the last
+    pub fn page_down(&mut self) -> bool {
+        let row = self.state.selected().map_or(0, |row| row + self.page_rows);
+        self.move_to(row)
+    }
+
+    /// Move a screen of rows up, stopping at the first
+    pub fn page_up(&mut self) -> bool {
+        let row = self.state.selected().unwrap_or(0);
+        self.move_to(row.saturating_sub(self.page_rows))
+    }
+
+    pub fn first(&mut self) -> bool {
+        self.move_to(0)
+    }
+
+    pub fn last(&mut self) -> bool {
+        self.move_to(usize::MAX)
+    }
+
+    /// Scroll the columns right of the pinned ones by `by` (negative is
+    /// left); false when already at that end
+    pub fn scroll_columns(&mut self, by: isize) -> bool {
+        let offset = self
+            .column_offset
+            .saturating_add_signed(by)
+            .min(self.max_column_offset);
+        let moved = offset != self.column_offset;
+        self.column_offset = offset;
+        moved
+    }
+
+    /// Move the cursor to job `id`; false when it is not listed
+    pub fn focus_job(&mut self, id: &str) -> bool {
+        match self.row_with(&RowKey::Job(id.to_string())) {
+            Some(row) => {
+                self.move_to(row);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The table row drawn at screen position (`x`, `y`) at the last render
+    pub fn row_at(&self, x: u16, y: u16) -> Option<usize> {
+        let inner = self.area.inner(Margin::new(1, 1));
+        // The first inner line is the header
+        if !inner.contains((x, y).into()) || y == inner.y {
+            return None;
+        }
+        let row = self.state.offset() + usize::from(y - inner.y - 1);
+        (row < self.layout.len()).then_some(row)
+    }
+
+    /// The column whose header is at screen position (`x`, `y`)
+    pub fn header_at(&self, x: u16, y: u16) -> Option<JobColumn> {
+        if y != self.area.y + 1 {
+            return None;
+        }
+        self.column_spans
+            .iter()
+            .find(|(start, width, _)| (*start..start + width).contains(&x))
+            .map(|(_, _, column)| *column)
+    }
+
     /// Draw the jobs list widget
     pub fn render(
         &mut self,
@@ -153,11 +697,96 @@ impl JobsList {
             return;
         }
 
+        // Get constraints for columns using the default_width method from JobColumn
+        let name_width = if self.jobs.is_empty() {
+            None
+        } else {
+            let max_len = self
+                .jobs
+                .iter()
+                .map(|j| {
+                    let n = j.name.chars().count();
+                    if n > 30 {
+                        30
+                    } else {
+                        n
+                    }
+                })
+                .max()
+                .unwrap_or(0);
+            let clamped = max_len.clamp(10, 30) as u16;
+            Some(clamped)
+        };
+
+        let constraints: Vec<Constraint> = columns
+            .iter()
+            .map(|col| {
+                // Use the default_width from JobColumn, but with some specific overrides
+                // for better display in the jobs list context
+                match col {
+                    // Override specific columns that need different constraints in the jobs list
+                    JobColumn::Name => name_width
+                        .map(Constraint::Length)
+                        .unwrap_or(Constraint::Percentage(10)),
+                    JobColumn::WorkDir => Constraint::Min(20),
+                    // For time-related columns, we use a slightly longer constraint
+                    JobColumn::SubmitTime | JobColumn::StartTime | JobColumn::EndTime => {
+                        Constraint::Length(19)
+                    }
+                    // Use the default_width for all other columns
+                    _ => col.default_width(),
+                }
+            })
+            .collect();
+
+        // Columns that do not fit scroll sideways behind the pinned ones;
+        // the borders and the highlight symbol take their share first
+        let highlight = if self.state.selected().is_some() {
+            3
+        } else {
+            0
+        };
+        let available = area.width.saturating_sub(2 + highlight);
+        let widths: Vec<u16> = constraints
+            .iter()
+            .map(|constraint| match *constraint {
+                Constraint::Length(n) | Constraint::Min(n) => n,
+                Constraint::Percentage(p) => (available * p / 100).max(8),
+                _ => 10,
+            })
+            .collect();
+        let pinned: Vec<bool> = columns
+            .iter()
+            .map(|col| matches!(col, JobColumn::Id | JobColumn::Name))
+            .collect();
+        let (shown, max_offset) = fit_columns(&widths, &pinned, self.column_offset, available);
+        self.max_column_offset = max_offset;
+        self.column_offset = self.column_offset.min(max_offset);
+        let hidden_left = self.column_offset;
+        let hidden_right = pinned.iter().filter(|p| !**p).count()
+            - hidden_left
+            - shown.iter().filter(|&&i| !pinned[i]).count();
+        let columns: Vec<JobColumn> = shown.iter().map(|&i| columns[i]).collect();
+        let constraints: Vec<Constraint> = shown.iter().map(|&i| constraints[i]).collect();
+        // Where the table will put the columns, to mark cut-off text and
+        // find the header clicked
+        let spans = Layout::horizontal(constraints.iter().copied())
+            .flex(Flex::Start)
+            .spacing(1)
+            .split(Rect::new(area.x + 1 + highlight, 0, available, 1));
+        let cell_widths: Vec<usize> = spans.iter().map(|r| r.width as usize).collect();
+        self.area = area;
+        self.column_spans = spans
+            .iter()
+            .zip(&columns)
+            .map(|(r, column)| (r.x, r.width, *column))
+            .collect();
+
         // Create headers based on selected columns
         let headers: Vec<&str> = columns.iter().map(|col| col.title()).collect();
 
         // Create header cells with appropriate styling
-        let header_cells = headers.iter().enumerate().map(|(_i, &h)| {
+        let header_cells = headers.iter().zip(&cell_widths).map(|(&h, &width)| {
             // Check if this column is in the sort list
             let is_sort_column = sort_columns.iter().any(|sc| sc.column.title() == h);
             let sort_indicator = if is_sort_column {
@@ -183,26 +812,31 @@ impl JobsList {
                     .add_modifier(Modifier::BOLD)
             };
 
-            Cell::from(format!("{}{}", h, sort_indicator)).style(header_style)
+            Cell::from(ellipsize(format!("{}{}", h, sort_indicator), width)).style(header_style)
         });
 
         let header = Row::new(header_cells)
             .style(Style::default().bg(Color::DarkGray))
             .height(1);
 
+        // Only the rows on screen are built, so listings of all users' jobs
+        // stay responsive
+        let visible = area.height.saturating_sub(3).max(1) as usize;
+        self.page_rows = visible;
+        let mut offset = self.state.offset().min(self.layout.len().saturating_sub(1));
+        match self.state.selected() {
+            Some(row) if row < offset => offset = row,
+            Some(row) if row >= offset + visible => offset = row + 1 - visible,
+            _ => {}
+        }
+        *self.state.offset_mut() = offset;
+        let end = (offset + visible).min(self.layout.len());
+
+        let now = Local::now();
         // Create rows for each job
-        let rows = self.jobs.iter().enumerate().map(|(i, job)| {
+        let job_row = |i: usize, job: &Job| {
             let is_selected = self.selected_jobs.contains(&i);
-            let color = match job.state {
-                JobState::Pending => Color::Yellow,
-                JobState::Running => Color::Green,
-                JobState::Completed => Color::Blue,
-                JobState::Failed | JobState::Timeout | JobState::NodeFail | JobState::Boot => {
-                    Color::Red
-                }
-                JobState::Cancelled => Color::Magenta,
-                _ => Color::White,
-            };
+            let color = state_color(job.state);
 
             let style = if is_selected {
                 Style::default().fg(color).add_modifier(Modifier::REVERSED)
@@ -213,9 +847,24 @@ impl JobsList {
             // Create cells based on selected columns
             let cells: Vec<Cell> = columns
                 .iter()
-                .map(|col| {
+                .zip(&cell_widths)
+                .map(|(col, &width)| {
                     let content = match col {
-                        JobColumn::Id => job.id.clone(),
+                        JobColumn::Id if !job.het_components.is_empty() => {
+                            let marker = if self.expanded.contains(&job.id) {
+                                "▾"
+                            } else {
+                                "▸"
+                            };
+                            format!("{} {}", marker, job.id)
+                        }
+                        JobColumn::Id
+                            if het_component(&job.id)
+                                .is_some_and(|(base, _)| self.expanded.contains(base)) =>
+                        {
+                            format!("  └ {}", job.id)
+                        }
+                        JobColumn::Id if self.is_watched(&job.id) => format!("★ {}", job.id),
                         JobColumn::Name => {
                             // Truncate name if too long
                             if job.name.len() > 30 {
@@ -224,102 +873,118 @@ impl JobsList {
                                 job.name.clone()
                             }
                         }
-                        JobColumn::User => job.user.clone(),
-                        JobColumn::State => job.state.to_string(),
-                        JobColumn::Partition => job.partition.clone(),
-                        JobColumn::Nodes => job.nodes.to_string(),
-                        JobColumn::Node => job.node.clone().unwrap_or_else(|| "-".to_string()),
-                        JobColumn::CPUs => job.cpus.to_string(),
-                        JobColumn::Time => job.time.clone(),
-                        JobColumn::Memory => job.memory.clone(),
-                        JobColumn::Account => {
-                            job.account.clone().unwrap_or_else(|| "-".to_string())
-                        }
-                        JobColumn::Priority => job
-                            .priority
-                            .map(|p| p.to_string())
+                        JobColumn::TimeLeft => time_left(job),
+                        JobColumn::SubmitTime
+                        | JobColumn::StartTime
+                        | JobColumn::EndTime
+                        | JobColumn::EstimatedStart => column_time(job, *col)
+                            .map(|t| self.times.format(&t))
                             .unwrap_or_else(|| "-".to_string()),
-                        JobColumn::WorkDir => {
-                            job.work_dir.clone().unwrap_or_else(|| "-".to_string())
-                        }
-                        JobColumn::SubmitTime => {
-                            job.submit_time.clone().unwrap_or_else(|| "-".to_string())
-                        }
-                        JobColumn::StartTime => {
-                            job.start_time.clone().unwrap_or_else(|| "-".to_string())
+                        JobColumn::Gpus if job.gpus == 0 => "-".to_string(),
+                        _ => column_value(job, *col, now).unwrap_or_else(|| "-".to_string()),
+                    };
+                    let cell = Cell::from(ellipsize(content, width));
+                    match col {
+                        JobColumn::TimeLeft
+                            if job.time_used_fraction().is_some_and(|f| f >= NEAR_LIMIT) =>
+                        {
+                            cell.style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
                         }
-                        JobColumn::EndTime => {
-                            job.end_time.clone().unwrap_or_else(|| "-".to_string())
+                        // Node-side failures stand out from the job's own
+                        JobColumn::State | JobColumn::PReason if job.setup_failure().is_some() => {
+                            cell.style(
+                                Style::default()
+                                    .fg(Color::LightMagenta)
+                                    .add_modifier(Modifier::BOLD),
+                            )
                         }
-                        JobColumn::PReason => job
-                            .pending_reason
-                            .clone()
-                            .unwrap_or_else(|| "-".to_string()),
-                    };
-                    Cell::from(content)
+                        JobColumn::CpuUsage => match job.cpu_efficiency().and_then(usage_color) {
+                            Some(color) => cell.style(Style::default().fg(color)),
+                            None => cell,
+                        },
+                        JobColumn::Efficiency => match overall(job).and_then(usage_color) {
+                            Some(color) => cell.style(Style::default().fg(color)),
+                            None => cell,
+                        },
+                        _ => cell,
+                    }
                 })
                 .collect();
 
             Row::new(cells).style(style).height(1)
-        });
-
-        // Calculate total available width
-        // let available_width = area.width.saturating_sub(2); // Subtract 2 for borders
-
-        // Get constraints for columns using the default_width method from JobColumn
-        let name_width = if self.jobs.is_empty() {
-            None
-        } else {
-            let max_len = self
-                .jobs
-                .iter()
-                .map(|j| {
-                    let n = j.name.chars().count();
-                    if n > 30 { 30 } else { n }
-                })
-                .max()
-                .unwrap_or(0);
-            let clamped = max_len.clamp(10, 30) as u16;
-            Some(clamped)
         };
-
-        let constraints: Vec<Constraint> = columns
+        let rows: Vec<Row> = self.layout[offset..end]
             .iter()
-            .map(|col| {
-                // Use the default_width from JobColumn, but with some specific overrides
-                // for better display in the jobs list context
-                match col {
-                    // Override specific columns that need different constraints in the jobs list
-                    JobColumn::Name => name_width
-                        .map(Constraint::Length)
-                        .unwrap_or(Constraint::Percentage(10)),
-                    JobColumn::WorkDir => Constraint::Min(20),
-                    // For time-related columns, we use a slightly longer constraint
-                    JobColumn::SubmitTime | JobColumn::StartTime | JobColumn::EndTime => {
-                        Constraint::Length(19)
-                    }
-                    // Use the default_width for all other columns
-                    _ => col.default_width(),
-                }
+            .map(|row| match row {
+                ListRow::Group(g) => self.group_row(&self.groups[*g], &cell_widths),
+                ListRow::Job(i) => job_row(*i, &self.jobs[*i]),
             })
             .collect();
 
         // Create the table
-        let job_count = self.jobs.len();
-        let title = format!("{} Jobs", job_count);
+        let job_count = self.listed.len();
+        let title = match self.group_by {
+            Some(group_by) => format!(
+                "{} Jobs by {} ({})",
+                job_count,
+                group_by.title(),
+                self.groups.len()
+            ),
+            None => format!("{} Jobs", self.jobs.len()),
+        };
+        let title = if hidden_left + hidden_right > 0 {
+            format!(
+                "{} | ◀ {} | {} ▶ more columns [</>]",
+                title, hidden_left, hidden_right
+            )
+        } else {
+            title
+        };
         let table = Table::new(rows, constraints)
             .header(header)
             .block(Block::default().borders(Borders::ALL).title(title))
-            .row_highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            // The selection style, so a theme's selection color shows the cursor
+            .row_highlight_style(theme::selection().add_modifier(Modifier::BOLD))
             .highlight_symbol(" ▶ ");
 
         // Render the table
-        frame.render_stateful_widget(table, area, &mut self.state);
+        let mut window = TableState::default().with_selected(
+            self.state
+                .selected()
+                .filter(|row| (offset..end).contains(row))
+                .map(|row| row - offset),
+        );
+        frame.render_stateful_widget(table, area, &mut window);
+    }
+
+    /// The header row of a section, its summary spread over the columns
+    fn group_row(&self, group: &Group, cell_widths: &[usize]) -> Row<'static> {
+        let marker = if self.collapsed.contains(&group.key) {
+            "▸"
+        } else {
+            "▾"
+        };
+        // A section by state holds jobs of that state alone
+        let states = match self.group_by {
+            Some(GroupBy::State) => String::new(),
+            _ => format!(", {} running, {} pending", group.running, group.pending),
+        };
+        let text = format!(
+            "{} {}: {} job(s){}, {} CPUs in use, {:.1} CPU-hours",
+            marker, group.key, group.jobs, states, group.cpus, group.cpu_hours
+        );
+        Row::new(spread(&text, cell_widths))
+            .style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .height(1)
     }
 
     /// Get the currently selected job, if any
     pub fn selected_job(&self) -> Option<&Job> {
-        self.state.selected().and_then(|i| self.jobs.get(i))
+        self.cursor_job().and_then(|i| self.jobs.get(i))
     }
 
     /// Get all selected jobs
@@ -331,3 +996,350 @@ impl JobsList {
             .collect()
     }
 }
+
+/// The text of `column` for `job`, as the list shows it but in full and
+/// without its decorations (bars, tree markers); `None` when unknown
+pub fn column_value(job: &Job, column: JobColumn, now: DateTime<Local>) -> Option<String> {
+    match column {
+        JobColumn::Id => Some(job.id.clone()),
+        JobColumn::Name => Some(job.name.clone()),
+        JobColumn::User => Some(job.user.clone()),
+        JobColumn::State => Some(job.state.to_string()),
+        JobColumn::Partition => Some(job.partition.clone()),
+        JobColumn::Nodes => Some(job.nodes.to_string()),
+        JobColumn::Node => job.node.clone(),
+        JobColumn::CPUs => Some(job.cpus.to_string()),
+        JobColumn::Time => Some(format_slurm_duration(job.time)),
+        JobColumn::TimeLeft => job.time_remaining().map(format_slurm_duration),
+        JobColumn::CpuUsage => job.cpu_efficiency().map(|e| format!("{:.0}%", e * 100.0)),
+        JobColumn::Efficiency => overall(job).map(|e| format!("{:.0}%", e * 100.0)),
+        JobColumn::Memory => job.memory.map(|m| m.to_string()),
+        JobColumn::Gpus => Some(job.gpus.to_string()),
+        JobColumn::Account => job.account.clone(),
+        JobColumn::Priority => job.priority.map(|p| p.to_string()),
+        JobColumn::WorkDir => job.work_dir.clone(),
+        JobColumn::SubmitTime
+        | JobColumn::StartTime
+        | JobColumn::EndTime
+        | JobColumn::EstimatedStart => column_time(job, column).as_ref().map(format_slurm_time),
+        JobColumn::QueueWait => queue_wait(job, now).map(format_slurm_duration),
+        JobColumn::PReason => job.pending_reason.clone(),
+        JobColumn::Reservation => job.reservation.clone(),
+        JobColumn::Licenses => job.licenses.clone(),
+        JobColumn::TmpDisk => job.tmp_disk.map(|m| m.to_string()),
+        JobColumn::BurstBuffer => job.burst_buffer.map(|m| m.to_string()),
+        JobColumn::Preemption if job.state == JobState::Preempted => Some("preempted".to_string()),
+        JobColumn::Preemption => job.preemption.clone(),
+        JobColumn::Energy => job.energy.map(format_energy),
+    }
+}
+
+/// The time a time column shows for `job`
+fn column_time(job: &Job, column: JobColumn) -> Option<DateTime<Local>> {
+    match column {
+        JobColumn::SubmitTime => job.submit_time,
+        JobColumn::StartTime => job.start_time,
+        JobColumn::EndTime => job.end_time,
+        JobColumn::EstimatedStart => job.estimated_start,
+        _ => None,
+    }
+}
+
+/// Time left of a running job with a bar of the time limit used
+/// (`▰▰▰▰▰▱▱▱ 1:02:03`); pending jobs show their limit
+fn time_left(job: &Job) -> String {
+    const WIDTH: usize = 8;
+    match (
+        job.time_remaining(),
+        job.time_used_fraction(),
+        job.time_limit,
+    ) {
+        (Some(left), Some(used), _) => {
+            let filled = ((used * WIDTH as f64).round() as usize).min(WIDTH);
+            format!(
+                "{}{} {}",
+                "▰".repeat(filled),
+                "▱".repeat(WIDTH - filled),
+                format_slurm_duration(left)
+            )
+        }
+        (None, _, Some(limit)) if job.state == JobState::Pending => {
+            format!("limit {}", format_slurm_duration(limit))
+        }
+        _ => "-".to_string(),
+    }
+}
+
+/// Colour of a job's CPU use: red when it barely used its allocation,
+/// yellow for half or less, none (the state colour) otherwise
+fn usage_color(efficiency: f64) -> Option<Color> {
+    match concern(efficiency) {
+        Concern::Bad => Some(Color::Red),
+        Concern::Fair => Some(Color::Yellow),
+        Concern::Fine => None,
+    }
+}
+
+/// Colour of a job state in the lists
+pub fn state_color(state: JobState) -> Color {
+    match state {
+        JobState::Pending
+        | JobState::Suspended
+        | JobState::Stopped
+        | JobState::Requeued
+        | JobState::RequeueHold
+        | JobState::RequeueFed
+        | JobState::ResvDelHold => Color::Yellow,
+        JobState::Running
+        | JobState::Completing
+        | JobState::Configuring
+        | JobState::Resizing
+        | JobState::Signaling
+        | JobState::StageOut => Color::Green,
+        JobState::Completed => Color::Blue,
+        JobState::Failed
+        | JobState::Timeout
+        | JobState::NodeFail
+        | JobState::Boot
+        | JobState::OutOfMemory
+        | JobState::Deadline
+        | JobState::SpecialExit
+        | JobState::LaunchFailed => Color::Red,
+        JobState::Cancelled { .. } | JobState::Revoked => Color::Magenta,
+        _ => Color::White,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn list_with(n: usize) -> JobsList {
+        let mut list = JobsList::new();
+        list.update_jobs(
+            (0..n)
+                .map(|i| Job {
+                    id: i.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+        );
+        list
+    }
+
+    #[test]
+    fn visual_mode_selects_range_on_top_of_existing_selection() {
+        let mut list = list_with(6);
+        list.toggle_select(); // row 0
+        list.next();
+        list.next(); // cursor on row 2
+        list.start_visual();
+        list.next();
+        list.next(); // cursor on row 4
+        assert_eq!(list.get_selected_jobs(), vec!["0", "2", "3", "4"]);
+
+        list.previous(); // shrinking the range drops row 4
+        assert_eq!(list.get_selected_jobs(), vec!["0", "2", "3"]);
+
+        list.end_visual();
+        list.next();
+        assert_eq!(list.get_selected_jobs(), vec!["0", "2", "3"]);
+    }
+
+    #[test]
+    fn pages_by_the_rows_on_screen_and_stops_at_the_ends() {
+        let mut list = list_with(30);
+        let cursor = |list: &JobsList| list.selected_job().map(|j| j.id.clone());
+        // Before any render the step is the default
+        assert!(list.page_down());
+        assert_eq!(cursor(&list).as_deref(), Some("10"));
+
+        // A 13-row area shows 10 jobs under its border and header
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 13)).unwrap();
+        list.page_rows = 0;
+        terminal
+            .draw(|frame| list.render(frame, frame.area(), &[JobColumn::Id], &[]))
+            .unwrap();
+        assert_eq!(list.page_rows, 10);
+
+        assert!(list.page_down());
+        assert!(list.page_down());
+        assert_eq!(cursor(&list).as_deref(), Some("29"));
+        assert!(!list.page_down(), "already on the last row");
+        assert!(list.page_up());
+        assert_eq!(cursor(&list).as_deref(), Some("19"));
+        assert!(list.first());
+        assert_eq!(cursor(&list).as_deref(), Some("0"));
+        assert!(!list.page_up(), "already on the first row");
+        assert!(list.last());
+        assert_eq!(cursor(&list).as_deref(), Some("29"));
+
+        assert!(!list_with(0).page_down(), "nothing to page through");
+    }
+
+    #[test]
+    fn updates_keep_cursor_and_selection_on_the_same_jobs() {
+        let jobs = |ids: &[&str]| {
+            ids.iter()
+                .map(|id| Job {
+                    id: id.to_string(),
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut list = JobsList::new();
+        list.update_jobs(jobs(&["1", "2", "3", "4"]));
+        list.next();
+        list.toggle_select(); // job 2
+        list.next();
+        list.next(); // cursor on job 4
+
+        list.update_jobs(jobs(&["0", "1", "2", "3", "4"]));
+        assert_eq!(list.selected_job().map(|j| j.id.as_str()), Some("4"));
+        assert_eq!(list.get_selected_jobs(), vec!["2"]);
+
+        // The cursor's job is gone: the cursor keeps its row, or the last
+        list.update_jobs(jobs(&["0", "2", "3"]));
+        assert_eq!(list.selected_job().map(|j| j.id.as_str()), Some("3"));
+        assert_eq!(list.get_selected_jobs(), vec!["2"]);
+    }
+
+    #[test]
+    fn het_jobs_expand_into_their_components() {
+        let part = |id: &str| Job {
+            id: id.to_string(),
+            ..Default::default()
+        };
+        let het = Job {
+            het_components: vec![part("5+0"), part("5+1")],
+            ..part("5")
+        };
+        let mut list = JobsList::new();
+        list.update_jobs(vec![part("4"), het, part("6")]);
+        list.next();
+        list.toggle_select();
+        list.next(); // cursor on row 2: job 6
+
+        assert!(!list.set_expanded(true), "job 6 is no het job");
+        list.previous();
+        assert!(list.set_expanded(true));
+        let ids: Vec<&str> = list.jobs.iter().map(|j| j.id.as_str()).collect();
+        assert_eq!(ids, ["4", "5", "5+0", "5+1", "6"]);
+        assert_eq!(list.listed_jobs().len(), 3);
+
+        // Collapsing from a component goes back to the het job, still selected
+        list.next();
+        assert!(list.set_expanded(false));
+        assert_eq!(list.jobs.len(), 3);
+        assert_eq!(list.selected_job().map(|j| j.id.as_str()), Some("5"));
+        assert_eq!(list.get_selected_jobs(), vec!["5"]);
+    }
+
+    #[test]
+    fn groups_jobs_into_collapsible_sections() {
+        let job = |id: &str, account: &str, state| Job {
+            id: id.to_string(),
+            account: Some(account.to_string()),
+            state,
+            cpus: 4,
+            time: Duration::from_secs(1800),
+            ..Default::default()
+        };
+        let mut list = JobsList::new();
+        list.update_jobs(vec![
+            job("1", "physics", JobState::Running),
+            job("2", "bio", JobState::Pending),
+            job("3", "physics", JobState::Running),
+        ]);
+        list.next(); // cursor on job 2
+        list.set_group_by(Some(GroupBy::Account));
+        assert_eq!(list.selected_job().map(|j| j.id.as_str()), Some("2"));
+        let ids: Vec<&str> = list.jobs.iter().map(|j| j.id.as_str()).collect();
+        assert_eq!(ids, ["2", "1", "3"]);
+        let physics = &list.groups[1];
+        assert_eq!((physics.jobs, physics.running, physics.cpus), (2, 2, 8));
+        assert_eq!(physics.cpu_hours, 4.0);
+
+        // Collapsing the header under the cursor hides its jobs' rows, but
+        // they stay listed and selected
+        list.next();
+        list.next(); // job 1
+        list.toggle_select();
+        list.previous(); // header of physics
+        assert!(list.selected_job().is_none());
+        assert!(list.set_expanded(false));
+        assert_eq!(list.row_count(), 3);
+        assert_eq!(list.jobs.len(), 3);
+        list.toggle_select();
+        assert_eq!(list.get_selected_jobs(), ["1"]);
+        assert!(list.set_expanded(true));
+        list.next();
+        assert_eq!(list.selected_job().map(|j| j.id.as_str()), Some("1"));
+        assert_eq!(list.get_selected_jobs(), ["1"]);
+
+        list.set_group_by(None);
+        assert_eq!(list.selected_job().map(|j| j.id.as_str()), Some("1"));
+        assert!(list.groups.is_empty());
+
+        // By state, running goes before pending whatever the names
+        list.set_group_by(Some(GroupBy::State));
+        let keys: Vec<&str> = list.groups.iter().map(|g| g.key.as_str()).collect();
+        assert_eq!(keys, ["RUNNING", "PENDING"]);
+        assert_eq!(list.groups[0].jobs, 2);
+    }
+
+    #[test]
+    fn section_headers_spread_between_words() {
+        assert_eq!(
+            spread("▾ physics: 2 job(s), 8 CPUs", &[10, 6, 20]),
+            ["▾ physics:", "2", "job(s), 8 CPUs"]
+        );
+        assert_eq!(spread("▾ a-long-account: 1", &[6, 4]), ["▾", "a-l…"]);
+    }
+
+    #[test]
+    fn narrow_tables_scroll_columns_behind_the_pinned_ones() {
+        // ID and Name pinned, then three columns of 10
+        let widths = [10, 20, 10, 10, 10];
+        let pinned = [true, true, false, false, false];
+        // 10 + 1 + 20 + 1 + 10 + 1 + 10 leaves out the last
+        assert_eq!(fit_columns(&widths, &pinned, 0, 53), (vec![0, 1, 2, 3], 1));
+        assert_eq!(fit_columns(&widths, &pinned, 1, 53), (vec![0, 1, 3, 4], 1));
+        // Past the end is the last useful offset
+        assert_eq!(fit_columns(&widths, &pinned, 5, 53), (vec![0, 1, 3, 4], 1));
+        // Everything fits: no scrolling
+        assert_eq!(
+            fit_columns(&widths, &pinned, 1, 200),
+            (vec![0, 1, 2, 3, 4], 0)
+        );
+        // Too narrow for any: one still shows, cut off
+        assert_eq!(fit_columns(&widths, &pinned, 0, 30), (vec![0, 1, 2], 2));
+
+        assert_eq!(ellipsize("gpu-partition".to_string(), 8), "gpu-par…");
+        assert_eq!(ellipsize("gpu".to_string(), 8), "gpu");
+    }
+
+    #[test]
+    fn time_left_shows_progress_towards_the_limit() {
+        let job = |state, used, limit: Option<u64>| Job {
+            state,
+            time: Duration::from_secs(used),
+            time_limit: limit.map(Duration::from_secs),
+            ..Default::default()
+        };
+        assert_eq!(
+            time_left(&job(JobState::Running, 1800, Some(7200))),
+            "▰▰▱▱▱▱▱▱ 1:30:00"
+        );
+        let nearly_done = job(JobState::Running, 3500, Some(3600));
+        assert_eq!(time_left(&nearly_done), "▰▰▰▰▰▰▰▰ 1:40");
+        assert!(nearly_done.time_used_fraction().unwrap() >= NEAR_LIMIT);
+        assert_eq!(
+            time_left(&job(JobState::Pending, 0, Some(86400))),
+            "limit 1-00:00:00"
+        );
+        assert_eq!(time_left(&job(JobState::Running, 60, None)), "-");
+    }
+}
@@ -5,7 +5,6 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
-use std::time::Duration;
 
 /// Defines the main layout of the application
 pub fn draw_main_layout(frame: &mut Frame) -> Vec<Rect> {
@@ -17,22 +16,22 @@ pub fn draw_main_layout(frame: &mut Frame) -> Vec<Rect> {
         .constraints([
             Constraint::Length(3), // Header area with status
             Constraint::Min(10),   // Main content area
+            Constraint::Length(1), // Status bar
             Constraint::Length(3), // Footer area with controls
         ])
         .split(size);
 
-    let main_chunk = chunks[1];
-
-    vec![chunks[0], main_chunk, chunks[2]]
+    chunks.to_vec()
 }
 
-/// Draws the application header with status information
+/// Draws the application header with status information; a `warning`
+/// (Slurm not answering) takes the place of the status text
 pub fn draw_header(
     frame: &mut Frame,
     area: Rect,
     status_text: &str,
-    time_since_refresh: Duration,
-    refresh_interval: u64,
+    warning: Option<&str>,
+    refresh_status: &str,
 ) {
     // Split the header area into title and status
     let header_chunks = Layout::default()
@@ -54,29 +53,39 @@ pub fn draw_header(
     frame.render_widget(title, header_chunks[0]);
 
     // Render the status part
-    let status_info = format!(
-        "{} | Refresh: {}s ago (auto: {}s)",
-        status_text,
-        time_since_refresh.as_secs(),
-        refresh_interval
-    );
-
-    let status = Paragraph::new(status_info)
-        .block(Block::default().borders(Borders::ALL))
+    let status = match warning {
+        Some(warning) => Paragraph::new(Line::styled(
+            warning.to_string(),
+            Style::default().fg(Color::Yellow).bold(),
+        )),
+        None => Paragraph::new(status_text.to_string()),
+    };
+    let status = status
+        .block(
+            Block::default().borders(Borders::ALL).title_top(
+                Line::styled(
+                    format!(" {} ", refresh_status),
+                    Style::default().fg(Color::Gray),
+                )
+                .right_aligned(),
+            ),
+        )
         .style(Style::default());
 
     frame.render_widget(status, header_chunks[1]);
 }
 
-/// Draws the application footer with help text and status
-pub fn draw_footer(frame: &mut Frame, area: Rect, job_stat: (usize, usize, usize)) {
+/// Draws the application footer with help text
+pub fn draw_footer(frame: &mut Frame, area: Rect) {
     // Controls help (lower part of footer)
     let color_style = Style::default().fg(Color::Cyan);
     let text_hashmap = [
         ("Esc", "Quit"),
         ("↑/↓", "Navigate"),
         ("Space", "Select"),
+        ("V", "Visual"),
         ("Enter", "Script"),
+        ("d", "Details"),
         ("f", "Filter"),
         ("c", "Columns"),
         ("s", "Settings"),
@@ -84,9 +93,14 @@ pub fn draw_footer(frame: &mut Frame, area: Rect, job_stat: (usize, usize, usize
         ("a", "SelectAll"),
         ("r", "Refresh"),
         ("x", "Cancel"),
+        ("e", "Edit"),
+        ("n", "Note"),
+        ("R", "Resubmit"),
+        ("?", "Explain"),
+        ("^P", "Commands"),
     ];
 
-    let mut footer_text: Vec<Span> = text_hashmap
+    let footer_text: Vec<Span> = text_hashmap
         .iter()
         .flat_map(|(key, description)| {
             vec![
@@ -98,20 +112,6 @@ pub fn draw_footer(frame: &mut Frame, area: Rect, job_stat: (usize, usize, usize
         })
         .collect();
 
-    footer_text.push(Span::styled("Job Stat: ", Style::default().fg(Color::Cyan)));
-    footer_text.push(Span::styled(
-        format!("P[ {} ] ", job_stat.0),
-        Style::default().fg(Color::Yellow),
-    ));
-    footer_text.push(Span::styled(
-        format!("R[ {} ] ", job_stat.1),
-        Style::default().fg(Color::Green),
-    ));
-    footer_text.push(Span::styled(
-        format!("Other[ {} ]", job_stat.2),
-        Style::default().fg(Color::Blue),
-    ));
-
     let footer =
         Paragraph::new(Line::from(footer_text)).block(Block::default().borders(Borders::ALL));
 
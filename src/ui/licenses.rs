@@ -0,0 +1,174 @@
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Cell, Row},
+    Frame,
+};
+
+use crate::keymap::Input;
+use crate::slurm::licenses::{requested, shortfalls, License};
+use crate::slurm::{Job, JobState};
+use crate::ui::table_popup::{TableAction, TablePopup, TableView};
+
+/// Width of the used/total bar
+const BAR_WIDTH: usize = 12;
+
+/// A license with the listed pending jobs that want it
+struct LicenseRow {
+    license: License,
+    /// Seats the listed pending jobs ask for
+    wanted: u32,
+    /// Listed pending jobs asking for more than are free
+    blocked: usize,
+}
+
+/// Used and free seats of each cluster license
+pub struct LicenseView {
+    pub popup: TablePopup,
+    rows: Vec<LicenseRow>,
+}
+
+impl LicenseView {
+    pub fn new() -> Self {
+        Self {
+            popup: TablePopup::new("Licenses", 'I'),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn show(&mut self, licenses: Result<Vec<License>, String>, jobs: &[Job]) {
+        let error = match licenses {
+            Ok(licenses) => {
+                let pending: Vec<&Job> = jobs
+                    .iter()
+                    .filter(|j| j.state == JobState::Pending)
+                    .collect();
+                self.rows = licenses
+                    .iter()
+                    .map(|license| LicenseRow {
+                        wanted: pending
+                            .iter()
+                            .filter_map(|j| j.licenses.as_deref())
+                            .flat_map(requested)
+                            .filter(|(name, _)| name == &license.name)
+                            .map(|(_, count)| count)
+                            .sum(),
+                        blocked: pending
+                            .iter()
+                            .filter(|j| {
+                                shortfalls(j, &licenses)
+                                    .iter()
+                                    .any(|(l, _)| l.name == license.name)
+                            })
+                            .count(),
+                        license: license.clone(),
+                    })
+                    .collect();
+                None
+            }
+            Err(e) => Some(e),
+        };
+        self.popup.show(error, self.rows.len());
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let overview = if let Some(unavailable) = self.popup.unavailable() {
+            unavailable
+        } else if self.rows.is_empty() {
+            Line::from("No licenses configured on the cluster")
+        } else {
+            let blocked: usize = self.rows.iter().map(|r| r.blocked).sum();
+            let style = if blocked > 0 {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            Line::styled(
+                format!(
+                    "{} exhausted; {} listed pending job(s) wait for seats",
+                    self.rows.iter().filter(|r| r.license.free == 0).count(),
+                    blocked
+                ),
+                style.add_modifier(Modifier::BOLD),
+            )
+        };
+
+        let rows = self.rows.iter().map(|row| {
+            let l = &row.license;
+            let filled = if l.total == 0 {
+                0
+            } else {
+                (l.used as usize * BAR_WIDTH).div_ceil(l.total as usize)
+            }
+            .min(BAR_WIDTH);
+            let color = match l.free {
+                0 => Color::Red,
+                free if free * 10 < l.total => Color::Yellow,
+                _ => Color::Green,
+            };
+            let name = if l.remote {
+                format!("{} (remote)", l.name)
+            } else {
+                l.name.clone()
+            };
+            Row::new(vec![
+                Cell::from(name),
+                Cell::from(l.used.to_string()),
+                Cell::from(format!(
+                    "{}{}",
+                    "█".repeat(filled),
+                    "░".repeat(BAR_WIDTH - filled)
+                ))
+                .style(Style::default().fg(color)),
+                Cell::from(l.free.to_string()).style(Style::default().fg(color)),
+                Cell::from(l.total.to_string()),
+                Cell::from(l.reserved.to_string()),
+                Cell::from(row.wanted.to_string()),
+                Cell::from(row.blocked.to_string()).style(if row.blocked > 0 {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                }),
+            ])
+        });
+        let table = TablePopup::table(
+            &[
+                "License", "Used", "", "Free", "Total", "Reserved", "Wanted", "Blocked",
+            ],
+            rows.collect(),
+            &[
+                Constraint::Min(16),
+                Constraint::Length(6),
+                Constraint::Length(BAR_WIDTH as u16),
+                Constraint::Length(6),
+                Constraint::Length(6),
+                Constraint::Length(9),
+                Constraint::Length(7),
+                Constraint::Length(8),
+            ],
+        );
+        self.popup.render(
+            frame,
+            area,
+            format!("Licenses: {}", self.rows.len()),
+            vec![overview],
+            table,
+            "[↑/↓] Select | [r] Refresh | [Esc/I] Close",
+        );
+    }
+}
+
+impl TableView for LicenseView {
+    fn popup(&self) -> &TablePopup {
+        &self.popup
+    }
+
+    fn popup_mut(&mut self) -> &mut TablePopup {
+        &mut self.popup
+    }
+
+    fn handle_key(&mut self, input: Input) -> TableAction {
+        self.popup.handle_key(input, self.rows.len())
+    }
+}
@@ -0,0 +1,165 @@
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Cell, Row},
+    Frame,
+};
+use std::collections::BTreeMap;
+
+use crate::keymap::Input;
+use crate::slurm::{sacctmgr::Limit, Job, JobState};
+use crate::ui::table_popup::{TableAction, TablePopup, TableView};
+
+/// Use of a limit from which it is shown as nearly reached
+const NEAR_LIMIT: f64 = 0.8;
+
+/// QOS and association limits against what the user's jobs hold of them
+pub struct LimitsView {
+    pub popup: TablePopup,
+    limits: Vec<Limit>,
+    /// Pending jobs of the user per pending reason
+    reasons: BTreeMap<String, usize>,
+}
+
+impl LimitsView {
+    pub fn new() -> Self {
+        Self {
+            popup: TablePopup::new("Limits", 'L'),
+            limits: Vec::new(),
+            reasons: BTreeMap::new(),
+        }
+    }
+
+    pub fn show(&mut self, limits: Result<(Vec<Limit>, Vec<Job>), String>) {
+        let error = match limits {
+            Ok((limits, jobs)) => {
+                self.limits = limits;
+                self.reasons.clear();
+                for job in jobs.iter().filter(|j| j.state == JobState::Pending) {
+                    if let Some(reason) = &job.pending_reason {
+                        let reason = reason.trim_matches(|c| c == '(' || c == ')').trim();
+                        *self.reasons.entry(reason.to_string()).or_default() += 1;
+                    }
+                }
+                None
+            }
+            Err(e) => Some(e),
+        };
+        self.popup.show(error, self.limits.len());
+    }
+
+    /// Pending jobs held back by `limit`
+    fn held_by(&self, limit: &Limit) -> usize {
+        self.reasons
+            .iter()
+            .filter(|(reason, _)| limit.explains(reason))
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Which limits the user's pending jobs wait on, in words
+    fn overview(&self) -> Vec<Line<'static>> {
+        if let Some(unavailable) = self.popup.unavailable() {
+            return vec![unavailable];
+        }
+        if self.limits.is_empty() {
+            return vec![Line::from(
+                "No QOS or association limits apply to your jobs",
+            )];
+        }
+        let mut lines = Vec::new();
+        for (reason, count) in &self.reasons {
+            let Some(limit) = self.limits.iter().find(|l| l.explains(reason)) else {
+                continue;
+            };
+            let used = limit.used.map_or("?".to_string(), |u| limit.format(u));
+            lines.push(Line::styled(
+                format!(
+                    "{} job(s) pending with {}: {} {} at {} of {}",
+                    count,
+                    reason,
+                    limit.scope,
+                    limit.name,
+                    used,
+                    limit.format(limit.max)
+                ),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        if lines.is_empty() {
+            lines.push(Line::styled(
+                "None of your pending jobs waits on these limits",
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+        }
+        lines
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let rows = self.limits.iter().map(|limit| {
+            let fraction = limit.fraction();
+            let color = match fraction {
+                Some(f) if f >= 1.0 => Color::Red,
+                Some(f) if f >= NEAR_LIMIT => Color::Yellow,
+                Some(_) => Color::Green,
+                None => Color::DarkGray,
+            };
+            let name = if limit.per_job {
+                format!("{} per job", limit.name)
+            } else {
+                limit.name.clone()
+            };
+            let held = self.held_by(limit);
+            Row::new(vec![
+                Cell::from(limit.scope.to_string()),
+                Cell::from(name),
+                Cell::from(limit.used.map_or("-".to_string(), |u| limit.format(u))),
+                Cell::from(limit.format(limit.max)),
+                Cell::from(fraction.map_or("-".to_string(), |f| format!("{:.0}%", f * 100.0)))
+                    .style(Style::default().fg(color)),
+                Cell::from(if held > 0 {
+                    format!("{} pending", held)
+                } else {
+                    String::new()
+                })
+                .style(Style::default().fg(Color::Yellow)),
+            ])
+        });
+        let table = TablePopup::table(
+            &["Scope", "Limit", "Used", "Max", "Use", "Holding"],
+            rows.collect(),
+            &[
+                Constraint::Percentage(30),
+                Constraint::Length(18),
+                Constraint::Length(12),
+                Constraint::Length(12),
+                Constraint::Length(6),
+                Constraint::Min(10),
+            ],
+        );
+        let overview = self.overview();
+        self.popup.render(
+            frame,
+            area,
+            "QOS and association limits".to_string(),
+            overview,
+            table,
+            "[↑/↓] Select | [r] Refresh | [Esc/L] Close",
+        );
+    }
+}
+
+impl TableView for LimitsView {
+    fn popup(&self) -> &TablePopup {
+        &self.popup
+    }
+
+    fn popup_mut(&mut self) -> &mut TablePopup {
+        &mut self.popup
+    }
+
+    fn handle_key(&mut self, input: Input) -> TableAction {
+        self.popup.handle_key(input, self.limits.len())
+    }
+}
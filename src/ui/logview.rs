@@ -1,6 +1,5 @@
 use color_eyre::Result;
 use crossbeam::channel::{unbounded, Receiver};
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
     layout::Rect,
     style::{Color, Modifier, Style},
@@ -12,12 +11,14 @@ use std::{
     collections::HashMap,
     iter::once,
     path::{Path, PathBuf},
-    process::Command,
-    time::SystemTime,
     time::Duration,
+    time::SystemTime,
 };
 use walkdir::WalkDir;
 
+use crate::keymap::{Action, Input};
+use crate::slurm::command::output_blocking;
+use crate::slurm::parse_scontrol_output;
 use crate::utils::file_watcher::{FileWatcherError, FileWatcherHandle};
 
 /// Type of log to view
@@ -97,6 +98,18 @@ impl LogView {
         self.resolved_cache.clear();
     }
 
+    /// Resolve the stdout log of any job, leaving what this view shows alone
+    pub fn stdout_path_of(&self, job_id: &str) -> Option<PathBuf> {
+        let mut probe = LogView::new();
+        probe.slurm_logs_dir = self.slurm_logs_dir.clone();
+        probe.job_id = Some(job_id.to_string());
+        probe.fetch_log_paths();
+        probe
+            .stdout_path
+            .filter(|p| !p.trim().is_empty())
+            .map(PathBuf::from)
+    }
+
     /// Show the log view for a specific job
     pub fn show(&mut self, job_id: String) {
         self.change_job(job_id);
@@ -188,9 +201,7 @@ impl LogView {
         //         .map_or(true, |instant| instant.elapsed() >= self.refresh_interval)
         // };
 
-        if self.file_receiver.is_some() {
-            let receiver = self.file_receiver.as_ref().unwrap();
-
+        if let Some(receiver) = self.file_receiver.as_ref() {
             // Check for new content from the file watcher
             while let Ok(result) = receiver.try_recv() {
                 match result {
@@ -300,29 +311,29 @@ impl LogView {
         frame.render_widget(log_paragraph, log_area);
     }
 
-    pub fn handle_key(&mut self, key: KeyEvent) -> () {
-        match (key.modifiers, key.code) {
-            (_, KeyCode::Char('o')) => {
+    pub fn handle_key(&mut self, input: Input) {
+        match input {
+            Input::Action(Action::LogStream) => {
                 // Toggle between stdout and stderr logs
                 self.toggle_tab();
             }
-            (_, KeyCode::Char('q')) => {
+            Input::Action(Action::PagerClose) => {
                 // Close the log view
                 self.hide();
             }
-            (_, KeyCode::Up) => {
+            Input::Action(Action::PagerUp) => {
                 // Scroll up
                 self.scroll_up();
             }
-            (_, KeyCode::Down) => {
+            Input::Action(Action::PagerDown) => {
                 // Scroll down
                 self.scroll_down();
             }
-            (_, KeyCode::PageUp) | (KeyModifiers::CONTROL, KeyCode::Char('u')) => {
+            Input::Action(Action::PagerPageUp) => {
                 // Page up
                 self.page_up();
             }
-            (_, KeyCode::PageDown) | (KeyModifiers::CONTROL, KeyCode::Char('d')) => {
+            Input::Action(Action::PagerPageDown) => {
                 // Page down
                 self.page_down();
             }
@@ -332,7 +343,7 @@ impl LogView {
         }
     }
 
-    fn fit_text(s: &str, lines: usize, cols: usize, offset: usize, _wrap: bool) -> Text {
+    fn fit_text(s: &str, lines: usize, cols: usize, offset: usize, _wrap: bool) -> Text<'_> {
         // Process text by handling carriage returns
         let processed_lines: Vec<String> = s
             .lines()
@@ -388,7 +399,7 @@ impl LogView {
             .enumerate()
             .filter(|&(i, _)| {
                 if i > (first_chunk_size) {
-                    chunk_size > 0 && (i - first_chunk_size) % chunk_size == 0
+                    chunk_size > 0 && (i - first_chunk_size).is_multiple_of(chunk_size)
                 } else {
                     i == 0 || i == first_chunk_size
                 }
@@ -405,9 +416,7 @@ impl LogView {
     /// Fetch the stdout and stderr paths for the current job
     fn fetch_log_paths(&mut self) {
         if let Some(job_id) = self.job_id.clone() {
-            let output = Command::new("scontrol")
-                .args(["show", "job", job_id.as_str(), "-o"])
-                .output();
+            let output = output_blocking("scontrol", ["show", "job", job_id.as_str(), "-o"]);
 
             if let Ok(output) = output {
                 if output.status.success() {
@@ -584,32 +593,12 @@ impl LogView {
     }
 }
 
-fn parse_scontrol_output(output: &str) -> HashMap<String, String> {
-    let mut result = HashMap::new();
-
-    for part in output.split_whitespace() {
-        if let Some(index) = part.find('=') {
-            let key = &part[0..index];
-            let value = &part[(index + 1)..];
-            result.insert(key.to_string(), value.to_string());
-        }
-    }
-
-    result
-}
-
 fn fetch_workdir_from_sacct(job_id: &str) -> Option<String> {
-    let output = Command::new("sacct")
-        .args([
-            "-n",
-            "-P",
-            "-X",
-            "-j",
-            job_id,
-            "--format=WorkDir",
-        ])
-        .output()
-        .ok()?;
+    let output = output_blocking(
+        "sacct",
+        ["-n", "-P", "-X", "-j", job_id, "--format=WorkDir"],
+    )
+    .ok()?;
 
     if !output.status.success() {
         return None;
@@ -617,7 +606,8 @@ fn fetch_workdir_from_sacct(job_id: &str) -> Option<String> {
 
     let stdout = String::from_utf8_lossy(&output.stdout);
     for line in stdout.lines() {
-        let v = line.split('|').next().unwrap_or("").trim();
+        // The only field, so a `|` in it is part of the path
+        let v = line.trim();
         if v.is_empty() || v == "Unknown" || v == "N/A" {
             continue;
         }
@@ -634,7 +624,10 @@ struct SearchStats {
     hit_limit: bool,
 }
 
-fn search_slurm_logs(root: &Path, job_id: &str) -> Result<(Option<PathBuf>, Option<PathBuf>, SearchStats)> {
+fn search_slurm_logs(
+    root: &Path,
+    job_id: &str,
+) -> Result<(Option<PathBuf>, Option<PathBuf>, SearchStats)> {
     let mut newest_out: Option<(SystemTime, PathBuf)> = None;
     let mut newest_err: Option<(SystemTime, PathBuf)> = None;
 
@@ -643,7 +636,11 @@ fn search_slurm_logs(root: &Path, job_id: &str) -> Result<(Option<PathBuf>, Opti
     let max_scan = 200_000usize;
     let mut hit_limit = false;
 
-    for entry in WalkDir::new(root).follow_links(false).into_iter().filter_map(|e| e.ok()) {
+    for entry in WalkDir::new(root)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
         if scanned >= max_scan {
             hit_limit = true;
             break;
@@ -660,7 +657,11 @@ fn search_slurm_logs(root: &Path, job_id: &str) -> Result<(Option<PathBuf>, Opti
         matched += 1;
 
         let path = entry.path().to_path_buf();
-        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
         if ext != "out" && ext != "err" {
             continue;
         }
@@ -672,21 +673,11 @@ fn search_slurm_logs(root: &Path, job_id: &str) -> Result<(Option<PathBuf>, Opti
             .unwrap_or(SystemTime::UNIX_EPOCH);
 
         if ext == "out" {
-            if newest_out
-                .as_ref()
-                .map(|(t, _)| mtime > *t)
-                .unwrap_or(true)
-            {
+            if newest_out.as_ref().map(|(t, _)| mtime > *t).unwrap_or(true) {
                 newest_out = Some((mtime, path));
             }
-        } else if ext == "err" {
-            if newest_err
-                .as_ref()
-                .map(|(t, _)| mtime > *t)
-                .unwrap_or(true)
-            {
-                newest_err = Some((mtime, path));
-            }
+        } else if ext == "err" && newest_err.as_ref().map(|(t, _)| mtime > *t).unwrap_or(true) {
+            newest_err = Some((mtime, path));
         }
     }
 
@@ -1,7 +1,27 @@
+pub mod action_menu;
+pub mod aggregate;
 pub mod columns;
+pub mod cron;
+pub mod dependencies;
+pub mod detail;
 pub mod filter;
+pub mod form;
 pub mod jobscript;
 pub mod jobslist;
 pub mod layout;
+pub mod licenses;
+pub mod limits;
 pub mod logview;
+pub mod nodes;
+pub mod palette;
+pub mod pending;
+pub mod reference;
+pub mod requeue;
+pub mod reservations;
+pub mod search;
 pub mod settings;
+pub mod stats;
+pub mod statusbar;
+pub mod table_popup;
+pub mod theme;
+pub mod trends;
@@ -0,0 +1,429 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Cell, Row},
+    Frame,
+};
+
+use crate::keymap::{Action, Input};
+use crate::slurm::nodes::{partition_gpus, Node, NodeUpdate};
+use crate::ui::table_popup::{TableAction, TablePopup, TableView};
+
+/// What the keys of the cluster view do
+enum Mode {
+    Browse,
+    /// Typing the drain reason of a node on its row
+    EditReason {
+        node: String,
+        reason: String,
+    },
+    /// Waiting for y/n
+    Confirm(NodeUpdate),
+}
+
+/// The cluster: its partitions, reservations and licenses, and its nodes,
+/// which operators can drain and resume
+pub struct NodeView {
+    pub popup: TablePopup,
+    /// Whether `[admin] node_actions` allows draining and resuming
+    pub actions_enabled: bool,
+    /// The cluster's name, partitions, reservations and licenses, as polled
+    summary: String,
+    /// Whether the nodes are being read
+    loading: bool,
+    nodes: Vec<Node>,
+    /// Shown in place of the overview, e.g. that actions are off
+    message: Option<String>,
+    mode: Mode,
+}
+
+impl NodeView {
+    pub fn new(actions_enabled: bool) -> Self {
+        Self {
+            popup: TablePopup::new("Nodes", 'E'),
+            actions_enabled,
+            summary: String::new(),
+            loading: false,
+            nodes: Vec::new(),
+            message: None,
+            mode: Mode::Browse,
+        }
+    }
+
+    /// Open the view on `summary` while the nodes are read
+    pub fn open(&mut self, summary: String) {
+        self.summary = summary;
+        self.loading = true;
+        self.message = None;
+        self.mode = Mode::Browse;
+        self.popup.visible = true;
+    }
+
+    /// Show the nodes as read, keeping the message of the last update
+    pub fn show(&mut self, nodes: Result<Vec<Node>, String>) {
+        self.loading = false;
+        let error = match nodes {
+            Ok(nodes) => {
+                self.nodes = nodes;
+                None
+            }
+            Err(e) => Some(e),
+        };
+        self.popup.show(error, self.nodes.len());
+    }
+
+    /// Show the outcome of the last update
+    pub fn set_message(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    /// Whether the list takes keys, rather than a drain reason or a
+    /// confirmation
+    pub fn browsing(&self) -> bool {
+        self.popup.visible && matches!(self.mode, Mode::Browse)
+    }
+
+    /// Whether a drain reason is being typed
+    pub fn editing(&self) -> bool {
+        self.popup.visible && matches!(self.mode, Mode::EditReason { .. })
+    }
+
+    fn selected(&self) -> Option<&Node> {
+        self.popup.selected().and_then(|i| self.nodes.get(i))
+    }
+
+    fn handle_browse_key(&mut self, input: Input) -> TableAction {
+        match input {
+            Input::Action(Action::NodeDrain | Action::NodeResume) if !self.actions_enabled => {
+                self.message =
+                    Some("Node actions are off; set node_actions = true under [admin]".to_string());
+                TableAction::None
+            }
+            // Drain, or edit the reason of a drained node
+            Input::Action(Action::NodeDrain) => {
+                if let Some(node) = self.selected() {
+                    self.mode = Mode::EditReason {
+                        node: node.name.clone(),
+                        reason: node.reason.clone().unwrap_or_default(),
+                    };
+                }
+                TableAction::None
+            }
+            Input::Action(Action::NodeResume) => {
+                match self.selected() {
+                    Some(node) if node.is_drained() || node.is_down() => {
+                        self.mode = Mode::Confirm(NodeUpdate::Resume {
+                            node: node.name.clone(),
+                        });
+                    }
+                    Some(node) => self.message = Some(format!("{} is in service", node.name)),
+                    None => {}
+                }
+                TableAction::None
+            }
+            _ => self.popup.handle_key(input, self.nodes.len()),
+        }
+    }
+
+    /// Node counts by state, or what is being asked
+    fn overview(&self) -> Line<'static> {
+        let warn = Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD);
+        match &self.mode {
+            Mode::Confirm(NodeUpdate::Drain { node, reason }) => {
+                return Line::styled(
+                    format!("Drain {} with reason \"{}\"? [y/n]", node, reason),
+                    warn,
+                );
+            }
+            Mode::Confirm(NodeUpdate::Resume { node }) => {
+                return Line::styled(format!("Resume {}? [y/n]", node), warn);
+            }
+            Mode::EditReason { node, .. } => {
+                return Line::styled(
+                    format!(
+                        "Drain reason for {}; running jobs finish, no new ones start",
+                        node
+                    ),
+                    warn,
+                );
+            }
+            Mode::Browse => {}
+        }
+        if let Some(unavailable) = self.popup.unavailable() {
+            return unavailable;
+        }
+        if let Some(message) = &self.message {
+            return Line::from(message.clone());
+        }
+        if self.loading && self.nodes.is_empty() {
+            return Line::from("Reading the nodes…");
+        }
+        let drained = self.nodes.iter().filter(|n| n.is_drained()).count();
+        let down = self.nodes.iter().filter(|n| n.is_down()).count();
+        let busy = self
+            .nodes
+            .iter()
+            .filter(|n| n.cpus_alloc > 0 && !n.is_down())
+            .count();
+        let watts: u32 = self.nodes.iter().filter_map(|n| n.current_watts).sum();
+        let power = if watts > 0 {
+            format!(", drawing {:.1} kW", f64::from(watts) / 1000.0)
+        } else {
+            String::new()
+        };
+        Line::styled(
+            format!(
+                "{} nodes: {} running jobs, {} drained or draining, {} down{}",
+                self.nodes.len(),
+                busy,
+                drained,
+                down,
+                power
+            ),
+            Style::default().add_modifier(Modifier::BOLD),
+        )
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let editing = match &self.mode {
+            Mode::EditReason { node, reason } => Some((node.as_str(), reason.as_str())),
+            _ => None,
+        };
+        let rows = self.nodes.iter().map(|node| {
+            let color = if node.is_down() {
+                Color::Red
+            } else if node.is_drained() {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            let reason = match editing {
+                Some((name, reason)) if name == node.name => Cell::from(format!("{}▏", reason))
+                    .style(
+                        Style::default()
+                            .fg(Color::Cyan)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                _ => Cell::from(match (&node.reason, &node.reason_by) {
+                    (Some(reason), Some(by)) => format!("{} [{}]", reason, by),
+                    (Some(reason), None) => reason.clone(),
+                    (None, _) => String::new(),
+                }),
+            };
+            Row::new(vec![
+                Cell::from(node.name.clone()),
+                Cell::from(node.state.clone()).style(Style::default().fg(color)),
+                Cell::from(format!("{}/{}", node.cpus_alloc, node.cpus_total)),
+                Cell::from(format!(
+                    "{}/{}",
+                    node.memory_alloc / 1024,
+                    node.memory_total / 1024
+                )),
+                Cell::from(match node.gpus_total {
+                    0 => String::new(),
+                    total => format!("{}/{}", node.gpus_alloc, total),
+                }),
+                Cell::from(
+                    node.current_watts
+                        .map(|w| format!("{} W", w))
+                        .unwrap_or_default(),
+                ),
+                Cell::from(node.partitions.clone()),
+                reason,
+            ])
+        });
+        let table = TablePopup::table(
+            &[
+                "Node",
+                "State",
+                "CPUs",
+                "Memory (GB)",
+                "GPUs",
+                "Power",
+                "Partitions",
+                "Reason",
+            ],
+            rows.collect(),
+            &[
+                Constraint::Length(14),
+                Constraint::Length(22),
+                Constraint::Length(9),
+                Constraint::Length(12),
+                Constraint::Length(6),
+                Constraint::Length(8),
+                Constraint::Length(14),
+                Constraint::Min(20),
+            ],
+        );
+
+        let help = match (&self.mode, self.actions_enabled) {
+            (Mode::EditReason { .. }, _) => "[Enter] Drain | [Esc] Cancel",
+            (Mode::Confirm(_), _) => "[y] Confirm | [n/Esc] Cancel",
+            (Mode::Browse, true) => {
+                "[↑/↓] Select | [D] Drain / edit reason | [R] Resume | [r] Refresh | [Esc/E] Close"
+            }
+            (Mode::Browse, false) => "[↑/↓] Select | [r] Refresh | [Esc/E] Close",
+        };
+        let mut overview = vec![
+            self.overview(),
+            Line::styled(self.summary.clone(), Style::default().fg(Color::Gray)),
+        ];
+        let gpus: Vec<String> = partition_gpus(&self.nodes)
+            .iter()
+            .map(|(partition, alloc, total)| format!("{} {}/{}", partition, alloc, total))
+            .collect();
+        if !gpus.is_empty() {
+            overview.push(Line::styled(
+                format!("GPUs allocated: {}", gpus.join(", ")),
+                Style::default().fg(Color::Gray),
+            ));
+        }
+        let title = format!("Cluster: {} nodes", self.nodes.len());
+        self.popup.render(frame, area, title, overview, table, help);
+    }
+}
+
+impl TableView for NodeView {
+    fn popup(&self) -> &TablePopup {
+        &self.popup
+    }
+
+    fn popup_mut(&mut self) -> &mut TablePopup {
+        &mut self.popup
+    }
+
+    fn handle_key(&mut self, input: Input) -> TableAction {
+        // Only browsing binds actions; the reason and the answer are typed
+        if let Mode::Browse = self.mode {
+            return self.handle_browse_key(input);
+        }
+        let Input::Key(key) = input else {
+            return TableAction::None;
+        };
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::EditReason { node, mut reason } => {
+                match key.code {
+                    KeyCode::Esc => {}
+                    // Slurm refuses to drain without a reason
+                    KeyCode::Enter if !reason.trim().is_empty() => {
+                        self.mode = Mode::Confirm(NodeUpdate::Drain {
+                            node,
+                            reason: reason.trim().to_string(),
+                        });
+                    }
+                    KeyCode::Backspace => {
+                        reason.pop();
+                        self.mode = Mode::EditReason { node, reason };
+                    }
+                    KeyCode::Char(c) => {
+                        reason.push(c);
+                        self.mode = Mode::EditReason { node, reason };
+                    }
+                    _ => self.mode = Mode::EditReason { node, reason },
+                }
+                TableAction::None
+            }
+            Mode::Confirm(update) => match key.code {
+                KeyCode::Char('y') => TableAction::Update(update),
+                KeyCode::Char('n') | KeyCode::Esc => TableAction::None,
+                _ => {
+                    self.mode = Mode::Confirm(update);
+                    TableAction::None
+                }
+            },
+            Mode::Browse => TableAction::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::{KeyContext, KeyOutcome, Keymap};
+    use crossterm::event::{KeyEvent, KeyModifiers};
+
+    /// Press `code` as the app hands it on: bound while browsing
+    fn press(view: &mut NodeView, code: KeyCode) -> TableAction {
+        let key = KeyEvent::new(code, KeyModifiers::NONE);
+        let input = match Keymap::default().press(KeyContext::Nodes, key) {
+            KeyOutcome::Action(action) if view.browsing() => Input::Action(action),
+            _ => Input::Key(key),
+        };
+        view.handle_key(input)
+    }
+
+    fn view(actions_enabled: bool) -> NodeView {
+        let mut view = NodeView::new(actions_enabled);
+        view.open(String::new());
+        view.show(Ok(vec![
+            Node {
+                name: "gpu01".to_string(),
+                state: "IDLE+DRAIN".to_string(),
+                reason: Some("fan".to_string()),
+                ..Default::default()
+            },
+            Node {
+                name: "gpu02".to_string(),
+                state: "MIXED".to_string(),
+                ..Default::default()
+            },
+        ]));
+        view
+    }
+
+    #[test]
+    fn drains_and_resumes_only_when_enabled_and_confirmed() {
+        let mut off = view(false);
+        assert!(matches!(
+            press(&mut off, KeyCode::Char('D')),
+            TableAction::None
+        ));
+        assert!(off.browsing());
+        assert!(off.message.as_deref().unwrap().contains("node_actions"));
+
+        let mut on = view(true);
+        // The reason of a drained node is edited from its current one
+        press(&mut on, KeyCode::Char('D'));
+        assert!(on.editing());
+        press(&mut on, KeyCode::Char('!'));
+        press(&mut on, KeyCode::Enter);
+        assert!(!on.browsing() && !on.editing());
+        assert!(matches!(
+            press(&mut on, KeyCode::Char('n')),
+            TableAction::None
+        ));
+        assert!(on.browsing());
+
+        // An empty reason is not taken
+        press(&mut on, KeyCode::Down);
+        press(&mut on, KeyCode::Char('D'));
+        press(&mut on, KeyCode::Enter);
+        assert!(on.editing());
+        press(&mut on, KeyCode::Char('x'));
+        press(&mut on, KeyCode::Enter);
+        match press(&mut on, KeyCode::Char('y')) {
+            TableAction::Update(update) => assert_eq!(
+                update,
+                NodeUpdate::Drain {
+                    node: "gpu02".to_string(),
+                    reason: "x".to_string()
+                }
+            ),
+            _ => panic!("the drain was not confirmed"),
+        }
+
+        // Only nodes out of service resume
+        press(&mut on, KeyCode::Char('R'));
+        assert!(on.browsing());
+        press(&mut on, KeyCode::Up);
+        press(&mut on, KeyCode::Char('R'));
+        assert!(matches!(
+            press(&mut on, KeyCode::Char('y')),
+            TableAction::Update(NodeUpdate::Resume { .. })
+        ));
+    }
+}
@@ -0,0 +1,242 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::keymap::Action;
+
+/// What a palette entry does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteCommand {
+    Action(Action),
+    /// The `[defaults]` view (key 0)
+    DefaultView,
+    /// A filter preset, by index
+    Preset(usize),
+    /// An `[[actions]]` entry of the config, by index
+    Custom(usize),
+}
+
+/// One entry of the palette
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub title: String,
+    /// Keys it is bound to, shown next to the title
+    pub keys: String,
+    pub command: PaletteCommand,
+}
+
+/// What the app should do after a key press in the palette
+pub enum PaletteAction {
+    None,
+    Close,
+    Run(PaletteCommand),
+}
+
+/// Fuzzy-searchable list of the current view's commands and their keys
+pub struct CommandPalette {
+    pub visible: bool,
+    pub query: String,
+    /// What the commands apply to, for the title (e.g. `job list`)
+    view: &'static str,
+    commands: Vec<Command>,
+    /// Indices into `commands`, best match first
+    results: Vec<usize>,
+    list_state: ListState,
+}
+
+impl CommandPalette {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            query: String::new(),
+            view: "",
+            commands: Vec::new(),
+            results: Vec::new(),
+            list_state: ListState::default(),
+        }
+    }
+
+    pub fn show(&mut self, view: &'static str, commands: Vec<Command>) {
+        self.view = view;
+        self.commands = commands;
+        self.query.clear();
+        self.update_results();
+        self.visible = true;
+    }
+
+    fn update_results(&mut self) {
+        let mut ranked: Vec<(u32, usize)> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| {
+                let text = format!("{} {}", c.title, c.keys);
+                fuzzy_score(&self.query, &text).map(|score| (score, i))
+            })
+            .collect();
+        // Stable sort keeps the commands' order among equal scores
+        ranked.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.results = ranked.into_iter().map(|(_, i)| i).collect();
+        self.list_state
+            .select((!self.results.is_empty()).then_some(0));
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> PaletteAction {
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc) | (KeyModifiers::CONTROL, KeyCode::Char('c' | 'p')) => {
+                return PaletteAction::Close
+            }
+            (_, KeyCode::Enter) => {
+                let selected = self.list_state.selected().and_then(|i| self.results.get(i));
+                if let Some(&i) = selected {
+                    return PaletteAction::Run(self.commands[i].command);
+                }
+            }
+            (_, KeyCode::Up) | (KeyModifiers::CONTROL, KeyCode::Char('k')) => {
+                let i = self.list_state.selected().unwrap_or(0);
+                self.list_state.select(Some(i.saturating_sub(1)));
+            }
+            (_, KeyCode::Down) | (KeyModifiers::CONTROL, KeyCode::Char('j')) => {
+                let i = self.list_state.selected().unwrap_or(0);
+                if i + 1 < self.results.len() {
+                    self.list_state.select(Some(i + 1));
+                }
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('u')) => {
+                self.query.clear();
+                self.update_results();
+            }
+            (_, KeyCode::Backspace) => {
+                self.query.pop();
+                self.update_results();
+            }
+            (_, KeyCode::Char(c)) => {
+                self.query.push(c);
+                self.update_results();
+            }
+            _ => {}
+        }
+        PaletteAction::None
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        if !self.visible {
+            return;
+        }
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(format!("Commands of the {}", self.view))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(3),
+                Constraint::Length(1),
+            ])
+            .split(inner);
+
+        let search_box = Paragraph::new(format!("{}▏", self.query)).block(
+            Block::default()
+                .title(format!("Search ({} matches)", self.results.len()))
+                .borders(Borders::ALL),
+        );
+        frame.render_widget(search_box, chunks[0]);
+
+        let items: Vec<ListItem> = self
+            .results
+            .iter()
+            .map(|&i| {
+                let command = &self.commands[i];
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:<54}", command.title),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(command.keys.clone(), Style::default().fg(Color::DarkGray)),
+                ]))
+            })
+            .collect();
+        let list = List::new(items).highlight_style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::REVERSED),
+        );
+        frame.render_stateful_widget(list, chunks[1], &mut self.list_state);
+
+        let help = Paragraph::new("[type] Search | [↑/↓] Select | [Enter] Run | [Esc] Close")
+            .style(Style::default().fg(Color::Gray));
+        frame.render_widget(help, chunks[2]);
+    }
+}
+
+/// How well `query` matches `text` when its characters appear in order
+/// (ignoring case), or `None`. Consecutive characters and characters that
+/// start a word score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut at = 0;
+    let mut previous: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = at + text[at..].iter().position(|&c| c == q)?;
+        score += 1;
+        if previous == Some(found.wrapping_sub(1)) {
+            score += 3;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        previous = Some(found);
+        at = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_ranks_fuzzy_matches() {
+        assert_eq!(fuzzy_score("", "Cancel the selected jobs"), Some(0));
+        assert!(fuzzy_score("cnl", "Cancel the selected jobs").is_some());
+        assert!(fuzzy_score("xyz", "Cancel the selected jobs").is_none());
+        assert!(
+            fuzzy_score("can", "Cancel the selected jobs")
+                > fuzzy_score("can", "Scroll the detail pane")
+        );
+
+        let command = |title: &str, name| Command {
+            title: title.to_string(),
+            keys: String::new(),
+            command: PaletteCommand::Action(Action::from_name(name).unwrap()),
+        };
+        let mut palette = CommandPalette::new();
+        palette.show(
+            "job list",
+            vec![
+                command("Refresh the job list", "refresh"),
+                command("Cancel the selected jobs", "cancel"),
+            ],
+        );
+        for c in "canc".chars() {
+            palette.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        let run = palette.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(matches!(
+            run,
+            PaletteAction::Run(PaletteCommand::Action(a)) if a.name() == "cancel"
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+use chrono::Local;
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+use std::collections::HashMap;
+
+use crate::keymap::{Action, Input};
+use crate::slurm::hints::{hint_for, HintKeys};
+use crate::slurm::pending::{summary, ReasonGroup, ReasonKind};
+use crate::ui::theme;
+use crate::utils::format_slurm_duration;
+
+/// What the app should do after a key press in the pending-reason view
+pub enum PendingAction {
+    None,
+    Close,
+    Refresh,
+}
+
+/// Pending jobs grouped by reason, to tell limits from plain cluster load
+pub struct PendingView {
+    pub visible: bool,
+    groups: Vec<ReasonGroup>,
+    /// Remediation hint per group
+    hints: Vec<Option<String>>,
+    table_state: TableState,
+}
+
+impl PendingView {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            groups: Vec::new(),
+            hints: Vec::new(),
+            table_state: TableState::default(),
+        }
+    }
+
+    /// Show the groups, with hints from the built-in texts and site overrides
+    pub fn show(
+        &mut self,
+        groups: Vec<ReasonGroup>,
+        overrides: &HashMap<String, String>,
+        keys: &HintKeys,
+    ) {
+        self.hints = groups
+            .iter()
+            .map(|g| hint_for("PENDING", Some(&g.reason), overrides, keys))
+            .collect();
+        let index = self
+            .table_state
+            .selected()
+            .filter(|i| *i < groups.len())
+            .or((!groups.is_empty()).then_some(0));
+        self.groups = groups;
+        self.table_state.select(index);
+        self.visible = true;
+    }
+
+    pub fn handle_key(&mut self, input: Input) -> PendingAction {
+        match input {
+            Input::Action(Action::PopupClose) => PendingAction::Close,
+            _ if input.is_key(KeyCode::Char('W')) || input.is_ctrl_c() => PendingAction::Close,
+            Input::Action(Action::PopupRefresh) => PendingAction::Refresh,
+            Input::Action(Action::PopupUp) => {
+                let i = self.table_state.selected().unwrap_or(0);
+                self.table_state.select(Some(i.saturating_sub(1)));
+                PendingAction::None
+            }
+            Input::Action(Action::PopupDown) => {
+                let i = self.table_state.selected().unwrap_or(0);
+                if i + 1 < self.groups.len() {
+                    self.table_state.select(Some(i + 1));
+                }
+                PendingAction::None
+            }
+            _ => PendingAction::None,
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        if !self.visible {
+            return;
+        }
+        frame.render_widget(Clear, area);
+
+        let jobs: usize = self.groups.iter().map(|g| g.jobs).sum();
+        let block = Block::default()
+            .title(format!("Pending jobs by reason: {}", jobs))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(2),
+                Constraint::Min(3),
+                Constraint::Length(3),
+                Constraint::Length(1),
+            ])
+            .split(inner);
+
+        let blocked_by = summary(&self.groups)
+            .iter()
+            .map(|(kind, n)| format!("{} on {}", n, kind))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let overview = if self.groups.is_empty() {
+            "No pending jobs".to_string()
+        } else {
+            format!("Waiting: {}", blocked_by)
+        };
+        frame.render_widget(
+            Paragraph::new(Line::styled(
+                overview,
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            chunks[0],
+        );
+
+        let now = Local::now();
+        let header = Row::new(["Reason", "Kind", "Jobs", "Oldest", "Waiting"])
+            .style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .height(1);
+        let rows = self.groups.iter().map(|g| {
+            let (oldest, waiting) = match &g.oldest {
+                Some((id, submit)) => (
+                    id.clone(),
+                    format_slurm_duration((now - *submit).to_std().unwrap_or_default()),
+                ),
+                None => ("-".to_string(), "-".to_string()),
+            };
+            Row::new(vec![
+                Cell::from(g.reason.clone()),
+                Cell::from(g.kind.to_string()).style(Style::default().fg(kind_color(g.kind))),
+                Cell::from(g.jobs.to_string()),
+                Cell::from(oldest),
+                Cell::from(waiting),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Percentage(40),
+                Constraint::Length(14),
+                Constraint::Length(7),
+                Constraint::Length(14),
+                Constraint::Length(14),
+            ],
+        )
+        .header(header)
+        .row_highlight_style(theme::selection());
+        frame.render_stateful_widget(table, chunks[1], &mut self.table_state);
+
+        let hint = self
+            .table_state
+            .selected()
+            .and_then(|i| self.hints.get(i).cloned().flatten());
+        if let Some(hint) = hint {
+            frame.render_widget(
+                Paragraph::new(format!("Hint: {}", hint))
+                    .style(Style::default().fg(Color::Yellow))
+                    .wrap(Wrap { trim: true }),
+                chunks[2],
+            );
+        }
+
+        let help = Paragraph::new("[↑/↓] Select | [r] Refresh | [Esc/W] Close")
+            .style(Style::default().fg(Color::Gray));
+        frame.render_widget(help, chunks[3]);
+    }
+}
+
+fn kind_color(kind: ReasonKind) -> Color {
+    match kind {
+        ReasonKind::Limit => Color::Red,
+        ReasonKind::Load => Color::Yellow,
+        ReasonKind::Dependency => Color::Cyan,
+        ReasonKind::Held => Color::Magenta,
+        ReasonKind::Other => Color::Gray,
+    }
+}
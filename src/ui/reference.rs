@@ -0,0 +1,246 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::keymap::{Action, KeyContext};
+use crate::slurm::{reference::search, Job, JobState};
+
+/// One match of the search: a reference entry or a key binding
+struct Found {
+    term: String,
+    topic: String,
+    text: String,
+}
+
+/// Searchable reference of job states, pending reasons, exit codes and the
+/// current key bindings
+pub struct ReferencePopup {
+    pub visible: bool,
+    pub query: String,
+    /// Each action with its keys, as bound by `[keys]`
+    bindings: Vec<(Action, String)>,
+    /// The view whose keys alone are listed
+    view: Option<KeyContext>,
+    results: Vec<Found>,
+    list_state: ListState,
+}
+
+impl ReferencePopup {
+    pub fn new() -> Self {
+        let mut popup = Self {
+            visible: false,
+            query: String::new(),
+            bindings: Vec::new(),
+            view: None,
+            results: Vec::new(),
+            list_state: ListState::default(),
+        };
+        popup.update_results();
+        popup
+    }
+
+    /// Open the reference with `query` searched
+    pub fn show(&mut self, query: &str) {
+        self.view = None;
+        self.query = query.to_string();
+        self.update_results();
+        self.visible = true;
+    }
+
+    /// Open the reference on what is most likely puzzling about `job`: the
+    /// pending reason of a pending job, otherwise its state.
+    pub fn show_for_job(&mut self, job: &Job) {
+        let reason = job
+            .pending_reason
+            .as_deref()
+            .map(|r| r.trim_matches(|c| c == '(' || c == ')'))
+            .filter(|r| !r.is_empty() && *r != "None");
+        match reason {
+            Some(reason) if job.state == JobState::Pending => self.show(reason),
+            _ => self.show(&job.state.to_string()),
+        }
+    }
+
+    /// Open the reference on the keys of the view of `context` alone
+    pub fn show_keys(&mut self, context: KeyContext) {
+        self.view = Some(context);
+        self.query.clear();
+        self.update_results();
+        self.visible = true;
+    }
+
+    pub fn set_bindings(&mut self, bindings: Vec<(Action, String)>) {
+        self.bindings = bindings;
+        self.update_results();
+    }
+
+    /// Reference entries first, then the bindings whose keys, action or
+    /// description match; "key" or "keys" lists every binding. Open on a
+    /// view, only the bindings of that view.
+    fn update_results(&mut self) {
+        let query = self.query.trim().to_lowercase();
+        let entries = match self.view {
+            Some(_) => Vec::new(),
+            None => search(&query),
+        };
+        let mut results: Vec<Found> = entries
+            .into_iter()
+            .map(|e| Found {
+                term: e.term.to_string(),
+                topic: e.topic.title().to_string(),
+                text: e.text.to_string(),
+            })
+            .collect();
+        let every = query.is_empty() || query == "key" || query == "keys";
+        for (action, keys) in &self.bindings {
+            if self
+                .view
+                .is_some_and(|view| !view.includes(action.context()))
+            {
+                continue;
+            }
+            let matches = every
+                || keys.to_lowercase() == query
+                || action.name().contains(&query)
+                || action.description().to_lowercase().contains(&query);
+            if !matches {
+                continue;
+            }
+            results.push(Found {
+                term: keys.clone(),
+                topic: format!("key of the {}", action.context().view()),
+                text: format!("{} (`{}` in [keys])", action.description(), action.name()),
+            });
+        }
+        self.results = results;
+        self.list_state.select(if self.results.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Up) => {
+                let i = self.list_state.selected().unwrap_or(0);
+                self.list_state.select(Some(i.saturating_sub(1)));
+            }
+            (_, KeyCode::Down) => {
+                let i = self.list_state.selected().unwrap_or(0);
+                if i + 1 < self.results.len() {
+                    self.list_state.select(Some(i + 1));
+                }
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('u')) => {
+                self.query.clear();
+                self.update_results();
+            }
+            (_, KeyCode::Backspace) => {
+                self.query.pop();
+                self.update_results();
+            }
+            (_, KeyCode::Char(c)) => {
+                self.query.push(c);
+                self.update_results();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        if !self.visible {
+            return;
+        }
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(match self.view {
+                Some(view) => format!("Keys of the {}", view.view()),
+                None => "Slurm reference and keys".to_string(),
+            })
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3), // Search box
+                Constraint::Min(3),    // Matches
+                Constraint::Length(5), // Explanation
+                Constraint::Length(1), // Help
+            ])
+            .split(inner);
+
+        let search_box = Paragraph::new(format!("{}▏", self.query)).block(
+            Block::default()
+                .title(format!("Search ({} matches)", self.results.len()))
+                .borders(Borders::ALL),
+        );
+        frame.render_widget(search_box, chunks[0]);
+
+        let items: Vec<ListItem> = self
+            .results
+            .iter()
+            .map(|e| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:<30}", e.term),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(e.topic.as_str(), Style::default().fg(Color::DarkGray)),
+                ]))
+            })
+            .collect();
+        let list = List::new(items).highlight_style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::REVERSED),
+        );
+        frame.render_stateful_widget(list, chunks[1], &mut self.list_state);
+
+        let explanation = self
+            .list_state
+            .selected()
+            .and_then(|i| self.results.get(i))
+            .map_or("No matching entry", |e| e.text.as_str());
+        let text = Paragraph::new(explanation)
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::TOP));
+        frame.render_widget(text, chunks[2]);
+
+        let help = Paragraph::new(
+            "[type] Search (\"keys\" for every key) | [↑/↓] Select | [Ctrl+u] Clear | [Esc] Close",
+        )
+        .style(Style::default().fg(Color::Gray));
+        frame.render_widget(help, chunks[3]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::Keymap;
+
+    #[test]
+    fn keys_of_a_view_include_the_shared_popup_keys() {
+        let mut popup = ReferencePopup::new();
+        popup.set_bindings(Keymap::default().describe());
+        popup.show_keys(KeyContext::Stats);
+        let terms: Vec<&str> = popup.results.iter().map(|f| f.term.as_str()).collect();
+        assert!(terms.contains(&"g"), "{:?}", terms);
+        assert!(terms.contains(&"esc"));
+        assert!(!terms.contains(&"x"));
+
+        // Opened on a query, the reference lists states and every view's keys
+        popup.show("keys");
+        assert!(popup.results.iter().any(|f| f.term == "x"));
+    }
+}
@@ -0,0 +1,185 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use super::jobslist::state_color;
+use crate::keymap::{Action, Input};
+use crate::slurm::requeue::{qos_summary, AttemptChain, QosSummary};
+use crate::ui::theme;
+use crate::utils::format_slurm_duration;
+
+/// What the app should do after a key press in the attempt-chain view
+pub enum ChainAction {
+    None,
+    Close,
+    Refresh,
+}
+
+/// Jobs that were preempted or requeued, with their later attempts, and the
+/// runtime each QOS lost to interruptions
+pub struct ChainView {
+    pub visible: bool,
+    /// Days of accounting covered, for the title
+    pub days: u32,
+    summaries: Vec<QosSummary>,
+    /// Only the chains with more than one attempt or an interruption
+    chains: Vec<AttemptChain>,
+    table_state: TableState,
+}
+
+impl ChainView {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            days: 0,
+            summaries: Vec::new(),
+            chains: Vec::new(),
+            table_state: TableState::default(),
+        }
+    }
+
+    pub fn show(&mut self, chains: Vec<AttemptChain>) {
+        self.summaries = qos_summary(&chains);
+        self.chains = chains
+            .into_iter()
+            .filter(|c| c.attempts.len() > 1 || c.was_interrupted())
+            .collect();
+        let index = self
+            .table_state
+            .selected()
+            .filter(|i| *i < self.chains.len())
+            .or((!self.chains.is_empty()).then_some(0));
+        self.table_state.select(index);
+        self.visible = true;
+    }
+
+    pub fn handle_key(&mut self, input: Input) -> ChainAction {
+        match input {
+            Input::Action(Action::PopupClose) => ChainAction::Close,
+            _ if input.is_key(KeyCode::Char('C')) || input.is_ctrl_c() => ChainAction::Close,
+            Input::Action(Action::PopupRefresh) => ChainAction::Refresh,
+            Input::Action(Action::PopupUp) => {
+                let i = self.table_state.selected().unwrap_or(0);
+                self.table_state.select(Some(i.saturating_sub(1)));
+                ChainAction::None
+            }
+            Input::Action(Action::PopupDown) => {
+                let i = self.table_state.selected().unwrap_or(0);
+                if i + 1 < self.chains.len() {
+                    self.table_state.select(Some(i + 1));
+                }
+                ChainAction::None
+            }
+            _ => ChainAction::None,
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        if !self.visible {
+            return;
+        }
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(format!(
+                "Requeued and preempted jobs, last {} days",
+                self.days
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let summary_height = self.summaries.len().clamp(1, 6) as u16;
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(summary_height),
+                Constraint::Length(1),
+                Constraint::Min(3),
+                Constraint::Length(1),
+            ])
+            .split(inner);
+
+        let summary: Vec<Line> = if self.summaries.is_empty() {
+            vec![Line::from("No jobs in this period")]
+        } else {
+            self.summaries
+                .iter()
+                .map(|s| {
+                    let wasted = match s.wasted_fraction() {
+                        Some(f) => format!("{:.0}%", f * 100.0),
+                        None => "-".to_string(),
+                    };
+                    Line::styled(
+                        format!(
+                            "QOS {}: {} jobs, {} interrupted; ran {}, {} wasted ({})",
+                            if s.qos.is_empty() { "-" } else { &s.qos },
+                            s.chains,
+                            s.interrupted,
+                            format_slurm_duration(s.runtime),
+                            format_slurm_duration(s.wasted),
+                            wasted
+                        ),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )
+                })
+                .collect()
+        };
+        frame.render_widget(Paragraph::new(summary), chunks[0]);
+
+        let header = Row::new([
+            "Job", "Name", "QOS", "Attempts", "Runtime", "Wasted", "Final",
+        ])
+        .style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .height(1);
+        let rows = self.chains.iter().map(|c| {
+            let first = c.first();
+            let last = c.last();
+            let ids = if first.id == last.id {
+                first.id.clone()
+            } else {
+                format!("{} → {}", first.id, last.id)
+            };
+            Row::new(vec![
+                Cell::from(ids),
+                Cell::from(first.name.clone()),
+                Cell::from(first.qos.clone()),
+                Cell::from(c.attempts.len().to_string()),
+                Cell::from(format_slurm_duration(c.runtime())),
+                Cell::from(format_slurm_duration(c.wasted()))
+                    .style(Style::default().fg(Color::Red)),
+                Cell::from(last.state.to_string())
+                    .style(Style::default().fg(state_color(last.state))),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(18),
+                Constraint::Percentage(30),
+                Constraint::Length(10),
+                Constraint::Length(9),
+                Constraint::Length(12),
+                Constraint::Length(12),
+                Constraint::Length(12),
+            ],
+        )
+        .header(header)
+        .row_highlight_style(theme::selection());
+        frame.render_stateful_widget(table, chunks[2], &mut self.table_state);
+
+        let help = Paragraph::new("[↑/↓] Select | [r] Refresh | [Esc/C] Close")
+            .style(Style::default().fg(Color::Gray));
+        frame.render_widget(help, chunks[3]);
+    }
+}
@@ -0,0 +1,189 @@
+use chrono::Local;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Cell, Row},
+    Frame,
+};
+
+use crate::keymap::Input;
+use crate::slurm::reservation::Reservation;
+use crate::slurm::scrontab::format_minute;
+use crate::slurm::{Job, JobState};
+use crate::ui::table_popup::{TableAction, TablePopup, TableView};
+use crate::utils::format_slurm_duration;
+
+/// A reservation with how it touches the listed jobs
+struct ReservationRow {
+    reservation: Reservation,
+    /// Listed jobs running inside it
+    running: usize,
+    /// Listed pending jobs that cannot start before it begins
+    blocked: usize,
+}
+
+/// Active and upcoming reservations of the cluster
+pub struct ReservationView {
+    pub popup: TablePopup,
+    rows: Vec<ReservationRow>,
+}
+
+impl ReservationView {
+    pub fn new() -> Self {
+        Self {
+            popup: TablePopup::new("Reservations", 'M'),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn show(&mut self, reservations: Result<Vec<Reservation>, String>, jobs: &[Job]) {
+        let now = Local::now();
+        let error = match reservations {
+            Ok(reservations) => {
+                self.rows = reservations
+                    .into_iter()
+                    .map(|reservation| ReservationRow {
+                        running: jobs
+                            .iter()
+                            .filter(|j| j.state == JobState::Running)
+                            .filter(|j| j.reservation.as_deref() == Some(&reservation.name))
+                            .count(),
+                        blocked: jobs.iter().filter(|j| reservation.blocks(j, now)).count(),
+                        reservation,
+                    })
+                    .collect();
+                None
+            }
+            Err(e) => Some(e),
+        };
+        self.popup.show(error, self.rows.len());
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let now = Local::now();
+        let overview = if let Some(unavailable) = self.popup.unavailable() {
+            unavailable
+        } else if self.rows.is_empty() {
+            Line::from("No reservations on the cluster")
+        } else {
+            let active = self
+                .rows
+                .iter()
+                .filter(|r| r.reservation.is_active(now))
+                .count();
+            let blocked: usize = self.rows.iter().map(|r| r.blocked).sum();
+            let style = if blocked > 0 {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            Line::styled(
+                format!(
+                    "{} active, {} upcoming; {} listed pending job(s) cannot start before one begins",
+                    active,
+                    self.rows.len() - active,
+                    blocked
+                ),
+                style.add_modifier(Modifier::BOLD),
+            )
+        };
+
+        let rows = self.rows.iter().map(|row| {
+            let r = &row.reservation;
+            let active = r.is_active(now);
+            let window = match (r.start_time, r.end_time) {
+                (_, Some(end)) if active => format!(
+                    "until {} (in {})",
+                    format_minute(&end),
+                    format_slurm_duration((end - now).to_std().unwrap_or_default())
+                ),
+                (Some(start), _) => format!(
+                    "from {} (in {})",
+                    format_minute(&start),
+                    format_slurm_duration((start - now).to_std().unwrap_or_default())
+                ),
+                _ => "-".to_string(),
+            };
+            let holders = [r.users.as_str(), r.accounts.as_str()]
+                .into_iter()
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<_>>()
+                .join(" / ");
+            let state_style = match (active, r.is_maintenance()) {
+                (true, true) => Style::default().fg(Color::Red),
+                (true, false) => Style::default().fg(Color::Green),
+                (false, _) => Style::default().fg(Color::Gray),
+            };
+            let count = |n: usize, color: Color| {
+                Cell::from(n.to_string()).style(if n > 0 {
+                    Style::default().fg(color)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                })
+            };
+            Row::new(vec![
+                Cell::from(r.name.clone()),
+                Cell::from(if active { "active" } else { "upcoming" }).style(state_style),
+                Cell::from(window),
+                Cell::from(format!("{} ({})", r.nodes, r.node_count)),
+                Cell::from(if r.partition.is_empty() {
+                    "-".to_string()
+                } else {
+                    r.partition.clone()
+                }),
+                Cell::from(r.flags.clone()),
+                Cell::from(holders),
+                count(row.running, Color::Cyan),
+                count(row.blocked, Color::Yellow),
+            ])
+        });
+        let table = TablePopup::table(
+            &[
+                "Name",
+                "State",
+                "Window",
+                "Nodes",
+                "Partition",
+                "Flags",
+                "For",
+                "Running",
+                "Blocked",
+            ],
+            rows.collect(),
+            &[
+                Constraint::Length(16),
+                Constraint::Length(9),
+                Constraint::Length(34),
+                Constraint::Length(18),
+                Constraint::Length(10),
+                Constraint::Percentage(15),
+                Constraint::Min(10),
+                Constraint::Length(8),
+                Constraint::Length(8),
+            ],
+        );
+        self.popup.render(
+            frame,
+            area,
+            format!("Reservations: {}", self.rows.len()),
+            vec![overview],
+            table,
+            "[↑/↓] Select | [r] Refresh | [Esc/M] Close",
+        );
+    }
+}
+
+impl TableView for ReservationView {
+    fn popup(&self) -> &TablePopup {
+        &self.popup
+    }
+
+    fn popup_mut(&mut self) -> &mut TablePopup {
+        &mut self.popup
+    }
+
+    fn handle_key(&mut self, input: Input) -> TableAction {
+        self.popup.handle_key(input, self.rows.len())
+    }
+}
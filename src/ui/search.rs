@@ -0,0 +1,216 @@
+use chrono::{DateTime, Local};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Tabs},
+    Frame,
+};
+
+use super::jobslist::state_color;
+use crate::keymap::{Action, Input};
+use crate::slurm::Job;
+use crate::ui::theme;
+use crate::utils::{format_slurm_duration, format_slurm_time};
+
+/// What the app should do after a key press in the search results
+pub enum SearchAction {
+    None,
+    Close,
+    /// Back to the job list, keeping the results tab
+    SwitchTab,
+    /// Open the detail pane on the job under the cursor
+    Detail(Box<Job>),
+    /// Change the query in the search form
+    Edit,
+    Refresh,
+}
+
+/// The jobs an ad-hoc search of squeue and accounting found, in a tab of
+/// their own beside the job list and its filters
+pub struct SearchView {
+    /// Whether the results tab exists
+    pub open: bool,
+    /// Whether the results tab is the one shown
+    pub visible: bool,
+    /// The query, for the title
+    pub summary: String,
+    jobs: Vec<Job>,
+    table_state: TableState,
+}
+
+impl SearchView {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            visible: false,
+            summary: String::new(),
+            jobs: Vec::new(),
+            table_state: TableState::default(),
+        }
+    }
+
+    pub fn show(&mut self, summary: String, jobs: Vec<Job>) {
+        self.summary = summary;
+        self.jobs = jobs;
+        self.table_state
+            .select((!self.jobs.is_empty()).then_some(0));
+        self.open = true;
+        self.visible = true;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.visible = false;
+    }
+
+    pub fn selected_job(&self) -> Option<&Job> {
+        self.table_state.selected().and_then(|i| self.jobs.get(i))
+    }
+
+    /// Move the cursor a row up; returns whether it moved
+    pub fn previous(&mut self) -> bool {
+        let i = self.table_state.selected().unwrap_or(0);
+        self.table_state.select(Some(i.saturating_sub(1)));
+        i > 0
+    }
+
+    /// Move the cursor a row down; returns whether it moved
+    pub fn next(&mut self) -> bool {
+        let i = self.table_state.selected().unwrap_or(0);
+        let moved = i + 1 < self.jobs.len();
+        if moved {
+            self.table_state.select(Some(i + 1));
+        }
+        moved
+    }
+
+    pub fn handle_key(&mut self, input: Input) -> SearchAction {
+        match input {
+            Input::Action(Action::SearchClose) => SearchAction::Close,
+            _ if input.is_ctrl_c() => SearchAction::Close,
+            Input::Action(Action::SearchTab) => SearchAction::SwitchTab,
+            Input::Action(Action::SearchDetail) => match self.selected_job() {
+                Some(job) => SearchAction::Detail(Box::new(job.clone())),
+                None => SearchAction::None,
+            },
+            Input::Action(Action::SearchEdit) => SearchAction::Edit,
+            Input::Action(Action::SearchRefresh) => SearchAction::Refresh,
+            Input::Action(Action::SearchUp) => {
+                self.previous();
+                SearchAction::None
+            }
+            Input::Action(Action::SearchDown) => {
+                self.next();
+                SearchAction::None
+            }
+            Input::Action(Action::SearchPageUp) => {
+                let i = self.table_state.selected().unwrap_or(0);
+                self.table_state.select(Some(i.saturating_sub(10)));
+                SearchAction::None
+            }
+            Input::Action(Action::SearchPageDown) => {
+                let i = self.table_state.selected().unwrap_or(0);
+                let last = self.jobs.len().saturating_sub(1);
+                self.table_state.select(Some((i + 10).min(last)));
+                SearchAction::None
+            }
+            _ => SearchAction::None,
+        }
+    }
+
+    /// The line of tabs over the main area while the results tab exists
+    pub fn render_tabs(&self, frame: &mut Frame, area: Rect) {
+        let titles = vec!["Jobs".to_string(), format!("Search ({})", self.jobs.len())];
+        let tabs = Tabs::new(titles)
+            .select(usize::from(self.visible))
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            );
+        frame.render_widget(tabs, area);
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::default()
+            .title(format!(
+                "Search: {} ({} jobs)",
+                self.summary,
+                self.jobs.len()
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(1)])
+            .split(inner);
+
+        if self.jobs.is_empty() {
+            frame.render_widget(Paragraph::new("No jobs match"), chunks[0]);
+        } else {
+            let header = Row::new([
+                "Job",
+                "Name",
+                "User",
+                "State",
+                "Partition",
+                "Account",
+                "Submit",
+                "End",
+                "Time",
+                "Nodes",
+            ])
+            .style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .height(1);
+            let opt = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+            let time =
+                |t: &Option<DateTime<Local>>| t.as_ref().map_or("-".to_string(), format_slurm_time);
+            let rows = self.jobs.iter().map(|job| {
+                Row::new(vec![
+                    Cell::from(job.id.clone()),
+                    Cell::from(job.name.clone()),
+                    Cell::from(job.user.clone()),
+                    Cell::from(job.state.to_string())
+                        .style(Style::default().fg(state_color(job.state))),
+                    Cell::from(job.partition.clone()),
+                    Cell::from(opt(&job.account)),
+                    Cell::from(time(&job.submit_time)),
+                    Cell::from(time(&job.end_time)),
+                    Cell::from(format_slurm_duration(job.time)),
+                    Cell::from(opt(&job.node)),
+                ])
+            });
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Length(12),
+                    Constraint::Percentage(20),
+                    Constraint::Length(10),
+                    Constraint::Length(12),
+                    Constraint::Length(10),
+                    Constraint::Length(12),
+                    Constraint::Length(19),
+                    Constraint::Length(19),
+                    Constraint::Length(11),
+                    Constraint::Min(8),
+                ],
+            )
+            .header(header)
+            .row_highlight_style(theme::selection());
+            frame.render_stateful_widget(table, chunks[0], &mut self.table_state);
+        }
+
+        let help = Paragraph::new(
+            "[↑/↓/PgUp/PgDn] Select | [Enter] Details | [Tab] Job list | [e/F] Edit query | [r] Search again | [Esc/q] Close tab",
+        )
+        .style(Style::default().fg(Color::Gray));
+        frame.render_widget(help, chunks[1]);
+    }
+}
@@ -1,5 +1,4 @@
-use color_eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyModifiers};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Position, Rect},
     style::{Color, Style},
@@ -9,7 +8,8 @@ use ratatui::{
 };
 use std::path::Path;
 
-use crate::config::{save_config, SlurmerConfig};
+use crate::config::{load_user_config, save_config};
+use crate::keymap::{Action, Input};
 
 pub struct SettingsPopup {
     pub visible: bool,
@@ -87,11 +87,9 @@ impl SettingsPopup {
             .block(Block::default().title("Status").borders(Borders::ALL));
         frame.render_widget(status, chunks[1]);
 
-        let help = Paragraph::new(
-            "Enter: Edit | Ctrl+a: Save | Esc: Close"
-        )
-        .style(Style::default().fg(Color::Gray))
-        .block(Block::default().borders(Borders::ALL));
+        let help = Paragraph::new("Enter: Edit | Ctrl+a: Save | Esc: Close")
+            .style(Style::default().fg(Color::Gray))
+            .block(Block::default().borders(Borders::ALL));
         frame.render_widget(help, chunks[3]);
 
         if self.input_mode {
@@ -102,36 +100,57 @@ impl SettingsPopup {
         }
     }
 
-    pub fn handle_key(&mut self, key: KeyEvent) -> SettingsAction {
-        match (key.modifiers, key.code) {
-            (_, KeyCode::Esc) => {
-                self.visible = false;
-                self.input_mode = false;
-                return SettingsAction::Close;
-            }
-            (KeyModifiers::CONTROL, KeyCode::Char('a')) => {
-                if self.valid == Some(true) {
-                    let cfg = SlurmerConfig {
-                        slurm_logs_dir: Some(self.slurm_logs_dir.trim().to_string()),
-                    };
-                    match save_config(&cfg) {
-                        Ok(()) => {
-                            self.status = "Saved".to_string();
-                            return SettingsAction::Saved;
-                        }
-                        Err(e) => {
-                            self.status = format!("Save failed: {}", e);
-                        }
-                    }
-                } else {
-                    self.status = "Not saved: invalid path".to_string();
-                }
+    fn close(&mut self) -> SettingsAction {
+        self.visible = false;
+        self.input_mode = false;
+        SettingsAction::Close
+    }
+
+    fn toggle_input(&mut self) -> SettingsAction {
+        self.input_mode = !self.input_mode;
+        SettingsAction::None
+    }
+
+    fn save(&mut self) -> SettingsAction {
+        if self.valid != Some(true) {
+            self.status = "Not saved: invalid path".to_string();
+            return SettingsAction::None;
+        }
+        // Keep the rest of the config file (e.g. presets) intact,
+        // and never overwrite a file that failed to load.
+        let mut cfg = match load_user_config() {
+            Ok(cfg) => cfg,
+            Err(e) => {
+                self.status = format!("Not saved: {:#}", e);
                 return SettingsAction::None;
             }
-            (_, KeyCode::Enter) => {
-                self.input_mode = !self.input_mode;
-                return SettingsAction::None;
+        };
+        cfg.slurm_logs_dir = Some(self.slurm_logs_dir.trim().to_string());
+        match save_config(&cfg) {
+            Ok(()) => {
+                self.status = "Saved".to_string();
+                SettingsAction::Saved
+            }
+            Err(e) => {
+                self.status = format!("Save failed: {}", e);
+                SettingsAction::None
             }
+        }
+    }
+
+    pub fn handle_key(&mut self, input: Input) -> SettingsAction {
+        let key = match input {
+            Input::Action(Action::SettingsClose) => return self.close(),
+            Input::Action(Action::SettingsSave) => return self.save(),
+            Input::Action(Action::SettingsEdit) => return self.toggle_input(),
+            Input::Action(_) => return SettingsAction::None,
+            Input::Key(key) => key,
+        };
+        // While typing these keys are not bound
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc) => return self.close(),
+            (KeyModifiers::CONTROL, KeyCode::Char('a')) => return self.save(),
+            (_, KeyCode::Enter) => return self.toggle_input(),
             _ => {}
         }
 
@@ -172,4 +191,3 @@ impl SettingsPopup {
         self.valid = Some(Path::new(v).is_dir());
     }
 }
-
@@ -0,0 +1,216 @@
+use chrono::{DateTime, Local};
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::keymap::{Action, Input};
+use crate::slurm::Job;
+use crate::stats::{group_stats, GroupBy, GroupStats, StatsSort};
+use crate::ui::theme;
+use crate::utils::format_slurm_duration;
+
+/// What the app should do after a key press in the statistics view
+pub enum StatsAction {
+    None,
+    Close,
+    Refresh,
+}
+
+/// Aggregate statistics of the listed jobs per user, account or partition
+pub struct StatsView {
+    pub visible: bool,
+    pub group_by: GroupBy,
+    pub sort: StatsSort,
+    pub descending: bool,
+    /// What the jobs cover, for the title (e.g. `active and ended in the last 24h`)
+    pub window: String,
+    jobs: Vec<Job>,
+    now: DateTime<Local>,
+    stats: Vec<GroupStats>,
+    table_state: TableState,
+}
+
+impl StatsView {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            group_by: GroupBy::User,
+            sort: StatsSort::Jobs,
+            descending: true,
+            window: String::new(),
+            jobs: Vec::new(),
+            now: Local::now(),
+            stats: Vec::new(),
+            table_state: TableState::default(),
+        }
+    }
+
+    pub fn show(&mut self, jobs: Vec<Job>, now: DateTime<Local>) {
+        self.jobs = jobs;
+        self.now = now;
+        self.regroup();
+        self.visible = true;
+    }
+
+    fn regroup(&mut self) {
+        let mut stats = group_stats(&self.jobs, self.group_by, self.now);
+        self.sort.sort(&mut stats);
+        if self.descending {
+            stats.reverse();
+        }
+        self.stats = stats;
+        self.table_state
+            .select((!self.stats.is_empty()).then_some(0));
+    }
+
+    pub fn handle_key(&mut self, input: Input) -> StatsAction {
+        match input {
+            Input::Action(Action::PopupClose) => return StatsAction::Close,
+            _ if input.is_key(KeyCode::Char('G')) || input.is_ctrl_c() => {
+                return StatsAction::Close
+            }
+            Input::Action(Action::PopupRefresh) => return StatsAction::Refresh,
+            Input::Action(Action::StatsGroup) => {
+                self.group_by = self.group_by.next();
+                self.regroup();
+            }
+            Input::Action(Action::StatsSort) => {
+                self.sort = self.sort.next();
+                self.regroup();
+            }
+            Input::Action(Action::StatsReverse) => {
+                self.descending = !self.descending;
+                self.regroup();
+            }
+            Input::Action(Action::PopupUp) => {
+                let i = self.table_state.selected().unwrap_or(0);
+                self.table_state.select(Some(i.saturating_sub(1)));
+            }
+            Input::Action(Action::PopupDown) => {
+                let i = self.table_state.selected().unwrap_or(0);
+                if i + 1 < self.stats.len() {
+                    self.table_state.select(Some(i + 1));
+                }
+            }
+            _ => {}
+        }
+        StatsAction::None
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        if !self.visible {
+            return;
+        }
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(format!(
+                "Statistics per {} of {} jobs ({})",
+                self.group_by.title().to_lowercase(),
+                self.jobs.len(),
+                self.window
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(1)])
+            .split(inner);
+
+        let titles: [(&str, Option<StatsSort>); 12] = [
+            (self.group_by.title(), Some(StatsSort::Key)),
+            ("Jobs", Some(StatsSort::Jobs)),
+            ("PD", None),
+            ("R", None),
+            ("CD", None),
+            ("Failed", None),
+            ("CA", None),
+            ("CPU-h", Some(StatsSort::CpuHours)),
+            ("GPU-h", Some(StatsSort::GpuHours)),
+            ("kWh", Some(StatsSort::Energy)),
+            ("Median wait", Some(StatsSort::MedianWait)),
+            ("Fail rate", Some(StatsSort::FailureRate)),
+        ];
+        let arrow = if self.descending { "▼" } else { "▲" };
+        let header = Row::new(titles.map(|(title, sort)| {
+            if sort == Some(self.sort) {
+                format!("{}{}", title, arrow)
+            } else {
+                title.to_string()
+            }
+        }))
+        .style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .height(1);
+        let rows = self.stats.iter().map(|s| {
+            let rate = s.failure_rate();
+            let rate_color = match rate {
+                Some(r) if r >= 0.5 => Color::Red,
+                Some(r) if r >= 0.1 => Color::Yellow,
+                _ => Color::Reset,
+            };
+            Row::new(vec![
+                Cell::from(s.key.clone()),
+                Cell::from(s.jobs.to_string()),
+                Cell::from(s.pending.to_string()),
+                Cell::from(s.running.to_string()),
+                Cell::from(s.completed.to_string()),
+                Cell::from(s.failed.to_string()),
+                Cell::from(s.cancelled.to_string()),
+                Cell::from(format!("{:.1}", s.cpu_hours)),
+                Cell::from(format!("{:.1}", s.gpu_hours)),
+                Cell::from(if s.kwh > 0.0 {
+                    format!("{:.1}", s.kwh)
+                } else {
+                    "-".to_string()
+                }),
+                Cell::from(
+                    s.median_wait
+                        .map(format_slurm_duration)
+                        .unwrap_or_else(|| "-".to_string()),
+                ),
+                Cell::from(
+                    rate.map(|r| format!("{:.0}%", r * 100.0))
+                        .unwrap_or_else(|| "-".to_string()),
+                )
+                .style(Style::default().fg(rate_color)),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Min(12),
+                Constraint::Length(6),
+                Constraint::Length(5),
+                Constraint::Length(5),
+                Constraint::Length(5),
+                Constraint::Length(7),
+                Constraint::Length(5),
+                Constraint::Length(9),
+                Constraint::Length(9),
+                Constraint::Length(8),
+                Constraint::Length(13),
+                Constraint::Length(10),
+            ],
+        )
+        .header(header)
+        .row_highlight_style(theme::selection());
+        frame.render_stateful_widget(table, chunks[0], &mut self.table_state);
+
+        let help = Paragraph::new(
+            "[↑/↓] Select | [g] Group by | [s] Sort column | [S] Reverse | [r] Refresh | [Esc/G] Close",
+        )
+        .style(Style::default().fg(Color::Gray));
+        frame.render_widget(help, chunks[1]);
+    }
+}
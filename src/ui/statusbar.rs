@@ -0,0 +1,175 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+use std::time::Duration;
+
+use crate::slurm::Job;
+use crate::ui::jobslist::state_color;
+
+/// Whether the Slurm controller answers, as the job list's polls found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Controller {
+    /// Not polled yet
+    Unknown,
+    Up,
+    Down,
+    /// Jobs come from a fixture, not Slurm
+    Fixture,
+}
+
+/// One source polled in the background
+#[derive(Debug, Clone)]
+pub struct Source<'a> {
+    pub name: &'a str,
+    /// How long its last poll took
+    pub took: Option<Duration>,
+    /// Why its last poll failed
+    pub error: Option<&'a str>,
+}
+
+/// The line under the job list: cluster, controller, the last poll of each
+/// source, and the listed jobs per state. Failing sources blink at its end.
+pub struct StatusBar<'a> {
+    pub cluster: &'a str,
+    pub controller: Controller,
+    pub sources: Vec<Source<'a>>,
+    pub jobs: &'a [Job],
+}
+
+impl StatusBar<'_> {
+    pub fn line(&self) -> Line<'static> {
+        let dim = Style::default().fg(Color::DarkGray);
+        let separator = || Span::styled(" │ ", dim);
+        let cluster = if self.cluster.is_empty() {
+            "cluster ?"
+        } else {
+            self.cluster
+        };
+        let mut spans = vec![
+            Span::styled(format!(" {}", cluster), Style::default().fg(Color::Cyan)),
+            separator(),
+        ];
+        spans.push(match &self.controller {
+            Controller::Unknown => Span::styled("controller ?", dim),
+            Controller::Up => Span::styled("● controller up", Style::default().fg(Color::Green)),
+            Controller::Down => Span::styled("● controller down", Style::default().fg(Color::Red)),
+            Controller::Fixture => Span::styled("no controller (fixture)", dim),
+        });
+
+        spans.push(separator());
+        let polls: Vec<String> = self
+            .sources
+            .iter()
+            .filter_map(|s| Some(format!("{} {}", s.name, short_duration(s.took?))))
+            .collect();
+        spans.push(Span::raw(polls.join(" · ")));
+
+        spans.push(separator());
+        for (i, (state, count)) in state_counts(self.jobs).into_iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(" "));
+            }
+            spans.push(Span::styled(
+                format!("{} {}", state.0, count),
+                Style::default().fg(state.1),
+            ));
+        }
+
+        let failing: Vec<String> = self
+            .sources
+            .iter()
+            .filter_map(|s| Some(format!("{}: {}", s.name, s.error?)))
+            .collect();
+        if !failing.is_empty() {
+            spans.push(separator());
+            spans.push(Span::styled(
+                format!("⚠ {}", failing.join("; ")),
+                Style::default()
+                    .fg(Color::Red)
+                    .add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK),
+            ));
+        }
+        Line::from(spans)
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        frame.render_widget(Paragraph::new(self.line()), area);
+    }
+}
+
+/// Listed jobs per state (short code and color), most common first
+pub fn state_counts(jobs: &[Job]) -> Vec<((&'static str, Color), usize)> {
+    let mut counts: Vec<((&'static str, Color), usize)> = Vec::new();
+    for job in jobs {
+        let key = (job.state.short_code(), state_color(job.state));
+        match counts.iter_mut().find(|(k, _)| k.0 == key.0) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((key, 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0 .0.cmp(b.0 .0)));
+    counts
+}
+
+/// `340ms`, `1.2s`, `14s`
+fn short_duration(duration: Duration) -> String {
+    match duration.as_millis() {
+        ms if ms < 1000 => format!("{}ms", ms),
+        ms if ms < 10_000 => format!("{:.1}s", ms as f64 / 1000.0),
+        _ => format!("{}s", duration.as_secs()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slurm::JobState;
+
+    #[test]
+    fn status_bar_shows_polls_counts_and_failures() {
+        let job = |state| Job {
+            state,
+            ..Default::default()
+        };
+        let jobs = vec![
+            job(JobState::Running),
+            job(JobState::Pending),
+            job(JobState::Running),
+            job(JobState::Failed),
+        ];
+        let bar = StatusBar {
+            cluster: "alps",
+            controller: Controller::Down,
+            sources: vec![
+                Source {
+                    name: "squeue",
+                    took: Some(Duration::from_millis(340)),
+                    error: Some("squeue timed out"),
+                },
+                Source {
+                    name: "sacct",
+                    took: Some(Duration::from_millis(1250)),
+                    error: None,
+                },
+                Source {
+                    name: "sinfo",
+                    took: None,
+                    error: None,
+                },
+            ],
+            jobs: &jobs,
+        };
+        let line = bar.line();
+        let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(
+            text,
+            " alps │ ● controller down │ squeue 340ms · sacct 1.2s │ R 2 F 1 PD 1 │ ⚠ squeue: squeue timed out"
+        );
+        let warning = line.spans.last().unwrap();
+        assert!(warning.style.add_modifier.contains(Modifier::SLOW_BLINK));
+    }
+}
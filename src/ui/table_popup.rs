@@ -0,0 +1,206 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+
+use crate::keymap::{Action, Input};
+use crate::slurm::nodes::NodeUpdate;
+use crate::ui::theme;
+
+/// What the app should do after a key press in a table popup
+#[derive(Debug, PartialEq, Eq)]
+pub enum TableAction {
+    None,
+    Close,
+    Refresh,
+    /// Enable or disable the scrontab entry at this index
+    Toggle(usize),
+    /// Open the scrontab entry at this index in the form
+    Edit(usize),
+    /// Drain or resume a node, confirmed by the operator
+    Update(NodeUpdate),
+}
+
+/// A popup of one table under an overview, as the scrontab, reservations,
+/// limits, licenses and cluster views are drawn
+pub struct TablePopup {
+    pub visible: bool,
+    /// What the rows are, e.g. `Licenses`, for the error line
+    what: &'static str,
+    /// The key opening the popup, which also closes it
+    toggle: char,
+    /// Why the rows could not be read
+    error: Option<String>,
+    table_state: TableState,
+}
+
+/// A view drawn in a `TablePopup`
+pub trait TableView {
+    fn popup(&self) -> &TablePopup;
+    fn popup_mut(&mut self) -> &mut TablePopup;
+    fn handle_key(&mut self, input: Input) -> TableAction;
+}
+
+impl TablePopup {
+    pub fn new(what: &'static str, toggle: char) -> Self {
+        Self {
+            visible: false,
+            what,
+            toggle,
+            error: None,
+            table_state: TableState::default(),
+        }
+    }
+
+    /// Open on `count` rows, or on why they could not be read over those
+    /// of the last time
+    pub fn show(&mut self, error: Option<String>, count: usize) {
+        self.error = error;
+        let index = self
+            .table_state
+            .selected()
+            .filter(|i| *i < count)
+            .or((count > 0).then_some(0));
+        self.table_state.select(index);
+        self.visible = true;
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.table_state.selected()
+    }
+
+    /// Close, refresh and move over `count` rows
+    pub fn handle_key(&mut self, input: Input, count: usize) -> TableAction {
+        match input {
+            Input::Action(Action::PopupClose) => TableAction::Close,
+            _ if input.is_key(KeyCode::Char(self.toggle)) || input.is_ctrl_c() => {
+                TableAction::Close
+            }
+            Input::Action(Action::PopupRefresh) => TableAction::Refresh,
+            Input::Action(Action::PopupUp) => {
+                let i = self.selected().unwrap_or(0);
+                self.table_state.select(Some(i.saturating_sub(1)));
+                TableAction::None
+            }
+            Input::Action(Action::PopupDown) => {
+                let i = self.selected().unwrap_or(0);
+                if i + 1 < count {
+                    self.table_state.select(Some(i + 1));
+                }
+                TableAction::None
+            }
+            _ => TableAction::None,
+        }
+    }
+
+    /// The table of `header` and `rows`, in the style of every table popup
+    pub fn table<'a>(header: &[&'a str], rows: Vec<Row<'a>>, widths: &[Constraint]) -> Table<'a> {
+        let header = Row::new(header.iter().copied())
+            .style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .height(1);
+        Table::new(rows, widths)
+            .header(header)
+            .row_highlight_style(theme::selection())
+    }
+
+    /// Why the rows could not be read, for the overview
+    pub fn unavailable(&self) -> Option<Line<'static>> {
+        self.error.as_ref().map(|e| {
+            Line::styled(
+                format!("{} unavailable: {}", self.what, e),
+                Style::default().fg(Color::Yellow),
+            )
+        })
+    }
+
+    /// Draw `title` around the `overview`, the `table` and a `help` line
+    pub fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        title: String,
+        overview: Vec<Line>,
+        table: Table,
+        help: &str,
+    ) {
+        if !self.visible {
+            return;
+        }
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(overview.len() as u16 + 1),
+                Constraint::Min(3),
+                Constraint::Length(1),
+            ])
+            .split(inner);
+        frame.render_widget(
+            Paragraph::new(overview).wrap(Wrap { trim: false }),
+            chunks[0],
+        );
+
+        frame.render_stateful_widget(table, chunks[1], &mut self.table_state);
+
+        frame.render_widget(
+            Paragraph::new(help).style(Style::default().fg(Color::Gray)),
+            chunks[2],
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyEvent, KeyModifiers};
+
+    fn key(c: char) -> Input {
+        Input::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))
+    }
+
+    fn press(popup: &mut TablePopup, input: Input) -> TableAction {
+        popup.handle_key(input, 2)
+    }
+
+    #[test]
+    fn shared_keys_move_refresh_and_close() {
+        let mut popup = TablePopup::new("Licenses", 'I');
+        popup.show(None, 2);
+        assert_eq!(popup.selected(), Some(0));
+        press(&mut popup, Input::Action(Action::PopupDown));
+        press(&mut popup, Input::Action(Action::PopupDown));
+        assert_eq!(popup.selected(), Some(1));
+        assert_eq!(
+            press(&mut popup, Input::Action(Action::PopupRefresh)),
+            TableAction::Refresh
+        );
+        assert_eq!(press(&mut popup, key('I')), TableAction::Close);
+        assert_eq!(
+            press(&mut popup, Input::Action(Action::PopupClose)),
+            TableAction::Close
+        );
+
+        // A failed read keeps the cursor on the rows still shown
+        popup.show(Some("timed out".to_string()), 2);
+        assert_eq!(popup.selected(), Some(1));
+        assert!(popup.unavailable().is_some());
+        popup.show(None, 0);
+        assert_eq!(popup.selected(), None);
+    }
+}
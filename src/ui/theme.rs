@@ -0,0 +1,342 @@
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier, Style},
+};
+use std::str::FromStr;
+
+use crate::config::{ColorsConfig, Theme};
+
+/// UI elements a theme colors, with the colors the UI draws them in as
+/// foreground and as background. `selection` is the reversed cells instead.
+const ELEMENTS: [(&str, &[Color], &[Color]); 11] = [
+    ("pending", &[Color::Yellow], &[Color::Yellow]),
+    ("running", &[Color::Green], &[Color::Green]),
+    ("completed", &[Color::Blue], &[Color::Blue]),
+    ("failed", &[Color::Red], &[Color::Red]),
+    ("cancelled", &[Color::Magenta], &[Color::Magenta]),
+    ("border", &[Color::Cyan, Color::LightCyan], &[Color::Cyan]),
+    ("text", &[Color::White], &[]),
+    ("dim", &[Color::Gray, Color::DarkGray], &[]),
+    ("header", &[], &[Color::DarkGray]),
+    ("background", &[], &[Color::Black]),
+    ("selection", &[], &[]),
+];
+
+const fn rgb(hex: u32) -> Option<Color> {
+    Some(Color::Rgb((hex >> 16) as u8, (hex >> 8) as u8, hex as u8))
+}
+
+/// Colors of each element under a theme, in `ELEMENTS` order; `None`
+/// keeps what the UI draws
+fn theme_colors(theme: Theme) -> [Option<Color>; 11] {
+    match theme {
+        Theme::Default | Theme::Mono => [None; 11],
+        Theme::Light => [
+            rgb(0x9a6700),
+            rgb(0x1a7f37),
+            rgb(0x0969da),
+            rgb(0xcf222e),
+            rgb(0x8250df),
+            rgb(0x1b7c83),
+            rgb(0x1f2328),
+            rgb(0x656d76),
+            rgb(0xd0d7de),
+            rgb(0xf6f8fa),
+            rgb(0xddf4ff),
+        ],
+        Theme::Solarized => [
+            rgb(0xb58900),
+            rgb(0x859900),
+            rgb(0x268bd2),
+            rgb(0xdc322f),
+            rgb(0xd33682),
+            rgb(0x2aa198),
+            rgb(0x93a1a1),
+            rgb(0x657b83),
+            rgb(0x073642),
+            rgb(0x002b36),
+            rgb(0x1d4f5c),
+        ],
+    }
+}
+
+fn overrides(colors: &ColorsConfig) -> [&Option<String>; 11] {
+    [
+        &colors.pending,
+        &colors.running,
+        &colors.completed,
+        &colors.failed,
+        &colors.cancelled,
+        &colors.border,
+        &colors.text,
+        &colors.dim,
+        &colors.header,
+        &colors.background,
+        &colors.selection,
+    ]
+}
+
+/// A `[colors]` value: a name, a 256-color index or `#rrggbb`
+pub fn parse_color(value: &str) -> Option<Color> {
+    Color::from_str(value.trim()).ok()
+}
+
+/// Colors the terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    Ansi16,
+    Indexed256,
+    TrueColor,
+}
+
+impl ColorDepth {
+    pub fn detect() -> Self {
+        Self::from_env(
+            std::env::var("COLORTERM").ok().as_deref(),
+            std::env::var("TERM").ok().as_deref(),
+        )
+    }
+
+    fn from_env(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        match (colorterm, term) {
+            (Some("truecolor" | "24bit"), _) => ColorDepth::TrueColor,
+            (_, Some(term)) if term.contains("256color") => ColorDepth::Indexed256,
+            _ => ColorDepth::Ansi16,
+        }
+    }
+
+    /// `color` as the closest color the terminal can show
+    pub fn fit(self, color: Color) -> Color {
+        let Some((r, g, b)) = rgb_of(color) else {
+            return color;
+        };
+        match (self, color) {
+            (ColorDepth::TrueColor, _) | (ColorDepth::Indexed256, Color::Indexed(_)) => color,
+            (ColorDepth::Indexed256, _) => Color::Indexed(nearest_256(r, g, b)),
+            (ColorDepth::Ansi16, _) => nearest_ansi(r, g, b),
+        }
+    }
+}
+
+/// Approximate RGB of the 16 ANSI colors (xterm's), in index order
+const ANSI: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Levels of the 6x6x6 color cube of the 256-color palette
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// RGB of a truecolor or 256-color (beyond the 16 ANSI) color
+fn rgb_of(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(i @ 16..=231) => {
+            let i = i - 16;
+            Some((
+                CUBE[usize::from(i / 36)],
+                CUBE[usize::from(i / 6 % 6)],
+                CUBE[usize::from(i % 6)],
+            ))
+        }
+        Color::Indexed(i @ 232..=255) => {
+            let level = 8 + 10 * (i - 232);
+            Some((level, level, level))
+        }
+        _ => None,
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).unsigned_abs().pow(2);
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| {
+        (0..CUBE.len())
+            .min_by_key(|&i| (i32::from(CUBE[i]) - i32::from(v)).abs())
+            .unwrap() as u8
+    };
+    let cube = 16 + 36 * level(r) + 6 * level(g) + level(b);
+    let gray = (232..=255u8)
+        .min_by_key(|&i| distance(rgb_of(Color::Indexed(i)).unwrap(), (r, g, b)))
+        .unwrap();
+    [cube, gray]
+        .into_iter()
+        .min_by_key(|&i| distance(rgb_of(Color::Indexed(i)).unwrap(), (r, g, b)))
+        .unwrap()
+}
+
+fn nearest_ansi(r: u8, g: u8, b: u8) -> Color {
+    ANSI.iter()
+        .min_by_key(|(_, rgb)| distance(*rgb, (r, g, b)))
+        .map(|(color, _)| *color)
+        .unwrap()
+}
+
+/// Style of the selected row of a list or table, which the `selection`
+/// color of a theme recolors
+pub fn selection() -> Style {
+    Style::default().add_modifier(Modifier::REVERSED)
+}
+
+/// What each color the UI draws in is shown as under the theme, `[colors]`
+/// and the terminal's color depth
+#[derive(Debug, Clone, Default)]
+pub struct Palette {
+    mono: bool,
+    fg: Vec<(Color, Color)>,
+    bg: Vec<(Color, Color)>,
+    /// Background of reversed (selected) cells
+    selection: Option<Color>,
+}
+
+impl Palette {
+    /// The palette, with a message per `[colors]` value that is not a color
+    /// (and is ignored)
+    pub fn new(theme: Theme, colors: &ColorsConfig, depth: ColorDepth) -> (Self, Vec<String>) {
+        let mut palette = Self {
+            mono: theme == Theme::Mono,
+            ..Default::default()
+        };
+        let mut problems = Vec::new();
+        let elements = ELEMENTS
+            .iter()
+            .zip(theme_colors(theme))
+            .zip(overrides(colors));
+        for (((name, fg, bg), color), value) in elements {
+            let value = value.as_deref().filter(|v| !v.trim().is_empty());
+            let color = match value.map(|v| (v, parse_color(v))) {
+                Some((_, Some(color))) => Some(color),
+                Some((value, None)) => {
+                    problems.push(format!("invalid color `{}` of [colors] {}", value, name));
+                    color
+                }
+                None => color,
+            };
+            let Some(color) = color.map(|c| depth.fit(c)) else {
+                continue;
+            };
+            if *name == "selection" {
+                palette.selection = Some(color);
+            }
+            palette.fg.extend(fg.iter().map(|from| (*from, color)));
+            palette.bg.extend(bg.iter().map(|from| (*from, color)));
+        }
+        (palette, problems)
+    }
+
+    /// Recolor a drawn frame
+    pub fn apply(&self, buffer: &mut Buffer) {
+        let recolor = |map: &[(Color, Color)], color: Color| {
+            map.iter()
+                .find(|(from, _)| *from == color)
+                .map_or(color, |(_, to)| *to)
+        };
+        for cell in buffer.content.iter_mut() {
+            if self.mono {
+                // Header rows and highlights only have a background; popups
+                // are filled with black
+                if !matches!(cell.bg, Color::Reset | Color::Black) {
+                    cell.modifier.insert(Modifier::REVERSED);
+                }
+                cell.set_fg(Color::Reset).set_bg(Color::Reset);
+                continue;
+            }
+            cell.fg = recolor(&self.fg, cell.fg);
+            cell.bg = recolor(&self.bg, cell.bg);
+            if let Some(selection) = self.selection {
+                if cell.modifier.contains(Modifier::REVERSED) {
+                    cell.modifier.remove(Modifier::REVERSED);
+                    cell.bg = selection;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{layout::Rect, style::Style};
+
+    #[test]
+    fn palette_recolors_and_fits_the_terminal() {
+        let colors = ColorsConfig {
+            pending: Some("#ff8700".to_string()),
+            failed: Some("bright red-ish".to_string()),
+            ..Default::default()
+        };
+        let (palette, problems) = Palette::new(Theme::Light, &colors, ColorDepth::TrueColor);
+        assert_eq!(
+            problems,
+            ["invalid color `bright red-ish` of [colors] failed"]
+        );
+
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 3, 1));
+        buffer[(0, 0)].set_style(Style::default().fg(Color::Yellow));
+        buffer[(1, 0)].set_style(Style::default().fg(Color::Red).bg(Color::Black));
+        // The job list's cursor, bold on top of the selection style
+        buffer[(2, 0)].set_style(selection().add_modifier(Modifier::BOLD));
+        palette.apply(&mut buffer);
+        assert_eq!(buffer[(0, 0)].fg, Color::Rgb(0xff, 0x87, 0x00));
+        assert_eq!(buffer[(1, 0)].fg, Color::Rgb(0xcf, 0x22, 0x2e));
+        assert_eq!(buffer[(1, 0)].bg, Color::Rgb(0xf6, 0xf8, 0xfa));
+        assert_eq!(buffer[(2, 0)].bg, Color::Rgb(0xdd, 0xf4, 0xff));
+        assert!(!buffer[(2, 0)].modifier.contains(Modifier::REVERSED));
+
+        // Without truecolor the same colors come from the 256 or 16 colors
+        let orange = Color::Rgb(0xff, 0x87, 0x00);
+        assert_eq!(ColorDepth::Indexed256.fit(orange), Color::Indexed(208));
+        assert_eq!(
+            ColorDepth::Indexed256.fit(Color::Rgb(0x30, 0x30, 0x30)),
+            Color::Indexed(236)
+        );
+        assert_eq!(ColorDepth::Ansi16.fit(Color::Indexed(196)), Color::LightRed);
+        assert_eq!(ColorDepth::Ansi16.fit(Color::Cyan), Color::Cyan);
+        assert_eq!(
+            ColorDepth::from_env(None, Some("xterm-256color")),
+            ColorDepth::Indexed256
+        );
+        assert_eq!(
+            ColorDepth::from_env(Some("truecolor"), Some("xterm")),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            ColorDepth::from_env(None, Some("linux")),
+            ColorDepth::Ansi16
+        );
+
+        // The default theme draws what the UI draws
+        let (palette, _) = Palette::new(Theme::Default, &Default::default(), ColorDepth::Ansi16);
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 1, 1));
+        buffer[(0, 0)].set_style(Style::default().fg(Color::Yellow));
+        palette.apply(&mut buffer);
+        assert_eq!(buffer[(0, 0)].fg, Color::Yellow);
+
+        // Mono keeps a header row's background as reversed text, not a popup's fill
+        let (palette, _) = Palette::new(Theme::Mono, &Default::default(), ColorDepth::TrueColor);
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 2, 1));
+        buffer[(0, 0)].set_style(Style::default().fg(Color::White).bg(Color::DarkGray));
+        buffer[(1, 0)].set_style(Style::default().bg(Color::Black));
+        palette.apply(&mut buffer);
+        assert!(buffer[(0, 0)].modifier.contains(Modifier::REVERSED));
+        assert_eq!(buffer[(0, 0)].bg, Color::Reset);
+        assert!(!buffer[(1, 0)].modifier.contains(Modifier::REVERSED));
+    }
+}
@@ -0,0 +1,100 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Sparkline},
+    Frame,
+};
+use std::time::Duration;
+
+use crate::history::{QueueHistory, QueueSample};
+use crate::keymap::{Action, Input};
+use crate::utils::format_slurm_duration;
+
+/// What the app should do after a key press in the trends dashboard
+pub enum TrendsAction {
+    None,
+    Close,
+}
+
+/// Metric name, color and the value it takes from a sample
+type Trend = (&'static str, Color, fn(&QueueSample) -> u64);
+
+const TRENDS: [Trend; 4] = [
+    ("Pending jobs", Color::Yellow, |s| s.pending),
+    ("Running jobs", Color::Green, |s| s.running),
+    ("Allocated CPUs", Color::Cyan, |s| s.cpus),
+    ("Allocated GPUs", Color::Magenta, |s| s.gpus),
+];
+
+/// Sparklines of queue depth and allocation over this session's polls
+pub struct TrendsView {
+    pub visible: bool,
+    pub history: QueueHistory,
+}
+
+impl TrendsView {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            history: QueueHistory::default(),
+        }
+    }
+
+    pub fn handle_key(&mut self, input: Input) -> TrendsAction {
+        match input {
+            Input::Action(Action::PopupClose) => TrendsAction::Close,
+            _ if input.is_key(KeyCode::Char('O')) || input.is_ctrl_c() => TrendsAction::Close,
+            _ => TrendsAction::None,
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        if !self.visible {
+            return;
+        }
+        frame.render_widget(Clear, area);
+
+        let samples = self.history.samples();
+        let span = match (samples.front(), samples.back()) {
+            (Some(first), Some(last)) if last.at > first.at => format!(
+                ", last {}",
+                format_slurm_duration(Duration::from_secs(last.at - first.at))
+            ),
+            _ => String::new(),
+        };
+        let block = Block::default()
+            .title(format!("Trends: {} polls{}", samples.len(), span))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let mut constraints = vec![Constraint::Ratio(1, TRENDS.len() as u32); TRENDS.len()];
+        constraints.push(Constraint::Length(1));
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(constraints)
+            .split(inner);
+
+        for ((name, color, value), area) in TRENDS.into_iter().zip(chunks.iter()) {
+            // Sparklines draw from the left: keep the samples that fit
+            let data = self
+                .history
+                .series(area.width.saturating_sub(2) as usize, value);
+            let title = match (data.last(), data.iter().max()) {
+                (Some(now), Some(max)) => format!("{}: {} (max {})", name, now, max),
+                _ => name.to_string(),
+            };
+            let sparkline = Sparkline::default()
+                .block(Block::default().title(title).borders(Borders::ALL))
+                .data(&data)
+                .style(Style::default().fg(color));
+            frame.render_widget(sparkline, *area);
+        }
+
+        let help = Paragraph::new("Sampled on every refresh | [Esc/O] Close")
+            .style(Style::default().fg(Color::Gray));
+        frame.render_widget(help, chunks[TRENDS.len()]);
+    }
+}
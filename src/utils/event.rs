@@ -1,12 +1,23 @@
-use crossterm::event::{self, Event as CrosstermEvent, KeyEvent, MouseEvent};
+use crossterm::{
+    event::{
+        self, DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+        EnableFocusChange, EnableMouseCapture, Event as CrosstermEvent, KeyEvent, MouseEvent,
+    },
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
 use std::{
-    sync::mpsc,
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
     thread,
     time::{Duration, Instant},
 };
 
 /// Events that can be handled by the application
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Event {
     /// Terminal tick (for animations)
     Tick,
@@ -17,6 +28,39 @@ pub enum Event {
     /// Terminal resize event
     #[allow(dead_code)]
     Resize(u16, u16),
+    /// The terminal window gained or lost focus (where the terminal reports it)
+    Focus(bool),
+    /// Text pasted into the terminal (where it supports bracketed paste)
+    Paste(String),
+}
+
+/// Take over the terminal for the TUI: raw mode on the alternate screen,
+/// with focus and paste events, and mouse events unless the mouse is left
+/// to the terminal
+pub fn enter_tui(mouse: bool) -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        EnterAlternateScreen,
+        EnableFocusChange,
+        EnableBracketedPaste
+    )?;
+    if mouse {
+        execute!(io::stdout(), EnableMouseCapture)?;
+    }
+    Ok(())
+}
+
+/// Give the terminal back as the TUI found it
+pub fn leave_tui() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableFocusChange,
+        DisableBracketedPaste
+    )
 }
 
 /// Event handler configuration
@@ -46,30 +90,42 @@ pub struct EventHandler {
     tx: mpsc::Sender<Event>,
     /// Thread handle for the event handler
     #[allow(dead_code)]
-    handle: thread::JoinHandle<()>,
+    handle: Option<thread::JoinHandle<()>>,
+    /// Set to stop reading the terminal, e.g. while a shell runs in it
+    paused: Arc<AtomicBool>,
+    /// Set by the thread once it stopped reading
+    idle: Arc<AtomicBool>,
 }
 
 impl EventHandler {
     /// Create a new event handler with the given configuration
     pub fn new(config: EventConfig) -> Self {
         let (tx, rx) = mpsc::channel();
+        let paused = Arc::new(AtomicBool::new(false));
+        let idle = Arc::new(AtomicBool::new(false));
         let handle = {
             let tx = tx.clone();
+            let paused = paused.clone();
+            let idle = idle.clone();
             thread::spawn(move || {
                 let tick_rate = config.tick_rate;
                 let mut last_tick = Instant::now();
 
                 loop {
+                    if paused.load(Ordering::SeqCst) {
+                        idle.store(true, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(20));
+                        continue;
+                    }
+                    idle.store(false, Ordering::SeqCst);
                     let timeout = tick_rate
                         .checked_sub(last_tick.elapsed())
                         .unwrap_or(Duration::from_secs(0));
 
                     if event::poll(timeout).expect("Failed to poll for events") {
                         match event::read().expect("Failed to read event") {
-                            CrosstermEvent::Key(key) => {
-                                if tx.send(Event::Key(key)).is_err() {
-                                    return;
-                                }
+                            CrosstermEvent::Key(key) if tx.send(Event::Key(key)).is_err() => {
+                                return;
                             }
                             CrosstermEvent::Mouse(mouse) => {
                                 if !config.enable_mouse_capture {
@@ -79,10 +135,19 @@ impl EventHandler {
                                     return;
                                 }
                             }
-                            CrosstermEvent::Resize(width, height) => {
-                                if tx.send(Event::Resize(width, height)).is_err() {
-                                    return;
-                                }
+                            CrosstermEvent::Resize(width, height)
+                                if tx.send(Event::Resize(width, height)).is_err() =>
+                            {
+                                return;
+                            }
+                            CrosstermEvent::FocusGained if tx.send(Event::Focus(true)).is_err() => {
+                                return;
+                            }
+                            CrosstermEvent::FocusLost if tx.send(Event::Focus(false)).is_err() => {
+                                return;
+                            }
+                            CrosstermEvent::Paste(text) => {
+                                let _ = tx.send(Event::Paste(text));
                             }
                             _ => {}
                         }
@@ -98,6 +163,42 @@ impl EventHandler {
             })
         };
 
-        Self { rx, tx, handle }
+        Self {
+            rx,
+            tx,
+            handle: Some(handle),
+            paused,
+            idle,
+        }
+    }
+
+    /// An event handler that reads nothing from the terminal, for driving
+    /// the app with synthetic events or without a terminal
+    pub fn detached() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            rx,
+            tx,
+            handle: None,
+            paused: Arc::default(),
+            idle: Arc::default(),
+        }
+    }
+
+    /// Stop reading the terminal, returning once the reader has let go of
+    /// it (it finishes the read it is waiting in first)
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+        if self.handle.is_none() {
+            return;
+        }
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !self.idle.load(Ordering::SeqCst) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
     }
 }
@@ -1,6 +1,16 @@
 pub mod event;
 pub mod file_watcher;
 
+use std::collections::HashMap;
+use std::fmt;
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone};
+
+use crate::slurm::command;
+
 /// Returns the current username from the environment
 pub fn get_username() -> String {
     std::env::var("USER").unwrap_or_else(|_| "unknown".to_string())
@@ -37,3 +47,248 @@ pub fn _format_duration(seconds: u64) -> String {
         format!("{:02}:{:02}:{:02}", hours, minutes, secs)
     }
 }
+
+/// How Slurm prints times: wall-clock time without an offset
+const SLURM_TIME: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// A Slurm timestamp (`2024-05-01T13:45:00`), which is on the cluster's
+/// clock; `None` for `Unknown`, `N/A` and the like
+pub fn parse_slurm_time(raw: &str) -> Option<DateTime<Local>> {
+    let naive = NaiveDateTime::parse_from_str(raw.trim(), SLURM_TIME).ok()?;
+    // A time repeated when the clocks go back is read as its first pass
+    Local.from_local_datetime(&naive).earliest()
+}
+
+/// A time the way Slurm prints it, on the clock of its zone
+pub fn format_slurm_time<Tz: TimeZone>(at: &DateTime<Tz>) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    at.format(SLURM_TIME).to_string()
+}
+
+/// `13m ago` or `in ~2h`, in the largest unit that fits
+pub fn format_relative(at: &DateTime<Local>, now: &DateTime<Local>) -> String {
+    let secs = (*at - *now).num_seconds();
+    let span = match secs.unsigned_abs() {
+        0..60 => return "now".to_string(),
+        s @ 60..3600 => format!("{}m", s / 60),
+        s @ 3600..86400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86400),
+    };
+    if secs > 0 {
+        format!("in ~{}", span)
+    } else {
+        format!("{} ago", span)
+    }
+}
+
+/// A configured time zone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    /// An offset from UTC (`+02:00`, `-0700`)
+    Fixed(FixedOffset),
+    /// A zone of the tz database (`Europe/Berlin`), which follows its
+    /// daylight saving time
+    Named(chrono_tz::Tz),
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Zone::Fixed(offset) => write!(f, "{}", offset),
+            Zone::Named(zone) => f.write_str(zone.name()),
+        }
+    }
+}
+
+/// A configured zone: `UTC`, a fixed offset (`+02:00`, `-0700`), or a
+/// zone of the tz database (`Europe/Berlin`)
+pub fn parse_zone(raw: &str) -> Option<Zone> {
+    match raw.trim() {
+        "UTC" | "utc" | "Z" => Some(Zone::Named(chrono_tz::UTC)),
+        raw if raw.starts_with(['+', '-']) => raw.parse().ok().map(Zone::Fixed),
+        raw => raw.parse().ok().map(Zone::Named),
+    }
+}
+
+/// How the submit, start and end times of jobs are shown
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimeDisplay {
+    /// `13m ago` rather than the timestamp
+    pub relative: bool,
+    /// The zone the timestamps are shown in; the cluster's clock when unset
+    pub zone: Option<Zone>,
+}
+
+impl TimeDisplay {
+    /// A job's time as set
+    pub fn format(&self, at: &DateTime<Local>) -> String {
+        self.format_at(at, &Local::now())
+    }
+
+    /// `format` when it is `now`
+    fn format_at(&self, at: &DateTime<Local>, now: &DateTime<Local>) -> String {
+        if self.relative {
+            return format_relative(at, now);
+        }
+        match self.zone {
+            Some(Zone::Fixed(zone)) => format_slurm_time(&at.with_timezone(&zone)),
+            Some(Zone::Named(zone)) => format_slurm_time(&at.with_timezone(&zone)),
+            None => format_slurm_time(at),
+        }
+    }
+}
+
+/// Parse a Slurm duration (`[D-]HH:MM:SS`, `MM:SS`, or `MM`); `None` for
+/// `UNLIMITED`, `INVALID` and the like
+pub fn parse_slurm_duration(raw: &str) -> Option<Duration> {
+    let raw = raw.trim();
+    let (days, clock) = match raw.split_once('-') {
+        Some((days, clock)) => (days.parse::<u64>().ok()?, clock),
+        None => (0, raw),
+    };
+    let parts = clock
+        .split(':')
+        .map(|p| p.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let (hours, minutes, seconds) = match (raw.contains('-'), parts.as_slice()) {
+        (_, [h, m, s]) => (*h, *m, *s),
+        // `D-HH` and `D-HH:MM` count from hours, plain values from minutes
+        (true, [h, m]) => (*h, *m, 0),
+        (true, [h]) => (*h, 0, 0),
+        (false, [m, s]) => (0, *m, *s),
+        (false, [m]) => (0, *m, 0),
+        _ => return None,
+    };
+    Some(Duration::from_secs(
+        days * 86400 + hours * 3600 + minutes * 60 + seconds,
+    ))
+}
+
+/// Format a duration the way squeue does (`M:SS`, `H:MM:SS`, `D-HH:MM:SS`)
+pub fn format_slurm_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes, seconds) = (
+        secs / 86400,
+        secs % 86400 / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+    );
+    if days > 0 {
+        format!("{}-{:02}:{:02}:{:02}", days, hours, minutes, seconds)
+    } else if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// The user name of a numeric uid, from `/etc/passwd` or, for directory
+/// users (LDAP, SSSD), `getent passwd`. Lookups are cached for the session.
+pub fn username_for_uid(uid: u32) -> Option<String> {
+    static CACHE: OnceLock<Mutex<HashMap<u32, Option<String>>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(Default::default);
+    if let Some(name) = cache.lock().ok()?.get(&uid) {
+        return name.clone();
+    }
+
+    let name = std::fs::read_to_string("/etc/passwd")
+        .ok()
+        .and_then(|passwd| passwd_user(&passwd, uid))
+        .or_else(|| {
+            let mut getent = Command::new("getent");
+            getent.args(["passwd", &uid.to_string()]);
+            let output = command::run_local(getent, None).ok()?;
+            passwd_user(&String::from_utf8_lossy(&output.stdout), uid)
+        });
+    cache.lock().ok()?.insert(uid, name.clone());
+    name
+}
+
+/// The name on the `passwd` line (`name:x:uid:gid:...`) with the given uid
+fn passwd_user(passwd: &str, uid: u32) -> Option<String> {
+    passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        let name = fields.next()?;
+        (fields.nth(1)?.parse::<u32>().ok()? == uid).then(|| name.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slurm_times_parse() {
+        let at = |raw| parse_slurm_time(raw).unwrap();
+        assert_eq!(
+            format_slurm_time(&at("2024-03-01T00:00:00")),
+            "2024-03-01T00:00:00"
+        );
+        assert_eq!(
+            (at("1970-01-02T00:00:10") - at("1970-01-01T00:00:00")).num_seconds(),
+            86410
+        );
+        assert_eq!(parse_slurm_time("Unknown"), None);
+        assert_eq!(
+            parse_zone("+02:00"),
+            FixedOffset::east_opt(7200).map(Zone::Fixed)
+        );
+        assert_eq!(
+            parse_zone("-0700"),
+            FixedOffset::west_opt(25200).map(Zone::Fixed)
+        );
+        assert_eq!(
+            parse_zone("Europe/Berlin"),
+            Some(Zone::Named(chrono_tz::Europe::Berlin))
+        );
+        assert_eq!(parse_zone("Mars/Olympus"), None);
+        assert_eq!(
+            parse_zone("utc").map(|z| z.to_string()).as_deref(),
+            Some("UTC")
+        );
+        let now = at("2024-05-01T12:00:00");
+        assert_eq!(format_relative(&at("2024-05-01T11:47:00"), &now), "13m ago");
+        assert_eq!(format_relative(&at("2024-05-01T14:00:05"), &now), "in ~2h");
+        let relative = TimeDisplay {
+            relative: true,
+            zone: None,
+        };
+        assert_eq!(
+            relative.format_at(&at("2024-05-01T11:00:00"), &now),
+            "1h ago"
+        );
+        // A job of a cluster at UTC+02:00, shown at UTC-07:00
+        let shifted = TimeDisplay {
+            relative: false,
+            zone: parse_zone("-0700"),
+        };
+        let cluster = DateTime::parse_from_rfc3339("2024-05-01T13:00:00+02:00").unwrap();
+        assert_eq!(
+            shifted.format_at(&cluster.with_timezone(&Local), &now),
+            "2024-05-01T04:00:00"
+        );
+        let secs = |raw| parse_slurm_duration(raw).map(|d| d.as_secs());
+        assert_eq!(secs("1-02:03:04"), Some(93784));
+        assert_eq!(secs("12:03:04"), Some(43384));
+        assert_eq!(secs("05:30"), Some(330));
+        assert_eq!(secs("2-12"), Some(216000));
+        assert_eq!(secs("90"), Some(5400));
+        assert_eq!(secs("UNLIMITED"), None);
+        for raw in ["1-02:03:04", "2:03:04", "5:30", "0:00"] {
+            assert_eq!(
+                format_slurm_duration(parse_slurm_duration(raw).unwrap()),
+                raw
+            );
+        }
+    }
+    #[test]
+    fn uid_resolves_from_passwd() {
+        let passwd = "root:x:0:0:root:/root:/bin/bash\n\
+                      alice:x:1234:100:Alice:/home/alice:/bin/zsh\n";
+        assert_eq!(passwd_user(passwd, 1234).as_deref(), Some("alice"));
+        assert_eq!(passwd_user(passwd, 0).as_deref(), Some("root"));
+        assert_eq!(passwd_user(passwd, 99), None);
+    }
+}